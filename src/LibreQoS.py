@@ -23,6 +23,7 @@ from deepdiff import DeepDiff
 
 from virtual_tree_nodes import (
     build_logical_to_physical_node_map,
+    build_node_sqm_map,
     build_physical_network,
     collect_physical_parent_node_aliases,
     collect_physical_parent_node_ids,
@@ -88,6 +89,12 @@ except ImportError:
     def validated_runtime_shaping_inputs_path():
         return None
 
+try:
+    from liblqos_python import traffic_planner_enabled  # type: ignore
+except ImportError:
+    def traffic_planner_enabled():
+        return False
+
 try:
     from liblqos_python import get_libreqos_state_directory as _get_state_dir_native
 except Exception:
@@ -105,6 +112,28 @@ class ValidationFailure(RefreshFailure):
 XDP_IP_MAPPING_APPLY_FAILED = "XDP_IP_MAPPING_APPLY_FAILED"
 XDP_MAPPING_READY_TIMEOUT_SECONDS = 5.0
 XDP_MAPPING_READY_INTERVAL_SECONDS = 0.1
+# Set by --plan. When set, the Bakery batch is planned instead of committed
+# and the plan is written here as JSON.
+PLAN_OUTPUT_PATH = None
+
+
+def write_bakery_plan(bakery, output_path):
+    plan_json = bakery.plan()
+    ensure_parent_dir(output_path)
+    with open(output_path, 'w') as f:
+        f.write(plan_json)
+    plan = json.loads(plan_json)
+    if plan.get("full_reload"):
+        print("Plan: full reload (" + str(plan.get("full_reload_triggers", 0)) + " trigger(s))")
+        for reason in plan.get("reasons", []):
+            print("\t" + reason)
+    else:
+        print("Plan: " + str(plan.get("live_changes", 0)) + " live change(s)")
+    print("\tEstimated tc commands: " + str(plan.get("estimated_tc_commands", 0)))
+    print("\tEstimated qdisc memory: " + str(plan.get("estimated_qdisc_memory_bytes", 0)) + " bytes")
+    for note in plan.get("notes", []):
+        print("\t" + note)
+    print("Plan written to " + output_path)
 
 
 def report_refresh_failure(code, message, context=None, dedupe_key=None):
@@ -350,6 +379,7 @@ _SHAPED_DEVICES_HEADER_ALIASES = {
     'uploadMax': {'uploadmax', 'uploadmaxmbps'},
     'comment': {'comment'},
     'sqm': {'sqm'},
+    'priorityClasses': {'priorityclasses'},
 }
 
 
@@ -357,6 +387,7 @@ def _build_shaped_devices_layout(header_row):
     layout = dict(_SHAPED_DEVICES_LEGACY_LAYOUT)
     layout['ParentNodeID'] = None
     layout['AnchorNodeID'] = None
+    layout['priorityClasses'] = None
     for idx, header in enumerate(header_row):
         normalized = _normalize_shaped_devices_header(header)
         for field, aliases in _SHAPED_DEVICES_HEADER_ALIASES.items():
@@ -592,6 +623,9 @@ def loadSubscriberCircuitsFromShapingInputs(shapingInputsPath):
         sqm_override = normalize_sqm_override_token(str(circuit.get('sqm_override', '') or ''))
         if sqm_override != '':
             thisCircuit['sqm'] = sqm_override
+        priority_classes = normalize_priority_classes(circuit.get('priority_classes', []))
+        if priority_classes:
+            thisCircuit['priorityClasses'] = priority_classes
         if thisCircuit['ParentNode'] == 'none':
             thisCircuit['idForCircuitsWithoutParentNodes'] = counterForCircuitsWithoutParentNodes
             dictForCircuitsWithoutParentNodes[counterForCircuitsWithoutParentNodes] = (
@@ -1276,6 +1310,9 @@ def loadSubscriberCircuits(shapedDevicesFile):
             downloadMax = _shaped_devices_row_value(row, layout, 'downloadMax')
             uploadMax = _shaped_devices_row_value(row, layout, 'uploadMax')
             comment = _shaped_devices_row_value(row, layout, 'comment')
+            priority_classes = normalize_priority_classes(
+                _shaped_devices_row_value(row, layout, 'priorityClasses')
+            )
             ipv4_subnets_and_hosts = []
             # Each entry in ShapedDevices.csv can have multiple IPv4s or IPv6s separated by commas. Split them up and parse each
             if ipv4_input != "":
@@ -1330,6 +1367,8 @@ def loadSubscriberCircuits(shapedDevicesFile):
                                 warnings.warn("Device " + deviceName + " with ID " + deviceID + " had different SQM override than other devices on this circuit. Will instead use the SQM defined by the first device added to its circuit.", stacklevel=2)
                         else:
                             circuit['sqm'] = sqm_override_token
+                    if priority_classes and 'priorityClasses' not in circuit:
+                        circuit['priorityClasses'] = priority_classes
                     devicesListForCircuit = circuit['devices']
                     thisDevice = 	{
                                       "deviceID": deviceID,
@@ -1372,6 +1411,8 @@ def loadSubscriberCircuits(shapedDevicesFile):
                     }
                     if sqm_override_token != '':
                         thisCircuit['sqm'] = sqm_override_token
+                    if priority_classes:
+                        thisCircuit['priorityClasses'] = priority_classes
                     if thisCircuit['ParentNode'] == 'none':
                         thisCircuit['idForCircuitsWithoutParentNodes'] = counterForCircuitsWithoutParentNodes
                         dictForCircuitsWithoutParentNodes[counterForCircuitsWithoutParentNodes] = ((float(downloadMax))+(float(uploadMax)))
@@ -1396,6 +1437,25 @@ def normalize_sqm_override_token(raw_token):
     return token
 
 
+def normalize_priority_classes(raw_value):
+    """
+    Normalizes a priority class list (comma-separated string or list) into
+    unique lowercase class names, preserving order.
+    """
+    if raw_value is None:
+        return []
+    if isinstance(raw_value, str):
+        entries = raw_value.split(',')
+    else:
+        entries = [str(entry) for entry in raw_value]
+    classes = []
+    for entry in entries:
+        name = entry.strip().lower()
+        if name != '' and name not in classes:
+            classes.append(name)
+    return classes
+
+
 def apply_effective_runtime_circuit_overrides(subscriberCircuits):
     """
     Overlay adaptive runtime circuit adjustments in memory without mutating
@@ -1408,7 +1468,15 @@ def apply_effective_runtime_circuit_overrides(subscriberCircuits):
         return 0
 
     sqm_by_device_id = {}
+    priority_classes_by_circuit_id = {}
     for adj in adjustments:
+        if adj.get('type') == 'circuit_adjust_priority_classes':
+            circuit_id = (adj.get('circuit_id') or '').strip()
+            if circuit_id != '':
+                priority_classes_by_circuit_id[circuit_id] = normalize_priority_classes(
+                    adj.get('priority_classes', [])
+                )
+            continue
         if adj.get('type') != 'device_adjust_sqm':
             continue
         device_id = (adj.get('device_id') or '').strip()
@@ -1417,11 +1485,19 @@ def apply_effective_runtime_circuit_overrides(subscriberCircuits):
             continue
         sqm_by_device_id[device_id] = sqm_override
 
-    if not sqm_by_device_id:
+    if not sqm_by_device_id and not priority_classes_by_circuit_id:
         return 0
 
     overlay_count = 0
     for circuit in subscriberCircuits:
+        classes = priority_classes_by_circuit_id.get(circuit.get('circuitID', ''))
+        if classes is not None and circuit.get('priorityClasses', []) != classes:
+            overlay_count += 1
+            if classes:
+                circuit['priorityClasses'] = classes
+            else:
+                circuit.pop('priorityClasses', None)
+
         circuit_override = None
         for device in circuit.get('devices', []):
             override = sqm_by_device_id.get(device.get('deviceID', ''))
@@ -1528,6 +1604,22 @@ def refreshShapers():
         with open(networkJSONfile, 'r') as j:
             network = json.loads(j.read())
 
+        # Site-level SQM profiles: circuits without their own override inherit the
+        # nearest "sqm" value set on their logical parent chain in network.json.
+        if isinstance(network, dict) and len(network) > 0:
+            node_sqm = build_node_sqm_map(network)
+            if len(node_sqm) > 0:
+                inherited_count = 0
+                for circuit in subscriberCircuits:
+                    if circuit.get('sqm'):
+                        continue
+                    site_sqm = node_sqm.get(circuit.get('logicalParentNode'))
+                    if site_sqm:
+                        circuit['sqm'] = site_sqm
+                        inherited_count += 1
+                if inherited_count > 0:
+                    print(f"Applied site-level SQM to {inherited_count} circuit(s) from network.json")
+
         # Flat networks ({}) don't require ParentNode entries. Treat every circuit as
         # unparented so they can be distributed across generated parent nodes / CPUs.
         flat_network = loaded_network_is_flat(network)
@@ -2113,21 +2205,54 @@ def refreshShapers():
                 move_budget = 0
 
             planner_mode = "stable_greedy" if insight_enabled else "round_robin"
+            # Traffic weighting is part of Insight binpacking; without Insight the
+            # round-robin fallback above stands.
             try:
-                plan_result = plan_top_level_cpu_bins(
-                    items,
-                    queuesAvailable,
-                    prev_assign=prev_assign,
-                    last_change_ts=last_change_ts,
-                    now_ts=now_ts,
-                    mode=planner_mode,
-                    move_budget_per_run=move_budget,
-                    cooldown_seconds=3600.0,
-                    hysteresis_threshold=0.03,
-                )
+                if insight_enabled and traffic_planner_enabled():
+                    planner_mode = "traffic_weighted"
+            except Exception:
+                pass
+            plan_kwargs = dict(
+                prev_assign=prev_assign,
+                last_change_ts=last_change_ts,
+                now_ts=now_ts,
+                move_budget_per_run=move_budget,
+                cooldown_seconds=3600.0,
+                hysteresis_threshold=0.03,
+            )
+            try:
+                try:
+                    plan_result = plan_top_level_cpu_bins(
+                        items, queuesAvailable, mode=planner_mode, **plan_kwargs
+                    )
+                except Exception as e:
+                    if planner_mode != "traffic_weighted":
+                        raise
+                    warnings.warn(
+                        f"Traffic-weighted planner failed ({e}); planning with configured weights.",
+                        stacklevel=2,
+                    )
+                    planner_mode = "stable_greedy"
+                    plan_result = plan_top_level_cpu_bins(
+                        items, queuesAvailable, mode=planner_mode, **plan_kwargs
+                    )
                 assignment = dict(plan_result.get("assignment", {}) or {})
                 changed = list(plan_result.get("changed", []) or [])
                 planner_used = bool(plan_result.get("planner_used", False))
+                if planner_mode == "traffic_weighted":
+                    print(
+                        "Traffic-weighted planner: "
+                        + str(len(plan_result.get("observed", []) or []))
+                        + " of "
+                        + str(len(items))
+                        + " top-level nodes have observed traffic."
+                    )
+                    for row in plan_result.get("projection", []) or []:
+                        print(
+                            f"  {row.get('bin')}: projected load {row.get('before', 0.0):.1f} -> "
+                            f"{row.get('after', 0.0):.1f} Mbps "
+                            f"({row.get('items_before', 0)} -> {row.get('items_after', 0)} nodes)"
+                        )
             except Exception as e:
                 warnings.warn(
                     f"Shared Rust planner failed ({e}); falling back to deterministic local assignment.",
@@ -2197,6 +2322,9 @@ def refreshShapers():
                     if iid in changed:
                         state["last_change_ts"][iid] = now_ts
                     state["assignments"][iid] = b
+                # The CPU affinity page projects the next run from these, so its
+                # preview uses the same weights as the planner.
+                state["weights"] = {str(it["id"]): float(it["weight"]) for it in items}
                 try:
                     print(f"Saving planner state to {state_path} (top-level CPU binning)")
                     save_planner_state(state, state_path, None)
@@ -2705,6 +2833,7 @@ def refreshShapers():
                             int(circuit['up_classMajor'], 16),
                             ip_addresses_str,
                             sqm_override,
+                            circuit.get('priorityClasses', []),
                         )
                         command = 'class add dev ' + interface_a() + ' parent ' + data[node]['classid'] + ' classid ' + circuit['classMinor'] + ' htb rate '+ format_rate_for_tc(min_down) + ' ceil '+ format_rate_for_tc(circuit['maxDownload']) + ' prio 3' + quantum(circuit['maxDownload']) + tcComment
                         linuxTCcommands.append(command)
@@ -2893,7 +3022,7 @@ def refreshShapers():
         # Observe mode removes the shaping tree entirely, so clear any live
         # per-circuit IP mappings before the commit to avoid pointing packets
         # at handles that are about to disappear.
-        if enable_actual_shell_commands() and observe_mode:
+        if enable_actual_shell_commands() and observe_mode and PLAN_OUTPUT_PATH is None:
             logging.info("# Observe Mode Mapping Quiesce")
             try:
                 clear_ip_mappings()
@@ -2937,6 +3066,10 @@ def refreshShapers():
         # 	shell("/sbin/tc -b linux_tc.txt")
         # else:
         # 	shell("/sbin/tc -f -b linux_tc.txt")
+        if PLAN_OUTPUT_PATH is not None:
+            # Plan only: nothing below this point may touch the live system.
+            write_bakery_plan(bakery, PLAN_OUTPUT_PATH)
+            return
         bakery.commit()
         tcEndTime = datetime.now()
         # print("Executed " + str(len(linuxTCcommands)) + " linux TC class/qdisc commands")
@@ -3175,6 +3308,15 @@ if __name__ == '__main__':
         help="Clear ip filters, qdiscs, and xdp setup if any",
         action=argparse.BooleanOptionalAction,
     )
+    parser.add_argument(
+        '--plan',
+        help="Plan the Bakery changes without applying them (lqosd runs this against staged inputs and state)",
+        action=argparse.BooleanOptionalAction,
+    )
+    parser.add_argument(
+        '--plan-output',
+        help="Where --plan writes the plan as JSON (default: shaping/bakery_plan.json in the state directory)",
+    )
     parser.add_argument(
         '--planner-reset',
         help="Delete planner state file before running",
@@ -3192,6 +3334,13 @@ if __name__ == '__main__':
         except Exception as e:
             print(f"Warning: could not remove planner state: {e}")
 
+    if args.plan:
+        PLAN_OUTPUT_PATH = args.plan_output or get_state_path("shaping", "bakery_plan.json")
+        # A plan must not raise or clear operator-facing issues on the live daemon.
+        submit_urgent_issue = lambda *_args, **_kwargs: False
+        clear_urgent_issue_by_identity = lambda *_args, **_kwargs: False
+        scheduler_error = lambda *_args, **_kwargs: None
+
     exit_code = 0
     try:
         if args.validate:
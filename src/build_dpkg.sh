@@ -33,6 +33,7 @@ LQOS_FILES=(
   integrationWISPGate.py
   LibreQoS.py
   lqos.example
+  lqos_live.py
   lqTools.py
   mikrotikFindIPv6.py
   mikrotik_ipv6.example.toml
@@ -70,6 +71,7 @@ RUSTPROGS=(
   lqos_map_perf
   uisp_integration
   lqos_overrides
+  lqos_ha
 )
 
 ####################################################
@@ -118,6 +120,7 @@ cargo build --release \
   -p uisp_integration \
   -p lqos_python \
   -p lqos_overrides \
+  -p lqos_ha \
   -p lqos_topology
 popd > /dev/null || exit
 
@@ -72,6 +72,7 @@ PROGS=(
     lqos_map_perf
     uisp_integration
     lqos_overrides
+    lqos_ha
 )
 BUILD_PACKAGES=(
     lqosd
@@ -84,6 +85,7 @@ BUILD_PACKAGES=(
     lqos_map_perf
     uisp_integration
     lqos_overrides
+    lqos_ha
     lqos_topology
     lqos_python
 )
@@ -0,0 +1,323 @@
+"""Live shaper data for notebooks and scripts.
+
+Thin wrappers over the ``live_*`` functions in ``liblqos_python``, which query
+lqosd over the local bus. Every call has three forms:
+
+* ``top_circuits(10)`` returns typed dataclasses.
+* ``top_circuits_rows(10)`` returns plain dicts, ready for
+  ``pandas.DataFrame(...)``.
+* ``await atop_circuits(10)`` is the asyncio version of the first; it runs the
+  bus query in a worker thread.
+
+``throughput_ticks()`` and ``athroughput_ticks()`` yield one ``Throughput``
+sample per interval until stopped.
+
+Example::
+
+    import pandas as pd
+    import lqos_live
+
+    print(lqos_live.throughput().bits_per_second_down)
+    df = pd.DataFrame(lqos_live.top_circuits_rows(20, direction="up"))
+    for tick in lqos_live.throughput_ticks(interval=1.0, count=10):
+        print(tick.timestamp, tick.bits_per_second_down)
+"""
+
+import asyncio
+import time
+from dataclasses import dataclass, field, fields
+
+import liblqos_python as _lqos
+
+
+def _build(cls, row):
+    if row is None:
+        return None
+    names = {f.name for f in fields(cls)}
+    known = {key: value for key, value in row.items() if key in names}
+    extra = {key: value for key, value in row.items() if key not in names}
+    if "extra" in names:
+        known["extra"] = extra
+    return cls(**known)
+
+
+@dataclass
+class Throughput:
+    timestamp: float = 0.0
+    bits_per_second_down: int = 0
+    bits_per_second_up: int = 0
+    packets_per_second_down: int = 0
+    packets_per_second_up: int = 0
+    tcp_packets_per_second_down: int = 0
+    tcp_packets_per_second_up: int = 0
+    udp_packets_per_second_down: int = 0
+    udp_packets_per_second_up: int = 0
+    icmp_packets_per_second_down: int = 0
+    icmp_packets_per_second_up: int = 0
+    shaped_bits_per_second_down: int = 0
+    shaped_bits_per_second_up: int = 0
+
+
+@dataclass
+class CircuitHealth:
+    """Live rate, RTT, retransmits and QoO for one circuit."""
+
+    circuit_id: str = ""
+    circuit_name: str = ""
+    parent_node: str = ""
+    device_names: list = field(default_factory=list)
+    ip_addrs: list = field(default_factory=list)
+    plan_mbps_down: float = 0.0
+    plan_mbps_up: float = 0.0
+    bytes_per_second_down: int = 0
+    bytes_per_second_up: int = 0
+    rtt_current_p50_nanos_down: int | None = None
+    rtt_current_p50_nanos_up: int | None = None
+    qoo_down: float | None = None
+    qoo_up: float | None = None
+    tcp_retransmit_sample_down_retransmits: int = 0
+    tcp_retransmit_sample_down_packets: int = 0
+    tcp_retransmit_sample_up_retransmits: int = 0
+    tcp_retransmit_sample_up_packets: int = 0
+    last_seen_nanos: int = 0
+    extra: dict = field(default_factory=dict)
+
+    @property
+    def rtt_ms_down(self):
+        if self.rtt_current_p50_nanos_down is None:
+            return None
+        return self.rtt_current_p50_nanos_down / 1_000_000
+
+    @property
+    def rtt_ms_up(self):
+        if self.rtt_current_p50_nanos_up is None:
+            return None
+        return self.rtt_current_p50_nanos_up / 1_000_000
+
+    @property
+    def retransmit_fraction_down(self):
+        if self.tcp_retransmit_sample_down_packets == 0:
+            return 0.0
+        return (
+            self.tcp_retransmit_sample_down_retransmits
+            / self.tcp_retransmit_sample_down_packets
+        )
+
+    @property
+    def retransmit_fraction_up(self):
+        if self.tcp_retransmit_sample_up_packets == 0:
+            return 0.0
+        return (
+            self.tcp_retransmit_sample_up_retransmits
+            / self.tcp_retransmit_sample_up_packets
+        )
+
+
+@dataclass
+class Flow:
+    local_ip: str = ""
+    remote_ip: str = ""
+    src_port: int = 0
+    dst_port: int = 0
+    ip_protocol: str = ""
+    bytes_sent_down: int = 0
+    bytes_sent_up: int = 0
+    rate_estimate_bps_down: int = 0
+    rate_estimate_bps_up: int = 0
+    rtt_nanos_down: int = 0
+    rtt_nanos_up: int = 0
+    tcp_retransmits_down: int = 0
+    tcp_retransmits_up: int = 0
+    remote_asn: int = 0
+    remote_asn_name: str = ""
+    circuit_id: str = ""
+    circuit_name: str = ""
+    extra: dict = field(default_factory=dict)
+
+
+@dataclass
+class QueueStats:
+    marks_down: int = 0
+    marks_up: int = 0
+    drops_down: int = 0
+    drops_up: int = 0
+
+
+@dataclass
+class UrgentIssue:
+    id: int = 0
+    ts: int = 0
+    source: str = ""
+    severity: str = ""
+    code: str = ""
+    message: str = ""
+    context: str | None = None
+    dedupe_key: str | None = None
+
+
+@dataclass
+class BakeryStatus:
+    active_circuits: int = 0
+    reload_in_progress: bool = False
+    circuit_count: int = 0
+
+
+# Plain rows, for pandas.
+
+def throughput_row():
+    row = _lqos.live_throughput()
+    row["timestamp"] = time.time()
+    return row
+
+
+def top_circuits_rows(n=10, direction="down"):
+    return _lqos.live_top_circuits(n, direction)
+
+
+def circuits_rows():
+    return _lqos.live_circuits()
+
+
+def circuit_row(circuit_id):
+    return _lqos.live_circuit(circuit_id)
+
+
+def circuit_qoo_row(circuit_id):
+    return _lqos.live_circuit_qoo(circuit_id)
+
+
+def circuit_top_applications_rows(circuit_id):
+    return _lqos.live_circuit_top_applications(circuit_id)
+
+
+def flows_by_ip_rows(ip):
+    return _lqos.live_flows_by_ip(ip)
+
+
+def queue_stats_row():
+    return _lqos.live_queue_stats()
+
+
+def urgent_issues_rows():
+    return _lqos.live_urgent_issues()
+
+
+def bakery_status_row():
+    return _lqos.live_bakery_status()
+
+
+# Typed results.
+
+def throughput():
+    return _build(Throughput, throughput_row())
+
+
+def top_circuits(n=10, direction="down"):
+    return [_build(CircuitHealth, row) for row in top_circuits_rows(n, direction)]
+
+
+def circuits():
+    return [_build(CircuitHealth, row) for row in circuits_rows()]
+
+
+def circuit(circuit_id):
+    """Returns ``None`` when the circuit has no recent traffic."""
+    return _build(CircuitHealth, circuit_row(circuit_id))
+
+
+def circuit_qoo(circuit_id):
+    """QoO history for a circuit as a dict, or ``None``."""
+    return circuit_qoo_row(circuit_id)
+
+
+def flows_by_ip(ip):
+    return [_build(Flow, row) for row in flows_by_ip_rows(ip)]
+
+
+def queue_stats():
+    return _build(QueueStats, queue_stats_row())
+
+
+def urgent_issues():
+    return [_build(UrgentIssue, row) for row in urgent_issues_rows()]
+
+
+def bakery_status():
+    return _build(BakeryStatus, bakery_status_row())
+
+
+# asyncio.
+
+async def athroughput():
+    return await asyncio.to_thread(throughput)
+
+
+async def atop_circuits(n=10, direction="down"):
+    return await asyncio.to_thread(top_circuits, n, direction)
+
+
+async def acircuits():
+    return await asyncio.to_thread(circuits)
+
+
+async def acircuit(circuit_id):
+    return await asyncio.to_thread(circuit, circuit_id)
+
+
+async def acircuit_qoo(circuit_id):
+    return await asyncio.to_thread(circuit_qoo, circuit_id)
+
+
+async def aflows_by_ip(ip):
+    return await asyncio.to_thread(flows_by_ip, ip)
+
+
+async def aqueue_stats():
+    return await asyncio.to_thread(queue_stats)
+
+
+async def aurgent_issues():
+    return await asyncio.to_thread(urgent_issues)
+
+
+async def abakery_status():
+    return await asyncio.to_thread(bakery_status)
+
+
+# Streaming.
+
+def throughput_ticks(interval=1.0, count=None, rows=False):
+    """Yields a throughput sample every ``interval`` seconds.
+
+    Stops after ``count`` samples, or runs until the caller stops iterating.
+    With ``rows=True`` each sample is a plain dict instead of a dataclass.
+    """
+    sample = throughput_row if rows else throughput
+    taken = 0
+    next_tick = time.monotonic()
+    while count is None or taken < count:
+        yield sample()
+        taken += 1
+        next_tick += interval
+        delay = next_tick - time.monotonic()
+        if delay > 0:
+            time.sleep(delay)
+        else:
+            next_tick = time.monotonic()
+
+
+async def athroughput_ticks(interval=1.0, count=None, rows=False):
+    """Async version of ``throughput_ticks``."""
+    sample = throughput_row if rows else throughput
+    loop = asyncio.get_running_loop()
+    taken = 0
+    next_tick = loop.time()
+    while count is None or taken < count:
+        yield await asyncio.to_thread(sample)
+        taken += 1
+        next_tick += interval
+        delay = next_tick - loop.time()
+        if delay > 0:
+            await asyncio.sleep(delay)
+        else:
+            next_tick = loop.time()
@@ -1,10 +1,12 @@
 use crate::MQ_CREATED;
+use crate::priority_lanes::{LaneLeafParams, lane_leaf_commands, priority_lanes};
 use crate::qdisc_handles::{InfraQdiscSlot, infra_qdisc_handle};
 use crate::queue_math::{
-    format_rate_for_tc, format_rate_for_tc_f32, quantum, r2q, sqm_as_vec, sqm_tokens_for,
+    SqmDirection, format_rate_for_tc, format_rate_for_tc_f32, quantum, r2q, sqm_as_vec,
+    sqm_tokens_for,
 };
 use allocative::Allocative;
-use lqos_bus::TcHandle;
+use lqos_bus::{BusRequest, TcHandle};
 use lqos_config::LazyQueueMode;
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -102,8 +104,11 @@ struct AddCircuitParams {
     up_class_major: u16,
     down_qdisc_handle: Option<u16>,
     up_qdisc_handle: Option<u16>,
-    // Optional per-circuit SQM override: "cake" or "fq_codel"
+    // Optional per-circuit SQM override: built-in qdisc, SQM profile name, or "down/up"
     sqm_override: Option<String>,
+    priority_classes: Vec<String>,
+    down_lane_qdisc_handles: Vec<u16>,
+    up_lane_qdisc_handles: Vec<u16>,
 }
 
 /// Execution Mode
@@ -199,6 +204,15 @@ pub enum BakeryCommands {
     StartBatch,
     /// Commit the current batch, diffing and applying queued changes.
     CommitBatch,
+    /// Plan a batch without applying it. The batch is built by the caller, so
+    /// the live batch between `StartBatch` and `CommitBatch` is untouched.
+    PlanBatch {
+        /// The `MqSetup`, `AddSite` and `AddCircuit` commands to plan.
+        batch: Vec<BakeryCommands>,
+        /// Synchronous reply with the plan.
+        #[allocative(skip)]
+        reply: ReplySender<lqos_bus::BakeryPlanReport>,
+    },
     /// Set up MQ roots and per-queue parents on one or both interfaces.
     MqSetup {
         /// Total number of MQ queues to create per interface.
@@ -258,8 +272,15 @@ pub enum BakeryCommands {
         up_qdisc_handle: Option<u16>,
         /// Concatenated list of all IPs for this circuit.
         ip_addresses: String, // Concatenated list of all IPs for this circuit
-        /// Optional per-circuit SQM override: "cake" or "fq_codel"
+        /// Optional per-circuit SQM override: "cake", "fq_codel", "fq_pie", "none",
+        /// an SQM profile name, or a directional "down/up" pair of those.
         sqm_override: Option<String>,
+        /// Intra-circuit priority class names, resolved against `lqos.conf`.
+        priority_classes: Vec<String>,
+        /// Explicit qdisc handle majors for the downlink priority-lane leaves.
+        down_lane_qdisc_handles: Vec<u16>,
+        /// Explicit qdisc handle majors for the uplink priority-lane leaves.
+        up_lane_qdisc_handles: Vec<u16>,
     },
     /// Create or update a runtime-only dynamic circuit overlay entry.
     ///
@@ -340,6 +361,78 @@ pub enum BakeryCommands {
 }
 
 impl BakeryCommands {
+    /// Converts a `BakeryMqSetup`, `BakeryAddSite` or `BakeryAddCircuit` bus
+    /// request into the matching batch command. Returns `None` for any other
+    /// request.
+    pub fn from_batch_request(request: &BusRequest) -> Option<Self> {
+        match request {
+            BusRequest::BakeryMqSetup {
+                queues_available,
+                stick_offset,
+            } => Some(Self::MqSetup {
+                queues_available: *queues_available,
+                stick_offset: *stick_offset,
+            }),
+            BusRequest::BakeryAddSite {
+                site_hash,
+                parent_class_id,
+                up_parent_class_id,
+                class_minor,
+                download_bandwidth_min,
+                upload_bandwidth_min,
+                download_bandwidth_max,
+                upload_bandwidth_max,
+            } => Some(Self::AddSite {
+                site_hash: *site_hash,
+                parent_class_id: *parent_class_id,
+                up_parent_class_id: *up_parent_class_id,
+                class_minor: *class_minor,
+                download_bandwidth_min: *download_bandwidth_min,
+                upload_bandwidth_min: *upload_bandwidth_min,
+                download_bandwidth_max: *download_bandwidth_max,
+                upload_bandwidth_max: *upload_bandwidth_max,
+            }),
+            BusRequest::BakeryAddCircuit {
+                circuit_hash,
+                circuit_name,
+                site_name,
+                parent_class_id,
+                up_parent_class_id,
+                class_minor,
+                download_bandwidth_min,
+                upload_bandwidth_min,
+                download_bandwidth_max,
+                upload_bandwidth_max,
+                class_major,
+                up_class_major,
+                ip_addresses,
+                sqm_override,
+                priority_classes,
+            } => Some(Self::AddCircuit {
+                circuit_hash: *circuit_hash,
+                circuit_name: circuit_name.clone(),
+                site_name: site_name.clone(),
+                parent_class_id: *parent_class_id,
+                up_parent_class_id: *up_parent_class_id,
+                class_minor: *class_minor,
+                download_bandwidth_min: *download_bandwidth_min,
+                upload_bandwidth_min: *upload_bandwidth_min,
+                download_bandwidth_max: *download_bandwidth_max,
+                upload_bandwidth_max: *upload_bandwidth_max,
+                class_major: *class_major,
+                up_class_major: *up_class_major,
+                down_qdisc_handle: None,
+                up_qdisc_handle: None,
+                ip_addresses: ip_addresses.clone(),
+                sqm_override: sqm_override.clone(),
+                priority_classes: priority_classes.clone(),
+                down_lane_qdisc_handles: Vec::new(),
+                up_lane_qdisc_handles: Vec::new(),
+            }),
+            _ => None,
+        }
+    }
+
     /// Translate this command into concrete `tc` argument vectors.
     ///
     /// Returns a list of `tc` argv arrays in execution order, or `None`
@@ -404,6 +497,9 @@ impl BakeryCommands {
                 up_qdisc_handle,
                 ip_addresses: _,
                 sqm_override,
+                priority_classes,
+                down_lane_qdisc_handles,
+                up_lane_qdisc_handles,
             } => Self::add_circuit(
                 execution_mode,
                 config,
@@ -421,6 +517,9 @@ impl BakeryCommands {
                     down_qdisc_handle: *down_qdisc_handle,
                     up_qdisc_handle: *up_qdisc_handle,
                     sqm_override: sqm_override.clone(),
+                    priority_classes: priority_classes.clone(),
+                    down_lane_qdisc_handles: down_lane_qdisc_handles.clone(),
+                    up_lane_qdisc_handles: up_lane_qdisc_handles.clone(),
                 },
             ),
             _ => None,
@@ -888,24 +987,50 @@ impl BakeryCommands {
             && do_sqm
             && !matches!(down_override_opt.as_deref(), Some(s) if s.eq_ignore_ascii_case("none"))
         {
-            let mut sqm_command = vec![
-                "qdisc".to_string(),
-                "replace".to_string(),
-                "dev".to_string(),
-                config.isp_interface(),
-                "parent".to_string(),
-                format!("0x{:x}:0x{:x}", params.class_major, params.class_minor),
-            ];
-            if let Some(handle) = params.down_qdisc_handle {
-                sqm_command.push("handle".to_string());
-                sqm_command.push(format!("0x{:x}:", handle));
-            }
-            sqm_command.extend(sqm_tokens_for(
-                params.download_bandwidth_max,
+            let lanes = priority_lanes(
                 config,
+                &params.priority_classes,
+                params.download_bandwidth_max,
+                false,
                 &down_override_opt,
-            ));
-            result.push(sqm_command);
+            );
+            if let Some(handle) = params.down_qdisc_handle
+                && !lanes.is_empty()
+                && params.down_lane_qdisc_handles.len() >= lanes.len()
+            {
+                result.extend(lane_leaf_commands(
+                    config,
+                    LaneLeafParams {
+                        interface: config.isp_interface(),
+                        parent: format!("0x{:x}:0x{:x}", params.class_major, params.class_minor),
+                        handle,
+                        lane_handles: &params.down_lane_qdisc_handles,
+                        lanes: &lanes,
+                        circuit_max_mbps: params.download_bandwidth_max,
+                        direction: SqmDirection::Download,
+                    },
+                ));
+            } else {
+                let mut sqm_command = vec![
+                    "qdisc".to_string(),
+                    "replace".to_string(),
+                    "dev".to_string(),
+                    config.isp_interface(),
+                    "parent".to_string(),
+                    format!("0x{:x}:0x{:x}", params.class_major, params.class_minor),
+                ];
+                if let Some(handle) = params.down_qdisc_handle {
+                    sqm_command.push("handle".to_string());
+                    sqm_command.push(format!("0x{:x}:", handle));
+                }
+                sqm_command.extend(sqm_tokens_for(
+                    params.download_bandwidth_max,
+                    config,
+                    &down_override_opt,
+                    SqmDirection::Download,
+                ));
+                result.push(sqm_command);
+            }
         }
 
         if do_htb {
@@ -940,24 +1065,50 @@ impl BakeryCommands {
             && !config.on_a_stick_mode()
             && !matches!(up_override_opt.as_deref(), Some(s) if s.eq_ignore_ascii_case("none"))
         {
-            let mut sqm_command = vec![
-                "qdisc".to_string(),
-                "replace".to_string(),
-                "dev".to_string(),
-                config.internet_interface(),
-                "parent".to_string(),
-                format!("0x{:x}:0x{:x}", params.up_class_major, params.class_minor),
-            ];
-            if let Some(handle) = params.up_qdisc_handle {
-                sqm_command.push("handle".to_string());
-                sqm_command.push(format!("0x{:x}:", handle));
-            }
-            sqm_command.extend(sqm_tokens_for(
-                params.upload_bandwidth_max,
+            let lanes = priority_lanes(
                 config,
+                &params.priority_classes,
+                params.upload_bandwidth_max,
+                true,
                 &up_override_opt,
-            ));
-            result.push(sqm_command);
+            );
+            if let Some(handle) = params.up_qdisc_handle
+                && !lanes.is_empty()
+                && params.up_lane_qdisc_handles.len() >= lanes.len()
+            {
+                result.extend(lane_leaf_commands(
+                    config,
+                    LaneLeafParams {
+                        interface: config.internet_interface(),
+                        parent: format!("0x{:x}:0x{:x}", params.up_class_major, params.class_minor),
+                        handle,
+                        lane_handles: &params.up_lane_qdisc_handles,
+                        lanes: &lanes,
+                        circuit_max_mbps: params.upload_bandwidth_max,
+                        direction: SqmDirection::Upload,
+                    },
+                ));
+            } else {
+                let mut sqm_command = vec![
+                    "qdisc".to_string(),
+                    "replace".to_string(),
+                    "dev".to_string(),
+                    config.internet_interface(),
+                    "parent".to_string(),
+                    format!("0x{:x}:0x{:x}", params.up_class_major, params.class_minor),
+                ];
+                if let Some(handle) = params.up_qdisc_handle {
+                    sqm_command.push("handle".to_string());
+                    sqm_command.push(format!("0x{:x}:", handle));
+                }
+                sqm_command.extend(sqm_tokens_for(
+                    params.upload_bandwidth_max,
+                    config,
+                    &up_override_opt,
+                    SqmDirection::Upload,
+                ));
+                result.push(sqm_command);
+            }
         }
 
         Some(result)
@@ -1367,6 +1518,9 @@ mod tests {
             up_qdisc_handle: Some(0x9001),
             ip_addresses: "192.0.2.42/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         }
     }
 
@@ -1388,6 +1542,53 @@ mod tests {
         assert_qdisc_add_replace_commands_use_explicit_handles(&live_commands);
     }
 
+    #[test]
+    fn add_circuit_with_priority_classes_builds_explicitly_handled_lanes() {
+        let config = Arc::new(Config {
+            priority_classes: vec![lqos_config::PriorityClassConfig {
+                name: "voip".to_string(),
+                download_min_mbps: 2.0,
+                upload_min_mbps: 2.0,
+                download_max_mbps: None,
+                upload_max_mbps: None,
+                sqm: String::new(),
+                dscp: vec![46],
+                ports: Vec::new(),
+                prefixes: Vec::new(),
+            }],
+            ..Config::default()
+        });
+        let mut circuit = test_circuit_command();
+        if let BakeryCommands::AddCircuit {
+            priority_classes,
+            down_lane_qdisc_handles,
+            up_lane_qdisc_handles,
+            ..
+        } = &mut circuit
+        {
+            *priority_classes = vec!["voip".to_string()];
+            *down_lane_qdisc_handles = vec![0x9100, 0x9101];
+            *up_lane_qdisc_handles = vec![0x9200, 0x9201];
+        }
+
+        let commands = circuit
+            .to_commands(&config, ExecutionMode::Builder)
+            .expect("builder add_circuit should emit commands");
+        assert_qdisc_add_replace_commands_use_explicit_handles(&commands);
+        let lines: Vec<String> = commands.iter().map(|cmd| cmd.join(" ")).collect();
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.contains("parent 0x1:0x21 handle 0x9000: htb default 0x10"))
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.contains("parent 0x9001:0x11 handle 0x9201:"))
+        );
+        assert_eq!(lines.iter().filter(|l| l.starts_with("filter")).count(), 2);
+    }
+
     #[test]
     fn htb_lazy_builder_defers_leaf_qdiscs_until_activation() {
         let mut htb_cfg = Config::default();
@@ -107,6 +107,7 @@ fn classify_circuit_change(a: &BakeryCommands, b: &BakeryCommands) -> CircuitCha
         up_class_major,
         ip_addresses,
         sqm_override,
+        priority_classes,
         ..
     } = a
     else {
@@ -128,6 +129,7 @@ fn classify_circuit_change(a: &BakeryCommands, b: &BakeryCommands) -> CircuitCha
         up_class_major: other_up_class_major,
         ip_addresses: other_ip_addresses,
         sqm_override: other_sqm_override,
+        priority_classes: other_priority_classes,
         ..
     } = b
     else {
@@ -152,7 +154,8 @@ fn classify_circuit_change(a: &BakeryCommands, b: &BakeryCommands) -> CircuitCha
         || upload_bandwidth_min != other_upload_bandwidth_min
         || download_bandwidth_max != other_download_bandwidth_max
         || upload_bandwidth_max != other_upload_bandwidth_max
-        || sqm_override != other_sqm_override; // treat SQM override changes as speed-level changes
+        || sqm_override != other_sqm_override // treat SQM override changes as speed-level changes
+        || priority_classes != other_priority_classes;
 
     let ip = ip_addresses != other_ip_addresses;
 
@@ -202,6 +205,9 @@ mod tests {
             up_qdisc_handle: Some(0x9001),
             ip_addresses: "192.0.2.1/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         })
     }
 
@@ -278,6 +284,9 @@ mod tests {
             up_qdisc_handle: *up_qdisc_handle,
             ip_addresses: "198.51.100.10/32".to_string(),
             sqm_override: sqm_override.clone(),
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
         let old_circuits = HashMap::from([(1, Arc::clone(&old))]);
         let batch = vec![new];
@@ -21,6 +21,8 @@
 
 mod commands;
 mod diff;
+mod plan;
+mod priority_lanes;
 mod qdisc_handles;
 mod queue_math;
 mod utils;
@@ -48,8 +50,14 @@ use crate::commands::{
 use crate::diff::{
     CircuitDiffResult, SiteDiffResult, StructuralSiteDiffDetails, diff_circuits, diff_sites,
 };
+use crate::priority_lanes::{
+    assign_lane_qdisc_handles, directional_lane_counts, release_lane_qdisc_handles,
+    sync_priority_lane_map,
+};
 use crate::qdisc_handles::QdiscHandleState;
-use crate::queue_math::{SqmKind, effective_sqm_kind, format_rate_for_tc_f32, quantum, r2q};
+use crate::queue_math::{
+    SqmDirection, SqmKind, effective_sqm_kind, format_rate_for_tc_f32, quantum, r2q,
+};
 use crate::utils::{
     ExecuteResult, LiveTcClassEntry, LiveTcQdiscEntry, MemorySnapshot, execute_in_memory,
     execute_in_memory_chunked, invalidate_live_tc_snapshots, read_live_class_snapshot,
@@ -743,6 +751,9 @@ fn append_dynamic_circuit_overlays_to_batch(
             up_qdisc_handle: None,
             ip_addresses,
             sqm_override: entry.shaped_device.sqm_override.clone(),
+            priority_classes: entry.shaped_device.priority_classes.clone(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         }));
     }
 }
@@ -875,6 +886,9 @@ fn handle_upsert_dynamic_circuit_overlay(
         up_qdisc_handle: None,
         ip_addresses,
         sqm_override: entry.shaped_device.sqm_override.clone(),
+        priority_classes: entry.shaped_device.priority_classes.clone(),
+        down_lane_qdisc_handles: Vec::new(),
+        up_lane_qdisc_handles: Vec::new(),
     });
 
     let mapped_limit = resolve_mapped_circuit_limit();
@@ -927,6 +941,7 @@ fn handle_upsert_dynamic_circuit_overlay(
     live_circuits.remove(&circuit_hash);
     qdisc_handles.save(&config);
     update_queue_distribution_snapshot(sites, circuits);
+    sync_priority_lane_map(&config, circuits);
 
     Ok(Some(TcHandle::from_u32(
         ((down_major as u32) << 16) | (class_minor as u32),
@@ -993,11 +1008,14 @@ fn handle_remove_dynamic_circuit_overlay(
         }
         live_circuits.remove(&circuit_hash);
         qdisc_handles.release_circuit(&config.isp_interface(), circuit_hash);
+        release_lane_qdisc_handles(qdisc_handles, &config.isp_interface(), circuit_hash);
         if !config.on_a_stick_mode() {
             qdisc_handles.release_circuit(&config.internet_interface(), circuit_hash);
+            release_lane_qdisc_handles(qdisc_handles, &config.internet_interface(), circuit_hash);
         }
         qdisc_handles.save(&config);
         update_queue_distribution_snapshot(sites, circuits);
+        sync_priority_lane_map(&config, circuits);
     }
 
     Ok(())
@@ -1212,6 +1230,9 @@ fn build_temp_add_cmd(
         up_qdisc_handle,
         ip_addresses,
         sqm_override,
+        priority_classes,
+        down_lane_qdisc_handles,
+        up_lane_qdisc_handles,
         ..
     } = base
     {
@@ -1234,6 +1255,23 @@ fn build_temp_add_cmd(
             up_qdisc_handle: preserve_qdisc_handles.then_some(*up_qdisc_handle).flatten(),
             ip_addresses: ip_addresses.clone(),
             sqm_override: sqm_override.clone(),
+            // Temporary queues keep priority lanes only when they reuse the
+            // circuit's own qdisc handles.
+            priority_classes: if preserve_qdisc_handles {
+                priority_classes.clone()
+            } else {
+                Vec::new()
+            },
+            down_lane_qdisc_handles: if preserve_qdisc_handles {
+                down_lane_qdisc_handles.clone()
+            } else {
+                Vec::new()
+            },
+            up_lane_qdisc_handles: if preserve_qdisc_handles {
+                up_lane_qdisc_handles.clone()
+            } else {
+                Vec::new()
+            },
         })
     } else {
         None
@@ -1321,6 +1359,9 @@ fn build_shadow_add_cmd(
             up_qdisc_handle: migration.up_qdisc_handle,
             ip_addresses: String::new(),
             sqm_override: migration.sqm_override.clone(),
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         },
         migration.shadow_minor,
         migration.old_down_min,
@@ -1571,7 +1612,7 @@ pub struct BakeryCapacityInterfaceSnapshot {
     pub infra_qdiscs: usize,
     /// Planned `cake` leaf qdiscs for that interface.
     pub cake_qdiscs: usize,
-    /// Planned `fq_codel` leaf qdiscs for that interface.
+    /// Planned `fq_codel` and `fq_pie` leaf qdiscs for that interface.
     pub fq_codel_qdiscs: usize,
     /// Estimated kernel memory cost for that interface's planned qdiscs.
     pub estimated_memory_bytes: u64,
@@ -2557,7 +2598,7 @@ pub struct QdiscInterfaceEstimate {
     pub infra_qdiscs: usize,
     /// Planned CAKE leaf qdiscs for the interface.
     pub cake_qdiscs: usize,
-    /// Planned fq_codel leaf qdiscs for the interface.
+    /// Planned fq_codel and fq_pie leaf qdiscs for the interface.
     pub fq_codel_qdiscs: usize,
     /// Estimated kernel memory cost for the interface's planned qdiscs.
     pub estimated_memory_bytes: u64,
@@ -2623,7 +2664,7 @@ fn planned_qdisc_kind(argv: &[String]) -> Option<PlannedQdiscKind> {
             PlannedQdiscKind::Infra
         });
     }
-    if argv.iter().any(|arg| arg == "fq_codel") {
+    if argv.iter().any(|arg| arg == "fq_codel" || arg == "fq_pie") {
         return Some(if planned_qdisc_is_leaf(argv) {
             PlannedQdiscKind::FqCodel
         } else {
@@ -3322,9 +3363,12 @@ fn with_assigned_qdisc_handles_reserved(
         up_reserved.extend(extra.iter().copied());
     }
 
+    let (down_lanes, up_lanes) = directional_lane_counts(command.as_ref(), config);
     if let BakeryCommands::AddCircuit {
         down_qdisc_handle,
         up_qdisc_handle,
+        down_lane_qdisc_handles,
+        up_lane_qdisc_handles,
         ..
     } = &mut enriched
     {
@@ -3339,6 +3383,24 @@ fn with_assigned_qdisc_handles_reserved(
                 &up_reserved,
             );
         }
+        if down_lane_qdisc_handles.len() != down_lanes {
+            *down_lane_qdisc_handles = assign_lane_qdisc_handles(
+                qdisc_handles,
+                &isp_interface,
+                *circuit_hash,
+                down_lanes,
+                &isp_reserved,
+            );
+        }
+        if up_lane_qdisc_handles.len() != up_lanes {
+            *up_lane_qdisc_handles = assign_lane_qdisc_handles(
+                qdisc_handles,
+                &internet_interface,
+                *circuit_hash,
+                up_lanes,
+                &up_reserved,
+            );
+        }
     }
 
     Arc::new(enriched)
@@ -3372,9 +3434,12 @@ fn assign_fresh_qdisc_handles_reserved(
         up_reserved.extend(extra.iter().copied());
     }
 
+    let (down_lanes, up_lanes) = directional_lane_counts(command.as_ref(), config);
     if let BakeryCommands::AddCircuit {
         down_qdisc_handle,
         up_qdisc_handle,
+        down_lane_qdisc_handles,
+        up_lane_qdisc_handles,
         ..
     } = &mut refreshed
     {
@@ -3401,6 +3466,21 @@ fn assign_fresh_qdisc_handles_reserved(
                 circuit_hash
             ));
         }
+
+        *down_lane_qdisc_handles = assign_lane_qdisc_handles(
+            qdisc_handles,
+            &isp_interface,
+            *circuit_hash,
+            if down_parent.is_some() { down_lanes } else { 0 },
+            &isp_reserved,
+        );
+        *up_lane_qdisc_handles = assign_lane_qdisc_handles(
+            qdisc_handles,
+            &internet_interface,
+            *circuit_hash,
+            if up_parent.is_some() { up_lanes } else { 0 },
+            &up_reserved,
+        );
     }
 
     Ok(Arc::new(refreshed))
@@ -3475,11 +3555,44 @@ fn effective_directional_sqm_kinds(
     let (down_override_opt, up_override_opt) = parse_directional_sqm_override(sqm_override);
 
     let down_kind =
-        (!matches!(down_override_opt.as_deref(), Some(s) if s.eq_ignore_ascii_case("none")))
-            .then(|| effective_sqm_kind(*download_bandwidth_max, config, &down_override_opt));
+        (!matches!(down_override_opt.as_deref(), Some(s) if s.eq_ignore_ascii_case("none"))).then(
+            || {
+                effective_sqm_kind(
+                    *download_bandwidth_max,
+                    config,
+                    &down_override_opt,
+                    SqmDirection::Download,
+                )
+            },
+        );
     let up_kind = (!config.on_a_stick_mode()
         && !matches!(up_override_opt.as_deref(), Some(s) if s.eq_ignore_ascii_case("none")))
-    .then(|| effective_sqm_kind(*upload_bandwidth_max, config, &up_override_opt));
+    .then(|| {
+        effective_sqm_kind(
+            *upload_bandwidth_max,
+            config,
+            &up_override_opt,
+            SqmDirection::Upload,
+        )
+    });
+
+    // A circuit with priority lanes carries a nested HTB instead of a plain
+    // leaf qdisc, so switching lanes on or off is a qdisc-kind change.
+    let (down_lanes, up_lanes) = directional_lane_counts(command, config);
+    let down_kind = down_kind.map(|kind| {
+        if down_lanes > 0 {
+            SqmKind::PriorityLanes
+        } else {
+            kind
+        }
+    });
+    let up_kind = up_kind.map(|kind| {
+        if up_lanes > 0 {
+            SqmKind::PriorityLanes
+        } else {
+            kind
+        }
+    });
 
     (down_kind, up_kind)
 }
@@ -3559,10 +3672,13 @@ fn rotate_changed_qdisc_handles_reserved(
         up_reserved.extend(extra.iter().copied());
     }
 
+    let (down_lanes, up_lanes) = directional_lane_counts(command.as_ref(), config);
     if let BakeryCommands::AddCircuit {
         circuit_hash,
         down_qdisc_handle,
         up_qdisc_handle,
+        down_lane_qdisc_handles,
+        up_lane_qdisc_handles,
         ..
     } = &mut rotated
     {
@@ -3577,6 +3693,13 @@ fn rotate_changed_qdisc_handles_reserved(
         if down_kind_changed || down_parent_changed || down_handle_conflicts_live {
             *down_qdisc_handle =
                 qdisc_handles.rotate_circuit_handle(&isp_interface, *circuit_hash, &isp_reserved);
+            *down_lane_qdisc_handles = assign_lane_qdisc_handles(
+                qdisc_handles,
+                &isp_interface,
+                *circuit_hash,
+                down_lanes,
+                &isp_reserved,
+            );
         }
         let up_kind_changed =
             old_up_kind.is_some() && new_up_kind.is_some() && old_up_kind != new_up_kind;
@@ -3591,6 +3714,13 @@ fn rotate_changed_qdisc_handles_reserved(
                 *circuit_hash,
                 &up_reserved,
             );
+            *up_lane_qdisc_handles = assign_lane_qdisc_handles(
+                qdisc_handles,
+                &internet_interface,
+                *circuit_hash,
+                up_lanes,
+                &up_reserved,
+            );
         }
     }
 
@@ -4839,6 +4969,9 @@ fn bakery_main(rx: Receiver<BakeryCommands>, tx: Sender<BakeryCommands>) {
                             up_qdisc_handle: None,
                             ip_addresses: "".to_string(),
                             sqm_override: mig.sqm_override.clone(),
+                            priority_classes: Vec::new(),
+                            down_lane_qdisc_handles: Vec::new(),
+                            up_lane_qdisc_handles: Vec::new(),
                         },
                         mig.shadow_minor,
                         mig.old_down_min,
@@ -5080,6 +5213,21 @@ fn bakery_main(rx: Receiver<BakeryCommands>, tx: Sender<BakeryCommands>) {
                     &mut runtime_node_operations,
                 );
             }
+            BakeryCommands::PlanBatch {
+                batch: plan_batch,
+                reply,
+            } => {
+                let report = plan_batch_without_applying(
+                    plan_batch.into_iter().map(Arc::new).collect(),
+                    &sites,
+                    &circuits,
+                    &dynamic_circuit_overlays,
+                    &migrations,
+                    &stormguard_overrides,
+                    &virtualized_sites,
+                );
+                let _ = reply.send(report);
+            }
             BakeryCommands::MqSetup { .. } => {
                 if let Some(batch) = &mut batch {
                     batch.push(Arc::new(command));
@@ -5275,6 +5423,41 @@ fn bakery_main(rx: Receiver<BakeryCommands>, tx: Sender<BakeryCommands>) {
     error!("Bakery thread exited unexpectedly.");
 }
 
+/// Plans a batch against the live Bakery state without applying it.
+fn plan_batch_without_applying(
+    batch: Vec<Arc<BakeryCommands>>,
+    sites: &HashMap<i64, Arc<BakeryCommands>>,
+    circuits: &HashMap<i64, Arc<BakeryCommands>>,
+    dynamic_circuit_overlays: &HashMap<i64, DynamicCircuitOverlayEntry>,
+    migrations: &HashMap<i64, Migration>,
+    stormguard_overrides: &HashMap<StormguardOverrideKey, StormguardOverrideValue>,
+    virtualized_sites: &HashMap<i64, VirtualizedSiteState>,
+) -> lqos_bus::BakeryPlanReport {
+    let Ok(config) = lqos_config::load_config() else {
+        return lqos_bus::BakeryPlanReport {
+            notes: vec!["Unable to load configuration while planning.".to_string()],
+            ..Default::default()
+        };
+    };
+    plan::plan_commit_batch(
+        batch,
+        &config,
+        plan::PlanState {
+            sites,
+            circuits,
+            dynamic_circuit_overlays,
+            migrations,
+            stormguard_overrides,
+            virtualized_sites,
+            mq_created: MQ_CREATED.load(Ordering::Relaxed),
+            shaping_tree_active: SHAPING_TREE_ACTIVE.load(Ordering::Relaxed),
+            reload_required_reason: bakery_reload_required_reason(),
+            live_mutation_blocker: live_tree_mutation_blocker_for_config(&config),
+            mapped_circuit_limit: resolve_mapped_circuit_limit().effective_limit,
+        },
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 fn handle_commit_batch(
     batch: &mut Option<Vec<Arc<BakeryCommands>>>,
@@ -5705,8 +5888,18 @@ fn handle_commit_batch(
                     }
                     live_circuits.remove(&circuit_hash);
                     qdisc_handles.release_circuit(&config.isp_interface(), circuit_hash);
+                    release_lane_qdisc_handles(
+                        qdisc_handles,
+                        &config.isp_interface(),
+                        circuit_hash,
+                    );
                     if !config.on_a_stick_mode() {
                         qdisc_handles.release_circuit(&config.internet_interface(), circuit_hash);
+                        release_lane_qdisc_handles(
+                            qdisc_handles,
+                            &config.internet_interface(),
+                            circuit_hash,
+                        );
                     }
                 } else {
                     debug!(
@@ -6134,6 +6327,7 @@ fn handle_commit_batch(
     *mq_layout = resolved_mq_layout;
     qdisc_handles.save(&config);
     update_queue_distribution_snapshot(sites, circuits);
+    sync_priority_lane_map(&config, circuits);
 }
 
 fn handle_circuit_activity(
@@ -6785,6 +6979,7 @@ fn reparent_circuit_command(
         up_class_major,
         ip_addresses,
         sqm_override,
+        priority_classes,
         ..
     } = circuit.as_ref()
     else {
@@ -6808,6 +7003,9 @@ fn reparent_circuit_command(
         up_qdisc_handle: None,
         ip_addresses: ip_addresses.clone(),
         sqm_override: sqm_override.clone(),
+        priority_classes: priority_classes.clone(),
+        down_lane_qdisc_handles: Vec::new(),
+        up_lane_qdisc_handles: Vec::new(),
     }))
 }
 
@@ -7904,6 +8102,7 @@ fn build_top_level_virtualization_plan(
             upload_bandwidth_max,
             ip_addresses,
             sqm_override,
+            priority_classes,
             ..
         } = circuit.as_ref()
         else {
@@ -7931,6 +8130,9 @@ fn build_top_level_virtualization_plan(
                     up_qdisc_handle: None,
                     ip_addresses: ip_addresses.clone(),
                     sqm_override: sqm_override.clone(),
+                    priority_classes: priority_classes.clone(),
+                    down_lane_qdisc_handles: Vec::new(),
+                    up_lane_qdisc_handles: Vec::new(),
                 }),
             },
         );
@@ -11215,6 +11417,7 @@ fn full_reload(
     if batch_result.result.ok {
         refresh_live_capacity_snapshot(config, true);
         update_queue_distribution_snapshot(sites, circuits);
+        sync_priority_lane_map(config, circuits);
         if let Err(error) = verify_tc_classify_attached(config) {
             let summary = format!(
                 "Bakery full reload applied TC commands but TC classify is not attached: {error}. Operator intervention may be required because traffic would otherwise remain unshaped."
@@ -12191,6 +12394,9 @@ mod tests {
             up_qdisc_handle: Some(0x9001),
             ip_addresses: "192.0.2.1".to_string(),
             sqm_override: Some("cake".to_string()),
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         };
         let updated = circuit_with_sqm_override(&existing, None).map_err(anyhow::Error::msg)?;
         let BakeryCommands::AddCircuit {
@@ -12404,6 +12610,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: ip_addresses.to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         })
     }
 
@@ -12467,6 +12676,9 @@ mod tests {
             up_qdisc_handle: Some(0x9001),
             ip_addresses: ip_addresses.to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         })
     }
 
@@ -12607,6 +12819,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.40/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let mut virtualized_sites = HashMap::new();
@@ -12649,6 +12864,9 @@ mod tests {
                         up_qdisc_handle: None,
                         ip_addresses: "192.0.2.40/32".to_string(),
                         sqm_override: None,
+                        priority_classes: Vec::new(),
+                        down_lane_qdisc_handles: Vec::new(),
+                        up_lane_qdisc_handles: Vec::new(),
                     }),
                 )]),
                 prune_sites: HashMap::new(),
@@ -13252,6 +13470,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.40/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let mut sites = HashMap::new();
@@ -14414,6 +14635,9 @@ mod tests {
                 up_qdisc_handle: Some(0x9001),
                 ip_addresses: "192.0.2.1/32".to_string(),
                 sqm_override: None,
+                priority_classes: Vec::new(),
+                down_lane_qdisc_handles: Vec::new(),
+                up_lane_qdisc_handles: Vec::new(),
             },
             BakeryCommands::CommitBatch,
         ];
@@ -14559,6 +14783,9 @@ mod tests {
                 up_qdisc_handle: Some(0x9001),
                 ip_addresses: "192.0.2.1/32".to_string(),
                 sqm_override: None,
+                priority_classes: Vec::new(),
+                down_lane_qdisc_handles: Vec::new(),
+                up_lane_qdisc_handles: Vec::new(),
             },
         ];
 
@@ -14925,6 +15152,9 @@ mod tests {
                 up_qdisc_handle: Some(0x9001),
                 ip_addresses: "192.0.2.1/32".to_string(),
                 sqm_override: Some("fq_codel".to_string()),
+                priority_classes: Vec::new(),
+                down_lane_qdisc_handles: Vec::new(),
+                up_lane_qdisc_handles: Vec::new(),
             },
         ];
 
@@ -14975,6 +15205,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.1/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let original = with_assigned_qdisc_handles(&original, &config, &layout, &mut handles);
@@ -15007,6 +15240,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.1/32".to_string(),
             sqm_override: Some("fq_codel/cake".to_string()),
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let switched = with_assigned_qdisc_handles(&switched, &config, &layout, &mut reloaded);
@@ -15049,6 +15285,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.2/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
         let new_circuit =
             with_assigned_qdisc_handles(&new_circuit, &config, &layout, &mut persisted);
@@ -15087,6 +15326,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.3/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let original = with_assigned_qdisc_handles(&original, &config, &layout, &mut handles);
@@ -15119,6 +15361,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.3/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let moved = with_assigned_qdisc_handles(&moved, &config, &layout, &mut reloaded);
@@ -15161,6 +15406,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.4/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
         let new_circuit =
             with_assigned_qdisc_handles(&new_circuit, &config, &layout, &mut persisted);
@@ -15198,6 +15446,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.13/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let original = with_assigned_qdisc_handles(&original, &config, &layout, &mut handles);
@@ -15230,6 +15481,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.13/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let live_reserved = HashMap::from([
@@ -15287,6 +15541,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.14/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let original = with_assigned_qdisc_handles(&original, &config, &layout, &mut handles);
@@ -15321,6 +15578,9 @@ mod tests {
             up_qdisc_handle: Some(conflicting_up),
             ip_addresses: "192.0.2.14/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let live_reserved = HashMap::from([
@@ -15381,6 +15641,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.15/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
         let original = with_assigned_qdisc_handles(&original, &config, &layout, &mut handles);
         let BakeryCommands::AddCircuit {
@@ -16058,6 +16321,9 @@ mod tests {
                 up_qdisc_handle: Some(0x93ca),
                 ip_addresses: "192.0.2.95/32".to_string(),
                 sqm_override: None,
+                priority_classes: Vec::new(),
+                down_lane_qdisc_handles: Vec::new(),
+                up_lane_qdisc_handles: Vec::new(),
             }),
             stage: MigrationStage::BuildFinal,
             shadow_verify_attempts: 0,
@@ -16185,6 +16451,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.96/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
         let original = with_assigned_qdisc_handles(&original, &config, &layout, &mut handles);
         let BakeryCommands::AddCircuit {
@@ -16215,6 +16484,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.96/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
         let moved = with_assigned_qdisc_handles(&moved, &config, &layout, &mut reloaded);
         let moved = rotate_changed_qdisc_handles(
@@ -16295,6 +16567,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.5/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
 
         let original = with_assigned_qdisc_handles(&original, &config, &layout, &mut persisted);
@@ -16329,6 +16604,9 @@ mod tests {
             up_qdisc_handle: None,
             ip_addresses: "192.0.2.5/32".to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         });
         let rebuilt = with_assigned_qdisc_handles_reserved(
             &rebuilt_cmd,
@@ -0,0 +1,388 @@
+//! Dry-run planning for Bakery commits.
+//!
+//! `plan_commit_batch` walks the same decisions as `handle_commit_batch`
+//! (reload-required state, baseline rebuild, queue-mode transition, site and
+//! circuit structure, StormGuard ownership) but only reads Bakery state. It
+//! reports every full-reload trigger rather than stopping at the first one,
+//! and estimates the `tc` work from the same command builders the commit uses.
+
+use crate::commands::ExecutionMode;
+use crate::diff::{CircuitDiffResult, SiteDiffResult, diff_circuits, diff_sites};
+use crate::{
+    BakeryCommands, DynamicCircuitOverlayEntry, Migration, StormguardOverrideKey,
+    StormguardOverrideValue, VirtualizedSiteState, append_dynamic_circuit_overlays_to_batch,
+    apply_runtime_virtualization_overlay, circuits_with_pending_migration_targets,
+    count_tc_command_types_and_estimate_qdisc_memory, desired_shaping_tree_active,
+    filter_batch_by_mapped_circuit_limit, reconstruct_structural_baseline_state,
+    stormguard_site_changes_intersect_ownership,
+};
+use lqos_bus::{BakeryPlanChangeKind, BakeryPlanCircuitChange, BakeryPlanReport};
+use lqos_config::Config;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Per-circuit changes beyond this many are counted but not listed.
+const MAX_LISTED_CIRCUIT_CHANGES: usize = 1000;
+
+/// Read-only view of the Bakery state a commit would be diffed against.
+pub(crate) struct PlanState<'a> {
+    pub(crate) sites: &'a HashMap<i64, Arc<BakeryCommands>>,
+    pub(crate) circuits: &'a HashMap<i64, Arc<BakeryCommands>>,
+    pub(crate) dynamic_circuit_overlays: &'a HashMap<i64, DynamicCircuitOverlayEntry>,
+    pub(crate) migrations: &'a HashMap<i64, Migration>,
+    pub(crate) stormguard_overrides: &'a HashMap<StormguardOverrideKey, StormguardOverrideValue>,
+    pub(crate) virtualized_sites: &'a HashMap<i64, VirtualizedSiteState>,
+    pub(crate) mq_created: bool,
+    pub(crate) shaping_tree_active: bool,
+    pub(crate) reload_required_reason: Option<String>,
+    pub(crate) live_mutation_blocker: Option<String>,
+    pub(crate) mapped_circuit_limit: Option<usize>,
+}
+
+/// Plans a commit of `raw_batch` against `state` without applying it.
+///
+/// This function is pure: it reads Bakery state and runs no `tc` commands.
+pub(crate) fn plan_commit_batch(
+    mut raw_batch: Vec<Arc<BakeryCommands>>,
+    config: &Arc<Config>,
+    state: PlanState<'_>,
+) -> BakeryPlanReport {
+    let mut overlays = state.dynamic_circuit_overlays.clone();
+    append_dynamic_circuit_overlays_to_batch(&mut raw_batch, &mut overlays, state.migrations);
+    let (baseline_sites, baseline_circuits) =
+        reconstruct_structural_baseline_state(state.sites, state.circuits, state.virtualized_sites);
+    let effective_new_batch =
+        apply_runtime_virtualization_overlay(raw_batch.clone(), state.virtualized_sites);
+
+    let mut report = BakeryPlanReport {
+        sites_planned: count_commands(&raw_batch, |cmd| {
+            matches!(cmd, BakeryCommands::AddSite { .. })
+        }),
+        circuits_planned: count_commands(&raw_batch, |cmd| {
+            matches!(cmd, BakeryCommands::AddCircuit { .. })
+        }),
+        ..Default::default()
+    };
+
+    if let Some(reason) = &state.reload_required_reason {
+        report
+            .reasons
+            .push(format!("Bakery is in reload-required state: {reason}"));
+    }
+    if !state.mq_created {
+        report.reasons.push(
+            "Bakery has no baseline yet (restart or cold start); the first commit is always a full reload."
+                .to_string(),
+        );
+    }
+    let desired_tree_active = desired_shaping_tree_active(config);
+    if state.mq_created && state.shaping_tree_active != desired_tree_active {
+        report.reasons.push(format!(
+            "Queue mode would transition to {}.",
+            if desired_tree_active {
+                "shape"
+            } else {
+                "observe"
+            }
+        ));
+    }
+    if let SiteDiffResult::RebuildRequired { summary, .. } =
+        diff_sites(&raw_batch, &baseline_sites)
+    {
+        report.reasons.push(summary);
+    }
+    let baseline_circuits_for_diff =
+        circuits_with_pending_migration_targets(&baseline_circuits, state.migrations);
+    if let CircuitDiffResult::Categorized(categories) =
+        diff_circuits(&raw_batch, &baseline_circuits_for_diff)
+        && !categories.structural_changed.is_empty()
+    {
+        report.reasons.push(format!(
+            "{} circuit(s) changed structurally.",
+            categories.structural_changed.len()
+        ));
+    }
+
+    let site_change_mode = diff_sites(&effective_new_batch, state.sites);
+    if stormguard_site_changes_intersect_ownership(
+        &site_change_mode,
+        config,
+        state.stormguard_overrides,
+    ) {
+        report.reasons.push(
+            "Site rate changes touch classes StormGuard currently owns.".to_string(),
+        );
+    }
+
+    let circuits_for_diff = circuits_with_pending_migration_targets(state.circuits, state.migrations);
+    let circuit_change_mode = diff_circuits(&effective_new_batch, &circuits_for_diff);
+    let mut live_commands = Vec::new();
+
+    if let SiteDiffResult::SpeedChanges { changes } = &site_change_mode {
+        report.site_speed_changes = changes.len();
+        for change in changes {
+            if let Some(commands) = change.to_commands(config, ExecutionMode::LiveUpdate) {
+                live_commands.extend(commands);
+            }
+        }
+    }
+
+    if let CircuitDiffResult::Categorized(categories) = &circuit_change_mode {
+        report.circuits_added = categories.newly_added.len();
+        report.circuits_removed = categories.removed_circuits.len();
+        report.circuit_speed_changes = categories.speed_changed.len();
+        report.circuit_ip_changes = categories.ip_changed.len();
+        report.circuits_migrated = categories.migrated.len();
+
+        for circuit_hash in &categories.removed_circuits {
+            let old = state.circuits.get(circuit_hash);
+            if let Some(commands) = old.and_then(|old| old.to_prune(config, true)) {
+                live_commands.extend(commands);
+            }
+            push_change(
+                &mut report,
+                *circuit_hash,
+                old.and_then(|old| circuit_name(old)),
+                BakeryPlanChangeKind::Removed,
+            );
+        }
+        for (commands, kind, prune_first) in [
+            (&categories.newly_added, BakeryPlanChangeKind::Added, false),
+            (&categories.speed_changed, BakeryPlanChangeKind::Speed, true),
+            (&categories.migrated, BakeryPlanChangeKind::Migrated, true),
+            (&categories.ip_changed, BakeryPlanChangeKind::Ip, false),
+        ] {
+            for cmd in commands {
+                let BakeryCommands::AddCircuit { circuit_hash, .. } = cmd.as_ref() else {
+                    continue;
+                };
+                if kind != BakeryPlanChangeKind::Ip {
+                    if prune_first
+                        && let Some(prune) = state
+                            .circuits
+                            .get(circuit_hash)
+                            .and_then(|old| old.to_prune(config, true))
+                    {
+                        live_commands.extend(prune);
+                    }
+                    if let Some(add) = cmd.to_commands(config, ExecutionMode::Builder) {
+                        live_commands.extend(add);
+                    }
+                }
+                push_change(&mut report, *circuit_hash, circuit_name(cmd), kind);
+            }
+        }
+    }
+
+    report.full_reload_triggers = report.reasons.len();
+    report.full_reload = !report.reasons.is_empty();
+    if report.full_reload {
+        let (new_batch, stats) = filter_batch_by_mapped_circuit_limit(
+            raw_batch,
+            &baseline_circuits,
+            state.mapped_circuit_limit,
+        );
+        if stats.dropped_mapped > 0 {
+            report.notes.push(format!(
+                "The mapped circuit limit would drop {} of {} mapped circuit(s).",
+                stats.dropped_mapped, stats.requested_mapped
+            ));
+        }
+        let commands = new_batch
+            .iter()
+            .filter_map(|cmd| cmd.to_commands(config, ExecutionMode::Builder))
+            .flatten()
+            .collect::<Vec<_>>();
+        let (total, _, _, memory) = count_tc_command_types_and_estimate_qdisc_memory(&commands);
+        report.estimated_tc_commands = total;
+        report.estimated_qdisc_memory_bytes = memory;
+    } else {
+        report.live_changes = report.site_speed_changes
+            + report.circuits_added
+            + report.circuits_removed
+            + report.circuit_speed_changes
+            + report.circuit_ip_changes
+            + report.circuits_migrated;
+        let (total, _, _, memory) =
+            count_tc_command_types_and_estimate_qdisc_memory(&live_commands);
+        report.estimated_tc_commands = total;
+        report.estimated_qdisc_memory_bytes = memory;
+        if report.live_changes == 0 {
+            report
+                .notes
+                .push("No changes detected; the commit would do nothing.".to_string());
+        } else if let Some(blocker) = &state.live_mutation_blocker {
+            report.notes.push(format!(
+                "Live tc changes would currently be deferred because {blocker}."
+            ));
+        }
+    }
+    report
+}
+
+fn count_commands(
+    batch: &[Arc<BakeryCommands>],
+    predicate: impl Fn(&BakeryCommands) -> bool,
+) -> usize {
+    batch.iter().filter(|cmd| predicate(cmd.as_ref())).count()
+}
+
+fn circuit_name(cmd: &BakeryCommands) -> Option<String> {
+    match cmd {
+        BakeryCommands::AddCircuit { circuit_name, .. } => circuit_name.clone(),
+        _ => None,
+    }
+}
+
+fn push_change(
+    report: &mut BakeryPlanReport,
+    circuit_hash: i64,
+    circuit_name: Option<String>,
+    change: BakeryPlanChangeKind,
+) {
+    if report.circuit_changes.len() >= MAX_LISTED_CIRCUIT_CHANGES {
+        report.circuit_changes_truncated = true;
+        return;
+    }
+    report.circuit_changes.push(BakeryPlanCircuitChange {
+        circuit_hash,
+        circuit_name,
+        change,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lqos_bus::TcHandle;
+
+    fn site(site_hash: i64, class_minor: u16, max: f32) -> Arc<BakeryCommands> {
+        Arc::new(BakeryCommands::AddSite {
+            site_hash,
+            parent_class_id: TcHandle::from_u32(0x10003),
+            up_parent_class_id: TcHandle::from_u32(0x20003),
+            class_minor,
+            download_bandwidth_min: 10.0,
+            upload_bandwidth_min: 10.0,
+            download_bandwidth_max: max,
+            upload_bandwidth_max: max,
+        })
+    }
+
+    fn circuit(circuit_hash: i64, class_minor: u16, max: f32) -> Arc<BakeryCommands> {
+        Arc::new(BakeryCommands::AddCircuit {
+            circuit_hash,
+            circuit_name: Some(format!("circuit-{circuit_hash}")),
+            site_name: None,
+            parent_class_id: TcHandle::from_u32(0x10010),
+            up_parent_class_id: TcHandle::from_u32(0x20010),
+            class_minor,
+            download_bandwidth_min: 5.0,
+            upload_bandwidth_min: 5.0,
+            download_bandwidth_max: max,
+            upload_bandwidth_max: max,
+            class_major: 0x1,
+            up_class_major: 0x2,
+            down_qdisc_handle: None,
+            up_qdisc_handle: None,
+            ip_addresses: "192.0.2.1/32".to_string(),
+            sqm_override: None,
+            priority_classes: Vec::new(),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
+        })
+    }
+
+    fn keyed(commands: &[Arc<BakeryCommands>]) -> HashMap<i64, Arc<BakeryCommands>> {
+        commands
+            .iter()
+            .filter_map(|cmd| match cmd.as_ref() {
+                BakeryCommands::AddSite { site_hash, .. } => Some((*site_hash, Arc::clone(cmd))),
+                BakeryCommands::AddCircuit { circuit_hash, .. } => {
+                    Some((*circuit_hash, Arc::clone(cmd)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn plan(
+        batch: Vec<Arc<BakeryCommands>>,
+        sites: &HashMap<i64, Arc<BakeryCommands>>,
+        circuits: &HashMap<i64, Arc<BakeryCommands>>,
+        mq_created: bool,
+    ) -> BakeryPlanReport {
+        let config = Arc::new(Config::default());
+        let shaping_tree_active = desired_shaping_tree_active(&config);
+        plan_commit_batch(
+            batch,
+            &config,
+            PlanState {
+                sites,
+                circuits,
+                dynamic_circuit_overlays: &HashMap::new(),
+                migrations: &HashMap::new(),
+                stormguard_overrides: &HashMap::new(),
+                virtualized_sites: &HashMap::new(),
+                mq_created,
+                shaping_tree_active,
+                reload_required_reason: None,
+                live_mutation_blocker: None,
+                mapped_circuit_limit: None,
+            },
+        )
+    }
+
+    #[test]
+    fn speed_and_membership_changes_plan_as_live_updates() {
+        let sites = keyed(&[site(1, 0x10, 100.0)]);
+        let circuits = keyed(&[circuit(10, 0x20, 50.0), circuit(11, 0x21, 50.0)]);
+        let batch = vec![
+            site(1, 0x10, 200.0),
+            circuit(10, 0x20, 75.0),
+            circuit(12, 0x22, 50.0),
+        ];
+
+        let report = plan(batch, &sites, &circuits, true);
+        assert!(!report.full_reload, "{:?}", report.reasons);
+        assert_eq!(report.site_speed_changes, 1);
+        assert_eq!(report.circuit_speed_changes, 1);
+        assert_eq!(report.circuits_added, 1);
+        assert_eq!(report.circuits_removed, 1);
+        assert_eq!(report.live_changes, 4);
+        assert!(report.estimated_tc_commands > 0);
+        assert!(report.circuit_changes.iter().any(|change| {
+            change.circuit_hash == 11
+                && change.change == BakeryPlanChangeKind::Removed
+                && change.circuit_name.as_deref() == Some("circuit-11")
+        }));
+    }
+
+    #[test]
+    fn every_full_reload_trigger_is_reported() {
+        let sites = keyed(&[site(1, 0x10, 100.0)]);
+        let circuits = keyed(&[circuit(10, 0x20, 50.0)]);
+        let batch = vec![site(1, 0x10, 100.0), site(2, 0x11, 100.0), circuit(10, 0x20, 50.0)];
+
+        let report = plan(batch, &sites, &circuits, false);
+        assert!(report.full_reload);
+        assert_eq!(report.full_reload_triggers, 2);
+        assert_eq!(report.reasons.len(), 2);
+        assert_eq!(report.live_changes, 0);
+        assert_eq!(report.sites_planned, 2);
+        assert!(report.estimated_tc_commands > 0);
+    }
+
+    #[test]
+    fn unchanged_batch_plans_nothing() {
+        let batch = vec![site(1, 0x10, 100.0), circuit(10, 0x20, 50.0)];
+        let sites = keyed(&batch[..1]);
+        let circuits = keyed(&batch[1..]);
+
+        let report = plan(batch, &sites, &circuits, true);
+        assert!(!report.full_reload);
+        assert_eq!(report.live_changes, 0);
+        assert_eq!(report.estimated_tc_commands, 0);
+        assert_eq!(report.notes.len(), 1);
+    }
+}
@@ -0,0 +1,550 @@
+//! Intra-circuit priority lanes.
+//!
+//! A circuit that opts into priority classes gets a nested HTB as its leaf
+//! qdisc instead of a single CAKE/fq_codel. Beneath it, one parent class
+//! holds a default lane plus one lane per class, each with its own leaf
+//! qdisc. `fw` filters pick the lane from `skb->mark`, which the eBPF
+//! classifier sets from the per-circuit rules synced here.
+
+use crate::commands::BakeryCommands;
+use crate::qdisc_handles::QdiscHandleState;
+use crate::queue_math::{SqmDirection, format_rate_for_tc_f32, quantum, r2q, sqm_tokens_for};
+use lqos_bus::TcHandle;
+use lqos_config::{Config, MAX_PRIORITY_CLASSES_PER_CIRCUIT, PriorityClassConfig};
+use lqos_sys::{MAX_PRIORITY_LANE_RULES, PriorityLaneRule};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// Class minor of the lane parent inside the nested HTB.
+const LANE_PARENT_MINOR: u16 = 0x1;
+/// Class minor of the default lane; class lanes follow it.
+pub(crate) const DEFAULT_LANE_MINOR: u16 = 0x10;
+/// Smallest rate handed to any lane.
+const MIN_LANE_RATE_MBPS: f32 = 0.01;
+
+/// Class names already reported as unknown, so a typo is logged once.
+static WARNED_UNKNOWN_CLASSES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// One lane beneath a circuit's nested HTB.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PriorityLane {
+    /// Class minor of the lane (also the `skb->mark` that selects it).
+    pub(crate) minor: u16,
+    /// HTB priority; class lanes are served before the default lane.
+    pub(crate) prio: u8,
+    /// Guaranteed rate in Mbps.
+    pub(crate) rate_mbps: f32,
+    /// Ceiling in Mbps.
+    pub(crate) ceil_mbps: f32,
+    /// SQM token for the lane's leaf qdisc.
+    pub(crate) sqm_override: Option<String>,
+}
+
+/// Resolves a circuit's class names against `lqos.conf`, ignoring unknown names.
+pub(crate) fn resolve_priority_classes<'a>(
+    config: &'a Config,
+    names: &[String],
+) -> Vec<&'a PriorityClassConfig> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let class = config.priority_class(name);
+            if class.is_none() {
+                let first_report = WARNED_UNKNOWN_CLASSES
+                    .lock()
+                    .map(|mut warned| warned.insert(name.to_string()))
+                    .unwrap_or(false);
+                if first_report {
+                    warn!("Unknown priority class '{name}' on a circuit; ignoring it");
+                }
+            }
+            class
+        })
+        .take(MAX_PRIORITY_CLASSES_PER_CIRCUIT)
+        .collect()
+}
+
+/// Builds the lanes for one direction of a circuit, default lane first.
+///
+/// Returns an empty list when none of the circuit's classes resolve, in which
+/// case the circuit keeps a plain leaf qdisc.
+pub(crate) fn priority_lanes(
+    config: &Config,
+    names: &[String],
+    circuit_max_mbps: f32,
+    upload: bool,
+    circuit_sqm: &Option<String>,
+) -> Vec<PriorityLane> {
+    let classes = resolve_priority_classes(config, names);
+    if classes.is_empty() {
+        return Vec::new();
+    }
+
+    let mut class_rates: Vec<f32> = classes
+        .iter()
+        .map(|class| {
+            class
+                .min_mbps(circuit_max_mbps, upload)
+                .max(MIN_LANE_RATE_MBPS)
+        })
+        .collect();
+    // Keep some guaranteed room for the default lane if the classes would
+    // otherwise claim the whole circuit.
+    let budget = circuit_max_mbps * 0.9;
+    let claimed: f32 = class_rates.iter().sum();
+    if claimed > budget && claimed > 0.0 {
+        let scale = budget / claimed;
+        for rate in class_rates.iter_mut() {
+            *rate = (*rate * scale).max(MIN_LANE_RATE_MBPS);
+        }
+    }
+    let claimed: f32 = class_rates.iter().sum();
+
+    let mut lanes = Vec::with_capacity(classes.len() + 1);
+    lanes.push(PriorityLane {
+        minor: DEFAULT_LANE_MINOR,
+        prio: 3,
+        rate_mbps: (circuit_max_mbps - claimed).max(MIN_LANE_RATE_MBPS),
+        ceil_mbps: circuit_max_mbps,
+        sqm_override: circuit_sqm.clone(),
+    });
+    for (idx, (class, rate)) in classes.iter().zip(class_rates).enumerate() {
+        let sqm = class.sqm.trim();
+        lanes.push(PriorityLane {
+            minor: DEFAULT_LANE_MINOR + 1 + idx as u16,
+            prio: 1,
+            rate_mbps: rate,
+            ceil_mbps: class.max_mbps(circuit_max_mbps, upload),
+            sqm_override: if sqm.is_empty() {
+                circuit_sqm.clone()
+            } else {
+                Some(sqm.to_string())
+            },
+        });
+    }
+    lanes
+}
+
+/// Everything needed to emit the nested HTB for one circuit direction.
+pub(crate) struct LaneLeafParams<'a> {
+    pub(crate) interface: String,
+    /// The circuit class the nested HTB attaches to.
+    pub(crate) parent: String,
+    /// Explicit handle major for the nested HTB.
+    pub(crate) handle: u16,
+    /// Explicit handle majors for each lane leaf, in lane order.
+    pub(crate) lane_handles: &'a [u16],
+    pub(crate) lanes: &'a [PriorityLane],
+    pub(crate) circuit_max_mbps: f32,
+    pub(crate) direction: SqmDirection,
+}
+
+/// Emits the `tc` commands for a circuit's nested HTB, lanes, lane leaf
+/// qdiscs and `fw` filters.
+pub(crate) fn lane_leaf_commands(config: &Arc<Config>, params: LaneLeafParams) -> Vec<Vec<String>> {
+    let interface_r2q = match params.direction {
+        SqmDirection::Download => r2q(config.queues.downlink_bandwidth_mbps),
+        SqmDirection::Upload => r2q(config.queues.uplink_bandwidth_mbps),
+    };
+    let handle = params.handle;
+    let mut result = Vec::new();
+
+    result.push(vec![
+        "qdisc".to_string(),
+        "replace".to_string(),
+        "dev".to_string(),
+        params.interface.clone(),
+        "parent".to_string(),
+        params.parent.clone(),
+        "handle".to_string(),
+        format!("0x{:x}:", handle),
+        "htb".to_string(),
+        "default".to_string(),
+        format!("0x{:x}", DEFAULT_LANE_MINOR),
+    ]);
+    result.push(vec![
+        "class".to_string(),
+        "replace".to_string(),
+        "dev".to_string(),
+        params.interface.clone(),
+        "parent".to_string(),
+        format!("0x{:x}:", handle),
+        "classid".to_string(),
+        format!("0x{:x}:0x{:x}", handle, LANE_PARENT_MINOR),
+        "htb".to_string(),
+        "rate".to_string(),
+        format_rate_for_tc_f32(params.circuit_max_mbps),
+        "ceil".to_string(),
+        format_rate_for_tc_f32(params.circuit_max_mbps),
+        "quantum".to_string(),
+        quantum(params.circuit_max_mbps as u64, interface_r2q),
+    ]);
+
+    for (idx, lane) in params.lanes.iter().enumerate() {
+        let lane_class = format!("0x{:x}:0x{:x}", handle, lane.minor);
+        result.push(vec![
+            "class".to_string(),
+            "replace".to_string(),
+            "dev".to_string(),
+            params.interface.clone(),
+            "parent".to_string(),
+            format!("0x{:x}:0x{:x}", handle, LANE_PARENT_MINOR),
+            "classid".to_string(),
+            lane_class.clone(),
+            "htb".to_string(),
+            "rate".to_string(),
+            format_rate_for_tc_f32(lane.rate_mbps),
+            "ceil".to_string(),
+            format_rate_for_tc_f32(lane.ceil_mbps),
+            "prio".to_string(),
+            lane.prio.to_string(),
+            "quantum".to_string(),
+            quantum(lane.ceil_mbps as u64, interface_r2q),
+        ]);
+
+        let mut leaf = vec![
+            "qdisc".to_string(),
+            "replace".to_string(),
+            "dev".to_string(),
+            params.interface.clone(),
+            "parent".to_string(),
+            lane_class.clone(),
+        ];
+        if let Some(lane_handle) = params.lane_handles.get(idx) {
+            leaf.push("handle".to_string());
+            leaf.push(format!("0x{:x}:", lane_handle));
+        }
+        leaf.extend(sqm_tokens_for(
+            lane.ceil_mbps,
+            config,
+            &lane.sqm_override,
+            params.direction,
+        ));
+        result.push(leaf);
+
+        if lane.minor != DEFAULT_LANE_MINOR {
+            result.push(vec![
+                "filter".to_string(),
+                "replace".to_string(),
+                "dev".to_string(),
+                params.interface.clone(),
+                "parent".to_string(),
+                format!("0x{:x}:", handle),
+                "protocol".to_string(),
+                "all".to_string(),
+                "prio".to_string(),
+                "1".to_string(),
+                "handle".to_string(),
+                format!("0x{:x}", lane.minor),
+                "fw".to_string(),
+                "classid".to_string(),
+                lane_class,
+            ]);
+        }
+    }
+
+    result
+}
+
+/// Allocation key for a lane leaf qdisc handle, distinct from circuit hashes
+/// and shadow-migration keys.
+pub(crate) fn lane_qdisc_allocation_key(circuit_hash: i64, lane_index: usize) -> i64 {
+    circuit_hash
+        .rotate_left(17)
+        .wrapping_add(0x5052_494f_4c41_4e45)
+        .wrapping_add(lane_index as i64)
+}
+
+/// Number of lane handle slots to release when a circuit goes away.
+pub(crate) const LANE_HANDLE_SLOTS: usize = MAX_PRIORITY_CLASSES_PER_CIRCUIT + 1;
+
+/// Returns how many lanes (default lane included) each direction of a
+/// circuit needs, or zero where the circuit keeps a plain leaf qdisc.
+pub(crate) fn directional_lane_counts(command: &BakeryCommands, config: &Config) -> (usize, usize) {
+    let BakeryCommands::AddCircuit {
+        priority_classes,
+        sqm_override,
+        ..
+    } = command
+    else {
+        return (0, 0);
+    };
+    if priority_classes.is_empty() || config.queues.queue_mode.is_observe() {
+        return (0, 0);
+    }
+    let classes = resolve_priority_classes(config, priority_classes).len();
+    if classes == 0 {
+        return (0, 0);
+    }
+    let is_none =
+        |token: Option<&str>| token.is_some_and(|t| t.trim().eq_ignore_ascii_case("none"));
+    let (down_token, up_token) = match sqm_override.as_deref() {
+        Some(s) if s.contains('/') => {
+            let mut it = s.splitn(2, '/');
+            (it.next(), it.next())
+        }
+        other => (other, other),
+    };
+    let down = if is_none(down_token) { 0 } else { classes + 1 };
+    let up = if config.on_a_stick_mode() || is_none(up_token) {
+        0
+    } else {
+        classes + 1
+    };
+    (down, up)
+}
+
+/// Assigns one explicit leaf handle per lane and releases the slots the
+/// circuit no longer uses. Returns an empty list if the allocator runs dry,
+/// which makes the circuit fall back to a plain leaf qdisc.
+pub(crate) fn assign_lane_qdisc_handles(
+    qdisc_handles: &mut QdiscHandleState,
+    interface: &str,
+    circuit_hash: i64,
+    lane_count: usize,
+    reserved: &HashSet<u16>,
+) -> Vec<u16> {
+    for lane in lane_count..LANE_HANDLE_SLOTS {
+        qdisc_handles.release_circuit(interface, lane_qdisc_allocation_key(circuit_hash, lane));
+    }
+    let mut handles = Vec::with_capacity(lane_count);
+    for lane in 0..lane_count {
+        let Some(handle) = qdisc_handles.assign_circuit_handle(
+            interface,
+            lane_qdisc_allocation_key(circuit_hash, lane),
+            reserved,
+        ) else {
+            warn!(
+                "Bakery could not allocate priority-lane qdisc handles for circuit {circuit_hash}"
+            );
+            return Vec::new();
+        };
+        handles.push(handle);
+    }
+    handles
+}
+
+/// Releases every lane handle slot a circuit may hold on an interface.
+pub(crate) fn release_lane_qdisc_handles(
+    qdisc_handles: &mut QdiscHandleState,
+    interface: &str,
+    circuit_hash: i64,
+) {
+    for lane in 0..LANE_HANDLE_SLOTS {
+        qdisc_handles.release_circuit(interface, lane_qdisc_allocation_key(circuit_hash, lane));
+    }
+}
+
+/// Builds the eBPF rules for one circuit direction from its lanes' classes.
+fn lane_rules(config: &Config, names: &[String], circuit_hash: i64) -> Vec<PriorityLaneRule> {
+    let mut rules = Vec::new();
+    for (idx, class) in resolve_priority_classes(config, names).iter().enumerate() {
+        let mark = u32::from(DEFAULT_LANE_MINOR) + 1 + idx as u32;
+        for rule in class.match_rules() {
+            rules.push(PriorityLaneRule {
+                dscp_mask: rule.dscp_mask,
+                protocol: rule.protocol,
+                port_min: rule.port_min,
+                port_max: rule.port_max,
+                prefix: rule.prefix,
+                mark,
+            });
+        }
+    }
+    if rules.len() > MAX_PRIORITY_LANE_RULES {
+        warn!(
+            "Circuit {circuit_hash} has {} priority-lane rules; only the first {MAX_PRIORITY_LANE_RULES} are used",
+            rules.len()
+        );
+        rules.truncate(MAX_PRIORITY_LANE_RULES);
+    }
+    rules
+}
+
+fn desired_lane_rules(
+    config: &Arc<Config>,
+    circuits: &HashMap<i64, Arc<BakeryCommands>>,
+) -> HashMap<(u32, bool), Vec<PriorityLaneRule>> {
+    let mut desired = HashMap::new();
+    if config.queues.queue_mode.is_observe() || config.priority_classes.is_empty() {
+        return desired;
+    }
+    for circuit in circuits.values() {
+        let BakeryCommands::AddCircuit {
+            circuit_hash,
+            class_minor,
+            class_major,
+            up_class_major,
+            priority_classes,
+            ..
+        } = circuit.as_ref()
+        else {
+            continue;
+        };
+        if priority_classes.is_empty() {
+            continue;
+        }
+        let rules = lane_rules(config, priority_classes, *circuit_hash);
+        if rules.is_empty() {
+            continue;
+        }
+        let down = ((*class_major as u32) << 16) | *class_minor as u32;
+        desired.insert((down, false), rules.clone());
+        if !config.on_a_stick_mode() {
+            let up = ((*up_class_major as u32) << 16) | *class_minor as u32;
+            desired.insert((up, true), rules);
+        }
+    }
+    desired
+}
+
+/// Reconciles the eBPF priority-lane map with the circuits Bakery holds.
+///
+/// This function has side effects: it writes and deletes entries in the
+/// pinned `map_priority_lanes` BPF map.
+pub(crate) fn sync_priority_lane_map(
+    config: &Arc<Config>,
+    circuits: &HashMap<i64, Arc<BakeryCommands>>,
+) {
+    let desired = desired_lane_rules(config, circuits);
+    let existing = match lqos_sys::list_priority_lanes() {
+        Ok(existing) => existing,
+        Err(e) => {
+            if !desired.is_empty() {
+                warn!("Unable to read the priority-lane map: {e}");
+            }
+            return;
+        }
+    };
+
+    let desired_keys: HashSet<(u32, bool)> = desired.keys().copied().collect();
+    for (handle, upload) in existing {
+        if !desired_keys.contains(&(handle.as_u32(), upload))
+            && let Err(e) = lqos_sys::del_priority_lanes(handle, upload)
+        {
+            warn!(
+                "Unable to remove priority lanes for {}: {e}",
+                handle.as_tc_string()
+            );
+        }
+    }
+    for ((handle, upload), rules) in desired.iter() {
+        let handle = TcHandle::from_u32(*handle);
+        if let Err(e) = lqos_sys::set_priority_lanes(handle, *upload, rules) {
+            warn!(
+                "Unable to program priority lanes for {}: {e}",
+                handle.as_tc_string()
+            );
+        }
+    }
+    debug!(
+        "Priority-lane map holds {} circuit directions",
+        desired.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_voip() -> Config {
+        Config {
+            priority_classes: vec![PriorityClassConfig {
+                name: "voip".to_string(),
+                download_min_mbps: 2.0,
+                upload_min_mbps: 1.0,
+                download_max_mbps: Some(5.0),
+                upload_max_mbps: None,
+                sqm: "fq_codel".to_string(),
+                dscp: vec![46],
+                ports: vec!["udp:5060-5061".to_string()],
+                prefixes: Vec::new(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lanes_put_the_default_first_and_keep_unknown_classes_out() {
+        let config = config_with_voip();
+        let lanes = priority_lanes(
+            &config,
+            &["voip".to_string(), "missing".to_string()],
+            100.0,
+            false,
+            &Some("cake".to_string()),
+        );
+        assert_eq!(lanes.len(), 2);
+        assert_eq!(lanes[0].minor, DEFAULT_LANE_MINOR);
+        assert_eq!(lanes[0].prio, 3);
+        assert_eq!(lanes[0].rate_mbps, 98.0);
+        assert_eq!(lanes[0].sqm_override.as_deref(), Some("cake"));
+        assert_eq!(lanes[1].minor, DEFAULT_LANE_MINOR + 1);
+        assert_eq!(lanes[1].ceil_mbps, 5.0);
+        assert_eq!(lanes[1].sqm_override.as_deref(), Some("fq_codel"));
+
+        assert!(priority_lanes(&config, &["missing".to_string()], 100.0, false, &None).is_empty());
+    }
+
+    #[test]
+    fn class_rates_are_scaled_to_leave_room_for_the_default_lane() {
+        let config = config_with_voip();
+        let lanes = priority_lanes(&config, &["voip".to_string()], 2.0, false, &None);
+        assert!(lanes[1].rate_mbps <= 1.8 + f32::EPSILON);
+        assert!(lanes[0].rate_mbps >= 0.2 - f32::EPSILON);
+    }
+
+    #[test]
+    fn lane_commands_nest_an_htb_with_filters_for_class_lanes() {
+        let config = Arc::new(config_with_voip());
+        let lanes = priority_lanes(&config, &["voip".to_string()], 100.0, false, &None);
+        let commands = lane_leaf_commands(
+            &config,
+            LaneLeafParams {
+                interface: "eth1".to_string(),
+                parent: "0x1:0x5".to_string(),
+                handle: 0x9000,
+                lane_handles: &[0x9001, 0x9002],
+                lanes: &lanes,
+                circuit_max_mbps: 100.0,
+                direction: SqmDirection::Download,
+            },
+        );
+        let lines: Vec<String> = commands.iter().map(|argv| argv.join(" ")).collect();
+        assert!(lines[0].starts_with("qdisc replace dev eth1 parent 0x1:0x5 handle 0x9000: htb"));
+        assert!(lines[0].ends_with("default 0x10"));
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.contains("parent 0x9000:0x10 handle 0x9001:"))
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.contains("parent 0x9000:0x11 handle 0x9002: fq_codel"))
+        );
+        let filters: Vec<&String> = lines.iter().filter(|l| l.starts_with("filter")).collect();
+        assert_eq!(filters.len(), 1);
+        assert!(filters[0].ends_with("handle 0x11 fw classid 0x9000:0x11"));
+    }
+
+    #[test]
+    fn lane_allocation_keys_do_not_collide_with_the_circuit() {
+        let keys: HashSet<i64> = (0..LANE_HANDLE_SLOTS)
+            .map(|lane| lane_qdisc_allocation_key(42, lane))
+            .collect();
+        assert_eq!(keys.len(), LANE_HANDLE_SLOTS);
+        assert!(!keys.contains(&42));
+    }
+
+    #[test]
+    fn lane_rules_carry_the_lane_mark() {
+        let config = config_with_voip();
+        let rules = lane_rules(&config, &["voip".to_string()], 1);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].mark, 0x11);
+        assert_eq!(rules[0].protocol, 17);
+        assert_eq!(rules[0].dscp_mask, 1u64 << 46);
+    }
+}
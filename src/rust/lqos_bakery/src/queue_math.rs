@@ -1,10 +1,14 @@
-use std::sync::Arc;
-use tracing::debug;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum SqmKind {
     Cake,
     FqCodel,
+    FqPie,
+    /// Nested HTB carrying intra-circuit priority lanes.
+    PriorityLanes,
 }
 
 pub(crate) fn sqm_as_vec(config: &Arc<lqos_config::Config>) -> Vec<String> {
@@ -131,32 +135,82 @@ pub(crate) fn sqm_rate_fixup(rate: f32, config: &Arc<lqos_config::Config>) -> Ve
     //    140ms at 3Mbps
     //    120ms at 4Mbps
     // We don't change anything for rates above 4Mbps, as the default is 100ms.
+    push_low_rate_rtt(rate, &mut result);
+    result
+}
+
+/// Direction of a circuit leaf qdisc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SqmDirection {
+    Download,
+    Upload,
+}
+
+/// CAKE RTT presets that set `rtt` without the `rtt` keyword.
+const CAKE_RTT_PRESETS: &[&str] = &[
+    "datacentre",
+    "lan",
+    "metro",
+    "regional",
+    "internet",
+    "oceanic",
+    "satellite",
+    "interplanetary",
+];
 
-    if rate <= 1.0 {
-        result.push("rtt".to_string());
-        result.push("300ms".to_string());
+/// Profile names already reported as unknown, so a typo is logged once rather
+/// than once per circuit per reload.
+static WARNED_UNKNOWN_PROFILES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Appends the CAKE low-rate RTT fixups used by `sqm_rate_fixup`.
+fn push_low_rate_rtt(rate: f32, tokens: &mut Vec<String>) {
+    let rtt = if rate <= 1.0 {
+        "300ms"
     } else if rate <= 2.0 {
-        result.push("rtt".to_string());
-        result.push("180ms".to_string());
+        "180ms"
     } else if rate <= 3.0 {
-        result.push("rtt".to_string());
-        result.push("140ms".to_string());
+        "140ms"
     } else if rate <= 4.0 {
-        result.push("rtt".to_string());
-        result.push("120ms".to_string());
+        "120ms"
+    } else {
+        return;
+    };
+    tokens.push("rtt".to_string());
+    tokens.push(rtt.to_string());
+}
+
+/// Resolves a named SQM profile to qdisc tokens for one direction.
+fn profile_tokens(
+    rate: f32,
+    profile: &lqos_config::SqmProfileConfig,
+    direction: SqmDirection,
+) -> Vec<String> {
+    let mut tokens: Vec<String> = profile
+        .tokens_for(rate as f64, direction == SqmDirection::Upload)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    let sets_rtt = tokens
+        .iter()
+        .any(|t| t == "rtt" || CAKE_RTT_PRESETS.contains(&t.as_str()));
+    if profile.low_rate_rtt_fixups && tokens.first().is_some_and(|t| t == "cake") && !sets_rtt {
+        push_low_rate_rtt(rate, &mut tokens);
     }
-    result
+    tokens
 }
 
 /// Build SQM token vector for a circuit given an optional per-circuit override.
 /// - None: use config default with cake low-rate RTT fixups (existing behavior)
-/// - Some("fq_codel"): use fq_codel
+/// - Some("fq_codel") / Some("fq_pie"): use that qdisc with kernel defaults
 /// - Some("cake"): use config default if it starts with "cake", otherwise fallback to
 ///   "cake diffserv4"; then apply low-rate RTT fixups.
+/// - Some(name): use the direction's tokens from the named SQM profile; unknown
+///   names are logged and fall back to the default.
 pub(crate) fn sqm_tokens_for(
     rate: f32,
     config: &Arc<lqos_config::Config>,
     override_opt: &Option<String>,
+    direction: SqmDirection,
 ) -> Vec<String> {
     match override_opt.as_deref() {
         None => {
@@ -168,6 +222,7 @@ pub(crate) fn sqm_tokens_for(
             sqm_rate_fixup(rate, config)
         }
         Some("fq_codel") => vec!["fq_codel".to_string()],
+        Some("fq_pie") => vec!["fq_pie".to_string()],
         Some("cake") => {
             let default = &config.queues.default_sqm;
             let mut base = if default.starts_with("cake") {
@@ -176,26 +231,24 @@ pub(crate) fn sqm_tokens_for(
                 vec!["cake".to_string(), "diffserv4".to_string()]
             };
             // If RTT already specified, leave as-is; otherwise apply low-rate fixups
-            let has_rtt = base.iter().any(|s| s == "rtt");
-            if !has_rtt {
-                // Mirror the thresholds used in sqm_rate_fixup
-                if rate <= 1.0 {
-                    base.push("rtt".to_string());
-                    base.push("300ms".to_string());
-                } else if rate <= 2.0 {
-                    base.push("rtt".to_string());
-                    base.push("180ms".to_string());
-                } else if rate <= 3.0 {
-                    base.push("rtt".to_string());
-                    base.push("140ms".to_string());
-                } else if rate <= 4.0 {
-                    base.push("rtt".to_string());
-                    base.push("120ms".to_string());
-                }
+            if !base.iter().any(|s| s == "rtt") {
+                push_low_rate_rtt(rate, &mut base);
             }
             base
         }
-        Some(_) => sqm_rate_fixup(rate, config), // defensive fallback
+        Some(name) => match config.sqm_profile(name) {
+            Some(profile) => profile_tokens(rate, profile, direction),
+            None => {
+                let first_report = WARNED_UNKNOWN_PROFILES
+                    .lock()
+                    .map(|mut warned| warned.insert(name.to_string()))
+                    .unwrap_or(false);
+                if first_report {
+                    warn!("Unknown SQM profile '{name}' in an sqm override; using the default SQM");
+                }
+                sqm_rate_fixup(rate, config)
+            }
+        },
     }
 }
 
@@ -203,12 +256,14 @@ pub(crate) fn effective_sqm_kind(
     rate: f32,
     config: &Arc<lqos_config::Config>,
     override_opt: &Option<String>,
+    direction: SqmDirection,
 ) -> SqmKind {
-    match sqm_tokens_for(rate, config, override_opt)
+    match sqm_tokens_for(rate, config, override_opt, direction)
         .first()
         .map(String::as_str)
     {
         Some("fq_codel") => SqmKind::FqCodel,
+        Some("fq_pie") => SqmKind::FqPie,
         _ => SqmKind::Cake,
     }
 }
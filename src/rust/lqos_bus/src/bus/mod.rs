@@ -13,8 +13,9 @@ pub use client::{LibreqosBusClient, bus_request, bus_request_with_timeout};
 pub use queue_data::*;
 pub use reply::BusReply;
 pub use request::{
-    BakeryCapacityReportInterface, BlackboardSystem, BusRequest, OverrideLayerSelection,
-    OverrideMutation, SchedulerProgressReport, TopFlowType, UrgentSeverity, UrgentSource,
+    BakeryCapacityReportInterface, BlackboardSystem, BusRequest, FlowArchiveQuery,
+    OverrideLayerSelection, OverrideMutation, SchedulerProgressReport, TopFlowType, UrgentSeverity,
+    UrgentSource,
 };
 #[allow(unused_imports)]
 pub use response::{
@@ -159,8 +159,8 @@ mod tests {
         encode_session_cbor, read_frame, write_frame,
     };
     use crate::{
-        BusReply, BusRequest, BusResponse, BusSession, CircuitRollup, OverrideLayerSelection,
-        OverrideMutation, QooData, bus::BusClientError,
+        BusReply, BusRequest, BusResponse, BusSession, CircuitApplicationEntry, CircuitRollup,
+        OverrideLayerSelection, OverrideMutation, QooData, bus::BusClientError,
     };
     use lqos_utils::{
         qoq_heatmap::QoqHeatmapBlocks,
@@ -239,6 +239,26 @@ mod tests {
         assert_eq!(decoded.requests, session.requests);
     }
 
+    #[test]
+    fn cbor_round_trip_bakery_plan_requests() {
+        let session = BusSession {
+            requests: vec![
+                BusRequest::BakeryPlanStart { plan_id: 7 },
+                BusRequest::BakeryPlanAdd {
+                    plan_id: 7,
+                    commands: vec![BusRequest::BakeryMqSetup {
+                        queues_available: 4,
+                        stick_offset: 0,
+                    }],
+                },
+                BusRequest::BakeryPlan { plan_id: 7 },
+            ],
+        };
+        let bytes = encode_session_cbor(&session).expect("encode_session_cbor");
+        let decoded = decode_session_cbor(&bytes).expect("decode_session_cbor");
+        assert_eq!(decoded.requests, session.requests);
+    }
+
     #[test]
     fn cbor_round_trip_reply() {
         let reply = BusReply {
@@ -270,6 +290,8 @@ mod tests {
                     down: Some(91.0),
                     up: Some(89.0),
                 },
+                profile_id: Some("video_streaming".to_string()),
+                profile_reason: Some("site override (North)".to_string()),
             }))],
         };
         let bytes = encode_reply_cbor(&reply).expect("encode_reply_cbor");
@@ -287,6 +309,39 @@ mod tests {
         assert_eq!(decoded.responses, reply.responses);
     }
 
+    #[test]
+    fn cbor_round_trip_circuit_top_applications() {
+        let session = BusSession {
+            requests: vec![BusRequest::GetCircuitTopApplications {
+                circuit_id: "Circuit-1".to_string(),
+            }],
+        };
+        let bytes = encode_session_cbor(&session).expect("encode_session_cbor");
+        let decoded = decode_session_cbor(&bytes).expect("decode_session_cbor");
+        assert_eq!(decoded.requests, session.requests);
+
+        let reply = BusReply {
+            responses: vec![BusResponse::CircuitTopApplications(vec![
+                CircuitApplicationEntry {
+                    application: "Netflix".to_string(),
+                    category: "Streaming Video".to_string(),
+                    bits_per_second: DownUpOrder {
+                        down: 70_000_000,
+                        up: 2_000_000,
+                    },
+                    bytes: DownUpOrder {
+                        down: 8_000,
+                        up: 2_000,
+                    },
+                    flow_count: 2,
+                },
+            ])],
+        };
+        let bytes = encode_reply_cbor(&reply).expect("encode_reply_cbor");
+        let decoded = decode_reply_cbor(&bytes).expect("decode_reply_cbor");
+        assert_eq!(decoded.responses, reply.responses);
+    }
+
     #[test]
     fn cbor_round_trip_circuit_rollup_reply() {
         let rollup = CircuitRollup {
@@ -315,6 +370,7 @@ mod tests {
                 down: TcpRetransmitSample::new(2, 100),
                 up: TcpRetransmitSample::new(1, 50),
             },
+            dscp_remarked_per_second: DownUpOrder { down: 12, up: 0 },
             last_seen_nanos: 42,
         };
         let reply = BusReply {
@@ -19,6 +19,22 @@ pub struct QueueStoreTransit {
     pub kind_down: String,
     /// Queue kind for uplink (e.g., "cake" or "fq_codel")
     pub kind_up: String,
+    /// Per-lane statistics when the circuit uses priority classes.
+    #[serde(default)]
+    pub priority_lanes: Vec<PriorityLaneTransit>,
+}
+
+/// Latest statistics for one intra-circuit priority lane.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, Allocative)]
+pub struct PriorityLaneTransit {
+    /// Lane class minor; `0x10` is the default lane.
+    pub minor: u16,
+    /// `true` for the uplink side of the circuit.
+    pub upload: bool,
+    /// Leaf queue kind (e.g., "cake" or "fq_codel").
+    pub kind: String,
+    /// Change since the previous sample.
+    pub latest: CakeDiffTransit,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, Allocative)]
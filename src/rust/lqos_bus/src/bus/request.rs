@@ -5,6 +5,7 @@ use crate::TcHandle;
 use allocative::Allocative;
 use lqos_config::Tunables;
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 
 /// Override layer selected for a bus-backed override mutation.
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Allocative)]
@@ -25,6 +26,18 @@ pub enum OverrideMutation {
         /// Exact node names from `network.json`.
         node_names: Vec<String>,
     },
+    /// Activate scheduled adjustments that are due and retire expired ones.
+    ApplyOverrideSchedule {
+        /// Unix time (seconds) to evaluate schedules against.
+        now_unix: u64,
+    },
+    /// Set or remove a circuit's access-policy adjustment.
+    SetCircuitAccessPolicy {
+        /// Circuit identifier, as in `ShapedDevices.csv`.
+        circuit_id: String,
+        /// Policy to apply, or `None` to remove the adjustment.
+        policy: Option<lqos_config::CircuitAccessPolicy>,
+    },
 }
 
 /// Per-interface Bakery qdisc-budget report entry.
@@ -85,6 +98,25 @@ pub struct SchedulerProgressReport {
     pub updated_unix: Option<u64>,
 }
 
+/// Filters for a finished-flow archive query. Unset filters match every flow.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Allocative)]
+pub struct FlowArchiveQuery {
+    /// Circuit ID the flow was attributed to.
+    pub circuit_id: Option<String>,
+    /// Local or remote address of the flow.
+    pub ip: Option<IpAddr>,
+    /// Remote ASN of the flow.
+    pub asn: Option<u32>,
+    /// Source or destination port of the flow.
+    pub port: Option<u16>,
+    /// Start of the time range (unix seconds). Flows that ended earlier are excluded.
+    pub start: u64,
+    /// End of the time range (unix seconds). Flows that started later are excluded.
+    pub end: u64,
+    /// Maximum number of flows to return. `lqosd` applies a default and a ceiling.
+    pub limit: Option<usize>,
+}
+
 /// One or more `BusRequest` objects must be included in a `BusSession`
 /// request. Each `BusRequest` represents a single request for action
 /// or data.
@@ -210,6 +242,18 @@ pub enum BusRequest {
     /// Retreieve list of all current IP/TC/CPU mappings.
     ListIpFlow,
 
+    /// Sets (or clears) a circuit's walled-garden access policy override.
+    ///
+    /// The override is saved to `lqos_overrides.json` and applied to the
+    /// circuit's mapped IPs immediately. `None` removes the override, so the
+    /// circuit falls back to its `ShapedDevices.csv` policy.
+    SetCircuitAccessPolicy {
+        /// Circuit identifier, as in `ShapedDevices.csv`.
+        circuit_id: String,
+        /// Policy to apply, or `None` to remove the override.
+        policy: Option<lqos_config::CircuitAccessPolicy>,
+    },
+
     /// Simulate the previous version's `xdp_pping` command, returning
     /// RTT data for all mapped flows by TC handle.
     XdpPping,
@@ -230,6 +274,85 @@ pub enum BusRequest {
     /// reload run.
     ReloadLibreQoS,
 
+    /// List retained revisions of `network.json`, `ShapedDevices.csv` and
+    /// the overrides files, newest first.
+    ListInputRevisions,
+
+    /// Compare two input revisions.
+    DiffInputRevisions {
+        /// Older revision id
+        from: u64,
+        /// Newer revision id
+        to: u64,
+    },
+
+    /// Restore the input files to a revision and reload LibreQoS.
+    RestoreInputRevision {
+        /// Revision id to restore
+        id: u64,
+    },
+
+    /// Plan a shaping change without applying it. Each candidate replaces
+    /// the matching live input for the plan; `None` keeps the live file.
+    PlanShapingChange {
+        /// Candidate `network.json` contents
+        network_json: Option<String>,
+        /// Candidate `ShapedDevices.csv` contents
+        shaped_devices_csv: Option<String>,
+        /// Candidate operator overrides file contents
+        operator_overrides: Option<String>,
+    },
+
+    /// Retrieve the active/standby replication status.
+    GetHaStatus,
+
+    /// Promote this node to active, ending the peer's active role.
+    HaPromote,
+
+    /// List the active flood mitigations.
+    ListFloodMitigations,
+
+    /// Lift flood mitigations. Cleared circuits are not mitigated again
+    /// automatically for `mitigation_minutes`.
+    ClearFloodMitigation {
+        /// Circuit to clear; `None` clears every circuit.
+        circuit_id: Option<String>,
+    },
+
+    /// List the active traffic mirror sessions.
+    ListTrafficMirrors,
+
+    /// Start mirroring a circuit, or a list of addresses and prefixes, to
+    /// the configured mirror interface.
+    StartTrafficMirror {
+        /// Circuit to mirror; all of its devices' addresses are included.
+        circuit_id: Option<String>,
+        /// Additional addresses or CIDR prefixes to mirror.
+        prefixes: Vec<String>,
+        /// Session length; `None` uses `default_minutes`.
+        minutes: Option<u32>,
+        /// Who asked for the session, recorded in the audit trail.
+        requested_by: String,
+        /// Why the session was started, recorded in the audit trail.
+        reason: String,
+    },
+
+    /// Stop a traffic mirror session before it expires.
+    StopTrafficMirror {
+        /// Session to stop.
+        session_id: u64,
+        /// Who stopped the session, recorded in the audit trail.
+        requested_by: String,
+    },
+
+    /// Issue a signed subscriber portal link for one circuit.
+    IssueSubscriberPortalLink {
+        /// Circuit the link shows.
+        circuit_id: String,
+        /// Link lifetime; `None` uses `default_link_hours`.
+        valid_hours: Option<u32>,
+    },
+
     /// Retrieve raw queue data for a given circuit ID.
     GetRawQueueData(String), // The string is the circuit ID
 
@@ -392,6 +515,25 @@ pub enum BusRequest {
     BakeryStart,
     /// Request a bakery commit
     BakeryCommit,
+    /// Start a dry-run plan batch. Plan batches are kept apart from the live
+    /// bakery batch, so a plan never mixes with a real shaping refresh.
+    BakeryPlanStart {
+        /// Identifies the plan batch; later plan requests must repeat it.
+        plan_id: u64,
+    },
+    /// Queue `BakeryMqSetup`, `BakeryAddSite` and `BakeryAddCircuit` requests
+    /// into a dry-run plan batch
+    BakeryPlanAdd {
+        /// The plan batch started with `BakeryPlanStart`
+        plan_id: u64,
+        /// The queued bakery requests
+        commands: Vec<BusRequest>,
+    },
+    /// Plan a dry-run plan batch without applying it, then discard it
+    BakeryPlan {
+        /// The plan batch started with `BakeryPlanStart`
+        plan_id: u64,
+    },
     /// Setup the MQ top
     BakeryMqSetup {
         /// The number of queues available
@@ -448,6 +590,9 @@ pub enum BusRequest {
         ip_addresses: String,
         /// Optional per-circuit SQM override: "cake" or "fq_codel"
         sqm_override: Option<String>,
+        /// Intra-circuit priority class names, resolved against `lqos.conf`.
+        #[serde(default)]
+        priority_classes: Vec<String>,
     },
 
     /// Get current Stormguard statistics
@@ -609,6 +754,12 @@ pub enum BusRequest {
     /// Retrieve list of protocols with recent flow data
     GetProtocolList,
 
+    /// Retrieve the top applications of one circuit's recent flows
+    GetCircuitTopApplications {
+        /// Circuit ID to query
+        circuit_id: String,
+    },
+
     /// Retrieve flow timeline entries for an ASN
     GetAsnFlowTimeline {
         /// ASN number to filter
@@ -627,6 +778,12 @@ pub enum BusRequest {
         protocol: String,
     },
 
+    /// Search the on-disk finished-flow archive
+    QueryFlowArchive(FlowArchiveQuery),
+
+    /// Retrieve per-group (on-net, peering partner, ...) traffic accounting
+    GetTrafficGroups,
+
     /// Retrieve scheduler details (diagnostics)
     GetSchedulerDetails,
 
@@ -703,11 +860,24 @@ impl BusRequest {
             Self::DelIpFlow { .. } => "DelIpFlow",
             Self::ClearIpFlow => "ClearIpFlow",
             Self::ListIpFlow => "ListIpFlow",
+            Self::SetCircuitAccessPolicy { .. } => "SetCircuitAccessPolicy",
             Self::XdpPping => "XdpPping",
             Self::RttHistogram => "RttHistogram",
             Self::HostCounts => "HostCounts",
             Self::AllUnknownIps => "AllUnknownIps",
             Self::ReloadLibreQoS => "ReloadLibreQoS",
+            Self::ListInputRevisions => "ListInputRevisions",
+            Self::DiffInputRevisions { .. } => "DiffInputRevisions",
+            Self::RestoreInputRevision { .. } => "RestoreInputRevision",
+            Self::PlanShapingChange { .. } => "PlanShapingChange",
+            Self::GetHaStatus => "GetHaStatus",
+            Self::HaPromote => "HaPromote",
+            Self::ListFloodMitigations => "ListFloodMitigations",
+            Self::ClearFloodMitigation { .. } => "ClearFloodMitigation",
+            Self::ListTrafficMirrors => "ListTrafficMirrors",
+            Self::StartTrafficMirror { .. } => "StartTrafficMirror",
+            Self::StopTrafficMirror { .. } => "StopTrafficMirror",
+            Self::IssueSubscriberPortalLink { .. } => "IssueSubscriberPortalLink",
             Self::GetRawQueueData(_) => "GetRawQueueData",
             Self::UpdateLqosDTuning(_, _) => "UpdateLqosDTuning",
             Self::UpdateLqosdConfig(_) => "UpdateLqosdConfig",
@@ -746,6 +916,9 @@ impl BusRequest {
             Self::BlackboardFinish => "BlackboardFinish",
             Self::BakeryStart => "BakeryStart",
             Self::BakeryCommit => "BakeryCommit",
+            Self::BakeryPlanStart { .. } => "BakeryPlanStart",
+            Self::BakeryPlanAdd { .. } => "BakeryPlanAdd",
+            Self::BakeryPlan { .. } => "BakeryPlan",
             Self::BakeryMqSetup { .. } => "BakeryMqSetup",
             Self::BakeryAddSite { .. } => "BakeryAddSite",
             Self::BakeryAddCircuit { .. } => "BakeryAddCircuit",
@@ -778,9 +951,12 @@ impl BusRequest {
             Self::GetAsnList => "GetAsnList",
             Self::GetCountryList => "GetCountryList",
             Self::GetProtocolList => "GetProtocolList",
+            Self::GetCircuitTopApplications { .. } => "GetCircuitTopApplications",
             Self::GetAsnFlowTimeline { .. } => "GetAsnFlowTimeline",
             Self::GetCountryFlowTimeline { .. } => "GetCountryFlowTimeline",
             Self::GetProtocolFlowTimeline { .. } => "GetProtocolFlowTimeline",
+            Self::QueryFlowArchive(_) => "QueryFlowArchive",
+            Self::GetTrafficGroups => "GetTrafficGroups",
             Self::GetSchedulerDetails => "GetSchedulerDetails",
             Self::GetQueueStatsTotal => "GetQueueStatsTotal",
             Self::GetCircuitCapacity => "GetCircuitCapacity",
@@ -857,9 +1033,17 @@ impl BusRequest {
                 | Self::GetAsnList
                 | Self::GetCountryList
                 | Self::GetProtocolList
+                | Self::GetCircuitTopApplications { .. }
                 | Self::GetAsnFlowTimeline { .. }
                 | Self::GetCountryFlowTimeline { .. }
                 | Self::GetProtocolFlowTimeline { .. }
+                | Self::QueryFlowArchive(_)
+                | Self::ListInputRevisions
+                | Self::DiffInputRevisions { .. }
+                | Self::GetHaStatus
+                | Self::ListFloodMitigations
+                | Self::ListTrafficMirrors
+                | Self::GetTrafficGroups
                 | Self::GetSchedulerDetails
                 | Self::GetQueueStatsTotal
                 | Self::GetCircuitCapacity
@@ -109,6 +109,12 @@ pub struct QooData {
     pub blocks: QoqHeatmapBlocks,
     /// Latest download and upload QoO score.
     pub latest: DownUpOrder<Option<f32>>,
+    /// Id of the QoO profile the scores were computed with.
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    /// Why that profile applies, e.g. "circuit override" or "auto: 62% video streaming".
+    #[serde(default)]
+    pub profile_reason: Option<String>,
 }
 
 /// Live traffic and quality rollup for one logical circuit ID.
@@ -134,6 +140,9 @@ pub struct CircuitRollup {
     pub qoo: DownUpOrder<Option<f32>>,
     /// TCP retransmit samples for this circuit at the current time.
     pub tcp_retransmit_sample: DownUpOrder<TcpRetransmitSample>,
+    /// Packets per second whose DSCP mark was rewritten, per direction.
+    #[serde(default)]
+    pub dscp_remarked_per_second: DownUpOrder<u64>,
     /// Most recent activity age for this circuit, in nanoseconds since boot.
     pub last_seen_nanos: u64,
 }
@@ -483,8 +492,26 @@ pub struct CountryListEntry {
 pub struct ProtocolListEntry {
     /// Flow count for this protocol
     pub count: usize,
-    /// Protocol name
+    /// Protocol name, or the application name when the flows were classified
     pub protocol: String,
+    /// Application category (empty for port-based protocols)
+    #[serde(default)]
+    pub category: String,
+}
+
+/// One application in a circuit's top-applications list
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct CircuitApplicationEntry {
+    /// Application name, or the port-based protocol for unclassified flows
+    pub application: String,
+    /// Application category (empty for port-based protocols)
+    pub category: String,
+    /// Current rate in bits per second
+    pub bits_per_second: DownUpOrder<u64>,
+    /// Bytes transferred by the recent flows
+    pub bytes: DownUpOrder<u64>,
+    /// Number of recent flows
+    pub flow_count: usize,
 }
 
 /// Flow timeline entry for flow explorer
@@ -518,6 +545,279 @@ pub struct FlowTimelineEntry {
     pub remote_ip: String,
 }
 
+/// A completed flow read back from the finished-flow archive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Allocative)]
+pub struct FlowArchiveRecord {
+    /// Flow start time (unix seconds)
+    pub start: u64,
+    /// Flow end time (unix seconds)
+    pub end: u64,
+    /// Local (subscriber-side) address
+    pub local_ip: IpAddr,
+    /// Remote address
+    pub remote_ip: IpAddr,
+    /// Source port (or ICMP type)
+    pub src_port: u16,
+    /// Destination port
+    pub dst_port: u16,
+    /// IP protocol number
+    pub ip_protocol: u8,
+    /// Total bytes sent
+    pub bytes: DownUpOrder<u64>,
+    /// Total packets sent
+    pub packets: DownUpOrder<u64>,
+    /// TCP retransmit counts
+    pub tcp_retransmits: DownUpOrder<u16>,
+    /// Circuit ID the flow was attributed to when it finished
+    pub circuit_id: String,
+    /// Circuit name the flow was attributed to when it finished
+    pub circuit_name: String,
+    /// Remote ASN (0 if unknown)
+    pub remote_asn: u32,
+    /// Remote ASN name, from the current ASN table
+    pub remote_asn_name: String,
+    /// Application or protocol label
+    pub application: String,
+    /// Remote host name, if one was known
+    pub remote_hostname: String,
+}
+
+/// Traffic attributed to one traffic group
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Allocative)]
+pub struct TrafficGroupCounter {
+    /// Group name ("Other" for traffic matching no configured group)
+    pub group: String,
+    /// Bytes transferred since lqosd started
+    pub bytes: DownUpOrder<u64>,
+    /// Current rate, in bits per second
+    pub bits_per_second: DownUpOrder<u64>,
+}
+
+/// Per-group traffic for a network.json site
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Allocative)]
+pub struct TrafficGroupSite {
+    /// Site name
+    pub site: String,
+    /// Per-group counters
+    pub groups: Vec<TrafficGroupCounter>,
+}
+
+/// Per-group traffic for a circuit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Allocative)]
+pub struct TrafficGroupCircuit {
+    /// Circuit ID
+    pub circuit_id: String,
+    /// Circuit name
+    pub circuit_name: String,
+    /// Per-group counters
+    pub groups: Vec<TrafficGroupCounter>,
+}
+
+/// On-net / off-net and peering-partner traffic accounting
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Allocative)]
+pub struct TrafficGroupReport {
+    /// Totals across all traffic
+    pub totals: Vec<TrafficGroupCounter>,
+    /// Totals per site
+    pub sites: Vec<TrafficGroupSite>,
+    /// Totals per circuit
+    pub circuits: Vec<TrafficGroupCircuit>,
+}
+
+/// How a planned Bakery commit would change one circuit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub enum BakeryPlanChangeKind {
+    /// The circuit would be created
+    Added,
+    /// The circuit would be removed
+    Removed,
+    /// The circuit's rates would change in place
+    Speed,
+    /// Only the circuit's IP mappings would change
+    Ip,
+    /// The circuit would move to a different parent node
+    Migrated,
+}
+
+/// One circuit touched by a planned Bakery commit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct BakeryPlanCircuitChange {
+    /// Bakery circuit hash
+    pub circuit_hash: i64,
+    /// Circuit name, when the batch carried one
+    pub circuit_name: Option<String>,
+    /// Kind of change
+    pub change: BakeryPlanChangeKind,
+}
+
+/// What a Bakery commit would do, computed without touching the live tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct BakeryPlanReport {
+    /// True when the commit would rebuild the whole tree
+    pub full_reload: bool,
+    /// Every reason that would force a full reload
+    pub reasons: Vec<String>,
+    /// Number of full-reload triggers found
+    pub full_reload_triggers: usize,
+    /// Number of changes that would be applied live
+    pub live_changes: usize,
+    /// Sites in the planned batch
+    pub sites_planned: usize,
+    /// Circuits in the planned batch
+    pub circuits_planned: usize,
+    /// Site rate changes applied live
+    pub site_speed_changes: usize,
+    /// Circuits that would be added
+    pub circuits_added: usize,
+    /// Circuits that would be removed
+    pub circuits_removed: usize,
+    /// Circuits whose rates would change
+    pub circuit_speed_changes: usize,
+    /// Circuits whose IP mappings would change
+    pub circuit_ip_changes: usize,
+    /// Circuits that would move to a new parent
+    pub circuits_migrated: usize,
+    /// Estimated number of `tc` commands the commit would run
+    pub estimated_tc_commands: usize,
+    /// Estimated kernel memory for the qdiscs the commit would create
+    pub estimated_qdisc_memory_bytes: u64,
+    /// Per-circuit changes (capped; see `circuit_changes_truncated`)
+    pub circuit_changes: Vec<BakeryPlanCircuitChange>,
+    /// True when `circuit_changes` was capped
+    pub circuit_changes_truncated: bool,
+    /// Informational notes that do not force a full reload
+    pub notes: Vec<String>,
+}
+
+/// A node's role in an active/standby pair
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub enum HaRole {
+    /// Replication is not enabled
+    #[default]
+    Disabled,
+    /// The node owns runtime state and replicates it to its peer
+    Active,
+    /// The node mirrors its peer's runtime state
+    Standby,
+}
+
+/// Replication state of one kind of runtime state
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct HaComponentStatus {
+    /// Component name
+    pub name: String,
+    /// Hash of the local copy
+    pub hash: String,
+    /// Hash of the peer's copy, when the peer has reported one
+    pub peer_hash: Option<String>,
+    /// When the local copy last changed through replication (unix seconds)
+    pub last_applied_unix: Option<u64>,
+}
+
+/// Active/standby replication status
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Allocative)]
+pub struct HaStatus {
+    /// True when replication is enabled
+    pub enabled: bool,
+    /// This node's name
+    pub node_name: String,
+    /// This node's role
+    pub role: HaRole,
+    /// Promotion epoch; the higher epoch wins when both nodes claim active
+    pub epoch: u64,
+    /// True when this node currently carries traffic
+    pub carrying_traffic: bool,
+    /// Configured peer address
+    pub peer_address: String,
+    /// True when an authenticated peer has reported within the failover window
+    pub peer_connected: bool,
+    /// The peer's node name
+    pub peer_name: Option<String>,
+    /// The peer's last reported role
+    pub peer_role: Option<HaRole>,
+    /// The peer's last reported epoch
+    pub peer_epoch: Option<u64>,
+    /// True when the peer last reported carrying traffic
+    pub peer_carrying_traffic: Option<bool>,
+    /// When the peer last reported (unix seconds)
+    pub peer_last_seen_unix: Option<u64>,
+    /// Per-component replication state
+    pub components: Vec<HaComponentStatus>,
+    /// RADIUS accounting packets forwarded to the peer
+    pub radius_packets_sent: u64,
+    /// RADIUS accounting packets received from the peer
+    pub radius_packets_applied: u64,
+    /// Times local standby state drifted from replicated state
+    pub conflicts: u64,
+    /// Most recent conflict or role decision
+    pub last_conflict: Option<String>,
+    /// Most recent replication error
+    pub last_error: Option<String>,
+}
+
+/// A circuit direction capped or dropped by flood detection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct FloodMitigationEntry {
+    /// Circuit ID
+    pub circuit_id: String,
+    /// Circuit name
+    pub circuit_name: String,
+    /// "download" or "upload"
+    pub direction: String,
+    /// "pps_cap" or "drop"
+    pub action: String,
+    /// Packets per second allowed by a `pps_cap` mitigation
+    pub pps_cap: u32,
+    /// The threshold that triggered the mitigation
+    pub reason: String,
+    /// When the mitigation was applied (unix seconds)
+    pub since_unix: u64,
+    /// When the mitigation lifts (unix seconds)
+    pub expires_unix: u64,
+    /// Packets dropped so far
+    pub dropped_packets: u64,
+}
+
+/// A traffic mirror session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct TrafficMirrorSession {
+    /// Session ID, unique for the life of `lqosd`
+    pub session_id: u64,
+    /// Mirrored circuit, if the session targets one
+    pub circuit_id: Option<String>,
+    /// Circuit name
+    pub circuit_name: String,
+    /// Addresses and prefixes currently mirrored
+    pub prefixes: Vec<String>,
+    /// Who started the session
+    pub requested_by: String,
+    /// Why the session was started
+    pub reason: String,
+    /// When the session started (unix seconds)
+    pub started_unix: u64,
+    /// When the session ends (unix seconds)
+    pub expires_unix: u64,
+    /// Packets mirrored so far
+    pub packets: u64,
+    /// Bytes mirrored so far, before truncation
+    pub bytes: u64,
+    /// Clones that could not be sent
+    pub errors: u64,
+}
+
+/// A signed link to one circuit's subscriber portal page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct SubscriberPortalLink {
+    /// Circuit the link shows
+    pub circuit_id: String,
+    /// Bearer token for the portal
+    pub token: String,
+    /// Complete link, when `subscriber_portal.public_url` is set
+    pub url: Option<String>,
+    /// When the link stops working (unix seconds)
+    pub expires_unix: u64,
+}
+
 /// Scheduler details response
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Allocative)]
 pub struct SchedulerDetails {
@@ -852,6 +1152,9 @@ pub enum BusResponse {
     /// Protocol list (recent flows)
     ProtocolList(Vec<ProtocolListEntry>),
 
+    /// Top applications for one circuit (recent flows)
+    CircuitTopApplications(Vec<CircuitApplicationEntry>),
+
     /// ASN flow timeline
     AsnFlowTimeline(Vec<FlowTimelineEntry>),
 
@@ -861,6 +1164,12 @@ pub enum BusResponse {
     /// Protocol flow timeline
     ProtocolFlowTimeline(Vec<FlowTimelineEntry>),
 
+    /// Finished-flow archive query results
+    FlowArchive(Vec<FlowArchiveRecord>),
+
+    /// Per-group traffic accounting
+    TrafficGroups(TrafficGroupReport),
+
     /// Scheduler details
     SchedulerDetails(SchedulerDetails),
 
@@ -879,6 +1188,30 @@ pub enum BusResponse {
     /// Retransmit summary
     RetransmitSummary(RetransmitSummary),
 
+    /// Retained shaping input revisions, newest first
+    InputRevisions(Vec<lqos_config::InputRevision>),
+
+    /// Differences between two shaping input revisions
+    InputRevisionDiff(lqos_config::InputRevisionDiff),
+
+    /// The revision recorded by restoring an earlier one
+    InputRevisionRestored(lqos_config::InputRevision),
+
+    /// Dry-run plan for a Bakery commit
+    BakeryPlan(BakeryPlanReport),
+
+    /// Active/standby replication status
+    HaStatus(HaStatus),
+
+    /// Active flood mitigations
+    FloodMitigations(Vec<FloodMitigationEntry>),
+
+    /// Active traffic mirror sessions
+    TrafficMirrorSessions(Vec<TrafficMirrorSession>),
+
+    /// A newly issued subscriber portal link
+    SubscriberPortalLink(SubscriberPortalLink),
+
     /// Two-level tree summary
     TreeSummaryL2(Vec<(usize, Vec<(usize, lqos_config::NetworkJsonTransport)>)>),
 
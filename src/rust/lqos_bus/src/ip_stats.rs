@@ -61,6 +61,10 @@ pub struct IpMapping {
     /// Defaults to `0` for backward compatibility.
     #[serde(default)]
     pub device_id: u64,
+
+    /// Walled-garden access policy value (0 = open, 1 = block, 2 = portal).
+    #[serde(default)]
+    pub access_policy: u32,
 }
 
 /// Provided for backwards compatibility with `xdp_pping`, with the intent
@@ -194,6 +198,9 @@ pub struct FlowbeeSummaryData {
     pub remote_asn_country: String,
     /// Analysis
     pub analysis: String,
+    /// Remote host name (TLS/QUIC handshake or passive DNS), empty if unknown
+    #[serde(default)]
+    pub remote_hostname: String,
     /// Circuit ID
     pub circuit_id: String,
     /// Circuit Name
@@ -29,21 +29,24 @@ pub use ip_stats::{
 };
 mod tc_handle;
 pub use bus::response::{
-    AsnHeatmapData, AsnListEntry, BakeryStatsSnapshot, CircuitCapacityRow, CircuitCount,
+    AsnHeatmapData, AsnListEntry, BakeryPlanChangeKind, BakeryPlanCircuitChange, BakeryPlanReport,
+    BakeryStatsSnapshot, CircuitApplicationEntry, CircuitCapacityRow, CircuitCount,
     CircuitHeatmapData, CircuitRollup, CountryListEntry, DeviceCounts, ExecutiveSummaryHeader,
-    FlowMapPoint, FlowTimelineEntry, InsightLicenseSummary, LtsCapabilitiesSummary, NodeCapacity,
+    FloodMitigationEntry, FlowArchiveRecord, FlowMapPoint, FlowTimelineEntry, HaComponentStatus,
+    HaRole, HaStatus, InsightLicenseSummary, LtsCapabilitiesSummary, NodeCapacity,
     OverrideMutationResult, ProtocolListEntry, QooData, QueueStatsTotal, RetransmitSummary,
     SchedulerDetails, SearchResultEntry, SiteHeatmapData, StormguardDebugDirection,
-    StormguardDebugEntry, StormguardRuntimeSettings, StormguardRuntimeStatus,
-    TreeGuardRuntimeNodeBranchSnapshot, TreeGuardRuntimeNodeOperationSnapshot, UrgentIssue,
-    WarningLevel,
+    StormguardDebugEntry, StormguardRuntimeSettings, StormguardRuntimeStatus, SubscriberPortalLink,
+    TrafficGroupCircuit, TrafficGroupCounter, TrafficGroupReport, TrafficGroupSite,
+    TrafficMirrorSession, TreeGuardRuntimeNodeBranchSnapshot,
+    TreeGuardRuntimeNodeOperationSnapshot, UrgentIssue, WarningLevel,
 };
 pub use bus::{
     BUS_SOCKET_PATH, BakeryCapacityReportInterface, BlackboardSystem, BusClientError, BusReply,
     BusRequest, BusResponse, BusSession, CakeDiffTinTransit, CakeDiffTransit, CakeTransit,
-    LibreqosBusClient, OverrideLayerSelection, OverrideMutation, QueueStoreTransit,
-    SchedulerProgressReport, TopFlowType, UnixSocketServer, UrgentSeverity, UrgentSource,
-    bus_request, bus_request_with_timeout,
+    FlowArchiveQuery, LibreqosBusClient, OverrideLayerSelection, OverrideMutation,
+    PriorityLaneTransit, QueueStoreTransit, SchedulerProgressReport, TopFlowType, UnixSocketServer,
+    UrgentSeverity, UrgentSource, bus_request, bus_request_with_timeout,
 };
 pub use tc_handle::TcHandle;
 
@@ -335,6 +335,31 @@ impl WebUsers {
         }
     }
 
+    /// Loads `lqusers.toml` from an explicit LibreQoS directory without
+    /// creating, migrating or rewriting it. Returns `None` when no auth file
+    /// exists yet.
+    pub fn load_existing_in(base_path: &Path) -> Result<Option<Self>, AuthenticationError> {
+        let current = Self::primary_path_for(base_path);
+        let legacy = Self::legacy_path_for(base_path);
+        let path = if current.exists() {
+            current
+        } else if legacy.exists() {
+            legacy
+        } else {
+            return Ok(None);
+        };
+        let raw = read_to_string(&path).map_err(|e| {
+            error!("Unable to read auth file {:?}: {e}", path);
+            AuthenticationError::UnableToRead
+        })?;
+        let mut users: Self = toml_edit::de::from_str(&raw).map_err(|e| {
+            error!("Unable to deserialize auth file {:?}: {e}", path);
+            AuthenticationError::UnableToParse
+        })?;
+        users.base_path_override = Some(base_path.to_path_buf());
+        Ok(Some(users))
+    }
+
     fn hash_password(password: &str) -> Result<String, AuthenticationError> {
         let salt = SaltString::generate(&mut OsRng);
         Argon2::default()
@@ -478,6 +503,25 @@ impl WebUsers {
         })
     }
 
+    /// Returns true when `username` exists with `role` and `password`.
+    ///
+    /// Unlike [`WebUsers::authenticate`], this never upgrades a legacy hash or
+    /// writes the auth file.
+    pub fn user_matches(
+        &self,
+        username: &str,
+        password: &str,
+        role: UserRole,
+    ) -> Result<bool, AuthenticationError> {
+        let Some(user) = self.users.iter().find(|u| u.username == username) else {
+            return Ok(false);
+        };
+        if user.role != role {
+            return Ok(false);
+        }
+        Ok(Self::verify_password(password, &user.password_hash)?.valid)
+    }
+
     /// Dump all users to the console.
     pub fn print_users(&self) -> Result<(), AuthenticationError> {
         self.users.iter().for_each(|u| {
@@ -566,4 +610,52 @@ role = "ReadOnly"
 
         fs::remove_dir_all(&dir).expect("remove auth test directory");
     }
+
+    #[test]
+    fn user_matches_checks_without_writing() {
+        let dir = temp_auth_dir("user-matches");
+        fs::create_dir_all(&dir).expect("create auth test directory");
+        assert!(
+            WebUsers::load_existing_in(&dir)
+                .expect("check missing auth file")
+                .is_none()
+        );
+        assert!(!dir.join(CURRENT_AUTH_FILE_NAME).exists());
+
+        let mut users = WebUsers::load_or_create_in(&dir).expect("create auth file");
+        users
+            .add_or_update_user("admin", "secret", UserRole::Admin)
+            .expect("add user");
+        let before = fs::read_to_string(dir.join(CURRENT_AUTH_FILE_NAME)).expect("read auth file");
+
+        let loaded = WebUsers::load_existing_in(&dir)
+            .expect("load auth file")
+            .expect("auth file exists");
+        assert!(
+            loaded
+                .user_matches("admin", "secret", UserRole::Admin)
+                .expect("check user")
+        );
+        assert!(
+            !loaded
+                .user_matches("admin", "other", UserRole::Admin)
+                .expect("check user")
+        );
+        assert!(
+            !loaded
+                .user_matches("admin", "secret", UserRole::ReadOnly)
+                .expect("check user")
+        );
+        assert!(
+            !loaded
+                .user_matches("nobody", "secret", UserRole::Admin)
+                .expect("check user")
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(CURRENT_AUTH_FILE_NAME)).expect("read auth file"),
+            before
+        );
+
+        fs::remove_dir_all(&dir).expect("remove auth test directory");
+    }
 }
@@ -140,6 +140,16 @@ impl ResolvedHybridCpuTopology {
     }
 }
 
+/// NIC locality for the host's CPUs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuLocality {
+    /// CPUs the kernel reports as local to the shaping interfaces' PCI devices, i.e. on the
+    /// same NUMA node.
+    ///
+    /// Empty when no interface exposes `device/local_cpulist` (for example virtual NICs).
+    pub nic_local_cpus: Vec<u32>,
+}
+
 /// Errors that can occur when parsing a Linux cpulist string.
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum CpuListParseError {
@@ -691,6 +701,75 @@ pub fn detect_shaping_cpus(cfg: &Config) -> ShapingCpuDetection {
     }
 }
 
+/// Reads NIC-local CPU lists from Linux sysfs.
+///
+/// `interfaces` are the shaping interface names; the union of their
+/// `/sys/class/net/<iface>/device/local_cpulist` sets becomes
+/// [`CpuLocality::nic_local_cpus`]. Missing sysfs entries yield an empty list.
+pub fn detect_cpu_locality(interfaces: &[&str]) -> CpuLocality {
+    let mut nic_local_cpus = Vec::new();
+    for interface in interfaces {
+        let path = format!("/sys/class/net/{interface}/device/local_cpulist");
+        if let Some(cpus) = try_read_cpu_list(&path) {
+            nic_local_cpus.extend(cpus);
+        }
+    }
+    nic_local_cpus.sort_unstable();
+    nic_local_cpus.dedup();
+    CpuLocality { nic_local_cpus }
+}
+
+/// Returns per-CPU planner cost multipliers that penalize CPUs remote from the shaping NICs.
+///
+/// NIC-local CPUs cost `1.0` and remote CPUs cost `1.0 + remote_penalty`. When locality is
+/// unknown, or every shaping CPU is local, the map is empty so planning is unaffected.
+pub fn locality_cost_multipliers(
+    shaping: &[u32],
+    locality: &CpuLocality,
+    remote_penalty: f64,
+) -> BTreeMap<u32, f64> {
+    if locality.nic_local_cpus.is_empty() || !remote_penalty.is_finite() || remote_penalty <= 0.0 {
+        return BTreeMap::new();
+    }
+    let local: HashSet<u32> = locality.nic_local_cpus.iter().copied().collect();
+    if shaping.iter().all(|cpu| local.contains(cpu)) {
+        return BTreeMap::new();
+    }
+    shaping
+        .iter()
+        .map(|cpu| {
+            let cost = if local.contains(cpu) {
+                1.0
+            } else {
+                1.0 + remote_penalty
+            };
+            (*cpu, cost)
+        })
+        .collect()
+}
+
+/// Returns planner cost multipliers keyed by zero-based shaping queue index.
+///
+/// Queue `n` runs on the `n`th shaping CPU from [`detect_shaping_cpus`]; CPUs remote from the
+/// configured ISP/Internet interfaces cost `1.0 + remote_penalty`. Reads Linux sysfs.
+pub fn shaping_queue_locality_costs(
+    cfg: &Config,
+    queue_count: usize,
+    remote_penalty: f64,
+) -> BTreeMap<usize, f64> {
+    let shaping = detect_shaping_cpus(cfg).shaping;
+    let isp_interface = cfg.isp_interface();
+    let internet_interface = cfg.internet_interface();
+    let locality = detect_cpu_locality(&[isp_interface.as_str(), internet_interface.as_str()]);
+    let cpu_costs = locality_cost_multipliers(&shaping, &locality, remote_penalty);
+    (0..queue_count)
+        .filter_map(|queue| {
+            let cpu = shaping.get(queue).copied().unwrap_or(queue as u32);
+            cpu_costs.get(&cpu).map(|cost| (queue, *cost))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -948,4 +1027,27 @@ mod tests {
         let _ = std::fs::remove_file(path_a);
         let _ = std::fs::remove_file(path_b);
     }
+
+    #[test]
+    fn locality_costs_penalize_remote_cpus() {
+        let locality = CpuLocality {
+            nic_local_cpus: vec![0, 1],
+        };
+        let costs = locality_cost_multipliers(&[0, 1, 2, 3], &locality, 0.25);
+        assert_eq!(
+            costs,
+            BTreeMap::from([(0, 1.0), (1, 1.0), (2, 1.25), (3, 1.25)])
+        );
+    }
+
+    #[test]
+    fn locality_costs_are_empty_when_unknown_or_all_local() {
+        let unknown = CpuLocality::default();
+        assert!(locality_cost_multipliers(&[0, 1], &unknown, 0.25).is_empty());
+
+        let all_local = CpuLocality {
+            nic_local_cpus: vec![0, 1, 2],
+        };
+        assert!(locality_cost_multipliers(&[0, 1], &all_local, 0.25).is_empty());
+    }
 }
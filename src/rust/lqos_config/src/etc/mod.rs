@@ -19,14 +19,26 @@ mod python_migration;
 pub mod test_data;
 mod v15;
 pub use v15::{
-    BridgeConfig, DynamicCircuitRangeRule, DynamicCircuitsConfig, IntegrationConfig, LazyQueueMode,
-    LocalApiKeyConfig, MAX_LOCAL_API_KEYS, MikrotikIpv6Config, QueueMode, RadiusAccountingClient,
-    RadiusAccountingConfig, RadiusClientSource, RadiusDynamicCircuitApplicationConfig,
-    RadiusFallbackSpeedProfile, RadiusSharedSecretSource, RateProfileValidationError,
-    RttThresholds, SingleInterfaceConfig, SslConfig, StormguardConfig, StormguardStrategy,
-    TopologyConfig, TreeguardCircuitsConfig, TreeguardConfig, TreeguardCpuConfig, TreeguardCpuMode,
-    TreeguardLinksConfig, TreeguardQooConfig, Tunables, normalize_external_hostname,
-    validate_rate_profile_mbps,
+    BridgeConfig, CgnatConfig, CgnatDeterministicRule, CgnatPool, CgnatPortBlock,
+    CgnatPortBlockPool, CircuitAccessPolicy, DhcpLeaseFileConfig, DhcpLeaseFileFormat,
+    DhcpLeasesConfig, DscpMarkRule, DscpMarkRuleConfig, DscpPolicyConfig, DscpPolicyDirections,
+    DscpPolicyMode, DscpRemarkingConfig, DynamicCircuitRangeRule, DynamicCircuitsConfig,
+    FleetConfig, FleetMemberConfig, FloodDetectionConfig, FloodMitigationMode, FlowArchiveConfig,
+    HaConfig, HaRoleMode, InputHistoryConfig, IntegrationConfig, LazyQueueMode, LocalApiKeyConfig,
+    MAX_CGNAT_PORT_BLOCKS, MAX_CGNAT_PUBLIC_ADDRESSES, MAX_DSCP_ASN_GROUPS, MAX_DSCP_POLICIES,
+    MAX_DSCP_RULES_PER_POLICY, MAX_FLOOD_DISTINCT_REMOTES, MAX_LOCAL_API_KEYS, MAX_MIRROR_PREFIXES,
+    MAX_MIRROR_SESSIONS, MAX_PRIORITY_CLASSES_PER_CIRCUIT, MAX_PRIORITY_RULES_PER_CIRCUIT,
+    MIN_MIRROR_SNAPLEN, MikrotikIpv6Config, MirrorEncapsulation, PriorityClassConfig,
+    PriorityMatchRule, QueueMode, RadiusAccountingClient, RadiusAccountingConfig,
+    RadiusClientSource, RadiusDynamicCircuitApplicationConfig, RadiusFallbackSpeedProfile,
+    RadiusSharedSecretSource, RateProfileValidationError, RttThresholds, SingleInterfaceConfig,
+    SnmpConfig, SqmProfileConfig, SqmProfileRule, SslConfig, StormguardConfig, StormguardStrategy,
+    SubscriberPortalConfig, TRAFFIC_GROUP_OTHER, TopologyConfig, TopologyRoutingHealthConfig,
+    TopologyRoutingHealthPolicy, TopologyRoutingHealthSource, TrafficGroupConfig,
+    TrafficMirrorConfig, TrafficPlannerConfig, TreeguardCircuitsConfig, TreeguardConfig,
+    TreeguardCpuConfig, TreeguardCpuMode, TreeguardLinksConfig, TreeguardQooConfig, Tunables,
+    WalledGardenConfig, is_valid_profile_name, normalize_external_hostname, parse_dscp,
+    parse_mac_address, parse_mirror_prefix, validate_rate_profile_mbps, validate_sqm_tokens,
 };
 
 static CONFIG: Lazy<ArcSwap<Option<Arc<Config>>>> = Lazy::new(|| ArcSwap::from_pointee(None));
@@ -0,0 +1,376 @@
+//! CGNAT-aware shaping.
+//!
+//! North of a carrier-grade NAT, every subscriber shares a handful of public
+//! addresses. Each public address is split into equal port blocks, and a block
+//! identifies the subscriber (inside) address it was handed to. Blocks come
+//! from deterministic NAT rules, or are learned at runtime from the CGNAT's
+//! port-block-allocation syslog.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+/// Most public addresses the dataplane can split into port blocks.
+pub const MAX_CGNAT_PUBLIC_ADDRESSES: usize = 65_536;
+
+/// Most port blocks the dataplane can hold.
+pub const MAX_CGNAT_PORT_BLOCKS: usize = 1_048_576;
+
+fn default_first_port() -> u16 {
+    1024
+}
+
+/// A deterministic NAT rule: each inside address always receives the same
+/// public address and port block.
+///
+/// Inside addresses are numbered from the start of `inside`. Address `n`
+/// gets public address `n / per_address` of `outside` and port block
+/// `n % per_address`, where `per_address = (65536 - first_port) /
+/// ports_per_subscriber`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct CgnatDeterministicRule {
+    /// Subscriber (inside) IPv4 prefix, e.g. `100.64.0.0/20`.
+    pub inside: String,
+    /// Public (outside) IPv4 prefix the inside addresses translate to.
+    pub outside: String,
+    /// First port handed out on each public address.
+    #[serde(default = "default_first_port")]
+    pub first_port: u16,
+    /// Ports given to each subscriber.
+    pub ports_per_subscriber: u16,
+}
+
+/// Public addresses whose port blocks are learned from allocation logs.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct CgnatPortBlockPool {
+    /// Public (outside) IPv4 prefix of the pool.
+    pub outside: String,
+    /// First port of the first block on each public address.
+    #[serde(default = "default_first_port")]
+    pub first_port: u16,
+    /// Ports in each block, as configured on the CGNAT.
+    pub block_size: u16,
+}
+
+/// CGNAT settings.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default, Allocative)]
+#[serde(default)]
+pub struct CgnatConfig {
+    /// Classifies shared public addresses by port block.
+    pub enabled: bool,
+    /// Deterministic NAT rules.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deterministic: Vec<CgnatDeterministicRule>,
+    /// Pools whose blocks are learned from port-block-allocation logs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub port_block_pools: Vec<CgnatPortBlockPool>,
+    /// UDP address receiving the CGNAT's allocation syslog, e.g.
+    /// `0.0.0.0:5514`. Empty disables it.
+    pub syslog_listen: String,
+}
+
+/// How one public address is split into port blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CgnatPool {
+    /// The shared public address.
+    pub public_ip: Ipv4Addr,
+    /// First port of block 0.
+    pub first_port: u16,
+    /// Ports in each block.
+    pub block_size: u16,
+}
+
+impl CgnatPool {
+    /// The block containing `port`, if any.
+    pub fn block_for_port(&self, port: u16) -> Option<u32> {
+        if self.block_size == 0 || port < self.first_port {
+            return None;
+        }
+        Some(u32::from(port - self.first_port) / u32::from(self.block_size))
+    }
+}
+
+/// One port block on a public address and the subscriber it belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CgnatPortBlock {
+    /// The shared public address.
+    pub public_ip: Ipv4Addr,
+    /// Block index, counted from the pool's `first_port`.
+    pub block: u32,
+    /// The subscriber's inside address.
+    pub inside_ip: Ipv4Addr,
+}
+
+fn parse_ipv4_prefix(label: &str, entry: &str) -> Result<(Ipv4Addr, u8), String> {
+    match super::priority_classes::parse_prefix(entry).map_err(|e| format!("{label}: {e}"))? {
+        (IpAddr::V4(address), length) => Ok((address, length)),
+        (IpAddr::V6(_), _) => Err(format!("{label}: '{entry}' is not an IPv4 prefix")),
+    }
+}
+
+/// Every address in an IPv4 prefix, in order.
+fn prefix_addresses((address, length): (Ipv4Addr, u8)) -> impl Iterator<Item = Ipv4Addr> {
+    let mask = u32::MAX.checked_shl(32 - u32::from(length)).unwrap_or(0);
+    let start = u32::from(address) & mask;
+    let count = 1u64 << (32 - u32::from(length));
+    (0..count).map(move |offset| Ipv4Addr::from(start.wrapping_add(offset as u32)))
+}
+
+fn prefix_size((_, length): (Ipv4Addr, u8)) -> u64 {
+    1u64 << (32 - u32::from(length))
+}
+
+impl CgnatDeterministicRule {
+    /// Subscribers sharing each public address.
+    fn per_address(&self) -> u64 {
+        if self.ports_per_subscriber == 0 {
+            return 0;
+        }
+        (65_536 - u64::from(self.first_port)) / u64::from(self.ports_per_subscriber)
+    }
+
+    fn validate(&self, label: &str) -> Result<(), String> {
+        let inside = parse_ipv4_prefix(&format!("{label}.inside"), &self.inside)?;
+        let outside = parse_ipv4_prefix(&format!("{label}.outside"), &self.outside)?;
+        if self.ports_per_subscriber == 0 {
+            return Err(format!("{label}.ports_per_subscriber must be > 0"));
+        }
+        let per_address = self.per_address();
+        if per_address == 0 {
+            return Err(format!(
+                "{label}: ports_per_subscriber leaves no room above first_port {}",
+                self.first_port
+            ));
+        }
+        if prefix_size(inside) > per_address * prefix_size(outside) {
+            return Err(format!(
+                "{label}: {} inside addresses need more than the {} public addresses in '{}' ({per_address} per address)",
+                prefix_size(inside),
+                prefix_size(outside),
+                self.outside
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl CgnatConfig {
+    /// Validates the CGNAT configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.deterministic.is_empty() && self.port_block_pools.is_empty() {
+            return Err(
+                "cgnat needs at least one deterministic rule or port_block_pool".to_string(),
+            );
+        }
+        let mut block_count = 0u64;
+        for (index, rule) in self.deterministic.iter().enumerate() {
+            rule.validate(&format!("cgnat.deterministic[{index}]"))?;
+            block_count += prefix_size(parse_ipv4_prefix("", &rule.inside)?);
+        }
+        if block_count > MAX_CGNAT_PORT_BLOCKS as u64 {
+            return Err(format!(
+                "cgnat.deterministic covers {block_count} inside addresses; at most {MAX_CGNAT_PORT_BLOCKS} are supported"
+            ));
+        }
+        for (index, pool) in self.port_block_pools.iter().enumerate() {
+            let label = format!("cgnat.port_block_pools[{index}]");
+            parse_ipv4_prefix(&format!("{label}.outside"), &pool.outside)?;
+            if pool.block_size == 0 {
+                return Err(format!("{label}.block_size must be > 0"));
+            }
+        }
+
+        let mut public_addresses = 0u64;
+        for outside in self.outside_prefixes() {
+            public_addresses += prefix_size(outside?);
+        }
+        if public_addresses > MAX_CGNAT_PUBLIC_ADDRESSES as u64 {
+            return Err(format!(
+                "cgnat covers {public_addresses} public addresses; at most {MAX_CGNAT_PUBLIC_ADDRESSES} are supported"
+            ));
+        }
+        let mut seen = HashSet::new();
+        for pool in self.pools() {
+            if !seen.insert(pool.public_ip) {
+                return Err(format!(
+                    "cgnat: public address {} is in more than one rule or pool",
+                    pool.public_ip
+                ));
+            }
+        }
+
+        let listen = self.syslog_listen.trim();
+        if !listen.is_empty() {
+            listen
+                .parse::<SocketAddr>()
+                .map_err(|_| format!("cgnat.syslog_listen '{listen}' is not an address:port"))?;
+            if self.port_block_pools.is_empty() {
+                return Err("cgnat.syslog_listen needs at least one port_block_pool".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn outside_prefixes(&self) -> impl Iterator<Item = Result<(Ipv4Addr, u8), String>> + '_ {
+        self.deterministic
+            .iter()
+            .map(|rule| parse_ipv4_prefix("cgnat", &rule.outside))
+            .chain(
+                self.port_block_pools
+                    .iter()
+                    .map(|pool| parse_ipv4_prefix("cgnat", &pool.outside)),
+            )
+    }
+
+    /// Every shared public address and how it is split, skipping entries
+    /// that fail to parse.
+    pub fn pools(&self) -> Vec<CgnatPool> {
+        let deterministic = self
+            .deterministic
+            .iter()
+            .map(|rule| (&rule.outside, rule.first_port, rule.ports_per_subscriber));
+        let logged = self
+            .port_block_pools
+            .iter()
+            .map(|pool| (&pool.outside, pool.first_port, pool.block_size));
+        let mut pools = Vec::new();
+        for (outside, first_port, block_size) in deterministic.chain(logged) {
+            let Ok(outside) = parse_ipv4_prefix("", outside) else {
+                continue;
+            };
+            pools.extend(prefix_addresses(outside).map(|public_ip| CgnatPool {
+                public_ip,
+                first_port,
+                block_size,
+            }));
+        }
+        pools
+    }
+
+    /// The port blocks fixed by the deterministic rules.
+    pub fn deterministic_blocks(&self) -> Vec<CgnatPortBlock> {
+        let mut blocks = Vec::new();
+        for rule in &self.deterministic {
+            let (Ok(inside), Ok(outside)) = (
+                parse_ipv4_prefix("", &rule.inside),
+                parse_ipv4_prefix("", &rule.outside),
+            ) else {
+                continue;
+            };
+            let per_address = rule.per_address();
+            if per_address == 0 {
+                continue;
+            }
+            let public: Vec<Ipv4Addr> = prefix_addresses(outside).collect();
+            for (index, inside_ip) in prefix_addresses(inside).enumerate() {
+                let Some(public_ip) = public.get(index / per_address as usize) else {
+                    break;
+                };
+                blocks.push(CgnatPortBlock {
+                    public_ip: *public_ip,
+                    block: (index as u64 % per_address) as u32,
+                    inside_ip,
+                });
+            }
+        }
+        blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deterministic() -> CgnatConfig {
+        toml::from_str(
+            r#"
+            enabled = true
+            [[deterministic]]
+            inside = "100.64.0.0/30"
+            outside = "203.0.113.8/31"
+            first_port = 1024
+            ports_per_subscriber = 32256
+            "#,
+        )
+        .expect("section parses")
+    }
+
+    #[test]
+    fn deterministic_rules_follow_the_formula() {
+        let config = deterministic();
+        assert!(config.validate().is_ok());
+
+        // Two subscribers per public address: (65536 - 1024) / 32256 = 2.
+        let blocks = config.deterministic_blocks();
+        let expected = [
+            ("100.64.0.0", "203.0.113.8", 0),
+            ("100.64.0.1", "203.0.113.8", 1),
+            ("100.64.0.2", "203.0.113.9", 0),
+            ("100.64.0.3", "203.0.113.9", 1),
+        ];
+        assert_eq!(blocks.len(), expected.len());
+        for (block, (inside, public, index)) in blocks.iter().zip(expected) {
+            assert_eq!(block.inside_ip, inside.parse::<Ipv4Addr>().expect("inside ip"));
+            assert_eq!(block.public_ip, public.parse::<Ipv4Addr>().expect("public ip"));
+            assert_eq!(block.block, index);
+        }
+
+        let pools = config.pools();
+        assert_eq!(pools.len(), 2);
+        assert_eq!(pools[0].block_for_port(1023), None);
+        assert_eq!(pools[0].block_for_port(1024), Some(0));
+        assert_eq!(pools[0].block_for_port(33_279), Some(0));
+        assert_eq!(pools[0].block_for_port(33_280), Some(1));
+    }
+
+    #[test]
+    fn rejects_rules_without_enough_public_addresses() {
+        let mut config = deterministic();
+        config.deterministic[0].outside = "203.0.113.8/32".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = deterministic();
+        config.deterministic[0].ports_per_subscriber = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validates_port_block_pools_and_syslog() {
+        let mut config: CgnatConfig = toml::from_str(
+            r#"
+            enabled = true
+            syslog_listen = "0.0.0.0:5514"
+            [[port_block_pools]]
+            outside = "198.51.100.0/30"
+            block_size = 512
+            "#,
+        )
+        .expect("section parses");
+        assert!(config.validate().is_ok());
+        assert_eq!(config.pools().len(), 4);
+        assert_eq!(config.pools()[0].first_port, 1024);
+        assert!(config.deterministic_blocks().is_empty());
+
+        config.syslog_listen = "not an address".to_string();
+        assert!(config.validate().is_err());
+
+        let overlapping = CgnatConfig {
+            enabled: true,
+            port_block_pools: vec![
+                config.port_block_pools[0].clone(),
+                CgnatPortBlockPool {
+                    outside: "198.51.100.2".to_string(),
+                    first_port: 1024,
+                    block_size: 256,
+                },
+            ],
+            ..Default::default()
+        };
+        assert!(overlapping.validate().is_err());
+        assert!(CgnatConfig::default().validate().is_ok());
+    }
+}
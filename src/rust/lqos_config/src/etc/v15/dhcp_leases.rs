@@ -0,0 +1,157 @@
+//! DHCP lease ingestion as an identity source for circuits and dynamic circuits.
+
+use super::DynamicCircuitRangeRule;
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+
+fn default_poll_interval_seconds() -> u64 {
+    10
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Lease file formats that can be tailed.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Allocative)]
+#[serde(rename_all = "snake_case")]
+pub enum DhcpLeaseFileFormat {
+    /// ISC Kea `memfile` CSV, DHCPv4 or DHCPv6 (detected from the header).
+    KeaMemfile,
+    /// dnsmasq `dhcp-leasefile`, including its DHCPv6 section.
+    Dnsmasq,
+}
+
+/// One lease file read by LibreQoS.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct DhcpLeaseFileConfig {
+    /// Format of the file.
+    pub format: DhcpLeaseFileFormat,
+    /// Path to the lease file. It is re-read whenever it changes.
+    pub path: String,
+}
+
+/// Settings for mapping DHCP leases onto circuits.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct DhcpLeasesConfig {
+    /// Reads the lease sources and applies them as dynamic circuits.
+    pub enabled: bool,
+    /// Seconds between checks of the lease files and lease expiry.
+    #[serde(default = "default_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// Lease files to tail.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<DhcpLeaseFileConfig>,
+    /// Unix socket accepting lease hook events as JSON lines. Empty disables it.
+    pub hook_socket: String,
+    /// Match the lease's hardware address against `ShapedDevices.csv` MAC values.
+    #[serde(default = "default_true")]
+    pub match_shaped_devices_by_mac: bool,
+    /// Match option 82 circuit-id and remote-id values verbatim against the MAC field.
+    #[serde(default = "default_true")]
+    pub match_shaped_devices_by_option82: bool,
+    /// Match the DHCPv4 client-id or DHCPv6 DUID verbatim against the MAC field.
+    pub match_shaped_devices_by_client_id: bool,
+    /// Rates and parent for leases that match no `ShapedDevices.csv` row, by lease address.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<DynamicCircuitRangeRule>,
+}
+
+impl Default for DhcpLeasesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_seconds: default_poll_interval_seconds(),
+            files: Vec::new(),
+            hook_socket: String::new(),
+            match_shaped_devices_by_mac: true,
+            match_shaped_devices_by_option82: true,
+            match_shaped_devices_by_client_id: false,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl DhcpLeasesConfig {
+    /// Validates the DHCP lease configuration. A disabled section is always valid.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.poll_interval_seconds == 0 {
+            return Err("dhcp_leases.poll_interval_seconds must be > 0".to_string());
+        }
+        if self.files.is_empty() && self.hook_socket.trim().is_empty() {
+            return Err("dhcp_leases needs at least one file or a hook_socket".to_string());
+        }
+        for (index, file) in self.files.iter().enumerate() {
+            if file.path.trim().is_empty() {
+                return Err(format!(
+                    "dhcp_leases.files[{index}]: path must not be empty"
+                ));
+            }
+        }
+        if !self.hook_socket.trim().is_empty() && !self.hook_socket.trim().starts_with('/') {
+            return Err("dhcp_leases.hook_socket must be an absolute path".to_string());
+        }
+        for (index, rule) in self.rules.iter().enumerate() {
+            let label = format!("dhcp_leases.rules[{index}]");
+            rule.validate(&label)?;
+            if rule.attach_to.trim().is_empty() {
+                return Err(format!(
+                    "{label} ('{}'): attach_to must name a network.json node",
+                    rule.name.trim()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sources_and_requires_rule_parents() {
+        let config: DhcpLeasesConfig = toml::from_str("").expect("empty section parses");
+        assert!(!config.enabled);
+        assert!(config.match_shaped_devices_by_mac);
+        assert!(config.validate().is_ok());
+
+        let mut config: DhcpLeasesConfig = toml::from_str(
+            r#"
+            enabled = true
+            hook_socket = "/run/lqos/dhcp-hook.sock"
+
+            [[files]]
+            format = "kea_memfile"
+            path = "/var/lib/kea/kea-leases4.csv"
+
+            [[rules]]
+            name = "Residential"
+            ip_range = "100.64.0.0/10"
+            download_min_mbps = 5.0
+            upload_min_mbps = 1.0
+            download_max_mbps = 50.0
+            upload_max_mbps = 10.0
+            attach_to = "Tower-A"
+            "#,
+        )
+        .expect("section parses");
+        assert_eq!(config.files[0].format, DhcpLeaseFileFormat::KeaMemfile);
+        assert!(config.validate().is_ok());
+
+        config.rules[0].attach_to.clear();
+        assert!(
+            config
+                .validate()
+                .is_err_and(|err| err.contains("attach_to"))
+        );
+        config.rules.clear();
+        config.files.clear();
+        config.hook_socket.clear();
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,420 @@
+//! DSCP re-marking policies.
+//!
+//! CAKE's `diffserv4` tins only help when traffic carries meaningful DSCP
+//! marks. A policy tells the TC egress path what to do with the marks of a
+//! circuit's packets: leave them alone (`trust`), reset them to best effort
+//! (`wash`), or set them from port, protocol and remote ASN rules (`rules`).
+//! Policies are assigned to circuits by ID or to sites by name; a circuit
+//! without its own assignment uses its nearest assigned site, then
+//! `default_policy`.
+
+use super::priority_classes::{PortMatch, parse_port_match};
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+
+/// Most policies the dataplane can hold.
+pub const MAX_DSCP_POLICIES: usize = 64;
+/// Most match rules per policy, after expanding each rule's `ports`.
+pub const MAX_DSCP_RULES_PER_POLICY: usize = 16;
+/// Most rules, across all policies, that may match on remote ASNs.
+pub const MAX_DSCP_ASN_GROUPS: usize = 64;
+
+/// What a policy does with existing DSCP marks.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(rename_all = "snake_case")]
+pub enum DscpPolicyMode {
+    /// Leave marks as they arrive.
+    #[default]
+    Trust,
+    /// Reset every mark to best effort (DSCP 0).
+    Wash,
+    /// Mark packets from `rules`; unmatched packets follow `wash_unmatched`.
+    Rules,
+}
+
+/// Which directions of a circuit a policy re-marks.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(rename_all = "snake_case")]
+pub enum DscpPolicyDirections {
+    /// Download and upload.
+    #[default]
+    Both,
+    /// Traffic towards the subscriber only.
+    Download,
+    /// Traffic from the subscriber only.
+    Upload,
+}
+
+impl DscpPolicyDirections {
+    /// Does the policy apply to this direction?
+    pub fn includes(&self, upload: bool) -> bool {
+        match self {
+            Self::Both => true,
+            Self::Download => !upload,
+            Self::Upload => upload,
+        }
+    }
+}
+
+/// One marking rule. Every listed condition must match; an empty list
+/// matches anything.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(default)]
+pub struct DscpMarkRuleConfig {
+    /// Mark to apply: a name such as `EF`, `AF41`, `CS1`, `LE` or `BE`, or a
+    /// number from 0 to 63.
+    pub dscp: String,
+    /// Protocols and optional port ranges, e.g. `udp:3478-3481`, `tcp:443`
+    /// or `icmp`. A port matches on either end of the flow.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    /// Remote autonomous system numbers, resolved through the ASN database.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub asns: Vec<u32>,
+}
+
+/// A named re-marking policy.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(default)]
+pub struct DscpPolicyConfig {
+    /// Name used by `default_policy`.
+    pub name: String,
+    /// What the policy does with existing marks.
+    pub mode: DscpPolicyMode,
+    /// Directions the policy re-marks.
+    pub directions: DscpPolicyDirections,
+    /// In `rules` mode, reset packets that match no rule to best effort.
+    pub wash_unmatched: bool,
+    /// Marking rules for `rules` mode, first match wins.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<DscpMarkRuleConfig>,
+    /// Circuit IDs that use this policy.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub circuits: Vec<String>,
+    /// Site (network node) names whose circuits use this policy.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sites: Vec<String>,
+}
+
+/// A flattened marking rule, ready for the dataplane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DscpMarkRule {
+    /// IP protocol number, or 0 for any.
+    pub protocol: u8,
+    /// Lowest source or destination port that matches.
+    pub port_min: u16,
+    /// Highest source or destination port that matches.
+    pub port_max: u16,
+    /// Index into [`DscpRemarkingConfig::asn_groups`] the remote address
+    /// must belong to.
+    pub asn_group: Option<usize>,
+    /// DSCP value to write.
+    pub dscp: u8,
+}
+
+/// DSCP re-marking settings.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(default)]
+pub struct DscpRemarkingConfig {
+    /// Apply re-marking policies in the TC egress path.
+    pub enabled: bool,
+    /// Policy for circuits with no circuit or site assignment. Empty leaves
+    /// them alone.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub default_policy: String,
+    /// Named policies.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<DscpPolicyConfig>,
+}
+
+/// Parses a DSCP name (`EF`, `AF41`, `CS1`, `VA`, `LE`, `BE`, `DF`) or a
+/// number from 0 to 63.
+pub fn parse_dscp(value: &str) -> Result<u8, String> {
+    let upper = value.trim().to_ascii_uppercase();
+    let parsed = match upper.as_str() {
+        "BE" | "DF" => Some(0),
+        "LE" => Some(1),
+        "EF" => Some(46),
+        "VA" => Some(44),
+        _ => {
+            if let Some(class) = upper.strip_prefix("CS") {
+                class.parse::<u8>().ok().filter(|c| *c <= 7).map(|c| c * 8)
+            } else if let Some(af) = upper.strip_prefix("AF") {
+                let digits: Vec<u8> = af.bytes().map(|b| b.wrapping_sub(b'0')).collect();
+                match digits.as_slice() {
+                    [class @ 1..=4, drop @ 1..=3] => Some(class * 8 + drop * 2),
+                    _ => None,
+                }
+            } else {
+                upper.parse::<u8>().ok().filter(|d| *d < 64)
+            }
+        }
+    };
+    parsed.ok_or_else(|| format!("'{value}' is not a valid DSCP name or value"))
+}
+
+impl DscpRemarkingConfig {
+    /// Finds a policy's index by name.
+    pub fn policy_index(&self, name: &str) -> Option<usize> {
+        self.policies.iter().position(|policy| policy.name == name)
+    }
+
+    /// The ASN lists of every rule that matches on ASNs, in policy and rule
+    /// order. A rule's position in this list is its `asn_group`.
+    pub fn asn_groups(&self) -> Vec<Vec<u32>> {
+        self.policies
+            .iter()
+            .flat_map(|policy| policy.rules.iter())
+            .filter(|rule| !rule.asns.is_empty())
+            .map(|rule| rule.asns.clone())
+            .collect()
+    }
+
+    /// Flattens a policy's rules, one entry per `ports` item. Unparseable
+    /// entries are skipped; [`Self::validate`] reports them.
+    pub fn match_rules(&self, policy_index: usize) -> Vec<DscpMarkRule> {
+        let Some(policy) = self.policies.get(policy_index) else {
+            return Vec::new();
+        };
+        let mut next_group = self.policies[..policy_index]
+            .iter()
+            .flat_map(|policy| policy.rules.iter())
+            .filter(|rule| !rule.asns.is_empty())
+            .count();
+        let mut rules = Vec::new();
+        for rule in &policy.rules {
+            let asn_group = if rule.asns.is_empty() {
+                None
+            } else {
+                next_group += 1;
+                Some(next_group - 1)
+            };
+            let Ok(dscp) = parse_dscp(&rule.dscp) else {
+                continue;
+            };
+            let ports: Vec<Option<PortMatch>> = if rule.ports.is_empty() {
+                vec![None]
+            } else {
+                rule.ports
+                    .iter()
+                    .filter_map(|p| parse_port_match(p).ok())
+                    .map(Some)
+                    .collect()
+            };
+            for port in ports {
+                rules.push(DscpMarkRule {
+                    protocol: port.map_or(0, |p| p.protocol),
+                    port_min: port.map_or(0, |p| p.port_min),
+                    port_max: port.map_or(0, |p| p.port_max),
+                    asn_group,
+                    dscp,
+                });
+            }
+        }
+        rules
+    }
+
+    /// Picks the policy for a circuit: its own assignment, then the first
+    /// assigned site in `sites` (nearest first), then `default_policy`.
+    pub fn resolve_policy(&self, circuit_id: &str, sites: &[&str]) -> Option<usize> {
+        if let Some(index) = self
+            .policies
+            .iter()
+            .position(|policy| policy.circuits.iter().any(|c| c == circuit_id))
+        {
+            return Some(index);
+        }
+        for site in sites {
+            if let Some(index) = self
+                .policies
+                .iter()
+                .position(|policy| policy.sites.iter().any(|s| s == site))
+            {
+                return Some(index);
+            }
+        }
+        if self.default_policy.is_empty() {
+            None
+        } else {
+            self.policy_index(&self.default_policy)
+        }
+    }
+
+    /// Validates the re-marking configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.policies.len() > MAX_DSCP_POLICIES {
+            return Err(format!(
+                "dscp_remarking supports at most {MAX_DSCP_POLICIES} policies"
+            ));
+        }
+        for (index, policy) in self.policies.iter().enumerate() {
+            if policy.name.trim().is_empty() {
+                return Err("dscp_remarking policies need a name".to_string());
+            }
+            if self.policies[..index]
+                .iter()
+                .any(|other| other.name == policy.name)
+            {
+                return Err(format!(
+                    "dscp_remarking policy \"{}\" is defined more than once",
+                    policy.name
+                ));
+            }
+            if policy.mode != DscpPolicyMode::Rules && !policy.rules.is_empty() {
+                return Err(format!(
+                    "dscp_remarking policy \"{}\" has rules but is not in rules mode",
+                    policy.name
+                ));
+            }
+            let mut flattened = 0;
+            for rule in &policy.rules {
+                parse_dscp(&rule.dscp)
+                    .map_err(|e| format!("dscp_remarking policy \"{}\": {e}", policy.name))?;
+                for port in &rule.ports {
+                    parse_port_match(port)
+                        .map_err(|e| format!("dscp_remarking policy \"{}\": {e}", policy.name))?;
+                }
+                flattened += rule.ports.len().max(1);
+            }
+            if flattened > MAX_DSCP_RULES_PER_POLICY {
+                return Err(format!(
+                    "dscp_remarking policy \"{}\" expands to {flattened} rules; at most {MAX_DSCP_RULES_PER_POLICY} are supported",
+                    policy.name
+                ));
+            }
+            for circuit in &policy.circuits {
+                if let Some(other) = self.policies[..index]
+                    .iter()
+                    .find(|other| other.circuits.contains(circuit))
+                {
+                    return Err(format!(
+                        "circuit \"{circuit}\" is assigned to dscp_remarking policies \"{}\" and \"{}\"",
+                        other.name, policy.name
+                    ));
+                }
+            }
+            for site in &policy.sites {
+                if let Some(other) = self.policies[..index]
+                    .iter()
+                    .find(|other| other.sites.contains(site))
+                {
+                    return Err(format!(
+                        "site \"{site}\" is assigned to dscp_remarking policies \"{}\" and \"{}\"",
+                        other.name, policy.name
+                    ));
+                }
+            }
+        }
+        if self.asn_groups().len() > MAX_DSCP_ASN_GROUPS {
+            return Err(format!(
+                "dscp_remarking supports at most {MAX_DSCP_ASN_GROUPS} rules that match on ASNs"
+            ));
+        }
+        if !self.default_policy.is_empty() && self.policy_index(&self.default_policy).is_none() {
+            return Err(format!(
+                "dscp_remarking.default_policy references unknown policy \"{}\"",
+                self.default_policy
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+        enabled = true
+        default_policy = "wash"
+
+        [[policies]]
+        name = "wash"
+        mode = "wash"
+
+        [[policies]]
+        name = "business"
+        mode = "rules"
+        wash_unmatched = true
+        sites = ["Tower 1"]
+        circuits = ["c-42"]
+
+        [[policies.rules]]
+        dscp = "AF41"
+        ports = ["udp:3478-3481", "udp:8801-8810"]
+
+        [[policies.rules]]
+        dscp = "CS1"
+        asns = [8075, 32934]
+    "#;
+
+    #[test]
+    fn parses_dscp_names() {
+        assert_eq!(parse_dscp("EF"), Ok(46));
+        assert_eq!(parse_dscp("af41"), Ok(34));
+        assert_eq!(parse_dscp("AF11"), Ok(10));
+        assert_eq!(parse_dscp("CS1"), Ok(8));
+        assert_eq!(parse_dscp("cs7"), Ok(56));
+        assert_eq!(parse_dscp("LE"), Ok(1));
+        assert_eq!(parse_dscp("BE"), Ok(0));
+        assert_eq!(parse_dscp("26"), Ok(26));
+        assert!(parse_dscp("AF51").is_err());
+        assert!(parse_dscp("64").is_err());
+        assert!(parse_dscp("video").is_err());
+    }
+
+    #[test]
+    fn flattens_rules_and_resolves_policies() {
+        let config: DscpRemarkingConfig = toml::from_str(EXAMPLE).expect("section parses");
+        assert!(config.validate().is_ok());
+
+        assert_eq!(config.asn_groups(), vec![vec![8075, 32934]]);
+        let rules = config.match_rules(1);
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].protocol, 17);
+        assert_eq!((rules[1].port_min, rules[1].port_max), (8801, 8810));
+        assert_eq!(rules[1].dscp, 34);
+        assert_eq!(rules[2].asn_group, Some(0));
+        assert_eq!(rules[2].protocol, 0);
+        assert_eq!(rules[2].dscp, 8);
+        assert!(config.match_rules(0).is_empty());
+
+        assert_eq!(config.resolve_policy("c-42", &[]), Some(1));
+        assert_eq!(config.resolve_policy("c-1", &["AP 3", "Tower 1"]), Some(1));
+        assert_eq!(config.resolve_policy("c-1", &["AP 3"]), Some(0));
+
+        let no_default = DscpRemarkingConfig {
+            default_policy: String::new(),
+            ..config
+        };
+        assert_eq!(no_default.resolve_policy("c-1", &[]), None);
+    }
+
+    #[test]
+    fn rejects_invalid_policies() {
+        let config: DscpRemarkingConfig = toml::from_str(EXAMPLE).expect("section parses");
+
+        let mut bad = config.clone();
+        bad.default_policy = "missing".to_string();
+        assert!(bad.validate().is_err());
+
+        let mut bad = config.clone();
+        bad.policies[0].circuits.push("c-42".to_string());
+        assert!(bad.validate().is_err());
+
+        let mut bad = config.clone();
+        bad.policies[0].rules.push(DscpMarkRuleConfig {
+            dscp: "EF".to_string(),
+            ..Default::default()
+        });
+        assert!(bad.validate().is_err());
+
+        let mut bad = config.clone();
+        bad.policies[1].rules[0].dscp = "AF99".to_string();
+        assert!(bad.validate().is_err());
+
+        let mut bad = config;
+        bad.policies[1].rules[0].ports = (1..=20).map(|p| format!("tcp:{p}")).collect();
+        assert!(bad.validate().is_err());
+    }
+}
@@ -63,6 +63,45 @@ pub struct DynamicCircuitRangeRule {
     pub attach_to: String,
 }
 
+impl DynamicCircuitRangeRule {
+    /// Validates the rule name and rates, prefixing errors with `label` and the rule name.
+    pub fn validate(&self, label: &str) -> Result<(), String> {
+        let label = if self.name.trim().is_empty() {
+            label.to_string()
+        } else {
+            format!("{label} ('{}')", self.name.trim())
+        };
+
+        if self.name.trim().is_empty() {
+            return Err(format!("{label}: name must not be empty"));
+        }
+
+        if !self.download_min_mbps.is_finite() || self.download_min_mbps < 0.1 {
+            return Err(format!("{label}: download_min_mbps must be >= 0.1"));
+        }
+        if !self.upload_min_mbps.is_finite() || self.upload_min_mbps < 0.1 {
+            return Err(format!("{label}: upload_min_mbps must be >= 0.1"));
+        }
+        if !self.download_max_mbps.is_finite() || self.download_max_mbps < 0.2 {
+            return Err(format!("{label}: download_max_mbps must be >= 0.2"));
+        }
+        if !self.upload_max_mbps.is_finite() || self.upload_max_mbps < 0.2 {
+            return Err(format!("{label}: upload_max_mbps must be >= 0.2"));
+        }
+        if self.download_min_mbps > self.download_max_mbps {
+            return Err(format!(
+                "{label}: download_min_mbps must be <= download_max_mbps"
+            ));
+        }
+        if self.upload_min_mbps > self.upload_max_mbps {
+            return Err(format!(
+                "{label}: upload_min_mbps must be <= upload_max_mbps"
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Dynamic circuits configuration.
 ///
 /// This section is optional in the top-level config so older installations can
@@ -102,38 +141,7 @@ impl DynamicCircuitsConfig {
         }
 
         for (index, rule) in self.ranges.iter().enumerate() {
-            let label = if rule.name.trim().is_empty() {
-                format!("dynamic_circuits.ranges[{index}]")
-            } else {
-                format!("dynamic_circuits.ranges[{index}] ('{}')", rule.name.trim())
-            };
-
-            if rule.name.trim().is_empty() {
-                return Err(format!("{label}: name must not be empty"));
-            }
-
-            if !rule.download_min_mbps.is_finite() || rule.download_min_mbps < 0.1 {
-                return Err(format!("{label}: download_min_mbps must be >= 0.1"));
-            }
-            if !rule.upload_min_mbps.is_finite() || rule.upload_min_mbps < 0.1 {
-                return Err(format!("{label}: upload_min_mbps must be >= 0.1"));
-            }
-            if !rule.download_max_mbps.is_finite() || rule.download_max_mbps < 0.2 {
-                return Err(format!("{label}: download_max_mbps must be >= 0.2"));
-            }
-            if !rule.upload_max_mbps.is_finite() || rule.upload_max_mbps < 0.2 {
-                return Err(format!("{label}: upload_max_mbps must be >= 0.2"));
-            }
-            if rule.download_min_mbps > rule.download_max_mbps {
-                return Err(format!(
-                    "{label}: download_min_mbps must be <= download_max_mbps"
-                ));
-            }
-            if rule.upload_min_mbps > rule.upload_max_mbps {
-                return Err(format!(
-                    "{label}: upload_min_mbps must be <= upload_max_mbps"
-                ));
-            }
+            rule.validate(&format!("dynamic_circuits.ranges[{index}]"))?;
         }
 
         Ok(())
@@ -0,0 +1,127 @@
+//! Combined view of several shapers, polled by one node over their fleet API.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+fn default_poll_interval_seconds() -> u64 {
+    15
+}
+
+fn default_request_timeout_seconds() -> u64 {
+    5
+}
+
+/// One shaper polled by the fleet view.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct FleetMemberConfig {
+    /// Display name, unique within the fleet.
+    pub name: String,
+    /// Base URL of the member's web UI, e.g. `https://pop2.example.net:9123`.
+    /// Drill-through links point here too.
+    pub url: String,
+    /// File holding a named local API key generated on the member.
+    pub api_key_file: String,
+    /// Accept self-signed or otherwise unverifiable HTTPS certificates.
+    #[serde(default)]
+    pub accept_invalid_certificates: bool,
+}
+
+/// Settings for aggregating other shapers into this node's fleet view.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct FleetConfig {
+    /// Polls the members and shows the fleet page.
+    pub enabled: bool,
+    /// Seconds between polls of every member.
+    #[serde(default = "default_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// Seconds before a member request is abandoned and the member marked down.
+    #[serde(default = "default_request_timeout_seconds")]
+    pub request_timeout_seconds: u64,
+    /// The other shapers. This node is always included.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<FleetMemberConfig>,
+}
+
+impl Default for FleetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_seconds: default_poll_interval_seconds(),
+            request_timeout_seconds: default_request_timeout_seconds(),
+            members: Vec::new(),
+        }
+    }
+}
+
+impl FleetConfig {
+    /// Validates the fleet configuration. A disabled section is always valid.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.poll_interval_seconds == 0 {
+            return Err("fleet.poll_interval_seconds must be > 0".to_string());
+        }
+        if self.request_timeout_seconds == 0 {
+            return Err("fleet.request_timeout_seconds must be > 0".to_string());
+        }
+        let mut names = HashSet::new();
+        for member in &self.members {
+            let name = member.name.trim();
+            if name.is_empty() {
+                return Err("fleet.members entries must have a name".to_string());
+            }
+            if !names.insert(name.to_lowercase()) {
+                return Err(format!("fleet member {name} is listed more than once"));
+            }
+            if !member.url.starts_with("http://") && !member.url.starts_with("https://") {
+                return Err(format!(
+                    "fleet member {name} must have an http:// or https:// url"
+                ));
+            }
+            if member.api_key_file.trim().is_empty() {
+                return Err(format!("fleet member {name} must set api_key_file"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_by_default_and_members_are_checked_when_enabled() {
+        let config: FleetConfig = toml::from_str("").expect("empty section parses");
+        assert!(!config.enabled);
+        assert!(config.members.is_empty());
+        assert!(config.validate().is_ok());
+
+        let config: FleetConfig = toml::from_str(
+            r#"
+            enabled = true
+
+            [[members]]
+            name = "pop2"
+            url = "https://pop2.example.net:9123"
+            api_key_file = "/etc/lqos/fleet/pop2.key"
+
+            [[members]]
+            name = "POP2"
+            url = "pop3.example.net:9123"
+            api_key_file = "/etc/lqos/fleet/pop3.key"
+            "#,
+        )
+        .expect("section parses");
+        assert_eq!(config.poll_interval_seconds, 15);
+        assert!(!config.members[0].accept_invalid_certificates);
+        assert!(
+            config
+                .validate()
+                .is_err_and(|err| err.contains("more than once"))
+        );
+    }
+}
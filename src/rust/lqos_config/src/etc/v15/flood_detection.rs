@@ -0,0 +1,181 @@
+//! Per-circuit flood and abuse detection.
+//!
+//! The XDP path keeps per-circuit counters (SYNs, new flows, distinct remote
+//! addresses and small packets); `lqosd` compares their rates with these
+//! thresholds and raises an urgent issue naming the circuit. It can also cap
+//! or drop the offending direction for a while.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+
+/// Highest `distinct_remotes_per_second` the dataplane estimate can measure.
+pub const MAX_FLOOD_DISTINCT_REMOTES: u64 = 1_000;
+
+fn default_sustain_seconds() -> u32 {
+    5
+}
+
+fn default_syn_ratio() -> f64 {
+    0.5
+}
+
+fn default_syn_min_packets_per_second() -> u64 {
+    500
+}
+
+fn default_new_flows_per_second() -> u64 {
+    1_000
+}
+
+fn default_distinct_remotes_per_second() -> u64 {
+    500
+}
+
+fn default_small_packets_per_second() -> u64 {
+    20_000
+}
+
+fn default_mitigation_pps_cap() -> u32 {
+    1_000
+}
+
+fn default_mitigation_minutes() -> u32 {
+    10
+}
+
+/// What happens to the offending direction of a flagged circuit.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(rename_all = "snake_case")]
+pub enum FloodMitigationMode {
+    /// Only raise an urgent issue.
+    #[default]
+    None,
+    /// Limit the direction to `mitigation_pps_cap` packets per second.
+    PpsCap,
+    /// Drop the direction entirely.
+    Drop,
+}
+
+/// Flood detection thresholds, evaluated per circuit and direction. A
+/// threshold of 0 turns that check off.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct FloodDetectionConfig {
+    /// Count and evaluate per-circuit anomaly counters.
+    pub enabled: bool,
+    /// Consecutive seconds a threshold must be exceeded before flagging.
+    #[serde(default = "default_sustain_seconds")]
+    pub sustain_seconds: u32,
+    /// Share of TCP packets that are SYNs without ACK.
+    #[serde(default = "default_syn_ratio")]
+    pub syn_ratio: f64,
+    /// TCP packets per second below which the SYN ratio is ignored.
+    #[serde(default = "default_syn_min_packets_per_second")]
+    pub syn_min_packets_per_second: u64,
+    /// New TCP, UDP and ICMP flows per second.
+    #[serde(default = "default_new_flows_per_second")]
+    pub new_flows_per_second: u64,
+    /// Distinct remote addresses per second (estimated).
+    #[serde(default = "default_distinct_remotes_per_second")]
+    pub distinct_remotes_per_second: u64,
+    /// Packets of 128 bytes or less per second.
+    #[serde(default = "default_small_packets_per_second")]
+    pub small_packets_per_second: u64,
+    /// Automatic action for a flagged direction.
+    pub mitigation: FloodMitigationMode,
+    /// Packets per second allowed by the `pps_cap` mitigation.
+    #[serde(default = "default_mitigation_pps_cap")]
+    pub mitigation_pps_cap: u32,
+    /// How long an automatic mitigation lasts.
+    #[serde(default = "default_mitigation_minutes")]
+    pub mitigation_minutes: u32,
+    /// Circuit IDs that are never mitigated automatically.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exempt_circuits: Vec<String>,
+}
+
+impl Default for FloodDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sustain_seconds: default_sustain_seconds(),
+            syn_ratio: default_syn_ratio(),
+            syn_min_packets_per_second: default_syn_min_packets_per_second(),
+            new_flows_per_second: default_new_flows_per_second(),
+            distinct_remotes_per_second: default_distinct_remotes_per_second(),
+            small_packets_per_second: default_small_packets_per_second(),
+            mitigation: FloodMitigationMode::None,
+            mitigation_pps_cap: default_mitigation_pps_cap(),
+            mitigation_minutes: default_mitigation_minutes(),
+            exempt_circuits: Vec::new(),
+        }
+    }
+}
+
+impl FloodDetectionConfig {
+    /// Validates the flood detection configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sustain_seconds == 0 {
+            return Err("flood_detection.sustain_seconds must be > 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.syn_ratio) {
+            return Err("flood_detection.syn_ratio must be between 0 and 1".to_string());
+        }
+        if self.distinct_remotes_per_second > MAX_FLOOD_DISTINCT_REMOTES {
+            return Err(format!(
+                "flood_detection.distinct_remotes_per_second can be at most {MAX_FLOOD_DISTINCT_REMOTES}"
+            ));
+        }
+        if self.mitigation == FloodMitigationMode::PpsCap && self.mitigation_pps_cap == 0 {
+            return Err("flood_detection.mitigation_pps_cap must be > 0".to_string());
+        }
+        if self.mitigation != FloodMitigationMode::None && self.mitigation_minutes == 0 {
+            return Err("flood_detection.mitigation_minutes must be > 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_the_section() {
+        let config: FloodDetectionConfig = toml::from_str("").expect("empty section parses");
+        assert!(!config.enabled);
+        assert_eq!(config.mitigation, FloodMitigationMode::None);
+        assert!(config.validate().is_ok());
+
+        let config: FloodDetectionConfig = toml::from_str(
+            r#"
+            enabled = true
+            syn_ratio = 0.7
+            mitigation = "pps_cap"
+            mitigation_pps_cap = 500
+            exempt_circuits = ["server-farm"]
+            "#,
+        )
+        .expect("section parses");
+        assert!(config.validate().is_ok());
+        assert_eq!(config.mitigation, FloodMitigationMode::PpsCap);
+        assert_eq!(config.sustain_seconds, 5);
+
+        let bad = FloodDetectionConfig {
+            syn_ratio: 1.5,
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+        let bad = FloodDetectionConfig {
+            distinct_remotes_per_second: 5_000,
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+        let bad = FloodDetectionConfig {
+            mitigation: FloodMitigationMode::Drop,
+            mitigation_minutes: 0,
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+}
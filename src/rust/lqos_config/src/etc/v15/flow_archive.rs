@@ -0,0 +1,105 @@
+//! Finished-flow archive configuration.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+fn default_false() -> bool {
+    false
+}
+
+fn default_retention_days() -> u32 {
+    30
+}
+
+fn default_max_size_mb() -> u64 {
+    10240
+}
+
+/// Settings for the on-disk archive of completed flows.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct FlowArchiveConfig {
+    /// Enables writing completed flows to the archive.
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    /// Directory holding the archive. Defaults to `flow_archive` under the state directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// Days of flows to keep before the oldest hours are deleted.
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+    /// Maximum total archive size, in megabytes. The oldest hours are deleted first.
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+impl Default for FlowArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_false(),
+            directory: None,
+            retention_days: default_retention_days(),
+            max_size_mb: default_max_size_mb(),
+        }
+    }
+}
+
+impl FlowArchiveConfig {
+    /// Validates the flow archive configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.retention_days == 0 {
+            return Err("flow_archive.retention_days must be > 0".to_string());
+        }
+        if self.max_size_mb == 0 {
+            return Err("flow_archive.max_size_mb must be > 0".to_string());
+        }
+        if self
+            .directory
+            .as_ref()
+            .is_some_and(|directory| directory.trim().is_empty())
+        {
+            return Err("flow_archive.directory must not be empty when set".to_string());
+        }
+        Ok(())
+    }
+
+    /// Resolves the archive directory, falling back to `flow_archive` under `state_directory`.
+    pub fn resolved_directory(&self, state_directory: &std::path::Path) -> PathBuf {
+        match &self.directory {
+            Some(directory) => PathBuf::from(directory.trim()),
+            None => state_directory.join("flow_archive"),
+        }
+    }
+
+    /// Maximum total archive size, in bytes.
+    pub fn max_size_bytes(&self) -> u64 {
+        self.max_size_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_disabled_and_valid() {
+        let config: FlowArchiveConfig = toml::from_str("").expect("empty section parses");
+        assert!(!config.enabled);
+        assert_eq!(config.retention_days, 30);
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.resolved_directory(std::path::Path::new("/var/lib/libreqos")),
+            PathBuf::from("/var/lib/libreqos/flow_archive")
+        );
+    }
+
+    #[test]
+    fn zero_retention_is_rejected() {
+        let config = FlowArchiveConfig {
+            retention_days: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
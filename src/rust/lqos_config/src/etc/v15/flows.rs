@@ -13,6 +13,10 @@ pub struct FlowConfig {
     pub netflow_ip: Option<String>,
     pub netflow_version: Option<u8>,
     pub do_not_track_subnets: Option<Vec<String>>,
+    /// Observe DNS answers to name remote flow endpoints. Disabled when unset.
+    pub passive_dns: Option<bool>,
+    /// Subscriber subnets whose DNS answers are never recorded.
+    pub passive_dns_exclude_subnets: Option<Vec<String>>,
 }
 
 impl Default for FlowConfig {
@@ -24,6 +28,8 @@ impl Default for FlowConfig {
             netflow_ip: None,
             netflow_version: None,
             do_not_track_subnets: None,
+            passive_dns: None,
+            passive_dns_exclude_subnets: None,
         }
     }
 }
@@ -0,0 +1,145 @@
+//! Active/standby state replication between two shapers.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+
+fn default_listen() -> String {
+    "0.0.0.0:9130".to_string()
+}
+
+fn default_sync_interval_seconds() -> u64 {
+    5
+}
+
+fn default_failover_seconds() -> u64 {
+    15
+}
+
+fn default_traffic_threshold_mbps() -> f64 {
+    1.0
+}
+
+/// Which role a node takes when it cannot learn one from its peer.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+#[serde(rename_all = "snake_case")]
+pub enum HaRoleMode {
+    /// Start as standby and promote automatically once this node carries
+    /// traffic while the peer is unreachable, idle or also standby.
+    #[default]
+    Auto,
+    /// Start as active. Fails back over like `auto` after losing a conflict.
+    Active,
+    /// Start as standby. Only promoted by the promotion command.
+    Standby,
+}
+
+/// Settings for replicating runtime state to a peer shaper.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct HaConfig {
+    /// Enables state replication.
+    pub enabled: bool,
+    /// Initial role and failover behaviour.
+    pub role: HaRoleMode,
+    /// Address the replication listener binds to.
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Address (`host:port`) of the peer's replication listener.
+    pub peer: String,
+    /// File holding the shared secret both peers authenticate with.
+    pub shared_secret_file: String,
+    /// PEM certificate presented to the peer.
+    pub tls_certificate: String,
+    /// PEM (PKCS#8) private key for `tls_certificate`.
+    pub tls_private_key: String,
+    /// PEM certificate, or the CA that issued it, trusted for the peer.
+    pub peer_certificate: String,
+    /// Seconds between status exchanges and state comparisons.
+    #[serde(default = "default_sync_interval_seconds")]
+    pub sync_interval_seconds: u64,
+    /// Seconds a peer may be silent or idle before `auto` mode fails over.
+    #[serde(default = "default_failover_seconds")]
+    pub failover_seconds: u64,
+    /// Combined throughput above which a node counts as carrying traffic.
+    #[serde(default = "default_traffic_threshold_mbps")]
+    pub traffic_threshold_mbps: f64,
+}
+
+impl Default for HaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            role: HaRoleMode::default(),
+            listen: default_listen(),
+            peer: String::new(),
+            shared_secret_file: String::new(),
+            tls_certificate: String::new(),
+            tls_private_key: String::new(),
+            peer_certificate: String::new(),
+            sync_interval_seconds: default_sync_interval_seconds(),
+            failover_seconds: default_failover_seconds(),
+            traffic_threshold_mbps: default_traffic_threshold_mbps(),
+        }
+    }
+}
+
+impl HaConfig {
+    /// Validates the replication configuration. A disabled section is always valid.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (name, value) in [
+            ("ha.listen", &self.listen),
+            ("ha.peer", &self.peer),
+            ("ha.shared_secret_file", &self.shared_secret_file),
+            ("ha.tls_certificate", &self.tls_certificate),
+            ("ha.tls_private_key", &self.tls_private_key),
+            ("ha.peer_certificate", &self.peer_certificate),
+        ] {
+            if value.trim().is_empty() {
+                return Err(format!("{name} must be set when ha.enabled = true"));
+            }
+        }
+        if self.sync_interval_seconds == 0 {
+            return Err("ha.sync_interval_seconds must be > 0".to_string());
+        }
+        if self.failover_seconds < self.sync_interval_seconds * 2 {
+            return Err(
+                "ha.failover_seconds must be at least twice ha.sync_interval_seconds".to_string(),
+            );
+        }
+        if !self.traffic_threshold_mbps.is_finite() || self.traffic_threshold_mbps < 0.0 {
+            return Err("ha.traffic_threshold_mbps must be a non-negative number".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_by_default_and_requires_peer_settings_when_enabled() {
+        let config: HaConfig = toml::from_str("").expect("empty section parses");
+        assert!(!config.enabled);
+        assert_eq!(config.role, HaRoleMode::Auto);
+        assert!(config.validate().is_ok());
+
+        let config: HaConfig = toml::from_str(
+            r#"
+            enabled = true
+            role = "standby"
+            peer = "192.0.2.2:9130"
+            "#,
+        )
+        .expect("section parses");
+        assert_eq!(config.role, HaRoleMode::Standby);
+        assert!(
+            config
+                .validate()
+                .is_err_and(|err| err.contains("ha.shared_secret_file"))
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Shaping input revision history configuration.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_revisions() -> u32 {
+    200
+}
+
+fn default_max_age_days() -> u32 {
+    30
+}
+
+/// Settings for the revision store that snapshots `network.json`,
+/// `ShapedDevices.csv` and `lqos_overrides.json` whenever they change.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct InputHistoryConfig {
+    /// Enables recording revisions.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Directory holding the store. Defaults to `input_history` under the state directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// Maximum number of revisions kept. The newest revision is always kept.
+    #[serde(default = "default_max_revisions")]
+    pub max_revisions: u32,
+    /// Days after which revisions are deleted. The newest revision is always kept.
+    #[serde(default = "default_max_age_days")]
+    pub max_age_days: u32,
+}
+
+impl Default for InputHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            directory: None,
+            max_revisions: default_max_revisions(),
+            max_age_days: default_max_age_days(),
+        }
+    }
+}
+
+impl InputHistoryConfig {
+    /// Validates the input history configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_revisions == 0 {
+            return Err("input_history.max_revisions must be > 0".to_string());
+        }
+        if self.max_age_days == 0 {
+            return Err("input_history.max_age_days must be > 0".to_string());
+        }
+        if self
+            .directory
+            .as_ref()
+            .is_some_and(|directory| directory.trim().is_empty())
+        {
+            return Err("input_history.directory must not be empty when set".to_string());
+        }
+        Ok(())
+    }
+
+    /// Resolves the store directory, falling back to `input_history` under `state_directory`.
+    pub fn resolved_directory(&self, state_directory: &std::path::Path) -> PathBuf {
+        match &self.directory {
+            Some(directory) => PathBuf::from(directory.trim()),
+            None => state_directory.join("input_history"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_enabled_and_valid() {
+        let config: InputHistoryConfig = toml::from_str("").expect("empty section parses");
+        assert!(config.enabled);
+        assert_eq!(config.max_revisions, 200);
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.resolved_directory(std::path::Path::new("/var/lib/libreqos")),
+            PathBuf::from("/var/lib/libreqos/input_history")
+        );
+    }
+}
@@ -31,6 +31,88 @@ fn default_attachment_refresh_debounce_seconds() -> u64 {
     3
 }
 
+fn default_routing_bird_socket() -> String {
+    "/run/bird/bird.ctl".to_string()
+}
+
+fn default_routing_vtysh_path() -> String {
+    "/usr/bin/vtysh".to_string()
+}
+
+fn default_routing_command_timeout_seconds() -> u64 {
+    2
+}
+
+/// Routing daemon read for attachment health.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(rename_all = "snake_case")]
+pub enum TopologyRoutingHealthSource {
+    /// Attachment health comes from probes only.
+    #[default]
+    Disabled,
+    /// BIRD, through its control socket.
+    Bird,
+    /// FRR, through `vtysh -c '... json'`.
+    Frr,
+}
+
+/// How routing state combines with probe results for an attachment pair.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(rename_all = "snake_case")]
+pub enum TopologyRoutingHealthPolicy {
+    /// Healthy only when probes succeed and routing is not down.
+    #[default]
+    Both,
+    /// Healthy when probes succeed or routing is up.
+    Either,
+    /// Routing decides; probes are used only for pairs without routing state.
+    RoutingOnly,
+}
+
+/// Routing-protocol (OSPF/BGP adjacency and next-hop) input to attachment health.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct TopologyRoutingHealthConfig {
+    /// Routing daemon to read, or `disabled`.
+    #[serde(default)]
+    pub source: TopologyRoutingHealthSource,
+
+    /// How routing state combines with probe results.
+    #[serde(default)]
+    pub policy: TopologyRoutingHealthPolicy,
+
+    /// BIRD control socket path.
+    #[serde(default = "default_routing_bird_socket")]
+    pub bird_socket: String,
+
+    /// Path to FRR's `vtysh`.
+    #[serde(default = "default_routing_vtysh_path")]
+    pub vtysh_path: String,
+
+    /// Also treat a remote probe IP used as an installed route next-hop as up.
+    ///
+    /// Reads the full routing table each round, so leave this off on routers
+    /// carrying full BGP tables.
+    #[serde(default)]
+    pub check_next_hops: bool,
+
+    /// Seconds to wait for the routing daemon before treating its state as unknown.
+    #[serde(default = "default_routing_command_timeout_seconds")]
+    pub command_timeout_seconds: u64,
+}
+
+impl Default for TopologyRoutingHealthConfig {
+    fn default() -> Self {
+        Self {
+            source: TopologyRoutingHealthSource::default(),
+            policy: TopologyRoutingHealthPolicy::default(),
+            bird_socket: default_routing_bird_socket(),
+            vtysh_path: default_routing_vtysh_path(),
+            check_next_hops: false,
+            command_timeout_seconds: default_routing_command_timeout_seconds(),
+        }
+    }
+}
+
 /// Shared runtime defaults for Topology Manager attachment health probing.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
 pub struct TopologyAttachmentHealthConfig {
@@ -57,6 +139,10 @@ pub struct TopologyAttachmentHealthConfig {
     /// Debounce window used before triggering topology/shaping refresh.
     #[serde(default = "default_attachment_refresh_debounce_seconds")]
     pub refresh_debounce_seconds: u64,
+
+    /// Optional OSPF/BGP routing state combined with probe health.
+    #[serde(default)]
+    pub routing: TopologyRoutingHealthConfig,
 }
 
 impl Default for TopologyAttachmentHealthConfig {
@@ -68,6 +154,7 @@ impl Default for TopologyAttachmentHealthConfig {
             hold_down_seconds: default_attachment_hold_down_seconds(),
             clear_after_successes: default_attachment_clear_after_successes(),
             refresh_debounce_seconds: default_attachment_refresh_debounce_seconds(),
+            routing: TopologyRoutingHealthConfig::default(),
         }
     }
 }
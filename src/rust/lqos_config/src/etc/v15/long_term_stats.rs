@@ -3,6 +3,14 @@
 use allocative::Allocative;
 use serde::{Deserialize, Serialize};
 
+fn default_spool_max_mb() -> u64 {
+    256
+}
+
+fn default_spool_max_age_hours() -> u64 {
+    72
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Allocative)]
 pub struct LongTermStats {
     /// Should we store long-term stats at all?
@@ -36,6 +44,15 @@ pub struct LongTermStats {
     /// "Primary", "None" are the only valid values for now. This is in alpha
     /// and may not work as expected. It is disabled by default.
     pub insight_topology_role: Option<String>,
+
+    /// Maximum size of the on-disk spool holding batches that could not be
+    /// sent to Insight yet, in megabytes. The oldest batches are evicted first.
+    #[serde(default = "default_spool_max_mb")]
+    pub spool_max_mb: u64,
+
+    /// Hours after which unsent spooled batches are discarded.
+    #[serde(default = "default_spool_max_age_hours")]
+    pub spool_max_age_hours: u64,
 }
 
 impl Default for LongTermStats {
@@ -48,6 +65,8 @@ impl Default for LongTermStats {
             lts_url: None,
             enable_insight_topology: None,
             insight_topology_role: None,
+            spool_max_mb: default_spool_max_mb(),
+            spool_max_age_hours: default_spool_max_age_hours(),
         }
     }
 }
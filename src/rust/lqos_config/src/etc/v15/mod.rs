@@ -5,9 +5,17 @@ pub use top_config::Config;
 pub use top_config::RttThresholds;
 pub use top_config::{SslConfig, normalize_external_hostname};
 mod bridge;
+mod cgnat;
+mod dhcp_leases;
+mod dscp_remarking;
 mod dynamic_circuits;
+mod fleet;
+mod flood_detection;
+mod flow_archive;
 mod flows;
+mod ha;
 pub mod influxdb;
+mod input_history;
 mod integration_common;
 mod ip_ranges;
 mod local_api;
@@ -16,33 +24,75 @@ mod long_term_stats;
 mod mikrotik_ipv6;
 mod netzur_integration;
 mod powercode_integration;
+mod priority_classes;
 mod queues;
 mod radius_accounting;
+mod snmp;
 mod sonar_integration;
 mod splynx_integration;
+mod sqm_profiles;
 mod stormguard;
+mod subscriber_portal;
 mod topology;
+mod traffic_groups;
+mod traffic_mirror;
+mod traffic_planner;
 mod treeguard;
 mod tuning;
 mod uisp_integration;
 mod visp_integration;
+mod walled_garden;
 mod wispgate;
 
 pub use bridge::*;
+pub use cgnat::{
+    CgnatConfig, CgnatDeterministicRule, CgnatPool, CgnatPortBlock, CgnatPortBlockPool,
+    MAX_CGNAT_PORT_BLOCKS, MAX_CGNAT_PUBLIC_ADDRESSES,
+};
+pub use dhcp_leases::{DhcpLeaseFileConfig, DhcpLeaseFileFormat, DhcpLeasesConfig};
+pub use dscp_remarking::{
+    DscpMarkRule, DscpMarkRuleConfig, DscpPolicyConfig, DscpPolicyDirections, DscpPolicyMode,
+    DscpRemarkingConfig, MAX_DSCP_ASN_GROUPS, MAX_DSCP_POLICIES, MAX_DSCP_RULES_PER_POLICY,
+    parse_dscp,
+};
 pub use dynamic_circuits::*;
-pub use integration_common::IntegrationConfig;
+pub use fleet::{FleetConfig, FleetMemberConfig};
+pub use flood_detection::{FloodDetectionConfig, FloodMitigationMode, MAX_FLOOD_DISTINCT_REMOTES};
+pub use flow_archive::FlowArchiveConfig;
+pub use ha::{HaConfig, HaRoleMode};
+pub use input_history::InputHistoryConfig;
+pub use integration_common::{
+    IntegrationConfig, TopologyRoutingHealthConfig, TopologyRoutingHealthPolicy,
+    TopologyRoutingHealthSource,
+};
 pub use long_term_stats::LongTermStats;
 pub use mikrotik_ipv6::MikrotikIpv6Config;
+pub use priority_classes::{
+    MAX_PRIORITY_CLASSES_PER_CIRCUIT, MAX_PRIORITY_RULES_PER_CIRCUIT, PriorityClassConfig,
+    PriorityMatchRule,
+};
 pub use queues::{LazyQueueMode, QueueMode};
 pub use radius_accounting::{
     RadiusAccountingClient, RadiusAccountingConfig, RadiusClientSource,
     RadiusDynamicCircuitApplicationConfig, RadiusFallbackSpeedProfile, RadiusSharedSecretSource,
     RateProfileValidationError, validate_rate_profile_mbps,
 };
+pub use snmp::SnmpConfig;
+pub use sqm_profiles::{
+    SqmProfileConfig, SqmProfileRule, is_valid_profile_name, validate_sqm_tokens,
+};
 pub use stormguard::{StormguardConfig, StormguardStrategy};
+pub use subscriber_portal::SubscriberPortalConfig;
 pub use topology::{TopologyConfig, normalize_topology_compile_mode};
+pub use traffic_groups::{TRAFFIC_GROUP_OTHER, TrafficGroupConfig};
+pub use traffic_mirror::{
+    MAX_MIRROR_PREFIXES, MAX_MIRROR_SESSIONS, MIN_MIRROR_SNAPLEN, MirrorEncapsulation,
+    TrafficMirrorConfig, parse_mac_address, parse_mirror_prefix,
+};
+pub use traffic_planner::TrafficPlannerConfig;
 pub use treeguard::{
     TreeguardCircuitsConfig, TreeguardConfig, TreeguardCpuConfig, TreeguardCpuMode,
     TreeguardLinksConfig, TreeguardQooConfig,
 };
 pub use tuning::Tunables;
+pub use walled_garden::{CircuitAccessPolicy, WalledGardenConfig};
@@ -0,0 +1,362 @@
+//! Named intra-circuit priority classes (for example a VoIP or gaming lane).
+//!
+//! A priority class is an HTB child inside a circuit with its own guaranteed
+//! rate, ceiling and leaf qdisc. The eBPF classifier steers matching packets
+//! into it by DSCP, protocol/port and remote prefix.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Most priority classes a single circuit can use.
+pub const MAX_PRIORITY_CLASSES_PER_CIRCUIT: usize = 4;
+
+/// Most expanded match rules a single circuit can use per direction.
+///
+/// Must match `MAX_PRIORITY_LANE_RULES` in the eBPF `maximums.h`.
+pub const MAX_PRIORITY_RULES_PER_CIRCUIT: usize = 16;
+
+/// One flattened match rule, as programmed into the eBPF classifier.
+///
+/// Every set field must match. `protocol` 0 matches any protocol, a zero port
+/// range matches any port, and `None` for `prefix` matches any remote address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriorityMatchRule {
+    /// Bit `n` set means DSCP `n` matches; zero matches any DSCP.
+    pub dscp_mask: u64,
+    /// IP protocol number, or 0 for any.
+    pub protocol: u8,
+    /// Lowest source or destination port that matches.
+    pub port_min: u16,
+    /// Highest source or destination port that matches.
+    pub port_max: u16,
+    /// Remote (non-subscriber) network and prefix length.
+    pub prefix: Option<(IpAddr, u8)>,
+}
+
+/// A protocol and optional port range parsed from a `ports` entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct PortMatch {
+    pub(super) protocol: u8,
+    pub(super) port_min: u16,
+    pub(super) port_max: u16,
+}
+
+pub(super) fn parse_port_match(entry: &str) -> Result<PortMatch, String> {
+    let entry = entry.trim().to_ascii_lowercase();
+    let (protocol, ports) = match entry.split_once(':') {
+        Some((protocol, ports)) => (protocol.trim().to_string(), Some(ports.trim().to_string())),
+        None => (entry.clone(), None),
+    };
+    let protocol = match protocol.as_str() {
+        "tcp" => 6,
+        "udp" => 17,
+        "icmp" => 1,
+        "icmpv6" => 58,
+        other => other
+            .parse::<u8>()
+            .ok()
+            .filter(|p| *p != 0)
+            .ok_or_else(|| format!("'{entry}' has an unknown protocol '{other}'"))?,
+    };
+    let Some(ports) = ports else {
+        return Ok(PortMatch {
+            protocol,
+            port_min: 0,
+            port_max: 0,
+        });
+    };
+    if protocol != 6 && protocol != 17 {
+        return Err(format!("'{entry}': ports are only valid for tcp and udp"));
+    }
+    let (min, max) = ports.split_once('-').unwrap_or((&ports, &ports));
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|p| *p != 0)
+            .ok_or_else(|| format!("'{entry}' has an invalid port '{value}'"))
+    };
+    let (port_min, port_max) = (parse(min)?, parse(max)?);
+    if port_min > port_max {
+        return Err(format!("'{entry}' has a reversed port range"));
+    }
+    Ok(PortMatch {
+        protocol,
+        port_min,
+        port_max,
+    })
+}
+
+pub(super) fn parse_prefix(entry: &str) -> Result<(IpAddr, u8), String> {
+    let entry = entry.trim();
+    let (address, length) = entry.split_once('/').unwrap_or((entry, ""));
+    let address = address
+        .parse::<IpAddr>()
+        .map_err(|_| format!("'{entry}' is not an IP address or prefix"))?;
+    let max_length = if address.is_ipv4() { 32 } else { 128 };
+    let length = if length.is_empty() {
+        max_length
+    } else {
+        length
+            .parse::<u8>()
+            .ok()
+            .filter(|l| *l <= max_length)
+            .ok_or_else(|| format!("'{entry}' has an invalid prefix length"))?
+    };
+    Ok((address, length))
+}
+
+/// A named priority class that circuits opt into by name.
+///
+/// Circuits select classes through the ShapedDevices.csv `priority_classes`
+/// column or a `circuit_adjust_priority_classes` override. Traffic that
+/// matches no class stays in the circuit's default lane.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct PriorityClassConfig {
+    /// Name referenced by circuits; lowercase letters, digits, `-` and `_`.
+    pub name: String,
+    /// Guaranteed download rate (Mbps) inside the circuit.
+    pub download_min_mbps: f32,
+    /// Guaranteed upload rate (Mbps) inside the circuit.
+    pub upload_min_mbps: f32,
+    /// Download ceiling (Mbps). Omitted uses the circuit's ceiling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_max_mbps: Option<f32>,
+    /// Upload ceiling (Mbps). Omitted uses the circuit's ceiling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_max_mbps: Option<f32>,
+    /// SQM override token for this class's leaf qdisc. Empty uses the circuit's.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub sqm: String,
+    /// DSCP values (0-63) that select this class.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dscp: Vec<u8>,
+    /// Protocol/port matches such as `udp:5060-5061`, `tcp:443` or `udp`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    /// Remote networks such as `203.0.113.10/32` or `2001:db8::/48`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prefixes: Vec<String>,
+}
+
+impl PriorityClassConfig {
+    /// Returns the ceiling in one direction, capped at the circuit ceiling.
+    pub fn max_mbps(&self, circuit_max_mbps: f32, upload: bool) -> f32 {
+        let own = if upload {
+            self.upload_max_mbps
+        } else {
+            self.download_max_mbps
+        };
+        own.map_or(circuit_max_mbps, |max| max.min(circuit_max_mbps))
+    }
+
+    /// Returns the guaranteed rate in one direction, capped at `max_mbps`.
+    pub fn min_mbps(&self, circuit_max_mbps: f32, upload: bool) -> f32 {
+        let own = if upload {
+            self.upload_min_mbps
+        } else {
+            self.download_min_mbps
+        };
+        own.min(self.max_mbps(circuit_max_mbps, upload))
+    }
+
+    /// Flattens the class into eBPF match rules: one per port entry and
+    /// prefix combination, each sharing the DSCP set.
+    ///
+    /// Entries that fail to parse are skipped; `validate` reports them.
+    pub fn match_rules(&self) -> Vec<PriorityMatchRule> {
+        let dscp_mask = self
+            .dscp
+            .iter()
+            .filter(|d| **d < 64)
+            .fold(0u64, |mask, d| mask | (1u64 << d));
+        let ports: Vec<Option<PortMatch>> = if self.ports.is_empty() {
+            vec![None]
+        } else {
+            self.ports
+                .iter()
+                .filter_map(|p| parse_port_match(p).ok())
+                .map(Some)
+                .collect()
+        };
+        let prefixes: Vec<Option<(IpAddr, u8)>> = if self.prefixes.is_empty() {
+            vec![None]
+        } else {
+            self.prefixes
+                .iter()
+                .filter_map(|p| parse_prefix(p).ok())
+                .map(Some)
+                .collect()
+        };
+        let mut rules = Vec::with_capacity(ports.len() * prefixes.len());
+        for port in &ports {
+            for prefix in &prefixes {
+                rules.push(PriorityMatchRule {
+                    dscp_mask,
+                    protocol: port.map_or(0, |p| p.protocol),
+                    port_min: port.map_or(0, |p| p.port_min),
+                    port_max: port.map_or(0, |p| p.port_max),
+                    prefix: *prefix,
+                });
+            }
+        }
+        rules
+    }
+
+    /// Validates a single priority class.
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.as_str();
+        if name.is_empty() || !crate::is_valid_profile_name(name) {
+            return Err(format!(
+                "priority_classes.name \"{name}\" must be lowercase letters, digits, '-' or '_'"
+            ));
+        }
+        let field = format!("priority class \"{name}\"");
+        for (label, rate) in [
+            ("download_min_mbps", Some(self.download_min_mbps)),
+            ("upload_min_mbps", Some(self.upload_min_mbps)),
+            ("download_max_mbps", self.download_max_mbps),
+            ("upload_max_mbps", self.upload_max_mbps),
+        ] {
+            if let Some(rate) = rate
+                && (!rate.is_finite() || rate < 0.01)
+            {
+                return Err(format!("{field} {label} must be at least 0.01 Mbps"));
+            }
+        }
+        if self.dscp.is_empty() && self.ports.is_empty() && self.prefixes.is_empty() {
+            return Err(format!(
+                "{field} must match on at least one of dscp, ports or prefixes"
+            ));
+        }
+        if let Some(dscp) = self.dscp.iter().find(|d| **d > 63) {
+            return Err(format!("{field} has DSCP {dscp}; DSCP values are 0-63"));
+        }
+        if !self.sqm.is_empty()
+            && (self.sqm.contains('/') || !crate::is_valid_profile_name(&self.sqm))
+        {
+            return Err(format!(
+                "{field} sqm must be 'cake', 'fq_codel', 'fq_pie', 'none' or an SQM profile name"
+            ));
+        }
+        for entry in &self.ports {
+            parse_port_match(entry).map_err(|e| format!("{field} ports: {e}"))?;
+        }
+        for entry in &self.prefixes {
+            parse_prefix(entry).map_err(|e| format!("{field} prefixes: {e}"))?;
+        }
+        let rule_count = self.ports.len().max(1) * self.prefixes.len().max(1);
+        if rule_count > MAX_PRIORITY_RULES_PER_CIRCUIT {
+            return Err(format!(
+                "{field} expands to {rule_count} match rules (ports x prefixes); the limit per circuit is {MAX_PRIORITY_RULES_PER_CIRCUIT}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Validates a list of priority classes, including name uniqueness.
+pub fn validate_priority_classes(classes: &[PriorityClassConfig]) -> Result<(), String> {
+    for (index, class) in classes.iter().enumerate() {
+        class.validate()?;
+        if classes[..index]
+            .iter()
+            .any(|other| other.name == class.name)
+        {
+            return Err(format!(
+                "priority class \"{}\" is defined more than once",
+                class.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(name: &str) -> PriorityClassConfig {
+        PriorityClassConfig {
+            name: name.to_string(),
+            download_min_mbps: 2.0,
+            upload_min_mbps: 2.0,
+            download_max_mbps: None,
+            upload_max_mbps: None,
+            sqm: String::new(),
+            dscp: vec![46],
+            ports: Vec::new(),
+            prefixes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_classes_and_flattens_rules() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            priority_classes: Vec<PriorityClassConfig>,
+        }
+        let wrapper: Wrapper = toml::from_str(
+            r#"
+            [[priority_classes]]
+            name = "sip"
+            download_min_mbps = 2
+            upload_min_mbps = 2
+            download_max_mbps = 10
+            sqm = "fq_codel"
+            dscp = [46, 26]
+            ports = ["udp:5060-5061", "udp:10000-20000"]
+            prefixes = ["203.0.113.0/24"]
+            "#,
+        )
+        .expect("priority classes parse");
+        assert!(validate_priority_classes(&wrapper.priority_classes).is_ok());
+        let sip = &wrapper.priority_classes[0];
+
+        assert_eq!(sip.max_mbps(100.0, false), 10.0);
+        assert_eq!(sip.max_mbps(5.0, false), 5.0);
+        assert_eq!(sip.max_mbps(100.0, true), 100.0);
+        assert_eq!(sip.min_mbps(1.0, false), 1.0);
+
+        let rules = sip.match_rules();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].dscp_mask, (1 << 46) | (1 << 26));
+        assert_eq!(rules[0].protocol, 17);
+        assert_eq!((rules[0].port_min, rules[0].port_max), (5060, 5061));
+        assert_eq!((rules[1].port_min, rules[1].port_max), (10000, 20000));
+        assert_eq!(
+            rules[1].prefix,
+            Some(("203.0.113.0".parse().expect("ip"), 24))
+        );
+    }
+
+    #[test]
+    fn invalid_classes_are_rejected() {
+        assert!(validate_priority_classes(&[class("VoIP")]).is_err());
+        assert!(validate_priority_classes(&[class("voip"), class("voip")]).is_err());
+
+        let mut no_match = class("voip");
+        no_match.dscp.clear();
+        assert!(no_match.validate().is_err());
+
+        let mut bad_dscp = class("voip");
+        bad_dscp.dscp = vec![64];
+        assert!(bad_dscp.validate().is_err());
+
+        let mut bad_port = class("voip");
+        bad_port.ports = vec!["icmp:80".to_string()];
+        assert!(bad_port.validate().is_err());
+        bad_port.ports = vec!["udp:6000-5000".to_string()];
+        assert!(bad_port.validate().is_err());
+
+        let mut bad_prefix = class("voip");
+        bad_prefix.prefixes = vec!["192.0.2.0/33".to_string()];
+        assert!(bad_prefix.validate().is_err());
+
+        let mut bad_sqm = class("voip");
+        bad_sqm.sqm = "cake/fq_codel".to_string();
+        assert!(bad_sqm.validate().is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! SNMP monitoring through an AgentX master agent such as net-snmp's `snmpd`.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+
+fn default_agentx_address() -> String {
+    "/var/agentx/master".to_string()
+}
+
+fn default_base_oid() -> String {
+    // netSnmpPlaypen.7341, matching the shipped LIBREQOS-MIB.
+    "1.3.6.1.4.1.8072.9999.9999.7341".to_string()
+}
+
+fn default_refresh_seconds() -> u64 {
+    10
+}
+
+/// Settings for serving the LIBREQOS-MIB to a local SNMP master agent.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct SnmpConfig {
+    /// Registers with the master agent and serves the MIB.
+    pub enabled: bool,
+    /// The master agent's `agentXSocket`: a Unix socket path or `tcp:host:port`.
+    #[serde(default = "default_agentx_address")]
+    pub agentx_address: String,
+    /// OID the LIBREQOS-MIB tree is registered under. Change it together with
+    /// the MIB file when moving the tree under your own enterprise number.
+    #[serde(default = "default_base_oid")]
+    pub base_oid: String,
+    /// Seconds between rebuilds of the served counters.
+    #[serde(default = "default_refresh_seconds")]
+    pub refresh_seconds: u64,
+}
+
+impl Default for SnmpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            agentx_address: default_agentx_address(),
+            base_oid: default_base_oid(),
+            refresh_seconds: default_refresh_seconds(),
+        }
+    }
+}
+
+impl SnmpConfig {
+    /// Validates the SNMP configuration. A disabled section is always valid.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.refresh_seconds == 0 {
+            return Err("snmp.refresh_seconds must be > 0".to_string());
+        }
+        let address = self.agentx_address.trim();
+        if !address.starts_with('/')
+            && !address.starts_with("unix:/")
+            && !address.starts_with("tcp:")
+        {
+            return Err("snmp.agentx_address must be a socket path or tcp:host:port".to_string());
+        }
+        let base = self.base_oid.trim().trim_start_matches('.');
+        if base.is_empty() || base.split('.').any(|part| part.parse::<u32>().is_err()) {
+            return Err(format!(
+                "snmp.base_oid {:?} is not a dotted object identifier",
+                self.base_oid
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_by_default_and_checked_when_enabled() {
+        let config: SnmpConfig = toml::from_str("").expect("empty section parses");
+        assert!(!config.enabled);
+        assert_eq!(config.agentx_address, "/var/agentx/master");
+        assert!(config.validate().is_ok());
+
+        let config: SnmpConfig = toml::from_str(
+            r#"
+            enabled = true
+            agentx_address = "tcp:127.0.0.1:705"
+            "#,
+        )
+        .expect("section parses");
+        assert!(config.validate().is_ok());
+
+        let bad_oid = SnmpConfig {
+            base_oid: "1.3.6.1.4.1.x".to_string(),
+            ..config.clone()
+        };
+        assert!(
+            bad_oid
+                .validate()
+                .is_err_and(|err| err.contains("base_oid"))
+        );
+        let bad_address = SnmpConfig {
+            agentx_address: "localhost:705".to_string(),
+            ..config
+        };
+        assert!(bad_address.validate().is_err());
+    }
+}
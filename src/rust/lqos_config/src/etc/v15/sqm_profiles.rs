@@ -0,0 +1,401 @@
+//! Named SQM profiles that circuits, sites and TreeGuard can reference by name.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+
+/// Built-in SQM override tokens that can not be used as profile names.
+const RESERVED_SQM_NAMES: &[&str] = &["cake", "fq_codel", "fq_pie", "none"];
+
+/// CAKE keywords that take no value.
+const CAKE_FLAGS: &[&str] = &[
+    "besteffort",
+    "diffserv3",
+    "diffserv4",
+    "diffserv8",
+    "precedence",
+    "flowblind",
+    "srchost",
+    "dsthost",
+    "hosts",
+    "flows",
+    "dual-srchost",
+    "dual-dsthost",
+    "triple-isolate",
+    "nat",
+    "nonat",
+    "wash",
+    "nowash",
+    "split-gso",
+    "no-split-gso",
+    "ack-filter",
+    "ack-filter-aggressive",
+    "no-ack-filter",
+    "ingress",
+    "egress",
+    "autorate-ingress",
+    "unlimited",
+    "raw",
+    "conservative",
+    "ethernet",
+    "docsis",
+    "atm",
+    "noatm",
+    "ptm",
+    "pppoa-vcmux",
+    "pppoa-llc",
+    "pppoe-vcmux",
+    "pppoe-llcsnap",
+    "pppoe-ptm",
+    "bridged-ptm",
+    "bridged-vcmux",
+    "bridged-llcsnap",
+    "ipoa-vcmux",
+    "ipoa-llcsnap",
+    "datacentre",
+    "lan",
+    "metro",
+    "regional",
+    "internet",
+    "oceanic",
+    "satellite",
+    "interplanetary",
+];
+
+/// `fq_codel` keywords that take no value.
+const FQ_CODEL_FLAGS: &[&str] = &["ecn", "noecn"];
+
+/// `fq_pie` keywords that take no value.
+const FQ_PIE_FLAGS: &[&str] = &[
+    "ecn",
+    "noecn",
+    "bytemode",
+    "nobytemode",
+    "dq_rate_estimator",
+    "no_dq_rate_estimator",
+];
+
+#[derive(Clone, Copy)]
+enum SqmValue {
+    /// A time such as `100ms`, `500us` or `1s`.
+    Time,
+    /// A whole number within the given range.
+    Integer(i64, i64),
+    /// A size or rate with an optional unit, such as `32mb` or `100mbit`.
+    Quantity,
+}
+
+fn valued_keyword(qdisc: &str, keyword: &str) -> Option<SqmValue> {
+    match (qdisc, keyword) {
+        ("cake", "rtt") => Some(SqmValue::Time),
+        ("cake", "overhead") => Some(SqmValue::Integer(-64, 256)),
+        ("cake", "mpu") => Some(SqmValue::Integer(0, 256)),
+        ("cake", "memlimit" | "bandwidth") => Some(SqmValue::Quantity),
+        ("cake", "fwmark") => Some(SqmValue::Integer(0, u32::MAX as i64)),
+        ("fq_codel" | "fq_pie", "target") => Some(SqmValue::Time),
+        ("fq_codel", "interval" | "ce_threshold") => Some(SqmValue::Time),
+        ("fq_pie", "tupdate") => Some(SqmValue::Time),
+        ("fq_codel" | "fq_pie", "limit" | "flows" | "quantum") => {
+            Some(SqmValue::Integer(1, u32::MAX as i64))
+        }
+        ("fq_codel", "drop_batch") => Some(SqmValue::Integer(1, u32::MAX as i64)),
+        ("fq_pie", "alpha" | "beta") => Some(SqmValue::Integer(0, 32)),
+        ("fq_pie", "ecn_prob") => Some(SqmValue::Integer(0, 100)),
+        ("fq_codel" | "fq_pie", "memory_limit") => Some(SqmValue::Quantity),
+        _ => None,
+    }
+}
+
+fn value_is_valid(kind: SqmValue, value: &str) -> bool {
+    match kind {
+        SqmValue::Time => {
+            let digits = value
+                .strip_suffix("us")
+                .or_else(|| value.strip_suffix("ms"))
+                .or_else(|| value.strip_suffix('s'))
+                .unwrap_or(value);
+            !digits.is_empty() && digits.parse::<f64>().is_ok_and(|v| v > 0.0)
+        }
+        SqmValue::Integer(min, max) => value.parse::<i64>().is_ok_and(|v| (min..=max).contains(&v)),
+        SqmValue::Quantity => {
+            let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            !digits.is_empty() && digits.parse::<f64>().is_ok_and(|v| v > 0.0)
+        }
+    }
+}
+
+/// Validates a qdisc token string such as `cake diffserv4 docsis ack-filter`.
+///
+/// The first token must be `cake`, `fq_codel` or `fq_pie`; the remaining
+/// tokens must be keywords (and values) that qdisc accepts.
+pub fn validate_sqm_tokens(field: &str, tokens: &str) -> Result<(), String> {
+    let mut words = tokens.split_whitespace();
+    let Some(qdisc) = words.next() else {
+        return Err(format!("{field} must not be empty"));
+    };
+    let flags = match qdisc {
+        "cake" => CAKE_FLAGS,
+        "fq_codel" => FQ_CODEL_FLAGS,
+        "fq_pie" => FQ_PIE_FLAGS,
+        other => {
+            return Err(format!(
+                "{field} must start with 'cake', 'fq_codel' or 'fq_pie', not '{other}'"
+            ));
+        }
+    };
+    while let Some(word) = words.next() {
+        if flags.contains(&word) {
+            continue;
+        }
+        let Some(kind) = valued_keyword(qdisc, word) else {
+            return Err(format!("{field}: '{word}' is not a {qdisc} option"));
+        };
+        match words.next() {
+            Some(value) if value_is_valid(kind, value) => {}
+            Some(value) => {
+                return Err(format!(
+                    "{field}: '{value}' is not a valid value for {qdisc} {word}"
+                ));
+            }
+            None => return Err(format!("{field}: {qdisc} {word} needs a value")),
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if `name` is a valid profile identifier: lowercase ASCII
+/// letters, digits, `-` and `_`, or empty.
+///
+/// Shared by SQM profile and override names, priority class names and QoO
+/// profile names. It only checks the spelling; callers check that a profile
+/// with that name exists.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    name.chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// One rate-dependent rule inside an SQM profile.
+///
+/// A rule matches when the circuit's ceiling in that direction is within
+/// `min_mbps..=max_mbps`; either bound may be omitted.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct SqmProfileRule {
+    /// Lowest ceiling (Mbps) this rule applies to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_mbps: Option<f64>,
+    /// Highest ceiling (Mbps) this rule applies to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_mbps: Option<f64>,
+    /// Download qdisc tokens when this rule matches. Empty keeps the profile's.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub download: String,
+    /// Upload qdisc tokens when this rule matches. Empty keeps the profile's.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub upload: String,
+}
+
+impl SqmProfileRule {
+    fn matches(&self, rate_mbps: f64) -> bool {
+        self.min_mbps.is_none_or(|min| rate_mbps >= min)
+            && self.max_mbps.is_none_or(|max| rate_mbps <= max)
+    }
+}
+
+/// A named SQM profile with separate download and upload qdisc tokens.
+///
+/// Circuits select it through the ShapedDevices.csv `sqm` column (for example
+/// `docsis` or `docsis/fq_pie`), sites through the network.json `sqm` key, and
+/// TreeGuard through `treeguard.circuits.cake_profile`/`fq_codel_profile`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct SqmProfileConfig {
+    /// Name referenced by overrides; lowercase letters, digits, `-` and `_`.
+    pub name: String,
+    /// Download qdisc tokens, e.g. `cake diffserv4 docsis ack-filter`.
+    pub download: String,
+    /// Upload qdisc tokens. Empty uses `download`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub upload: String,
+    /// Add the CAKE low-rate `rtt` fixups when the tokens set no `rtt`.
+    #[serde(default = "default_low_rate_rtt_fixups")]
+    pub low_rate_rtt_fixups: bool,
+    /// Rate-dependent token sets; the first matching rule wins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<SqmProfileRule>,
+}
+
+fn default_low_rate_rtt_fixups() -> bool {
+    true
+}
+
+impl SqmProfileConfig {
+    /// Returns the qdisc tokens for a circuit ceiling of `rate_mbps` in one direction.
+    pub fn tokens_for(&self, rate_mbps: f64, upload: bool) -> &str {
+        let from_rule = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(rate_mbps))
+            .map(|rule| if upload { &rule.upload } else { &rule.download })
+            .find(|tokens| !tokens.is_empty());
+        match from_rule {
+            Some(tokens) => tokens,
+            None if upload && !self.upload.is_empty() => &self.upload,
+            None => &self.download,
+        }
+    }
+
+    /// Validates a single SQM profile.
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.as_str();
+        if name.is_empty() || !is_valid_profile_name(name) {
+            return Err(format!(
+                "sqm_profiles.name \"{name}\" must be lowercase letters, digits, '-' or '_'"
+            ));
+        }
+        if RESERVED_SQM_NAMES.contains(&name) {
+            return Err(format!(
+                "sqm_profiles.name \"{name}\" is reserved for the built-in qdisc"
+            ));
+        }
+        validate_sqm_tokens(&format!("sqm profile \"{name}\" download"), &self.download)?;
+        if !self.upload.is_empty() {
+            validate_sqm_tokens(&format!("sqm profile \"{name}\" upload"), &self.upload)?;
+        }
+        for (index, rule) in self.rules.iter().enumerate() {
+            let field = format!("sqm profile \"{name}\" rule {}", index + 1);
+            if rule.download.is_empty() && rule.upload.is_empty() {
+                return Err(format!("{field} must set download or upload"));
+            }
+            for bound in [rule.min_mbps, rule.max_mbps].into_iter().flatten() {
+                if !bound.is_finite() || bound < 0.0 {
+                    return Err(format!("{field} has an invalid rate bound {bound}"));
+                }
+            }
+            if let (Some(min), Some(max)) = (rule.min_mbps, rule.max_mbps)
+                && min > max
+            {
+                return Err(format!("{field} has min_mbps greater than max_mbps"));
+            }
+            if !rule.download.is_empty() {
+                validate_sqm_tokens(&format!("{field} download"), &rule.download)?;
+            }
+            if !rule.upload.is_empty() {
+                validate_sqm_tokens(&format!("{field} upload"), &rule.upload)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validates a list of SQM profiles, including name uniqueness.
+pub fn validate_sqm_profiles(profiles: &[SqmProfileConfig]) -> Result<(), String> {
+    for (index, profile) in profiles.iter().enumerate() {
+        profile.validate()?;
+        if profiles[..index]
+            .iter()
+            .any(|other| other.name == profile.name)
+        {
+            return Err(format!(
+                "sqm profile \"{}\" is defined more than once",
+                profile.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, download: &str, upload: &str) -> SqmProfileConfig {
+        SqmProfileConfig {
+            name: name.to_string(),
+            download: download.to_string(),
+            upload: upload.to_string(),
+            low_rate_rtt_fixups: true,
+            rules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_profiles_with_rules() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            sqm_profiles: Vec<SqmProfileConfig>,
+        }
+        let wrapper: Wrapper = toml::from_str(
+            r#"
+            [[sqm_profiles]]
+            name = "docsis"
+            download = "cake diffserv4 docsis nat wash"
+            upload = "cake diffserv4 docsis ack-filter overhead 18 mpu 64"
+
+            [[sqm_profiles.rules]]
+            max_mbps = 5
+            upload = "cake besteffort docsis ack-filter-aggressive rtt 200ms"
+
+            [[sqm_profiles.rules]]
+            min_mbps = 1000
+            download = "fq_pie target 5ms"
+            upload = "fq_codel"
+            "#,
+        )
+        .expect("sqm profiles parse");
+        let docsis = &wrapper.sqm_profiles[0];
+        assert!(validate_sqm_profiles(&wrapper.sqm_profiles).is_ok());
+        assert!(docsis.low_rate_rtt_fixups);
+
+        assert_eq!(
+            docsis.tokens_for(100.0, false),
+            "cake diffserv4 docsis nat wash"
+        );
+        assert_eq!(
+            docsis.tokens_for(100.0, true),
+            "cake diffserv4 docsis ack-filter overhead 18 mpu 64"
+        );
+        // The low-rate rule only sets upload, so download keeps the profile tokens.
+        assert_eq!(
+            docsis.tokens_for(4.0, false),
+            "cake diffserv4 docsis nat wash"
+        );
+        assert_eq!(
+            docsis.tokens_for(4.0, true),
+            "cake besteffort docsis ack-filter-aggressive rtt 200ms"
+        );
+        assert_eq!(docsis.tokens_for(2000.0, false), "fq_pie target 5ms");
+        assert_eq!(docsis.tokens_for(2000.0, true), "fq_codel");
+    }
+
+    #[test]
+    fn invalid_profiles_are_rejected() {
+        assert!(validate_sqm_profiles(&[profile("Docsis", "cake", "")]).is_err());
+        assert!(validate_sqm_profiles(&[profile("cake", "cake", "")]).is_err());
+        assert!(validate_sqm_profiles(&[profile("p", "sfq", "")]).is_err());
+        assert!(validate_sqm_profiles(&[profile("p", "cake docsis overhead", "")]).is_err());
+        assert!(validate_sqm_profiles(&[profile("p", "cake overhead 900", "")]).is_err());
+        assert!(validate_sqm_profiles(&[profile("p", "cake rtt soon", "")]).is_err());
+        assert!(validate_sqm_profiles(&[profile("p", "fq_codel ack-filter", "")]).is_err());
+        assert!(
+            validate_sqm_profiles(&[profile("p", "cake", ""), profile("p", "fq_codel", "")])
+                .is_err()
+        );
+
+        let mut ruled = profile("p", "cake", "");
+        ruled.rules.push(SqmProfileRule {
+            min_mbps: Some(10.0),
+            max_mbps: Some(5.0),
+            download: "fq_codel".to_string(),
+            upload: String::new(),
+        });
+        assert!(validate_sqm_profiles(&[ruled]).is_err());
+    }
+
+    #[test]
+    fn profile_names_are_lowercase_identifiers() {
+        assert!(is_valid_profile_name(""));
+        assert!(is_valid_profile_name("fq_pie"));
+        assert!(is_valid_profile_name("docsis-2"));
+        assert!(!is_valid_profile_name("Docsis"));
+        assert!(!is_valid_profile_name("cake diffserv4"));
+        assert!(!is_valid_profile_name("a/b"));
+    }
+}
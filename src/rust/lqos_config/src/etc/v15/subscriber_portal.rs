@@ -0,0 +1,195 @@
+//! Read-only subscriber status portal.
+//!
+//! An optional listener, separate from the Node Manager, that shows one
+//! circuit's throughput against its plan, latency, QoO, bufferbloat grade
+//! and recent history. Subscribers reach it through a signed link issued
+//! per circuit over the bus or the portal API, so a CRM can embed it without
+//! exposing other subscribers or any admin API. Only the named local API keys
+//! listed in `link_api_keys` may issue links through the portal API.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+fn default_listen() -> String {
+    ":::9124".to_string()
+}
+
+fn default_default_link_hours() -> u32 {
+    720
+}
+
+fn default_max_link_hours() -> u32 {
+    8_760
+}
+
+/// The `[subscriber_portal]` section.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Allocative)]
+#[serde(default)]
+pub struct SubscriberPortalConfig {
+    /// Start the portal listener and allow links to be issued.
+    pub enabled: bool,
+    /// Address and port the portal listens on.
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Address subscribers use to reach the portal, e.g.
+    /// `https://status.example.net`. Issued links start with it; when empty
+    /// only the token is returned.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub public_url: String,
+    /// Link lifetime when a request doesn't give one.
+    #[serde(default = "default_default_link_hours")]
+    pub default_link_hours: u32,
+    /// Longest link lifetime that may be requested.
+    #[serde(default = "default_max_link_hours")]
+    pub max_link_hours: u32,
+    /// Origins allowed to embed the portal in a frame, such as a CRM's
+    /// customer area. Empty forbids framing.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub frame_ancestors: Vec<String>,
+    /// IDs of the named local API keys allowed to issue links through the
+    /// portal API. Other keys, and the legacy bearer token, can't; empty
+    /// leaves only the bus and `lqos_overrides`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub link_api_keys: Vec<String>,
+}
+
+impl Default for SubscriberPortalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: default_listen(),
+            public_url: String::new(),
+            default_link_hours: default_default_link_hours(),
+            max_link_hours: default_max_link_hours(),
+            frame_ancestors: Vec::new(),
+            link_api_keys: Vec::new(),
+        }
+    }
+}
+
+impl SubscriberPortalConfig {
+    /// Link lifetime in hours for a request, clamped to `max_link_hours`.
+    pub fn link_hours(&self, requested: Option<u32>) -> u32 {
+        requested
+            .filter(|hours| *hours > 0)
+            .unwrap_or(self.default_link_hours)
+            .min(self.max_link_hours)
+    }
+
+    /// The subscriber-facing link for a token, if `public_url` is set. The
+    /// token travels in the fragment so it never reaches access logs or
+    /// `Referer` headers.
+    pub fn link_for(&self, token: &str) -> Option<String> {
+        let base = self.public_url.trim().trim_end_matches('/');
+        if base.is_empty() {
+            return None;
+        }
+        Some(format!("{base}/#t={token}"))
+    }
+
+    /// Validates the subscriber portal configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.listen.trim().is_empty() {
+            return Err(
+                "subscriber_portal.listen is required when the portal is enabled".to_string(),
+            );
+        }
+        let public_url = self.public_url.trim();
+        if !public_url.is_empty()
+            && !public_url.starts_with("http://")
+            && !public_url.starts_with("https://")
+        {
+            return Err("subscriber_portal.public_url must start with http:// or https://".into());
+        }
+        if self.default_link_hours == 0 || self.max_link_hours == 0 {
+            return Err(
+                "subscriber_portal.default_link_hours and max_link_hours must be > 0".to_string(),
+            );
+        }
+        if let Some(origin) = self.frame_ancestors.iter().find(|origin| {
+            origin.trim().is_empty()
+                || origin
+                    .chars()
+                    .any(|c| c.is_whitespace() || c == ';' || c == ',' || c == '\'')
+        }) {
+            return Err(format!(
+                "subscriber_portal.frame_ancestors entry '{origin}' is not an origin"
+            ));
+        }
+        if let Some(id) = self
+            .link_api_keys
+            .iter()
+            .find(|id| Uuid::parse_str(id).is_err())
+        {
+            return Err(format!(
+                "subscriber_portal.link_api_keys entry '{id}' is not a local API key ID"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_builds_links() {
+        let config: SubscriberPortalConfig = toml::from_str("").expect("empty section parses");
+        assert!(!config.enabled);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.listen, ":::9124");
+        assert_eq!(config.link_hours(None), 720);
+        assert_eq!(config.link_hours(Some(0)), 720);
+        assert_eq!(config.link_hours(Some(100_000)), 8_760);
+        assert_eq!(config.link_for("abc"), None);
+
+        let config: SubscriberPortalConfig = toml::from_str(
+            r#"
+            enabled = true
+            public_url = "https://status.example.net/"
+            frame_ancestors = ["https://crm.example.net"]
+            link_api_keys = ["00000000-0000-0000-0000-000000000003"]
+            "#,
+        )
+        .expect("section parses");
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.link_for("abc").as_deref(),
+            Some("https://status.example.net/#t=abc")
+        );
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        let enabled = SubscriberPortalConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(enabled.validate().is_ok());
+
+        let mut bad = enabled.clone();
+        bad.listen.clear();
+        assert!(bad.validate().is_err());
+
+        let mut bad = enabled.clone();
+        bad.public_url = "status.example.net".to_string();
+        assert!(bad.validate().is_err());
+
+        let mut bad = enabled.clone();
+        bad.max_link_hours = 0;
+        assert!(bad.validate().is_err());
+
+        let mut bad = enabled.clone();
+        bad.link_api_keys = vec!["CRM".to_string()];
+        assert!(bad.validate().is_err());
+
+        let mut bad = enabled;
+        bad.frame_ancestors = vec!["https://crm.example.net; script-src *".to_string()];
+        assert!(bad.validate().is_err());
+    }
+}
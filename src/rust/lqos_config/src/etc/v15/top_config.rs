@@ -190,6 +190,66 @@ pub struct Config {
     /// Network flows configuration
     pub flows: Option<super::flows::FlowConfig>,
 
+    /// On-disk archive of completed flows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow_archive: Option<super::flow_archive::FlowArchiveConfig>,
+
+    /// Named prefix/ASN groups for on-net, off-net and peering traffic accounting.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traffic_groups: Vec<super::traffic_groups::TrafficGroupConfig>,
+
+    /// Named SQM profiles selectable per circuit, site and TreeGuard state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sqm_profiles: Vec<super::sqm_profiles::SqmProfileConfig>,
+
+    /// Named intra-circuit priority classes (e.g. a VoIP lane) circuits can opt into.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority_classes: Vec<super::priority_classes::PriorityClassConfig>,
+
+    /// Revision history of `network.json`, `ShapedDevices.csv` and overrides.
+    #[serde(default)]
+    pub input_history: super::input_history::InputHistoryConfig,
+
+    /// Active/standby state replication with a peer shaper.
+    #[serde(default)]
+    pub ha: super::ha::HaConfig,
+
+    /// Combined view of other shapers polled over their fleet API.
+    #[serde(default)]
+    pub fleet: super::fleet::FleetConfig,
+
+    /// SNMP monitoring through an AgentX master agent.
+    #[serde(default)]
+    pub snmp: super::snmp::SnmpConfig,
+
+    /// DHCP lease ingestion for circuits and dynamic circuits.
+    #[serde(default)]
+    pub dhcp_leases: super::dhcp_leases::DhcpLeasesConfig,
+
+    /// What circuits under a `block` or `portal` access policy can reach.
+    #[serde(default)]
+    pub walled_garden: super::walled_garden::WalledGardenConfig,
+
+    /// Port-block classification of shared public addresses behind a CGNAT.
+    #[serde(default)]
+    pub cgnat: super::cgnat::CgnatConfig,
+
+    /// Per-circuit flood and abuse detection.
+    #[serde(default)]
+    pub flood_detection: super::flood_detection::FloodDetectionConfig,
+
+    /// DSCP re-marking policies applied in the TC egress path.
+    #[serde(default)]
+    pub dscp_remarking: super::dscp_remarking::DscpRemarkingConfig,
+
+    /// Mirroring of selected circuits to an analysis interface.
+    #[serde(default)]
+    pub traffic_mirror: super::traffic_mirror::TrafficMirrorConfig,
+
+    /// Read-only status portal for subscribers, reached through signed links.
+    #[serde(default)]
+    pub subscriber_portal: super::subscriber_portal::SubscriberPortalConfig,
+
     /// Integration Common Variables
     #[serde(default)]
     pub integration_common: super::integration_common::IntegrationConfig,
@@ -255,6 +315,10 @@ pub struct Config {
     #[serde(default)]
     pub treeguard: treeguard::TreeguardConfig,
 
+    /// Observed-traffic weighting for the top-level CPU queue planner.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traffic_planner: Option<super::traffic_planner::TrafficPlannerConfig>,
+
     /// Disable ICMP Ping Monitoring for Devices in the hosts view
     pub disable_icmp_ping: Option<bool>,
 
@@ -375,6 +439,64 @@ impl Config {
             stormguard.validate()?;
         }
         self.treeguard.validate()?;
+        if let Some(traffic_planner) = &self.traffic_planner {
+            traffic_planner.validate()?;
+        }
+        if let Some(flow_archive) = &self.flow_archive {
+            flow_archive.validate()?;
+        }
+        super::traffic_groups::validate_traffic_groups(&self.traffic_groups)?;
+        super::sqm_profiles::validate_sqm_profiles(&self.sqm_profiles)?;
+        for profile in [
+            &self.treeguard.circuits.cake_profile,
+            &self.treeguard.circuits.fq_codel_profile,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if self.sqm_profile(profile).is_none() {
+                return Err(format!(
+                    "treeguard.circuits references unknown sqm profile \"{profile}\""
+                ));
+            }
+        }
+        super::priority_classes::validate_priority_classes(&self.priority_classes)?;
+        for class in &self.priority_classes {
+            if !class.sqm.is_empty()
+                && !matches!(class.sqm.as_str(), "cake" | "fq_codel" | "fq_pie" | "none")
+                && self.sqm_profile(&class.sqm).is_none()
+            {
+                return Err(format!(
+                    "priority class \"{}\" references unknown sqm profile \"{}\"",
+                    class.name, class.sqm
+                ));
+            }
+        }
+        self.input_history.validate()?;
+        self.ha.validate()?;
+        self.fleet.validate()?;
+        self.snmp.validate()?;
+        self.dhcp_leases.validate()?;
+        self.walled_garden.validate()?;
+        self.cgnat.validate()?;
+        self.flood_detection.validate()?;
+        self.dscp_remarking.validate()?;
+        self.traffic_mirror.validate()?;
+        if self.traffic_mirror.enabled
+            && (self.traffic_mirror.interface == self.isp_interface()
+                || self.traffic_mirror.interface == self.internet_interface())
+        {
+            return Err("traffic_mirror.interface must not be a shaping interface".to_string());
+        }
+        self.subscriber_portal.validate()?;
+        if self.subscriber_portal.enabled
+            && self.subscriber_portal.listen.trim()
+                == self.webserver_listen.as_deref().unwrap_or(":::9123")
+        {
+            return Err(
+                "subscriber_portal.listen must differ from the Node Manager listener".to_string(),
+            );
+        }
         if let Some(dynamic_circuits) = &self.dynamic_circuits {
             dynamic_circuits.validate()?;
         }
@@ -471,12 +593,28 @@ impl Default for Config {
             packet_capture_time: 10,
             queue_check_period_ms: 1000,
             flows: None,
+            flow_archive: None,
+            traffic_groups: Vec::new(),
+            sqm_profiles: Vec::new(),
+            priority_classes: Vec::new(),
+            input_history: super::input_history::InputHistoryConfig::default(),
+            ha: super::ha::HaConfig::default(),
+            fleet: super::fleet::FleetConfig::default(),
+            snmp: super::snmp::SnmpConfig::default(),
+            dhcp_leases: super::dhcp_leases::DhcpLeasesConfig::default(),
+            walled_garden: super::walled_garden::WalledGardenConfig::default(),
+            cgnat: super::cgnat::CgnatConfig::default(),
+            flood_detection: super::flood_detection::FloodDetectionConfig::default(),
+            dscp_remarking: super::dscp_remarking::DscpRemarkingConfig::default(),
+            traffic_mirror: super::traffic_mirror::TrafficMirrorConfig::default(),
+            subscriber_portal: super::subscriber_portal::SubscriberPortalConfig::default(),
             disable_webserver: None,
             webserver_listen: None,
             ssl: None,
             display_cobrand: false,
             stormguard: None,
             treeguard: treeguard::TreeguardConfig::default(),
+            traffic_planner: None,
             disable_icmp_ping: Some(false),
             exclude_efficiency_cores: true,
             enable_circuit_heatmaps: true,
@@ -644,6 +782,23 @@ impl Config {
         }
     }
 
+    /// Looks up a named SQM profile.
+    pub fn sqm_profile(&self, name: &str) -> Option<&super::sqm_profiles::SqmProfileConfig> {
+        self.sqm_profiles
+            .iter()
+            .find(|profile| profile.name == name)
+    }
+
+    /// Looks up a named intra-circuit priority class.
+    pub fn priority_class(
+        &self,
+        name: &str,
+    ) -> Option<&super::priority_classes::PriorityClassConfig> {
+        self.priority_classes
+            .iter()
+            .find(|class| class.name == name)
+    }
+
     /// Are we in single-interface mode?
     pub fn on_a_stick_mode(&self) -> bool {
         self.bridge.is_none()
@@ -0,0 +1,118 @@
+//! Named prefix/ASN groups used for on-net, off-net and peering traffic accounting.
+
+use allocative::Allocative;
+use ip_network::IpNetwork;
+use serde::{Deserialize, Serialize};
+
+/// Name of the implicit group that collects traffic matching no configured group.
+pub const TRAFFIC_GROUP_OTHER: &str = "Other";
+
+/// A named set of remote prefixes and ASNs (e.g. "Netflix OCA", "IX peers", "on-net").
+///
+/// Flow remote addresses are matched against prefixes first (longest match
+/// wins), then against the remote ASN.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+pub struct TrafficGroupConfig {
+    /// Display name of the group.
+    pub name: String,
+    /// CIDR prefixes belonging to the group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prefixes: Vec<String>,
+    /// Autonomous system numbers belonging to the group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub asns: Vec<u32>,
+}
+
+impl TrafficGroupConfig {
+    /// Validates a single traffic group.
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("traffic_groups.name must not be empty".to_string());
+        }
+        if name.eq_ignore_ascii_case(TRAFFIC_GROUP_OTHER) {
+            return Err(format!(
+                "traffic_groups.name \"{TRAFFIC_GROUP_OTHER}\" is reserved for unmatched traffic"
+            ));
+        }
+        if self.prefixes.is_empty() && self.asns.is_empty() {
+            return Err(format!(
+                "traffic group \"{name}\" must list at least one prefix or ASN"
+            ));
+        }
+        for prefix in &self.prefixes {
+            if prefix.trim().parse::<IpNetwork>().is_err() {
+                return Err(format!(
+                    "traffic group \"{name}\" has an invalid prefix \"{prefix}\""
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validates a list of traffic groups, including name uniqueness.
+pub fn validate_traffic_groups(groups: &[TrafficGroupConfig]) -> Result<(), String> {
+    for (index, group) in groups.iter().enumerate() {
+        group.validate()?;
+        if groups[..index]
+            .iter()
+            .any(|other| other.name.trim() == group.name.trim())
+        {
+            return Err(format!(
+                "traffic group \"{}\" is defined more than once",
+                group.name.trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str, prefixes: &[&str], asns: &[u32]) -> TrafficGroupConfig {
+        TrafficGroupConfig {
+            name: name.to_string(),
+            prefixes: prefixes.iter().map(|p| p.to_string()).collect(),
+            asns: asns.to_vec(),
+        }
+    }
+
+    #[test]
+    fn parses_array_of_tables() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            traffic_groups: Vec<TrafficGroupConfig>,
+        }
+        let wrapper: Wrapper = toml::from_str(
+            r#"
+            [[traffic_groups]]
+            name = "On-net"
+            prefixes = ["100.64.0.0/10", "2001:db8::/32"]
+
+            [[traffic_groups]]
+            name = "Netflix OCA"
+            asns = [2906]
+            "#,
+        )
+        .expect("traffic groups parse");
+        assert_eq!(wrapper.traffic_groups.len(), 2);
+        assert!(validate_traffic_groups(&wrapper.traffic_groups).is_ok());
+    }
+
+    #[test]
+    fn invalid_groups_are_rejected() {
+        assert!(validate_traffic_groups(&[group("Peers", &["not-a-prefix"], &[])]).is_err());
+        assert!(validate_traffic_groups(&[group("Peers", &[], &[])]).is_err());
+        assert!(validate_traffic_groups(&[group("other", &[], &[64500])]).is_err());
+        assert!(
+            validate_traffic_groups(&[
+                group("Peers", &[], &[64500]),
+                group("Peers", &[], &[64501]),
+            ])
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Traffic mirroring to an analysis interface.
+//!
+//! The TC egress path can clone the packets of selected circuits, addresses
+//! or prefixes to a spare interface for an IDS or lawful-intercept capture,
+//! without a span port upstream. Mirrored copies can be truncated and
+//! wrapped in a VLAN tag or a GRE (transparent Ethernet) tunnel. Sessions
+//! are started on demand through the bus or local API and always expire.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+
+/// Most mirror sessions that can run at once.
+pub const MAX_MIRROR_SESSIONS: usize = 16;
+/// Most addresses and prefixes mirrored at once, across all sessions.
+pub const MAX_MIRROR_PREFIXES: usize = 1_024;
+/// Smallest non-zero `snaplen`, enough for the headers an IDS needs.
+pub const MIN_MIRROR_SNAPLEN: u32 = 64;
+
+fn default_default_minutes() -> u32 {
+    30
+}
+
+fn default_max_minutes() -> u32 {
+    240
+}
+
+/// How mirrored copies leave the mirror interface.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Allocative)]
+#[serde(rename_all = "snake_case")]
+pub enum MirrorEncapsulation {
+    /// Copies are sent unchanged.
+    #[default]
+    None,
+    /// Copies carry an extra 802.1Q tag of `vlan_id`.
+    Vlan,
+    /// Copies are wrapped in GRE (transparent Ethernet bridging) from
+    /// `gre_local` to `gre_remote`.
+    Gre,
+}
+
+/// The `[traffic_mirror]` section.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Allocative)]
+#[serde(default)]
+pub struct TrafficMirrorConfig {
+    /// Allow mirror sessions and attach the encapsulation program.
+    pub enabled: bool,
+    /// Interface that receives mirrored copies. It must not be a shaping
+    /// interface.
+    pub interface: String,
+    /// Encapsulation of mirrored copies.
+    pub encapsulation: MirrorEncapsulation,
+    /// 802.1Q tag for `vlan` encapsulation.
+    pub vlan_id: u16,
+    /// Outer source IPv4 address for `gre` encapsulation.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub gre_local: String,
+    /// Outer destination IPv4 address (the collector) for `gre`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub gre_remote: String,
+    /// MAC address of the collector or next-hop router for `gre`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub gre_next_hop_mac: String,
+    /// Bytes kept from each packet, or 0 for whole packets.
+    pub snaplen: u32,
+    /// Session length when a request doesn't give one.
+    #[serde(default = "default_default_minutes")]
+    pub default_minutes: u32,
+    /// Longest session that may be requested.
+    #[serde(default = "default_max_minutes")]
+    pub max_minutes: u32,
+}
+
+impl Default for TrafficMirrorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: String::new(),
+            encapsulation: MirrorEncapsulation::None,
+            vlan_id: 0,
+            gre_local: String::new(),
+            gre_remote: String::new(),
+            gre_next_hop_mac: String::new(),
+            snaplen: 0,
+            default_minutes: default_default_minutes(),
+            max_minutes: default_max_minutes(),
+        }
+    }
+}
+
+/// Parses a colon- or dash-separated MAC address.
+pub fn parse_mac_address(mac: &str) -> Result<[u8; 6], String> {
+    let parts: Vec<&str> = mac.trim().split([':', '-']).collect();
+    let mut result = [0u8; 6];
+    if parts.len() != result.len() {
+        return Err(format!("'{mac}' is not a MAC address"));
+    }
+    for (byte, part) in result.iter_mut().zip(parts) {
+        *byte =
+            u8::from_str_radix(part, 16).map_err(|_| format!("'{mac}' is not a MAC address"))?;
+    }
+    Ok(result)
+}
+
+/// Parses a mirror target: an IP address or a prefix in CIDR notation.
+pub fn parse_mirror_prefix(entry: &str) -> Result<(IpAddr, u8), String> {
+    super::priority_classes::parse_prefix(entry)
+}
+
+impl TrafficMirrorConfig {
+    /// Session length in minutes for a request, clamped to `max_minutes`.
+    pub fn session_minutes(&self, requested: Option<u32>) -> u32 {
+        requested
+            .filter(|minutes| *minutes > 0)
+            .unwrap_or(self.default_minutes)
+            .min(self.max_minutes)
+    }
+
+    /// Outer source, destination and next-hop MAC for GRE encapsulation.
+    pub fn gre_endpoints(&self) -> Result<(Ipv4Addr, Ipv4Addr, [u8; 6]), String> {
+        let local = self
+            .gre_local
+            .trim()
+            .parse::<Ipv4Addr>()
+            .map_err(|_| "traffic_mirror.gre_local must be an IPv4 address".to_string())?;
+        let remote = self
+            .gre_remote
+            .trim()
+            .parse::<Ipv4Addr>()
+            .map_err(|_| "traffic_mirror.gre_remote must be an IPv4 address".to_string())?;
+        let mac = parse_mac_address(&self.gre_next_hop_mac)
+            .map_err(|e| format!("traffic_mirror.gre_next_hop_mac: {e}"))?;
+        Ok((local, remote, mac))
+    }
+
+    /// Validates the traffic mirror configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.interface.trim().is_empty() {
+            return Err(
+                "traffic_mirror.interface is required when mirroring is enabled".to_string(),
+            );
+        }
+        if self.snaplen != 0 && self.snaplen < MIN_MIRROR_SNAPLEN {
+            return Err(format!(
+                "traffic_mirror.snaplen must be 0 or at least {MIN_MIRROR_SNAPLEN}"
+            ));
+        }
+        if self.default_minutes == 0 || self.max_minutes == 0 {
+            return Err("traffic_mirror.default_minutes and max_minutes must be > 0".to_string());
+        }
+        match self.encapsulation {
+            MirrorEncapsulation::None => {}
+            MirrorEncapsulation::Vlan => {
+                if !(1..=4094).contains(&self.vlan_id) {
+                    return Err("traffic_mirror.vlan_id must be between 1 and 4094".to_string());
+                }
+            }
+            MirrorEncapsulation::Gre => {
+                self.gre_endpoints()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_the_section() {
+        let config: TrafficMirrorConfig = toml::from_str("").expect("empty section parses");
+        assert!(!config.enabled);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.session_minutes(None), 30);
+        assert_eq!(config.session_minutes(Some(10_000)), 240);
+
+        let config: TrafficMirrorConfig = toml::from_str(
+            r#"
+            enabled = true
+            interface = "eth9"
+            encapsulation = "gre"
+            gre_local = "192.0.2.1"
+            gre_remote = "192.0.2.50"
+            gre_next_hop_mac = "02:00:5e:10:00:01"
+            snaplen = 128
+            "#,
+        )
+        .expect("gre section parses");
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.gre_endpoints(),
+            Ok((
+                Ipv4Addr::new(192, 0, 2, 1),
+                Ipv4Addr::new(192, 0, 2, 50),
+                [0x02, 0x00, 0x5e, 0x10, 0x00, 0x01]
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_incomplete_settings() {
+        let enabled = TrafficMirrorConfig {
+            enabled: true,
+            interface: "eth9".to_string(),
+            ..Default::default()
+        };
+        assert!(enabled.validate().is_ok());
+
+        let mut bad = enabled.clone();
+        bad.interface.clear();
+        assert!(bad.validate().is_err());
+
+        let mut bad = enabled.clone();
+        bad.snaplen = 20;
+        assert!(bad.validate().is_err());
+
+        let mut bad = enabled.clone();
+        bad.encapsulation = MirrorEncapsulation::Vlan;
+        assert!(bad.validate().is_err());
+        bad.vlan_id = 300;
+        assert!(bad.validate().is_ok());
+
+        let mut bad = enabled;
+        bad.encapsulation = MirrorEncapsulation::Gre;
+        bad.gre_local = "192.0.2.1".to_string();
+        bad.gre_remote = "2001:db8::1".to_string();
+        bad.gre_next_hop_mac = "02:00:5e:10:00:01".to_string();
+        assert!(bad.validate().is_err());
+
+        assert!(parse_mac_address("02-00-5e-10-00-01").is_ok());
+        assert!(parse_mac_address("02:00:5e:10:00").is_err());
+        assert!(parse_mac_address("zz:00:5e:10:00:01").is_err());
+    }
+}
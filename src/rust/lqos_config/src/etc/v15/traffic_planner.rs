@@ -0,0 +1,131 @@
+//! Traffic-weighted CPU queue planner configuration.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+
+fn default_false() -> bool {
+    false
+}
+
+fn default_window_hours() -> u32 {
+    168
+}
+
+fn default_percentile() -> f64 {
+    95.0
+}
+
+fn default_packet_cost_bits() -> f64 {
+    4000.0
+}
+
+fn default_min_samples() -> usize {
+    30
+}
+
+fn default_sample_interval_seconds() -> u64 {
+    60
+}
+
+fn default_numa_remote_penalty() -> f64 {
+    0.15
+}
+
+/// Settings for deriving top-level CPU planner weights from observed site traffic.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct TrafficPlannerConfig {
+    /// Enables observed-traffic weighting for top-level CPU queue planning.
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+    /// Rolling window of samples considered, in hours.
+    #[serde(default = "default_window_hours")]
+    pub window_hours: u32,
+    /// Percentile (0-100) of each site's load used as its weight.
+    #[serde(default = "default_percentile")]
+    pub percentile: f64,
+    /// Optional first UTC hour of the peak window, inclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_start_hour_utc: Option<u8>,
+    /// Optional last UTC hour of the peak window, exclusive. May wrap past midnight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_end_hour_utc: Option<u8>,
+    /// Bits charged per packet so packet-heavy sites weigh more than their byte rate alone.
+    #[serde(default = "default_packet_cost_bits")]
+    pub packet_cost_bits: f64,
+    /// Minimum in-window samples before a site's observed load replaces its configured weight.
+    #[serde(default = "default_min_samples")]
+    pub min_samples: usize,
+    /// How often `lqosd` samples top-level site throughput.
+    #[serde(default = "default_sample_interval_seconds")]
+    pub sample_interval_seconds: u64,
+    /// Extra cost applied to CPUs that are not local to the shaping NICs (0 disables).
+    #[serde(default = "default_numa_remote_penalty")]
+    pub numa_remote_penalty: f64,
+}
+
+impl Default for TrafficPlannerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_false(),
+            window_hours: default_window_hours(),
+            percentile: default_percentile(),
+            peak_start_hour_utc: None,
+            peak_end_hour_utc: None,
+            packet_cost_bits: default_packet_cost_bits(),
+            min_samples: default_min_samples(),
+            sample_interval_seconds: default_sample_interval_seconds(),
+            numa_remote_penalty: default_numa_remote_penalty(),
+        }
+    }
+}
+
+impl TrafficPlannerConfig {
+    /// Validates the traffic planner configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_hours == 0 {
+            return Err("traffic_planner.window_hours must be > 0".to_string());
+        }
+        if !self.percentile.is_finite() || !(0.0..=100.0).contains(&self.percentile) {
+            return Err("traffic_planner.percentile must be between 0 and 100".to_string());
+        }
+        if self.peak_start_hour_utc.is_some() != self.peak_end_hour_utc.is_some() {
+            return Err(
+                "traffic_planner.peak_start_hour_utc and peak_end_hour_utc must be set together"
+                    .to_string(),
+            );
+        }
+        if self.peak_start_hour_utc.is_some_and(|hour| hour > 23)
+            || self.peak_end_hour_utc.is_some_and(|hour| hour > 23)
+        {
+            return Err("traffic_planner peak hours must be between 0 and 23".to_string());
+        }
+        if !self.packet_cost_bits.is_finite() || self.packet_cost_bits < 0.0 {
+            return Err("traffic_planner.packet_cost_bits must be >= 0".to_string());
+        }
+        if self.sample_interval_seconds == 0 {
+            return Err("traffic_planner.sample_interval_seconds must be > 0".to_string());
+        }
+        if !self.numa_remote_penalty.is_finite() || self.numa_remote_penalty < 0.0 {
+            return Err("traffic_planner.numa_remote_penalty must be >= 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Returns the configured peak window as a `(start, end)` UTC hour pair.
+    pub fn peak_hours_utc(&self) -> Option<(u8, u8)> {
+        self.peak_start_hour_utc.zip(self.peak_end_hour_utc)
+    }
+
+    /// Builds planner weight options from this configuration, without bin cost multipliers.
+    pub fn weight_options(&self) -> crate::TrafficWeightOptions {
+        crate::TrafficWeightOptions {
+            window_seconds: f64::from(self.window_hours) * 3600.0,
+            percentile: self.percentile,
+            packet_cost_bits: self.packet_cost_bits,
+            peak_hours_utc: self.peak_hours_utc(),
+            min_samples: self.min_samples,
+            ..Default::default()
+        }
+    }
+}
@@ -242,6 +242,12 @@ pub struct TreeguardCircuitsConfig {
     /// Persist overrides to avoid scheduler fights.
     #[serde(default = "default_enabled")]
     pub persist_sqm_overrides: bool,
+    /// SQM profile applied instead of plain `cake` when TreeGuard restores full shaping.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cake_profile: Option<String>,
+    /// SQM profile applied instead of plain `fq_codel` when TreeGuard saves CPU.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fq_codel_profile: Option<String>,
 }
 
 impl Default for TreeguardCircuitsConfig {
@@ -259,6 +265,8 @@ impl Default for TreeguardCircuitsConfig {
             min_switch_dwell_minutes: default_min_switch_dwell_minutes(),
             max_switches_per_hour: default_max_switches_per_hour(),
             persist_sqm_overrides: default_enabled(),
+            cake_profile: None,
+            fq_codel_profile: None,
         }
     }
 }
@@ -0,0 +1,203 @@
+//! Walled garden for suspended circuits.
+//!
+//! A circuit with a non-open access policy may only reach the allowlisted
+//! prefixes (and DNS, if enabled). Everything else is dropped, or for the
+//! `portal` policy, plain HTTP is redirected to a "your account is suspended"
+//! page.
+
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_portal_port() -> u16 {
+    80
+}
+
+/// Per-circuit access policy enforced by the XDP dataplane.
+#[derive(
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Allocative,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitAccessPolicy {
+    /// Normal, unrestricted access.
+    #[default]
+    Open,
+    /// Only the walled garden is reachable; everything else is dropped.
+    Block,
+    /// Like `Block`, but HTTP is redirected to the portal.
+    Portal,
+}
+
+impl CircuitAccessPolicy {
+    /// Is this the default, unrestricted policy?
+    pub fn is_open(&self) -> bool {
+        *self == Self::Open
+    }
+
+    /// Name used in `ShapedDevices.csv`, overrides and the CLI.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Block => "block",
+            Self::Portal => "portal",
+        }
+    }
+
+    /// Value stored in the dataplane's `ip_hash_info.access_policy`.
+    pub fn dataplane_value(self) -> u32 {
+        match self {
+            Self::Open => 0,
+            Self::Block => 1,
+            Self::Portal => 2,
+        }
+    }
+}
+
+impl FromStr for CircuitAccessPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "open" => Ok(Self::Open),
+            "block" => Ok(Self::Block),
+            "portal" => Ok(Self::Portal),
+            other => Err(format!(
+                "unknown access policy '{other}' (expected open, block or portal)"
+            )),
+        }
+    }
+}
+
+/// What suspended circuits can still reach.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Allocative)]
+#[serde(default)]
+pub struct WalledGardenConfig {
+    /// Destinations suspended circuits may reach, e.g. the payment portal.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_prefixes: Vec<String>,
+    /// Allow DNS (port 53) to any resolver.
+    #[serde(default = "default_true")]
+    pub allow_dns: bool,
+    /// IPv4 address of the suspension page. Without it, `portal` behaves like `block`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub portal_ipv4: Option<String>,
+    /// TCP port the suspension page listens on.
+    #[serde(default = "default_portal_port")]
+    pub portal_port: u16,
+}
+
+impl Default for WalledGardenConfig {
+    fn default() -> Self {
+        Self {
+            allowed_prefixes: Vec::new(),
+            allow_dns: default_true(),
+            portal_ipv4: None,
+            portal_port: default_portal_port(),
+        }
+    }
+}
+
+impl WalledGardenConfig {
+    /// Validates the walled garden configuration.
+    pub fn validate(&self) -> Result<(), String> {
+        for entry in &self.allowed_prefixes {
+            super::priority_classes::parse_prefix(entry)
+                .map_err(|e| format!("walled_garden.allowed_prefixes: {e}"))?;
+        }
+        if let Some(portal) = &self.portal_ipv4 {
+            portal.trim().parse::<Ipv4Addr>().map_err(|_| {
+                format!("walled_garden.portal_ipv4 '{portal}' is not an IPv4 address")
+            })?;
+        }
+        if self.portal_port == 0 {
+            return Err("walled_garden.portal_port must be > 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// The allowlisted prefixes, skipping any that fail to parse.
+    pub fn allowed_networks(&self) -> Vec<(IpAddr, u8)> {
+        self.allowed_prefixes
+            .iter()
+            .filter_map(|entry| super::priority_classes::parse_prefix(entry).ok())
+            .collect()
+    }
+
+    /// The portal address and port, if a portal is configured.
+    pub fn portal(&self) -> Option<(Ipv4Addr, u16)> {
+        let address = self.portal_ipv4.as_ref()?.trim().parse().ok()?;
+        Some((address, self.portal_port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_the_section() {
+        let config: WalledGardenConfig = toml::from_str("").expect("empty section parses");
+        assert!(config.allow_dns);
+        assert_eq!(config.portal(), None);
+        assert!(config.validate().is_ok());
+
+        let config: WalledGardenConfig = toml::from_str(
+            r#"
+            allowed_prefixes = ["203.0.113.10", "2001:db8::/48"]
+            portal_ipv4 = "203.0.113.10"
+            portal_port = 8080
+            "#,
+        )
+        .expect("section parses");
+        assert!(config.validate().is_ok());
+        assert_eq!(config.allowed_networks().len(), 2);
+        assert_eq!(config.allowed_networks()[0].1, 32);
+        assert_eq!(
+            config.portal(),
+            Some((Ipv4Addr::new(203, 0, 113, 10), 8080))
+        );
+
+        let bad = WalledGardenConfig {
+            portal_ipv4: Some("2001:db8::1".to_string()),
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+        let bad = WalledGardenConfig {
+            allowed_prefixes: vec!["198.51.100.0/40".to_string()],
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn access_policy_names_round_trip() {
+        for policy in [
+            CircuitAccessPolicy::Open,
+            CircuitAccessPolicy::Block,
+            CircuitAccessPolicy::Portal,
+        ] {
+            assert_eq!(policy.as_str().parse::<CircuitAccessPolicy>(), Ok(policy));
+        }
+        assert_eq!(
+            "".parse::<CircuitAccessPolicy>(),
+            Ok(CircuitAccessPolicy::Open)
+        );
+        assert!("suspended".parse::<CircuitAccessPolicy>().is_err());
+    }
+}
@@ -0,0 +1,753 @@
+//! Content-addressed revision history for shaping inputs.
+//!
+//! Every time `network.json`, `ShapedDevices.csv` or the operator overrides
+//! file changes, `lqosd` records a revision: the content of each tracked
+//! file is stored once under `objects/<sha256>` and the revision manifest
+//! lists which object each file pointed at, when, and who made the change.
+//! Revisions can be diffed against each other and restored.
+//!
+//! Writers that live outside `lqosd` (integrations, the overrides CLI) call
+//! [`mark_input_change_source`] before writing so that the next revision is
+//! attributed to them rather than to an anonymous external edit.
+//!
+//! The StormGuard and TreeGuard override layers are not tracked. They are
+//! rewritten by running actors many times a day, so they would crowd operator
+//! changes out of the retention window, and restoring them would fight the
+//! actors that own them.
+
+use crate::Config;
+use crate::hex_encoding::encode_hex_lower;
+use allocative::Allocative;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Operator and integration input files tracked by the revision history,
+/// relative to `lqos_directory`.
+pub const INPUT_HISTORY_TRACKED_FILES: [&str; 3] =
+    ["network.json", "ShapedDevices.csv", "lqos_overrides.json"];
+
+/// Source recorded when a change was not announced by any known writer.
+pub const INPUT_HISTORY_EXTERNAL_SOURCE: &str = "external edit";
+
+const MANIFEST_FILENAME: &str = "revisions.json";
+const PENDING_SOURCE_FILENAME: &str = "pending_source.json";
+const OBJECTS_DIRECTORY: &str = "objects";
+const PENDING_SOURCE_TTL_SECONDS: u64 = 600;
+
+/// Serializes manifest updates made from within one process.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Errors returned by the input revision history.
+#[derive(Debug, Error)]
+pub enum InputHistoryError {
+    /// The revision history is disabled in `/etc/lqos.conf`.
+    #[error("Input history is disabled")]
+    Disabled,
+    /// Reading or writing the store failed.
+    #[error("Unable to access input history: {0}")]
+    Io(#[from] std::io::Error),
+    /// Serializing or deserializing the manifest failed.
+    #[error("Unable to parse input history JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// No revision with this id is retained.
+    #[error("Input revision {0} does not exist")]
+    UnknownRevision(u64),
+    /// A revision references a content object that is no longer on disk.
+    #[error("Input history object {0} is missing")]
+    MissingObject(String),
+}
+
+/// One recorded state of the tracked input files.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct InputRevision {
+    /// Monotonic revision number.
+    pub id: u64,
+    /// Unix time (seconds) the revision was recorded.
+    pub created_unix: u64,
+    /// Who changed the inputs, e.g. `uisp integration` or `external edit`.
+    pub source: String,
+    /// Tracked files that existed at this revision, mapped to their content hash.
+    pub files: BTreeMap<String, String>,
+    /// Tracked files that differ from the previous revision.
+    #[serde(default)]
+    pub changed_files: Vec<String>,
+}
+
+/// Differences between two revisions.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Allocative)]
+pub struct InputRevisionDiff {
+    /// Older revision id.
+    pub from: u64,
+    /// Newer revision id.
+    pub to: u64,
+    /// Tracked files whose content differs.
+    pub changed_files: Vec<String>,
+    /// `network.json` nodes present only in `to`.
+    pub nodes_added: Vec<String>,
+    /// `network.json` nodes present only in `from`.
+    pub nodes_removed: Vec<String>,
+    /// `network.json` nodes whose own settings differ.
+    pub nodes_changed: Vec<String>,
+    /// Circuit IDs present only in `to`.
+    pub circuits_added: Vec<String>,
+    /// Circuit IDs present only in `from`.
+    pub circuits_removed: Vec<String>,
+    /// Circuit IDs whose device rows differ.
+    pub circuits_changed: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct InputHistoryManifest {
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    revisions: Vec<InputRevision>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingSource {
+    source: String,
+    marked_unix: u64,
+}
+
+/// Returns the directory holding the revision store.
+///
+/// This function is pure: it has no side effects.
+pub fn input_history_directory(config: &Config) -> PathBuf {
+    config
+        .input_history
+        .resolved_directory(&config.resolved_state_directory())
+}
+
+/// Returns the tracked file names with their on-disk paths.
+///
+/// This function is pure: it has no side effects.
+pub fn input_history_tracked_paths(config: &Config) -> Vec<(&'static str, PathBuf)> {
+    INPUT_HISTORY_TRACKED_FILES
+        .iter()
+        .map(|name| (*name, Path::new(&config.lqos_directory).join(name)))
+        .collect()
+}
+
+/// Attributes the next recorded revision to `source`.
+///
+/// The mark is used by the next revision recorded within ten minutes and is
+/// discarded after that.
+///
+/// Side effects: writes `pending_source.json` in the store directory.
+pub fn mark_input_change_source(config: &Config, source: &str) -> Result<(), InputHistoryError> {
+    if !config.input_history.enabled {
+        return Ok(());
+    }
+    let pending = PendingSource {
+        source: source.to_string(),
+        marked_unix: now_unix(),
+    };
+    atomic_write(
+        &input_history_directory(config).join(PENDING_SOURCE_FILENAME),
+        serde_json::to_string_pretty(&pending)?.as_bytes(),
+    )
+}
+
+/// Records a revision if any tracked file differs from the newest revision.
+///
+/// The revision is attributed to `source`, else to a fresh pending mark, else
+/// to an external edit. Retention limits are applied afterwards and content
+/// no longer referenced by any revision is deleted. Returns `None` when
+/// nothing changed or the history is disabled.
+///
+/// Side effects: reads the tracked files and writes to the store directory.
+pub fn record_input_snapshot(
+    config: &Config,
+    source: Option<&str>,
+) -> Result<Option<InputRevision>, InputHistoryError> {
+    if !config.input_history.enabled {
+        return Ok(None);
+    }
+    let _guard = STORE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    record_snapshot_locked(config, source, now_unix())
+}
+
+/// Lists retained revisions, newest first.
+///
+/// Side effects: reads the manifest from disk.
+pub fn list_input_revisions(config: &Config) -> Result<Vec<InputRevision>, InputHistoryError> {
+    let mut revisions = load_manifest(&input_history_directory(config))?.revisions;
+    revisions.reverse();
+    Ok(revisions)
+}
+
+/// Compares two retained revisions.
+///
+/// Side effects: reads the manifest and content objects from disk.
+pub fn diff_input_revisions(
+    config: &Config,
+    from: u64,
+    to: u64,
+) -> Result<InputRevisionDiff, InputHistoryError> {
+    let directory = input_history_directory(config);
+    let manifest = load_manifest(&directory)?;
+    let from_revision = find_revision(&manifest, from)?;
+    let to_revision = find_revision(&manifest, to)?;
+
+    let mut diff = InputRevisionDiff {
+        from,
+        to,
+        changed_files: changed_files(&from_revision.files, &to_revision.files),
+        ..InputRevisionDiff::default()
+    };
+
+    if diff.changed_files.iter().any(|file| file == "network.json") {
+        let before = flatten_network_nodes(&read_revision_file(
+            &directory,
+            from_revision,
+            "network.json",
+        )?);
+        let after = flatten_network_nodes(&read_revision_file(
+            &directory,
+            to_revision,
+            "network.json",
+        )?);
+        (diff.nodes_added, diff.nodes_removed, diff.nodes_changed) = compare_maps(&before, &after);
+    }
+    if diff
+        .changed_files
+        .iter()
+        .any(|file| file == "ShapedDevices.csv")
+    {
+        let before = group_circuit_rows(&read_revision_file(
+            &directory,
+            from_revision,
+            "ShapedDevices.csv",
+        )?);
+        let after = group_circuit_rows(&read_revision_file(
+            &directory,
+            to_revision,
+            "ShapedDevices.csv",
+        )?);
+        (
+            diff.circuits_added,
+            diff.circuits_removed,
+            diff.circuits_changed,
+        ) = compare_maps(&before, &after);
+    }
+    Ok(diff)
+}
+
+/// Restores the tracked files to revision `id`.
+///
+/// The current files are recorded first so the state being replaced can be
+/// restored in turn. Tracked files that did not exist at `id` are removed.
+/// The restore itself is recorded as a new revision, which is returned. The
+/// caller is responsible for reloading LibreQoS.
+///
+/// Side effects: overwrites or removes files in `lqos_directory` and writes to
+/// the store directory.
+pub fn restore_input_revision(
+    config: &Config,
+    id: u64,
+) -> Result<InputRevision, InputHistoryError> {
+    if !config.input_history.enabled {
+        return Err(InputHistoryError::Disabled);
+    }
+    let _guard = STORE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let now = now_unix();
+    record_snapshot_locked(config, None, now)?;
+
+    let directory = input_history_directory(config);
+    let manifest = load_manifest(&directory)?;
+    let revision = find_revision(&manifest, id)?;
+    let mut contents = Vec::new();
+    for (name, path) in input_history_tracked_paths(config) {
+        let content = match revision.files.get(name) {
+            Some(hash) => Some(read_object(&directory, hash)?),
+            None => None,
+        };
+        contents.push((path, content));
+    }
+    for (path, content) in contents {
+        match content {
+            Some(content) => atomic_write(&path, &content)?,
+            None if path.exists() => std::fs::remove_file(&path)?,
+            None => {}
+        }
+    }
+
+    let source = format!("rollback to revision {id}");
+    match record_snapshot_locked(config, Some(&source), now)? {
+        Some(restored) => Ok(restored),
+        // The files already matched `id`; report the newest revision instead.
+        None => load_manifest(&directory)?
+            .revisions
+            .pop()
+            .ok_or(InputHistoryError::UnknownRevision(id)),
+    }
+}
+
+fn record_snapshot_locked(
+    config: &Config,
+    source: Option<&str>,
+    now: u64,
+) -> Result<Option<InputRevision>, InputHistoryError> {
+    let directory = input_history_directory(config);
+    let mut manifest = load_manifest(&directory)?;
+
+    let mut files = BTreeMap::new();
+    for (name, path) in input_history_tracked_paths(config) {
+        let content = match std::fs::read(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let hash = encode_hex_lower(Sha256::digest(&content));
+        let object_path = directory.join(OBJECTS_DIRECTORY).join(&hash);
+        if !object_path.exists() {
+            atomic_write(&object_path, &content)?;
+        }
+        files.insert(name.to_string(), hash);
+    }
+
+    // Only tracked names are compared, so entries left by older releases that
+    // tracked more files do not count as a change.
+    let previous = manifest.revisions.last();
+    let changed_files = changed_files(
+        previous
+            .map(|previous| &previous.files)
+            .unwrap_or(&BTreeMap::new()),
+        &files,
+    );
+    if previous.is_some() && changed_files.is_empty() {
+        discard_stale_pending_source(&directory, now);
+        return Ok(None);
+    }
+
+    let source = match source {
+        Some(source) => source.to_string(),
+        None => take_pending_source(&directory, now)
+            .unwrap_or_else(|| INPUT_HISTORY_EXTERNAL_SOURCE.to_string()),
+    };
+    let revision = InputRevision {
+        id: manifest.next_id.max(1),
+        created_unix: now,
+        source,
+        files,
+        changed_files,
+    };
+    manifest.next_id = revision.id + 1;
+    manifest.revisions.push(revision.clone());
+    apply_retention(&mut manifest, config, now);
+    save_manifest(&directory, &manifest)?;
+    remove_unreferenced_objects(&directory, &manifest)?;
+    Ok(Some(revision))
+}
+
+fn apply_retention(manifest: &mut InputHistoryManifest, config: &Config, now: u64) {
+    let newest = manifest.revisions.len().saturating_sub(1);
+    let cutoff = now.saturating_sub(u64::from(config.input_history.max_age_days) * 86_400);
+    let mut index = 0;
+    manifest.revisions.retain(|revision| {
+        let keep = index == newest || revision.created_unix >= cutoff;
+        index += 1;
+        keep
+    });
+    let max_revisions = config.input_history.max_revisions.max(1) as usize;
+    if manifest.revisions.len() > max_revisions {
+        let excess = manifest.revisions.len() - max_revisions;
+        manifest.revisions.drain(..excess);
+    }
+}
+
+fn remove_unreferenced_objects(
+    directory: &Path,
+    manifest: &InputHistoryManifest,
+) -> Result<(), InputHistoryError> {
+    let referenced: BTreeSet<&String> = manifest
+        .revisions
+        .iter()
+        .flat_map(|revision| revision.files.values())
+        .collect();
+    let objects = directory.join(OBJECTS_DIRECTORY);
+    let Ok(entries) = std::fs::read_dir(&objects) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !referenced.contains(&name) {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+fn take_pending_source(directory: &Path, now: u64) -> Option<String> {
+    let path = directory.join(PENDING_SOURCE_FILENAME);
+    let raw = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    let pending: PendingSource = serde_json::from_str(&raw).ok()?;
+    (now.saturating_sub(pending.marked_unix) <= PENDING_SOURCE_TTL_SECONDS)
+        .then_some(pending.source)
+}
+
+fn discard_stale_pending_source(directory: &Path, now: u64) {
+    let path = directory.join(PENDING_SOURCE_FILENAME);
+    let Ok(raw) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let stale = serde_json::from_str::<PendingSource>(&raw).map_or(true, |pending| {
+        now.saturating_sub(pending.marked_unix) > PENDING_SOURCE_TTL_SECONDS
+    });
+    if stale {
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+fn changed_files(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> Vec<String> {
+    INPUT_HISTORY_TRACKED_FILES
+        .iter()
+        .filter(|name| before.get(**name) != after.get(**name))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+fn find_revision(
+    manifest: &InputHistoryManifest,
+    id: u64,
+) -> Result<&InputRevision, InputHistoryError> {
+    manifest
+        .revisions
+        .iter()
+        .find(|revision| revision.id == id)
+        .ok_or(InputHistoryError::UnknownRevision(id))
+}
+
+fn read_revision_file(
+    directory: &Path,
+    revision: &InputRevision,
+    name: &str,
+) -> Result<Vec<u8>, InputHistoryError> {
+    match revision.files.get(name) {
+        Some(hash) => read_object(directory, hash),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn read_object(directory: &Path, hash: &str) -> Result<Vec<u8>, InputHistoryError> {
+    std::fs::read(directory.join(OBJECTS_DIRECTORY).join(hash))
+        .map_err(|_| InputHistoryError::MissingObject(hash.to_string()))
+}
+
+/// Maps every `network.json` node name to its own settings, without children.
+fn flatten_network_nodes(raw: &[u8]) -> BTreeMap<String, Value> {
+    fn walk(map: &serde_json::Map<String, Value>, out: &mut BTreeMap<String, Value>) {
+        for (name, node) in map {
+            let Value::Object(node) = node else {
+                continue;
+            };
+            let mut settings = node.clone();
+            if let Some(Value::Object(children)) = settings.remove("children") {
+                walk(&children, out);
+            }
+            out.insert(name.clone(), Value::Object(settings));
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    if let Ok(Value::Object(root)) = serde_json::from_slice::<Value>(raw) {
+        walk(&root, &mut out);
+    }
+    out
+}
+
+/// Groups `ShapedDevices.csv` rows by circuit ID, in a stable order.
+fn group_circuit_rows(raw: &[u8]) -> BTreeMap<String, Vec<Vec<String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(raw);
+    let circuit_column = reader
+        .headers()
+        .ok()
+        .and_then(|headers| {
+            headers.iter().position(|header| {
+                header
+                    .chars()
+                    .filter(|ch| ch.is_ascii_alphanumeric())
+                    .collect::<String>()
+                    .eq_ignore_ascii_case("circuitid")
+            })
+        })
+        .unwrap_or(0);
+
+    let mut circuits: BTreeMap<String, Vec<Vec<String>>> = BTreeMap::new();
+    for record in reader.records().flatten() {
+        let Some(circuit_id) = record.get(circuit_column) else {
+            continue;
+        };
+        circuits
+            .entry(circuit_id.to_string())
+            .or_default()
+            .push(record.iter().map(str::to_string).collect());
+    }
+    for rows in circuits.values_mut() {
+        rows.sort();
+    }
+    circuits
+}
+
+fn compare_maps<T: PartialEq>(
+    before: &BTreeMap<String, T>,
+    after: &BTreeMap<String, T>,
+) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let added = after
+        .keys()
+        .filter(|key| !before.contains_key(*key))
+        .cloned()
+        .collect();
+    let removed = before
+        .keys()
+        .filter(|key| !after.contains_key(*key))
+        .cloned()
+        .collect();
+    let changed = before
+        .iter()
+        .filter(|(key, value)| after.get(*key).is_some_and(|other| other != *value))
+        .map(|(key, _)| key.clone())
+        .collect();
+    (added, removed, changed)
+}
+
+fn load_manifest(directory: &Path) -> Result<InputHistoryManifest, InputHistoryError> {
+    let path = directory.join(MANIFEST_FILENAME);
+    if !path.exists() {
+        return Ok(InputHistoryManifest::default());
+    }
+    let raw = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&raw)?)
+}
+
+fn save_manifest(
+    directory: &Path,
+    manifest: &InputHistoryManifest,
+) -> Result<(), InputHistoryError> {
+    atomic_write(
+        &directory.join(MANIFEST_FILENAME),
+        serde_json::to_string_pretty(manifest)?.as_bytes(),
+    )
+}
+
+fn atomic_write(path: &Path, content: &[u8]) -> Result<(), InputHistoryError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+    let mut file = File::create(&temp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+fn now_unix() -> u64 {
+    lqos_utils::unix_time::unix_now().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn test_config(label: &str) -> Config {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock should be after UNIX_EPOCH")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("libreqos-input-history-{label}-{stamp}"));
+        fs::create_dir_all(root.join("src")).expect("test should create src dir");
+        Config {
+            lqos_directory: root.join("src").display().to_string(),
+            state_directory: Some(root.join("state").display().to_string()),
+            ..Config::default()
+        }
+    }
+
+    fn write_input(config: &Config, name: &str, content: &str) {
+        fs::write(Path::new(&config.lqos_directory).join(name), content)
+            .expect("test should write input file");
+    }
+
+    const HEADER: &str = "Circuit ID,Circuit Name,Device ID,Device Name,Parent Node,MAC,IPv4,IPv6,Download Min Mbps,Upload Min Mbps,Download Max Mbps,Upload Max Mbps,Comment\n";
+
+    #[test]
+    fn records_only_changes_and_attributes_pending_source() {
+        let config = test_config("record");
+        write_input(
+            &config,
+            "network.json",
+            r#"{"Site A":{"downloadBandwidthMbps":100}}"#,
+        );
+
+        let first = record_input_snapshot(&config, None)
+            .expect("record should succeed")
+            .expect("first snapshot should be recorded");
+        assert_eq!(first.source, INPUT_HISTORY_EXTERNAL_SOURCE);
+        assert!(
+            record_input_snapshot(&config, None)
+                .expect("record should succeed")
+                .is_none()
+        );
+
+        mark_input_change_source(&config, "uisp integration").expect("mark should succeed");
+        write_input(
+            &config,
+            "network.json",
+            r#"{"Site B":{"downloadBandwidthMbps":100}}"#,
+        );
+        let second = record_input_snapshot(&config, None)
+            .expect("record should succeed")
+            .expect("change should be recorded");
+        assert_eq!(second.source, "uisp integration");
+        assert_eq!(second.changed_files, vec!["network.json".to_string()]);
+        assert_eq!(list_input_revisions(&config).expect("list").len(), 2);
+    }
+
+    #[test]
+    fn diff_reports_nodes_and_circuits() {
+        let config = test_config("diff");
+        write_input(
+            &config,
+            "network.json",
+            r#"{"Site A":{"downloadBandwidthMbps":100,"children":{"AP 1":{"downloadBandwidthMbps":50}}}}"#,
+        );
+        write_input(
+            &config,
+            "ShapedDevices.csv",
+            &format!(
+                "{HEADER}1,One,d1,Dev1,AP 1,,10.0.0.1,,1,1,10,10,\n2,Two,d2,Dev2,AP 1,,10.0.0.2,,1,1,10,10,\n"
+            ),
+        );
+        let from = record_input_snapshot(&config, None)
+            .expect("record")
+            .expect("recorded");
+        write_input(
+            &config,
+            "network.json",
+            r#"{"Site A":{"downloadBandwidthMbps":200,"children":{"AP 2":{"downloadBandwidthMbps":50}}}}"#,
+        );
+        write_input(
+            &config,
+            "ShapedDevices.csv",
+            &format!(
+                "{HEADER}1,One,d1,Dev1,AP 2,,10.0.0.1,,1,1,10,10,\n3,Three,d3,Dev3,AP 2,,10.0.0.3,,1,1,10,10,\n"
+            ),
+        );
+        let to = record_input_snapshot(&config, None)
+            .expect("record")
+            .expect("recorded");
+
+        let diff = diff_input_revisions(&config, from.id, to.id).expect("diff");
+        assert_eq!(diff.nodes_added, vec!["AP 2".to_string()]);
+        assert_eq!(diff.nodes_removed, vec!["AP 1".to_string()]);
+        assert_eq!(diff.nodes_changed, vec!["Site A".to_string()]);
+        assert_eq!(diff.circuits_added, vec!["3".to_string()]);
+        assert_eq!(diff.circuits_removed, vec!["2".to_string()]);
+        assert_eq!(diff.circuits_changed, vec!["1".to_string()]);
+    }
+
+    #[test]
+    fn restore_rewrites_files_and_records_a_rollback() {
+        let config = test_config("restore");
+        write_input(&config, "network.json", "{}");
+        let original = record_input_snapshot(&config, None)
+            .expect("record")
+            .expect("recorded");
+        write_input(&config, "network.json", r#"{"Bad":{}}"#);
+        write_input(&config, "lqos_overrides.json", "{}");
+
+        let restored = restore_input_revision(&config, original.id).expect("restore");
+        assert_eq!(
+            restored.source,
+            format!("rollback to revision {}", original.id)
+        );
+        assert_eq!(restored.files, original.files);
+        let dir = Path::new(&config.lqos_directory);
+        assert_eq!(
+            fs::read_to_string(dir.join("network.json")).expect("network.json"),
+            "{}"
+        );
+        assert!(!dir.join("lqos_overrides.json").exists());
+        // The pre-rollback state was captured before it was replaced.
+        assert_eq!(list_input_revisions(&config).expect("list").len(), 3);
+    }
+
+    #[test]
+    fn automation_layers_are_neither_recorded_nor_restored() {
+        let config = test_config("automation");
+        write_input(&config, "network.json", "{}");
+        write_input(&config, "lqos_overrides.stormguard.json", "{}");
+        let original = record_input_snapshot(&config, None)
+            .expect("record")
+            .expect("recorded");
+        assert!(
+            !original
+                .files
+                .contains_key("lqos_overrides.stormguard.json")
+        );
+
+        write_input(&config, "lqos_overrides.stormguard.json", r#"{"x":1}"#);
+        write_input(&config, "lqos_overrides.treeguard.json", r#"{"y":1}"#);
+        assert!(
+            record_input_snapshot(&config, None)
+                .expect("record")
+                .is_none()
+        );
+
+        write_input(&config, "network.json", r#"{"Bad":{}}"#);
+        restore_input_revision(&config, original.id).expect("restore");
+        let dir = Path::new(&config.lqos_directory);
+        assert_eq!(
+            fs::read_to_string(dir.join("lqos_overrides.stormguard.json")).expect("stormguard"),
+            r#"{"x":1}"#
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("lqos_overrides.treeguard.json")).expect("treeguard"),
+            r#"{"y":1}"#
+        );
+    }
+
+    #[test]
+    fn retention_keeps_newest_and_drops_unreferenced_objects() {
+        let mut config = test_config("retention");
+        config.input_history.max_revisions = 2;
+        for content in ["{}", r#"{"A":{}}"#, r#"{"B":{}}"#] {
+            write_input(&config, "network.json", content);
+            record_input_snapshot(&config, None).expect("record");
+        }
+        let revisions = list_input_revisions(&config).expect("list");
+        assert_eq!(
+            revisions.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![3, 2]
+        );
+        let objects = fs::read_dir(input_history_directory(&config).join(OBJECTS_DIRECTORY))
+            .expect("objects dir")
+            .count();
+        assert_eq!(objects, 2);
+    }
+}
@@ -16,9 +16,11 @@ mod cpu_topology;
 mod etc;
 mod ethernet_port_limits;
 mod hex_encoding;
+mod input_history;
 mod mikrotik_ipv6_credentials;
 mod network_json;
 mod planner;
+mod planner_traffic_history;
 mod program_control;
 mod qoo_profiles;
 mod runtime_state_migration;
@@ -38,23 +40,42 @@ pub use circuit_ethernet_metadata::{
     EthernetCapTargetKind, circuit_ethernet_metadata_path,
 };
 pub use cpu_topology::{
-    CpuListParseError, ShapingCpuDetection, ShapingCpuSource, detect_shaping_cpus,
+    CpuListParseError, CpuLocality, ShapingCpuDetection, ShapingCpuSource, detect_cpu_locality,
+    detect_shaping_cpus, locality_cost_multipliers, shaping_queue_locality_costs,
 };
 pub use etc::{
-    BridgeConfig, Config, DynamicCircuitRangeRule, DynamicCircuitsConfig, LazyQueueMode,
-    LocalApiKeyConfig, MAX_LOCAL_API_KEYS, MikrotikIpv6Config, QueueMode, RadiusAccountingClient,
-    RadiusAccountingConfig, RadiusClientSource, RadiusDynamicCircuitApplicationConfig,
-    RadiusFallbackSpeedProfile, RadiusSharedSecretSource, RateProfileValidationError,
-    RttThresholds, SingleInterfaceConfig, SslConfig, StormguardConfig, StormguardStrategy,
-    TopologyConfig, TreeguardCircuitsConfig, TreeguardConfig, TreeguardCpuConfig, TreeguardCpuMode,
-    TreeguardLinksConfig, TreeguardQooConfig, Tunables, clear_cached_config, disable_xdp_bridge,
-    enable_long_term_stats, load_config, normalize_external_hostname,
-    treeguard_cpu_mode_migration_notice, update_config, validate_rate_profile_mbps,
+    BridgeConfig, CgnatConfig, CgnatDeterministicRule, CgnatPool, CgnatPortBlock,
+    CgnatPortBlockPool, CircuitAccessPolicy, Config, DhcpLeaseFileConfig, DhcpLeaseFileFormat,
+    DhcpLeasesConfig, DscpMarkRule, DscpMarkRuleConfig, DscpPolicyConfig, DscpPolicyDirections,
+    DscpPolicyMode, DscpRemarkingConfig, DynamicCircuitRangeRule, DynamicCircuitsConfig,
+    FleetConfig, FleetMemberConfig, FloodDetectionConfig, FloodMitigationMode, FlowArchiveConfig,
+    HaConfig, HaRoleMode, InputHistoryConfig, LazyQueueMode, LocalApiKeyConfig,
+    MAX_CGNAT_PORT_BLOCKS, MAX_CGNAT_PUBLIC_ADDRESSES, MAX_DSCP_ASN_GROUPS, MAX_DSCP_POLICIES,
+    MAX_DSCP_RULES_PER_POLICY, MAX_FLOOD_DISTINCT_REMOTES, MAX_LOCAL_API_KEYS, MAX_MIRROR_PREFIXES,
+    MAX_MIRROR_SESSIONS, MAX_PRIORITY_CLASSES_PER_CIRCUIT, MAX_PRIORITY_RULES_PER_CIRCUIT,
+    MIN_MIRROR_SNAPLEN, MikrotikIpv6Config, MirrorEncapsulation, PriorityClassConfig,
+    PriorityMatchRule, QueueMode, RadiusAccountingClient, RadiusAccountingConfig,
+    RadiusClientSource, RadiusDynamicCircuitApplicationConfig, RadiusFallbackSpeedProfile,
+    RadiusSharedSecretSource, RateProfileValidationError, RttThresholds, SingleInterfaceConfig,
+    SnmpConfig, SqmProfileConfig, SqmProfileRule, SslConfig, StormguardConfig, StormguardStrategy,
+    SubscriberPortalConfig, TRAFFIC_GROUP_OTHER, TopologyConfig, TopologyRoutingHealthConfig,
+    TopologyRoutingHealthPolicy, TopologyRoutingHealthSource, TrafficGroupConfig,
+    TrafficMirrorConfig, TrafficPlannerConfig, TreeguardCircuitsConfig, TreeguardConfig,
+    TreeguardCpuConfig, TreeguardCpuMode, TreeguardLinksConfig, TreeguardQooConfig, Tunables,
+    WalledGardenConfig, clear_cached_config, disable_xdp_bridge, enable_long_term_stats,
+    is_valid_profile_name, load_config, normalize_external_hostname, parse_dscp, parse_mac_address,
+    parse_mirror_prefix, treeguard_cpu_mode_migration_notice, update_config,
+    validate_rate_profile_mbps, validate_sqm_tokens,
 };
 pub use ethernet_port_limits::{
     DEFAULT_ETHERNET_PORT_LIMIT_MULTIPLIER, EthernetPortLimitPolicy, EthernetPortObservation,
     EthernetRateDecision, RequestedCircuitRates, apply_ethernet_rate_cap, usable_ethernet_cap_mbps,
 };
+pub use input_history::{
+    INPUT_HISTORY_EXTERNAL_SOURCE, INPUT_HISTORY_TRACKED_FILES, InputHistoryError, InputRevision,
+    InputRevisionDiff, diff_input_revisions, input_history_directory, input_history_tracked_paths,
+    list_input_revisions, mark_input_change_source, record_input_snapshot, restore_input_revision,
+};
 pub use mikrotik_ipv6_credentials::{
     MikrotikIpv6CredentialError, MikrotikIpv6CredentialsFile, MikrotikIpv6RouterCredential,
     load_mikrotik_ipv6_router_credentials, migrate_legacy_mikrotik_ipv6_credentials,
@@ -63,15 +84,21 @@ pub use network_json::{NetworkJson, NetworkJsonNode, NetworkJsonTransport};
 pub use planner::{
     CircuitIdentityAssignment, CircuitIdentityGroupInput, ClassIdentityPlannerConstraints,
     ClassIdentityPlannerOutput, PlannerCircuitIdentityState, PlannerMinorReservations,
-    PlannerSiteIdentityState, SiteIdentityAssignment, SiteIdentityInput, TopLevelPlannerItem,
-    TopLevelPlannerMode, TopLevelPlannerOutput, TopLevelPlannerParams,
-    build_class_identity_reservations, plan_class_identities,
+    PlannerSiteIdentityState, SiteIdentityAssignment, SiteIdentityInput, TopLevelBinProjection,
+    TopLevelPlannerItem, TopLevelPlannerMode, TopLevelPlannerOutput, TopLevelPlannerParams,
+    TopLevelTrafficSample, TrafficWeightOptions, TrafficWeightedPlannerOutput,
+    build_class_identity_reservations, derive_traffic_weights, plan_class_identities,
     plan_class_identities_with_constraints, plan_top_level_assignments,
+    plan_traffic_weighted_assignments,
+};
+pub use planner_traffic_history::{
+    PLANNER_TRAFFIC_HISTORY_FILENAME, PlannerTrafficHistoryError, PlannerTrafficHistoryFile,
+    load_planner_traffic_history, planner_traffic_history_path, store_planner_traffic_history,
 };
-pub use program_control::load_libreqos;
+pub use program_control::{load_libreqos, plan_libreqos};
 pub use qoo_profiles::{
     DEFAULT_QOO_PROFILE_ID, QooProfileInfo, QooProfilesError, active_qoo_profile,
-    list_qoo_profiles, load_qoo_profiles_file,
+    active_qoo_profile_id, list_qoo_profiles, load_qoo_profiles_file,
 };
 pub use shaped_devices::{ConfigShapedDevices, ShapedDevice};
 pub use topology_canonical_state::{
@@ -96,17 +123,17 @@ pub use topology_runtime_state::{
     TOPOLOGY_EFFECTIVE_NETWORK_FILENAME, TOPOLOGY_EFFECTIVE_STATE_FILENAME,
     TOPOLOGY_IMPORT_FILENAME, TOPOLOGY_RUNTIME_STATUS_FILENAME, TOPOLOGY_SHAPING_INPUTS_FILENAME,
     TopologyAttachmentEndpointStatus, TopologyAttachmentHealthEntry,
-    TopologyAttachmentHealthStateFile, TopologyEffectiveAttachmentState,
-    TopologyEffectiveNodeState, TopologyEffectiveStateFile, TopologyRuntimeShapingPayloadIdentity,
-    TopologyRuntimeStateError, TopologyRuntimeStatusFile, TopologyShapingCircuitInput,
-    TopologyShapingDeviceInput, TopologyShapingInputsFile, TopologyShapingResolutionSource,
-    active_runtime_shaping_inputs_path, active_runtime_shaping_inputs_path_from_status,
-    compute_effective_network_file_generation, compute_effective_network_generation,
-    compute_shaping_inputs_file_generation, compute_topology_source_generation,
-    load_active_runtime_shaping_inputs, load_active_runtime_shaping_inputs_from_status,
-    topology_attachment_health_state_path, topology_compiled_shaping_path,
-    topology_effective_network_path, topology_effective_state_path, topology_import_path,
-    topology_runtime_status_path, topology_shaping_inputs_path,
+    TopologyAttachmentHealthStateFile, TopologyAttachmentRoutingState,
+    TopologyEffectiveAttachmentState, TopologyEffectiveNodeState, TopologyEffectiveStateFile,
+    TopologyRuntimeShapingPayloadIdentity, TopologyRuntimeStateError, TopologyRuntimeStatusFile,
+    TopologyShapingCircuitInput, TopologyShapingDeviceInput, TopologyShapingInputsFile,
+    TopologyShapingResolutionSource, active_runtime_shaping_inputs_path,
+    active_runtime_shaping_inputs_path_from_status, compute_effective_network_file_generation,
+    compute_effective_network_generation, compute_shaping_inputs_file_generation,
+    compute_topology_source_generation, load_active_runtime_shaping_inputs,
+    load_active_runtime_shaping_inputs_from_status, topology_attachment_health_state_path,
+    topology_compiled_shaping_path, topology_effective_network_path, topology_effective_state_path,
+    topology_import_path, topology_runtime_status_path, topology_shaping_inputs_path,
     validated_runtime_shaping_inputs_path,
 };
 
@@ -3,7 +3,7 @@ mod network_json_transport;
 
 use allocative_derive::Allocative;
 use lqos_utils::{
-    qoo::{LossMeasurement, QoqScores, compute_qoq_scores},
+    qoo::{LossMeasurement, QooProfile, QoqScores, compute_qoq_scores},
     qoq_heatmap::TemporalQoqHeatmap,
     rtt::{FlowbeeEffectiveDirection, RttBucket, RttBuffer},
     temporal_heatmap::{TemporalHeatmap, executive_retransmit_percent},
@@ -16,6 +16,7 @@ use std::{
     collections::HashMap,
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 use thiserror::Error;
 use tracing::{debug, warn};
@@ -393,6 +394,17 @@ impl NetworkJson {
 
     /// Record a heatmap sample for each site based on the current per-cycle data.
     pub fn record_site_heatmaps(&mut self, enable: bool) {
+        let qoo_profile = crate::active_qoo_profile().ok();
+        self.record_site_heatmaps_with_profiles(enable, |_| qoo_profile.clone());
+    }
+
+    /// As `record_site_heatmaps`, but scores each node's QoO with the profile
+    /// `profile_for` returns for its name. Nodes without a profile get no QoO sample.
+    pub fn record_site_heatmaps_with_profiles(
+        &mut self,
+        enable: bool,
+        profile_for: impl Fn(&str) -> Option<Arc<QooProfile>>,
+    ) {
         if !enable {
             self.nodes.iter_mut().for_each(|node| {
                 node.heatmap = None;
@@ -401,8 +413,6 @@ impl NetworkJson {
             return;
         }
 
-        let qoo_profile = crate::active_qoo_profile().ok();
-
         for node in self.nodes.iter_mut() {
             let download_util =
                 utilization_percent_bytes(node.current_throughput.down, node.max_throughput.0)
@@ -455,7 +465,7 @@ impl NetworkJson {
                 node.current_tcp_retransmits.up,
                 node.current_tcp_retransmit_packets.up,
             );
-            let scores = if let Some(profile) = qoo_profile.as_ref() {
+            let scores = if let Some(profile) = profile_for(&node.name) {
                 compute_qoq_scores(
                     profile.as_ref(),
                     &node.rtt_buffer,
@@ -25,6 +25,12 @@ pub enum TopLevelPlannerMode {
     /// Greedy balancing that prefers to keep prior assignments unless a move is worthwhile.
     #[default]
     StableGreedy,
+    /// Stable greedy balancing over weights derived from observed traffic, with optional
+    /// per-bin locality costs.
+    ///
+    /// Use [`plan_traffic_weighted_assignments`] to derive the weights and bin costs; when
+    /// called through [`plan_top_level_assignments`] this behaves like `StableGreedy`.
+    TrafficWeighted,
 }
 
 /// Tunables for top-level queue planning.
@@ -62,6 +68,78 @@ pub struct TopLevelPlannerOutput {
     pub planner_used: bool,
 }
 
+/// One observed load sample for a top-level planner item.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TopLevelTrafficSample {
+    /// Planner item id the sample belongs to.
+    pub id: String,
+    /// Sample time in seconds since the UNIX epoch.
+    pub unix_ts: f64,
+    /// Combined download and upload throughput in bits per second.
+    pub bits_per_second: f64,
+    /// Combined download and upload packets per second.
+    pub packets_per_second: f64,
+}
+
+/// Tunables for deriving top-level planner weights from observed traffic.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TrafficWeightOptions {
+    /// Samples older than this many seconds are ignored.
+    pub window_seconds: f64,
+    /// Percentile (0-100) of each item's in-window load used as its weight.
+    pub percentile: f64,
+    /// Bits charged per packet, so packet-heavy sites weigh more than their byte rate alone.
+    pub packet_cost_bits: f64,
+    /// Optional `[start, end)` UTC hour range limiting which samples count. Wraps past midnight.
+    pub peak_hours_utc: Option<(u8, u8)>,
+    /// Minimum in-window samples before observed load replaces an item's configured weight.
+    pub min_samples: usize,
+    /// Per-bin cost multipliers, for example to penalize CPUs remote from the NIC's NUMA node.
+    /// Bins without an entry cost `1.0`.
+    pub bin_cost_multipliers: BTreeMap<String, f64>,
+}
+
+impl Default for TrafficWeightOptions {
+    fn default() -> Self {
+        Self {
+            window_seconds: 7.0 * 24.0 * 3600.0,
+            percentile: 95.0,
+            packet_cost_bits: 4000.0,
+            peak_hours_utc: None,
+            min_samples: 30,
+            bin_cost_multipliers: BTreeMap::new(),
+        }
+    }
+}
+
+/// Projected load for one bin before and after a planning pass.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TopLevelBinProjection {
+    /// Bin identifier.
+    pub bin: String,
+    /// Cost-adjusted load under the previous assignment. Items without one are not counted.
+    pub before: f64,
+    /// Cost-adjusted load under the planned assignment.
+    pub after: f64,
+    /// Number of items assigned to the bin under the previous assignment.
+    pub items_before: usize,
+    /// Number of items assigned to the bin under the planned assignment.
+    pub items_after: usize,
+}
+
+/// Result of a traffic-weighted top-level planning pass.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TrafficWeightedPlannerOutput {
+    /// Assignment result, subject to the usual hysteresis, cooldown and move budget.
+    pub plan: TopLevelPlannerOutput,
+    /// Weight used for each item.
+    pub weights: BTreeMap<String, f64>,
+    /// Item ids whose weight came from observed traffic rather than the scaled configured weight.
+    pub observed: Vec<String>,
+    /// Per-bin before/after load projection, in bin order.
+    pub projection: Vec<TopLevelBinProjection>,
+}
+
 /// Input describing a site that needs class identity assignment.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SiteIdentityInput {
@@ -354,7 +432,19 @@ fn round_robin_assign(items: &[TopLevelPlannerItem], bins: &[String]) -> BTreeMa
     assignment
 }
 
-fn greedy_assign(items: &[TopLevelPlannerItem], bins: &[String]) -> BTreeMap<String, String> {
+fn bin_cost(bin_costs: &BTreeMap<String, f64>, bin: &str) -> f64 {
+    bin_costs
+        .get(bin)
+        .copied()
+        .filter(|cost| cost.is_finite() && *cost > 0.0)
+        .unwrap_or(1.0)
+}
+
+fn greedy_assign(
+    items: &[TopLevelPlannerItem],
+    bins: &[String],
+    bin_costs: &BTreeMap<String, f64>,
+) -> BTreeMap<String, String> {
     let mut pairs: Vec<(&str, f64)> = items
         .iter()
         .map(|item| (item.id.as_str(), sanitize_weight(item.weight)))
@@ -370,8 +460,9 @@ fn greedy_assign(items: &[TopLevelPlannerItem], bins: &[String]) -> BTreeMap<Str
     for (id, weight) in pairs {
         let target = loads
             .iter()
+            .map(|(bin, load)| (bin, (load + weight) * bin_cost(bin_costs, bin)))
             .min_by(|a, b| {
-                a.1.partial_cmp(b.1)
+                a.1.partial_cmp(&b.1)
                     .unwrap_or(std::cmp::Ordering::Equal)
                     .then_with(|| a.0.cmp(b.0))
             })
@@ -411,6 +502,26 @@ pub fn plan_top_level_assignments(
     last_change_ts: &BTreeMap<String, f64>,
     now_ts: f64,
     params: &TopLevelPlannerParams,
+) -> TopLevelPlannerOutput {
+    plan_top_level_assignments_with_costs(
+        items,
+        bins,
+        prev_assign,
+        last_change_ts,
+        now_ts,
+        params,
+        &BTreeMap::new(),
+    )
+}
+
+fn plan_top_level_assignments_with_costs(
+    items: &[TopLevelPlannerItem],
+    bins: &[String],
+    prev_assign: &BTreeMap<String, String>,
+    last_change_ts: &BTreeMap<String, f64>,
+    now_ts: f64,
+    params: &TopLevelPlannerParams,
+    bin_costs: &BTreeMap<String, f64>,
 ) -> TopLevelPlannerOutput {
     if bins.is_empty() || items.is_empty() {
         return TopLevelPlannerOutput {
@@ -433,12 +544,12 @@ pub fn plan_top_level_assignments(
         .collect();
 
     let rr_assignment = round_robin_assign(items, bins);
-    let greedy_assignment = greedy_assign(items, bins);
+    let greedy_assignment = greedy_assign(items, bins, bin_costs);
 
     let (assignment, planner_used) = match params.mode {
         TopLevelPlannerMode::RoundRobin => (rr_assignment.clone(), false),
         TopLevelPlannerMode::Greedy => (greedy_assignment.clone(), false),
-        TopLevelPlannerMode::StableGreedy => {
+        TopLevelPlannerMode::StableGreedy | TopLevelPlannerMode::TrafficWeighted => {
             let mut assignment = rr_assignment.clone();
             for id in &item_ids {
                 let chosen = prev_valid
@@ -481,9 +592,13 @@ pub fn plan_top_level_assignments(
                 }
                 let current_load = loads.get(&current_bin).copied().unwrap_or(0.0);
                 let target_load = loads.get(&target_bin).copied().unwrap_or(0.0);
+                let current_cost = bin_cost(bin_costs, &current_bin);
+                let target_cost = bin_cost(bin_costs, &target_bin);
                 let weight = item_weights.get(id).copied().unwrap_or(1.0);
-                let imbalance_before = current_load - target_load;
-                let imbalance_after = ((current_load - weight) - (target_load + weight)).abs();
+                let imbalance_before = current_load * current_cost - target_load * target_cost;
+                let imbalance_after = ((current_load - weight) * current_cost
+                    - (target_load + weight) * target_cost)
+                    .abs();
                 if imbalance_before - imbalance_after <= min_improvement {
                     continue;
                 }
@@ -539,6 +654,177 @@ pub fn plan_top_level_assignments(
     }
 }
 
+fn sample_in_peak_hours(unix_ts: f64, peak_hours_utc: Option<(u8, u8)>) -> bool {
+    let Some((start, end)) = peak_hours_utc else {
+        return true;
+    };
+    let hour = ((unix_ts / 3600.0).floor() as i64).rem_euclid(24) as u8;
+    match start.cmp(&end) {
+        std::cmp::Ordering::Less => (start..end).contains(&hour),
+        std::cmp::Ordering::Greater => hour >= start || hour < end,
+        std::cmp::Ordering::Equal => true,
+    }
+}
+
+fn nearest_rank_percentile(values: &mut [f64], percentile: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let fraction = (percentile / 100.0).clamp(0.0, 1.0);
+    let rank = (fraction * values.len() as f64).ceil() as usize;
+    values.get(rank.saturating_sub(1)).copied()
+}
+
+/// Derives planner weights from observed traffic samples.
+///
+/// Each item with at least `min_samples` in-window samples is weighted by the configured
+/// percentile of its load, in Mbps, where packets are charged `packet_cost_bits` each. Items
+/// without enough samples keep their configured weight, scaled by the median observed/configured
+/// ratio so both kinds of weight stay comparable. Returns the reweighted items and the ids whose
+/// weight was observed.
+pub fn derive_traffic_weights(
+    items: &[TopLevelPlannerItem],
+    samples: &[TopLevelTrafficSample],
+    now_ts: f64,
+    options: &TrafficWeightOptions,
+) -> (Vec<TopLevelPlannerItem>, Vec<String>) {
+    let oldest = now_ts - options.window_seconds.max(0.0);
+    let mut loads: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for sample in samples {
+        if sample.unix_ts < oldest
+            || sample.unix_ts > now_ts
+            || !sample_in_peak_hours(sample.unix_ts, options.peak_hours_utc)
+        {
+            continue;
+        }
+        let bits = sample.bits_per_second.max(0.0)
+            + sample.packets_per_second.max(0.0) * options.packet_cost_bits.max(0.0);
+        if bits.is_finite() {
+            loads
+                .entry(sample.id.as_str())
+                .or_default()
+                .push(bits / 1_000_000.0);
+        }
+    }
+
+    let mut observed_weights = BTreeMap::new();
+    for item in items {
+        let Some(values) = loads.get_mut(item.id.as_str()) else {
+            continue;
+        };
+        if values.len() < options.min_samples.max(1) {
+            continue;
+        }
+        if let Some(weight) = nearest_rank_percentile(values, options.percentile) {
+            observed_weights.insert(item.id.clone(), weight);
+        }
+    }
+
+    let mut ratios: Vec<f64> = items
+        .iter()
+        .filter_map(|item| {
+            observed_weights
+                .get(&item.id)
+                .map(|observed| observed / sanitize_weight(item.weight))
+        })
+        .filter(|ratio| ratio.is_finite() && *ratio > 0.0)
+        .collect();
+    let fallback_scale = nearest_rank_percentile(&mut ratios, 50.0).unwrap_or(1.0);
+
+    let reweighted = items
+        .iter()
+        .map(|item| TopLevelPlannerItem {
+            id: item.id.clone(),
+            weight: observed_weights
+                .get(&item.id)
+                .copied()
+                .unwrap_or_else(|| sanitize_weight(item.weight) * fallback_scale),
+        })
+        .collect();
+    (reweighted, observed_weights.into_keys().collect())
+}
+
+fn project_bin_loads(
+    assignment: &BTreeMap<String, String>,
+    item_weights: &BTreeMap<String, f64>,
+    bins: &[String],
+    bin_costs: &BTreeMap<String, f64>,
+) -> BTreeMap<String, (f64, usize)> {
+    let mut loads: BTreeMap<String, (f64, usize)> =
+        bins.iter().cloned().map(|bin| (bin, (0.0, 0))).collect();
+    for (id, bin) in assignment {
+        if let Some(weight) = item_weights.get(id)
+            && let Some((load, count)) = loads.get_mut(bin)
+        {
+            *load += *weight * bin_cost(bin_costs, bin);
+            *count += 1;
+        }
+    }
+    loads
+}
+
+/// Plans top-level queue assignments using weights derived from observed traffic.
+///
+/// Weights come from [`derive_traffic_weights`]; assignment then follows the stable greedy rules
+/// in `params` (hysteresis, cooldown and move budget) with `options.bin_cost_multipliers` applied
+/// when comparing bin loads. The output includes a before/after per-bin projection. This function
+/// is pure and performs no I/O.
+#[allow(clippy::too_many_arguments)]
+pub fn plan_traffic_weighted_assignments(
+    items: &[TopLevelPlannerItem],
+    samples: &[TopLevelTrafficSample],
+    bins: &[String],
+    prev_assign: &BTreeMap<String, String>,
+    last_change_ts: &BTreeMap<String, f64>,
+    now_ts: f64,
+    params: &TopLevelPlannerParams,
+    options: &TrafficWeightOptions,
+) -> TrafficWeightedPlannerOutput {
+    let (weighted_items, observed) = derive_traffic_weights(items, samples, now_ts, options);
+    let plan = plan_top_level_assignments_with_costs(
+        &weighted_items,
+        bins,
+        prev_assign,
+        last_change_ts,
+        now_ts,
+        params,
+        &options.bin_cost_multipliers,
+    );
+    let weights: BTreeMap<String, f64> = weighted_items
+        .iter()
+        .map(|item| (item.id.clone(), sanitize_weight(item.weight)))
+        .collect();
+    let before = project_bin_loads(prev_assign, &weights, bins, &options.bin_cost_multipliers);
+    let after = project_bin_loads(
+        &plan.assignment,
+        &weights,
+        bins,
+        &options.bin_cost_multipliers,
+    );
+    let projection = bins
+        .iter()
+        .map(|bin| {
+            let (before_load, items_before) = before.get(bin).copied().unwrap_or_default();
+            let (after_load, items_after) = after.get(bin).copied().unwrap_or_default();
+            TopLevelBinProjection {
+                bin: bin.clone(),
+                before: before_load,
+                after: after_load,
+                items_before,
+                items_after,
+            }
+        })
+        .collect();
+
+    TrafficWeightedPlannerOutput {
+        plan,
+        weights,
+        observed,
+        projection,
+    }
+}
+
 /// Plans site and circuit class identities for a physical queue tree.
 ///
 /// This function is pure: it computes deterministic minor/class-major assignments from the
@@ -737,8 +1023,10 @@ mod tests {
     use super::{
         CircuitIdentityGroupInput, ClassIdentityPlannerConstraints, PlannerCircuitIdentityState,
         PlannerSiteIdentityState, SiteIdentityInput, TopLevelPlannerItem, TopLevelPlannerMode,
-        TopLevelPlannerParams, build_class_identity_reservations, plan_class_identities,
+        TopLevelPlannerParams, TopLevelTrafficSample, TrafficWeightOptions,
+        build_class_identity_reservations, derive_traffic_weights, plan_class_identities,
         plan_class_identities_with_constraints, plan_top_level_assignments,
+        plan_traffic_weighted_assignments,
     };
     use std::collections::BTreeMap;
 
@@ -948,6 +1236,129 @@ mod tests {
         );
     }
 
+    fn traffic_samples(id: &str, mbps: &[f64], start_ts: f64) -> Vec<TopLevelTrafficSample> {
+        mbps.iter()
+            .enumerate()
+            .map(|(index, mbps)| TopLevelTrafficSample {
+                id: id.to_string(),
+                unix_ts: start_ts + index as f64 * 60.0,
+                bits_per_second: mbps * 1_000_000.0,
+                packets_per_second: 0.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn traffic_weights_use_percentile_and_scale_unobserved_items() {
+        let items = vec![
+            TopLevelPlannerItem {
+                id: "busy".to_string(),
+                weight: 100.0,
+            },
+            TopLevelPlannerItem {
+                id: "new".to_string(),
+                weight: 50.0,
+            },
+        ];
+        let loads = (1..=20).map(f64::from).collect::<Vec<_>>();
+        let samples = traffic_samples("busy", &loads, 0.0);
+        let options = TrafficWeightOptions {
+            min_samples: 10,
+            ..Default::default()
+        };
+
+        let (weighted, observed) = derive_traffic_weights(&items, &samples, 20.0 * 60.0, &options);
+
+        assert_eq!(observed, vec!["busy".to_string()]);
+        assert_eq!(weighted[0].weight, 19.0);
+        // The unobserved item keeps its configured share relative to the observed one.
+        assert!((weighted[1].weight - 9.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn traffic_weights_honor_window_and_peak_hours() {
+        let items = vec![TopLevelPlannerItem {
+            id: "site".to_string(),
+            weight: 1.0,
+        }];
+        // One hour of 10 Mbps at 02:00 UTC, then one hour of 500 Mbps at 20:00 UTC.
+        let mut samples = traffic_samples("site", &[10.0; 60], 2.0 * 3600.0);
+        samples.extend(traffic_samples("site", &[500.0; 60], 20.0 * 3600.0));
+        let now_ts = 21.0 * 3600.0;
+
+        let evening = TrafficWeightOptions {
+            peak_hours_utc: Some((18, 23)),
+            min_samples: 1,
+            ..Default::default()
+        };
+        let (weighted, _) = derive_traffic_weights(&items, &samples, now_ts, &evening);
+        assert_eq!(weighted[0].weight, 500.0);
+
+        let short_window = TrafficWeightOptions {
+            window_seconds: 3600.0,
+            min_samples: 61,
+            ..Default::default()
+        };
+        let (weighted, observed) = derive_traffic_weights(&items, &samples, now_ts, &short_window);
+        assert!(observed.is_empty());
+        assert_eq!(weighted[0].weight, 1.0);
+    }
+
+    #[test]
+    fn traffic_weighted_plan_prefers_local_bins_and_projects_loads() {
+        let items = vec![
+            TopLevelPlannerItem {
+                id: "a".to_string(),
+                weight: 1.0,
+            },
+            TopLevelPlannerItem {
+                id: "b".to_string(),
+                weight: 1.0,
+            },
+            TopLevelPlannerItem {
+                id: "c".to_string(),
+                weight: 1.0,
+            },
+        ];
+        let mut samples = traffic_samples("a", &[300.0; 5], 0.0);
+        samples.extend(traffic_samples("b", &[100.0; 5], 0.0));
+        samples.extend(traffic_samples("c", &[100.0; 5], 0.0));
+        let prev_assign = BTreeMap::from([
+            ("a".to_string(), "CpueQueue0".to_string()),
+            ("b".to_string(), "CpueQueue0".to_string()),
+            ("c".to_string(), "CpueQueue1".to_string()),
+        ]);
+        let options = TrafficWeightOptions {
+            min_samples: 1,
+            bin_cost_multipliers: BTreeMap::from([("CpueQueue1".to_string(), 1.5)]),
+            ..Default::default()
+        };
+
+        let result = plan_traffic_weighted_assignments(
+            &items,
+            &samples,
+            &bins(),
+            &prev_assign,
+            &BTreeMap::new(),
+            10_000.0,
+            &TopLevelPlannerParams {
+                mode: TopLevelPlannerMode::TrafficWeighted,
+                cooldown_seconds: 0.0,
+                move_budget_per_run: 1,
+                hysteresis_threshold: 0.0,
+            },
+            &options,
+        );
+
+        assert_eq!(result.plan.changed, vec!["b".to_string()]);
+        assert_eq!(result.plan.assignment["a"], "CpueQueue0");
+        assert_eq!(result.projection[0].before, 400.0);
+        assert_eq!(result.projection[0].after, 300.0);
+        assert_eq!(result.projection[1].before, 150.0);
+        assert_eq!(result.projection[1].after, 300.0);
+        assert_eq!(result.projection[1].items_after, 2);
+    }
+
     #[test]
     fn class_identity_planner_reuses_site_and_circuit_minors() {
         let site_inputs = vec![
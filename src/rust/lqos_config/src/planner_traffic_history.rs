@@ -0,0 +1,157 @@
+//! Persisted observed-traffic history for the traffic-weighted CPU planner.
+//!
+//! `lqosd` samples top-level site load and periodically writes it here so the Python shaper
+//! build path can derive planner weights without querying the daemon.
+
+use crate::Config;
+use crate::planner::TopLevelTrafficSample;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Runtime filename for the planner traffic history.
+pub const PLANNER_TRAFFIC_HISTORY_FILENAME: &str = "planner_traffic_history.json";
+
+const PLANNER_TRAFFIC_HISTORY_VERSION: u32 = 1;
+
+/// Returns the path of the planner traffic history runtime file.
+pub fn planner_traffic_history_path(config: &Config) -> PathBuf {
+    config.stats_state_read_path(PLANNER_TRAFFIC_HISTORY_FILENAME)
+}
+
+/// Errors raised while reading or writing the planner traffic history.
+#[derive(Debug, Error)]
+pub enum PlannerTrafficHistoryError {
+    /// The history file could not be read or written.
+    #[error("Unable to access planner traffic history: {0}")]
+    Io(#[from] std::io::Error),
+    /// The history file could not be encoded or decoded.
+    #[error("Unable to parse planner traffic history: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The history file was written by an incompatible version.
+    #[error("Unsupported planner traffic history version {0}")]
+    Version(u32),
+}
+
+/// Observed per-site load, bucketed by time.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PlannerTrafficHistoryFile {
+    /// File format version.
+    pub version: u32,
+    /// Width of each bucket in seconds.
+    pub bucket_seconds: u64,
+    /// Per-site `(bucket start unix seconds, bits per second, packets per second)` entries.
+    ///
+    /// Each entry holds the peak sample seen during its bucket.
+    pub sites: BTreeMap<String, Vec<(u64, f64, f64)>>,
+}
+
+impl PlannerTrafficHistoryFile {
+    /// Creates an empty history using the given bucket width.
+    pub fn new(bucket_seconds: u64) -> Self {
+        Self {
+            version: PLANNER_TRAFFIC_HISTORY_VERSION,
+            bucket_seconds: bucket_seconds.max(1),
+            sites: BTreeMap::new(),
+        }
+    }
+
+    /// Records one observation, keeping the per-bucket peak.
+    pub fn record(
+        &mut self,
+        site: &str,
+        unix_ts: u64,
+        bits_per_second: f64,
+        packets_per_second: f64,
+    ) {
+        let bucket = unix_ts - unix_ts % self.bucket_seconds.max(1);
+        let entries = self.sites.entry(site.to_string()).or_default();
+        match entries.last_mut() {
+            Some(last) if last.0 == bucket => {
+                last.1 = last.1.max(bits_per_second);
+                last.2 = last.2.max(packets_per_second);
+            }
+            _ => entries.push((bucket, bits_per_second, packets_per_second)),
+        }
+    }
+
+    /// Drops buckets older than `oldest_unix_ts` and sites left without any buckets.
+    pub fn prune(&mut self, oldest_unix_ts: u64) {
+        self.sites.retain(|_, entries| {
+            entries.retain(|(bucket, _, _)| *bucket >= oldest_unix_ts);
+            !entries.is_empty()
+        });
+    }
+
+    /// Flattens the history into planner samples.
+    pub fn samples(&self) -> Vec<TopLevelTrafficSample> {
+        self.sites
+            .iter()
+            .flat_map(|(site, entries)| {
+                entries
+                    .iter()
+                    .map(move |(bucket, bits, packets)| TopLevelTrafficSample {
+                        id: site.clone(),
+                        unix_ts: *bucket as f64,
+                        bits_per_second: *bits,
+                        packets_per_second: *packets,
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Loads the planner traffic history. A missing file yields `Ok(None)`.
+pub fn load_planner_traffic_history(
+    config: &Config,
+) -> Result<Option<PlannerTrafficHistoryFile>, PlannerTrafficHistoryError> {
+    let path = planner_traffic_history_path(config);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = std::fs::read_to_string(path)?;
+    let history: PlannerTrafficHistoryFile = serde_json::from_str(&raw)?;
+    if history.version != PLANNER_TRAFFIC_HISTORY_VERSION {
+        return Err(PlannerTrafficHistoryError::Version(history.version));
+    }
+    Ok(Some(history))
+}
+
+/// Atomically writes the planner traffic history.
+pub fn store_planner_traffic_history(
+    config: &Config,
+    history: &PlannerTrafficHistoryFile,
+) -> Result<(), PlannerTrafficHistoryError> {
+    let path = planner_traffic_history_path(config);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, serde_json::to_vec(history)?)?;
+    std::fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlannerTrafficHistoryFile;
+
+    #[test]
+    fn record_keeps_bucket_peak_and_prune_drops_old_sites() {
+        let mut history = PlannerTrafficHistoryFile::new(900);
+        history.record("site-a", 1_000, 10.0, 1.0);
+        history.record("site-a", 1_100, 30.0, 0.5);
+        history.record("site-a", 1_900, 5.0, 2.0);
+        history.record("site-b", 100, 1.0, 1.0);
+
+        assert_eq!(
+            history.sites["site-a"],
+            vec![(900, 30.0, 1.0), (1_800, 5.0, 2.0)]
+        );
+
+        history.prune(900);
+        assert!(!history.sites.contains_key("site-b"));
+        assert_eq!(history.samples().len(), 2);
+    }
+}
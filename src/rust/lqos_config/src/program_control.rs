@@ -63,6 +63,54 @@ pub fn load_libreqos() -> Result<String, ProgramControlError> {
     Ok(result_display)
 }
 
+/// Runs `LibreQoS.py --plan` against `staged`, a copy of the configuration
+/// whose input and state directories point at scratch copies. The staged
+/// configuration is written to `staging_directory/lqos.conf`.
+///
+/// Returns the Bakery plan as JSON. Side effects: writes into
+/// `staging_directory` and asks `lqosd` to plan (not apply) a Bakery batch.
+pub fn plan_libreqos(
+    staged: &crate::Config,
+    staging_directory: &Path,
+) -> Result<String, ProgramControlError> {
+    let path = path_to_libreqos()?;
+    if !path.exists() {
+        return Err(ProgramControlError::LibreQosPyNotFound);
+    }
+    if !Path::new(PYTHON_PATH).exists() {
+        return Err(ProgramControlError::PythonNotFound);
+    }
+
+    let config_path = staging_directory.join("lqos.conf");
+    let plan_path = staging_directory.join("plan.json");
+    let raw = toml::to_string_pretty(staged)
+        .map_err(|e| ProgramControlError::PlanFailed(e.to_string()))?;
+    std::fs::write(&config_path, raw)
+        .map_err(|e| ProgramControlError::PlanFailed(e.to_string()))?;
+    let _ = std::fs::remove_file(&plan_path);
+
+    let result = Command::new(PYTHON_PATH)
+        .current_dir(working_directory()?)
+        .env("LQOS_CONFIG", &config_path)
+        .env("LQOS_DIRECTORY", &staged.lqos_directory)
+        .arg("LibreQoS.py")
+        .arg("--plan")
+        .arg("--plan-output")
+        .arg(&plan_path)
+        .output()
+        .map_err(|_| ProgramControlError::CommandFailed)?;
+    match std::fs::read_to_string(&plan_path) {
+        Ok(plan) if result.status.success() => Ok(plan),
+        _ => {
+            let mut output = String::from_utf8_lossy(&result.stdout).to_string();
+            output += &String::from_utf8_lossy(&result.stderr);
+            let tail = output.lines().rev().take(20).collect::<Vec<_>>();
+            let tail = tail.into_iter().rev().collect::<Vec<_>>().join("\n");
+            Err(ProgramControlError::PlanFailed(tail))
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ProgramControlError {
     #[error("Unable to load lqos configuration from /etc")]
@@ -75,4 +123,6 @@ pub enum ProgramControlError {
     CommandFailed,
     #[error("Problem accessing stdin/stderr. This shouldn't happen")]
     StdInErrAccess,
+    #[error("LibreQoS.py did not produce a plan: {0}")]
+    PlanFailed(String),
 }
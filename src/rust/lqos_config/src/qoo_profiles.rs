@@ -120,6 +120,19 @@ pub fn list_qoo_profiles() -> Result<Vec<QooProfileInfo>, QooProfilesError> {
 
 /// Select the active QoO profile based on config (fallbacks to `DEFAULT_QOO_PROFILE_ID`).
 pub fn active_qoo_profile() -> Result<Arc<QooProfile>, QooProfilesError> {
+    let file = load_qoo_profiles_file()?;
+    let id = active_qoo_profile_id()?;
+    let selected = file
+        .profiles
+        .iter()
+        .find(|p| p.id == id)
+        .ok_or(QooProfilesError::EmptyProfiles)?;
+    Ok(Arc::new(selected.to_runtime()))
+}
+
+/// Id of the profile `active_qoo_profile` selects; the global default for circuits
+/// and sites without their own assignment.
+pub fn active_qoo_profile_id() -> Result<String, QooProfilesError> {
     let cfg = crate::load_config()?;
     let requested = cfg
         .qoo_profile_id
@@ -137,5 +150,5 @@ pub fn active_qoo_profile() -> Result<Arc<QooProfile>, QooProfilesError> {
         .or_else(|| file.pick_default())
         .ok_or(QooProfilesError::EmptyProfiles)?;
 
-    Ok(Arc::new(selected.to_runtime()))
+    Ok(selected.id.clone())
 }
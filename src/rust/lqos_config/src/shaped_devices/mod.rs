@@ -512,6 +512,95 @@ mod test {
         assert_eq!(matched.circuit_id, "circuit-1");
     }
 
+    #[test]
+    fn priority_classes_column_is_parsed_by_header() {
+        let headers = csv::StringRecord::from(vec![
+            "Circuit ID",
+            "Circuit Name",
+            "Device ID",
+            "Device Name",
+            "Parent Node",
+            "MAC",
+            "IPv4",
+            "IPv6",
+            "Download Min Mbps",
+            "Upload Min Mbps",
+            "Download Max Mbps",
+            "Upload Max Mbps",
+            "Comment",
+            "Priority Classes",
+        ]);
+        let row = |classes: &str| {
+            csv::StringRecord::from(vec![
+                "1",
+                "Office",
+                "1",
+                "Router",
+                "",
+                "",
+                "192.0.2.1",
+                "",
+                "5",
+                "5",
+                "100",
+                "100",
+                "",
+                classes,
+            ])
+        };
+        let device =
+            ShapedDevice::from_csv(&row(" SIP, gaming,sip "), Some(&headers)).expect("parse row");
+        assert_eq!(device.priority_classes, vec!["sip", "gaming"]);
+        assert!(device.sqm_override.is_none());
+
+        assert!(ShapedDevice::from_csv(&row("voice lane"), Some(&headers)).is_err());
+        assert!(ShapedDevice::from_csv(&row("a,b,c,d,e"), Some(&headers)).is_err());
+    }
+
+    #[test]
+    fn qoo_profile_column_is_parsed_by_header() {
+        let headers = csv::StringRecord::from(vec![
+            "Circuit ID",
+            "Circuit Name",
+            "Device ID",
+            "Device Name",
+            "Parent Node",
+            "MAC",
+            "IPv4",
+            "IPv6",
+            "Download Min Mbps",
+            "Upload Min Mbps",
+            "Download Max Mbps",
+            "Upload Max Mbps",
+            "Comment",
+            "QoO Profile",
+        ]);
+        let row = |profile: &str| {
+            csv::StringRecord::from(vec![
+                "1",
+                "Office",
+                "1",
+                "Router",
+                "",
+                "",
+                "192.0.2.1",
+                "",
+                "5",
+                "5",
+                "100",
+                "100",
+                "",
+                profile,
+            ])
+        };
+        let device = ShapedDevice::from_csv(&row(" Real_Time_Gaming "), Some(&headers))
+            .expect("parse row");
+        assert_eq!(device.qoo_profile.as_deref(), Some("real_time_gaming"));
+        let device = ShapedDevice::from_csv(&row(""), Some(&headers)).expect("parse row");
+        assert!(device.qoo_profile.is_none());
+        assert!(ShapedDevice::from_csv(&row("video streaming"), Some(&headers)).is_err());
+    }
+
     #[test]
     fn test_handle_encodings_valid_utf8() {
         // Test plain UTF-8 text
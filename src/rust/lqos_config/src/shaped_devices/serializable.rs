@@ -24,6 +24,12 @@ pub(crate) struct SerializableShapedDevice {
     /// Optional per-circuit SQM override: "cake", "fq_codel", "none", or "down_sqm/up_sqm".
     /// Empty = default.
     pub sqm: String,
+    /// Comma-separated intra-circuit priority class names. Empty = none.
+    pub priority_classes: String,
+    /// Walled-garden access policy. Empty = open.
+    pub access_policy: String,
+    /// QoO profile id or "auto". Empty = inherit.
+    pub qoo_profile: String,
 }
 
 impl From<&ShapedDevice> for SerializableShapedDevice {
@@ -49,6 +55,13 @@ impl From<&ShapedDevice> for SerializableShapedDevice {
                 .as_ref()
                 .map(|s| s.to_string())
                 .unwrap_or_default(),
+            priority_classes: d.priority_classes.join(","),
+            access_policy: if d.access_policy.is_open() {
+                String::new()
+            } else {
+                d.access_policy.as_str().to_string()
+            },
+            qoo_profile: d.qoo_profile.clone().unwrap_or_default(),
         }
     }
 }
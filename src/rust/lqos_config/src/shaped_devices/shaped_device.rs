@@ -1,4 +1,5 @@
 use super::ShapedDevicesError;
+use crate::CircuitAccessPolicy;
 use allocative::Allocative;
 use csv::StringRecord;
 use lqos_utils::hash_to_i64;
@@ -10,7 +11,7 @@ use tracing::error;
 /// Represents a row in the `ShapedDevices.csv` file.
 #[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Allocative)]
 pub struct ShapedDevice {
-    // Circuit ID,Circuit Name,Device ID,Device Name,Parent Node,Parent Node ID,Anchor Node ID,MAC,IPv4,IPv6,Download Min Mbps,Upload Min Mbps,Download Max Mbps,Upload Max Mbps,Comment[,sqm]
+    // Circuit ID,Circuit Name,Device ID,Device Name,Parent Node,Parent Node ID,Anchor Node ID,MAC,IPv4,IPv6,Download Min Mbps,Upload Min Mbps,Download Max Mbps,Upload Max Mbps,Comment[,sqm][,priority_classes][,qoo_profile][,access_policy]
     /// The ID of the circuit to which the device belongs. Circuits are 1:many,
     /// multiple devices may be in a single circuit.
     pub circuit_id: String,
@@ -70,12 +71,28 @@ pub struct ShapedDevice {
     pub comment: String,
 
     /// Optional per-circuit SQM override token. Accepts "cake", "fq_codel",
-    /// "none", or directional "down_sqm/up_sqm" values like "cake/none" or
-    /// "/fq_codel". A single token applies to both directions; empty means
-    /// "use global default".
+    /// "fq_pie", "none", a named SQM profile, or directional "down_sqm/up_sqm"
+    /// values like "cake/none" or "/docsis". A single token applies to both
+    /// directions; empty means "use global default".
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sqm_override: Option<String>,
 
+    /// Optional intra-circuit priority classes, by name, from
+    /// `priority_classes` in `lqos.conf`. Empty means a single default lane.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority_classes: Vec<String>,
+
+    /// Walled-garden access policy for the circuit. Integrations set this for
+    /// suspended subscribers; `open` means unrestricted.
+    #[serde(default, skip_serializing_if = "CircuitAccessPolicy::is_open")]
+    pub access_policy: CircuitAccessPolicy,
+
+    /// Optional QoO profile id from `qoo_profiles.json` for scoring this
+    /// circuit, or `auto` to pick one from its traffic. `None` inherits the
+    /// site or global profile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qoo_profile: Option<String>,
+
     /// Hash of the circuit ID, used for internal lookups.
     #[serde(skip)]
     pub circuit_hash: i64,
@@ -184,6 +201,15 @@ impl ShapedDevice {
                 "sqm" => {
                     layout.insert("sqm", idx);
                 }
+                "priorityclasses" => {
+                    layout.insert("priority_classes", idx);
+                }
+                "accesspolicy" => {
+                    layout.insert("access_policy", idx);
+                }
+                "qooprofile" => {
+                    layout.insert("qoo_profile", idx);
+                }
                 _ => {}
             }
         }
@@ -224,10 +250,14 @@ impl ShapedDevice {
     /// 13. Download Max Mbps
     /// 14. Upload Max Mbps
     /// 15. Comment
-    /// 16. sqm (optional; allowed values: "cake", "fq_codel", "none", or
-    ///     a directional override in the form "down_sqm/up_sqm". Either side
+    /// 16. sqm (optional; allowed values: "cake", "fq_codel", "fq_pie", "none",
+    ///     a named SQM profile, or a directional override in the form "down_sqm/up_sqm". Either side
     ///     may be empty to indicate no override for that direction, e.g.
     ///     "cake/" or "/fq_codel".)
+    /// 17. priority_classes (optional, header-only; comma-separated priority
+    ///     class names such as "sip,gaming")
+    /// 18. access_policy (optional, header-only; "open", "block" or "portal")
+    /// 19. qoo_profile (optional, header-only; a QoO profile id or "auto")
     ///
     /// # Arguments
     ///
@@ -339,6 +369,9 @@ impl ShapedDevice {
             },
             comment: Self::field(record, &layout, "comment").to_string(),
             sqm_override: None,
+            priority_classes: Vec::new(),
+            access_policy: CircuitAccessPolicy::Open,
+            qoo_profile: None,
             circuit_hash: 0,
             device_hash: 0,
             parent_hash: 0,
@@ -358,11 +391,9 @@ impl ShapedDevice {
                     let up = parts.next().unwrap_or("").trim();
 
                     // Validate each side if present
-                    let valid =
-                        |s: &str| -> bool { matches!(s, "" | "cake" | "fq_codel" | "none") };
-                    if !valid(down) || !valid(up) {
+                    if !crate::is_valid_profile_name(down) || !crate::is_valid_profile_name(up) {
                         return Err(ShapedDevicesError::CsvEntryParseError(format!(
-                            "Invalid directional sqm override '{token}'. Allowed: 'cake', 'fq_codel', 'none', or 'down_sqm/up_sqm' (e.g. 'cake/fq_codel', '/none')"
+                            "Invalid directional sqm override '{token}'. Allowed: 'cake', 'fq_codel', 'fq_pie', 'none', an SQM profile name, or 'down_sqm/up_sqm' (e.g. 'cake/fq_codel', '/none')"
                         )));
                     }
 
@@ -370,21 +401,67 @@ impl ShapedDevice {
                     device.sqm_override = Some(format!("{down}/{up}"));
                 } else {
                     // Single token applies to both directions when used
-                    match token.as_str() {
-                        "cake" | "fq_codel" | "none" => device.sqm_override = Some(token),
-                        other => {
-                            return Err(ShapedDevicesError::CsvEntryParseError(format!(
-                                "Invalid sqm override '{other}'. Allowed values: 'cake', 'fq_codel', 'none', or 'down_sqm/up_sqm' (e.g. 'cake/fq_codel', '/none')"
-                            )));
-                        }
+                    if !crate::is_valid_profile_name(&token) {
+                        return Err(ShapedDevicesError::CsvEntryParseError(format!(
+                            "Invalid sqm override '{token}'. Allowed values: 'cake', 'fq_codel', 'fq_pie', 'none', an SQM profile name, or 'down_sqm/up_sqm' (e.g. 'cake/fq_codel', '/none')"
+                        )));
                     }
+                    device.sqm_override = Some(token);
                 }
             }
         }
 
+        device.priority_classes =
+            Self::parse_priority_classes(Self::field(record, &layout, "priority_classes"))?;
+        device.access_policy = Self::field(record, &layout, "access_policy")
+            .parse()
+            .map_err(ShapedDevicesError::CsvEntryParseError)?;
+        device.qoo_profile = Self::parse_qoo_profile(Self::field(record, &layout, "qoo_profile"))?;
+
         Ok(device)
     }
 
+    /// Parses a QoO profile id or `auto`. Empty means no assignment; whether
+    /// the profile exists is checked against `qoo_profiles.json` when scoring.
+    pub fn parse_qoo_profile(raw: &str) -> Result<Option<String>, ShapedDevicesError> {
+        let name = raw.trim().to_lowercase();
+        if name.is_empty() {
+            return Ok(None);
+        }
+        if !crate::is_valid_profile_name(&name) {
+            return Err(ShapedDevicesError::CsvEntryParseError(format!(
+                "Invalid QoO profile '{name}'. Profile ids are lowercase letters, digits, '-' or '_'"
+            )));
+        }
+        Ok(Some(name))
+    }
+
+    /// Parses a comma-separated list of priority class names.
+    ///
+    /// Names are lowercased and de-duplicated; existence is checked when
+    /// queues are built.
+    pub fn parse_priority_classes(raw: &str) -> Result<Vec<String>, ShapedDevicesError> {
+        let mut classes: Vec<String> = Vec::new();
+        for name in raw.split(',').map(|n| n.trim().to_lowercase()) {
+            if name.is_empty() || classes.contains(&name) {
+                continue;
+            }
+            if !crate::is_valid_profile_name(&name) {
+                return Err(ShapedDevicesError::CsvEntryParseError(format!(
+                    "Invalid priority class name '{name}'. Names are lowercase letters, digits, '-' or '_'"
+                )));
+            }
+            classes.push(name);
+        }
+        if classes.len() > crate::MAX_PRIORITY_CLASSES_PER_CIRCUIT {
+            return Err(ShapedDevicesError::CsvEntryParseError(format!(
+                "Too many priority classes '{raw}' (at most {})",
+                crate::MAX_PRIORITY_CLASSES_PER_CIRCUIT
+            )));
+        }
+        Ok(classes)
+    }
+
     pub(crate) fn parse_cidr_v4(address: &str) -> Result<(Ipv4Addr, u32), ShapedDevicesError> {
         if address.contains('/') {
             let split: Vec<&str> = address.split('/').collect();
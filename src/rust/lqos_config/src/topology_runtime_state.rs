@@ -121,6 +121,18 @@ pub struct TopologyAttachmentHealthEntry {
     /// Endpoint-by-endpoint status for the last probe round.
     #[serde(default)]
     pub endpoint_status: Vec<TopologyAttachmentEndpointStatus>,
+    /// Routing-protocol state for the remote probe IP, once a routing daemon has reported it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing_state: Option<TopologyAttachmentRoutingState>,
+}
+
+/// Routing-protocol view of an attachment pair's remote probe IP.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TopologyAttachmentRoutingState {
+    /// Whether an adjacency or installed next-hop via the remote probe IP is up.
+    pub up: bool,
+    /// Human-readable detail, e.g. `OSPF adjacency Full/PtP`.
+    pub detail: String,
 }
 
 /// Full transient attachment-health snapshot.
@@ -290,6 +302,15 @@ pub struct TopologyShapingCircuitInput {
     /// Optional per-circuit SQM override token.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sqm_override: Option<String>,
+    /// Optional intra-circuit priority class names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority_classes: Vec<String>,
+    /// Walled-garden access policy for the circuit.
+    #[serde(default, skip_serializing_if = "crate::CircuitAccessPolicy::is_open")]
+    pub access_policy: crate::CircuitAccessPolicy,
+    /// Optional QoO profile id, or `auto`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qoo_profile: Option<String>,
     /// Device rows belonging to this circuit.
     #[serde(default)]
     pub devices: Vec<TopologyShapingDeviceInput>,
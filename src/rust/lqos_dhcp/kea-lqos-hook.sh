@@ -0,0 +1,98 @@
+#!/usr/bin/env bash
+# Forwards ISC Kea lease changes to lqosd's DHCP hook socket.
+#
+# Load it with Kea's run_script hook library:
+#
+#   "hooks-libraries": [{
+#       "library": "/usr/lib/x86_64-linux-gnu/kea/hooks/libdhcp_run_script.so",
+#       "parameters": { "name": "/opt/libreqos/src/rust/lqos_dhcp/kea-lqos-hook.sh", "sync": false }
+#   }]
+#
+# Kea passes the hook point as $1 and the lease as environment variables. Each
+# lease becomes one JSON line on LQOS_DHCP_HOOK_SOCKET (default
+# /run/lqos/dhcp-hook.sock), matching dhcp_leases.hook_socket in /etc/lqos.conf.
+# Requires socat or an nc that supports -U.
+set -u
+
+SOCKET="${LQOS_DHCP_HOOK_SOCKET:-/run/lqos/dhcp-hook.sock}"
+EVENTS=()
+
+json_string() {
+    local value="${1//\\/\\\\}"
+    value="${value//\"/\\\"}"
+    printf '"%s"' "${value}"
+}
+
+# add_event ACTION ADDRESS [key value]...
+add_event() {
+    local action="$1" address="$2"
+    shift 2
+    [[ -n "${address}" ]] || return 0
+    local line="{\"action\":\"${action}\",\"address\":$(json_string "${address}")"
+    while [[ $# -ge 2 ]]; do
+        local key="$1" value="$2"
+        shift 2
+        [[ -n "${value}" ]] || continue
+        if [[ "${key}" == "valid_lifetime" || "${key}" == "prefix_len" ]]; then
+            [[ "${value}" =~ ^[0-9]+$ ]] || continue
+            line+=",\"${key}\":${value}"
+        else
+            line+=",\"${key}\":$(json_string "${value}")"
+        fi
+    done
+    EVENTS+=("${line}}")
+}
+
+lease4() {
+    local action="$1" prefix="$2"
+    local address="${prefix}ADDRESS" hwaddr="${prefix}HWADDR" client_id="${prefix}CLIENT_ID"
+    local hostname="${prefix}HOSTNAME" lifetime="${prefix}VALID_LIFETIME"
+    add_event "${action}" "${!address:-}" \
+        hwaddr "${!hwaddr:-}" client_id "${!client_id:-}" hostname "${!hostname:-}" \
+        valid_lifetime "${!lifetime:-}" option82 "${QUERY4_OPTION82:-}"
+}
+
+lease6() {
+    local action="$1" prefix="$2"
+    local address="${prefix}ADDRESS" prefix_len="${prefix}PREFIX_LEN" lease_type="${prefix}TYPE"
+    local duid="${prefix}DUID" hwaddr="${prefix}HWADDR" hostname="${prefix}HOSTNAME"
+    local lifetime="${prefix}VALID_LIFETIME"
+    add_event "${action}" "${!address:-}" \
+        lease_type "${!lease_type:-}" prefix_len "${!prefix_len:-}" duid "${!duid:-}" \
+        hwaddr "${!hwaddr:-}" hostname "${!hostname:-}" valid_lifetime "${!lifetime:-}"
+}
+
+# committed FAMILY: every lease in LEASESn_AT<i>_* and DELETED_LEASESn_AT<i>_*.
+committed() {
+    local family="$1" size index
+    size="LEASES${family}_SIZE"
+    for ((index = 0; index < ${!size:-0}; index++)); do
+        "lease${family}" commit "LEASES${family}_AT${index}_"
+    done
+    size="DELETED_LEASES${family}_SIZE"
+    for ((index = 0; index < ${!size:-0}; index++)); do
+        "lease${family}" release "DELETED_LEASES${family}_AT${index}_"
+    done
+}
+
+case "${1:-}" in
+    leases4_committed) committed 4 ;;
+    lease4_renew | lease4_recover) lease4 commit LEASE4_ ;;
+    lease4_release) lease4 release LEASE4_ ;;
+    lease4_decline) lease4 decline LEASE4_ ;;
+    lease4_expire) lease4 expire LEASE4_ ;;
+    leases6_committed) committed 6 ;;
+    lease6_renew | lease6_rebind | lease6_recover) lease6 commit LEASE6_ ;;
+    lease6_release) lease6 release LEASE6_ ;;
+    lease6_decline) lease6 decline LEASE6_ ;;
+    lease6_expire) lease6 expire LEASE6_ ;;
+    *) exit 0 ;;
+esac
+
+[[ ${#EVENTS[@]} -gt 0 && -S "${SOCKET}" ]] || exit 0
+if command -v socat >/dev/null 2>&1; then
+    printf '%s\n' "${EVENTS[@]}" | socat -t 2 - "UNIX-CONNECT:${SOCKET}" >/dev/null 2>&1
+else
+    printf '%s\n' "${EVENTS[@]}" | nc -N -U "${SOCKET}" >/dev/null 2>&1
+fi
+exit 0
@@ -0,0 +1,94 @@
+//! dnsmasq `dhcp-leasefile` lease files.
+//!
+//! dnsmasq rewrites the whole file on every change. DHCPv4 lines are
+//! `expiry mac address hostname client-id`. A `duid <server-duid>` line starts
+//! the DHCPv6 section, whose lines are `expiry iaid address hostname duid`.
+//! An expiry of 0 means the lease never expires and `*` marks a missing field.
+
+use crate::lease::{
+    DhcpLease, LeaseFile, LeaseKey, LeaseKind, canonical_hex, non_empty, normalize_mac,
+};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// Parses a dnsmasq lease file.
+///
+/// Side effects: none.
+#[must_use]
+pub fn parse_dnsmasq_leases(text: &str) -> LeaseFile {
+    let mut leases = BTreeMap::new();
+    let mut skipped_rows = 0;
+    let mut v6_section = false;
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if line.starts_with("duid ") {
+            v6_section = true;
+            continue;
+        }
+        match parse_line(line, v6_section) {
+            Some(lease) => {
+                leases.insert(lease.key, lease);
+            }
+            None => skipped_rows += 1,
+        }
+    }
+    LeaseFile {
+        leases: leases.into_values().collect(),
+        skipped_rows,
+    }
+}
+
+fn parse_line(line: &str, v6_section: bool) -> Option<DhcpLease> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 3 {
+        return None;
+    }
+    let expiry: u64 = fields[0].parse().ok()?;
+    let address: IpAddr = fields[2].parse().ok()?;
+    if address.is_ipv6() != v6_section {
+        return None;
+    }
+    let (kind, prefix_len, mac) = if v6_section {
+        (LeaseKind::V6Address, 128, None)
+    } else {
+        (LeaseKind::V4, 32, normalize_mac(fields[1]))
+    };
+    Some(DhcpLease {
+        key: LeaseKey {
+            address,
+            prefix_len,
+        },
+        kind,
+        mac,
+        client_id: fields.get(4).copied().and_then(canonical_hex),
+        hostname: fields.get(3).copied().and_then(non_empty),
+        relay: Default::default(),
+        expires_unix: (expiry != 0).then_some(expiry),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_v4_and_v6_sections() {
+        let parsed = parse_dnsmasq_leases(
+            "1700003600 aa:bb:cc:dd:ee:01 192.0.2.10 cpe-1 01:aa:bb:cc:dd:ee:01\n\
+             0 aa:bb:cc:dd:ee:02 192.0.2.11 * *\n\
+             garbage\n\
+             duid 00:01:00:01:2c:aa:bb:cc:00:11:22:33:44:55\n\
+             1700003600 12345 2001:db8::10 * 00:03:00:01:aa:bb:cc:dd:ee:01\n",
+        );
+        assert_eq!(parsed.skipped_rows, 1);
+        assert_eq!(parsed.leases.len(), 3);
+        assert_eq!(parsed.leases[0].mac.as_deref(), Some("aabbccddee01"));
+        assert_eq!(parsed.leases[0].hostname.as_deref(), Some("cpe-1"));
+        assert_eq!(parsed.leases[1].expires_unix, None);
+        assert_eq!(parsed.leases[1].client_id, None);
+        assert_eq!(parsed.leases[2].kind, LeaseKind::V6Address);
+        assert_eq!(
+            parsed.leases[2].client_id.as_deref(),
+            Some("00:03:00:01:aa:bb:cc:dd:ee:01")
+        );
+    }
+}
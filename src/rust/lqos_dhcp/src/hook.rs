@@ -0,0 +1,195 @@
+//! Lease events pushed by a DHCP server hook, one JSON object per line.
+//!
+//! The shipped `kea-lqos-hook.sh` turns Kea `run_script` hook calls into these
+//! events. Any other server can send them too:
+//!
+//! ```json
+//! {"action":"commit","address":"100.64.0.10","hwaddr":"aa:bb:cc:dd:ee:01","valid_lifetime":3600}
+//! {"action":"commit","address":"2001:db8:100::","lease_type":"IA_PD","prefix_len":56,"duid":"00:03:00:01:aa:bb:cc:dd:ee:01","valid_lifetime":3600}
+//! {"action":"release","address":"100.64.0.10"}
+//! ```
+
+use crate::lease::{
+    DhcpLease, LeaseKey, LeaseKind, RelayAgentInfo, canonical_hex, decode_hex, non_empty,
+    normalize_mac,
+};
+use serde::Deserialize;
+use std::net::IpAddr;
+use thiserror::Error;
+
+/// What happened to the lease.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookAction {
+    /// The lease was granted, renewed or rebound.
+    Commit,
+    /// The client released the lease.
+    Release,
+    /// The server expired or reclaimed the lease.
+    Expire,
+    /// The client declined the address.
+    Decline,
+}
+
+/// One lease event.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct HookEvent {
+    /// What happened to the lease.
+    pub action: HookAction,
+    /// Leased address, or the first address of a delegated prefix.
+    pub address: IpAddr,
+    /// Delegated prefix length. Ignored unless `lease_type` is `IA_PD`.
+    #[serde(default)]
+    pub prefix_len: Option<u8>,
+    /// `IA_NA`, `IA_TA` or `IA_PD` for DHCPv6.
+    #[serde(default)]
+    pub lease_type: Option<String>,
+    /// Client hardware address.
+    #[serde(default)]
+    pub hwaddr: Option<String>,
+    /// DHCPv4 client-id in hex.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// DHCPv6 client DUID in hex.
+    #[serde(default)]
+    pub duid: Option<String>,
+    /// Client hostname.
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// Lease lifetime in seconds from now. Missing or `4294967295` never expires.
+    #[serde(default)]
+    pub valid_lifetime: Option<u64>,
+    /// Whole option 82 payload in hex, as Kea exports it.
+    #[serde(default)]
+    pub option82: Option<String>,
+    /// Option 82 circuit-id, when the server decodes it itself.
+    #[serde(default)]
+    pub circuit_id: Option<String>,
+    /// Option 82 remote-id, when the server decodes it itself.
+    #[serde(default)]
+    pub remote_id: Option<String>,
+}
+
+/// A hook line that could not be used.
+#[derive(Debug, Error)]
+pub enum HookEventError {
+    /// The line is not a valid event object.
+    #[error("invalid lease event: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A delegated prefix event without a usable prefix length.
+    #[error("IA_PD event for {0} needs a prefix_len of at most 128")]
+    MissingPrefixLength(IpAddr),
+}
+
+impl HookEvent {
+    /// Parses one JSON line.
+    pub fn parse(line: &str) -> Result<Self, HookEventError> {
+        let event: Self = serde_json::from_str(line)?;
+        event.key()?;
+        Ok(event)
+    }
+
+    /// Returns true when the event ends the lease.
+    #[must_use]
+    pub fn ends_lease(&self) -> bool {
+        self.action != HookAction::Commit
+    }
+
+    /// The address or prefix the event is about.
+    pub fn key(&self) -> Result<LeaseKey, HookEventError> {
+        let prefix_len = match self.kind() {
+            LeaseKind::V4 => 32,
+            LeaseKind::V6Address => 128,
+            LeaseKind::V6Prefix => self
+                .prefix_len
+                .filter(|len| *len <= 128)
+                .ok_or(HookEventError::MissingPrefixLength(self.address))?,
+        };
+        Ok(LeaseKey {
+            address: self.address,
+            prefix_len,
+        })
+    }
+
+    /// The lease this event grants, with its expiry counted from `now_unix`.
+    pub fn lease(&self, now_unix: u64) -> Result<DhcpLease, HookEventError> {
+        let mut relay = self
+            .option82
+            .as_deref()
+            .and_then(decode_hex)
+            .map(|bytes| RelayAgentInfo::from_sub_options(&bytes))
+            .unwrap_or_default();
+        if let Some(circuit_id) = self.circuit_id.as_deref().and_then(non_empty) {
+            relay.circuit_id = Some(circuit_id);
+        }
+        if let Some(remote_id) = self.remote_id.as_deref().and_then(non_empty) {
+            relay.remote_id = Some(remote_id);
+        }
+        let client_id = match self.kind() {
+            LeaseKind::V4 => self.client_id.as_deref(),
+            _ => self.duid.as_deref().or(self.client_id.as_deref()),
+        };
+        Ok(DhcpLease {
+            key: self.key()?,
+            kind: self.kind(),
+            mac: self.hwaddr.as_deref().and_then(normalize_mac),
+            client_id: client_id.and_then(canonical_hex),
+            hostname: self.hostname.as_deref().and_then(non_empty),
+            relay,
+            expires_unix: self
+                .valid_lifetime
+                .filter(|lifetime| *lifetime != u64::from(u32::MAX))
+                .map(|lifetime| now_unix.saturating_add(lifetime)),
+        })
+    }
+
+    fn kind(&self) -> LeaseKind {
+        if self.address.is_ipv4() {
+            LeaseKind::V4
+        } else if self
+            .lease_type
+            .as_deref()
+            .is_some_and(|lease_type| lease_type.eq_ignore_ascii_case("IA_PD") || lease_type == "2")
+        {
+            LeaseKind::V6Prefix
+        } else {
+            LeaseKind::V6Address
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_events_become_leases() {
+        let event = HookEvent::parse(
+            r#"{"action":"commit","address":"100.64.0.10","hwaddr":"AA:BB:CC:DD:EE:01","valid_lifetime":600,"option82":"0x0106504F4E2D3130"}"#,
+        )
+        .unwrap();
+        assert!(!event.ends_lease());
+        let lease = event.lease(1_000).unwrap();
+        assert_eq!(lease.mac.as_deref(), Some("aabbccddee01"));
+        assert_eq!(lease.relay.circuit_id.as_deref(), Some("PON-10"));
+        assert_eq!(lease.expires_unix, Some(1_600));
+
+        let prefix = HookEvent::parse(
+            r#"{"action":"commit","address":"2001:db8:100::","lease_type":"IA_PD","prefix_len":56,"duid":"00:03:00:01:aa:bb:cc:dd:ee:01"}"#,
+        )
+        .unwrap()
+        .lease(1_000)
+        .unwrap();
+        assert_eq!(prefix.kind, LeaseKind::V6Prefix);
+        assert_eq!(prefix.key.prefix_len, 56);
+        assert_eq!(prefix.expires_unix, None);
+
+        let release = HookEvent::parse(r#"{"action":"release","address":"100.64.0.10"}"#).unwrap();
+        assert!(release.ends_lease());
+        assert!(
+            HookEvent::parse(r#"{"action":"commit","address":"2001:db8::","lease_type":"IA_PD"}"#)
+                .is_err()
+        );
+        assert!(HookEvent::parse(r#"{"action":"renew","address":"100.64.0.10"}"#).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! ISC Kea `memfile` lease files.
+//!
+//! Kea appends a row every time a lease changes and compacts the file in the
+//! background, so the last row for an address wins. A row with a zero valid
+//! lifetime or a non-default state marks a released, declined or reclaimed
+//! lease.
+
+use crate::lease::{
+    DhcpLease, LeaseFile, LeaseKey, LeaseKind, RelayAgentInfo, canonical_hex, decode_hex,
+    display_bytes, non_empty, normalize_mac,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use thiserror::Error;
+
+/// Kea's `infinite` valid lifetime.
+const INFINITE_LIFETIME: u64 = u32::MAX as u64;
+
+/// Kea lease-type column values for DHCPv6.
+const LEASE_TYPE_TA: &str = "1";
+const LEASE_TYPE_PD: &str = "2";
+
+/// Failure to read a lease file as a whole.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LeaseFileError {
+    /// The file does not start with a Kea memfile header.
+    #[error("missing Kea memfile header")]
+    MissingHeader,
+    /// The header lacks a column the parser needs.
+    #[error("Kea memfile header lacks the {0} column")]
+    MissingColumn(&'static str),
+}
+
+/// Parses a Kea DHCPv4 or DHCPv6 memfile, detected from its header.
+///
+/// Side effects: none.
+pub fn parse_kea_memfile(text: &str) -> Result<LeaseFile, LeaseFileError> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let header = lines.next().ok_or(LeaseFileError::MissingHeader)?;
+    let columns = Columns::new(header)?;
+    let v6 = columns.index.contains_key("duid");
+
+    let mut current: BTreeMap<LeaseKey, Option<DhcpLease>> = BTreeMap::new();
+    let mut skipped_rows = 0;
+    for line in lines {
+        if line == header {
+            continue;
+        }
+        let fields: Vec<String> = line.split(',').map(unescape).collect();
+        let row = if v6 {
+            columns.v6_row(&fields)
+        } else {
+            columns.v4_row(&fields)
+        };
+        match row {
+            Some((key, lease)) => {
+                current.insert(key, lease);
+            }
+            None => skipped_rows += 1,
+        }
+    }
+
+    Ok(LeaseFile {
+        leases: current.into_values().flatten().collect(),
+        skipped_rows,
+    })
+}
+
+struct Columns {
+    index: HashMap<String, usize>,
+}
+
+impl Columns {
+    fn new(header: &str) -> Result<Self, LeaseFileError> {
+        let index: HashMap<String, usize> = header
+            .split(',')
+            .enumerate()
+            .map(|(position, name)| (name.trim().to_string(), position))
+            .collect();
+        if !index.contains_key("address") {
+            return Err(LeaseFileError::MissingHeader);
+        }
+        for required in ["valid_lifetime", "expire"] {
+            if !index.contains_key(required) {
+                return Err(LeaseFileError::MissingColumn(required));
+            }
+        }
+        Ok(Self { index })
+    }
+
+    fn field<'a>(&self, fields: &'a [String], name: &str) -> &'a str {
+        self.index
+            .get(name)
+            .and_then(|position| fields.get(*position))
+            .map_or("", |value| value.trim())
+    }
+
+    /// The lease key and the lease, or `None` for a lease that was given up.
+    fn common(
+        &self,
+        fields: &[String],
+        kind: LeaseKind,
+        prefix_len: u8,
+    ) -> Option<(LeaseKey, Option<DhcpLease>)> {
+        let address: IpAddr = self.field(fields, "address").parse().ok()?;
+        let valid_lifetime: u64 = self.field(fields, "valid_lifetime").parse().ok()?;
+        let expire: u64 = self.field(fields, "expire").parse().ok()?;
+        let state = self.field(fields, "state");
+        let key = LeaseKey {
+            address,
+            prefix_len,
+        };
+        if valid_lifetime == 0 || !(state.is_empty() || state == "0") {
+            return Some((key, None));
+        }
+        let hwaddr = self.field(fields, "hwaddr");
+        let client_id = if kind == LeaseKind::V4 {
+            self.field(fields, "client_id")
+        } else {
+            self.field(fields, "duid")
+        };
+        Some((
+            key,
+            Some(DhcpLease {
+                key,
+                kind,
+                mac: normalize_mac(hwaddr),
+                client_id: canonical_hex(client_id),
+                hostname: non_empty(self.field(fields, "hostname")),
+                relay: relay_from_user_context(self.field(fields, "user_context")),
+                expires_unix: (valid_lifetime != INFINITE_LIFETIME).then_some(expire),
+            }),
+        ))
+    }
+
+    fn v4_row(&self, fields: &[String]) -> Option<(LeaseKey, Option<DhcpLease>)> {
+        let row = self.common(fields, LeaseKind::V4, 32)?;
+        row.0.address.is_ipv4().then_some(row)
+    }
+
+    fn v6_row(&self, fields: &[String]) -> Option<(LeaseKey, Option<DhcpLease>)> {
+        let (kind, prefix_len) = match self.field(fields, "lease_type") {
+            LEASE_TYPE_PD => (
+                LeaseKind::V6Prefix,
+                self.field(fields, "prefix_len")
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|len| *len <= 128)?,
+            ),
+            "0" | LEASE_TYPE_TA | "" => (LeaseKind::V6Address, 128),
+            _ => return None,
+        };
+        let row = self.common(fields, kind, prefix_len)?;
+        row.0.address.is_ipv6().then_some(row)
+    }
+}
+
+/// Kea escapes commas inside a column as `&#x2c;`.
+fn unescape(field: &str) -> String {
+    field.replace("&#x2c;", ",")
+}
+
+/// Reads option 82 from the `ISC.relay-agent-info` entry Kea stores when
+/// `store-extended-info` is enabled. Older Kea versions store the raw option
+/// as a hex string; newer ones store an object with `sub-options`.
+fn relay_from_user_context(user_context: &str) -> RelayAgentInfo {
+    let Ok(context) = serde_json::from_str::<serde_json::Value>(user_context) else {
+        return RelayAgentInfo::default();
+    };
+    let Some(relay) = context
+        .get("ISC")
+        .and_then(|isc| isc.get("relay-agent-info"))
+    else {
+        return RelayAgentInfo::default();
+    };
+    let sub_options = relay
+        .as_str()
+        .or_else(|| relay.get("sub-options").and_then(|value| value.as_str()));
+    let mut info = sub_options
+        .and_then(decode_hex)
+        .map(|bytes| RelayAgentInfo::from_sub_options(&bytes))
+        .unwrap_or_default();
+    if info.remote_id.is_none()
+        && let Some(remote_id) = relay
+            .get("remote-id")
+            .and_then(|value| value.as_str())
+            .and_then(decode_hex)
+    {
+        info.remote_id = Some(display_bytes(&remote_id));
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,82 @@
+use super::*;
+
+const V4_HEADER: &str = "address,hwaddr,client_id,valid_lifetime,expire,subnet_id,fqdn_fwd,fqdn_rev,hostname,state,user_context,pool_id";
+const V6_HEADER: &str = "address,duid,valid_lifetime,expire,subnet_id,pref_lifetime,lease_type,iaid,prefix_len,fqdn_fwd,fqdn_rev,hostname,hwaddr,state,user_context,hwtype,hwaddr_source,pool_id";
+
+fn file(header: &str, rows: &[&str]) -> String {
+    let mut text = format!("{header}\n");
+    for row in rows {
+        text.push_str(row);
+        text.push('\n');
+    }
+    text
+}
+
+#[test]
+fn v4_rows_replay_to_the_latest_lease_with_relay_info() {
+    let text = file(
+        V4_HEADER,
+        &[
+            "100.64.0.10,aa:bb:cc:dd:ee:01,01:aa:bb:cc:dd:ee:01,3600,1700003600,1,0,0,cpe-1,0,,0",
+            "100.64.0.11,aa:bb:cc:dd:ee:02,,3600,1700003600,1,0,0,,0,{ \"ISC\": { \"relay-agent-info\": { \"sub-options\": \"0x0106504F4E2D31300209414243313233343536\"&#x2c; \"remote-id\": \"414243313233343536\" } } },0",
+            "100.64.0.12,aa:bb:cc:dd:ee:03,,3600,1700003600,1,0,0,gone,0,,0",
+            "100.64.0.12,aa:bb:cc:dd:ee:03,,0,1700000000,1,0,0,gone,0,,0",
+            "100.64.0.13,aa:bb:cc:dd:ee:04,,3600,1700003600,1,0,0,,2,,0",
+            "100.64.0.10,aa:bb:cc:dd:ee:01,01:aa:bb:cc:dd:ee:01,4294967295,1700007200,1,0,0,cpe-1,0,,0",
+            "not an address,aa:bb:cc:dd:ee:05,,3600,1700003600,1,0,0,,0,,0",
+        ],
+    );
+    let parsed = parse_kea_memfile(&text).unwrap();
+    assert_eq!(parsed.skipped_rows, 1);
+    assert_eq!(parsed.leases.len(), 2);
+
+    let first = &parsed.leases[0];
+    assert_eq!(first.key.address.to_string(), "100.64.0.10");
+    assert_eq!(first.key.prefix_len, 32);
+    assert_eq!(first.mac.as_deref(), Some("aabbccddee01"));
+    assert_eq!(first.client_id.as_deref(), Some("01:aa:bb:cc:dd:ee:01"));
+    assert_eq!(first.hostname.as_deref(), Some("cpe-1"));
+    assert_eq!(first.expires_unix, None);
+
+    let relayed = &parsed.leases[1];
+    assert_eq!(relayed.relay.circuit_id.as_deref(), Some("PON-10"));
+    assert_eq!(relayed.relay.remote_id.as_deref(), Some("ABC123456"));
+    assert_eq!(relayed.expires_unix, Some(1_700_003_600));
+}
+
+#[test]
+fn v6_rows_include_delegated_prefixes() {
+    let text = file(
+        V6_HEADER,
+        &[
+            "2001:db8:1::10,00:03:00:01:aa:bb:cc:dd:ee:01,3600,1700003600,1,1800,0,1,128,0,0,,aa:bb:cc:dd:ee:01,0,,1,2,0",
+            "2001:db8:100::,00:03:00:01:aa:bb:cc:dd:ee:01,3600,1700003600,1,1800,2,2,56,0,0,,aa:bb:cc:dd:ee:01,0,,1,2,0",
+            "2001:db8:200::,00:03:00:01:aa:bb:cc:dd:ee:02,3600,1700003600,1,1800,2,2,,0,0,,,0,,1,2,0",
+        ],
+    );
+    let parsed = parse_kea_memfile(&text).unwrap();
+    assert_eq!(parsed.skipped_rows, 1);
+    assert_eq!(parsed.leases.len(), 2);
+    assert_eq!(parsed.leases[0].kind, LeaseKind::V6Address);
+    assert_eq!(parsed.leases[0].key.prefix_len, 128);
+    let prefix = &parsed.leases[1];
+    assert_eq!(prefix.kind, LeaseKind::V6Prefix);
+    assert_eq!(prefix.key.prefix_len, 56);
+    assert_eq!(prefix.mac.as_deref(), Some("aabbccddee01"));
+    assert_eq!(
+        prefix.client_id.as_deref(),
+        Some("00:03:00:01:aa:bb:cc:dd:ee:01")
+    );
+}
+
+#[test]
+fn rejects_files_without_a_header() {
+    assert_eq!(
+        parse_kea_memfile("100.64.0.10,aa:bb:cc:dd:ee:01,,3600,1700003600"),
+        Err(LeaseFileError::MissingHeader)
+    );
+    assert_eq!(
+        parse_kea_memfile("address,hwaddr,expire\n"),
+        Err(LeaseFileError::MissingColumn("valid_lifetime"))
+    );
+}
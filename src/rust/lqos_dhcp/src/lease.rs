@@ -0,0 +1,208 @@
+//! DHCP leases as read from lease files and hook events.
+
+use std::net::IpAddr;
+
+/// What a lease hands out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LeaseKind {
+    /// A DHCPv4 address.
+    V4,
+    /// A DHCPv6 IA_NA or IA_TA address.
+    V6Address,
+    /// A DHCPv6 delegated prefix (IA_PD).
+    V6Prefix,
+}
+
+/// The address or prefix a lease covers, which identifies it within a server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LeaseKey {
+    /// Leased address, or the first address of a delegated prefix.
+    pub address: IpAddr,
+    /// Prefix length: 32 for DHCPv4, 128 for DHCPv6 addresses.
+    pub prefix_len: u8,
+}
+
+/// DHCPv4 relay agent information (option 82) carried with a lease.
+///
+/// Values that are printable ASCII are kept as text; anything else is kept as
+/// colon-separated lowercase hex, e.g. `00:04:0a:01`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RelayAgentInfo {
+    /// Sub-option 1, agent circuit-id.
+    pub circuit_id: Option<String>,
+    /// Sub-option 2, agent remote-id.
+    pub remote_id: Option<String>,
+}
+
+impl RelayAgentInfo {
+    /// Decodes the sub-options of an option 82 payload. Malformed trailing
+    /// bytes are ignored.
+    #[must_use]
+    pub fn from_sub_options(mut bytes: &[u8]) -> Self {
+        let mut info = Self::default();
+        while bytes.len() >= 2 {
+            let (code, len) = (bytes[0], usize::from(bytes[1]));
+            let Some(value) = bytes.get(2..2 + len) else {
+                break;
+            };
+            match code {
+                1 => info.circuit_id = Some(display_bytes(value)),
+                2 => info.remote_id = Some(display_bytes(value)),
+                _ => {}
+            }
+            bytes = &bytes[2 + len..];
+        }
+        info
+    }
+
+    /// Returns true when neither sub-option is present.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.circuit_id.is_none() && self.remote_id.is_none()
+    }
+}
+
+/// One DHCP lease.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DhcpLease {
+    /// Address or delegated prefix.
+    pub key: LeaseKey,
+    /// Address, DHCPv6 address or delegated prefix.
+    pub kind: LeaseKind,
+    /// Client hardware address, normalized to twelve lowercase hex digits.
+    pub mac: Option<String>,
+    /// DHCPv4 client-id or DHCPv6 DUID as colon-separated lowercase hex.
+    pub client_id: Option<String>,
+    /// Hostname the client asked for or was given.
+    pub hostname: Option<String>,
+    /// Option 82 values the relay added to the client's request.
+    pub relay: RelayAgentInfo,
+    /// Unix time the lease ends. `None` never expires.
+    pub expires_unix: Option<u64>,
+}
+
+impl DhcpLease {
+    /// Returns true while the lease has not expired at `now_unix`.
+    #[must_use]
+    pub fn is_active(&self, now_unix: u64) -> bool {
+        self.expires_unix.is_none_or(|expires| expires > now_unix)
+    }
+}
+
+/// Active leases read from one lease file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LeaseFile {
+    /// The current lease for each address or prefix, ordered by address.
+    pub leases: Vec<DhcpLease>,
+    /// Rows that could not be parsed and were skipped.
+    pub skipped_rows: usize,
+}
+
+/// Normalizes common MAC address formats to twelve lowercase hex digits.
+///
+/// Accepts colon-, hyphen- and dot-separated forms as well as plain hex.
+#[must_use]
+pub fn normalize_mac(raw_mac: &str) -> Option<String> {
+    let raw_mac = raw_mac.trim();
+    let (separator, groups, group_len) = if raw_mac.contains(':') {
+        (':', 6, 2)
+    } else if raw_mac.contains('-') {
+        ('-', 6, 2)
+    } else if raw_mac.contains('.') {
+        ('.', 3, 4)
+    } else {
+        (' ', 1, 12)
+    };
+    let parts: Vec<&str> = raw_mac.split(separator).collect();
+    if parts.len() != groups
+        || parts
+            .iter()
+            .any(|part| part.len() != group_len || !part.chars().all(|ch| ch.is_ascii_hexdigit()))
+    {
+        return None;
+    }
+    Some(parts.concat().to_ascii_lowercase())
+}
+
+/// Formats a normalized MAC as `aa:bb:cc:dd:ee:ff`.
+#[must_use]
+pub fn display_mac(normalized: &str) -> String {
+    normalized
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| String::from_utf8_lossy(pair).into_owned())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Decodes hex written as `0x0104..`, `01:04:..`, `01-04-..` or `0104..`.
+pub(crate) fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    let digits: String = text.chars().filter(|ch| *ch != ':' && *ch != '-').collect();
+    if digits.is_empty() || !digits.is_ascii() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).ok())
+        .collect()
+}
+
+/// Canonical colon-separated lowercase hex for client identifiers.
+pub(crate) fn canonical_hex(text: &str) -> Option<String> {
+    decode_hex(text).map(|bytes| hex_string(&bytes))
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Printable ASCII as text, anything else as colon-separated hex.
+pub(crate) fn display_bytes(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text)
+            if !text.is_empty() && text.chars().all(|ch| ch.is_ascii_graphic() || ch == ' ') =>
+        {
+            text.to_string()
+        }
+        _ => hex_string(value),
+    }
+}
+
+/// Treats empty strings and the `*` placeholder as missing.
+pub(crate) fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty() && value != "*").then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_relay_sub_options_as_text_or_hex() {
+        let bytes = decode_hex("0x010b657468312f312f313a3130020600040a01aabb").unwrap();
+        let info = RelayAgentInfo::from_sub_options(&bytes);
+        assert_eq!(info.circuit_id.as_deref(), Some("eth1/1/1:10"));
+        assert_eq!(info.remote_id.as_deref(), Some("00:04:0a:01:aa:bb"));
+
+        assert_eq!(
+            normalize_mac("AA-BB-CC-DD-EE-FF").as_deref(),
+            Some("aabbccddeeff")
+        );
+        assert_eq!(
+            normalize_mac("aabb.ccdd.eeff").as_deref(),
+            Some("aabbccddeeff")
+        );
+        assert_eq!(normalize_mac("1-aa:bb:cc:dd:ee:ff"), None);
+        assert_eq!(display_mac("aabbccddeeff"), "aa:bb:cc:dd:ee:ff");
+    }
+}
@@ -0,0 +1,29 @@
+//! DHCP lease ingestion for LibreQoS circuits.
+//!
+//! The crate parses ISC Kea memfile and dnsmasq lease files and JSON lease
+//! events pushed by a DHCP server hook, keeps the combined set of active
+//! leases, and resolves them into in-memory shaped-device definitions: leases
+//! whose MAC, option 82 or client identity matches a `ShapedDevices.csv` row
+//! join that row's circuit, and other leases become dynamic circuits with the
+//! rates of the first matching range rule. Applying the circuits is left to
+//! the caller.
+
+#![warn(missing_docs)]
+
+mod dnsmasq;
+mod hook;
+mod kea;
+mod lease;
+mod resolve;
+mod table;
+
+pub use dnsmasq::parse_dnsmasq_leases;
+pub use hook::{HookAction, HookEvent, HookEventError};
+pub use kea::{LeaseFileError, parse_kea_memfile};
+pub use lease::{
+    DhcpLease, LeaseFile, LeaseKey, LeaseKind, RelayAgentInfo, display_mac, normalize_mac,
+};
+pub use resolve::{
+    LeaseMatchOptions, LeaseResolution, LeaseResolver, UnresolvedLease, UnresolvedReason,
+};
+pub use table::LeaseTable;
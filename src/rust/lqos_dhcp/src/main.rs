@@ -0,0 +1,103 @@
+//! Diagnostic executable for LibreQoS DHCP lease ingestion.
+
+use clap::Parser;
+use lqos_dhcp::{DhcpLease, HookEvent, LeaseTable, parse_dnsmasq_leases, parse_kea_memfile};
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Parser)]
+#[command(
+    name = "lqos_dhcp",
+    about = "Show the active leases LibreQoS would read from DHCP lease files or hook events."
+)]
+struct Args {
+    /// Kea memfile lease file (DHCPv4 or DHCPv6).
+    #[arg(long = "kea-memfile", value_name = "PATH")]
+    kea_memfiles: Vec<PathBuf>,
+    /// dnsmasq lease file.
+    #[arg(long = "dnsmasq", value_name = "PATH")]
+    dnsmasq_files: Vec<PathBuf>,
+    /// Listen on a Unix socket and print each hook event as it arrives.
+    #[arg(long, value_name = "PATH")]
+    hook_socket: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let now = now_unix();
+    let mut table = LeaseTable::new();
+    let mut source = 0;
+    for path in &args.kea_memfiles {
+        let file = parse_kea_memfile(&std::fs::read_to_string(path)?)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        report_file(path, file.leases.len(), file.skipped_rows);
+        table.replace_file(source, file.leases);
+        source += 1;
+    }
+    for path in &args.dnsmasq_files {
+        let file = parse_dnsmasq_leases(&std::fs::read_to_string(path)?);
+        report_file(path, file.leases.len(), file.skipped_rows);
+        table.replace_file(source, file.leases);
+        source += 1;
+    }
+    for lease in table.active_leases(now) {
+        print_lease(&lease, now);
+    }
+
+    let Some(socket) = args.hook_socket else {
+        return Ok(());
+    };
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+    println!("Listening for lease events on {}", socket.display());
+    for stream in listener.incoming() {
+        for line in BufReader::new(stream?).lines() {
+            let line = line?;
+            match HookEvent::parse(&line).and_then(|event| {
+                let lease = event.lease(now_unix())?;
+                Ok((event.action, lease))
+            }) {
+                Ok((action, lease)) => {
+                    print!("{action:?}: ");
+                    print_lease(&lease, now_unix());
+                }
+                Err(err) => println!("Rejected {line:?}: {err}"),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn report_file(path: &std::path::Path, leases: usize, skipped_rows: usize) {
+    println!(
+        "{}: {leases} current lease(s), {skipped_rows} unreadable row(s)",
+        path.display()
+    );
+}
+
+fn print_lease(lease: &DhcpLease, now: u64) {
+    let expires = match lease.expires_unix {
+        None => "never expires".to_string(),
+        Some(expires) if expires > now => format!("expires in {}s", expires - now),
+        Some(_) => "expired".to_string(),
+    };
+    println!(
+        "{}/{} {:?} mac={} client_id={} circuit_id={} remote_id={} hostname={} {expires}",
+        lease.key.address,
+        lease.key.prefix_len,
+        lease.kind,
+        lease.mac.as_deref().unwrap_or("-"),
+        lease.client_id.as_deref().unwrap_or("-"),
+        lease.relay.circuit_id.as_deref().unwrap_or("-"),
+        lease.relay.remote_id.as_deref().unwrap_or("-"),
+        lease.hostname.as_deref().unwrap_or("-"),
+    );
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
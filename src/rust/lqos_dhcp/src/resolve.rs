@@ -0,0 +1,270 @@
+//! Maps active leases onto `ShapedDevices.csv` circuits or rule-based dynamic circuits.
+
+use crate::lease::{DhcpLease, LeaseKey, display_mac, normalize_mac};
+use ip_network::IpNetwork;
+use lqos_config::{DynamicCircuitRangeRule, ShapedDevice};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+
+/// Which lease identities are matched against the `ShapedDevices.csv` MAC field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LeaseMatchOptions {
+    /// Match the normalized hardware address.
+    pub by_mac: bool,
+    /// Match option 82 circuit-id, then remote-id, verbatim.
+    pub by_option82: bool,
+    /// Match the client-id or DUID verbatim, as colon-separated lowercase hex.
+    pub by_client_id: bool,
+}
+
+/// Why a lease was not applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnresolvedReason {
+    /// The lease identity matched more than one `ShapedDevices.csv` row.
+    AmbiguousMatch,
+    /// The lease matched no row and its address is in no rule's range.
+    NoMatchingRule,
+}
+
+/// A lease that was left out of the resolved circuits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnresolvedLease {
+    /// The lease's address or prefix.
+    pub key: LeaseKey,
+    /// Why it was left out.
+    pub reason: UnresolvedReason,
+}
+
+/// Circuits built from the current leases.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LeaseResolution {
+    /// One dynamic circuit per circuit ID, ordered by circuit ID.
+    pub circuits: Vec<ShapedDevice>,
+    /// Leases that could not be placed.
+    pub unresolved: Vec<UnresolvedLease>,
+}
+
+#[derive(Clone, Debug)]
+enum Candidate {
+    Unique(usize),
+    Ambiguous,
+}
+
+enum Identity {
+    Matched(usize),
+    Ambiguous,
+    Unmatched,
+}
+
+/// Resolves leases against `ShapedDevices.csv` rows and dynamic circuit rules.
+#[derive(Clone, Debug)]
+pub struct LeaseResolver {
+    devices: Vec<ShapedDevice>,
+    by_mac: HashMap<String, Candidate>,
+    by_text: HashMap<String, Candidate>,
+    rules: Vec<DynamicCircuitRangeRule>,
+    options: LeaseMatchOptions,
+}
+
+impl LeaseResolver {
+    /// Indexes `devices` by MAC field. Valid MAC addresses are indexed
+    /// normalized; every non-empty value is also indexed verbatim for option 82
+    /// and client-id matching.
+    #[must_use]
+    pub fn new(
+        devices: &[ShapedDevice],
+        rules: Vec<DynamicCircuitRangeRule>,
+        options: LeaseMatchOptions,
+    ) -> Self {
+        let mut by_mac = HashMap::new();
+        let mut by_text = HashMap::new();
+        let insert = |index: &mut HashMap<String, Candidate>, key: String, row: usize| {
+            index
+                .entry(key)
+                .and_modify(|candidate| *candidate = Candidate::Ambiguous)
+                .or_insert(Candidate::Unique(row));
+        };
+        for (row, device) in devices.iter().enumerate() {
+            if let Some(mac) = normalize_mac(&device.mac) {
+                insert(&mut by_mac, mac, row);
+            }
+            let text = device.mac.trim();
+            if !text.is_empty() {
+                insert(&mut by_text, text.to_string(), row);
+            }
+        }
+        Self {
+            devices: devices.to_vec(),
+            by_mac,
+            by_text,
+            rules,
+            options,
+        }
+    }
+
+    /// Builds circuits for `leases`.
+    ///
+    /// A lease whose identity matches one `ShapedDevices.csv` row adds its
+    /// address to that row's circuit, keeping the row's parent and rates.
+    /// Other leases are grouped by MAC, then client-id, and placed by the
+    /// first rule whose range contains one of the group's addresses.
+    ///
+    /// Side effects: none.
+    #[must_use]
+    pub fn resolve(&self, leases: &[DhcpLease]) -> LeaseResolution {
+        let mut matched: BTreeMap<String, ShapedDevice> = BTreeMap::new();
+        let mut unmatched: BTreeMap<String, Vec<&DhcpLease>> = BTreeMap::new();
+        let mut unresolved = Vec::new();
+
+        for lease in leases {
+            match self.identify(lease) {
+                Identity::Matched(row) => {
+                    let device = &self.devices[row];
+                    let circuit =
+                        matched
+                            .entry(device.circuit_id.clone())
+                            .or_insert_with(|| ShapedDevice {
+                                ipv4: Vec::new(),
+                                ipv6: Vec::new(),
+                                ..device.clone()
+                            });
+                    add_address(circuit, lease);
+                }
+                Identity::Ambiguous => unresolved.push(UnresolvedLease {
+                    key: lease.key,
+                    reason: UnresolvedReason::AmbiguousMatch,
+                }),
+                Identity::Unmatched => unmatched
+                    .entry(subscriber_circuit_id(lease))
+                    .or_default()
+                    .push(lease),
+            }
+        }
+
+        for (circuit_id, group) in unmatched {
+            let Some(rule) = group.iter().find_map(|lease| self.rule_for(lease)) else {
+                unresolved.extend(group.iter().map(|lease| UnresolvedLease {
+                    key: lease.key,
+                    reason: UnresolvedReason::NoMatchingRule,
+                }));
+                continue;
+            };
+            let mut circuit = rule_circuit(circuit_id.clone(), rule, &group);
+            for lease in &group {
+                add_address(&mut circuit, lease);
+            }
+            matched.insert(circuit_id, circuit);
+        }
+
+        for circuit in matched.values_mut() {
+            circuit.ipv4.sort();
+            circuit.ipv4.dedup();
+            circuit.ipv6.sort();
+            circuit.ipv6.dedup();
+        }
+        LeaseResolution {
+            circuits: matched.into_values().collect(),
+            unresolved,
+        }
+    }
+
+    fn identify(&self, lease: &DhcpLease) -> Identity {
+        let mut candidates: Vec<Option<&Candidate>> = Vec::new();
+        if self.options.by_option82 {
+            candidates.push(
+                lease
+                    .relay
+                    .circuit_id
+                    .as_ref()
+                    .and_then(|id| self.by_text.get(id)),
+            );
+            candidates.push(
+                lease
+                    .relay
+                    .remote_id
+                    .as_ref()
+                    .and_then(|id| self.by_text.get(id)),
+            );
+        }
+        if self.options.by_mac {
+            candidates.push(lease.mac.as_ref().and_then(|mac| self.by_mac.get(mac)));
+        }
+        if self.options.by_client_id {
+            candidates.push(lease.client_id.as_ref().and_then(|id| self.by_text.get(id)));
+        }
+        match candidates.into_iter().flatten().next() {
+            Some(Candidate::Unique(row)) => Identity::Matched(*row),
+            Some(Candidate::Ambiguous) => Identity::Ambiguous,
+            None => Identity::Unmatched,
+        }
+    }
+
+    fn rule_for(&self, lease: &DhcpLease) -> Option<&DynamicCircuitRangeRule> {
+        self.rules
+            .iter()
+            .find(|rule| range_contains(&rule.ip_range, lease.key.address))
+    }
+}
+
+fn range_contains(range: &IpNetwork, address: IpAddr) -> bool {
+    match (range, address) {
+        (IpNetwork::V4(range), IpAddr::V4(address)) => range.contains(address),
+        (IpNetwork::V6(range), IpAddr::V6(address)) => range.contains(address),
+        _ => false,
+    }
+}
+
+/// Stable circuit ID for a subscriber that is not in `ShapedDevices.csv`.
+fn subscriber_circuit_id(lease: &DhcpLease) -> String {
+    if let Some(mac) = &lease.mac {
+        format!("dhcp:mac:{mac}")
+    } else if let Some(client_id) = &lease.client_id {
+        format!("dhcp:client-id:{}", client_id.replace(':', ""))
+    } else {
+        format!("dhcp:address:{}", lease.key.address)
+    }
+}
+
+fn rule_circuit(
+    circuit_id: String,
+    rule: &DynamicCircuitRangeRule,
+    group: &[&DhcpLease],
+) -> ShapedDevice {
+    let hostname = group.iter().find_map(|lease| lease.hostname.clone());
+    let mac = group
+        .iter()
+        .find_map(|lease| lease.mac.as_deref())
+        .map(display_mac)
+        .unwrap_or_default();
+    let name = hostname
+        .or_else(|| (!mac.is_empty()).then(|| mac.clone()))
+        .unwrap_or_else(|| circuit_id.clone());
+    ShapedDevice {
+        circuit_name: name.clone(),
+        device_id: circuit_id.clone(),
+        device_name: name,
+        circuit_id,
+        parent_node: rule.attach_to.clone(),
+        mac,
+        download_min_mbps: rule.download_min_mbps,
+        upload_min_mbps: rule.upload_min_mbps,
+        download_max_mbps: rule.download_max_mbps,
+        upload_max_mbps: rule.upload_max_mbps,
+        comment: format!("DHCP rule {}", rule.name),
+        ..ShapedDevice::default()
+    }
+}
+
+fn add_address(circuit: &mut ShapedDevice, lease: &DhcpLease) {
+    match lease.key.address {
+        IpAddr::V4(address) => circuit
+            .ipv4
+            .push((address, u32::from(lease.key.prefix_len))),
+        IpAddr::V6(address) => circuit
+            .ipv6
+            .push((address, u32::from(lease.key.prefix_len))),
+    }
+}
+
+#[cfg(test)]
+mod tests;
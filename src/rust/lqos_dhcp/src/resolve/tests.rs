@@ -0,0 +1,139 @@
+use super::*;
+use crate::lease::{LeaseKind, RelayAgentInfo};
+
+fn device(circuit_id: &str, device_id: &str, mac: &str) -> ShapedDevice {
+    ShapedDevice {
+        circuit_id: circuit_id.to_string(),
+        circuit_name: format!("Circuit {circuit_id}"),
+        device_id: device_id.to_string(),
+        device_name: format!("Device {device_id}"),
+        parent_node: "Tower-A".to_string(),
+        mac: mac.to_string(),
+        ipv4: vec![("198.51.100.1".parse().unwrap(), 32)],
+        download_min_mbps: 10.0,
+        upload_min_mbps: 2.0,
+        download_max_mbps: 100.0,
+        upload_max_mbps: 20.0,
+        ..ShapedDevice::default()
+    }
+}
+
+fn lease(address: &str, prefix_len: u8, mac: Option<&str>) -> DhcpLease {
+    let address: IpAddr = address.parse().unwrap();
+    DhcpLease {
+        key: LeaseKey {
+            address,
+            prefix_len,
+        },
+        kind: match (address.is_ipv4(), prefix_len) {
+            (true, _) => LeaseKind::V4,
+            (false, 128) => LeaseKind::V6Address,
+            (false, _) => LeaseKind::V6Prefix,
+        },
+        mac: mac.map(str::to_string),
+        client_id: None,
+        hostname: None,
+        relay: RelayAgentInfo::default(),
+        expires_unix: None,
+    }
+}
+
+fn rule(name: &str, range: &str, attach_to: &str) -> DynamicCircuitRangeRule {
+    DynamicCircuitRangeRule {
+        name: name.to_string(),
+        ip_range: range.parse().unwrap(),
+        download_min_mbps: 5.0,
+        upload_min_mbps: 1.0,
+        download_max_mbps: 50.0,
+        upload_max_mbps: 10.0,
+        attach_to: attach_to.to_string(),
+    }
+}
+
+const ALL: LeaseMatchOptions = LeaseMatchOptions {
+    by_mac: true,
+    by_option82: true,
+    by_client_id: true,
+};
+
+#[test]
+fn matched_leases_take_the_row_and_replace_its_addresses() {
+    let resolver = LeaseResolver::new(
+        &[
+            device("c1", "d1", "AA:BB:CC:DD:EE:01"),
+            device("c2", "d2", "PON-10"),
+        ],
+        Vec::new(),
+        ALL,
+    );
+    let mut relayed = lease("100.64.0.20", 32, Some("aabbccddee99"));
+    relayed.relay.circuit_id = Some("PON-10".to_string());
+    let resolution = resolver.resolve(&[
+        lease("100.64.0.10", 32, Some("aabbccddee01")),
+        lease("2001:db8:100::", 56, Some("aabbccddee01")),
+        relayed,
+    ]);
+
+    assert!(resolution.unresolved.is_empty());
+    assert_eq!(resolution.circuits.len(), 2);
+    let c1 = &resolution.circuits[0];
+    assert_eq!(c1.circuit_id, "c1");
+    assert_eq!(c1.parent_node, "Tower-A");
+    assert_eq!(c1.download_max_mbps, 100.0);
+    assert_eq!(c1.ipv4, vec![("100.64.0.10".parse().unwrap(), 32)]);
+    assert_eq!(c1.ipv6, vec![("2001:db8:100::".parse().unwrap(), 56)]);
+    // Option 82 wins over the lease's own MAC.
+    assert_eq!(resolution.circuits[1].circuit_id, "c2");
+}
+
+#[test]
+fn unmatched_leases_become_rule_circuits_grouped_by_subscriber() {
+    let resolver = LeaseResolver::new(
+        &[
+            device("c1", "d1", "aa:bb:cc:dd:ee:01"),
+            device("c2", "d2", "aa:bb:cc:dd:ee:01"),
+        ],
+        vec![
+            rule("Residential", "100.64.0.0/10", "Tower-B"),
+            rule("Residential v6", "2001:db8::/32", "Tower-B"),
+        ],
+        ALL,
+    );
+    let mut named = lease("100.64.0.30", 32, Some("aabbccddee02"));
+    named.hostname = Some("cpe-2".to_string());
+    let resolution = resolver.resolve(&[
+        lease("100.64.0.10", 32, Some("aabbccddee01")),
+        named,
+        lease("2001:db8:200::", 56, Some("aabbccddee02")),
+        lease("192.0.2.10", 32, Some("aabbccddee03")),
+    ]);
+
+    assert_eq!(
+        resolution.unresolved,
+        vec![
+            UnresolvedLease {
+                key: LeaseKey {
+                    address: "100.64.0.10".parse().unwrap(),
+                    prefix_len: 32,
+                },
+                reason: UnresolvedReason::AmbiguousMatch,
+            },
+            UnresolvedLease {
+                key: LeaseKey {
+                    address: "192.0.2.10".parse().unwrap(),
+                    prefix_len: 32,
+                },
+                reason: UnresolvedReason::NoMatchingRule,
+            },
+        ]
+    );
+    assert_eq!(resolution.circuits.len(), 1);
+    let circuit = &resolution.circuits[0];
+    assert_eq!(circuit.circuit_id, "dhcp:mac:aabbccddee02");
+    assert_eq!(circuit.circuit_name, "cpe-2");
+    assert_eq!(circuit.mac, "aa:bb:cc:dd:ee:02");
+    assert_eq!(circuit.parent_node, "Tower-B");
+    assert_eq!(circuit.download_max_mbps, 50.0);
+    assert_eq!(circuit.ipv4.len(), 1);
+    assert_eq!(circuit.ipv6, vec![("2001:db8:200::".parse().unwrap(), 56)]);
+}
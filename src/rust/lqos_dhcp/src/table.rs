@@ -0,0 +1,144 @@
+//! The combined set of leases from every file and hook event.
+
+use crate::hook::{HookEvent, HookEventError};
+use crate::lease::{DhcpLease, LeaseKey};
+use std::collections::BTreeMap;
+
+/// Current leases from every configured source.
+///
+/// Hook events take precedence over lease files for the same address: a
+/// committed lease is used until it expires, and a released one hides the
+/// file's copy until the file stops listing it.
+#[derive(Clone, Debug, Default)]
+pub struct LeaseTable {
+    files: BTreeMap<usize, Vec<DhcpLease>>,
+    hooks: BTreeMap<LeaseKey, Option<DhcpLease>>,
+}
+
+impl LeaseTable {
+    /// Creates an empty table.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces everything previously read from lease file `source`.
+    pub fn replace_file(&mut self, source: usize, leases: Vec<DhcpLease>) {
+        self.files.insert(source, leases);
+    }
+
+    /// Records one hook event received at `now_unix`.
+    pub fn apply_hook(&mut self, event: &HookEvent, now_unix: u64) -> Result<(), HookEventError> {
+        let key = event.key()?;
+        let lease = if event.ends_lease() {
+            None
+        } else {
+            Some(event.lease(now_unix)?)
+        };
+        self.hooks.insert(key, lease);
+        Ok(())
+    }
+
+    /// Leases that are active at `now_unix`, ordered by address.
+    #[must_use]
+    pub fn active_leases(&self, now_unix: u64) -> Vec<DhcpLease> {
+        let mut merged: BTreeMap<LeaseKey, &DhcpLease> = BTreeMap::new();
+        for lease in self.files.values().flatten() {
+            if !lease.is_active(now_unix) {
+                continue;
+            }
+            let longer = merged.get(&lease.key).is_none_or(|existing| {
+                match (existing.expires_unix, lease.expires_unix) {
+                    (_, None) => true,
+                    (None, Some(_)) => false,
+                    (Some(existing), Some(candidate)) => candidate > existing,
+                }
+            });
+            if longer {
+                merged.insert(lease.key, lease);
+            }
+        }
+        for (key, entry) in &self.hooks {
+            match entry {
+                Some(lease) if lease.is_active(now_unix) => {
+                    merged.insert(*key, lease);
+                }
+                Some(_) => {}
+                None => {
+                    merged.remove(key);
+                }
+            }
+        }
+        merged.into_values().cloned().collect()
+    }
+
+    /// Drops expired hook leases, and releases that no longer hide a file lease.
+    pub fn prune(&mut self, now_unix: u64) {
+        let files = &self.files;
+        self.hooks.retain(|key, entry| match entry {
+            Some(lease) => lease.is_active(now_unix),
+            None => files
+                .values()
+                .flatten()
+                .any(|lease| lease.key == *key && lease.is_active(now_unix)),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lease::LeaseKind;
+
+    fn lease(address: &str, expires_unix: Option<u64>) -> DhcpLease {
+        DhcpLease {
+            key: LeaseKey {
+                address: address.parse().unwrap(),
+                prefix_len: 32,
+            },
+            kind: LeaseKind::V4,
+            mac: None,
+            client_id: None,
+            hostname: None,
+            relay: Default::default(),
+            expires_unix,
+        }
+    }
+
+    fn addresses(table: &LeaseTable, now_unix: u64) -> Vec<String> {
+        table
+            .active_leases(now_unix)
+            .iter()
+            .map(|lease| lease.key.address.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn hook_events_override_files_until_they_expire_or_the_file_catches_up() {
+        let mut table = LeaseTable::new();
+        table.replace_file(
+            0,
+            vec![lease("192.0.2.1", Some(200)), lease("192.0.2.2", None)],
+        );
+        table.replace_file(1, vec![lease("192.0.2.1", Some(500))]);
+        let event = |line: &str| HookEvent::parse(line).unwrap();
+        table
+            .apply_hook(&event(r#"{"action":"release","address":"192.0.2.2"}"#), 100)
+            .unwrap();
+        table
+            .apply_hook(
+                &event(r#"{"action":"commit","address":"192.0.2.3","valid_lifetime":100}"#),
+                100,
+            )
+            .unwrap();
+        assert_eq!(addresses(&table, 100), ["192.0.2.1", "192.0.2.3"]);
+        assert_eq!(table.active_leases(100)[0].expires_unix, Some(500));
+
+        table.prune(300);
+        assert_eq!(addresses(&table, 300), ["192.0.2.1"]);
+        table.replace_file(0, Vec::new());
+        table.prune(300);
+        assert!(table.hooks.is_empty());
+        assert_eq!(addresses(&table, 600), Vec::<String>::new());
+    }
+}
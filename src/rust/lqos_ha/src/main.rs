@@ -0,0 +1,127 @@
+//! `lqos_ha` shows and controls active/standby replication on the local shaper.
+
+use anyhow::{Result, bail};
+use clap::{Parser, Subcommand};
+use lqos_bus::{BusRequest, BusResponse, HaRole, HaStatus, bus_request};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Parser)]
+#[command()]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Show this node's role, its peer and replication health
+    Status,
+    /// Make this node active; the peer steps down when it sees the promotion
+    Promote,
+}
+
+fn send(request: BusRequest) -> Result<HaStatus> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let responses = runtime.block_on(bus_request(vec![request]))?;
+    match responses.into_iter().next() {
+        Some(BusResponse::HaStatus(status)) => Ok(status),
+        Some(BusResponse::Fail(message)) => bail!(message),
+        other => bail!("Unexpected response from lqosd: {other:?}"),
+    }
+}
+
+fn role_label(role: HaRole) -> &'static str {
+    match role {
+        HaRole::Disabled => "disabled",
+        HaRole::Active => "active",
+        HaRole::Standby => "standby",
+    }
+}
+
+fn print_status(status: &HaStatus) {
+    if !status.enabled {
+        println!("HA replication is not enabled.");
+        if let Some(error) = &status.last_error {
+            println!("Last error: {error}");
+        }
+        return;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+
+    println!(
+        "This node: {} {} (epoch {}), {}",
+        status.node_name,
+        role_label(status.role),
+        status.epoch,
+        if status.carrying_traffic {
+            "carrying traffic"
+        } else {
+            "idle"
+        }
+    );
+    let peer_name = status.peer_name.as_deref().unwrap_or("unknown");
+    match (status.peer_connected, status.peer_role, status.peer_epoch) {
+        (true, Some(role), Some(epoch)) => println!(
+            "Peer:      {peer_name} at {} {} (epoch {epoch}), {}",
+            status.peer_address,
+            role_label(role),
+            if status.peer_carrying_traffic == Some(true) {
+                "carrying traffic"
+            } else {
+                "idle"
+            }
+        ),
+        _ => match status.peer_last_seen_unix {
+            Some(seen) => println!(
+                "Peer:      {peer_name} at {} not heard from for {}s",
+                status.peer_address,
+                now.saturating_sub(seen)
+            ),
+            None => println!("Peer:      {} never connected", status.peer_address),
+        },
+    }
+
+    println!("\nComponent                  In sync  Last applied");
+    for component in &status.components {
+        let in_sync = match &component.peer_hash {
+            Some(peer_hash) if *peer_hash == component.hash => "yes",
+            Some(_) => "no",
+            None => "-",
+        };
+        let applied = component
+            .last_applied_unix
+            .map(|at| format!("{}s ago", now.saturating_sub(at)))
+            .unwrap_or_else(|| "-".to_string());
+        println!("{:<26} {in_sync:<8} {applied}", component.name);
+    }
+
+    println!(
+        "\nRADIUS packets sent {}, applied {}; conflicts {}",
+        status.radius_packets_sent, status.radius_packets_applied, status.conflicts
+    );
+    if let Some(conflict) = &status.last_conflict {
+        println!("Last role change or conflict: {conflict}");
+    }
+    if let Some(error) = &status.last_error {
+        println!("Last error: {error}");
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Args::parse();
+    match cli.command {
+        Some(Commands::Status) => print_status(&send(BusRequest::GetHaStatus)?),
+        Some(Commands::Promote) => {
+            let status = send(BusRequest::HaPromote)?;
+            println!("Promotion requested.\n");
+            print_status(&status);
+        }
+        None => println!("Run with --help to see instructions"),
+    }
+    Ok(())
+}
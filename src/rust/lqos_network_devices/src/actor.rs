@@ -301,6 +301,9 @@ struct ShapedDeviceSemanticKey {
     upload_max_mbps_bits: u32,
     comment: String,
     sqm_override: Option<String>,
+    priority_classes: Vec<String>,
+    access_policy: lqos_config::CircuitAccessPolicy,
+    qoo_profile: Option<String>,
     circuit_hash: i64,
     device_hash: i64,
     parent_hash: i64,
@@ -329,6 +332,9 @@ fn shaped_device_semantic_key(device: &lqos_config::ShapedDevice) -> ShapedDevic
         upload_max_mbps_bits: device.upload_max_mbps.to_bits(),
         comment: device.comment.clone(),
         sqm_override: device.sqm_override.clone(),
+        priority_classes: device.priority_classes.clone(),
+        access_policy: device.access_policy,
+        qoo_profile: device.qoo_profile.clone(),
         circuit_hash: device.circuit_hash,
         device_hash: device.device_hash,
         parent_hash: device.parent_hash,
@@ -387,6 +393,9 @@ fn reload_dynamic_circuits_inner(reason: &str) {
 }
 
 fn prune_expired_dynamic_circuits_inner(hooks: Option<&dyn DaemonHooks>) {
+    if state::dynamic_expiry_paused() {
+        return;
+    }
     let Ok(config) = lqos_config::load_config() else {
         return;
     };
@@ -244,6 +244,14 @@ pub fn dynamic_circuits_snapshot() -> Arc<Vec<DynamicCircuit>> {
     state::dynamic_circuits_snapshot()
 }
 
+/// Pauses or resumes TTL expiry of dynamic circuits.
+///
+/// A high-availability standby pauses expiry so that circuits replicated from the
+/// active peer are only removed when the peer removes them.
+pub fn set_dynamic_circuit_expiry_paused(paused: bool) {
+    state::set_dynamic_expiry_paused(paused)
+}
+
 /// Run a closure with a read-only view of the current in-memory `NetworkJson`.
 pub fn with_network_json_read<R>(f: impl FnOnce(&NetworkJson) -> R) -> R {
     state::with_network_json_read(f)
@@ -7,7 +7,7 @@ use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use std::collections::HashSet;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 #[derive(Clone)]
 struct PublishedShapedState {
@@ -28,6 +28,7 @@ static SHAPED_STATE: Lazy<ArcSwap<PublishedShapedState>> = Lazy::new(|| {
 static DYNAMIC_CIRCUITS: Lazy<ArcSwap<Vec<DynamicCircuit>>> =
     Lazy::new(|| ArcSwap::new(Arc::new(Vec::new())));
 static NETWORK_JSON: Lazy<RwLock<NetworkJson>> = Lazy::new(|| RwLock::new(NetworkJson::default()));
+static DYNAMIC_EXPIRY_PAUSED: AtomicBool = AtomicBool::new(false);
 
 pub(crate) fn shaped_devices_snapshot() -> Arc<ConfigShapedDevices> {
     SHAPED_STATE.load_full().shaped.clone()
@@ -46,6 +47,14 @@ pub(crate) fn dynamic_circuits_snapshot() -> Arc<Vec<DynamicCircuit>> {
     DYNAMIC_CIRCUITS.load_full()
 }
 
+pub(crate) fn dynamic_expiry_paused() -> bool {
+    DYNAMIC_EXPIRY_PAUSED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_dynamic_expiry_paused(paused: bool) {
+    DYNAMIC_EXPIRY_PAUSED.store(paused, Ordering::Relaxed);
+}
+
 pub(crate) fn with_network_json_read<R>(f: impl FnOnce(&NetworkJson) -> R) -> R {
     let reader = NETWORK_JSON.read();
     f(&reader)
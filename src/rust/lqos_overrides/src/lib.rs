@@ -2,11 +2,14 @@
 #![warn(missing_docs)]
 
 mod file_lock;
+mod override_schedule;
 mod overrides_file;
 mod topology_overrides;
+pub use override_schedule::{OverrideSchedule, OverrideScheduleState};
 pub use overrides_file::{
     CircuitAdjustment, NetworkAdjustment, OverrideFile, OverrideLayer, OverrideStore,
-    TopologyParentOverrideMode, UispOverrides, UispRouteOverride,
+    QooProfileOverrides, ScheduledOverrideSummary, TopologyParentOverrideMode, UispOverrides,
+    UispRouteOverride,
 };
 pub use topology_overrides::{
     AttachmentProbePolicy, ManualAttachment, ManualAttachmentGroup, TopologyAttachmentMode,
@@ -1,16 +1,23 @@
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
 
 use anyhow::{Result, anyhow};
 use clap::{Args, Parser, Subcommand};
 
-use lqos_bus::{BusRequest, BusResponse, LibreqosBusClient};
-use lqos_config::ShapedDevice;
-use lqos_overrides::{CircuitAdjustment, NetworkAdjustment, OverrideFile};
+use lqos_bus::{BakeryPlanReport, BusRequest, BusResponse, LibreqosBusClient};
+use lqos_config::{ShapedDevice, is_valid_profile_name};
+use lqos_overrides::{CircuitAdjustment, NetworkAdjustment, OverrideFile, OverrideSchedule};
+
+/// Source recorded in the input revision history for edits made here.
+const CLI_HISTORY_SOURCE: &str = "lqos_overrides CLI";
 
 #[derive(Parser, Debug)]
 #[command(name = "lqos_overrides")]
 #[command(about = "Manage LibreQoS overrides", version, author)]
 struct Cli {
+    /// Preview what an edit would do to the shaping tree instead of saving it (via lqosd bus)
+    #[arg(long, global = true)]
+    plan: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -42,6 +49,136 @@ enum Commands {
         #[command(subcommand)]
         command: DynamicCircuitsCommand,
     },
+    /// Assign QoO scoring profiles to circuits and sites
+    QooProfile {
+        #[command(subcommand)]
+        command: QooProfileCommand,
+    },
+    /// Inspect scheduled and expired adjustments
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommand,
+    },
+    /// Browse and roll back revisions of network.json, ShapedDevices.csv and overrides (via lqosd bus)
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
+    },
+    /// Preview candidate input files against the live shaping tree (via lqosd bus)
+    Plan(PlanArgs),
+    /// List or lift automatic flood mitigations (via lqosd bus)
+    FloodMitigations {
+        #[command(subcommand)]
+        command: FloodMitigationsCommand,
+    },
+    /// Mirror circuits or prefixes to the analysis interface (via lqosd bus)
+    TrafficMirror {
+        #[command(subcommand)]
+        command: TrafficMirrorCommand,
+    },
+    /// Issue subscriber status portal links (via lqosd bus)
+    SubscriberPortal {
+        #[command(subcommand)]
+        command: SubscriberPortalCommand,
+    },
+}
+
+/// Candidate input files; any file left out keeps its live contents.
+#[derive(Args, Debug, Default)]
+struct PlanArgs {
+    #[arg(long)]
+    network_json: Option<PathBuf>,
+    #[arg(long)]
+    shaped_devices: Option<PathBuf>,
+    #[arg(long)]
+    overrides: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+    /// List retained revisions, newest first
+    List,
+    /// Show nodes, circuits and files changed between two revisions
+    Diff {
+        #[arg(long)]
+        from: u64,
+        #[arg(long)]
+        to: u64,
+    },
+    /// Restore a revision and reload LibreQoS
+    Rollback {
+        #[arg(long)]
+        revision: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum FloodMitigationsCommand {
+    /// List circuits that are capped or dropped
+    List,
+    /// Lift a circuit's mitigation, or every mitigation when no circuit is given
+    Clear {
+        #[arg(long)]
+        circuit_id: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SubscriberPortalCommand {
+    /// Issue a signed status link for one circuit
+    Link {
+        #[arg(long)]
+        circuit_id: String,
+        /// Link lifetime; defaults to subscriber_portal.default_link_hours
+        #[arg(long)]
+        valid_hours: Option<u32>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TrafficMirrorCommand {
+    /// List running mirror sessions with their counters
+    List,
+    /// Start mirroring a circuit and/or addresses and prefixes
+    Start {
+        #[arg(long)]
+        circuit_id: Option<String>,
+        /// Address or CIDR prefix to mirror; repeat for several
+        #[arg(long = "prefix")]
+        prefixes: Vec<String>,
+        /// Session length; defaults to traffic_mirror.default_minutes
+        #[arg(long)]
+        minutes: Option<u32>,
+        /// Recorded in the audit trail, e.g. a ticket or warrant reference
+        #[arg(long, default_value = "")]
+        reason: String,
+        /// Recorded in the audit trail; defaults to the invoking user
+        #[arg(long)]
+        requested_by: Option<String>,
+    },
+    /// Stop a mirror session before it expires
+    Stop {
+        #[arg(long)]
+        session_id: u64,
+        /// Recorded in the audit trail; defaults to the invoking user
+        #[arg(long)]
+        requested_by: Option<String>,
+    },
+}
+
+/// The login behind this invocation, seeing through sudo.
+fn invoking_user() -> String {
+    ["SUDO_USER", "USER"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|user| !user.is_empty()))
+        .map(|user| format!("{user} (lqos_overrides)"))
+        .unwrap_or_else(|| "lqos_overrides".to_string())
+}
+
+#[derive(Subcommand, Debug)]
+enum ScheduleCommand {
+    /// List upcoming, timed and expired adjustments
+    List,
 }
 
 #[derive(Subcommand, Debug)]
@@ -73,11 +210,15 @@ enum AdjustmentsCommand {
     AddRemoveCircuit {
         #[arg(long)]
         circuit_id: String,
+        #[command(flatten)]
+        schedule: ScheduleArgs,
     },
     /// Add a device removal adjustment
     AddRemoveDevice {
         #[arg(long)]
         device_id: String,
+        #[command(flatten)]
+        schedule: ScheduleArgs,
     },
     /// Add a circuit reparent adjustment
     AddReparentCircuit {
@@ -85,6 +226,26 @@ enum AdjustmentsCommand {
         circuit_id: String,
         #[arg(long)]
         parent_node: String,
+        #[command(flatten)]
+        schedule: ScheduleArgs,
+    },
+    /// Add a circuit priority class adjustment (comma-separated class names)
+    AddPriorityClasses {
+        #[arg(long)]
+        circuit_id: String,
+        #[arg(long, value_delimiter = ',')]
+        priority_classes: Vec<String>,
+        #[command(flatten)]
+        schedule: ScheduleArgs,
+    },
+    /// Add a circuit walled-garden access policy adjustment (open, block or portal)
+    AddAccessPolicy {
+        #[arg(long)]
+        circuit_id: String,
+        #[arg(long)]
+        access_policy: lqos_config::CircuitAccessPolicy,
+        #[command(flatten)]
+        schedule: ScheduleArgs,
     },
     /// Remove an adjustment by index (see list)
     DeleteIndex {
@@ -154,6 +315,36 @@ enum UispCommand {
     RouteList,
 }
 
+#[derive(Subcommand, Debug)]
+enum QooProfileCommand {
+    /// Score a circuit with a profile id from qoo_profiles.json, or `auto`
+    SetCircuit {
+        #[arg(long)]
+        circuit_id: String,
+        #[arg(long)]
+        profile: String,
+    },
+    /// Remove a circuit's QoO profile assignment
+    ClearCircuit {
+        #[arg(long)]
+        circuit_id: String,
+    },
+    /// Score every circuit below a site with a profile id, or `auto`
+    SetSite {
+        #[arg(long)]
+        site_name: String,
+        #[arg(long)]
+        profile: String,
+    },
+    /// Remove a site's QoO profile assignment
+    ClearSite {
+        #[arg(long)]
+        site_name: String,
+    },
+    /// List QoO profile assignments
+    List,
+}
+
 #[derive(Subcommand, Debug)]
 enum DynamicCircuitsCommand {
     /// Add or update a dynamic circuit overlay entry
@@ -195,7 +386,7 @@ struct AddArgs {
     upload_max_mbps: f32,
     #[arg(long, default_value = "")]
     comment: String,
-    /// Optional per-circuit SQM override token ("cake", "fq_codel", "none", or "down_sqm/up_sqm").
+    /// Optional per-circuit SQM override token ("cake", "fq_codel", "fq_pie", "none", a profile name, or "down_sqm/up_sqm").
     /// A single token applies to both directions; empty means use defaults.
     #[arg(long, default_value = "")]
     sqm_override: String,
@@ -257,6 +448,9 @@ impl AddArgs {
             upload_max_mbps: self.upload_max_mbps,
             comment: self.comment,
             sqm_override,
+            priority_classes: Vec::new(),
+            access_policy: lqos_config::CircuitAccessPolicy::Open,
+            qoo_profile: None,
             circuit_hash: 0,
             device_hash: 0,
             parent_hash: 0,
@@ -275,20 +469,20 @@ fn normalize_sqm_override(raw: &str) -> Result<Option<String>> {
         let mut parts = token.splitn(2, '/');
         let down = parts.next().unwrap_or("").trim();
         let up = parts.next().unwrap_or("").trim();
-        let valid = |s: &str| matches!(s, "" | "cake" | "fq_codel" | "none");
-        if !valid(down) || !valid(up) {
+        if !is_valid_profile_name(down) || !is_valid_profile_name(up) {
             return Err(anyhow!(
-                "invalid directional sqm override '{token}'. Allowed: 'cake', 'fq_codel', 'none', or 'down_sqm/up_sqm' (e.g. 'cake/fq_codel', '/none')"
+                "invalid directional sqm override '{token}'. Allowed: 'cake', 'fq_codel', 'fq_pie', 'none', an SQM profile name, or 'down_sqm/up_sqm' (e.g. 'cake/fq_codel', '/none')"
             ));
         }
         return Ok(Some(format!("{down}/{up}")));
     }
 
-    match token.as_str() {
-        "cake" | "fq_codel" | "none" => Ok(Some(token)),
-        _ => Err(anyhow!(
-            "invalid sqm override '{token}'. Allowed values: 'cake', 'fq_codel', 'none', or 'down_sqm/up_sqm' (e.g. 'cake/fq_codel', '/none')"
-        )),
+    if is_valid_profile_name(&token) {
+        Ok(Some(token))
+    } else {
+        Err(anyhow!(
+            "invalid sqm override '{token}'. Allowed values: 'cake', 'fq_codel', 'fq_pie', 'none', an SQM profile name, or 'down_sqm/up_sqm' (e.g. 'cake/fq_codel', '/none')"
+        ))
     }
 }
 
@@ -304,6 +498,8 @@ struct AddCircuitSpeedArgs {
     min_upload_bandwidth: Option<f32>,
     #[arg(long)]
     max_upload_bandwidth: Option<f32>,
+    #[command(flatten)]
+    schedule: ScheduleArgs,
 }
 
 #[derive(Args, Debug, Default)]
@@ -318,6 +514,8 @@ struct AddDeviceSpeedArgs {
     min_upload_bandwidth: Option<f32>,
     #[arg(long)]
     max_upload_bandwidth: Option<f32>,
+    #[command(flatten)]
+    schedule: ScheduleArgs,
 }
 
 #[derive(Args, Debug, Default)]
@@ -330,22 +528,152 @@ struct AddSiteSpeedArgs {
     download_bandwidth_mbps: Option<f32>,
     #[arg(long)]
     upload_bandwidth_mbps: Option<f32>,
+    #[command(flatten)]
+    schedule: ScheduleArgs,
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Optional activation window and provenance shared by the `add-*` commands.
+#[derive(Args, Debug, Default)]
+struct ScheduleArgs {
+    /// Do not apply before this time: unix seconds, or `+<n>[s|m|h|d]` from now
+    #[arg(long)]
+    not_before: Option<String>,
+    /// Retire the adjustment at this time: unix seconds, or `+<n>[s|m|h|d]` from now
+    #[arg(long)]
+    expires_at: Option<String>,
+    /// Why the adjustment exists, e.g. a ticket reference
+    #[arg(long)]
+    reason: Option<String>,
+    /// Who is responsible for the adjustment
+    #[arg(long)]
+    owner: Option<String>,
+}
 
-    fn send_bus_request(requests: Vec<BusRequest>) -> Result<Vec<BusResponse>> {
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()?;
-        rt.block_on(async {
-            let mut bus = LibreqosBusClient::new().await?;
-            Ok(bus.request(requests).await?)
-        })
+impl ScheduleArgs {
+    fn into_schedule(self, now_unix: u64) -> Result<OverrideSchedule> {
+        let schedule = OverrideSchedule {
+            not_before: self
+                .not_before
+                .as_deref()
+                .map(|raw| parse_schedule_time(raw, now_unix))
+                .transpose()?,
+            expires_at: self
+                .expires_at
+                .as_deref()
+                .map(|raw| parse_schedule_time(raw, now_unix))
+                .transpose()?,
+            reason: self.reason.filter(|reason| !reason.trim().is_empty()),
+            owner: self.owner.filter(|owner| !owner.trim().is_empty()),
+        };
+        schedule.validate().map_err(|e| anyhow!(e))?;
+        if schedule
+            .expires_at
+            .is_some_and(|expires| expires <= now_unix)
+        {
+            return Err(anyhow!("--expires-at is already in the past"));
+        }
+        Ok(schedule)
+    }
+}
+
+/// Parses unix seconds, or `+<n>[s|m|h|d]` relative to `now_unix`.
+fn parse_schedule_time(raw: &str, now_unix: u64) -> Result<u64> {
+    let raw = raw.trim();
+    let Some(relative) = raw.strip_prefix('+') else {
+        return raw
+            .parse()
+            .map_err(|_| anyhow!("invalid time '{raw}': expected unix seconds or +<n>[s|m|h|d]"));
+    };
+    let (digits, multiplier) = match relative.char_indices().last() {
+        Some((idx, 's')) => (&relative[..idx], 1),
+        Some((idx, 'm')) => (&relative[..idx], 60),
+        Some((idx, 'h')) => (&relative[..idx], 3_600),
+        Some((idx, 'd')) => (&relative[..idx], 86_400),
+        _ => (relative, 1),
+    };
+    let amount: u64 = digits
+        .parse()
+        .map_err(|_| anyhow!("invalid relative time '{raw}': expected +<n>[s|m|h|d]"))?;
+    Ok(now_unix.saturating_add(amount.saturating_mul(multiplier)))
+}
+
+fn send_bus_request(requests: Vec<BusRequest>) -> Result<Vec<BusResponse>> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    rt.block_on(async {
+        let mut bus = LibreqosBusClient::new().await?;
+        Ok(bus.request(requests).await?)
+    })
+}
+
+/// Asks lqosd to plan a shaping change and prints the resulting report.
+fn request_plan(request: BusRequest) -> Result<()> {
+    match send_bus_request(vec![request])?.pop() {
+        Some(BusResponse::BakeryPlan(report)) => print_plan(&report),
+        Some(BusResponse::Fail(message)) => Err(anyhow!(message)),
+        Some(other) => Err(anyhow!("Unexpected bus response: {other:?}")),
+        None => Err(anyhow!("No bus response.")),
+    }
+}
+
+fn print_plan(report: &BakeryPlanReport) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(report)?);
+    if report.full_reload {
+        println!("Plan: full reload ({}).", report.reasons.join("; "));
+    } else {
+        println!(
+            "Plan: {} live change(s), about {} tc command(s).",
+            report.live_changes, report.estimated_tc_commands
+        );
     }
+    Ok(())
+}
+
+/// Saves the edited overrides, or in plan mode previews them without saving.
+/// Returns how the edit was handled, for the confirmation message.
+fn persist(overrides: &OverrideFile, plan: bool) -> Result<&'static str> {
+    if !plan {
+        overrides.save_with_source(CLI_HISTORY_SOURCE)?;
+        return Ok("overrides saved");
+    }
+    request_plan(BusRequest::PlanShapingChange {
+        network_json: None,
+        shaped_devices_csv: None,
+        operator_overrides: Some(serde_json::to_string_pretty(overrides)?),
+    })?;
+    Ok("planned only, overrides not saved")
+}
+
+fn read_candidate(path: Option<PathBuf>) -> Result<Option<String>> {
+    path.map(|path| {
+        std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Unable to read {}: {e}", path.display()))
+    })
+    .transpose()
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let plan = cli.plan;
 
     match cli.command {
+        Commands::DynamicCircuits { .. }
+        | Commands::History { .. }
+        | Commands::FloodMitigations { .. }
+        | Commands::TrafficMirror { .. }
+        | Commands::SubscriberPortal { .. }
+            if plan =>
+        {
+            return Err(anyhow!(
+                "--plan only applies to override edits and the plan command"
+            ));
+        }
+        Commands::Plan(args) => request_plan(BusRequest::PlanShapingChange {
+            network_json: read_candidate(args.network_json)?,
+            shaped_devices_csv: read_candidate(args.shaped_devices)?,
+            operator_overrides: read_candidate(args.overrides)?,
+        })?,
         Commands::DynamicCircuits { command: cmd } => match cmd {
             DynamicCircuitsCommand::Add(args) => {
                 let device = (*args).into_device()?;
@@ -370,9 +698,105 @@ fn main() -> Result<()> {
                 }
             }
         },
+        Commands::History { command: cmd } => {
+            let request = match cmd {
+                HistoryCommand::List => BusRequest::ListInputRevisions,
+                HistoryCommand::Diff { from, to } => BusRequest::DiffInputRevisions { from, to },
+                HistoryCommand::Rollback { revision } => {
+                    BusRequest::RestoreInputRevision { id: revision }
+                }
+            };
+            match send_bus_request(vec![request])?.pop() {
+                Some(BusResponse::InputRevisions(revisions)) => {
+                    println!("{}", serde_json::to_string_pretty(&revisions)?)
+                }
+                Some(BusResponse::InputRevisionDiff(diff)) => {
+                    println!("{}", serde_json::to_string_pretty(&diff)?)
+                }
+                Some(BusResponse::InputRevisionRestored(revision)) => println!(
+                    "Restored; recorded as revision {} and reloaded LibreQoS.",
+                    revision.id
+                ),
+                Some(BusResponse::Fail(message)) => return Err(anyhow!(message)),
+                Some(other) => println!("Unexpected bus response: {other:?}"),
+                None => println!("No bus response."),
+            }
+        }
+        Commands::FloodMitigations { command: cmd } => {
+            let request = match cmd {
+                FloodMitigationsCommand::List => BusRequest::ListFloodMitigations,
+                FloodMitigationsCommand::Clear { circuit_id } => {
+                    BusRequest::ClearFloodMitigation { circuit_id }
+                }
+            };
+            match send_bus_request(vec![request])?.pop() {
+                Some(BusResponse::FloodMitigations(entries)) => {
+                    println!("{}", serde_json::to_string_pretty(&entries)?)
+                }
+                Some(BusResponse::Ack) => println!("Flood mitigation cleared."),
+                Some(BusResponse::Fail(message)) => return Err(anyhow!(message)),
+                Some(other) => println!("Unexpected bus response: {other:?}"),
+                None => println!("No bus response."),
+            }
+        }
+        Commands::TrafficMirror { command: cmd } => {
+            let request = match cmd {
+                TrafficMirrorCommand::List => BusRequest::ListTrafficMirrors,
+                TrafficMirrorCommand::Start {
+                    circuit_id,
+                    prefixes,
+                    minutes,
+                    reason,
+                    requested_by,
+                } => BusRequest::StartTrafficMirror {
+                    circuit_id,
+                    prefixes,
+                    minutes,
+                    requested_by: requested_by.unwrap_or_else(invoking_user),
+                    reason,
+                },
+                TrafficMirrorCommand::Stop {
+                    session_id,
+                    requested_by,
+                } => BusRequest::StopTrafficMirror {
+                    session_id,
+                    requested_by: requested_by.unwrap_or_else(invoking_user),
+                },
+            };
+            match send_bus_request(vec![request])?.pop() {
+                Some(BusResponse::TrafficMirrorSessions(sessions)) => {
+                    println!("{}", serde_json::to_string_pretty(&sessions)?)
+                }
+                Some(BusResponse::Ack) => println!("Mirror session stopped."),
+                Some(BusResponse::Fail(message)) => return Err(anyhow!(message)),
+                Some(other) => println!("Unexpected bus response: {other:?}"),
+                None => println!("No bus response."),
+            }
+        }
+        Commands::SubscriberPortal {
+            command:
+                SubscriberPortalCommand::Link {
+                    circuit_id,
+                    valid_hours,
+                },
+        } => {
+            let request = BusRequest::IssueSubscriberPortalLink {
+                circuit_id,
+                valid_hours,
+            };
+            match send_bus_request(vec![request])?.pop() {
+                Some(BusResponse::SubscriberPortalLink(link)) => {
+                    println!("{}", serde_json::to_string_pretty(&link)?)
+                }
+                Some(BusResponse::Fail(message)) => return Err(anyhow!(message)),
+                Some(other) => println!("Unexpected bus response: {other:?}"),
+                None => println!("No bus response."),
+            }
+        }
         command => {
             // All other commands operate on the overrides file.
             let mut overrides = OverrideFile::load()?;
+            let now = lqos_utils::unix_time::unix_now()?;
 
             match command {
                 Commands::PersistentDevices { command: cmd } => match cmd {
@@ -380,8 +804,8 @@ fn main() -> Result<()> {
                         let device = args.into_device()?;
                         let changed = overrides.add_persistent_shaped_device_return_changed(device);
                         if changed {
-                            overrides.save()?;
-                            println!("Added device; overrides saved.");
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Added device; {outcome}.");
                         } else {
                             println!("No changes (device already present).");
                         }
@@ -390,8 +814,8 @@ fn main() -> Result<()> {
                         let removed =
                             overrides.remove_persistent_shaped_device_by_circuit_count(&circuit_id);
                         if removed > 0 {
-                            overrides.save()?;
-                            println!("Removed {removed} device(s) by circuit_id; overrides saved.");
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Removed {removed} device(s) by circuit_id; {outcome}.");
                         } else {
                             println!("No devices matched circuit_id {circuit_id}.");
                         }
@@ -400,8 +824,8 @@ fn main() -> Result<()> {
                         let removed =
                             overrides.remove_persistent_shaped_device_by_device_count(&device_id);
                         if removed > 0 {
-                            overrides.save()?;
-                            println!("Removed {removed} device(s) by device_id; overrides saved.");
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Removed {removed} device(s) by device_id; {outcome}.");
                         } else {
                             println!("No devices matched device_id {device_id}.");
                         }
@@ -419,10 +843,11 @@ fn main() -> Result<()> {
                             max_download_bandwidth: args.max_download_bandwidth,
                             min_upload_bandwidth: args.min_upload_bandwidth,
                             max_upload_bandwidth: args.max_upload_bandwidth,
+                            schedule: args.schedule.into_schedule(now)?,
                         };
                         overrides.add_circuit_adjustment(adj);
-                        overrides.save()?;
-                        println!("Added circuit speed adjustment; overrides saved.");
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Added circuit speed adjustment; {outcome}.");
                     }
                     AdjustmentsCommand::AddDeviceSpeed(args) => {
                         let adj = CircuitAdjustment::DeviceAdjustSpeed {
@@ -431,40 +856,87 @@ fn main() -> Result<()> {
                             max_download_bandwidth: args.max_download_bandwidth,
                             min_upload_bandwidth: args.min_upload_bandwidth,
                             max_upload_bandwidth: args.max_upload_bandwidth,
+                            schedule: args.schedule.into_schedule(now)?,
                         };
                         overrides.add_circuit_adjustment(adj);
-                        overrides.save()?;
-                        println!("Added device speed adjustment; overrides saved.");
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Added device speed adjustment; {outcome}.");
                     }
-                    AdjustmentsCommand::AddRemoveCircuit { circuit_id } => {
-                        let adj = CircuitAdjustment::RemoveCircuit { circuit_id };
+                    AdjustmentsCommand::AddRemoveCircuit {
+                        circuit_id,
+                        schedule,
+                    } => {
+                        let adj = CircuitAdjustment::RemoveCircuit {
+                            circuit_id,
+                            schedule: schedule.into_schedule(now)?,
+                        };
                         overrides.add_circuit_adjustment(adj);
-                        overrides.save()?;
-                        println!("Added remove-circuit adjustment; overrides saved.");
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Added remove-circuit adjustment; {outcome}.");
                     }
-                    AdjustmentsCommand::AddRemoveDevice { device_id } => {
-                        let adj = CircuitAdjustment::RemoveDevice { device_id };
+                    AdjustmentsCommand::AddRemoveDevice {
+                        device_id,
+                        schedule,
+                    } => {
+                        let adj = CircuitAdjustment::RemoveDevice {
+                            device_id,
+                            schedule: schedule.into_schedule(now)?,
+                        };
                         overrides.add_circuit_adjustment(adj);
-                        overrides.save()?;
-                        println!("Added remove-device adjustment; overrides saved.");
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Added remove-device adjustment; {outcome}.");
                     }
                     AdjustmentsCommand::AddReparentCircuit {
                         circuit_id,
                         parent_node,
+                        schedule,
                     } => {
                         let adj = CircuitAdjustment::ReparentCircuit {
                             circuit_id,
                             parent_node,
+                            schedule: schedule.into_schedule(now)?,
+                        };
+                        overrides.add_circuit_adjustment(adj);
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Added reparent-circuit adjustment; {outcome}.");
+                    }
+                    AdjustmentsCommand::AddPriorityClasses {
+                        circuit_id,
+                        priority_classes,
+                        schedule,
+                    } => {
+                        let adj = CircuitAdjustment::CircuitAdjustPriorityClasses {
+                            circuit_id,
+                            priority_classes: priority_classes
+                                .iter()
+                                .map(|class| class.trim().to_ascii_lowercase())
+                                .filter(|class| !class.is_empty())
+                                .collect(),
+                            schedule: schedule.into_schedule(now)?,
+                        };
+                        overrides.add_circuit_adjustment(adj);
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Added priority-class adjustment; {outcome}.");
+                    }
+                    AdjustmentsCommand::AddAccessPolicy {
+                        circuit_id,
+                        access_policy,
+                        schedule,
+                    } => {
+                        let adj = CircuitAdjustment::CircuitAdjustAccessPolicy {
+                            circuit_id,
+                            access_policy,
+                            schedule: schedule.into_schedule(now)?,
                         };
                         overrides.add_circuit_adjustment(adj);
-                        overrides.save()?;
-                        println!("Added reparent-circuit adjustment; overrides saved.");
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Added access-policy adjustment; {outcome}.");
                     }
                     AdjustmentsCommand::DeleteIndex { index } => {
                         let ok = overrides.remove_circuit_adjustment_by_index(index);
                         if ok {
-                            overrides.save()?;
-                            println!("Removed adjustment at index {index}; overrides saved.");
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Removed adjustment at index {index}; {outcome}.");
                         } else {
                             println!("No adjustment at index {index}.");
                         }
@@ -481,26 +953,27 @@ fn main() -> Result<()> {
                             site_name: args.site_name,
                             download_bandwidth_mbps: args.download_bandwidth_mbps,
                             upload_bandwidth_mbps: args.upload_bandwidth_mbps,
+                            schedule: args.schedule.into_schedule(now)?,
                         };
                         overrides.add_network_adjustment(adj);
-                        overrides.save()?;
-                        println!("Added site speed adjustment; overrides saved.");
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Added site speed adjustment; {outcome}.");
                     }
                     NetworkAdjustmentsCommand::SetVirtual {
                         node_name,
                         virtual_node,
                     } => {
                         overrides.set_network_node_virtual(node_name, virtual_node);
-                        overrides.save()?;
-                        println!("Set node virtual flag; overrides saved.");
+                        let outcome = persist(&overrides, plan)?;
+                        println!("Set node virtual flag; {outcome}.");
                     }
                     NetworkAdjustmentsCommand::DeleteVirtual { node_name } => {
                         let removed =
                             overrides.remove_network_node_virtual_by_name_count(&node_name);
                         if removed > 0 {
-                            overrides.save()?;
+                            let outcome = persist(&overrides, plan)?;
                             println!(
-                                "Removed {removed} virtual override(s) for node '{node_name}'; overrides saved."
+                                "Removed {removed} virtual override(s) for node '{node_name}'; {outcome}."
                             );
                         } else {
                             println!("No virtual override found for node '{node_name}'.");
@@ -509,10 +982,8 @@ fn main() -> Result<()> {
                     NetworkAdjustmentsCommand::DeleteIndex { index } => {
                         let ok = overrides.remove_network_adjustment_by_index(index);
                         if ok {
-                            overrides.save()?;
-                            println!(
-                                "Removed network adjustment at index {index}; overrides saved."
-                            );
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Removed network adjustment at index {index}; {outcome}.");
                         } else {
                             println!("No network adjustment at index {index}.");
                         }
@@ -529,17 +1000,17 @@ fn main() -> Result<()> {
                         up,
                     } => {
                         overrides.set_uisp_bandwidth_override(site_name, down, up);
-                        overrides.save()?;
+                        let outcome = persist(&overrides, plan)?;
                         println!(
-                            "Added deprecated UISP bandwidth override entry; overrides saved. Current UISP builds ignore these entries and use AdjustSiteSpeed overrides instead."
+                            "Added deprecated UISP bandwidth override entry; {outcome}. Current UISP builds ignore these entries and use AdjustSiteSpeed overrides instead."
                         );
                     }
                     UispCommand::BandwidthRemove { site_name } => {
                         let removed = overrides.remove_uisp_bandwidth_override(&site_name);
                         if removed {
-                            overrides.save()?;
+                            let outcome = persist(&overrides, plan)?;
                             println!(
-                                "Removed deprecated UISP bandwidth override for {site_name}; overrides saved."
+                                "Removed deprecated UISP bandwidth override for {site_name}; {outcome}."
                             );
                         } else {
                             println!("No UISP bandwidth override found for {site_name}.");
@@ -564,17 +1035,17 @@ fn main() -> Result<()> {
                         cost,
                     } => {
                         overrides.add_uisp_route_override(from_site, to_site, cost);
-                        overrides.save()?;
+                        let outcome = persist(&overrides, plan)?;
                         println!(
-                            "Added deprecated UISP route override entry; overrides saved. Current UISP builds ignore these overrides."
+                            "Added deprecated UISP route override entry; {outcome}. Current UISP builds ignore these overrides."
                         );
                     }
                     UispCommand::RouteRemoveIndex { index } => {
                         let removed = overrides.remove_uisp_route_by_index(index);
                         if removed {
-                            overrides.save()?;
+                            let outcome = persist(&overrides, plan)?;
                             println!(
-                                "Removed deprecated UISP route override at index {index}; overrides saved."
+                                "Removed deprecated UISP route override at index {index}; {outcome}."
                             );
                         } else {
                             println!("No UISP route override at index {index}.");
@@ -591,7 +1062,66 @@ fn main() -> Result<()> {
                         }
                     }
                 },
-                Commands::DynamicCircuits { .. } => unreachable!("dynamic circuits handled above"),
+                Commands::QooProfile { command: cmd } => match cmd {
+                    QooProfileCommand::SetCircuit {
+                        circuit_id,
+                        profile,
+                    } => {
+                        if overrides
+                            .set_circuit_qoo_profile_return_changed(&circuit_id, Some(&profile))
+                        {
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Set QoO profile for circuit {circuit_id}; {outcome}.");
+                        } else {
+                            println!("Circuit {circuit_id} already uses that QoO profile.");
+                        }
+                    }
+                    QooProfileCommand::ClearCircuit { circuit_id } => {
+                        if overrides.set_circuit_qoo_profile_return_changed(&circuit_id, None) {
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Cleared QoO profile for circuit {circuit_id}; {outcome}.");
+                        } else {
+                            println!("No QoO profile assigned to circuit {circuit_id}.");
+                        }
+                    }
+                    QooProfileCommand::SetSite { site_name, profile } => {
+                        if overrides.set_site_qoo_profile_return_changed(&site_name, Some(&profile))
+                        {
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Set QoO profile for site {site_name}; {outcome}.");
+                        } else {
+                            println!("Site {site_name} already uses that QoO profile.");
+                        }
+                    }
+                    QooProfileCommand::ClearSite { site_name } => {
+                        if overrides.set_site_qoo_profile_return_changed(&site_name, None) {
+                            let outcome = persist(&overrides, plan)?;
+                            println!("Cleared QoO profile for site {site_name}; {outcome}.");
+                        } else {
+                            println!("No QoO profile assigned to site {site_name}.");
+                        }
+                    }
+                    QooProfileCommand::List => {
+                        println!(
+                            "{}",
+                            serde_json::to_string_pretty(overrides.qoo_profiles())?
+                        );
+                    }
+                },
+                Commands::Schedule { command: cmd } => match cmd {
+                    ScheduleCommand::List => {
+                        let list = overrides.scheduled_overrides(now);
+                        println!("{}", serde_json::to_string_pretty(&list)?);
+                    }
+                },
+                Commands::DynamicCircuits { .. }
+                | Commands::History { .. }
+                | Commands::FloodMitigations { .. }
+                | Commands::TrafficMirror { .. }
+                | Commands::SubscriberPortal { .. }
+                | Commands::Plan(_) => {
+                    unreachable!("bus commands handled above")
+                }
             }
         }
     }
@@ -653,4 +1183,78 @@ mod tests {
             other => panic!("unexpected top-level command: {other:?}"),
         }
     }
+
+    #[test]
+    fn schedule_flags_accept_relative_and_absolute_times() {
+        let cli = Cli::try_parse_from([
+            "lqos_overrides",
+            "adjustments",
+            "add-remove-circuit",
+            "--circuit-id",
+            "c1",
+            "--not-before",
+            "1000",
+            "--expires-at",
+            "+2h",
+            "--reason",
+            "TICKET-12",
+        ])
+        .expect("CLI parse must succeed");
+
+        match cli.command {
+            Commands::Adjustments {
+                command: AdjustmentsCommand::AddRemoveCircuit { schedule, .. },
+            } => {
+                let schedule = schedule.into_schedule(500).expect("valid schedule");
+                assert_eq!(schedule.not_before, Some(1000));
+                assert_eq!(schedule.expires_at, Some(500 + 7_200));
+                assert_eq!(schedule.reason.as_deref(), Some("TICKET-12"));
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        assert!(parse_schedule_time("+3x", 0).is_err());
+        assert_eq!(parse_schedule_time("+90", 10).ok(), Some(100));
+        assert!(
+            ScheduleArgs {
+                expires_at: Some("100".to_string()),
+                ..ScheduleArgs::default()
+            }
+            .into_schedule(200)
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn plan_flag_is_global_and_plan_takes_candidate_paths() {
+        let cli = Cli::try_parse_from([
+            "lqos_overrides",
+            "adjustments",
+            "add-remove-circuit",
+            "--circuit-id",
+            "c1",
+            "--plan",
+        ])
+        .expect("CLI parse must succeed");
+        assert!(cli.plan);
+
+        let cli = Cli::try_parse_from([
+            "lqos_overrides",
+            "plan",
+            "--shaped-devices",
+            "/tmp/ShapedDevices.csv",
+        ])
+        .expect("CLI parse must succeed");
+        match cli.command {
+            Commands::Plan(args) => {
+                assert_eq!(
+                    args.shaped_devices,
+                    Some(PathBuf::from("/tmp/ShapedDevices.csv"))
+                );
+                assert!(args.network_json.is_none());
+                assert!(args.overrides.is_none());
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
 }
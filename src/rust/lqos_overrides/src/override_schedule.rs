@@ -0,0 +1,114 @@
+//! Optional activation window and provenance carried by every override entry.
+//!
+//! Entries without a schedule are permanent. The effective overrides view skips
+//! entries that are not yet due or already expired; `lqosd` activates and
+//! retires scheduled entries in the operator file as their times pass.
+
+use serde::{Deserialize, Serialize};
+
+/// When an override takes effect, when it is retired, and who added it and why.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OverrideSchedule {
+    /// Unix time (seconds) before which the override is not applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<u64>,
+    /// Unix time (seconds) at which the override is retired.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// Why the override exists, e.g. a ticket reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Who is responsible for the override.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+/// Where a scheduled override is in its lifetime.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideScheduleState {
+    /// `not_before` has not been reached yet.
+    Upcoming,
+    /// The override is applied.
+    Active,
+    /// `expires_at` has passed.
+    Expired,
+}
+
+impl OverrideSchedule {
+    /// True when no field is set.
+    ///
+    /// This function is pure: it has no side effects.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// True when the entry has a start or end time.
+    ///
+    /// This function is pure: it has no side effects.
+    pub fn is_timed(&self) -> bool {
+        self.not_before.is_some() || self.expires_at.is_some()
+    }
+
+    /// Lifetime state at `now_unix`. Expiry wins over a pending start.
+    ///
+    /// This function is pure: it has no side effects.
+    pub fn state_at(&self, now_unix: u64) -> OverrideScheduleState {
+        if self.expires_at.is_some_and(|expires| expires <= now_unix) {
+            OverrideScheduleState::Expired
+        } else if self.not_before.is_some_and(|start| start > now_unix) {
+            OverrideScheduleState::Upcoming
+        } else {
+            OverrideScheduleState::Active
+        }
+    }
+
+    /// True when the override should be applied at `now_unix`.
+    ///
+    /// This function is pure: it has no side effects.
+    pub fn is_active_at(&self, now_unix: u64) -> bool {
+        self.state_at(now_unix) == OverrideScheduleState::Active
+    }
+
+    /// Rejects windows that end before they start.
+    ///
+    /// This function is pure: it has no side effects.
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(start), Some(end)) = (self.not_before, self.expires_at)
+            && end <= start
+        {
+            return Err(format!(
+                "expires_at ({end}) must be later than not_before ({start})"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_follows_the_window() {
+        let schedule = OverrideSchedule {
+            not_before: Some(100),
+            expires_at: Some(200),
+            ..OverrideSchedule::default()
+        };
+        assert_eq!(schedule.state_at(99), OverrideScheduleState::Upcoming);
+        assert_eq!(schedule.state_at(100), OverrideScheduleState::Active);
+        assert_eq!(schedule.state_at(200), OverrideScheduleState::Expired);
+        assert!(OverrideSchedule::default().is_active_at(0));
+        assert!(schedule.validate().is_ok());
+        assert!(
+            OverrideSchedule {
+                not_before: Some(200),
+                expires_at: Some(200),
+                ..OverrideSchedule::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     fs::read_to_string,
     io::Write,
     path::{Path, PathBuf},
@@ -10,6 +10,7 @@ use lqos_config::ShapedDevice;
 use serde::{Deserialize, Serialize};
 
 use crate::file_lock::FileLock;
+use crate::override_schedule::{OverrideSchedule, OverrideScheduleState};
 
 const OPERATOR_OVERRIDES_FILE: &str = "lqos_overrides.json";
 const STORMGUARD_OVERRIDES_FILE: &str = "lqos_overrides.stormguard.json";
@@ -27,6 +28,19 @@ pub enum OverrideLayer {
     Treeguard,
 }
 
+impl OverrideLayer {
+    /// Source recorded in the input revision history for saves of this layer.
+    ///
+    /// This function is pure: it has no side effects.
+    pub fn history_source(self) -> &'static str {
+        match self {
+            OverrideLayer::Operator => "operator overrides",
+            OverrideLayer::Stormguard => "stormguard",
+            OverrideLayer::Treeguard => "treeguard",
+        }
+    }
+}
+
 /// Helper for working with layered override files.
 pub struct OverrideStore;
 
@@ -46,6 +60,9 @@ pub enum CircuitAdjustment {
         min_upload_bandwidth: Option<f32>,
         /// Replacement maximum upload bandwidth in Mbps.
         max_upload_bandwidth: Option<f32>,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
     /// Replaces some or all bandwidth values for a specific device.
     DeviceAdjustSpeed {
@@ -59,6 +76,9 @@ pub enum CircuitAdjustment {
         min_upload_bandwidth: Option<f32>,
         /// Replacement maximum upload bandwidth in Mbps.
         max_upload_bandwidth: Option<f32>,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
     /// Replaces the SQM override token for a specific device without changing any other fields.
     DeviceAdjustSqm {
@@ -66,16 +86,45 @@ pub enum CircuitAdjustment {
         device_id: String,
         /// Replacement SQM override token. `None` or empty removes the override.
         sqm_override: Option<String>,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
+    },
+    /// Replaces the intra-circuit priority classes for a specific circuit.
+    CircuitAdjustPriorityClasses {
+        /// Circuit identifier to update.
+        circuit_id: String,
+        /// Replacement priority class names. Empty removes all classes.
+        priority_classes: Vec<String>,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
+    },
+    /// Replaces the walled-garden access policy for a specific circuit.
+    CircuitAdjustAccessPolicy {
+        /// Circuit identifier to update.
+        circuit_id: String,
+        /// Replacement access policy.
+        access_policy: lqos_config::CircuitAccessPolicy,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
     /// Removes a circuit from generated output by circuit ID.
     RemoveCircuit {
         /// Circuit identifier to remove.
         circuit_id: String,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
     /// Removes a device from generated output by device ID.
     RemoveDevice {
         /// Device identifier to remove.
         device_id: String,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
     /// Assigns a circuit to a different parent node.
     ReparentCircuit {
@@ -83,6 +132,9 @@ pub enum CircuitAdjustment {
         circuit_id: String,
         /// Target parent node name.
         parent_node: String,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
 }
 
@@ -101,6 +153,9 @@ pub enum NetworkAdjustment {
         download_bandwidth_mbps: Option<f32>,
         /// Replacement upload bandwidth in Mbps.
         upload_bandwidth_mbps: Option<f32>,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
     /// Marks a named node as virtual or non-virtual.
     SetNodeVirtual {
@@ -109,6 +164,9 @@ pub enum NetworkAdjustment {
         #[serde(rename = "virtual")]
         /// Whether the node should be treated as virtual.
         virtual_node: bool,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
     /// Overrides immediate upstream parent selection for a topology node.
     TopologyParentOverride {
@@ -122,9 +180,137 @@ pub enum NetworkAdjustment {
         parent_node_ids: Vec<String>,
         /// Ordered parent display names matching `parent_node_ids`.
         parent_node_names: Vec<String>,
+        /// Optional activation window, reason and owner.
+        #[serde(default, flatten)]
+        schedule: OverrideSchedule,
     },
 }
 
+impl CircuitAdjustment {
+    /// Borrow this entry's activation window and provenance.
+    pub fn schedule(&self) -> &OverrideSchedule {
+        match self {
+            Self::CircuitAdjustSpeed { schedule, .. }
+            | Self::DeviceAdjustSpeed { schedule, .. }
+            | Self::DeviceAdjustSqm { schedule, .. }
+            | Self::CircuitAdjustPriorityClasses { schedule, .. }
+            | Self::CircuitAdjustAccessPolicy { schedule, .. }
+            | Self::RemoveCircuit { schedule, .. }
+            | Self::RemoveDevice { schedule, .. }
+            | Self::ReparentCircuit { schedule, .. } => schedule,
+        }
+    }
+
+    /// Mutably borrow this entry's activation window and provenance.
+    pub fn schedule_mut(&mut self) -> &mut OverrideSchedule {
+        match self {
+            Self::CircuitAdjustSpeed { schedule, .. }
+            | Self::DeviceAdjustSpeed { schedule, .. }
+            | Self::DeviceAdjustSqm { schedule, .. }
+            | Self::CircuitAdjustPriorityClasses { schedule, .. }
+            | Self::CircuitAdjustAccessPolicy { schedule, .. }
+            | Self::RemoveCircuit { schedule, .. }
+            | Self::RemoveDevice { schedule, .. }
+            | Self::ReparentCircuit { schedule, .. } => schedule,
+        }
+    }
+
+    /// Short human-readable description, e.g. `remove_circuit 1234`.
+    pub fn describe(&self) -> String {
+        let (kind, target) = circuit_adjustment_merge_key(self);
+        match self {
+            Self::ReparentCircuit { parent_node, .. } => {
+                format!("{kind} {target} -> {parent_node}")
+            }
+            Self::DeviceAdjustSqm { sqm_override, .. } => {
+                format!(
+                    "{kind} {target} = {}",
+                    sqm_override.as_deref().unwrap_or("-")
+                )
+            }
+            Self::CircuitAdjustPriorityClasses {
+                priority_classes, ..
+            } => {
+                if priority_classes.is_empty() {
+                    format!("{kind} {target} = -")
+                } else {
+                    format!("{kind} {target} = {}", priority_classes.join(","))
+                }
+            }
+            Self::CircuitAdjustAccessPolicy { access_policy, .. } => {
+                format!("{kind} {target} = {}", access_policy.as_str())
+            }
+            _ => format!("{kind} {target}"),
+        }
+    }
+}
+
+impl NetworkAdjustment {
+    /// Borrow this entry's activation window and provenance.
+    pub fn schedule(&self) -> &OverrideSchedule {
+        match self {
+            Self::AdjustSiteSpeed { schedule, .. }
+            | Self::SetNodeVirtual { schedule, .. }
+            | Self::TopologyParentOverride { schedule, .. } => schedule,
+        }
+    }
+
+    /// Mutably borrow this entry's activation window and provenance.
+    pub fn schedule_mut(&mut self) -> &mut OverrideSchedule {
+        match self {
+            Self::AdjustSiteSpeed { schedule, .. }
+            | Self::SetNodeVirtual { schedule, .. }
+            | Self::TopologyParentOverride { schedule, .. } => schedule,
+        }
+    }
+
+    /// Short human-readable description, e.g. `set_node_virtual Tower1 = true`.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::AdjustSiteSpeed {
+                site_name,
+                download_bandwidth_mbps,
+                upload_bandwidth_mbps,
+                ..
+            } => format!(
+                "adjust_site_speed {site_name} = {}/{} Mbps",
+                download_bandwidth_mbps.map_or("-".to_string(), |v| v.to_string()),
+                upload_bandwidth_mbps.map_or("-".to_string(), |v| v.to_string()),
+            ),
+            Self::SetNodeVirtual {
+                node_name,
+                virtual_node,
+                ..
+            } => format!("set_node_virtual {node_name} = {virtual_node}"),
+            Self::TopologyParentOverride {
+                node_name,
+                parent_node_names,
+                ..
+            } => format!(
+                "topology_parent_override {node_name} -> {}",
+                parent_node_names.join(", ")
+            ),
+        }
+    }
+}
+
+/// One timed or retired override, as listed by the CLI and UI.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduledOverrideSummary {
+    /// `circuit` or `network`.
+    pub section: String,
+    /// Index into the live list, or into the retired history when `retired` is set.
+    pub index: usize,
+    /// Lifetime state at the time of listing.
+    pub state: OverrideScheduleState,
+    /// True when `lqosd` has moved the entry into the retired history.
+    pub retired: bool,
+    /// Short human-readable description of the override.
+    pub description: String,
+    /// The entry's activation window and provenance.
+    pub schedule: OverrideSchedule,
+}
+
 /// Override behavior for topology parent selection.
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -135,6 +321,25 @@ pub enum TopologyParentOverrideMode {
     PreferredOrder,
 }
 
+/// QoO profile assignments used for scoring, keyed by circuit ID or site
+/// name. Values are profile ids from `qoo_profiles.json`, or `auto`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct QooProfileOverrides {
+    /// Profile per circuit ID. These win over the ShapedDevices column.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub circuits: BTreeMap<String, String>,
+    /// Profile per site name, inherited by the circuits below the site.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sites: BTreeMap<String, String>,
+}
+
+impl QooProfileOverrides {
+    /// True when nothing is assigned.
+    pub fn is_empty(&self) -> bool {
+        self.circuits.is_empty() && self.sites.is_empty()
+    }
+}
+
 /// Consolidated UISP-specific overrides stored in an override file.
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct UispOverrides {
@@ -178,11 +383,47 @@ pub struct OverrideFile {
     /// Circuit IDs excluded from RTT aggregation/summarization in the UI.
     #[serde(default)]
     rtt_excluded_circuits: Vec<String>,
+    /// QoO profiles assigned to circuits and sites.
+    #[serde(default, skip_serializing_if = "QooProfileOverrides::is_empty")]
+    qoo_profiles: QooProfileOverrides,
     /// UISP integration consolidated overrides
     #[serde(default)]
     uisp: Option<UispOverrides>,
+    /// Circuit adjustments retired after their `expires_at`, newest last.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    expired_circuit_adjustments: Vec<CircuitAdjustment>,
+    /// Network adjustments retired after their `expires_at`, newest last.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    expired_network_adjustments: Vec<NetworkAdjustment>,
 }
 
+fn set_qoo_profile(
+    assignments: &mut BTreeMap<String, String>,
+    key: &str,
+    profile: Option<&str>,
+) -> bool {
+    let key = key.trim();
+    if key.is_empty() {
+        return false;
+    }
+    let profile = profile
+        .map(|p| p.trim().to_ascii_lowercase())
+        .filter(|p| !p.is_empty());
+    match profile {
+        Some(profile) => {
+            if assignments.get(key) == Some(&profile) {
+                return false;
+            }
+            assignments.insert(key.to_string(), profile);
+            true
+        }
+        None => assignments.remove(key).is_some(),
+    }
+}
+
+/// Retired entries kept per section so operators can see what expired.
+const RETIRED_HISTORY_LIMIT: usize = 200;
+
 fn overrides_path(config: &lqos_config::Config, layer: OverrideLayer) -> PathBuf {
     let file = match layer {
         OverrideLayer::Operator => OPERATOR_OVERRIDES_FILE,
@@ -192,6 +433,11 @@ fn overrides_path(config: &lqos_config::Config, layer: OverrideLayer) -> PathBuf
     Path::new(&config.lqos_directory).join(file)
 }
 
+/// Attribution is best effort: a failed mark must never block a save.
+fn mark_history_source(config: &lqos_config::Config, source: &str) {
+    let _ = lqos_config::mark_input_change_source(config, source);
+}
+
 fn treeguard_read_path(config: &lqos_config::Config) -> PathBuf {
     let canonical = Path::new(&config.lqos_directory).join(TREEGUARD_OVERRIDES_FILE);
     if canonical.exists() {
@@ -344,6 +590,7 @@ fn legacy_sqm_adjustments_from_devices(devices: &[ShapedDevice]) -> Vec<CircuitA
             Some(CircuitAdjustment::DeviceAdjustSqm {
                 device_id: device.device_id.clone(),
                 sqm_override: Some(sqm_override.to_string()),
+                schedule: OverrideSchedule::default(),
             })
         })
         .collect()
@@ -354,8 +601,14 @@ fn circuit_adjustment_merge_key(adj: &CircuitAdjustment) -> (&'static str, &str)
         CircuitAdjustment::CircuitAdjustSpeed { circuit_id, .. } => ("circuit_speed", circuit_id),
         CircuitAdjustment::DeviceAdjustSpeed { device_id, .. } => ("device_speed", device_id),
         CircuitAdjustment::DeviceAdjustSqm { device_id, .. } => ("device_sqm", device_id),
-        CircuitAdjustment::RemoveCircuit { circuit_id } => ("remove_circuit", circuit_id),
-        CircuitAdjustment::RemoveDevice { device_id } => ("remove_device", device_id),
+        CircuitAdjustment::CircuitAdjustPriorityClasses { circuit_id, .. } => {
+            ("circuit_priority_classes", circuit_id)
+        }
+        CircuitAdjustment::CircuitAdjustAccessPolicy { circuit_id, .. } => {
+            ("circuit_access_policy", circuit_id)
+        }
+        CircuitAdjustment::RemoveCircuit { circuit_id, .. } => ("remove_circuit", circuit_id),
+        CircuitAdjustment::RemoveDevice { device_id, .. } => ("remove_device", device_id),
         CircuitAdjustment::ReparentCircuit { circuit_id, .. } => ("reparent_circuit", circuit_id),
     }
 }
@@ -440,22 +693,11 @@ fn merge_network_adjustments_owned(
     let mut stormguard_site_seen: HashSet<String> = HashSet::new();
     for adj in stormguard_adjustments {
         if let NetworkAdjustment::AdjustSiteSpeed {
-            node_id,
-            site_name,
-            download_bandwidth_mbps,
-            upload_bandwidth_mbps,
+            node_id, site_name, ..
         } = adj
         {
             let key = site_speed_key(node_id.as_deref(), site_name);
-            stormguard_site_speeds.insert(
-                key.clone(),
-                NetworkAdjustment::AdjustSiteSpeed {
-                    node_id: node_id.clone(),
-                    site_name: site_name.clone(),
-                    download_bandwidth_mbps: *download_bandwidth_mbps,
-                    upload_bandwidth_mbps: *upload_bandwidth_mbps,
-                },
-            );
+            stormguard_site_speeds.insert(key.clone(), adj.clone());
             if !stormguard_site_seen.contains(&key) {
                 stormguard_site_order.push(key.clone());
                 stormguard_site_seen.insert(key);
@@ -471,25 +713,16 @@ fn merge_network_adjustments_owned(
 
     for adj in operator_adjustments {
         match adj {
-            NetworkAdjustment::SetNodeVirtual {
-                node_name,
-                virtual_node,
-            } => {
+            NetworkAdjustment::SetNodeVirtual { node_name, .. } => {
                 let name = node_name.as_str();
                 if operator_virtual_seen.contains(name) {
                     continue;
                 }
                 operator_virtual_seen.insert(name);
-                out.push(NetworkAdjustment::SetNodeVirtual {
-                    node_name: node_name.clone(),
-                    virtual_node: *virtual_node,
-                });
+                out.push(adj.clone());
             }
             NetworkAdjustment::AdjustSiteSpeed {
-                node_id,
-                site_name,
-                download_bandwidth_mbps,
-                upload_bandwidth_mbps,
+                node_id, site_name, ..
             } => {
                 let key = site_speed_key(node_id.as_deref(), site_name);
                 if operator_site_speed_seen.contains(&key) {
@@ -497,32 +730,15 @@ fn merge_network_adjustments_owned(
                 }
                 operator_site_speed_seen.insert(key);
                 operator_site_name_seen.insert(site_name.clone());
-                out.push(NetworkAdjustment::AdjustSiteSpeed {
-                    node_id: node_id.clone(),
-                    site_name: site_name.clone(),
-                    download_bandwidth_mbps: *download_bandwidth_mbps,
-                    upload_bandwidth_mbps: *upload_bandwidth_mbps,
-                });
+                out.push(adj.clone());
             }
-            NetworkAdjustment::TopologyParentOverride {
-                node_id,
-                node_name,
-                mode,
-                parent_node_ids,
-                parent_node_names,
-            } => {
+            NetworkAdjustment::TopologyParentOverride { node_id, .. } => {
                 let node_id_ref = node_id.as_str();
                 if operator_topology_seen.contains(node_id_ref) {
                     continue;
                 }
                 operator_topology_seen.insert(node_id_ref);
-                out.push(NetworkAdjustment::TopologyParentOverride {
-                    node_id: node_id.clone(),
-                    node_name: node_name.clone(),
-                    mode: *mode,
-                    parent_node_ids: parent_node_ids.clone(),
-                    parent_node_names: parent_node_names.clone(),
-                });
+                out.push(adj.clone());
             }
         }
     }
@@ -531,24 +747,15 @@ fn merge_network_adjustments_owned(
         if operator_site_speed_seen.contains(&key) {
             continue;
         }
-        let Some(NetworkAdjustment::AdjustSiteSpeed {
-            node_id,
-            site_name,
-            download_bandwidth_mbps,
-            upload_bandwidth_mbps,
-        }) = stormguard_site_speeds.get(&key)
+        let Some(adj @ NetworkAdjustment::AdjustSiteSpeed { site_name, .. }) =
+            stormguard_site_speeds.get(&key)
         else {
             continue;
         };
         if operator_site_name_seen.contains(site_name) {
             continue;
         }
-        out.push(NetworkAdjustment::AdjustSiteSpeed {
-            node_id: node_id.clone(),
-            site_name: site_name.clone(),
-            download_bandwidth_mbps: *download_bandwidth_mbps,
-            upload_bandwidth_mbps: *upload_bandwidth_mbps,
-        });
+        out.push(adj.clone());
     }
 
     out
@@ -619,9 +826,16 @@ impl OverrideFile {
 
     /// Saves this value to the operator-owned overrides file.
     pub fn save(&self) -> Result<()> {
+        self.save_with_source(OverrideLayer::Operator.history_source())
+    }
+
+    /// Saves this value to the operator-owned overrides file, attributing the
+    /// change to `source` in the input revision history.
+    pub fn save_with_source(&self, source: &str) -> Result<()> {
         let lock = FileLock::new_for_operation("save operator overrides")?;
         let config = lqos_config::load_config()?;
         let path = overrides_path(&config, OverrideLayer::Operator);
+        mark_history_source(&config, source);
         save_to_path(&path, self)?;
         drop(lock); // Explicitly drop for clarity. RAII does it anyway.
         Ok(())
@@ -690,6 +904,29 @@ impl OverrideFile {
         true
     }
 
+    /// Borrow the QoO profile assignments.
+    pub fn qoo_profiles(&self) -> &QooProfileOverrides {
+        &self.qoo_profiles
+    }
+
+    /// Assign (or with `None`, clear) a circuit's QoO profile. Returns true if changed.
+    pub fn set_circuit_qoo_profile_return_changed(
+        &mut self,
+        circuit_id: &str,
+        profile: Option<&str>,
+    ) -> bool {
+        set_qoo_profile(&mut self.qoo_profiles.circuits, circuit_id, profile)
+    }
+
+    /// Assign (or with `None`, clear) a site's QoO profile. Returns true if changed.
+    pub fn set_site_qoo_profile_return_changed(
+        &mut self,
+        site_name: &str,
+        profile: Option<&str>,
+    ) -> bool {
+        set_qoo_profile(&mut self.qoo_profiles.sites, site_name, profile)
+    }
+
     /// Add or replace a shaped device by `device_id`. Returns true if changed.
     pub fn add_persistent_shaped_device_return_changed(&mut self, device: ShapedDevice) -> bool {
         if let Some(existing) = self
@@ -748,7 +985,8 @@ impl OverrideFile {
                 CircuitAdjustment::DeviceAdjustSqm {
                     device_id: current,
                     sqm_override: existing_sqm,
-                } if current == &device_id && existing_sqm == &normalized
+                    schedule,
+                } if current == &device_id && existing_sqm == &normalized && schedule.is_empty()
             )
         }) {
             return false;
@@ -766,6 +1004,7 @@ impl OverrideFile {
             .push(CircuitAdjustment::DeviceAdjustSqm {
                 device_id,
                 sqm_override: normalized,
+                schedule: OverrideSchedule::default(),
             });
         true
     }
@@ -784,6 +1023,119 @@ impl OverrideFile {
         before.saturating_sub(self.circuit_adjustments.len())
     }
 
+    /// Set (or replace) the priority classes for a circuit. Returns true if changed.
+    pub fn set_circuit_priority_classes_return_changed(
+        &mut self,
+        circuit_id: String,
+        priority_classes: Vec<String>,
+    ) -> bool {
+        let mut normalized: Vec<String> = Vec::new();
+        for class in priority_classes {
+            let class = class.trim().to_ascii_lowercase();
+            if !class.is_empty() && !normalized.contains(&class) {
+                normalized.push(class);
+            }
+        }
+
+        if self.circuit_adjustments.iter().any(|adj| {
+            matches!(
+                adj,
+                CircuitAdjustment::CircuitAdjustPriorityClasses {
+                    circuit_id: current,
+                    priority_classes: existing,
+                    schedule,
+                } if current == &circuit_id && existing == &normalized && schedule.is_empty()
+            )
+        }) {
+            return false;
+        }
+
+        self.circuit_adjustments.retain(|adj| {
+            !matches!(
+                adj,
+                CircuitAdjustment::CircuitAdjustPriorityClasses {
+                    circuit_id: current, ..
+                } if current == &circuit_id
+            )
+        });
+        self.circuit_adjustments
+            .push(CircuitAdjustment::CircuitAdjustPriorityClasses {
+                circuit_id,
+                priority_classes: normalized,
+                schedule: OverrideSchedule::default(),
+            });
+        true
+    }
+
+    /// Remove any priority class adjustments for `circuit_id`. Returns number removed.
+    pub fn remove_circuit_priority_classes_count(&mut self, circuit_id: &str) -> usize {
+        let before = self.circuit_adjustments.len();
+        self.circuit_adjustments.retain(|adj| {
+            !matches!(
+                adj,
+                CircuitAdjustment::CircuitAdjustPriorityClasses {
+                    circuit_id: current, ..
+                } if current == circuit_id
+            )
+        });
+        before.saturating_sub(self.circuit_adjustments.len())
+    }
+
+    /// Find the access policy override for `circuit_id`, if any.
+    pub fn circuit_access_policy(&self, circuit_id: &str) -> Option<lqos_config::CircuitAccessPolicy> {
+        self.circuit_adjustments.iter().rev().find_map(|adj| match adj {
+            CircuitAdjustment::CircuitAdjustAccessPolicy {
+                circuit_id: current,
+                access_policy,
+                ..
+            } if current == circuit_id => Some(*access_policy),
+            _ => None,
+        })
+    }
+
+    /// Set (or replace) the access policy for a circuit. Returns true if changed.
+    pub fn set_circuit_access_policy_return_changed(
+        &mut self,
+        circuit_id: String,
+        access_policy: lqos_config::CircuitAccessPolicy,
+    ) -> bool {
+        if self.circuit_adjustments.iter().any(|adj| {
+            matches!(
+                adj,
+                CircuitAdjustment::CircuitAdjustAccessPolicy {
+                    circuit_id: current,
+                    access_policy: existing,
+                    schedule,
+                } if current == &circuit_id && *existing == access_policy && schedule.is_empty()
+            )
+        }) {
+            return false;
+        }
+
+        self.remove_circuit_access_policy_count(&circuit_id);
+        self.circuit_adjustments
+            .push(CircuitAdjustment::CircuitAdjustAccessPolicy {
+                circuit_id,
+                access_policy,
+                schedule: OverrideSchedule::default(),
+            });
+        true
+    }
+
+    /// Remove any access policy adjustments for `circuit_id`. Returns number removed.
+    pub fn remove_circuit_access_policy_count(&mut self, circuit_id: &str) -> usize {
+        let before = self.circuit_adjustments.len();
+        self.circuit_adjustments.retain(|adj| {
+            !matches!(
+                adj,
+                CircuitAdjustment::CircuitAdjustAccessPolicy {
+                    circuit_id: current, ..
+                } if current == circuit_id
+            )
+        });
+        before.saturating_sub(self.circuit_adjustments.len())
+    }
+
     /// Remove a circuit adjustment by index. Returns true if removed.
     pub fn remove_circuit_adjustment_by_index(&mut self, index: usize) -> bool {
         if index < self.circuit_adjustments.len() {
@@ -847,6 +1199,7 @@ impl OverrideFile {
             site_name: site_name.clone(),
             download_bandwidth_mbps,
             upload_bandwidth_mbps,
+            schedule: OverrideSchedule::default(),
         };
         if self.find_site_bandwidth_override(node_id.as_deref(), &site_name) == Some(&desired) {
             return false;
@@ -902,6 +1255,7 @@ impl OverrideFile {
             .push(NetworkAdjustment::SetNodeVirtual {
                 node_name,
                 virtual_node,
+                schedule: OverrideSchedule::default(),
             });
     }
 
@@ -987,6 +1341,7 @@ impl OverrideFile {
             mode,
             parent_node_ids,
             parent_node_names,
+            schedule: OverrideSchedule::default(),
         };
         if self.find_topology_parent_override(&normalized_node_id) == Some(&desired) {
             return false;
@@ -1071,6 +1426,183 @@ impl OverrideFile {
         }
         false
     }
+
+    /// Borrow circuit adjustments retired after expiring, oldest first.
+    pub fn expired_circuit_adjustments(&self) -> &[CircuitAdjustment] {
+        &self.expired_circuit_adjustments
+    }
+
+    /// Borrow network adjustments retired after expiring, oldest first.
+    pub fn expired_network_adjustments(&self) -> &[NetworkAdjustment] {
+        &self.expired_network_adjustments
+    }
+
+    /// Activates due entries and retires expired ones at `now_unix`.
+    ///
+    /// Due entries have their `not_before` cleared; expired entries move into the
+    /// retired history, which keeps the newest `RETIRED_HISTORY_LIMIT` per section.
+    /// Returns one line per change, empty when nothing changed.
+    pub fn apply_schedule_return_changes(&mut self, now_unix: u64) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        let mut kept = Vec::with_capacity(self.circuit_adjustments.len());
+        for mut adj in std::mem::take(&mut self.circuit_adjustments) {
+            match adj.schedule().state_at(now_unix) {
+                OverrideScheduleState::Expired => {
+                    changes.push(format!("retired {}", adj.describe()));
+                    self.expired_circuit_adjustments.push(adj);
+                }
+                OverrideScheduleState::Active if adj.schedule().not_before.is_some() => {
+                    adj.schedule_mut().not_before = None;
+                    changes.push(format!("activated {}", adj.describe()));
+                    kept.push(adj);
+                }
+                _ => kept.push(adj),
+            }
+        }
+        self.circuit_adjustments = kept;
+
+        let mut kept = Vec::with_capacity(self.network_adjustments.len());
+        for mut adj in std::mem::take(&mut self.network_adjustments) {
+            match adj.schedule().state_at(now_unix) {
+                OverrideScheduleState::Expired => {
+                    changes.push(format!("retired {}", adj.describe()));
+                    self.expired_network_adjustments.push(adj);
+                }
+                OverrideScheduleState::Active if adj.schedule().not_before.is_some() => {
+                    adj.schedule_mut().not_before = None;
+                    changes.push(format!("activated {}", adj.describe()));
+                    kept.push(adj);
+                }
+                _ => kept.push(adj),
+            }
+        }
+        self.network_adjustments = kept;
+
+        trim_history(&mut self.expired_circuit_adjustments);
+        trim_history(&mut self.expired_network_adjustments);
+        changes
+    }
+
+    /// Lists timed entries (upcoming, active with an expiry, or overdue) and the
+    /// retired history, as seen at `now_unix`.
+    ///
+    /// This function is pure: it has no side effects.
+    pub fn scheduled_overrides(&self, now_unix: u64) -> Vec<ScheduledOverrideSummary> {
+        let summary =
+            |section: &str,
+             index: usize,
+             retired: bool,
+             description: String,
+             schedule: &OverrideSchedule| ScheduledOverrideSummary {
+                section: section.to_string(),
+                index,
+                state: if retired {
+                    OverrideScheduleState::Expired
+                } else {
+                    schedule.state_at(now_unix)
+                },
+                retired,
+                description,
+                schedule: schedule.clone(),
+            };
+
+        let mut out = Vec::new();
+        for (index, adj) in self.circuit_adjustments.iter().enumerate() {
+            if adj.schedule().is_timed() {
+                out.push(summary(
+                    "circuit",
+                    index,
+                    false,
+                    adj.describe(),
+                    adj.schedule(),
+                ));
+            }
+        }
+        for (index, adj) in self.network_adjustments.iter().enumerate() {
+            if adj.schedule().is_timed() {
+                out.push(summary(
+                    "network",
+                    index,
+                    false,
+                    adj.describe(),
+                    adj.schedule(),
+                ));
+            }
+        }
+        for (index, adj) in self.expired_circuit_adjustments.iter().enumerate() {
+            out.push(summary(
+                "circuit",
+                index,
+                true,
+                adj.describe(),
+                adj.schedule(),
+            ));
+        }
+        for (index, adj) in self.expired_network_adjustments.iter().enumerate() {
+            out.push(summary(
+                "network",
+                index,
+                true,
+                adj.describe(),
+                adj.schedule(),
+            ));
+        }
+        out
+    }
+
+    /// Drops entries that do not apply at `now_unix`, and permanent entries that
+    /// an active timed entry for the same target temporarily replaces.
+    fn retain_active_at(&mut self, now_unix: u64) {
+        use std::collections::HashSet;
+
+        self.circuit_adjustments
+            .retain(|adj| adj.schedule().is_active_at(now_unix));
+        let timed: HashSet<(&'static str, String)> = self
+            .circuit_adjustments
+            .iter()
+            .filter(|adj| adj.schedule().is_timed())
+            .map(|adj| {
+                let (kind, target) = circuit_adjustment_merge_key(adj);
+                (kind, target.to_string())
+            })
+            .collect();
+        self.circuit_adjustments.retain(|adj| {
+            let (kind, target) = circuit_adjustment_merge_key(adj);
+            adj.schedule().is_timed() || !timed.contains(&(kind, target.to_string()))
+        });
+
+        self.network_adjustments
+            .retain(|adj| adj.schedule().is_active_at(now_unix));
+        let timed: HashSet<(&'static str, String)> = self
+            .network_adjustments
+            .iter()
+            .filter(|adj| adj.schedule().is_timed())
+            .map(network_adjustment_merge_key)
+            .collect();
+        self.network_adjustments.retain(|adj| {
+            adj.schedule().is_timed() || !timed.contains(&network_adjustment_merge_key(adj))
+        });
+    }
+}
+
+fn trim_history<T>(history: &mut Vec<T>) {
+    if history.len() > RETIRED_HISTORY_LIMIT {
+        let excess = history.len() - RETIRED_HISTORY_LIMIT;
+        history.drain(..excess);
+    }
+}
+
+fn network_adjustment_merge_key(adj: &NetworkAdjustment) -> (&'static str, String) {
+    match adj {
+        NetworkAdjustment::AdjustSiteSpeed {
+            node_id, site_name, ..
+        } => ("site_speed", site_speed_key(node_id.as_deref(), site_name)),
+        NetworkAdjustment::SetNodeVirtual { node_name, .. } => ("node_virtual", node_name.clone()),
+        NetworkAdjustment::TopologyParentOverride { node_id, .. } => {
+            ("topology_parent", node_id.clone())
+        }
+    }
 }
 
 fn site_speed_key(node_id: Option<&str>, site_name: &str) -> String {
@@ -1138,6 +1670,7 @@ impl OverrideStore {
         let lock = FileLock::new_for_operation(&format!("save {layer:?} overrides"))?;
         let config = lqos_config::load_config()?;
         let path = overrides_path(&config, layer);
+        mark_history_source(&config, layer.history_source());
         save_to_path(&path, overrides)?;
         drop(lock);
         Ok(())
@@ -1177,27 +1710,33 @@ impl OverrideStore {
         apply_stormguard: bool,
         apply_treeguard: bool,
     ) -> Result<OverrideFile> {
+        // Scheduled entries only count while their window is open, even if
+        // lqosd has not activated or retired them yet.
+        let now = lqos_utils::unix_time::unix_now().unwrap_or(0);
         let operator_path = overrides_path(config, OverrideLayer::Operator);
         ensure_exists_default(&operator_path)?;
-        let operator = load_from_path(&operator_path)?;
+        let mut operator = load_from_path(&operator_path)?;
+        operator.retain_active_at(now);
 
         if !apply_stormguard && !apply_treeguard {
             return Ok(operator);
         }
 
         let stormguard_path = overrides_path(config, OverrideLayer::Stormguard);
-        let stormguard = if !apply_stormguard || !stormguard_path.exists() {
+        let mut stormguard = if !apply_stormguard || !stormguard_path.exists() {
             OverrideFile::default()
         } else {
             load_from_path(&stormguard_path)?
         };
+        stormguard.retain_active_at(now);
 
         let treeguard_path = treeguard_read_path(config);
-        let treeguard = if !apply_treeguard || !treeguard_path.exists() {
+        let mut treeguard = if !apply_treeguard || !treeguard_path.exists() {
             OverrideFile::default()
         } else {
             load_from_path(&treeguard_path)?
         };
+        treeguard.retain_active_at(now);
 
         Ok(merge_owned_sections(operator, stormguard, treeguard))
     }
@@ -1299,6 +1838,7 @@ mod tests {
                 CircuitAdjustment::DeviceAdjustSqm {
                     device_id,
                     sqm_override,
+                    ..
                 } => sqm_override.as_deref().map(|sqm| (device_id.as_str(), sqm)),
                 _ => None,
             })
@@ -1325,6 +1865,25 @@ mod tests {
         assert_eq!(of.remove_device_sqm_override_by_device_count("dev1"), 0);
     }
 
+    #[test]
+    fn set_circuit_priority_classes_normalizes_and_is_idempotent() {
+        let mut of = OverrideFile::default();
+        assert!(of.set_circuit_priority_classes_return_changed(
+            "c1".to_string(),
+            vec![" VoIP ".to_string(), "voip".to_string(), "gaming".to_string()]
+        ));
+        assert!(!of.set_circuit_priority_classes_return_changed(
+            "c1".to_string(),
+            vec!["voip".to_string(), "gaming".to_string()]
+        ));
+        assert_eq!(
+            of.circuit_adjustments()[0].describe(),
+            "circuit_priority_classes c1 = voip,gaming"
+        );
+        assert_eq!(of.remove_circuit_priority_classes_count("c1"), 1);
+        assert_eq!(of.remove_circuit_priority_classes_count("c1"), 0);
+    }
+
     #[test]
     fn effective_merge_keeps_operator_node_virtual_and_ignores_treeguard_runtime_virtualization() {
         let mut operator = OverrideFile::default();
@@ -1334,6 +1893,7 @@ mod tests {
             site_name: "Site1".to_string(),
             download_bandwidth_mbps: Some(100.0),
             upload_bandwidth_mbps: Some(50.0),
+            schedule: OverrideSchedule::default(),
         });
 
         let mut stormguard = OverrideFile::default();
@@ -1347,6 +1907,7 @@ mod tests {
             site_name: "Site3".to_string(),
             download_bandwidth_mbps: Some(200.0),
             upload_bandwidth_mbps: Some(100.0),
+            schedule: OverrideSchedule::default(),
         });
 
         let merged = merge_owned_sections(operator, stormguard, treeguard);
@@ -1358,6 +1919,7 @@ mod tests {
                 NetworkAdjustment::SetNodeVirtual {
                     node_name,
                     virtual_node,
+                    ..
                 } if node_name == "NodeA" => Some(*virtual_node),
                 _ => None,
             });
@@ -1370,6 +1932,7 @@ mod tests {
                 NetworkAdjustment::SetNodeVirtual {
                     node_name,
                     virtual_node,
+                    ..
                 } if node_name == "Node3" => Some(*virtual_node),
                 _ => None,
             });
@@ -1486,6 +2049,7 @@ mod tests {
         treeguard.add_network_adjustment(NetworkAdjustment::SetNodeVirtual {
             node_name: "NodeA".to_string(),
             virtual_node: true,
+            schedule: OverrideSchedule::default(),
         });
 
         let merged = merge_owned_sections(operator, stormguard, treeguard);
@@ -1520,4 +2084,130 @@ mod tests {
 
         assert!(!of.set_circuit_rtt_excluded_return_changed("C1", false));
     }
+
+    #[test]
+    fn qoo_profile_assignments_are_normalized_and_idempotent() {
+        let mut of = OverrideFile::default();
+        assert!(of.set_circuit_qoo_profile_return_changed(" C1 ", Some("Real_Time_Gaming")));
+        assert!(!of.set_circuit_qoo_profile_return_changed("C1", Some("real_time_gaming")));
+        assert!(of.set_site_qoo_profile_return_changed("Tower", Some("auto")));
+        assert_eq!(
+            of.qoo_profiles().circuits.get("C1").map(String::as_str),
+            Some("real_time_gaming")
+        );
+
+        let json = serde_json::to_string(&of).expect("serialize");
+        let reloaded: OverrideFile = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(reloaded.qoo_profiles(), of.qoo_profiles());
+
+        assert!(of.set_circuit_qoo_profile_return_changed("C1", Some(" ")));
+        assert!(!of.set_circuit_qoo_profile_return_changed("C1", None));
+        assert!(of.set_site_qoo_profile_return_changed("Tower", None));
+        assert!(of.qoo_profiles().is_empty());
+        assert!(
+            !serde_json::to_string(&of)
+                .expect("serialize")
+                .contains("qoo_profiles")
+        );
+    }
+
+    fn timed(not_before: Option<u64>, expires_at: Option<u64>) -> OverrideSchedule {
+        OverrideSchedule {
+            not_before,
+            expires_at,
+            reason: Some("maintenance".to_string()),
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn schedule_fields_round_trip_alongside_the_type_tag() {
+        let adj = CircuitAdjustment::RemoveCircuit {
+            circuit_id: "c1".to_string(),
+            schedule: timed(Some(10), Some(20)),
+        };
+        let json = serde_json::to_value(&adj).expect("serialize");
+        assert_eq!(json["type"], "remove_circuit");
+        assert_eq!(json["not_before"], 10);
+        assert_eq!(json["reason"], "maintenance");
+        let back: CircuitAdjustment = serde_json::from_value(json).expect("deserialize");
+        assert_eq!(back.schedule(), &timed(Some(10), Some(20)));
+
+        // Entries written before schedules existed stay permanent.
+        let legacy: NetworkAdjustment =
+            serde_json::from_str(r#"{"type":"set_node_virtual","node_name":"A","virtual":true}"#)
+                .expect("legacy entry");
+        assert!(legacy.schedule().is_empty());
+        let permanent = serde_json::to_string(&legacy).expect("serialize");
+        assert!(!permanent.contains("not_before"));
+    }
+
+    #[test]
+    fn apply_schedule_activates_due_and_retires_expired_entries() {
+        let mut file = OverrideFile::default();
+        file.add_circuit_adjustment(CircuitAdjustment::RemoveCircuit {
+            circuit_id: "due".to_string(),
+            schedule: timed(Some(100), Some(300)),
+        });
+        file.add_circuit_adjustment(CircuitAdjustment::RemoveCircuit {
+            circuit_id: "later".to_string(),
+            schedule: timed(Some(500), None),
+        });
+        file.add_network_adjustment(NetworkAdjustment::SetNodeVirtual {
+            node_name: "A".to_string(),
+            virtual_node: true,
+            schedule: timed(None, Some(150)),
+        });
+
+        let changes = file.apply_schedule_return_changes(200);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(file.circuit_adjustments().len(), 2);
+        assert_eq!(file.circuit_adjustments()[0].schedule().not_before, None);
+        assert!(file.network_adjustments().is_empty());
+        assert_eq!(file.expired_network_adjustments().len(), 1);
+        assert!(file.apply_schedule_return_changes(200).is_empty());
+
+        let listed = file.scheduled_overrides(200);
+        let states: Vec<(OverrideScheduleState, bool)> =
+            listed.iter().map(|s| (s.state, s.retired)).collect();
+        assert_eq!(
+            states,
+            vec![
+                (OverrideScheduleState::Active, false),
+                (OverrideScheduleState::Upcoming, false),
+                (OverrideScheduleState::Expired, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn effective_view_skips_inactive_entries_and_lets_timed_entries_win() {
+        let mut file = OverrideFile::default();
+        file.set_site_bandwidth_override(None, "Tower".to_string(), Some(100.0), Some(50.0));
+        file.add_network_adjustment(NetworkAdjustment::AdjustSiteSpeed {
+            node_id: None,
+            site_name: "Tower".to_string(),
+            download_bandwidth_mbps: Some(20.0),
+            upload_bandwidth_mbps: Some(10.0),
+            schedule: timed(Some(100), Some(200)),
+        });
+
+        let mut before = OverrideFile {
+            network_adjustments: file.network_adjustments.clone(),
+            ..Default::default()
+        };
+        before.retain_active_at(50);
+        assert_eq!(before.network_adjustments().len(), 1);
+        assert!(before.network_adjustments()[0].schedule().is_empty());
+
+        file.retain_active_at(150);
+        assert_eq!(file.network_adjustments().len(), 1);
+        assert!(matches!(
+            file.network_adjustments()[0],
+            NetworkAdjustment::AdjustSiteSpeed {
+                download_bandwidth_mbps: Some(20.0),
+                ..
+            }
+        ));
+    }
 }
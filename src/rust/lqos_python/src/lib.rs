@@ -30,6 +30,7 @@ use blocking::{run_query, run_query_wait_for_bus};
 use lqos_bakery::estimate_full_reload_auto_qdisc_budget;
 use sysinfo::System;
 mod device_weights;
+mod live_data;
 use base64::Engine as _;
 use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use serde::{Deserialize, Serialize};
@@ -208,29 +209,59 @@ fn plan_top_level_cpu_bins(
     let planner_mode = match mode.as_deref() {
         Some("round_robin") => lqos_config::TopLevelPlannerMode::RoundRobin,
         Some("greedy") => lqos_config::TopLevelPlannerMode::Greedy,
+        Some("traffic_weighted") => lqos_config::TopLevelPlannerMode::TrafficWeighted,
         _ => lqos_config::TopLevelPlannerMode::StableGreedy,
     };
     let params = lqos_config::TopLevelPlannerParams {
-        mode: planner_mode,
+        mode: planner_mode.clone(),
         hysteresis_threshold: hysteresis_threshold.unwrap_or(0.03),
         cooldown_seconds: cooldown_seconds.unwrap_or(3600.0),
         move_budget_per_run: move_budget_per_run.unwrap_or(1),
     };
-    let result = lqos_config::plan_top_level_assignments(
-        &planner_items,
-        &bins,
-        &prev_assign_map,
-        &last_change_map,
-        now_ts.unwrap_or_else(|| {
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs_f64())
-                .unwrap_or(0.0)
-        }),
-        &params,
-    );
+    let now_ts = now_ts.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0)
+    });
 
     let out = PyDict::new(py);
+    let result = if planner_mode == lqos_config::TopLevelPlannerMode::TrafficWeighted {
+        let (samples, options) = traffic_planner_inputs(queues_available, &bins)?;
+        let traffic = lqos_config::plan_traffic_weighted_assignments(
+            &planner_items,
+            &samples,
+            &bins,
+            &prev_assign_map,
+            &last_change_map,
+            now_ts,
+            &params,
+            &options,
+        );
+        let projection = PyList::empty(py);
+        for row in traffic.projection {
+            let entry = PyDict::new(py);
+            entry.set_item("bin", row.bin)?;
+            entry.set_item("before", row.before)?;
+            entry.set_item("after", row.after)?;
+            entry.set_item("items_before", row.items_before)?;
+            entry.set_item("items_after", row.items_after)?;
+            projection.append(entry)?;
+        }
+        out.set_item("projection", projection)?;
+        out.set_item("observed", traffic.observed)?;
+        traffic.plan
+    } else {
+        lqos_config::plan_top_level_assignments(
+            &planner_items,
+            &bins,
+            &prev_assign_map,
+            &last_change_map,
+            now_ts,
+            &params,
+        )
+    };
+
     let assignment = PyDict::new(py);
     for (item, bin) in result.assignment {
         assignment.set_item(item, bin)?;
@@ -241,6 +272,47 @@ fn plan_top_level_cpu_bins(
     Ok(out.into())
 }
 
+/// Loads observed site traffic and NIC-locality bin costs for traffic-weighted planning.
+///
+/// Missing configuration or history degrades to configured weights with no locality costs; a
+/// history file that can't be read raises `OSError`.
+fn traffic_planner_inputs(
+    queues_available: usize,
+    bins: &[String],
+) -> PyResult<(
+    Vec<lqos_config::TopLevelTrafficSample>,
+    lqos_config::TrafficWeightOptions,
+)> {
+    let Ok(config) = lqos_config::load_config() else {
+        return Ok((Vec::new(), lqos_config::TrafficWeightOptions::default()));
+    };
+    let settings = config.traffic_planner.clone().unwrap_or_default();
+    let mut options = settings.weight_options();
+    options.bin_cost_multipliers = lqos_config::shaping_queue_locality_costs(
+        config.as_ref(),
+        queues_available,
+        settings.numa_remote_penalty,
+    )
+    .into_iter()
+    .filter_map(|(queue, cost)| bins.get(queue).map(|bin| (bin.clone(), cost)))
+    .collect();
+    let samples = lqos_config::load_planner_traffic_history(config.as_ref())
+        .map_err(|err| {
+            PyOSError::new_err(format!("Unable to load planner traffic history: {err}"))
+        })?
+        .map(|history| history.samples())
+        .unwrap_or_default();
+    Ok((samples, options))
+}
+
+#[pyfunction]
+fn traffic_planner_enabled() -> PyResult<bool> {
+    Ok(lqos_config::load_config()
+        .ok()
+        .and_then(|config| config.traffic_planner.as_ref().map(|tp| tp.enabled))
+        .unwrap_or(false))
+}
+
 #[pyfunction(
     signature = (
         sites,
@@ -937,6 +1009,19 @@ fn liblqos_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyExceptionCpe>()?;
     m.add_class::<device_weights::DeviceWeightResponse>()?;
     m.add_function(wrap_pyfunction!(is_lqosd_alive, m)?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_throughput, m)?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_top_circuits, m)?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_circuits, m)?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_circuit, m)?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_circuit_qoo, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        live_data::live_circuit_top_applications,
+        m
+    )?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_flows_by_ip, m)?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_queue_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_urgent_issues, m)?)?;
+    m.add_function(wrap_pyfunction!(live_data::live_bakery_status, m)?)?;
     m.add_function(wrap_pyfunction!(list_ip_mappings, m)?)?;
     m.add_function(wrap_pyfunction!(clear_ip_mappings, m)?)?;
     m.add_function(wrap_pyfunction!(sync_lqosd_config_from_disk, m)?)?;
@@ -964,6 +1049,7 @@ fn liblqos_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(interface_b, m)?)?;
     m.add_function(wrap_pyfunction!(enable_actual_shell_commands, m)?)?;
     m.add_function(wrap_pyfunction!(use_bin_packing_to_balance_cpu, m)?)?;
+    m.add_function(wrap_pyfunction!(traffic_planner_enabled, m)?)?;
     m.add_function(wrap_pyfunction!(queue_mode, m)?)?;
     m.add_function(wrap_pyfunction!(shaping_cpu_count, m)?)?;
     m.add_function(wrap_pyfunction!(efficiency_core_ids, m)?)?;
@@ -1646,6 +1732,7 @@ fn overrides_circuit_adjustments(py: Python<'_>) -> PyResult<Vec<PyObject>> {
                 max_download_bandwidth,
                 min_upload_bandwidth,
                 max_upload_bandwidth,
+                ..
             } => {
                 d.set_item("type", "circuit_adjust_speed")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
@@ -1668,6 +1755,7 @@ fn overrides_circuit_adjustments(py: Python<'_>) -> PyResult<Vec<PyObject>> {
                 max_download_bandwidth,
                 min_upload_bandwidth,
                 max_upload_bandwidth,
+                ..
             } => {
                 d.set_item("type", "device_adjust_speed")?;
                 d.set_item("device_id", device_id.clone())?;
@@ -1687,6 +1775,7 @@ fn overrides_circuit_adjustments(py: Python<'_>) -> PyResult<Vec<PyObject>> {
             lqos_overrides::CircuitAdjustment::DeviceAdjustSqm {
                 device_id,
                 sqm_override,
+                ..
             } => {
                 d.set_item("type", "device_adjust_sqm")?;
                 d.set_item("device_id", device_id.clone())?;
@@ -1694,17 +1783,36 @@ fn overrides_circuit_adjustments(py: Python<'_>) -> PyResult<Vec<PyObject>> {
                     d.set_item("sqm_override", value.clone())?;
                 }
             }
-            lqos_overrides::CircuitAdjustment::RemoveCircuit { circuit_id } => {
+            lqos_overrides::CircuitAdjustment::CircuitAdjustPriorityClasses {
+                circuit_id,
+                priority_classes,
+                ..
+            } => {
+                d.set_item("type", "circuit_adjust_priority_classes")?;
+                d.set_item("circuit_id", circuit_id.clone())?;
+                d.set_item("priority_classes", priority_classes.clone())?;
+            }
+            lqos_overrides::CircuitAdjustment::CircuitAdjustAccessPolicy {
+                circuit_id,
+                access_policy,
+                ..
+            } => {
+                d.set_item("type", "circuit_adjust_access_policy")?;
+                d.set_item("circuit_id", circuit_id.clone())?;
+                d.set_item("access_policy", access_policy.as_str())?;
+            }
+            lqos_overrides::CircuitAdjustment::RemoveCircuit { circuit_id, .. } => {
                 d.set_item("type", "remove_circuit")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
             }
-            lqos_overrides::CircuitAdjustment::RemoveDevice { device_id } => {
+            lqos_overrides::CircuitAdjustment::RemoveDevice { device_id, .. } => {
                 d.set_item("type", "remove_device")?;
                 d.set_item("device_id", device_id.clone())?;
             }
             lqos_overrides::CircuitAdjustment::ReparentCircuit {
                 circuit_id,
                 parent_node,
+                ..
             } => {
                 d.set_item("type", "reparent_circuit")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
@@ -1745,6 +1853,7 @@ fn overrides_circuit_adjustments_effective(py: Python<'_>) -> PyResult<Vec<PyObj
                 max_download_bandwidth,
                 min_upload_bandwidth,
                 max_upload_bandwidth,
+                ..
             } => {
                 d.set_item("type", "circuit_adjust_speed")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
@@ -1767,6 +1876,7 @@ fn overrides_circuit_adjustments_effective(py: Python<'_>) -> PyResult<Vec<PyObj
                 max_download_bandwidth,
                 min_upload_bandwidth,
                 max_upload_bandwidth,
+                ..
             } => {
                 d.set_item("type", "device_adjust_speed")?;
                 d.set_item("device_id", device_id.clone())?;
@@ -1786,6 +1896,7 @@ fn overrides_circuit_adjustments_effective(py: Python<'_>) -> PyResult<Vec<PyObj
             lqos_overrides::CircuitAdjustment::DeviceAdjustSqm {
                 device_id,
                 sqm_override,
+                ..
             } => {
                 d.set_item("type", "device_adjust_sqm")?;
                 d.set_item("device_id", device_id.clone())?;
@@ -1793,17 +1904,36 @@ fn overrides_circuit_adjustments_effective(py: Python<'_>) -> PyResult<Vec<PyObj
                     d.set_item("sqm_override", value.clone())?;
                 }
             }
-            lqos_overrides::CircuitAdjustment::RemoveCircuit { circuit_id } => {
+            lqos_overrides::CircuitAdjustment::CircuitAdjustPriorityClasses {
+                circuit_id,
+                priority_classes,
+                ..
+            } => {
+                d.set_item("type", "circuit_adjust_priority_classes")?;
+                d.set_item("circuit_id", circuit_id.clone())?;
+                d.set_item("priority_classes", priority_classes.clone())?;
+            }
+            lqos_overrides::CircuitAdjustment::CircuitAdjustAccessPolicy {
+                circuit_id,
+                access_policy,
+                ..
+            } => {
+                d.set_item("type", "circuit_adjust_access_policy")?;
+                d.set_item("circuit_id", circuit_id.clone())?;
+                d.set_item("access_policy", access_policy.as_str())?;
+            }
+            lqos_overrides::CircuitAdjustment::RemoveCircuit { circuit_id, .. } => {
                 d.set_item("type", "remove_circuit")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
             }
-            lqos_overrides::CircuitAdjustment::RemoveDevice { device_id } => {
+            lqos_overrides::CircuitAdjustment::RemoveDevice { device_id, .. } => {
                 d.set_item("type", "remove_device")?;
                 d.set_item("device_id", device_id.clone())?;
             }
             lqos_overrides::CircuitAdjustment::ReparentCircuit {
                 circuit_id,
                 parent_node,
+                ..
             } => {
                 d.set_item("type", "reparent_circuit")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
@@ -1842,6 +1972,7 @@ fn circuit_adjustments_to_py(
                 max_download_bandwidth,
                 min_upload_bandwidth,
                 max_upload_bandwidth,
+                ..
             } => {
                 d.set_item("type", "circuit_adjust_speed")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
@@ -1864,6 +1995,7 @@ fn circuit_adjustments_to_py(
                 max_download_bandwidth,
                 min_upload_bandwidth,
                 max_upload_bandwidth,
+                ..
             } => {
                 d.set_item("type", "device_adjust_speed")?;
                 d.set_item("device_id", device_id.clone())?;
@@ -1883,6 +2015,7 @@ fn circuit_adjustments_to_py(
             lqos_overrides::CircuitAdjustment::DeviceAdjustSqm {
                 device_id,
                 sqm_override,
+                ..
             } => {
                 d.set_item("type", "device_adjust_sqm")?;
                 d.set_item("device_id", device_id.clone())?;
@@ -1890,17 +2023,36 @@ fn circuit_adjustments_to_py(
                     d.set_item("sqm_override", value.clone())?;
                 }
             }
-            lqos_overrides::CircuitAdjustment::RemoveCircuit { circuit_id } => {
+            lqos_overrides::CircuitAdjustment::CircuitAdjustPriorityClasses {
+                circuit_id,
+                priority_classes,
+                ..
+            } => {
+                d.set_item("type", "circuit_adjust_priority_classes")?;
+                d.set_item("circuit_id", circuit_id.clone())?;
+                d.set_item("priority_classes", priority_classes.clone())?;
+            }
+            lqos_overrides::CircuitAdjustment::CircuitAdjustAccessPolicy {
+                circuit_id,
+                access_policy,
+                ..
+            } => {
+                d.set_item("type", "circuit_adjust_access_policy")?;
+                d.set_item("circuit_id", circuit_id.clone())?;
+                d.set_item("access_policy", access_policy.as_str())?;
+            }
+            lqos_overrides::CircuitAdjustment::RemoveCircuit { circuit_id, .. } => {
                 d.set_item("type", "remove_circuit")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
             }
-            lqos_overrides::CircuitAdjustment::RemoveDevice { device_id } => {
+            lqos_overrides::CircuitAdjustment::RemoveDevice { device_id, .. } => {
                 d.set_item("type", "remove_device")?;
                 d.set_item("device_id", device_id.clone())?;
             }
             lqos_overrides::CircuitAdjustment::ReparentCircuit {
                 circuit_id,
                 parent_node,
+                ..
             } => {
                 d.set_item("type", "reparent_circuit")?;
                 d.set_item("circuit_id", circuit_id.clone())?;
@@ -2002,6 +2154,7 @@ fn network_adjustments_to_py(
                 site_name,
                 download_bandwidth_mbps,
                 upload_bandwidth_mbps,
+                ..
             } => {
                 d.set_item("type", "adjust_site_speed")?;
                 if let Some(node_id) = node_id {
@@ -2018,6 +2171,7 @@ fn network_adjustments_to_py(
             lqos_overrides::NetworkAdjustment::SetNodeVirtual {
                 node_name,
                 virtual_node,
+                ..
             } => {
                 d.set_item("type", "set_node_virtual")?;
                 d.set_item("node_name", node_name.clone())?;
@@ -2029,6 +2183,7 @@ fn network_adjustments_to_py(
                 mode,
                 parent_node_ids,
                 parent_node_names,
+                ..
             } => {
                 d.set_item("type", "topology_parent_override")?;
                 d.set_item("node_id", node_id.clone())?;
@@ -3194,6 +3349,7 @@ fn shaping_runtime_sqm_fingerprint() -> Result<String> {
             lqos_overrides::CircuitAdjustment::DeviceAdjustSqm {
                 device_id,
                 sqm_override,
+                ..
             } => {
                 let token = sqm_override
                     .as_deref()
@@ -3424,6 +3580,7 @@ enum BakeryCommands {
         up_class_major: u16,
         ip_addresses: String,
         sqm_override: Option<String>,
+        priority_classes: Vec<String>,
     },
 }
 
@@ -3473,6 +3630,7 @@ impl BakeryCommands {
                 up_class_major,
                 ip_addresses,
                 sqm_override,
+                priority_classes,
             } => lqos_bakery::BakeryCommands::AddCircuit {
                 circuit_hash: *circuit_hash,
                 circuit_name: circuit_name.clone(),
@@ -3490,11 +3648,152 @@ impl BakeryCommands {
                 up_qdisc_handle: None,
                 ip_addresses: ip_addresses.clone(),
                 sqm_override: sqm_override.clone(),
+                priority_classes: priority_classes.clone(),
+                down_lane_qdisc_handles: Vec::new(),
+                up_lane_qdisc_handles: Vec::new(),
             },
         }
     }
 }
 
+/// Sends Bakery commands to `lqosd` in chunks, then sends `trailer` on its
+/// own and returns its response. With a `plan_id` the commands go into that
+/// plan batch instead of the live Bakery batch.
+fn send_bakery_queue(
+    queue: Vec<BakeryCommands>,
+    plan_id: Option<u64>,
+    trailer: Option<BusRequest>,
+) -> Option<BusResponse> {
+    let handle = std::thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let Ok(mut bus) = lqos_bus::LibreqosBusClient::new().await else {
+                    eprintln!("Failed to connect to lqosd bus for Bakery commit");
+                    return None;
+                };
+                if let Some(plan_id) = plan_id
+                    && let Err(e) = bus
+                        .request(vec![BusRequest::BakeryPlanStart { plan_id }])
+                        .await
+                {
+                    eprintln!("Failed to start Bakery plan batch: {}", e);
+                    return None;
+                }
+                let chunks = queue.chunks(1024);
+                for chunk in chunks {
+                    let mut requests = Vec::new();
+                    for msg in chunk {
+                        match msg {
+                            BakeryCommands::StartBatch if plan_id.is_some() => {}
+                            BakeryCommands::StartBatch => requests.push(BusRequest::BakeryStart),
+                            BakeryCommands::Commit => requests.push(BusRequest::BakeryCommit),
+                            BakeryCommands::MqSetup {
+                                queues_available,
+                                stick_offset,
+                            } => {
+                                requests.push(BusRequest::BakeryMqSetup {
+                                    queues_available: *queues_available,
+                                    stick_offset: *stick_offset,
+                                });
+                            }
+                            BakeryCommands::AddSite {
+                                site_hash,
+                                parent_class_id,
+                                up_parent_class_id,
+                                class_minor,
+                                download_bandwidth_min,
+                                upload_bandwidth_min,
+                                download_bandwidth_max,
+                                upload_bandwidth_max,
+                            } => {
+                                let command = BusRequest::BakeryAddSite {
+                                    site_hash: *site_hash,
+                                    parent_class_id: *parent_class_id,
+                                    up_parent_class_id: *up_parent_class_id,
+                                    class_minor: *class_minor,
+                                    download_bandwidth_min: *download_bandwidth_min,
+                                    upload_bandwidth_min: *upload_bandwidth_min,
+                                    download_bandwidth_max: *download_bandwidth_max,
+                                    upload_bandwidth_max: *upload_bandwidth_max,
+                                };
+                                requests.push(command);
+                            }
+                            BakeryCommands::AddCircuit {
+                                circuit_hash,
+                                circuit_name,
+                                site_name,
+                                parent_class_id,
+                                up_parent_class_id,
+                                class_minor,
+                                download_bandwidth_min,
+                                upload_bandwidth_min,
+                                download_bandwidth_max,
+                                upload_bandwidth_max,
+                                class_major,
+                                up_class_major,
+                                ip_addresses,
+                                sqm_override,
+                                priority_classes,
+                            } => {
+                                let command = BusRequest::BakeryAddCircuit {
+                                    circuit_hash: *circuit_hash,
+                                    circuit_name: circuit_name.clone(),
+                                    site_name: site_name.clone(),
+                                    parent_class_id: *parent_class_id,
+                                    up_parent_class_id: *up_parent_class_id,
+                                    class_minor: *class_minor,
+                                    download_bandwidth_min: *download_bandwidth_min,
+                                    upload_bandwidth_min: *upload_bandwidth_min,
+                                    download_bandwidth_max: *download_bandwidth_max,
+                                    upload_bandwidth_max: *upload_bandwidth_max,
+                                    class_major: *class_major,
+                                    up_class_major: *up_class_major,
+                                    ip_addresses: ip_addresses.clone(),
+                                    sqm_override: sqm_override.clone(),
+                                    priority_classes: priority_classes.clone(),
+                                };
+                                requests.push(command);
+                            }
+                        }
+                    }
+                    if let Some(plan_id) = plan_id {
+                        requests = vec![BusRequest::BakeryPlanAdd {
+                            plan_id,
+                            commands: requests,
+                        }];
+                    }
+                    if let Err(e) = bus.request(requests).await {
+                        eprintln!("Failed to send batch commands: {}", e);
+                    } else {
+                        println!("Sent a batch of commands to Bakery");
+                    }
+                }
+                let trailer = trailer?;
+                match bus.request(vec![trailer]).await {
+                    Ok(mut responses) if !responses.is_empty() => Some(responses.remove(0)),
+                    Ok(_) => None,
+                    Err(e) => {
+                        eprintln!("Failed to send Bakery request: {}", e);
+                        None
+                    }
+                }
+            })
+    });
+    handle.join().ok().flatten()
+}
+
+/// Picks an ID for a Bakery plan batch that is unlikely to repeat across runs.
+fn new_plan_id() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default();
+    (u64::from(std::process::id()) << 32) ^ nanos
+}
+
 #[pyclass]
 /// Queues Bakery operations for batched submission to the LibreQoS daemon.
 pub struct Bakery {
@@ -3519,107 +3818,27 @@ impl Bakery {
     pub fn commit(&mut self) -> PyResult<()> {
         self.queue.push(BakeryCommands::Commit);
 
-        // Send the commands batched up to the bus
-        let queue = self.queue.clone();
-        let handle = std::thread::spawn(move || {
-            tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap()
-                .block_on(async {
-                    let Ok(mut bus) = lqos_bus::LibreqosBusClient::new().await else {
-                        eprintln!("Failed to connect to lqosd bus for Bakery commit");
-                        return;
-                    };
-                    let chunks = queue.chunks(1024);
-                    for chunk in chunks {
-                        let mut requests = Vec::new();
-                        for msg in chunk {
-                            match msg {
-                                BakeryCommands::StartBatch => {
-                                    requests.push(BusRequest::BakeryStart)
-                                }
-                                BakeryCommands::Commit => requests.push(BusRequest::BakeryCommit),
-                                BakeryCommands::MqSetup {
-                                    queues_available,
-                                    stick_offset,
-                                } => {
-                                    requests.push(BusRequest::BakeryMqSetup {
-                                        queues_available: *queues_available,
-                                        stick_offset: *stick_offset,
-                                    });
-                                }
-                                BakeryCommands::AddSite {
-                                    site_hash,
-                                    parent_class_id,
-                                    up_parent_class_id,
-                                    class_minor,
-                                    download_bandwidth_min,
-                                    upload_bandwidth_min,
-                                    download_bandwidth_max,
-                                    upload_bandwidth_max,
-                                } => {
-                                    let command = BusRequest::BakeryAddSite {
-                                        site_hash: *site_hash,
-                                        parent_class_id: *parent_class_id,
-                                        up_parent_class_id: *up_parent_class_id,
-                                        class_minor: *class_minor,
-                                        download_bandwidth_min: *download_bandwidth_min,
-                                        upload_bandwidth_min: *upload_bandwidth_min,
-                                        download_bandwidth_max: *download_bandwidth_max,
-                                        upload_bandwidth_max: *upload_bandwidth_max,
-                                    };
-                                    requests.push(command);
-                                }
-                                BakeryCommands::AddCircuit {
-                                    circuit_hash,
-                                    circuit_name,
-                                    site_name,
-                                    parent_class_id,
-                                    up_parent_class_id,
-                                    class_minor,
-                                    download_bandwidth_min,
-                                    upload_bandwidth_min,
-                                    download_bandwidth_max,
-                                    upload_bandwidth_max,
-                                    class_major,
-                                    up_class_major,
-                                    ip_addresses,
-                                    sqm_override,
-                                } => {
-                                    let command = BusRequest::BakeryAddCircuit {
-                                        circuit_hash: *circuit_hash,
-                                        circuit_name: circuit_name.clone(),
-                                        site_name: site_name.clone(),
-                                        parent_class_id: *parent_class_id,
-                                        up_parent_class_id: *up_parent_class_id,
-                                        class_minor: *class_minor,
-                                        download_bandwidth_min: *download_bandwidth_min,
-                                        upload_bandwidth_min: *upload_bandwidth_min,
-                                        download_bandwidth_max: *download_bandwidth_max,
-                                        upload_bandwidth_max: *upload_bandwidth_max,
-                                        class_major: *class_major,
-                                        up_class_major: *up_class_major,
-                                        ip_addresses: ip_addresses.clone(),
-                                        sqm_override: sqm_override.clone(),
-                                    };
-                                    requests.push(command);
-                                }
-                            }
-                        }
-                        if let Err(e) = bus.request(requests).await {
-                            eprintln!("Failed to send batch commands: {}", e);
-                        } else {
-                            println!("Sent a batch of commands to Bakery");
-                        }
-                    }
-                });
-        });
-        let _ = handle.join();
-
+        send_bakery_queue(self.queue.clone(), None, None);
         Ok(())
     }
 
+    /// Sends the queued Bakery commands to `lqosd` and asks for a dry-run
+    /// plan instead of a commit. Returns the plan as a JSON string.
+    pub fn plan(&self) -> PyResult<String> {
+        let plan_id = new_plan_id();
+        match send_bakery_queue(
+            self.queue.clone(),
+            Some(plan_id),
+            Some(BusRequest::BakeryPlan { plan_id }),
+        ) {
+            Some(BusResponse::BakeryPlan(report)) => {
+                serde_json::to_string(&report).map_err(|e| PyOSError::new_err(e.to_string()))
+            }
+            Some(BusResponse::Fail(message)) => Err(PyOSError::new_err(message)),
+            _ => Err(PyOSError::new_err("lqosd did not return a Bakery plan")),
+        }
+    }
+
     /// Estimates whether the queued full-reload batch fits within the per-interface qdisc budget.
     pub fn estimate_qdisc_budget(&self, py: Python) -> PyResult<PyObject> {
         let config = lqos_config::load_config().map_err(|e| PyOSError::new_err(e.to_string()))?;
@@ -3814,6 +4033,7 @@ impl Bakery {
         up_class_major: u16,
         ip_addresses: String,
         sqm_override: Option<String>,
+        priority_classes: Vec<String>,
     ) -> PyResult<()> {
         let circuit_hash = lqos_utils::hash_to_i64(&circuit_name);
         //println!("Name: {circuit_name}, hash: {circuit_hash}");
@@ -3832,6 +4052,7 @@ impl Bakery {
             up_class_major,
             ip_addresses,
             sqm_override,
+            priority_classes,
         };
         self.queue.push(command);
         Ok(())
@@ -0,0 +1,354 @@
+//! Live shaper data for notebooks and scripts.
+//!
+//! Each function runs one bus query with the GIL released and returns plain
+//! dictionaries (or lists of them). Nested down/up pairs are flattened into
+//! `_down` / `_up` keys, so a list of rows drops straight into
+//! `pandas.DataFrame`.
+//!
+//! # Example
+//!
+//! ```python
+//! import pandas as pd
+//! from liblqos_python import live_top_circuits
+//! df = pd.DataFrame(live_top_circuits(10))
+//! print(df[["circuit_id", "bytes_per_second_down", "qoo_down"]])
+//! ```
+//!
+//! `lqos_live.py` wraps these in dataclasses, asyncio helpers and a
+//! throughput tick iterator.
+
+use crate::PyObject;
+use crate::blocking::run_query;
+use lqos_bus::{BusRequest, BusResponse, CircuitRollup};
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+fn query(py: Python<'_>, requests: Vec<BusRequest>) -> PyResult<Vec<BusResponse>> {
+    py.detach(|| run_query(requests))
+        .map_err(|e| PyOSError::new_err(e.to_string()))
+}
+
+fn query_one(py: Python<'_>, request: BusRequest) -> PyResult<BusResponse> {
+    query(py, vec![request])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| PyOSError::new_err("lqosd returned no response"))
+}
+
+fn unexpected(response: &BusResponse) -> PyErr {
+    match response {
+        BusResponse::Fail(message) => PyOSError::new_err(message.clone()),
+        BusResponse::NotReadyYet => PyOSError::new_err("lqosd is not ready yet"),
+        _ => PyOSError::new_err("Unexpected response from lqosd"),
+    }
+}
+
+fn flatten_into(prefix: &str, value: Value, out: &mut Map<String, Value>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}_{key}")
+                };
+                flatten_into(&key, value, out);
+            }
+        }
+        value => {
+            out.insert(prefix.to_string(), value);
+        }
+    }
+}
+
+/// Serializes `row` and flattens nested objects into `parent_child` keys.
+/// Lists are kept as they are.
+fn flat_row<T: Serialize>(row: &T) -> PyResult<Map<String, Value>> {
+    let value = serde_json::to_value(row).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let mut out = Map::new();
+    match value {
+        Value::Object(_) => flatten_into("", value, &mut out),
+        value => {
+            out.insert("value".to_string(), value);
+        }
+    }
+    Ok(out)
+}
+
+fn to_py(py: Python<'_>, value: Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.into_pyobject(py)?.to_owned().into_any().unbind(),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                u.into_pyobject(py)?.into_any().unbind()
+            } else if let Some(i) = n.as_i64() {
+                i.into_pyobject(py)?.into_any().unbind()
+            } else {
+                n.as_f64()
+                    .unwrap_or_default()
+                    .into_pyobject(py)?
+                    .into_any()
+                    .unbind()
+            }
+        }
+        Value::String(s) => s.into_pyobject(py)?.into_any().unbind(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_py(py, item)?)?;
+            }
+            list.into_any().unbind()
+        }
+        Value::Object(fields) => map_to_py(py, fields)?,
+    })
+}
+
+fn map_to_py(py: Python<'_>, fields: Map<String, Value>) -> PyResult<PyObject> {
+    let d = PyDict::new(py);
+    for (key, value) in fields {
+        d.set_item(key, to_py(py, value)?)?;
+    }
+    Ok(d.into_any().unbind())
+}
+
+fn rows_to_py<T: Serialize>(py: Python<'_>, rows: &[T]) -> PyResult<Vec<PyObject>> {
+    rows.iter()
+        .map(|row| map_to_py(py, flat_row(row)?))
+        .collect()
+}
+
+fn direction_is_upload(direction: &str) -> PyResult<bool> {
+    match direction.trim().to_ascii_lowercase().as_str() {
+        "down" | "download" => Ok(false),
+        "up" | "upload" => Ok(true),
+        other => Err(PyValueError::new_err(format!(
+            "direction must be 'down' or 'up', not '{other}'"
+        ))),
+    }
+}
+
+/// Busiest `n` circuits in one direction, busiest first.
+fn top_rollups(mut rollups: Vec<CircuitRollup>, n: usize, upload: bool) -> Vec<CircuitRollup> {
+    let rate = |r: &CircuitRollup| {
+        if upload {
+            r.bytes_per_second.up
+        } else {
+            r.bytes_per_second.down
+        }
+    };
+    rollups.sort_by(|a, b| {
+        rate(b)
+            .cmp(&rate(a))
+            .then_with(|| a.circuit_id.cmp(&b.circuit_id))
+    });
+    rollups.truncate(n);
+    rollups
+}
+
+/// Current shaper-wide throughput: bits, packets per protocol and shaped bits
+/// per second, each as `_down` / `_up` keys.
+#[pyfunction]
+pub fn live_throughput(py: Python<'_>) -> PyResult<PyObject> {
+    let response = query_one(py, BusRequest::GetCurrentThroughput)?;
+    let BusResponse::CurrentThroughput {
+        bits_per_second,
+        packets_per_second,
+        tcp_packets_per_second,
+        udp_packets_per_second,
+        icmp_packets_per_second,
+        shaped_bits_per_second,
+    } = response
+    else {
+        return Err(unexpected(&response));
+    };
+    let mut out = Map::new();
+    for (name, value) in [
+        ("bits_per_second", bits_per_second),
+        ("packets_per_second", packets_per_second),
+        ("tcp_packets_per_second", tcp_packets_per_second),
+        ("udp_packets_per_second", udp_packets_per_second),
+        ("icmp_packets_per_second", icmp_packets_per_second),
+        ("shaped_bits_per_second", shaped_bits_per_second),
+    ] {
+        out.insert(format!("{name}_down"), value.down.into());
+        out.insert(format!("{name}_up"), value.up.into());
+    }
+    map_to_py(py, out)
+}
+
+/// The `n` busiest circuits in `direction` ("down" or "up"), with their
+/// live rate, RTT, retransmits and QoO.
+#[pyfunction]
+#[pyo3(signature = (n = 10, direction = "down"))]
+pub fn live_top_circuits(py: Python<'_>, n: usize, direction: &str) -> PyResult<Vec<PyObject>> {
+    let upload = direction_is_upload(direction)?;
+    let response = query_one(py, BusRequest::GetAllCircuitRollups)?;
+    let BusResponse::CircuitRollups(rollups) = response else {
+        return Err(unexpected(&response));
+    };
+    rows_to_py(py, &top_rollups(rollups, n, upload))
+}
+
+/// Live rollups for every active circuit.
+#[pyfunction]
+pub fn live_circuits(py: Python<'_>) -> PyResult<Vec<PyObject>> {
+    let response = query_one(py, BusRequest::GetAllCircuitRollups)?;
+    let BusResponse::CircuitRollups(rollups) = response else {
+        return Err(unexpected(&response));
+    };
+    rows_to_py(py, &rollups)
+}
+
+/// Live rate, RTT, retransmits and QoO for one circuit, or `None` if the
+/// circuit has no recent traffic.
+#[pyfunction]
+pub fn live_circuit(py: Python<'_>, circuit_id: String) -> PyResult<Option<PyObject>> {
+    let response = query_one(py, BusRequest::GetCircuitRollupById { circuit_id })?;
+    let BusResponse::CircuitRollup(rollup) = response else {
+        return Err(unexpected(&response));
+    };
+    rollup
+        .map(|rollup| map_to_py(py, flat_row(&rollup)?))
+        .transpose()
+}
+
+/// QoO history blocks and latest score for one circuit, or `None`.
+#[pyfunction]
+pub fn live_circuit_qoo(py: Python<'_>, circuit_id: String) -> PyResult<Option<PyObject>> {
+    let response = query_one(py, BusRequest::GetCircuitQoo { circuit_id })?;
+    let BusResponse::Qoo(qoo) = response else {
+        return Err(unexpected(&response));
+    };
+    qoo.map(|qoo| map_to_py(py, flat_row(&qoo)?)).transpose()
+}
+
+/// Top applications of one circuit's recent flows, busiest first.
+#[pyfunction]
+pub fn live_circuit_top_applications(
+    py: Python<'_>,
+    circuit_id: String,
+) -> PyResult<Vec<PyObject>> {
+    let response = query_one(py, BusRequest::GetCircuitTopApplications { circuit_id })?;
+    let BusResponse::CircuitTopApplications(applications) = response else {
+        return Err(unexpected(&response));
+    };
+    rows_to_py(py, &applications)
+}
+
+/// Active flows to or from an IP address.
+#[pyfunction]
+pub fn live_flows_by_ip(py: Python<'_>, ip: String) -> PyResult<Vec<PyObject>> {
+    let response = query_one(py, BusRequest::FlowsByIp(ip))?;
+    let BusResponse::FlowsByIp(flows) = response else {
+        return Err(unexpected(&response));
+    };
+    rows_to_py(py, &flows)
+}
+
+/// Total queue marks and drops across all queues.
+#[pyfunction]
+pub fn live_queue_stats(py: Python<'_>) -> PyResult<PyObject> {
+    let response = query_one(py, BusRequest::GetQueueStatsTotal)?;
+    let BusResponse::QueueStatsTotal(totals) = response else {
+        return Err(unexpected(&response));
+    };
+    map_to_py(py, flat_row(&totals)?)
+}
+
+/// Urgent issues currently raised by lqosd and its helpers.
+#[pyfunction]
+pub fn live_urgent_issues(py: Python<'_>) -> PyResult<Vec<PyObject>> {
+    let response = query_one(py, BusRequest::GetUrgentIssues)?;
+    let BusResponse::UrgentIssues(issues) = response else {
+        return Err(unexpected(&response));
+    };
+    rows_to_py(py, &issues)
+}
+
+/// Bakery status: active circuits and whether a full reload is running.
+#[pyfunction]
+pub fn live_bakery_status(py: Python<'_>) -> PyResult<PyObject> {
+    let responses = query(
+        py,
+        vec![
+            BusRequest::GetBakeryStats,
+            BusRequest::GetExecutiveSummaryHeader,
+        ],
+    )?;
+    let mut out = Map::new();
+    for response in responses {
+        match response {
+            BusResponse::BakeryActiveCircuits(count) => {
+                out.insert("active_circuits".to_string(), count.into());
+            }
+            BusResponse::ExecutiveSummaryHeader(header) => {
+                out.insert(
+                    "reload_in_progress".to_string(),
+                    header.bakery_reload_in_progress.into(),
+                );
+                out.insert("circuit_count".to_string(), header.circuit_count.into());
+            }
+            other => return Err(unexpected(&other)),
+        }
+    }
+    map_to_py(py, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lqos_utils::units::DownUpOrder;
+
+    fn rollup(circuit_id: &str, down: u64, up: u64) -> CircuitRollup {
+        CircuitRollup {
+            circuit_id: circuit_id.to_string(),
+            circuit_name: String::new(),
+            parent_node: String::new(),
+            device_names: Vec::new(),
+            ip_addrs: vec!["192.0.2.1".to_string()],
+            plan_mbps: DownUpOrder::new(100.0, 20.0),
+            bytes_per_second: DownUpOrder::new(down, up),
+            rtt_current_p50_nanos: DownUpOrder::new(Some(12_000_000), None),
+            qoo: DownUpOrder::new(Some(90.0), None),
+            tcp_retransmit_sample: Default::default(),
+            dscp_remarked_per_second: DownUpOrder::zeroed(),
+            last_seen_nanos: 0,
+        }
+    }
+
+    #[test]
+    fn rows_are_flattened_for_dataframes() {
+        let row = flat_row(&rollup("c1", 5, 6)).expect("serializes");
+        assert_eq!(row["circuit_id"], "c1");
+        assert_eq!(row["bytes_per_second_down"], 5);
+        assert_eq!(row["bytes_per_second_up"], 6);
+        assert_eq!(row["rtt_current_p50_nanos_up"], Value::Null);
+        assert!(row["ip_addrs"].is_array());
+        assert!(!row.contains_key("bytes_per_second"));
+    }
+
+    #[test]
+    fn top_circuits_sort_by_the_requested_direction() {
+        let rollups = vec![
+            rollup("a", 10, 90),
+            rollup("b", 50, 10),
+            rollup("c", 30, 30),
+        ];
+        let down: Vec<_> = top_rollups(rollups.clone(), 2, false)
+            .into_iter()
+            .map(|r| r.circuit_id)
+            .collect();
+        assert_eq!(down, ["b", "c"]);
+        let up: Vec<_> = top_rollups(rollups, 1, true)
+            .into_iter()
+            .map(|r| r.circuit_id)
+            .collect();
+        assert_eq!(up, ["a"]);
+        assert!(direction_is_upload("Upload").expect("valid"));
+        assert!(direction_is_upload("sideways").is_err());
+    }
+}
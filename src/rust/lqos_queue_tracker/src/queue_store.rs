@@ -3,7 +3,9 @@ use crate::{
     queue_diff::{CakeDiffTin, QueueDiff, make_queue_diff},
     queue_types::QueueType,
 };
-use lqos_bus::{CakeDiffTinTransit, CakeDiffTransit, CakeTransit, QueueStoreTransit};
+use lqos_bus::{
+    CakeDiffTinTransit, CakeDiffTransit, CakeTransit, PriorityLaneTransit, QueueStoreTransit,
+};
 use serde::Serialize;
 
 #[derive(Debug, Serialize, Clone)]
@@ -14,6 +16,16 @@ pub struct QueueStore {
     prev_upload: Option<QueueType>,
     current_download: QueueType,
     current_upload: QueueType,
+    priority_lanes: Vec<LaneStore>,
+}
+
+/// Latest sample and change for one intra-circuit priority lane.
+#[derive(Debug, Serialize, Clone)]
+struct LaneStore {
+    minor: u16,
+    upload: bool,
+    current: QueueType,
+    latest: QueueDiff,
 }
 
 impl QueueStore {
@@ -25,6 +37,34 @@ impl QueueStore {
             prev_download: None,
             current_download: download,
             current_upload: upload,
+            priority_lanes: Vec::new(),
+        }
+    }
+
+    /// Replaces one direction's priority-lane samples, diffing each lane
+    /// against its previous sample.
+    pub(crate) fn update_priority_lanes(&mut self, upload: bool, lanes: Vec<(u16, QueueType)>) {
+        let mut previous: Vec<LaneStore> = Vec::new();
+        self.priority_lanes.retain(|lane| {
+            if lane.upload == upload {
+                previous.push(lane.clone());
+                false
+            } else {
+                true
+            }
+        });
+        for (minor, current) in lanes {
+            let latest = previous
+                .iter()
+                .find(|lane| lane.minor == minor)
+                .and_then(|lane| make_queue_diff(&lane.current, &current).ok())
+                .unwrap_or(QueueDiff::None);
+            self.priority_lanes.push(LaneStore {
+                minor,
+                upload,
+                current,
+                latest,
+            });
         }
     }
 
@@ -54,22 +94,22 @@ impl QueueStore {
     }
 }
 
+fn queue_kind(queue: &QueueType) -> &'static str {
+    match queue {
+        QueueType::Cake(_) => "cake",
+        QueueType::FqCodel(_) => "fq_codel",
+        _ => "none",
+    }
+}
+
 // Note: I'm overriding the warning because the "from only" behaviour
 // is actually what we want here.
 #[allow(clippy::from_over_into)]
 impl Into<QueueStoreTransit> for QueueStore {
     fn into(self) -> QueueStoreTransit {
         // Determine queue kinds for display
-        let kind_down = match &self.current_download {
-            QueueType::Cake(_) => "cake",
-            QueueType::FqCodel(_) => "fq_codel",
-            _ => "none",
-        };
-        let kind_up = match &self.current_upload {
-            QueueType::Cake(_) => "cake",
-            QueueType::FqCodel(_) => "fq_codel",
-            _ => "none",
-        };
+        let kind_down = queue_kind(&self.current_download);
+        let kind_up = queue_kind(&self.current_upload);
         QueueStoreTransit {
             history: self
                 .history
@@ -84,6 +124,16 @@ impl Into<QueueStoreTransit> for QueueStore {
             current_upload: self.current_upload.into(),
             kind_down: kind_down.to_string(),
             kind_up: kind_up.to_string(),
+            priority_lanes: self
+                .priority_lanes
+                .into_iter()
+                .map(|lane| PriorityLaneTransit {
+                    minor: lane.minor,
+                    upload: lane.upload,
+                    kind: queue_kind(&lane.current).to_string(),
+                    latest: lane.latest.into(),
+                })
+                .collect(),
         }
     }
 }
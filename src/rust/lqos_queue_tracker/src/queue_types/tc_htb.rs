@@ -33,6 +33,11 @@ struct TcHtbOptions {
 }
 
 impl TcHtb {
+    /// The qdisc handle, used to find the lanes of a nested priority HTB.
+    pub(crate) fn handle(&self) -> TcHandle {
+        self.handle
+    }
+
     pub(crate) fn from_json(
         map: &serde_json::Map<std::string::String, Value>,
     ) -> Result<Self, QDiscError> {
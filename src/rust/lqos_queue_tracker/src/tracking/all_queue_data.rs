@@ -149,6 +149,18 @@ impl AllQueueData {
         }
     }
 
+    /// Visits the raw drop and mark counters of every queue, as last read from
+    /// `tc`. They restart whenever a queue is rebuilt.
+    pub fn iterate_queue_counters(
+        &self,
+        mut f: impl FnMut(i64, &DownUpOrder<u64>, &DownUpOrder<u64>),
+    ) {
+        let lock = self.data.lock();
+        for (circuit_hash, q) in lock.iter() {
+            f(*circuit_hash, &q.drops, &q.marks);
+        }
+    }
+
     pub fn calculate_total_queue_stats(&self) {
         zero_total_queue_stats();
         let lock = self.data.lock();
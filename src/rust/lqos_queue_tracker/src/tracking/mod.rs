@@ -1,6 +1,8 @@
 use crate::{
-    circuit_to_queue::CIRCUIT_TO_QUEUE, interval::QUEUE_MONITOR_INTERVAL, queue_store::QueueStore,
-    tracking::reader::read_named_queue_from_interface,
+    circuit_to_queue::CIRCUIT_TO_QUEUE,
+    interval::QUEUE_MONITOR_INTERVAL,
+    queue_store::QueueStore,
+    tracking::reader::{read_named_queue_from_interface, read_priority_lane_queues},
 };
 use lqos_bakery::full_reload_in_progress;
 use lqos_utils::fdtimer::periodic;
@@ -64,17 +66,30 @@ fn track_queues() {
         if let Ok(download) = download
             && let Ok(upload) = upload
         {
+            let (down_interface, up_interface) = if config.on_a_stick_mode() {
+                (config.internet_interface(), config.internet_interface())
+            } else {
+                (config.isp_interface(), config.internet_interface())
+            };
             if let Some(mut circuit) = CIRCUIT_TO_QUEUE.get_mut(circuit_id) {
                 if !download.is_empty() && !upload.is_empty() {
-                    circuit.update(&download[0], &upload[0]);
+                    let (download, down_lanes) =
+                        resolve_priority_lanes(&down_interface, &download[0]);
+                    let (upload, up_lanes) = resolve_priority_lanes(&up_interface, &upload[0]);
+                    circuit.update(&download, &upload);
+                    circuit.update_priority_lanes(false, down_lanes);
+                    circuit.update_priority_lanes(true, up_lanes);
                 }
             } else {
                 // It's new: insert it
                 if !download.is_empty() && !upload.is_empty() {
-                    CIRCUIT_TO_QUEUE.insert(
-                        circuit_id.to_string(),
-                        QueueStore::new(download[0].clone(), upload[0].clone()),
-                    );
+                    let (download, down_lanes) =
+                        resolve_priority_lanes(&down_interface, &download[0]);
+                    let (upload, up_lanes) = resolve_priority_lanes(&up_interface, &upload[0]);
+                    let mut store = QueueStore::new(download, upload);
+                    store.update_priority_lanes(false, down_lanes);
+                    store.update_priority_lanes(true, up_lanes);
+                    CIRCUIT_TO_QUEUE.insert(circuit_id.to_string(), store);
                 } else {
                     debug!(
                         "No queue data returned for {}, {}/{} found.",
@@ -91,6 +106,23 @@ fn track_queues() {
     expire_watched_queues();
 }
 
+/// Swaps a circuit's nested priority HTB for its default lane, so the usual
+/// queue history keeps working, and returns every lane's leaf queue.
+fn resolve_priority_lanes(
+    interface: &str,
+    queue: &QueueType,
+) -> (QueueType, Vec<(u16, QueueType)>) {
+    let QueueType::Htb(htb) = queue else {
+        return (queue.clone(), Vec::new());
+    };
+    let lanes = read_priority_lane_queues(interface, htb.handle());
+    let primary = lanes
+        .first()
+        .map(|(_, lane)| lane.clone())
+        .unwrap_or_else(|| queue.clone());
+    (primary, lanes)
+}
+
 /// Holds the CAKE marks/drops for a given queue/circuit.
 pub struct TrackedQueue {
     circuit_hash: i64,
@@ -1,10 +1,13 @@
 use crate::{deserialize_tc_tree, queue_types::QueueType};
 use lqos_bus::TcHandle;
+use lqos_config::MAX_PRIORITY_CLASSES_PER_CIRCUIT;
 use std::process::Command;
 use thiserror::Error;
 use tracing::{debug, error, info};
 
 const TC: &str = "/sbin/tc";
+/// Class minor of the default priority lane; must match the Bakery layout.
+const DEFAULT_LANE_MINOR: u16 = 0x10;
 
 pub fn read_all_queues_from_interface(interface: &str) -> Result<Vec<QueueType>, QueueReaderError> {
     let command_output = Command::new(TC)
@@ -72,6 +75,25 @@ pub fn read_named_queue_from_interface(
     Ok(result)
 }
 
+/// Reads the lane leaf qdiscs beneath a circuit's nested priority HTB.
+///
+/// Lanes are numbered from the default lane's class minor upward with no
+/// gaps, so reading stops at the first minor without a leaf.
+pub fn read_priority_lane_queues(interface: &str, htb_handle: TcHandle) -> Vec<(u16, QueueType)> {
+    let (major, _) = htb_handle.get_major_minor();
+    let first = DEFAULT_LANE_MINOR;
+    let last = first + MAX_PRIORITY_CLASSES_PER_CIRCUIT as u16;
+    let mut lanes = Vec::new();
+    for minor in first..=last {
+        let parent = TcHandle::from_u32(((major as u32) << 16) | minor as u32);
+        match read_named_queue_from_interface(interface, parent) {
+            Ok(mut queues) if !queues.is_empty() => lanes.push((minor, queues.swap_remove(0))),
+            _ => break,
+        }
+    }
+    lanes
+}
+
 #[derive(Error, Debug)]
 pub enum QueueReaderError {
     #[error("Subprocess call failed")]
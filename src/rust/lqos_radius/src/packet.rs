@@ -128,6 +128,31 @@ impl RadiusPacket {
     pub fn attributes(&self) -> &[RadiusAttribute] {
         &self.attributes
     }
+
+    /// Encodes the packet back into its wire format.
+    ///
+    /// Parsed packets round-trip byte for byte, so the authenticators stay
+    /// valid for the original shared secret. Side effects: none.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = RADIUS_HEADER_LEN
+            + self
+                .attributes
+                .iter()
+                .map(|attribute| RADIUS_ATTRIBUTE_HEADER_LEN + attribute.value.len())
+                .sum::<usize>();
+        let mut bytes = Vec::with_capacity(len);
+        bytes.push(self.code.as_u8());
+        bytes.push(self.identifier);
+        bytes.extend_from_slice(&(len as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.authenticator);
+        for attribute in &self.attributes {
+            bytes.push(attribute.kind);
+            bytes.push((RADIUS_ATTRIBUTE_HEADER_LEN + attribute.value.len()) as u8);
+            bytes.extend_from_slice(&attribute.value);
+        }
+        bytes
+    }
 }
 
 /// A well-formed RADIUS Accounting-Request packet.
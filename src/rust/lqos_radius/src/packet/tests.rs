@@ -116,6 +116,20 @@ fn parses_minimal_accounting_request_raw_fixture() {
     assert_eq!(parsed.attributes(), &[]);
 }
 
+#[test]
+fn encodes_parsed_packets_byte_for_byte() {
+    for datagram in [
+        &FIXED_ACCOUNTING_REQUEST[..],
+        &FIXED_MESSAGE_AUTHENTICATOR_REQUEST[..],
+        &FIXED_MULTI_PROXY_ACCOUNTING_REQUEST[..],
+    ] {
+        let encoded = parse_packet(datagram).unwrap().to_bytes();
+
+        assert_eq!(encoded, datagram);
+        assert!(verify_optional(&encoded).is_ok());
+    }
+}
+
 #[test]
 fn rejects_short_packet_header() {
     assert_eq!(
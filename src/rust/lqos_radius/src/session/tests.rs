@@ -3263,6 +3263,9 @@ fn shaped_device(circuit_id: &str, device_id: &str, mac: &str) -> ShapedDevice {
         upload_max_mbps: 20.0,
         comment: "matched from shaped devices".to_string(),
         sqm_override: Some("cake/none".to_string()),
+        priority_classes: Vec::new(),
+        access_policy: lqos_config::CircuitAccessPolicy::Open,
+        qoo_profile: None,
         circuit_hash: 0,
         device_hash: 0,
         parent_hash: 0,
@@ -8,12 +8,13 @@ mod service_handoff;
 mod setup_actions;
 #[cfg(test)]
 mod test_support;
+mod unattended;
 mod web;
 mod webusers;
 
 use clap::{Parser, Subcommand};
 use lqos_setup::{bootstrap, hotfix};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use bandwidth::bandwidth_view;
 use config_builder::{CURRENT_CONFIG, existing_config_load_error};
@@ -56,6 +57,14 @@ enum Command {
     ActivateSetup,
     /// Create or refresh and print the current tokenized setup link(s)
     PrintLink,
+    /// Apply an answers file without prompting and print a JSON report
+    Unattended {
+        /// TOML answers file, or YAML when the name ends in .yaml or .yml
+        answers: PathBuf,
+        /// Validate and report what would change without writing anything
+        #[arg(long)]
+        check: bool,
+    },
 }
 
 fn config_exists() -> bool {
@@ -268,6 +277,16 @@ fn main() {
                 std::process::exit(1);
             }
         },
+        Some(Command::Unattended { answers, check }) => {
+            let report = unattended::run(&answers, check);
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => println!("{json}"),
+                Err(err) => eprintln!("Unable to render setup report: {err}"),
+            }
+            if !report.ok {
+                std::process::exit(1);
+            }
+        }
         Some(Command::Tui) | None => run_tui(args.skip_if_ready),
     }
 }
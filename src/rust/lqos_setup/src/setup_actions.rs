@@ -127,7 +127,19 @@ pub(crate) fn prepare_commit() -> Result<CommitOutcome> {
     let mut event_log = Vec::new();
     let existing_config = load_existing_or_default(&mut event_log)?;
     let config = build_candidate_config(Some(existing_config));
-    let using_helper = !matches!(CURRENT_CONFIG.lock().bridge_mode, BridgeMode::XDP);
+    commit_candidate(config, event_log)
+}
+
+/// Writes a fully built candidate config, staging a netplan transaction
+/// through the helper unless the candidate keeps the legacy XDP bridge.
+pub(crate) fn commit_candidate(
+    config: lqos_config::Config,
+    mut event_log: Vec<String>,
+) -> Result<CommitOutcome> {
+    let using_helper = !config
+        .bridge
+        .as_ref()
+        .is_some_and(|bridge| bridge.use_xdp_bridge);
 
     if !using_helper {
         lqos_config::update_config(&config)?;
@@ -237,7 +249,7 @@ pub(crate) fn persist_setup_success(
     Ok(())
 }
 
-fn load_existing_or_default(event_log: &mut Vec<String>) -> Result<lqos_config::Config> {
+pub(crate) fn load_existing_or_default(event_log: &mut Vec<String>) -> Result<lqos_config::Config> {
     if let Ok(config) = lqos_config::load_config() {
         event_log.push("Loaded existing configuration".to_string());
         return Ok((*config).clone());
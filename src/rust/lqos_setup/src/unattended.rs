@@ -0,0 +1,825 @@
+//! Unattended setup driven by an answers file, for Ansible and cloud-init.
+//!
+//! The answers file is TOML, or YAML when its name ends in `.yaml` or `.yml`.
+//! It drives the same actions as the TUI and the setup WebUI. Each step
+//! compares the requested state with the current one before acting, so
+//! re-running the same file changes nothing. With `--check` the file is
+//! validated and the report says what would change, without writing.
+
+use crate::config_builder::{BridgeMode, CURRENT_CONFIG, existing_config_uses_xdp};
+use crate::setup_actions::{self, CommitOutcome};
+use crate::{interfaces, service_handoff};
+use anyhow::{Context, Result, bail};
+use ip_network::IpNetwork;
+use lqos_config::{UserRole, WebUsers};
+use lqos_netplan_helper::transaction::{HelperPaths, inspect_with_paths};
+use lqos_setup::{bootstrap, hotfix};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Integrations whose `<name>_integration` config section can be set from
+/// the answers file.
+const INTEGRATIONS: &[&str] = &[
+    "netzur",
+    "powercode",
+    "sonar",
+    "splynx",
+    "uisp",
+    "visp",
+    "wispgate",
+];
+
+/// Desired state read from the answers file. Omitted sections keep the
+/// current values.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Answers {
+    #[serde(default)]
+    pub(crate) node_name: Option<String>,
+    /// Install the Ubuntu 24.04 systemd hotfix when the host needs it.
+    #[serde(default)]
+    pub(crate) install_hotfix: bool,
+    #[serde(default)]
+    pub(crate) network: Option<NetworkAnswers>,
+    #[serde(default)]
+    pub(crate) bandwidth: Option<BandwidthAnswers>,
+    #[serde(default)]
+    pub(crate) users: Vec<UserAnswers>,
+    #[serde(default)]
+    pub(crate) ssl: Option<SslAnswers>,
+    #[serde(default)]
+    pub(crate) ip_ranges: Option<IpRangeAnswers>,
+    /// Keys merged into the matching `<name>_integration` config section.
+    #[serde(default)]
+    pub(crate) integrations: BTreeMap<String, toml::Table>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum NetworkMode {
+    Linux,
+    Single,
+    Xdp,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NetworkAnswers {
+    pub(crate) mode: NetworkMode,
+    /// Internet-facing interface of a bridge.
+    #[serde(default)]
+    pub(crate) to_internet: String,
+    /// Network-facing interface of a bridge.
+    #[serde(default)]
+    pub(crate) to_network: String,
+    /// Shaping interface in single-interface mode.
+    #[serde(default)]
+    pub(crate) interface: String,
+    #[serde(default)]
+    pub(crate) internet_vlan: u32,
+    #[serde(default)]
+    pub(crate) network_vlan: u32,
+    /// Confirm netplan changes the helper flags as able to cut off access.
+    #[serde(default)]
+    pub(crate) allow_disruptive_changes: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BandwidthAnswers {
+    pub(crate) downlink_mbps: u64,
+    pub(crate) uplink_mbps: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RoleAnswer {
+    #[default]
+    Admin,
+    ReadOnly,
+}
+
+impl From<RoleAnswer> for UserRole {
+    fn from(role: RoleAnswer) -> Self {
+        match role {
+            RoleAnswer::Admin => UserRole::Admin,
+            RoleAnswer::ReadOnly => UserRole::ReadOnly,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct UserAnswers {
+    pub(crate) username: String,
+    #[serde(default)]
+    pub(crate) password: Option<String>,
+    /// File holding the password; one trailing newline is ignored.
+    #[serde(default)]
+    pub(crate) password_file: Option<PathBuf>,
+    #[serde(default)]
+    pub(crate) role: RoleAnswer,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SslAnswers {
+    pub(crate) enabled: bool,
+    #[serde(default)]
+    pub(crate) external_hostname: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct IpRangeAnswers {
+    #[serde(default)]
+    pub(crate) allow_subnets: Option<Vec<String>>,
+    #[serde(default)]
+    pub(crate) ignore_subnets: Option<Vec<String>>,
+}
+
+/// Host facts the answers are validated against.
+pub(crate) struct HostFacts {
+    /// Detected multi-queue interfaces LibreQoS can shape on.
+    pub(crate) interfaces: Vec<String>,
+    pub(crate) allow_legacy_xdp: bool,
+    pub(crate) config_loads: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StepStatus {
+    Unchanged,
+    Changed,
+    WouldChange,
+    Skipped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct StepReport {
+    pub(crate) step: &'static str,
+    pub(crate) status: StepStatus,
+    pub(crate) detail: String,
+}
+
+/// Machine-readable outcome printed as JSON.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct UnattendedReport {
+    pub(crate) check: bool,
+    pub(crate) ok: bool,
+    pub(crate) changed: bool,
+    /// The install was already set up and its config changed; restart
+    /// `lqosd` to apply it.
+    pub(crate) restart_required: bool,
+    pub(crate) steps: Vec<StepReport>,
+    pub(crate) errors: Vec<String>,
+}
+
+impl UnattendedReport {
+    fn step(&mut self, step: &'static str, status: StepStatus, detail: impl Into<String>) {
+        if matches!(status, StepStatus::Changed | StepStatus::WouldChange) {
+            self.changed = true;
+        }
+        self.steps.push(StepReport {
+            step,
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    fn pending(&self) -> StepStatus {
+        if self.check {
+            StepStatus::WouldChange
+        } else {
+            StepStatus::Changed
+        }
+    }
+}
+
+/// Runs (or with `check`, validates) the answers file at `path`.
+///
+/// Side effects when `check` is false: may install the systemd hotfix, write
+/// `lqusers.toml`, `/etc/lqos.conf` and the managed netplan file, configure
+/// Caddy, record setup completion and schedule the runtime service handoff.
+pub(crate) fn run(path: &Path, check: bool) -> UnattendedReport {
+    let mut report = UnattendedReport {
+        check,
+        ok: false,
+        changed: false,
+        restart_required: false,
+        steps: Vec::new(),
+        errors: Vec::new(),
+    };
+    if let Err(err) = run_steps(path, &mut report) {
+        report.errors.push(format!("{err:#}"));
+    }
+    report.ok = report.errors.is_empty();
+    report
+}
+
+fn run_steps(path: &Path, report: &mut UnattendedReport) -> Result<()> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read answers file {}", path.display()))?;
+    let answers = parse_answers(path, &raw)?;
+    let facts = HostFacts {
+        interfaces: interfaces::get_interfaces()?,
+        allow_legacy_xdp: existing_config_uses_xdp(),
+        config_loads: lqos_config::load_config().is_ok(),
+    };
+    let errors = validate_answers(&answers, &facts);
+    if !errors.is_empty() {
+        report.errors.extend(errors);
+        return Ok(());
+    }
+    let users = read_passwords(&answers.users)?;
+
+    hotfix_step(&answers, report)?;
+    users_step(&users, report)?;
+    if !bootstrap::first_admin_exists()
+        && !users
+            .iter()
+            .any(|(_, _, role)| matches!(role, UserRole::Admin))
+    {
+        bail!("Setup requires at least one admin user. Add one under [[users]].");
+    }
+
+    let setup_was_incomplete = bootstrap::setup_is_incomplete().unwrap_or(true);
+    let config_changed = config_step(&answers, report)?;
+    ssl_step(&answers, report)?;
+
+    if setup_was_incomplete {
+        if report.check {
+            report.step(
+                "setup_state",
+                StepStatus::WouldChange,
+                "Setup would be marked complete and runtime services started.",
+            );
+        } else {
+            let config = reload_config()?;
+            let mut event_log = Vec::new();
+            setup_actions::persist_setup_success(&config, &mut event_log)?;
+            match service_handoff::schedule_runtime_handoff() {
+                Ok(notice) => event_log.push(notice.message),
+                Err(err) => event_log.push(format!("WARNING: {err:#}")),
+            }
+            let _ = bootstrap::store_setup_completion_report(&event_log.join("\n"));
+            report.step("setup_state", StepStatus::Changed, event_log.join(" "));
+        }
+    } else {
+        report.restart_required = config_changed;
+        report.step(
+            "setup_state",
+            StepStatus::Unchanged,
+            "Setup is already complete.",
+        );
+    }
+    Ok(())
+}
+
+/// Parses answers as YAML for `.yaml`/`.yml` files and as TOML otherwise.
+pub(crate) fn parse_answers(path: &Path, raw: &str) -> Result<Answers> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(raw)
+            .with_context(|| format!("Unable to parse YAML answers file {}", path.display())),
+        _ => toml::from_str(raw)
+            .with_context(|| format!("Unable to parse TOML answers file {}", path.display())),
+    }
+}
+
+/// Returns every problem with `answers`, without touching the host.
+pub(crate) fn validate_answers(answers: &Answers, facts: &HostFacts) -> Vec<String> {
+    let mut errors = Vec::new();
+    if answers
+        .node_name
+        .as_deref()
+        .is_some_and(|name| name.trim().is_empty())
+    {
+        errors.push("node_name must not be empty.".to_string());
+    }
+    if !facts.config_loads {
+        if answers.network.is_none() {
+            errors.push("[network] is required when no configuration exists yet.".to_string());
+        }
+        if answers.bandwidth.is_none() {
+            errors.push("[bandwidth] is required when no configuration exists yet.".to_string());
+        }
+    }
+
+    if let Some(network) = &answers.network {
+        let mut check_interface = |role: &str, name: &str| {
+            if name.trim().is_empty() {
+                errors.push(format!("network.{role} is required for this mode."));
+            } else if !facts.interfaces.iter().any(|detected| detected == name) {
+                errors.push(format!(
+                    "network.{role} = \"{name}\" is not a detected multi-queue interface (detected: {}).",
+                    facts.interfaces.join(", ")
+                ));
+            }
+        };
+        match network.mode {
+            NetworkMode::Single => check_interface("interface", &network.interface),
+            NetworkMode::Linux | NetworkMode::Xdp => {
+                check_interface("to_internet", &network.to_internet);
+                check_interface("to_network", &network.to_network);
+                if network.to_internet == network.to_network {
+                    errors.push("Internet and network interfaces must be different.".to_string());
+                }
+            }
+        }
+        if network.mode == NetworkMode::Xdp && !facts.allow_legacy_xdp {
+            errors.push(
+                "network.mode = \"xdp\" is only available to installs that already use the XDP bridge."
+                    .to_string(),
+            );
+        }
+    }
+
+    if let Some(bandwidth) = &answers.bandwidth
+        && (bandwidth.downlink_mbps == 0 || bandwidth.uplink_mbps == 0)
+    {
+        errors.push("Bandwidth values must be greater than zero.".to_string());
+    }
+
+    let mut usernames = BTreeSet::new();
+    for user in &answers.users {
+        let username = user.username.trim();
+        if username.is_empty() {
+            errors.push("users: username must not be empty.".to_string());
+            continue;
+        }
+        if !usernames.insert(username) {
+            errors.push(format!("users: {username} is listed more than once."));
+        }
+        match (&user.password, &user.password_file) {
+            (Some(_), Some(_)) | (None, None) => errors.push(format!(
+                "users: {username} needs exactly one of password or password_file."
+            )),
+            (Some(password), None) if password.is_empty() => {
+                errors.push(format!("users: {username} has an empty password."));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(ranges) = &answers.ip_ranges {
+        for (field, subnets) in [
+            ("allow_subnets", &ranges.allow_subnets),
+            ("ignore_subnets", &ranges.ignore_subnets),
+        ] {
+            for subnet in subnets.iter().flatten() {
+                if subnet.parse::<IpNetwork>().is_err() {
+                    errors.push(format!(
+                        "ip_ranges.{field}: {subnet} is not a CIDR range such as 192.168.0.0/16."
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(ssl) = &answers.ssl
+        && let Err(err) =
+            lqos_config::normalize_external_hostname(ssl.external_hostname.as_deref().unwrap_or(""))
+    {
+        errors.push(format!("ssl.external_hostname: {err}"));
+    }
+
+    for name in answers.integrations.keys() {
+        if !INTEGRATIONS.contains(&name.as_str()) {
+            errors.push(format!(
+                "integrations.{name} is not a known integration (known: {}).",
+                INTEGRATIONS.join(", ")
+            ));
+        }
+    }
+    errors
+}
+
+fn read_passwords(users: &[UserAnswers]) -> Result<Vec<(String, String, UserRole)>> {
+    users
+        .iter()
+        .map(|user| {
+            let password = match (&user.password, &user.password_file) {
+                (Some(password), _) => password.clone(),
+                (None, Some(path)) => {
+                    let raw = std::fs::read_to_string(path).with_context(|| {
+                        format!("Unable to read password_file {}", path.display())
+                    })?;
+                    let password = raw.strip_suffix('\n').unwrap_or(&raw);
+                    let password = password.strip_suffix('\r').unwrap_or(password);
+                    if password.is_empty() {
+                        bail!("password_file {} is empty.", path.display());
+                    }
+                    password.to_string()
+                }
+                (None, None) => bail!("users: {} has no password.", user.username),
+            };
+            Ok((user.username.trim().to_string(), password, user.role.into()))
+        })
+        .collect()
+}
+
+fn hotfix_step(answers: &Answers, report: &mut UnattendedReport) -> Result<()> {
+    let status = hotfix::status()?;
+    if !status.required {
+        report.step("hotfix", StepStatus::Unchanged, status.detail);
+        return Ok(());
+    }
+    if !answers.install_hotfix {
+        bail!(
+            "{}\nSet install_hotfix = true to install it during unattended setup.",
+            status.detail
+        );
+    }
+    if report.check {
+        report.step("hotfix", StepStatus::WouldChange, status.detail);
+    } else {
+        let result = hotfix::install()?;
+        report.step("hotfix", StepStatus::Changed, result.summary);
+    }
+    Ok(())
+}
+
+fn users_step(users: &[(String, String, UserRole)], report: &mut UnattendedReport) -> Result<()> {
+    if users.is_empty() {
+        return Ok(());
+    }
+    let directory = bootstrap::runtime_lqos_directory();
+    let existing = WebUsers::load_existing_in(&directory)?;
+    let mut created = Vec::new();
+    let mut unchanged = Vec::new();
+    for (username, password, role) in users {
+        let matches = match &existing {
+            Some(existing) => existing.user_matches(username, password, *role)?,
+            None => false,
+        };
+        if matches {
+            unchanged.push(username.as_str());
+        } else {
+            created.push(username.as_str());
+        }
+    }
+    if !created.is_empty() && !report.check {
+        let mut web_users = WebUsers::load_or_create_in(&directory)?;
+        for (username, password, role) in users {
+            if created.contains(&username.as_str()) {
+                web_users.add_or_update_user(username, password, *role)?;
+            }
+        }
+    }
+
+    let status = if created.is_empty() {
+        StepStatus::Unchanged
+    } else {
+        report.pending()
+    };
+    let mut detail = Vec::new();
+    if !created.is_empty() {
+        detail.push(format!("Created or updated: {}.", created.join(", ")));
+    }
+    if !unchanged.is_empty() {
+        detail.push(format!("Already up to date: {}.", unchanged.join(", ")));
+    }
+    report.step("users", status, detail.join(" "));
+    Ok(())
+}
+
+/// Writes the candidate config, staging netplan changes through the helper.
+/// Returns true when the config or network changed.
+fn config_step(answers: &Answers, report: &mut UnattendedReport) -> Result<bool> {
+    let mut event_log = Vec::new();
+    let existing = setup_actions::load_existing_or_default(&mut event_log)?;
+    let config_exists = lqos_config::load_config().is_ok();
+    let candidate = candidate_config(answers, existing.clone())?;
+    let uses_helper = !candidate
+        .bridge
+        .as_ref()
+        .is_some_and(|bridge| bridge.use_xdp_bridge);
+
+    let network_changed = if uses_helper {
+        let inspection = inspect_with_paths(&HelperPaths::default(), &candidate);
+        let in_sync = inspection.inspector_state == "ManagedByLibreQoS"
+            && inspection
+                .managed_preview_yaml
+                .as_deref()
+                .is_some_and(|preview| {
+                    std::fs::read_to_string(&inspection.managed_file_path)
+                        .is_ok_and(|current| current == preview)
+                });
+        if !in_sync {
+            if !inspection.conflicts.is_empty() || !inspection.can_apply {
+                bail!(
+                    "The network change cannot be applied:\n{}",
+                    setup_actions::inspection_report(&inspection)
+                );
+            }
+            let disruptive_allowed = answers
+                .network
+                .as_ref()
+                .is_some_and(|network| network.allow_disruptive_changes);
+            if !inspection.dangerous_changes.is_empty() && !disruptive_allowed {
+                bail!(
+                    "The network change may interrupt access to this system. Set network.allow_disruptive_changes = true to apply it unattended:\n{}",
+                    setup_actions::inspection_report(&inspection)
+                );
+            }
+        }
+        report.step(
+            "network",
+            if in_sync {
+                StepStatus::Unchanged
+            } else {
+                report.pending()
+            },
+            inspection.summary,
+        );
+        !in_sync
+    } else {
+        report.step(
+            "network",
+            StepStatus::Skipped,
+            "The legacy XDP bridge is configured manually.",
+        );
+        false
+    };
+
+    let config_changed = !config_exists || candidate != existing;
+    if !config_changed && !network_changed {
+        report.step(
+            "config",
+            StepStatus::Unchanged,
+            "Configuration already matches the answers file.",
+        );
+        return Ok(false);
+    }
+    if report.check {
+        report.step(
+            "config",
+            StepStatus::WouldChange,
+            "Configuration would be updated.",
+        );
+        return Ok(true);
+    }
+
+    if !network_changed {
+        lqos_config::update_config(&candidate)?;
+        event_log.push("Configuration updated.".to_string());
+        report.step("config", StepStatus::Changed, event_log.join(" "));
+        return Ok(true);
+    }
+    // Nobody is watching to confirm, so keep the change as soon as it is
+    // applied. Disruptive changes were refused above unless allowed.
+    let success = match setup_actions::commit_candidate(candidate, event_log)? {
+        CommitOutcome::Complete(success) => *success,
+        CommitOutcome::Pending(pending) => {
+            setup_actions::confirm_pending_commit(&pending.operation_id)?
+        }
+    };
+    report.step("config", StepStatus::Changed, success.event_log.join(" "));
+    Ok(true)
+}
+
+/// Builds the config the answers describe on top of `existing`, using the
+/// same builder as the interactive flows.
+pub(crate) fn candidate_config(
+    answers: &Answers,
+    existing: lqos_config::Config,
+) -> Result<lqos_config::Config> {
+    {
+        let mut builder = CURRENT_CONFIG.lock();
+        if let Some(name) = &answers.node_name {
+            builder.node_name = name.trim().to_string();
+        }
+        if let Some(bandwidth) = &answers.bandwidth {
+            builder.mbps_to_internet = bandwidth.downlink_mbps;
+            builder.mbps_to_network = bandwidth.uplink_mbps;
+        }
+        if let Some(allow) = answers
+            .ip_ranges
+            .as_ref()
+            .and_then(|ranges| ranges.allow_subnets.as_ref())
+        {
+            builder.allow_subnets = allow.clone();
+        }
+        if let Some(network) = &answers.network {
+            match network.mode {
+                NetworkMode::Single => {
+                    builder.bridge_mode = BridgeMode::Single;
+                    builder.to_internet = network.interface.clone();
+                    builder.to_network.clear();
+                    builder.internet_vlan = network.internet_vlan;
+                    builder.network_vlan = network.network_vlan;
+                }
+                NetworkMode::Linux | NetworkMode::Xdp => {
+                    builder.bridge_mode = if network.mode == NetworkMode::Xdp {
+                        BridgeMode::XDP
+                    } else {
+                        BridgeMode::Linux
+                    };
+                    builder.to_internet = network.to_internet.clone();
+                    builder.to_network = network.to_network.clone();
+                    builder.internet_vlan = 0;
+                    builder.network_vlan = 0;
+                }
+            }
+        }
+    }
+    let mut config = setup_actions::build_candidate_config(Some(existing));
+    if let Some(ignore) = answers
+        .ip_ranges
+        .as_ref()
+        .and_then(|ranges| ranges.ignore_subnets.as_ref())
+    {
+        config.ip_ranges.ignore_subnets = ignore.clone();
+    }
+    apply_integrations(&mut config, &answers.integrations)?;
+    config.validate().map_err(anyhow::Error::msg)?;
+    Ok(config)
+}
+
+/// Merges answer keys into the `<name>_integration` sections of `config`.
+pub(crate) fn apply_integrations(
+    config: &mut lqos_config::Config,
+    integrations: &BTreeMap<String, toml::Table>,
+) -> Result<()> {
+    if integrations.is_empty() {
+        return Ok(());
+    }
+    let toml::Value::Table(mut document) =
+        toml::Value::try_from(&*config).context("Unable to serialize the configuration")?
+    else {
+        bail!("The configuration did not serialize to a TOML table.");
+    };
+    for (name, values) in integrations {
+        if !INTEGRATIONS.contains(&name.as_str()) {
+            bail!("integrations.{name} is not a known integration.");
+        }
+        let section = document
+            .entry(format!("{name}_integration"))
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        let Some(section) = section.as_table_mut() else {
+            bail!("{name}_integration is not a table in the configuration.");
+        };
+        for (key, value) in values {
+            section.insert(key.clone(), value.clone());
+        }
+    }
+    *config = toml::Value::Table(document)
+        .try_into()
+        .context("Integration settings from the answers file are not valid")?;
+    Ok(())
+}
+
+fn ssl_step(answers: &Answers, report: &mut UnattendedReport) -> Result<()> {
+    let Some(ssl) = &answers.ssl else {
+        return Ok(());
+    };
+    let config = if report.check {
+        lqos_config::load_config()
+            .map(|config| (*config).clone())
+            .unwrap_or_default()
+    } else {
+        reload_config()?
+    };
+    let current = config.ssl.clone().unwrap_or_default();
+    let hostname =
+        lqos_config::normalize_external_hostname(ssl.external_hostname.as_deref().unwrap_or(""))
+            .map_err(anyhow::Error::msg)?;
+
+    if !ssl.enabled {
+        if !current.enabled {
+            report.step("ssl", StepStatus::Unchanged, "HTTPS is disabled.");
+        } else if !current.managed_by_libreqos {
+            report.step(
+                "ssl",
+                StepStatus::Skipped,
+                "HTTPS is enabled but not managed by LibreQoS; leaving it alone.",
+            );
+        } else if report.check {
+            report.step("ssl", StepStatus::WouldChange, "HTTPS would be disabled.");
+        } else {
+            let outcome = lqos_setup::ssl::disable_runtime_ssl(None)?;
+            report.step("ssl", StepStatus::Changed, outcome.message);
+        }
+        return Ok(());
+    }
+
+    if current.enabled && current.managed_by_libreqos && current.external_hostname == hostname {
+        report.step("ssl", StepStatus::Unchanged, "HTTPS is already enabled.");
+    } else if report.check {
+        report.step(
+            "ssl",
+            StepStatus::WouldChange,
+            "HTTPS would be enabled with Caddy.",
+        );
+    } else {
+        let outcome = lqos_setup::ssl::enable_setup_ssl(&config, hostname, None)?;
+        report.step("ssl", StepStatus::Changed, outcome.message);
+    }
+    Ok(())
+}
+
+/// Reads `/etc/lqos.conf` from disk; the netplan helper writes it directly.
+fn reload_config() -> Result<lqos_config::Config> {
+    lqos_config::clear_cached_config();
+    Ok((*lqos_config::load_config()?).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML_ANSWERS: &str = r#"
+node_name = "POP 1"
+
+[network]
+mode = "linux"
+to_internet = "eth1"
+to_network = "eth2"
+
+[bandwidth]
+downlink_mbps = 10000
+uplink_mbps = 10000
+
+[[users]]
+username = "admin"
+password_file = "/run/secrets/lqos-admin"
+
+[ip_ranges]
+allow_subnets = ["100.64.0.0/10"]
+
+[integrations.splynx]
+enable_splynx = true
+api_key = "key"
+"#;
+
+    const YAML_ANSWERS: &str = r#"
+node_name: POP 1
+network:
+  mode: linux
+  to_internet: eth1
+  to_network: eth2
+bandwidth:
+  downlink_mbps: 10000
+  uplink_mbps: 10000
+users:
+  - username: admin
+    password_file: /run/secrets/lqos-admin
+ip_ranges:
+  allow_subnets: ["100.64.0.0/10"]
+integrations:
+  splynx:
+    enable_splynx: true
+    api_key: key
+"#;
+
+    fn facts() -> HostFacts {
+        HostFacts {
+            interfaces: vec!["eth1".to_string(), "eth2".to_string()],
+            allow_legacy_xdp: false,
+            config_loads: false,
+        }
+    }
+
+    #[test]
+    fn toml_and_yaml_answers_validate_against_detected_interfaces() {
+        let toml = parse_answers(Path::new("answers.toml"), TOML_ANSWERS).unwrap();
+        let yaml = parse_answers(Path::new("answers.yaml"), YAML_ANSWERS).unwrap();
+        assert_eq!(toml, yaml);
+        assert!(validate_answers(&toml, &facts()).is_empty());
+
+        let mut bad = toml.clone();
+        if let Some(network) = bad.network.as_mut() {
+            network.mode = NetworkMode::Xdp;
+            network.to_network = "eth9".to_string();
+        }
+        bad.bandwidth = None;
+        bad.users[0].password = Some("secret".to_string());
+        bad.ip_ranges = Some(IpRangeAnswers {
+            allow_subnets: None,
+            ignore_subnets: Some(vec!["not-a-range".to_string()]),
+        });
+        bad.integrations
+            .insert("crm".to_string(), toml::Table::new());
+        let errors = validate_answers(&bad, &facts());
+        assert_eq!(errors.len(), 6, "{errors:#?}");
+        assert!(errors.iter().any(|error| error.contains("eth9")));
+        assert!(errors.iter().any(|error| error.contains("[bandwidth]")));
+
+        assert!(parse_answers(Path::new("answers.toml"), "unknown = 1").is_err());
+    }
+
+    #[test]
+    fn integrations_merge_into_their_config_sections() {
+        let answers = parse_answers(Path::new("answers.toml"), TOML_ANSWERS).unwrap();
+        let mut config = lqos_config::Config::default();
+        apply_integrations(&mut config, &answers.integrations).unwrap();
+        assert!(config.splynx_integration.enable_splynx);
+        assert_eq!(config.splynx_integration.api_key, "key");
+        assert_eq!(config.splynx_integration.strategy, "ap_site");
+
+        let mut unknown = BTreeMap::new();
+        unknown.insert("crm".to_string(), toml::Table::new());
+        assert!(apply_integrations(&mut config, &unknown).is_err());
+    }
+}
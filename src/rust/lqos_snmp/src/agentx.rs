@@ -0,0 +1,346 @@
+//! A read-only AgentX subagent.
+//!
+//! The subagent connects to a master agent such as net-snmp's `snmpd`,
+//! registers one subtree and answers Get, GetNext and GetBulk requests from a
+//! [`MibView`]. The master agent handles SNMPv1/v2c communities, SNMPv3 users
+//! and access control, so none of that is duplicated here.
+
+mod pdu;
+#[cfg(test)]
+mod tests;
+
+use crate::oid::Oid;
+use crate::value::SnmpValue;
+use crate::view::MibView;
+use pdu::{Header, Incoming, SearchRange};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// The socket net-snmp's master agent listens on by default.
+pub const DEFAULT_AGENTX_ADDRESS: &str = "/var/agentx/master";
+
+/// Priority used when registering; lower wins, 127 is the AgentX default.
+const REGISTRATION_PRIORITY: u8 = 127;
+/// Seconds the master should wait for a response before giving up on us.
+const RESPONSE_TIMEOUT_SECONDS: u8 = 5;
+/// Repetitions honoured for a single GetBulk, regardless of what is asked.
+const MAX_BULK_REPETITIONS: u16 = 256;
+
+/// Where to reach the master agent, in net-snmp's `agentXSocket` notation:
+/// a Unix socket path (optionally `unix:` prefixed) or `tcp:host:port`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AgentxAddress {
+    /// A Unix stream socket.
+    Unix(PathBuf),
+    /// A TCP `host:port`.
+    Tcp(String),
+}
+
+impl FromStr for AgentxAddress {
+    type Err = AgentxError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if let Some(endpoint) = text.strip_prefix("tcp:") {
+            if endpoint.rsplit_once(':').is_none_or(|(host, port)| {
+                host.is_empty() || port.parse::<u16>().is_err()
+            }) {
+                return Err(AgentxError::InvalidAddress(text.to_string()));
+            }
+            return Ok(Self::Tcp(endpoint.to_string()));
+        }
+        let path = text.strip_prefix("unix:").unwrap_or(text);
+        if !path.starts_with('/') {
+            return Err(AgentxError::InvalidAddress(text.to_string()));
+        }
+        Ok(Self::Unix(PathBuf::from(path)))
+    }
+}
+
+/// Errors from an AgentX session.
+#[derive(Debug, Error)]
+pub enum AgentxError {
+    /// The configured master agent address could not be understood.
+    #[error("invalid AgentX address {0:?}; use a socket path or tcp:host:port")]
+    InvalidAddress(String),
+    /// The socket failed.
+    #[error("AgentX I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The master agent sent something this subagent cannot parse.
+    #[error("malformed AgentX PDU: {0}")]
+    Malformed(String),
+    /// The master agent rejected the session or the registration.
+    #[error("master agent refused the {stage}: {reason}")]
+    Refused {
+        /// What was being attempted.
+        stage: &'static str,
+        /// The AgentX error the master returned.
+        reason: String,
+    },
+    /// The master agent closed the connection.
+    #[error("master agent closed the session")]
+    Disconnected,
+}
+
+enum Stream {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl Stream {
+    fn connect(address: &AgentxAddress) -> io::Result<Self> {
+        match address {
+            AgentxAddress::Unix(path) => UnixStream::connect(path).map(Self::Unix),
+            AgentxAddress::Tcp(endpoint) => {
+                let stream = TcpStream::connect(endpoint)?;
+                stream.set_nodelay(true)?;
+                Ok(Self::Tcp(stream))
+            }
+        }
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Self::Unix(stream) => stream.set_read_timeout(timeout),
+            Self::Tcp(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Unix(stream) => stream.read(buf),
+            Self::Tcp(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Unix(stream) => stream.write(buf),
+            Self::Tcp(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Unix(stream) => stream.flush(),
+            Self::Tcp(stream) => stream.flush(),
+        }
+    }
+}
+
+/// An open, registered AgentX session.
+pub struct AgentxSession {
+    stream: Stream,
+    session_id: u32,
+    next_packet_id: u32,
+    idle: Duration,
+}
+
+impl AgentxSession {
+    /// Connects to the master agent, opens a session and registers `subtree`.
+    ///
+    /// `idle` is how long the session may be silent before the subagent pings
+    /// the master to check it is still there.
+    pub fn open(
+        address: &AgentxAddress,
+        subtree: &Oid,
+        description: &str,
+        idle: Duration,
+    ) -> Result<Self, AgentxError> {
+        let stream = Stream::connect(address)?;
+        stream.set_read_timeout(Some(Duration::from_secs(u64::from(
+            RESPONSE_TIMEOUT_SECONDS,
+        ))))?;
+        let mut session = Self {
+            stream,
+            session_id: 0,
+            next_packet_id: 1,
+            idle,
+        };
+
+        let packet_id = session.packet_id();
+        session.send(&pdu::open_pdu(
+            packet_id,
+            RESPONSE_TIMEOUT_SECONDS,
+            subtree,
+            description,
+        ))?;
+        let (header, error) = session.expect_response(packet_id)?;
+        if error != pdu::ERROR_NONE {
+            return Err(AgentxError::Refused {
+                stage: "session",
+                reason: pdu::error_name(error),
+            });
+        }
+        session.session_id = header.session_id;
+
+        let packet_id = session.packet_id();
+        session.send(&pdu::register_pdu(
+            session.session_id,
+            packet_id,
+            subtree,
+            REGISTRATION_PRIORITY,
+        ))?;
+        let (_, error) = session.expect_response(packet_id)?;
+        if error != pdu::ERROR_NONE {
+            return Err(AgentxError::Refused {
+                stage: "registration",
+                reason: pdu::error_name(error),
+            });
+        }
+        session.stream.set_read_timeout(Some(session.idle))?;
+        Ok(session)
+    }
+
+    /// Answers requests from the master agent until the session ends,
+    /// fetching the current view for each request.
+    ///
+    /// Returns only with an error: the master went away, closed the session,
+    /// or sent something unparseable. Callers usually reconnect.
+    pub fn serve(&mut self, view: impl Fn() -> Arc<MibView>) -> Result<(), AgentxError> {
+        let mut awaiting_ping = false;
+        loop {
+            let Some((header, payload)) = self.read_pdu()? else {
+                if awaiting_ping {
+                    return Err(AgentxError::Disconnected);
+                }
+                let packet_id = self.packet_id();
+                self.send(&pdu::ping_pdu(self.session_id, packet_id))?;
+                awaiting_ping = true;
+                continue;
+            };
+            awaiting_ping = false;
+            match pdu::decode(&header, &payload)? {
+                Incoming::Close(_) => return Err(AgentxError::Disconnected),
+                Incoming::Response { .. } | Incoming::CleanupSet | Incoming::Other(_) => {}
+                Incoming::TestSet => {
+                    self.send(&pdu::response_pdu(&header, pdu::ERROR_NOT_WRITABLE, 1, &[]))?;
+                }
+                Incoming::CommitSet | Incoming::UndoSet => {
+                    self.send(&pdu::response_pdu(&header, pdu::ERROR_PROCESSING, 0, &[]))?;
+                }
+                request => {
+                    let varbinds = answer(&view(), &request);
+                    self.send(&pdu::response_pdu(&header, pdu::ERROR_NONE, 0, &varbinds))?;
+                }
+            }
+        }
+    }
+
+    /// Tells the master agent the subagent is shutting down.
+    pub fn close(mut self) -> Result<(), AgentxError> {
+        let packet_id = self.packet_id();
+        self.send(&pdu::close_pdu(
+            self.session_id,
+            packet_id,
+            pdu::CLOSE_REASON_SHUTDOWN,
+        ))
+    }
+
+    fn packet_id(&mut self) -> u32 {
+        let id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        id
+    }
+
+    fn send(&mut self, bytes: &[u8]) -> Result<(), AgentxError> {
+        self.stream.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn expect_response(&mut self, packet_id: u32) -> Result<(Header, u16), AgentxError> {
+        loop {
+            let Some((header, payload)) = self.read_pdu()? else {
+                return Err(AgentxError::Io(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "no response from the master agent",
+                )));
+            };
+            if let Incoming::Response { error } = pdu::decode(&header, &payload)?
+                && header.packet_id == packet_id
+            {
+                return Ok((header, error));
+            }
+        }
+    }
+
+    /// Reads one PDU, or returns `None` if nothing arrived within the read
+    /// timeout.
+    fn read_pdu(&mut self) -> Result<Option<(Header, Vec<u8>)>, AgentxError> {
+        let mut header = [0u8; pdu::HEADER_LEN];
+        match self.stream.read(&mut header[..1]) {
+            Ok(0) => return Err(AgentxError::Disconnected),
+            Ok(_) => {}
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        }
+        self.stream.read_exact(&mut header[1..])?;
+        let header = Header::parse(&header)?;
+        let mut payload = vec![0; header.payload_len];
+        self.stream.read_exact(&mut payload)?;
+        Ok(Some((header, payload)))
+    }
+}
+
+/// Builds the variable bindings answering a Get, GetNext or GetBulk.
+pub(crate) fn answer(view: &MibView, request: &Incoming) -> Vec<(Oid, SnmpValue)> {
+    match request {
+        Incoming::Get(ranges) => ranges
+            .iter()
+            .map(|range| (range.start.clone(), view.get(&range.start)))
+            .collect(),
+        Incoming::GetNext(ranges) => ranges.iter().map(|range| next(view, range)).collect(),
+        Incoming::GetBulk {
+            non_repeaters,
+            max_repetitions,
+            ranges,
+        } => {
+            let split = usize::from(*non_repeaters).min(ranges.len());
+            let (singles, repeaters) = ranges.split_at(split);
+            let mut varbinds: Vec<_> = singles.iter().map(|range| next(view, range)).collect();
+            let mut cursors = repeaters.to_vec();
+            for _ in 0..(*max_repetitions).min(MAX_BULK_REPETITIONS) {
+                if cursors.is_empty() {
+                    break;
+                }
+                let mut all_ended = true;
+                for cursor in &mut cursors {
+                    let (oid, value) = next(view, cursor);
+                    if value != SnmpValue::EndOfMibView {
+                        all_ended = false;
+                        cursor.start = oid.clone();
+                        cursor.include = false;
+                    }
+                    varbinds.push((oid, value));
+                }
+                if all_ended {
+                    break;
+                }
+            }
+            varbinds
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn next(view: &MibView, range: &SearchRange) -> (Oid, SnmpValue) {
+    view.get_next(&range.start, range.include, &range.end)
+        .unwrap_or_else(|| (range.start.clone(), SnmpValue::EndOfMibView))
+}
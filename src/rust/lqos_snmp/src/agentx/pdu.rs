@@ -0,0 +1,419 @@
+//! AgentX (RFC 2741) PDU encoding and decoding for the subset a read-only
+//! subagent needs.
+
+use super::AgentxError;
+use crate::oid::Oid;
+use crate::value::SnmpValue;
+
+pub(crate) const HEADER_LEN: usize = 20;
+pub(crate) const MAX_PAYLOAD_LEN: usize = 1024 * 1024;
+const VERSION: u8 = 1;
+const MAX_SUBIDS: usize = 128;
+const INTERNET_PREFIX: [u32; 4] = [1, 3, 6, 1];
+
+const FLAG_NON_DEFAULT_CONTEXT: u8 = 0x08;
+const FLAG_NETWORK_BYTE_ORDER: u8 = 0x10;
+
+pub(crate) const TYPE_OPEN: u8 = 1;
+pub(crate) const TYPE_CLOSE: u8 = 2;
+pub(crate) const TYPE_REGISTER: u8 = 3;
+pub(crate) const TYPE_GET: u8 = 5;
+pub(crate) const TYPE_GET_NEXT: u8 = 6;
+pub(crate) const TYPE_GET_BULK: u8 = 7;
+pub(crate) const TYPE_TEST_SET: u8 = 8;
+pub(crate) const TYPE_COMMIT_SET: u8 = 9;
+pub(crate) const TYPE_UNDO_SET: u8 = 10;
+pub(crate) const TYPE_CLEANUP_SET: u8 = 11;
+pub(crate) const TYPE_PING: u8 = 13;
+pub(crate) const TYPE_RESPONSE: u8 = 18;
+
+/// `res.error` values used by this subagent.
+pub(crate) const ERROR_NONE: u16 = 0;
+pub(crate) const ERROR_NOT_WRITABLE: u16 = 17;
+pub(crate) const ERROR_PROCESSING: u16 = 268;
+
+/// `close.reason` sent when the subagent shuts down.
+pub(crate) const CLOSE_REASON_SHUTDOWN: u8 = 5;
+
+const VB_INTEGER: u16 = 2;
+const VB_OCTET_STRING: u16 = 4;
+const VB_COUNTER32: u16 = 65;
+const VB_GAUGE32: u16 = 66;
+const VB_TIME_TICKS: u16 = 67;
+const VB_COUNTER64: u16 = 70;
+const VB_NO_SUCH_OBJECT: u16 = 128;
+const VB_NO_SUCH_INSTANCE: u16 = 129;
+const VB_END_OF_MIB_VIEW: u16 = 130;
+
+/// The fixed 20-byte header that starts every PDU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Header {
+    pub pdu_type: u8,
+    pub flags: u8,
+    pub session_id: u32,
+    pub transaction_id: u32,
+    pub packet_id: u32,
+    pub payload_len: usize,
+}
+
+impl Header {
+    pub(crate) fn parse(bytes: &[u8; HEADER_LEN]) -> Result<Self, AgentxError> {
+        if bytes[0] != VERSION {
+            return Err(AgentxError::Malformed(format!(
+                "unsupported AgentX version {}",
+                bytes[0]
+            )));
+        }
+        let flags = bytes[2];
+        let mut decoder = Decoder::new(&bytes[4..], flags & FLAG_NETWORK_BYTE_ORDER != 0);
+        let session_id = decoder.u32()?;
+        let transaction_id = decoder.u32()?;
+        let packet_id = decoder.u32()?;
+        let payload_len = decoder.u32()? as usize;
+        if payload_len > MAX_PAYLOAD_LEN || !payload_len.is_multiple_of(4) {
+            return Err(AgentxError::Malformed(format!(
+                "invalid payload length {payload_len}"
+            )));
+        }
+        Ok(Self {
+            pdu_type: bytes[1],
+            flags,
+            session_id,
+            transaction_id,
+            packet_id,
+            payload_len,
+        })
+    }
+}
+
+/// One AgentX search range: where a Get or walk starts and where it must stop.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SearchRange {
+    pub start: Oid,
+    pub include: bool,
+    pub end: Oid,
+}
+
+/// A PDU received from the master agent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Incoming {
+    Get(Vec<SearchRange>),
+    GetNext(Vec<SearchRange>),
+    GetBulk {
+        non_repeaters: u16,
+        max_repetitions: u16,
+        ranges: Vec<SearchRange>,
+    },
+    TestSet,
+    CommitSet,
+    UndoSet,
+    CleanupSet,
+    Close(u8),
+    Response {
+        error: u16,
+    },
+    Other(u8),
+}
+
+/// Decodes the payload that follows `header`.
+pub(crate) fn decode(header: &Header, payload: &[u8]) -> Result<Incoming, AgentxError> {
+    let mut decoder = Decoder::new(payload, header.flags & FLAG_NETWORK_BYTE_ORDER != 0);
+    let has_context = header.flags & FLAG_NON_DEFAULT_CONTEXT != 0;
+    let incoming = match header.pdu_type {
+        TYPE_GET | TYPE_GET_NEXT => {
+            if has_context {
+                decoder.octets()?;
+            }
+            let ranges = decoder.search_ranges()?;
+            if header.pdu_type == TYPE_GET {
+                Incoming::Get(ranges)
+            } else {
+                Incoming::GetNext(ranges)
+            }
+        }
+        TYPE_GET_BULK => {
+            if has_context {
+                decoder.octets()?;
+            }
+            let non_repeaters = decoder.u16()?;
+            let max_repetitions = decoder.u16()?;
+            Incoming::GetBulk {
+                non_repeaters,
+                max_repetitions,
+                ranges: decoder.search_ranges()?,
+            }
+        }
+        TYPE_TEST_SET => Incoming::TestSet,
+        TYPE_COMMIT_SET => Incoming::CommitSet,
+        TYPE_UNDO_SET => Incoming::UndoSet,
+        TYPE_CLEANUP_SET => Incoming::CleanupSet,
+        TYPE_CLOSE => Incoming::Close(decoder.u8()?),
+        TYPE_RESPONSE => {
+            decoder.u32()?;
+            Incoming::Response {
+                error: decoder.u16()?,
+            }
+        }
+        other => Incoming::Other(other),
+    };
+    Ok(incoming)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8], big_endian: bool) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            big_endian,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], AgentxError> {
+        let slice = self
+            .bytes
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| AgentxError::Malformed("truncated PDU".to_string()))?;
+        self.pos += N;
+        let mut out = [0; N];
+        out.copy_from_slice(slice);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, AgentxError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, AgentxError> {
+        let bytes = self.take()?;
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&mut self) -> Result<u32, AgentxError> {
+        let bytes = self.take()?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn octets(&mut self) -> Result<Vec<u8>, AgentxError> {
+        let len = self.u32()? as usize;
+        let padded = len.div_ceil(4) * 4;
+        let slice = self
+            .bytes
+            .get(self.pos..self.pos + padded)
+            .ok_or_else(|| AgentxError::Malformed("truncated octet string".to_string()))?;
+        self.pos += padded;
+        Ok(slice[..len].to_vec())
+    }
+
+    fn oid(&mut self) -> Result<(Oid, bool), AgentxError> {
+        let [n_subid, prefix, include, _reserved] = self.take::<4>()?;
+        let n_subid = usize::from(n_subid);
+        if n_subid > MAX_SUBIDS {
+            return Err(AgentxError::Malformed(format!(
+                "object identifier with {n_subid} sub-identifiers"
+            )));
+        }
+        let mut subids = Vec::with_capacity(n_subid + 5);
+        if prefix != 0 {
+            subids.extend_from_slice(&INTERNET_PREFIX);
+            subids.push(u32::from(prefix));
+        }
+        for _ in 0..n_subid {
+            subids.push(self.u32()?);
+        }
+        Ok((Oid::new(subids), include != 0))
+    }
+
+    fn search_ranges(&mut self) -> Result<Vec<SearchRange>, AgentxError> {
+        let mut ranges = Vec::new();
+        while !self.is_empty() {
+            let (start, include) = self.oid()?;
+            let (end, _) = self.oid()?;
+            ranges.push(SearchRange {
+                start,
+                include,
+                end,
+            });
+        }
+        Ok(ranges)
+    }
+}
+
+/// Builds big-endian PDUs with `NETWORK_BYTE_ORDER` set.
+pub(crate) struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Self { bytes: Vec::new() }
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn octets(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value);
+        let padding = value.len().div_ceil(4) * 4 - value.len();
+        self.bytes.extend(std::iter::repeat_n(0, padding));
+    }
+
+    fn oid(&mut self, oid: &Oid, include: bool) {
+        let subids = oid.subids();
+        let (prefix, rest) = match subids {
+            [1, 3, 6, 1, prefix @ 1..=255, rest @ ..] => (*prefix as u8, rest),
+            _ => (0, subids),
+        };
+        let rest = &rest[..rest.len().min(MAX_SUBIDS)];
+        self.u8(rest.len() as u8);
+        self.u8(prefix);
+        self.u8(u8::from(include));
+        self.u8(0);
+        for subid in rest {
+            self.u32(*subid);
+        }
+    }
+
+    fn varbind(&mut self, oid: &Oid, value: &SnmpValue) {
+        let kind = match value {
+            SnmpValue::Integer(_) => VB_INTEGER,
+            SnmpValue::OctetString(_) => VB_OCTET_STRING,
+            SnmpValue::Counter32(_) => VB_COUNTER32,
+            SnmpValue::Gauge32(_) => VB_GAUGE32,
+            SnmpValue::TimeTicks(_) => VB_TIME_TICKS,
+            SnmpValue::Counter64(_) => VB_COUNTER64,
+            SnmpValue::NoSuchObject => VB_NO_SUCH_OBJECT,
+            SnmpValue::NoSuchInstance => VB_NO_SUCH_INSTANCE,
+            SnmpValue::EndOfMibView => VB_END_OF_MIB_VIEW,
+        };
+        self.u16(kind);
+        self.u16(0);
+        self.oid(oid, false);
+        match value {
+            SnmpValue::Integer(value) => self.u32(*value as u32),
+            SnmpValue::OctetString(bytes) => self.octets(bytes),
+            SnmpValue::Counter32(value) | SnmpValue::Gauge32(value) | SnmpValue::TimeTicks(value) => {
+                self.u32(*value)
+            }
+            SnmpValue::Counter64(value) => self.u64(*value),
+            SnmpValue::NoSuchObject | SnmpValue::NoSuchInstance | SnmpValue::EndOfMibView => {}
+        }
+    }
+
+    fn finish(self, pdu_type: u8, session_id: u32, transaction_id: u32, packet_id: u32) -> Vec<u8> {
+        let mut pdu = Vec::with_capacity(HEADER_LEN + self.bytes.len());
+        pdu.extend_from_slice(&[VERSION, pdu_type, FLAG_NETWORK_BYTE_ORDER, 0]);
+        pdu.extend_from_slice(&session_id.to_be_bytes());
+        pdu.extend_from_slice(&transaction_id.to_be_bytes());
+        pdu.extend_from_slice(&packet_id.to_be_bytes());
+        pdu.extend_from_slice(&(self.bytes.len() as u32).to_be_bytes());
+        pdu.extend_from_slice(&self.bytes);
+        pdu
+    }
+}
+
+/// An agentx-Open-PDU identifying the subagent by `id` and `description`.
+pub(crate) fn open_pdu(packet_id: u32, timeout_seconds: u8, id: &Oid, description: &str) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    encoder.u8(timeout_seconds);
+    encoder.u8(0);
+    encoder.u8(0);
+    encoder.u8(0);
+    encoder.oid(id, false);
+    encoder.octets(description.as_bytes());
+    encoder.finish(TYPE_OPEN, 0, 0, packet_id)
+}
+
+/// An agentx-Register-PDU claiming `subtree` in the default context.
+pub(crate) fn register_pdu(session_id: u32, packet_id: u32, subtree: &Oid, priority: u8) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    encoder.u8(0);
+    encoder.u8(priority);
+    encoder.u8(0);
+    encoder.u8(0);
+    encoder.oid(subtree, false);
+    encoder.finish(TYPE_REGISTER, session_id, 0, packet_id)
+}
+
+/// An agentx-Ping-PDU, used to notice a master agent that went away.
+pub(crate) fn ping_pdu(session_id: u32, packet_id: u32) -> Vec<u8> {
+    Encoder::new().finish(TYPE_PING, session_id, 0, packet_id)
+}
+
+/// An agentx-Close-PDU.
+pub(crate) fn close_pdu(session_id: u32, packet_id: u32, reason: u8) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    encoder.u8(reason);
+    encoder.u8(0);
+    encoder.u8(0);
+    encoder.u8(0);
+    encoder.finish(TYPE_CLOSE, session_id, 0, packet_id)
+}
+
+/// An agentx-Response-PDU answering `request`.
+pub(crate) fn response_pdu(
+    request: &Header,
+    error: u16,
+    index: u16,
+    varbinds: &[(Oid, SnmpValue)],
+) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    encoder.u32(0);
+    encoder.u16(error);
+    encoder.u16(index);
+    for (oid, value) in varbinds {
+        encoder.varbind(oid, value);
+    }
+    encoder.finish(
+        TYPE_RESPONSE,
+        request.session_id,
+        request.transaction_id,
+        request.packet_id,
+    )
+}
+
+/// Human-readable name of an AgentX `res.error` value.
+pub(crate) fn error_name(code: u16) -> String {
+    match code {
+        256 => "openFailed".to_string(),
+        257 => "notOpen".to_string(),
+        262 => "unsupportedContext".to_string(),
+        263 => "duplicateRegistration".to_string(),
+        264 => "unknownRegistration".to_string(),
+        266 => "parseError".to_string(),
+        267 => "requestDenied".to_string(),
+        268 => "processingError".to_string(),
+        other => format!("error {other}"),
+    }
+}
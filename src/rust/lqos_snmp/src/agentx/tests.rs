@@ -0,0 +1,190 @@
+use super::pdu::{self, Header, Incoming, SearchRange};
+use super::*;
+use std::os::unix::net::UnixListener;
+use std::thread;
+
+fn oid(text: &str) -> Oid {
+    text.parse().unwrap()
+}
+
+fn sample_view() -> MibView {
+    let mut view = MibView::new();
+    view.scalar(&oid("1.3.6.1.4.1.99.1.1"), SnmpValue::string("1.5"));
+    view.cell(&oid("1.3.6.1.4.1.99.2.1.3"), &[0, 1], SnmpValue::Counter64(10));
+    view.cell(&oid("1.3.6.1.4.1.99.2.1.3"), &[0, 2], SnmpValue::Counter64(20));
+    view.cell(&oid("1.3.6.1.4.1.99.2.1.4"), &[0, 1], SnmpValue::Gauge32(1));
+    view
+}
+
+/// A little-endian agentx-GetNext-PDU with one compressed search range, the
+/// way net-snmp's master encodes it on x86.
+fn little_endian_get_next(start: &[u32], include: bool) -> Vec<u8> {
+    let mut payload = vec![start.len() as u8, 4, u8::from(include), 0];
+    for subid in start {
+        payload.extend_from_slice(&subid.to_le_bytes());
+    }
+    payload.extend_from_slice(&[0, 0, 0, 0]);
+    let mut bytes = vec![1, pdu::TYPE_GET_NEXT, 0, 0];
+    for field in [7u32, 11, 13, payload.len() as u32] {
+        bytes.extend_from_slice(&field.to_le_bytes());
+    }
+    bytes.extend_from_slice(&payload);
+    bytes
+}
+
+fn split(bytes: &[u8]) -> (Header, &[u8]) {
+    let header = Header::parse(bytes[..pdu::HEADER_LEN].try_into().unwrap()).unwrap();
+    (header, &bytes[pdu::HEADER_LEN..])
+}
+
+#[test]
+fn decodes_little_endian_requests_and_answers_in_network_order() {
+    let bytes = little_endian_get_next(&[1, 99, 1, 1], false);
+    let (header, payload) = split(&bytes);
+    assert_eq!((header.session_id, header.transaction_id, header.packet_id), (7, 11, 13));
+    let request = pdu::decode(&header, payload).unwrap();
+    assert_eq!(
+        request,
+        Incoming::GetNext(vec![SearchRange {
+            start: oid("1.3.6.1.4.1.99.1.1"),
+            include: false,
+            end: Oid::default(),
+        }])
+    );
+
+    let varbinds = answer(&sample_view(), &request);
+    assert_eq!(
+        varbinds,
+        vec![(oid("1.3.6.1.4.1.99.1.1.0"), SnmpValue::string("1.5"))]
+    );
+    let response = pdu::response_pdu(&header, pdu::ERROR_NONE, 0, &varbinds);
+    let (reply, body) = split(&response);
+    assert_eq!(reply.pdu_type, pdu::TYPE_RESPONSE);
+    assert_eq!((reply.session_id, reply.transaction_id, reply.packet_id), (7, 11, 13));
+    assert_eq!(
+        pdu::decode(&reply, body).unwrap(),
+        Incoming::Response { error: 0 }
+    );
+    // Varbind: OCTET STRING, compressed 1.3.6.1.4 prefix, "1.5" padded to 4.
+    assert_eq!(&body[8..12], &[0, 4, 0, 0]);
+    assert_eq!(&body[12..16], &[5, 4, 0, 0]);
+    assert_eq!(&body[body.len() - 8..], &[0, 0, 0, 3, b'1', b'.', b'5', 0]);
+}
+
+#[test]
+fn get_bulk_walks_columns_until_the_view_ends() {
+    let request = Incoming::GetBulk {
+        non_repeaters: 1,
+        max_repetitions: 5,
+        ranges: vec![
+            SearchRange {
+                start: oid("1.3.6.1.4.1.99.1"),
+                include: false,
+                end: Oid::default(),
+            },
+            SearchRange {
+                start: oid("1.3.6.1.4.1.99.2.1.3"),
+                include: false,
+                end: Oid::default(),
+            },
+        ],
+    };
+    let names: Vec<_> = answer(&sample_view(), &request)
+        .into_iter()
+        .map(|(oid, value)| (oid.to_string(), value))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            ("1.3.6.1.4.1.99.1.1.0".to_string(), SnmpValue::string("1.5")),
+            ("1.3.6.1.4.1.99.2.1.3.0.1".to_string(), SnmpValue::Counter64(10)),
+            ("1.3.6.1.4.1.99.2.1.3.0.2".to_string(), SnmpValue::Counter64(20)),
+            ("1.3.6.1.4.1.99.2.1.4.0.1".to_string(), SnmpValue::Gauge32(1)),
+            ("1.3.6.1.4.1.99.2.1.4.0.1".to_string(), SnmpValue::EndOfMibView),
+        ]
+    );
+}
+
+#[test]
+fn addresses_follow_net_snmp_notation() {
+    assert_eq!(
+        "/var/agentx/master".parse::<AgentxAddress>().unwrap(),
+        AgentxAddress::Unix("/var/agentx/master".into())
+    );
+    assert_eq!(
+        "unix:/run/agentx".parse::<AgentxAddress>().unwrap(),
+        AgentxAddress::Unix("/run/agentx".into())
+    );
+    assert_eq!(
+        "tcp:localhost:705".parse::<AgentxAddress>().unwrap(),
+        AgentxAddress::Tcp("localhost:705".to_string())
+    );
+    assert!("tcp:localhost".parse::<AgentxAddress>().is_err());
+    assert!("agentx".parse::<AgentxAddress>().is_err());
+}
+
+fn read_pdu(stream: &mut UnixStream) -> (Header, Vec<u8>) {
+    let mut header = [0u8; pdu::HEADER_LEN];
+    stream.read_exact(&mut header).unwrap();
+    let header = Header::parse(&header).unwrap();
+    let mut payload = vec![0; header.payload_len];
+    stream.read_exact(&mut payload).unwrap();
+    (header, payload)
+}
+
+#[test]
+fn session_opens_registers_and_serves_a_master() {
+    let dir = std::env::temp_dir().join(format!("lqos_snmp_agentx_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("master");
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+
+    let master = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let (open, _) = read_pdu(&mut stream);
+        assert_eq!(open.pdu_type, pdu::TYPE_OPEN);
+        let mut accept = open;
+        accept.session_id = 42;
+        stream
+            .write_all(&pdu::response_pdu(&accept, pdu::ERROR_NONE, 0, &[]))
+            .unwrap();
+
+        let (register, payload) = read_pdu(&mut stream);
+        assert_eq!(register.pdu_type, pdu::TYPE_REGISTER);
+        assert_eq!(register.session_id, 42);
+        assert_eq!(&payload[4..8], &[2, 4, 0, 0]);
+        stream
+            .write_all(&pdu::response_pdu(&register, pdu::ERROR_NONE, 0, &[]))
+            .unwrap();
+
+        stream
+            .write_all(&little_endian_get_next(&[1, 99, 2, 1, 3, 0, 1], false))
+            .unwrap();
+        let (reply, body) = read_pdu(&mut stream);
+        assert_eq!((reply.pdu_type, reply.packet_id), (pdu::TYPE_RESPONSE, 13));
+        // Counter64 20 at the end of the only varbind.
+        assert_eq!(&body[body.len() - 8..], &20u64.to_be_bytes());
+
+        let mut close = vec![1, pdu::TYPE_CLOSE, 0x10, 0];
+        for field in [42u32, 0, 99, 4] {
+            close.extend_from_slice(&field.to_be_bytes());
+        }
+        close.extend_from_slice(&[5, 0, 0, 0]);
+        stream.write_all(&close).unwrap();
+    });
+
+    let address = AgentxAddress::Unix(path.clone());
+    let mut session = AgentxSession::open(
+        &address,
+        &oid("1.3.6.1.4.1.99"),
+        "test",
+        Duration::from_secs(5),
+    )
+    .unwrap();
+    let view = Arc::new(sample_view());
+    let result = session.serve(|| view.clone());
+    assert!(matches!(result, Err(AgentxError::Disconnected)));
+    master.join().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+}
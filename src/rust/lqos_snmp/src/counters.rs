@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Turns raw counters that may reset, or disappear and come back, into the
+/// growth needed to advance a monotonic `Counter64`.
+///
+/// LibreQoS' per-host byte counters and CAKE's drop/mark counters restart
+/// whenever a host ages out of the kernel map or a queue is rebuilt. SNMP
+/// managers treat any decrease as a wrap, so the agent keeps its own totals
+/// and feeds them with the deltas this tracker returns.
+#[derive(Debug)]
+pub struct DeltaTracker<K> {
+    last: HashMap<K, (u64, u64)>,
+    cycle: u64,
+    forget_after: u64,
+}
+
+impl<K: Hash + Eq> DeltaTracker<K> {
+    /// Starts tracking with no history. Keys unseen for `forget_after` cycles
+    /// are dropped.
+    pub fn new(forget_after: u64) -> Self {
+        Self {
+            last: HashMap::new(),
+            cycle: 0,
+            forget_after: forget_after.max(1),
+        }
+    }
+
+    /// Records `raw` for `key` and returns how much it grew since the previous
+    /// observation.
+    ///
+    /// A new key contributes its whole value; so does a value that went
+    /// backwards, which is treated as a counter reset.
+    pub fn delta(&mut self, key: K, raw: u64) -> u64 {
+        match self.last.insert(key, (raw, self.cycle)) {
+            Some((previous, _)) if raw >= previous => raw - previous,
+            _ => raw,
+        }
+    }
+
+    /// Closes one refresh cycle, forgetting keys that have been absent for
+    /// too long.
+    pub fn end_cycle(&mut self) {
+        let cycle = self.cycle;
+        let forget_after = self.forget_after;
+        self.last
+            .retain(|_, (_, seen)| cycle.saturating_sub(*seen) < forget_after);
+        self.cycle = self.cycle.wrapping_add(1);
+    }
+
+    /// Number of keys currently tracked.
+    pub fn len(&self) -> usize {
+        self.last.len()
+    }
+
+    /// Returns true when no keys are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.last.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resets_and_returning_keys_never_run_backwards() {
+        let mut tracker = DeltaTracker::new(2);
+        assert_eq!(tracker.delta("a", 100), 100);
+        tracker.end_cycle();
+        assert_eq!(tracker.delta("a", 150), 50);
+        tracker.end_cycle();
+        // Missed one cycle but kept counting: only the growth is new.
+        tracker.end_cycle();
+        assert_eq!(tracker.delta("a", 170), 20);
+        tracker.end_cycle();
+        // Host aged out and came back with a fresh counter.
+        assert_eq!(tracker.delta("a", 20), 20);
+        tracker.end_cycle();
+        tracker.end_cycle();
+        tracker.end_cycle();
+        assert!(tracker.is_empty());
+    }
+}
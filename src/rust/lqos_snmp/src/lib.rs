@@ -0,0 +1,25 @@
+//! SNMP support for LibreQoS, as an AgentX subagent.
+//!
+//! The crate lays LibreQoS counters out as the LIBREQOS-MIB, keeps SNMP
+//! counters monotonic across host and queue resets, and serves the result to
+//! a master agent (such as net-snmp's `snmpd`) over AgentX. The master agent
+//! owns the SNMP ports, communities and SNMPv3 users.
+
+#![warn(missing_docs)]
+
+mod agentx;
+mod counters;
+mod mib;
+mod oid;
+mod value;
+mod view;
+
+pub use agentx::{AgentxAddress, AgentxError, AgentxSession, DEFAULT_AGENTX_ADDRESS};
+pub use counters::DeltaTracker;
+pub use mib::{
+    DEFAULT_BASE_OID, LibreQosSnapshot, SnmpBakeryMode, SnmpCircuitRow, SnmpHealth, SnmpSiteRow,
+    SnmpTraffic, hash_index,
+};
+pub use oid::{Oid, OidParseError};
+pub use value::SnmpValue;
+pub use view::MibView;
@@ -0,0 +1,89 @@
+//! Diagnostic AgentX subagent serving a fixed, slowly counting LIBREQOS-MIB
+//! view, for checking a master agent and NMS templates without lqosd.
+
+use clap::Parser;
+use lqos_snmp::{
+    AgentxAddress, AgentxError, AgentxSession, DEFAULT_AGENTX_ADDRESS, DEFAULT_BASE_OID,
+    LibreQosSnapshot, MibView, Oid, SnmpCircuitRow, SnmpSiteRow, SnmpTraffic,
+};
+use lqos_utils::units::DownUpOrder;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(
+    name = "lqos_snmp",
+    about = "Serve a sample LIBREQOS-MIB view to an AgentX master agent."
+)]
+struct Args {
+    /// Master agent socket path, or tcp:host:port.
+    #[arg(long, value_name = "ADDRESS", default_value = DEFAULT_AGENTX_ADDRESS)]
+    agentx: AgentxAddress,
+    /// OID the LIBREQOS-MIB tree is registered under.
+    #[arg(long, value_name = "OID", default_value = DEFAULT_BASE_OID)]
+    base_oid: Oid,
+}
+
+fn main() -> Result<(), AgentxError> {
+    let args = Args::parse();
+    let started = Instant::now();
+    let mut session = AgentxSession::open(
+        &args.agentx,
+        &args.base_oid,
+        "LibreQoS diagnostic subagent",
+        Duration::from_secs(30),
+    )?;
+    println!("Registered {} with the master agent", args.base_oid);
+    let base = args.base_oid;
+    session.serve(|| Arc::new(sample_view(&base, started.elapsed().as_secs())))
+}
+
+fn sample_traffic(seconds: u64, mbps: u64) -> SnmpTraffic {
+    let bytes_per_second = mbps * 125_000;
+    SnmpTraffic {
+        octets: DownUpOrder::new(bytes_per_second * seconds, bytes_per_second * seconds / 10),
+        packets: DownUpOrder::new(bytes_per_second * seconds / 1200, bytes_per_second * seconds / 9000),
+        drops: DownUpOrder::new(seconds, 0),
+        marks: DownUpOrder::new(seconds * 2, 0),
+        bits_per_second: DownUpOrder::new(mbps * 1_000_000, mbps * 100_000),
+        rtt_p50_micros: DownUpOrder::new(Some(18_000), Some(21_000)),
+        rtt_p90_micros: DownUpOrder::new(Some(42_000), None),
+    }
+}
+
+fn sample_view(base: &Oid, seconds: u64) -> MibView {
+    LibreQosSnapshot {
+        version: "diagnostic".to_string(),
+        node_name: "lqos_snmp".to_string(),
+        node_id: "0".to_string(),
+        global: sample_traffic(seconds, 150),
+        active_circuits: 2,
+        health: Default::default(),
+        sites: vec![SnmpSiteRow {
+            hash: 1,
+            name: "Site A".to_string(),
+            parent: String::new(),
+            max_mbps: DownUpOrder::new(1000, 1000),
+            traffic: sample_traffic(seconds, 150),
+        }],
+        circuits: vec![
+            SnmpCircuitRow {
+                hash: 2,
+                circuit_id: "100".to_string(),
+                name: "Sample circuit 100".to_string(),
+                parent: "Site A".to_string(),
+                plan_mbps: DownUpOrder::new(100, 20),
+                traffic: sample_traffic(seconds, 100),
+            },
+            SnmpCircuitRow {
+                hash: -3,
+                circuit_id: "101".to_string(),
+                name: "Sample circuit 101".to_string(),
+                parent: "Site A".to_string(),
+                plan_mbps: DownUpOrder::new(50, 10),
+                traffic: sample_traffic(seconds, 50),
+            },
+        ],
+    }
+    .to_view(base)
+}
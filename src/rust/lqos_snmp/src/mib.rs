@@ -0,0 +1,290 @@
+//! The LIBREQOS-MIB object layout.
+//!
+//! `LIBREQOS-MIB.txt` beside this crate describes the same tree for network
+//! management systems; keep the two in step. Every identifier below is
+//! relative to the configured base OID.
+
+use crate::oid::Oid;
+use crate::value::SnmpValue;
+use crate::view::MibView;
+use lqos_utils::units::DownUpOrder;
+
+/// The base the shipped MIB registers under: net-snmp's `netSnmpPlaypen`
+/// arc, which is reserved for local use. Sites with their own enterprise
+/// number can move the tree by editing the MIB and `base_oid` together.
+pub const DEFAULT_BASE_OID: &str = "1.3.6.1.4.1.8072.9999.9999.7341";
+
+const OBJECTS: u32 = 1;
+const INFO: u32 = 1;
+const GLOBAL: u32 = 2;
+const HEALTH: u32 = 3;
+const SITE_TABLE: u32 = 4;
+const CIRCUIT_TABLE: u32 = 5;
+const TABLE_ENTRY: u32 = 1;
+
+/// Table columns before this are row descriptions; traffic columns follow it
+/// in the same order as the global scalars.
+const TRAFFIC_COLUMN_OFFSET: u32 = 10;
+const GLOBAL_ACTIVE_CIRCUITS: u32 = 15;
+
+/// Traffic counters and gauges shared by the global scalars and both tables.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnmpTraffic {
+    /// Octets since lqosd started.
+    pub octets: DownUpOrder<u64>,
+    /// Packets since lqosd started.
+    pub packets: DownUpOrder<u64>,
+    /// CAKE drops since lqosd started.
+    pub drops: DownUpOrder<u64>,
+    /// CAKE ECN marks since lqosd started.
+    pub marks: DownUpOrder<u64>,
+    /// Current rate.
+    pub bits_per_second: DownUpOrder<u64>,
+    /// Current median round-trip time, when there are enough samples.
+    pub rtt_p50_micros: DownUpOrder<Option<u64>>,
+    /// Current 90th-percentile round-trip time.
+    pub rtt_p90_micros: DownUpOrder<Option<u64>>,
+}
+
+/// One `lqosSiteTable` row: a `network.json` node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnmpSiteRow {
+    /// Stable row key, a hash of the site name.
+    pub hash: i64,
+    /// Site name.
+    pub name: String,
+    /// Name of the parent site, empty at the top of the tree.
+    pub parent: String,
+    /// Configured capacity in Mbps.
+    pub max_mbps: DownUpOrder<u64>,
+    /// Traffic through the site.
+    pub traffic: SnmpTraffic,
+}
+
+/// One `lqosCircuitTable` row.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnmpCircuitRow {
+    /// Circuit hash, the same key LibreQoS uses internally.
+    pub hash: i64,
+    /// Circuit ID from `ShapedDevices.csv`.
+    pub circuit_id: String,
+    /// Circuit name.
+    pub name: String,
+    /// Parent node.
+    pub parent: String,
+    /// Plan maximum in Mbps.
+    pub plan_mbps: DownUpOrder<u64>,
+    /// Traffic through the circuit.
+    pub traffic: SnmpTraffic,
+}
+
+/// What the Bakery is doing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SnmpBakeryMode {
+    /// Nothing in flight.
+    #[default]
+    Idle,
+    /// Rebuilding every queue.
+    ApplyingFullReload,
+    /// Applying an incremental change.
+    ApplyingLiveChange,
+}
+
+/// Bakery, StormGuard and urgent-issue health.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnmpHealth {
+    /// Bakery activity.
+    pub bakery_mode: SnmpBakeryMode,
+    /// A full reload is needed to apply pending changes.
+    pub bakery_reload_required: bool,
+    /// Queues could not be built and traffic is passing unshaped.
+    pub bakery_passthrough_degraded: bool,
+    /// When the Bakery last applied a change successfully.
+    pub bakery_last_success_unix: Option<u64>,
+    /// Summary of the most recent Bakery failure.
+    pub bakery_last_failure: Option<String>,
+    /// StormGuard is enabled in configuration.
+    pub stormguard_enabled: bool,
+    /// StormGuard mode.
+    pub stormguard_mode: String,
+    /// StormGuard phase.
+    pub stormguard_phase: String,
+    /// Most recent StormGuard error.
+    pub stormguard_last_error: Option<String>,
+    /// Open urgent issues.
+    pub urgent_issues: u64,
+    /// Open urgent issues with error severity.
+    pub urgent_errors: u64,
+}
+
+/// Everything the subagent serves, captured at one instant.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LibreQosSnapshot {
+    /// LibreQoS version.
+    pub version: String,
+    /// Configured node name.
+    pub node_name: String,
+    /// Node ID.
+    pub node_id: String,
+    /// Traffic through the whole shaper.
+    pub global: SnmpTraffic,
+    /// Circuits with traffic right now.
+    pub active_circuits: u64,
+    /// Component health.
+    pub health: SnmpHealth,
+    /// Sites.
+    pub sites: Vec<SnmpSiteRow>,
+    /// Circuits.
+    pub circuits: Vec<SnmpCircuitRow>,
+}
+
+/// Splits a 64-bit row hash into the two `Unsigned32` index sub-identifiers.
+pub fn hash_index(hash: i64) -> [u32; 2] {
+    let bits = hash as u64;
+    [(bits >> 32) as u32, bits as u32]
+}
+
+impl LibreQosSnapshot {
+    /// Lays the snapshot out under `base`.
+    pub fn to_view(&self, base: &Oid) -> MibView {
+        let mut view = MibView::new();
+        let objects = base.child(&[OBJECTS]);
+
+        let info = objects.child(&[INFO]);
+        view.scalar(&info.child(&[1]), SnmpValue::string(&self.version));
+        view.scalar(&info.child(&[2]), SnmpValue::string(&self.node_name));
+        view.scalar(&info.child(&[3]), SnmpValue::string(&self.node_id));
+
+        let global = objects.child(&[GLOBAL]);
+        for (column, value) in traffic_values(&self.global) {
+            view.scalar(&global.child(&[column]), value);
+        }
+        view.scalar(
+            &global.child(&[GLOBAL_ACTIVE_CIRCUITS]),
+            SnmpValue::gauge(self.active_circuits),
+        );
+
+        let health = objects.child(&[HEALTH]);
+        let h = &self.health;
+        let mode = match h.bakery_mode {
+            SnmpBakeryMode::Idle => 1,
+            SnmpBakeryMode::ApplyingFullReload => 2,
+            SnmpBakeryMode::ApplyingLiveChange => 3,
+        };
+        let health_values = [
+            SnmpValue::Integer(mode),
+            SnmpValue::truth(h.bakery_reload_required),
+            SnmpValue::truth(h.bakery_passthrough_degraded),
+            SnmpValue::gauge(h.bakery_last_success_unix.unwrap_or_default()),
+            SnmpValue::string(h.bakery_last_failure.as_deref().unwrap_or_default()),
+            SnmpValue::truth(h.stormguard_enabled),
+            SnmpValue::string(&h.stormguard_mode),
+            SnmpValue::string(&h.stormguard_phase),
+            SnmpValue::string(h.stormguard_last_error.as_deref().unwrap_or_default()),
+            SnmpValue::gauge(h.urgent_issues),
+            SnmpValue::gauge(h.urgent_errors),
+        ];
+        for (column, value) in (1..).zip(health_values) {
+            view.scalar(&health.child(&[column]), value);
+        }
+
+        let sites = objects.child(&[SITE_TABLE, TABLE_ENTRY]);
+        for site in &self.sites {
+            let index = hash_index(site.hash);
+            view.cell(&sites.child(&[3]), &index, SnmpValue::string(&site.name));
+            view.cell(&sites.child(&[4]), &index, SnmpValue::string(&site.parent));
+            view.cell(&sites.child(&[5]), &index, SnmpValue::gauge(site.max_mbps.down));
+            view.cell(&sites.child(&[6]), &index, SnmpValue::gauge(site.max_mbps.up));
+            for (column, value) in traffic_values(&site.traffic) {
+                view.cell(&sites.child(&[TRAFFIC_COLUMN_OFFSET + column]), &index, value);
+            }
+        }
+
+        let circuits = objects.child(&[CIRCUIT_TABLE, TABLE_ENTRY]);
+        for circuit in &self.circuits {
+            let index = hash_index(circuit.hash);
+            view.cell(&circuits.child(&[3]), &index, SnmpValue::string(&circuit.circuit_id));
+            view.cell(&circuits.child(&[4]), &index, SnmpValue::string(&circuit.name));
+            view.cell(&circuits.child(&[5]), &index, SnmpValue::string(&circuit.parent));
+            view.cell(&circuits.child(&[6]), &index, SnmpValue::gauge(circuit.plan_mbps.down));
+            view.cell(&circuits.child(&[7]), &index, SnmpValue::gauge(circuit.plan_mbps.up));
+            for (column, value) in traffic_values(&circuit.traffic) {
+                view.cell(&circuits.child(&[TRAFFIC_COLUMN_OFFSET + column]), &index, value);
+            }
+        }
+
+        view
+    }
+}
+
+/// Traffic objects numbered 1-14, skipping RTT percentiles that have no
+/// samples so tables stay sparse rather than reporting a fake zero.
+fn traffic_values(traffic: &SnmpTraffic) -> Vec<(u32, SnmpValue)> {
+    let mut values = vec![
+        (1, SnmpValue::Counter64(traffic.octets.down)),
+        (2, SnmpValue::Counter64(traffic.octets.up)),
+        (3, SnmpValue::Counter64(traffic.packets.down)),
+        (4, SnmpValue::Counter64(traffic.packets.up)),
+        (5, SnmpValue::Counter64(traffic.drops.down)),
+        (6, SnmpValue::Counter64(traffic.drops.up)),
+        (7, SnmpValue::Counter64(traffic.marks.down)),
+        (8, SnmpValue::Counter64(traffic.marks.up)),
+        (9, SnmpValue::gauge(traffic.bits_per_second.down / 1000)),
+        (10, SnmpValue::gauge(traffic.bits_per_second.up / 1000)),
+    ];
+    let rtts = [
+        (11, traffic.rtt_p50_micros.down),
+        (12, traffic.rtt_p50_micros.up),
+        (13, traffic.rtt_p90_micros.down),
+        (14, traffic.rtt_p90_micros.up),
+    ];
+    values.extend(
+        rtts.into_iter()
+            .filter_map(|(column, rtt)| rtt.map(|rtt| (column, SnmpValue::gauge(rtt)))),
+    );
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circuits_are_indexed_by_split_hash_and_skip_missing_rtt() {
+        let base: Oid = "1.3.6.1.4.1.8072.9999.9999.7341".parse().unwrap();
+        let snapshot = LibreQosSnapshot {
+            version: "1.5".to_string(),
+            circuits: vec![SnmpCircuitRow {
+                hash: -2,
+                circuit_id: "c1".to_string(),
+                traffic: SnmpTraffic {
+                    octets: DownUpOrder::new(10, 20),
+                    bits_per_second: DownUpOrder::new(12_345_000, 0),
+                    rtt_p50_micros: DownUpOrder::new(Some(15_000), None),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let view = snapshot.to_view(&base);
+        let row = base.child(&[1, 5, 1]);
+        let index = [u32::MAX, u32::MAX - 1];
+
+        assert_eq!(hash_index(-2), index);
+        assert_eq!(view.get(&row.child(&[3]).child(&index)), SnmpValue::string("c1"));
+        assert_eq!(view.get(&row.child(&[11]).child(&index)), SnmpValue::Counter64(10));
+        assert_eq!(view.get(&row.child(&[19]).child(&index)), SnmpValue::Gauge32(12_345));
+        assert_eq!(view.get(&row.child(&[21]).child(&index)), SnmpValue::Gauge32(15_000));
+        assert_eq!(
+            view.get(&row.child(&[22]).child(&index)),
+            SnmpValue::NoSuchObject
+        );
+        assert_eq!(
+            view.get(&base.child(&[1, 1, 1, 0])),
+            SnmpValue::string("1.5")
+        );
+        // Health scalars exist even before anything has happened.
+        assert_eq!(view.get(&base.child(&[1, 3, 1, 0])), SnmpValue::Integer(1));
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An SNMP object identifier, ordered lexicographically by sub-identifier.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Oid(Vec<u32>);
+
+/// Error returned when a dotted object identifier cannot be parsed.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum OidParseError {
+    /// The identifier had no sub-identifiers.
+    #[error("object identifier is empty")]
+    Empty,
+    /// A sub-identifier was not an unsigned 32-bit number.
+    #[error("invalid sub-identifier {0:?}")]
+    InvalidSubId(String),
+}
+
+impl Oid {
+    /// Builds an identifier from its sub-identifiers.
+    pub fn new(subids: Vec<u32>) -> Self {
+        Self(subids)
+    }
+
+    /// Returns the sub-identifiers.
+    pub fn subids(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// Returns true for the zero-length identifier AgentX uses as "no bound".
+    pub fn is_null(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns a new identifier with `suffix` appended.
+    pub fn child(&self, suffix: &[u32]) -> Self {
+        let mut subids = self.0.clone();
+        subids.extend_from_slice(suffix);
+        Self(subids)
+    }
+
+    /// Returns true when `self` equals `prefix` or lies beneath it.
+    pub fn starts_with(&self, prefix: &Oid) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+}
+
+impl FromStr for Oid {
+    type Err = OidParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let trimmed = text.trim().trim_start_matches('.');
+        if trimmed.is_empty() {
+            return Err(OidParseError::Empty);
+        }
+        trimmed
+            .split('.')
+            .map(|part| {
+                part.parse::<u32>()
+                    .map_err(|_| OidParseError::InvalidSubId(part.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, subid) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{subid}")?;
+        }
+        Ok(())
+    }
+}
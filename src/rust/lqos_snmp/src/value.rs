@@ -0,0 +1,39 @@
+/// The value of one SNMP variable binding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnmpValue {
+    /// `INTEGER` / `Integer32`, also used for enumerations and `TruthValue`.
+    Integer(i32),
+    /// `OCTET STRING`, usually a `DisplayString`.
+    OctetString(Vec<u8>),
+    /// `Counter32`.
+    Counter32(u32),
+    /// `Gauge32` / `Unsigned32`.
+    Gauge32(u32),
+    /// `TimeTicks`, in hundredths of a second.
+    TimeTicks(u32),
+    /// `Counter64`.
+    Counter64(u64),
+    /// No object type exists at the requested identifier.
+    NoSuchObject,
+    /// The object type exists but has no such instance.
+    NoSuchInstance,
+    /// A walk ran past the last variable in range.
+    EndOfMibView,
+}
+
+impl SnmpValue {
+    /// A `DisplayString` value.
+    pub fn string(text: impl AsRef<str>) -> Self {
+        Self::OctetString(text.as_ref().as_bytes().to_vec())
+    }
+
+    /// A `TruthValue`: 1 for true, 2 for false.
+    pub fn truth(value: bool) -> Self {
+        Self::Integer(if value { 1 } else { 2 })
+    }
+
+    /// A `Gauge32`, saturating values that do not fit.
+    pub fn gauge(value: u64) -> Self {
+        Self::Gauge32(u32::try_from(value).unwrap_or(u32::MAX))
+    }
+}
@@ -0,0 +1,114 @@
+use crate::oid::Oid;
+use crate::value::SnmpValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+
+/// An immutable, ordered set of variables served to the SNMP master agent.
+///
+/// Views are rebuilt from scratch on every refresh and swapped in whole, so a
+/// walk never sees a half-updated table.
+#[derive(Clone, Debug, Default)]
+pub struct MibView {
+    variables: BTreeMap<Oid, SnmpValue>,
+    objects: BTreeSet<Oid>,
+}
+
+impl MibView {
+    /// Starts an empty view.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the scalar `object` as instance `object.0`.
+    pub fn scalar(&mut self, object: &Oid, value: SnmpValue) {
+        self.objects.insert(object.clone());
+        self.variables.insert(object.child(&[0]), value);
+    }
+
+    /// Adds the row `index` of the table column `column`.
+    pub fn cell(&mut self, column: &Oid, index: &[u32], value: SnmpValue) {
+        self.objects.insert(column.clone());
+        self.variables.insert(column.child(index), value);
+    }
+
+    /// Number of variables in the view.
+    pub fn len(&self) -> usize {
+        self.variables.len()
+    }
+
+    /// Returns true when the view holds no variables.
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// Answers a Get for `oid`.
+    pub fn get(&self, oid: &Oid) -> SnmpValue {
+        if let Some(value) = self.variables.get(oid) {
+            return value.clone();
+        }
+        let known_object = self
+            .objects
+            .range(..=oid.clone())
+            .next_back()
+            .is_some_and(|object| oid.starts_with(object));
+        if known_object {
+            SnmpValue::NoSuchInstance
+        } else {
+            SnmpValue::NoSuchObject
+        }
+    }
+
+    /// Answers a GetNext: the first variable after `start` (or at it, when
+    /// `include` is set) and before `end`, unless `end` is null.
+    pub fn get_next(&self, start: &Oid, include: bool, end: &Oid) -> Option<(Oid, SnmpValue)> {
+        let lower = if include {
+            Bound::Included(start)
+        } else {
+            Bound::Excluded(start)
+        };
+        let (oid, value) = self
+            .variables
+            .range::<Oid, _>((lower, Bound::Unbounded))
+            .next()?;
+        if !end.is_null() && oid >= end {
+            return None;
+        }
+        Some((oid.clone(), value.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oid(text: &str) -> Oid {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn walks_in_order_and_reports_missing_instances() {
+        let mut view = MibView::new();
+        view.cell(&oid("1.3.6.1.9.2.1.4"), &[0, 7], SnmpValue::Counter64(9));
+        view.scalar(&oid("1.3.6.1.9.1.1"), SnmpValue::string("1.5"));
+        view.cell(&oid("1.3.6.1.9.2.1.3"), &[0, 7], SnmpValue::string("c1"));
+
+        let first = view.get_next(&oid("1.3.6.1.9"), false, &Oid::default());
+        assert_eq!(first.unwrap().0, oid("1.3.6.1.9.1.1.0"));
+        let second = view.get_next(&oid("1.3.6.1.9.1.1.0"), false, &Oid::default());
+        assert_eq!(second.unwrap().0, oid("1.3.6.1.9.2.1.3.0.7"));
+        let included = view.get_next(&oid("1.3.6.1.9.2.1.3.0.7"), true, &Oid::default());
+        assert_eq!(included.unwrap().1, SnmpValue::string("c1"));
+        assert!(
+            view.get_next(&oid("1.3.6.1.9.2.1.3.0.7"), false, &oid("1.3.6.1.9.2.1.4"))
+                .is_none()
+        );
+        assert!(
+            view.get_next(&oid("1.3.6.1.9.2.1.4.0.7"), false, &Oid::default())
+                .is_none()
+        );
+
+        assert_eq!(view.get(&oid("1.3.6.1.9.1.1.0")), SnmpValue::string("1.5"));
+        assert_eq!(view.get(&oid("1.3.6.1.9.2.1.4.0.8")), SnmpValue::NoSuchInstance);
+        assert_eq!(view.get(&oid("1.3.6.1.9.3.0")), SnmpValue::NoSuchObject);
+    }
+}
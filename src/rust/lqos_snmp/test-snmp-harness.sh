@@ -0,0 +1,164 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+usage() {
+    cat <<'EOF'
+Usage: test-snmp-harness.sh
+
+Builds the lqos_snmp diagnostic subagent, starts an unprivileged net-snmp
+master agent on loopback, registers the subagent over AgentX, and queries the
+LIBREQOS-MIB with snmpget/snmpbulkwalk over SNMPv2c and SNMPv3.
+
+Environment overrides:
+  SNMP_HARNESS_PORT=16161
+  SNMP_HARNESS_AGENTX_PORT=16705
+  SNMP_HARNESS_COMMUNITY=lqos-harness
+  SNMP_HARNESS_READY_ATTEMPTS=50
+  KEEP_SNMP_HARNESS_TMP=1
+EOF
+}
+
+if [[ "${1:-}" == "-h" || "${1:-}" == "--help" ]]; then
+    usage
+    exit 0
+fi
+
+SCRIPT_DIR="$(cd -- "$(dirname -- "${BASH_SOURCE[0]}")" && pwd)"
+RUST_DIR="$(cd -- "${SCRIPT_DIR}/.." && pwd)"
+PORT="${SNMP_HARNESS_PORT:-16161}"
+AGENTX_PORT="${SNMP_HARNESS_AGENTX_PORT:-16705}"
+COMMUNITY="${SNMP_HARNESS_COMMUNITY:-lqos-harness}"
+READY_ATTEMPTS="${SNMP_HARNESS_READY_ATTEMPTS:-50}"
+AGENT="127.0.0.1:${PORT}"
+BASE_OID=".1.3.6.1.4.1.8072.9999.9999.7341"
+V3_USER="lqosharness"
+V3_AUTH="harness-auth-pass"
+V3_PRIV="harness-priv-pass"
+
+for tool in snmpd snmpget snmpbulkwalk; do
+    if ! command -v "${tool}" >/dev/null 2>&1; then
+        echo "${tool} is required but was not found in PATH (install net-snmp / snmpd)" >&2
+        exit 1
+    fi
+done
+
+for value in "${PORT}" "${AGENTX_PORT}" "${READY_ATTEMPTS}"; do
+    case "${value}" in
+        ''|*[!0-9]*)
+            echo "ports and attempt counts must be numeric, got '${value}'" >&2
+            exit 1
+            ;;
+    esac
+done
+
+WORK_DIR="$(mktemp -d "${TMPDIR:-/tmp}/lqos-snmp-harness.XXXXXX")"
+SNMPD_LOG="${WORK_DIR}/snmpd.log"
+SUBAGENT_LOG="${WORK_DIR}/subagent.log"
+SNMPD_PID=""
+SUBAGENT_PID=""
+
+cleanup() {
+    for pid in "${SUBAGENT_PID}" "${SNMPD_PID}"; do
+        if [[ -n "${pid}" ]] && kill -0 "${pid}" >/dev/null 2>&1; then
+            kill "${pid}" >/dev/null 2>&1 || true
+            wait "${pid}" >/dev/null 2>&1 || true
+        fi
+    done
+
+    if [[ "${KEEP_SNMP_HARNESS_TMP:-0}" == "1" ]]; then
+        echo "Kept harness files in ${WORK_DIR}"
+    else
+        rm -rf "${WORK_DIR}"
+    fi
+}
+trap cleanup EXIT
+
+dump_logs() {
+    for log in "${SNMPD_LOG}" "${SUBAGENT_LOG}"; do
+        echo "--- ${log}" >&2
+        cat "${log}" >&2 || true
+    done
+}
+
+expect_output() {
+    local label="$1"
+    local pattern="$2"
+    shift 2
+    local output
+
+    if ! output="$("$@" 2>&1)"; then
+        echo "${label} failed:" >&2
+        echo "${output}" >&2
+        dump_logs
+        exit 1
+    fi
+    if ! grep -q -- "${pattern}" <<<"${output}"; then
+        echo "${label} did not contain '${pattern}':" >&2
+        echo "${output}" >&2
+        dump_logs
+        exit 1
+    fi
+    echo "${label}: ok"
+}
+
+echo "Building lqos_snmp diagnostic subagent..."
+cargo build --manifest-path "${RUST_DIR}/Cargo.toml" -p lqos_snmp
+
+cat >"${WORK_DIR}/snmpd.conf" <<EOF
+master agentx
+agentXSocket tcp:127.0.0.1:${AGENTX_PORT}
+rocommunity ${COMMUNITY} 127.0.0.1
+createUser ${V3_USER} SHA "${V3_AUTH}" AES "${V3_PRIV}"
+rouser ${V3_USER} priv
+EOF
+
+SNMP_PERSISTENT_DIR="${WORK_DIR}/persist" snmpd -f -Lf "${SNMPD_LOG}" -C \
+    -c "${WORK_DIR}/snmpd.conf" "udp:${AGENT}" &
+SNMPD_PID="$!"
+
+start_subagent() {
+    "${RUST_DIR}/target/debug/lqos_snmp" --agentx "tcp:127.0.0.1:${AGENTX_PORT}" \
+        >"${SUBAGENT_LOG}" 2>&1 &
+    SUBAGENT_PID="$!"
+    for _ in $(seq 1 20); do
+        if grep -q "Registered" "${SUBAGENT_LOG}"; then
+            return 0
+        fi
+        if ! kill -0 "${SUBAGENT_PID}" >/dev/null 2>&1; then
+            return 1
+        fi
+        sleep 0.1
+    done
+    kill "${SUBAGENT_PID}" >/dev/null 2>&1 || true
+    return 1
+}
+
+# snmpd needs a moment before it accepts AgentX connections.
+registered=0
+for _ in $(seq 1 "${READY_ATTEMPTS}"); do
+    if start_subagent; then
+        registered=1
+        break
+    fi
+    sleep 0.1
+done
+if [[ "${registered}" != "1" ]]; then
+    echo "lqos_snmp could not register with the master agent" >&2
+    dump_logs
+    exit 1
+fi
+
+expect_output "SNMPv2c get lqosVersion" "diagnostic" \
+    snmpget -v2c -c "${COMMUNITY}" -On "${AGENT}" "${BASE_OID}.1.1.1.0"
+expect_output "SNMPv2c bulk walk of lqosCircuitId" "STRING: \"101\"" \
+    snmpbulkwalk -v2c -c "${COMMUNITY}" -On "${AGENT}" "${BASE_OID}.1.5.1.3"
+expect_output "SNMPv2c Counter64 lqosCircuitDownOctets" "Counter64" \
+    snmpbulkwalk -v2c -c "${COMMUNITY}" -On "${AGENT}" "${BASE_OID}.1.5.1.11"
+expect_output "SNMPv3 authPriv get lqosDownOctets" "Counter64" \
+    snmpget -v3 -l authPriv -u "${V3_USER}" -a SHA -A "${V3_AUTH}" -x AES -X "${V3_PRIV}" \
+    -On "${AGENT}" "${BASE_OID}.1.2.1.0"
+expect_output "MIB names resolve" "lqosBakeryMode.0 = INTEGER: idle(1)" \
+    snmpget -v2c -c "${COMMUNITY}" -M "+${SCRIPT_DIR}" -m "+LIBREQOS-MIB" "${AGENT}" \
+    "LIBREQOS-MIB::lqosBakeryMode.0"
+
+echo "SNMP AgentX harness passed against ${AGENT}"
@@ -236,6 +236,7 @@ fn current_site_override(
                 site_name: current,
                 download_bandwidth_mbps,
                 upload_bandwidth_mbps,
+                ..
             } if current == site_name => Some((*download_bandwidth_mbps, *upload_bandwidth_mbps)),
             _ => None,
         })
@@ -293,6 +294,7 @@ fn has_sqm_override_for_device_ids(overrides: &OverrideFile, device_ids: &HashSe
             lqos_overrides::CircuitAdjustment::DeviceAdjustSqm {
                 device_id,
                 sqm_override,
+                ..
             } if device_ids.contains(device_id.as_str())
                 && sqm_override
                     .as_deref()
@@ -381,6 +383,7 @@ fn group_circuit_fallbacks(
         let lqos_overrides::CircuitAdjustment::DeviceAdjustSqm {
             device_id,
             sqm_override,
+            ..
         } = adj
         else {
             continue;
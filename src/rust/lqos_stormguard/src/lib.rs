@@ -17,6 +17,7 @@ use lqos_queue_tracker::QUEUE_STRUCTURE_CHANGED_STORMGUARD;
 use parking_lot::Mutex;
 use std::collections::HashSet;
 use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::{debug, info, warn};
 
@@ -176,6 +177,17 @@ pub static STORMGUARD_STATS: Mutex<Vec<(String, u64, u64)>> = Mutex::new(Vec::ne
 /// Debug snapshots of StormGuard evaluation state
 pub static STORMGUARD_DEBUG: Mutex<Vec<StormguardDebugEntry>> = Mutex::new(Vec::new());
 
+static STANDBY_HOLD: AtomicBool = AtomicBool::new(false);
+
+/// Holds StormGuard idle while this node is a high-availability standby.
+///
+/// A held StormGuard drops its in-memory state without touching the Bakery or
+/// the persisted StormGuard overrides layer, which the active peer replicates.
+/// Releasing the hold reconfigures StormGuard from that persisted layer.
+pub fn set_standby_hold(hold: bool) {
+    STANDBY_HOLD.store(hold, Ordering::Relaxed);
+}
+
 /// Launches the StormGuard component. Will exit if there's
 /// nothing to do.
 pub async fn start_stormguard(
@@ -206,6 +218,30 @@ pub async fn start_stormguard(
     loop {
         interval.tick().await;
 
+        if STANDBY_HOLD.load(Ordering::Relaxed) {
+            if config.is_some() || site_state_tracker.is_some() {
+                info!("StormGuard is holding while this node is an HA standby.");
+                config = None;
+                site_state_tracker = None;
+                log_sender = None;
+                logger_error = None;
+                last_application_error = None;
+                live_reset_pending = false;
+                clear_published_state();
+                active_ping.reconfigure(None);
+            }
+            observed_tree_generation = lqos_bakery::stormguard_tree_generation();
+            publish_runtime_status(
+                None,
+                requested_runtime_mode().unwrap_or(StormguardRuntimeMode::Disabled),
+                "standby",
+                false,
+                Some("StormGuard is held while this node is an HA standby".to_string()),
+                None,
+            );
+            continue;
+        }
+
         let Some(requested_mode) = requested_runtime_mode() else {
             warn!("StormGuard could not read the current runtime mode; retaining existing state.");
             publish_runtime_status(
@@ -220,7 +256,7 @@ pub async fn start_stormguard(
         };
         let active_mode = active_runtime_mode(config.as_ref());
         let dry_run_plan_changed = QUEUE_STRUCTURE_CHANGED_STORMGUARD
-            .swap(false, std::sync::atomic::Ordering::Relaxed)
+            .swap(false, Ordering::Relaxed)
             && active_mode == StormguardRuntimeMode::DryRun
             && requested_mode == StormguardRuntimeMode::DryRun;
         let current_tree_generation = lqos_bakery::stormguard_tree_generation();
@@ -0,0 +1,150 @@
+#pragma once
+
+#include <linux/bpf.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_endian.h>
+#include <linux/if_ether.h>
+#include <linux/in.h>
+#include <linux/in6.h>
+#include <linux/ip.h>
+#include <linux/udp.h>
+#include <stdbool.h>
+#include "maximums.h"
+#include "dissector.h"
+#include "dissector_tc.h"
+
+// CGNAT-aware classification.
+//
+// North of a CGNAT, subscribers share public addresses, so the address alone
+// can't find the circuit. Each shared public address is split into equal port
+// blocks, and every block resolves to the subscriber's inside address. That
+// address is then looked up in `map_ip_to_cpu_and_tc` like any other host.
+// Only TCP and UDP carry a usable port; anything else stays on the public
+// address.
+
+struct cgnat_control {
+    // Number of pools loaded; 0 skips the CGNAT lookups entirely.
+    __u32 pools;
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_ARRAY);
+    __uint(max_entries, 1);
+    __type(key, __u32);
+    __type(value, struct cgnat_control);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} cgnat_control SEC(".maps");
+
+// How a shared public address is split into port blocks.
+struct cgnat_pool {
+    __u16 first_port;
+    __u16 block_size;
+    __u32 pad;
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_HASH);
+    __uint(max_entries, CGNAT_PUBLIC_ADDRESSES_MAX);
+    __type(key, struct in6_addr);
+    __type(value, struct cgnat_pool);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+    __uint(map_flags, BPF_F_NO_PREALLOC);
+} map_cgnat_pools SEC(".maps");
+
+struct cgnat_block_key {
+    struct in6_addr public_ip;
+    __u32 block;
+};
+
+// Port block -> subscriber (inside) address, encoded by `ip_hash.h`.
+struct {
+    __uint(type, BPF_MAP_TYPE_HASH);
+    __uint(max_entries, CGNAT_PORT_BLOCKS_MAX);
+    __type(key, struct cgnat_block_key);
+    __type(value, struct in6_addr);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+    __uint(map_flags, BPF_F_NO_PREALLOC);
+} map_cgnat_blocks SEC(".maps");
+
+// Replaces a shared public address in `host` with the subscriber that owns
+// `port` (host byte order). Returns false, leaving `host` alone, if the
+// address isn't shared or the block isn't allocated.
+static __always_inline bool cgnat_resolve(
+    struct in6_addr *host,
+    __u8 protocol,
+    __u16 port
+) {
+    if (protocol != IPPROTO_TCP && protocol != IPPROTO_UDP) {
+        return false;
+    }
+    __u32 zero = 0;
+    struct cgnat_control *control = bpf_map_lookup_elem(&cgnat_control, &zero);
+    if (!control || control->pools == 0) {
+        return false;
+    }
+    struct cgnat_pool *pool = bpf_map_lookup_elem(&map_cgnat_pools, host);
+    if (!pool || pool->block_size == 0 || port < pool->first_port) {
+        return false;
+    }
+    struct cgnat_block_key key = {
+        .public_ip = *host,
+        .block = (__u32)(port - pool->first_port) / pool->block_size,
+    };
+    struct in6_addr *inside = bpf_map_lookup_elem(&map_cgnat_blocks, &key);
+    if (!inside) {
+        return false;
+    }
+    *host = *inside;
+    return true;
+}
+
+// XDP: resolves the subscriber side of the packet (the destination for
+// download, the source for upload).
+static __always_inline bool cgnat_resolve_xdp(
+    struct dissector_t *dissector,
+    u_int8_t effective_direction,
+    struct in6_addr *host
+) {
+    // The dissector keeps TCP/UDP ports in network byte order.
+    __u16 port = effective_direction == 1 ?
+        bpf_ntohs(dissector->dst_port) : bpf_ntohs(dissector->src_port);
+    return cgnat_resolve(host, dissector->ip_protocol, port);
+}
+
+// TC egress: as `cgnat_resolve_xdp`, reading the port from the packet.
+static __always_inline bool cgnat_resolve_tc(
+    struct tc_dissector_t *dissector,
+    bool subscriber_is_src,
+    struct in6_addr *host
+) {
+    void *l4 = NULL;
+    __u8 protocol = 0;
+    switch (dissector->eth_type) {
+    case ETH_P_IP: {
+        struct iphdr *iph = dissector->ip_header.iph;
+        if ((void *)(iph + 1) > dissector->end) return false;
+        __u32 ihl = iph->ihl * 4;
+        if (ihl < sizeof(struct iphdr)) return false;
+        protocol = iph->protocol;
+        l4 = (void *)iph + ihl;
+    } break;
+    case ETH_P_IPV6: {
+        struct ipv6hdr *ip6h = dissector->ip_header.ip6h;
+        if ((void *)(ip6h + 1) > dissector->end) return false;
+        protocol = ip6h->nexthdr;
+        l4 = (void *)(ip6h + 1);
+    } break;
+    default:
+        return false;
+    }
+    if (protocol != IPPROTO_TCP && protocol != IPPROTO_UDP) {
+        return false;
+    }
+    // TCP and UDP both start with the source and destination ports.
+    struct udphdr *ports = l4;
+    if ((void *)(ports + 1) > dissector->end) {
+        return false;
+    }
+    __u16 port = subscriber_is_src ? bpf_ntohs(ports->source) : bpf_ntohs(ports->dest);
+    return cgnat_resolve(host, protocol, port);
+}
@@ -0,0 +1,185 @@
+#pragma once
+
+#include <linux/bpf.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_endian.h>
+#include <linux/in.h>
+#include <linux/in6.h>
+#include <linux/ip.h>
+#include <linux/ipv6.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include "maximums.h"
+#include "dissector_tc.h"
+#include "lpm.h"
+#include "priority_lanes.h"
+
+// DSCP re-marking.
+//
+// lqosd assigns each circuit direction (by TC handle) a policy. `wash`
+// resets the DSCP to best effort; `rules` writes the mark of the first
+// matching rule and optionally washes the rest. ECN bits are preserved.
+// Re-marking runs before the priority lanes, so lane rules see the new mark.
+
+#define DSCP_MODE_TRUST 0
+#define DSCP_MODE_WASH 1
+#define DSCP_MODE_RULES 2
+
+struct dscp_rule {
+    // Bit n set means the remote address must be in ASN group n. Zero
+    // matches any remote.
+    __u64 asn_groups;
+    // Source or destination port range, host order. 0-0 matches any port.
+    __u16 port_min;
+    __u16 port_max;
+    // IP protocol number, or 0 for any.
+    __u8 protocol;
+    // DSCP to write.
+    __u8 dscp;
+    __u8 pad[2];
+};
+
+struct dscp_policy {
+    __u8 mode;
+    __u8 wash_unmatched;
+    __u8 count;
+    // Non-zero if any rule matches on ASN groups.
+    __u8 uses_asns;
+    __u32 pad;
+    struct dscp_rule rules[MAX_DSCP_RULES];
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_ARRAY);
+    __uint(max_entries, DSCP_POLICIES_MAX);
+    __type(key, __u32);
+    __type(value, struct dscp_policy);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} map_dscp_policies SEC(".maps");
+
+struct dscp_circuit {
+    // Index into `map_dscp_policies`.
+    __u32 policy;
+    __u32 pad;
+    // Packets whose mark was changed.
+    __u64 remarked;
+};
+
+// Keyed like `map_priority_lanes`.
+struct {
+    __uint(type, BPF_MAP_TYPE_HASH);
+    __type(key, struct priority_lane_key);
+    __type(value, struct dscp_circuit);
+    __uint(max_entries, IP_HASH_ENTRIES_MAX);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} map_dscp_circuits SEC(".maps");
+
+// Remote prefixes of the ASNs used by rules, keyed like
+// `map_ip_to_cpu_and_tc`. The value is the mask of ASN groups the prefix
+// belongs to.
+struct {
+    __uint(type, BPF_MAP_TYPE_LPM_TRIE);
+    __uint(max_entries, DSCP_ASN_PREFIXES_MAX);
+    __type(key, struct ip_hash_key);
+    __type(value, __u64);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+    __uint(map_flags, BPF_F_NO_PREALLOC);
+} map_dscp_asn_prefixes SEC(".maps");
+
+static __always_inline int dscp_pick(
+    const struct dscp_policy *policy,
+    const struct priority_lane_packet *packet,
+    const struct in6_addr *remote
+) {
+    if (policy->mode == DSCP_MODE_WASH) return 0;
+    if (policy->mode != DSCP_MODE_RULES) return -1;
+
+    __u64 groups = 0;
+    if (policy->uses_asns) {
+        struct ip_hash_key key = {
+            .prefixlen = 128,
+            .address = *remote,
+        };
+        __u64 *found = bpf_map_lookup_elem(&map_dscp_asn_prefixes, &key);
+        if (found) groups = *found;
+    }
+
+    for (__u32 i = 0; i < MAX_DSCP_RULES; i++) {
+        if (i >= policy->count) break;
+        const struct dscp_rule *rule = &policy->rules[i];
+        if (rule->protocol != 0 && rule->protocol != packet->protocol) continue;
+        if (rule->port_min != 0 || rule->port_max != 0) {
+            if (!((packet->src_port >= rule->port_min && packet->src_port <= rule->port_max)
+                || (packet->dst_port >= rule->port_min && packet->dst_port <= rule->port_max))) {
+                continue;
+            }
+        }
+        if (rule->asn_groups != 0 && !(rule->asn_groups & groups)) continue;
+        return rule->dscp;
+    }
+    return policy->wash_unmatched ? 0 : -1;
+}
+
+// Applies the circuit's re-marking policy, if it has one. `dissected` says
+// whether `dissector` already located the IP header. Returns true if the
+// packet was rewritten, which invalidates the dissector's packet pointers.
+static __always_inline bool dscp_remark(
+    struct __sk_buff *skb,
+    struct tc_dissector_t *dissector,
+    bool dissected,
+    __u32 tc_handle,
+    __u32 upload
+) {
+    struct priority_lane_key key = {
+        .tc_handle = tc_handle,
+        .upload = upload,
+    };
+    struct dscp_circuit *circuit = bpf_map_lookup_elem(&map_dscp_circuits, &key);
+    if (!circuit) return false;
+    __u32 policy_index = circuit->policy;
+    struct dscp_policy *policy = bpf_map_lookup_elem(&map_dscp_policies, &policy_index);
+    if (!policy || policy->mode == DSCP_MODE_TRUST) return false;
+
+    if (!dissected) {
+        if (!tc_dissector_new(skb, dissector)) return false;
+        if (!tc_dissector_find_l3_offset(dissector)) return false;
+        if (!tc_dissector_find_ip_header(dissector)) return false;
+    }
+
+    struct priority_lane_packet packet = {0};
+    if (!priority_lane_parse(dissector, &packet)) return false;
+    struct in6_addr *remote = upload ? &dissector->dst_ip : &dissector->src_ip;
+    int dscp = dscp_pick(policy, &packet, remote);
+    if (dscp < 0 || dscp == packet.dscp) return false;
+
+    __u32 offset = dissector->l3offset;
+    if (dissector->eth_type == ETH_P_IP) {
+        struct iphdr *iph = dissector->ip_header.iph;
+        if ((void *)(iph + 1) > dissector->end) return false;
+        // The checksum covers the 16-bit word holding version, IHL and TOS.
+        __u16 old_word = *(__u16 *)iph;
+        __u8 tos = (iph->tos & 0x03) | (dscp << 2);
+        __u16 new_word = old_word;
+        ((__u8 *)&new_word)[1] = tos;
+        if (bpf_l3_csum_replace(skb, offset + offsetof(struct iphdr, check),
+                old_word, new_word, sizeof(__u16)) < 0) {
+            return false;
+        }
+        if (bpf_skb_store_bytes(skb, offset + offsetof(struct iphdr, tos),
+                &tos, sizeof(tos), 0) < 0) {
+            return false;
+        }
+    } else {
+        struct ipv6hdr *ip6h = dissector->ip_header.ip6h;
+        if ((void *)(ip6h + 1) > dissector->end) return false;
+        // Version (4 bits), traffic class (8 bits), then the flow label.
+        __u16 word = bpf_ntohs(*(__u16 *)ip6h);
+        word = (word & 0xF03F) | ((__u16)dscp << 6);
+        word = bpf_htons(word);
+        if (bpf_skb_store_bytes(skb, offset, &word, sizeof(word), 0) < 0) {
+            return false;
+        }
+    }
+    __sync_fetch_and_add(&circuit->remarked, 1);
+    return true;
+}
@@ -0,0 +1,199 @@
+#pragma once
+
+#include <linux/bpf.h>
+#include <bpf/bpf_helpers.h>
+#include <linux/in.h>
+#include <linux/in6.h>
+#include <stdbool.h>
+#include "maximums.h"
+#include "dissector.h"
+
+// Per-circuit flood and abuse detection.
+//
+// For every packet of a mapped circuit, XDP counts TCP packets, SYNs without
+// ACK, new flows and small packets per direction, and marks the remote
+// address in a small bitmap so lqosd can estimate how many distinct remotes
+// the circuit talked to (linear counting). lqosd turns the counters into
+// rates, and may install a mitigation that caps or drops one direction of a
+// circuit.
+//
+// Counter arrays are indexed 0 = download, 1 = upload.
+
+#define FLOOD_SMALL_PACKET_BYTES 128
+#define FLOOD_REMOTE_SKETCH_WORDS 4 // 256 bits
+#define FLOOD_ACTION_NONE 0
+#define FLOOD_ACTION_PPS_CAP 1
+#define FLOOD_ACTION_DROP 2
+
+struct flood_control {
+    // Non-zero counts packets into `map_flood_counters`.
+    __u32 enabled;
+    // Bumped by lqosd after reading the remote sketches; a counter with an
+    // older epoch starts a fresh sketch.
+    __u32 sketch_epoch;
+    // Number of mitigations loaded; 0 skips the mitigation lookup.
+    __u32 mitigations;
+    __u32 pad;
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_ARRAY);
+    __uint(max_entries, 1);
+    __type(key, __u32);
+    __type(value, struct flood_control);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} flood_control SEC(".maps");
+
+struct flood_counter {
+    __u64 packets[2];
+    __u64 tcp_packets[2];
+    __u64 syn_packets[2];
+    __u64 new_flows[2];
+    __u64 small_packets[2];
+    __u64 remotes[2][FLOOD_REMOTE_SKETCH_WORDS];
+    __u64 last_seen;
+    __u32 sketch_epoch;
+    __u32 pad;
+};
+
+// Per-circuit counters, keyed by circuit hash. lqosd removes circuits that
+// have gone quiet.
+struct {
+    __uint(type, BPF_MAP_TYPE_PERCPU_HASH);
+    __type(key, __u64);
+    __type(value, struct flood_counter);
+    __uint(max_entries, FLOOD_CIRCUITS_MAX);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+    __uint(map_flags, BPF_F_NO_PREALLOC);
+} map_flood_counters SEC(".maps");
+
+// Scratch space to avoid a large flood_counter on the stack
+struct {
+    __uint(type, BPF_MAP_TYPE_PERCPU_ARRAY);
+    __uint(max_entries, 1);
+    __type(key, __u32);
+    __type(value, struct flood_counter);
+} map_flood_scratch SEC(".maps");
+
+struct flood_mitigation {
+    __u8 action[2];
+    __u16 pad;
+    __u32 pps_cap[2];
+    __u32 pad1;
+    // One-second window used by FLOOD_ACTION_PPS_CAP. Shared by all CPUs;
+    // a racy window reset only lets a few extra packets through.
+    __u64 window_start[2];
+    __u64 window_packets[2];
+    __u64 dropped_packets[2];
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_HASH);
+    __type(key, __u64);
+    __type(value, struct flood_mitigation);
+    __uint(max_entries, FLOOD_MITIGATIONS_MAX);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+    __uint(map_flags, BPF_F_NO_PREALLOC);
+} map_flood_mitigations SEC(".maps");
+
+static __always_inline __u32 flood_remote_bit(const struct in6_addr *remote) {
+    __u32 hash = remote->in6_u.u6_addr32[0] ^ remote->in6_u.u6_addr32[1] ^
+        remote->in6_u.u6_addr32[2] ^ remote->in6_u.u6_addr32[3];
+    hash *= 2654435761u;
+    return hash >> 24;
+}
+
+static __always_inline void flood_count(
+    struct flood_counter *counter,
+    struct dissector_t *dissector,
+    __u32 dir,
+    __u32 size,
+    bool new_flow,
+    __u32 sketch_epoch
+) {
+    if (counter->sketch_epoch != sketch_epoch) {
+        __builtin_memset(counter->remotes, 0, sizeof(counter->remotes));
+        counter->sketch_epoch = sketch_epoch;
+    }
+    counter->last_seen = dissector->now;
+    counter->packets[dir] += 1;
+    if (dissector->ip_protocol == IPPROTO_TCP) {
+        counter->tcp_packets[dir] += 1;
+        if (BITCHECK(DIS_TCP_SYN) && !BITCHECK(DIS_TCP_ACK)) {
+            counter->syn_packets[dir] += 1;
+        }
+    }
+    if (new_flow) {
+        counter->new_flows[dir] += 1;
+    }
+    if (size <= FLOOD_SMALL_PACKET_BYTES) {
+        counter->small_packets[dir] += 1;
+    }
+    // The remote end is the source for download and the destination for
+    // upload.
+    __u32 bit = flood_remote_bit(dir == 0 ? &dissector->src_ip : &dissector->dst_ip);
+    counter->remotes[dir][(bit >> 6) & (FLOOD_REMOTE_SKETCH_WORDS - 1)] |= 1ULL << (bit & 63);
+}
+
+// Counts the packet for its circuit and applies any mitigation. Returns
+// XDP_DROP if the packet should be dropped, XDP_PASS otherwise.
+static __always_inline int flood_detection_check(
+    struct dissector_t *dissector,
+    u_int8_t effective_direction,
+    __u64 circuit_id,
+    __u32 size,
+    bool new_flow
+) {
+    if (circuit_id == 0) {
+        return XDP_PASS;
+    }
+    __u32 zero = 0;
+    struct flood_control *control = bpf_map_lookup_elem(&flood_control, &zero);
+    if (!control) {
+        return XDP_PASS;
+    }
+    __u32 dir = effective_direction == 1 ? 0 : 1;
+
+    if (control->enabled) {
+        struct flood_counter *counter = bpf_map_lookup_elem(&map_flood_counters, &circuit_id);
+        if (counter) {
+            flood_count(counter, dissector, dir, size, new_flow, control->sketch_epoch);
+        } else {
+            struct flood_counter *fresh = bpf_map_lookup_elem(&map_flood_scratch, &zero);
+            if (fresh) {
+                __builtin_memset(fresh, 0, sizeof(*fresh));
+                flood_count(fresh, dissector, dir, size, new_flow, control->sketch_epoch);
+                bpf_map_update_elem(&map_flood_counters, &circuit_id, fresh, BPF_NOEXIST);
+            }
+        }
+    }
+
+    if (control->mitigations == 0) {
+        return XDP_PASS;
+    }
+    struct flood_mitigation *mitigation =
+        bpf_map_lookup_elem(&map_flood_mitigations, &circuit_id);
+    if (!mitigation) {
+        return XDP_PASS;
+    }
+    switch (mitigation->action[dir]) {
+    case FLOOD_ACTION_DROP:
+        __sync_fetch_and_add(&mitigation->dropped_packets[dir], 1);
+        return XDP_DROP;
+    case FLOOD_ACTION_PPS_CAP: {
+        if (dissector->now - mitigation->window_start[dir] >= 1000000000ULL) {
+            mitigation->window_start[dir] = dissector->now;
+            mitigation->window_packets[dir] = 0;
+        }
+        // The XADD result isn't usable on every BPF target, so re-read.
+        __sync_fetch_and_add(&mitigation->window_packets[dir], 1);
+        if (mitigation->window_packets[dir] > mitigation->pps_cap[dir]) {
+            __sync_fetch_and_add(&mitigation->dropped_packets[dir], 1);
+            return XDP_DROP;
+        }
+        return XDP_PASS;
+    }
+    default:
+        return XDP_PASS;
+    }
+}
@@ -106,7 +106,8 @@ struct flow_data_t {
     // Cached mapping epoch (from ip_mapping_epoch). When this differs from the
     // current epoch, per-flow mapping metadata should be refreshed from the LPM/hotcache.
     __u32 mapping_epoch;
-    __u32 pad3;
+    // Cached walled-garden access policy (see walled_garden.h).
+    __u32 access_policy;
 };
 
 // Map for tracking TCP flow progress.
@@ -141,6 +142,26 @@ struct flowbee_event {
 	__u32 effective_direction;
 };
 
+// Ringbuffer to userspace for early-packet payload samples, used for
+// application classification (TLS/QUIC SNI and DNS answers).
+struct {
+	__uint(type, BPF_MAP_TYPE_RINGBUF);
+	__uint(max_entries, 2 * 1024 * 1024 /* 2 MB */);
+} flowbee_payloads SEC(".maps");
+
+// Largest payload prefix copied into a sample
+#define FLOWBEE_PAYLOAD_BYTES 1500
+// Only the first few packets of a flow (per direction) are sampled
+#define FLOWBEE_PAYLOAD_PACKETS 8
+
+// Payload sample structure we send to userspace.
+struct flowbee_payload_event {
+    struct flow_key_t key;
+    __u32 effective_direction;
+    __u32 payload_len;
+    __u8 payload[FLOWBEE_PAYLOAD_BYTES];
+};
+
 // Construct an empty flow_data_t structure, using default values.
 static __always_inline void init_flow_data(
     // The packet dissector from the previous step
@@ -226,12 +247,14 @@ static __always_inline void update_flow_metadata(
     __u32 cpu,
     __u64 circuit_hash,
     __u64 device_hash,
+    __u32 access_policy,
     __u32 mapping_epoch
 ) {
     data->tc_handle = tc_handle;
     data->cpu = cpu;
     data->circuit_hash = circuit_hash;
     data->device_hash = device_hash;
+    data->access_policy = access_policy;
     data->mapping_epoch = mapping_epoch;
 }
 
@@ -264,6 +287,7 @@ static __always_inline void process_icmp(
             mapping->cpu,
             mapping->circuit_id,
             mapping->device_id,
+            mapping->access_policy,
             mapping_epoch
         );
         update_flow_rates(dissector, rate_index, new_data);
@@ -296,6 +320,7 @@ static __always_inline void process_udp(
             mapping->cpu,
             mapping->circuit_id,
             mapping->device_id,
+            mapping->access_policy,
             mapping_epoch
         );
         update_flow_rates(dissector, rate_index, new_data);
@@ -498,6 +523,7 @@ static __always_inline void process_tcp(
             mapping->cpu,
             mapping->circuit_id,
             mapping->device_id,
+            mapping->access_policy,
             mapping_epoch
         );
         if (bpf_map_update_elem(&flowbee, key, new_data, BPF_ANY) != 0) {
@@ -527,6 +553,7 @@ static __always_inline void process_tcp(
             mapping->cpu,
             mapping->circuit_id,
             mapping->device_id,
+            mapping->access_policy,
             mapping_epoch
         );
 
@@ -566,11 +593,59 @@ static __always_inline void process_tcp(
     }
 }
 
+// Copies the start of the payload for the first packets of flows that can
+// identify an application: TLS/QUIC client hellos (to remote port 443) and
+// DNS answers (from remote port 53).
+static __always_inline void sample_flow_payload(
+    struct dissector_t *dissector,
+    u_int8_t direction,
+    struct flow_key_t *key,
+    struct flow_data_t *data,
+    u_int8_t rate_index
+) {
+    __u16 remote_port = key->src_port;
+    bool client_hello = direction == TO_INTERNET && remote_port == 443;
+    bool dns_answer = direction == FROM_INTERNET && remote_port == 53 &&
+        dissector->ip_protocol == IPPROTO_UDP;
+    if (!client_hello && !dns_answer) return;
+    if (data && data->packets_sent[rate_index] >= FLOWBEE_PAYLOAD_PACKETS) return;
+
+    void *payload = NULL;
+    if (dissector->ip_protocol == IPPROTO_TCP) {
+        struct tcphdr *hdr = get_tcp_header(dissector);
+        if (hdr == NULL || hdr + 1 > dissector->end) return;
+        payload = (void *)hdr + (hdr->doff * 4);
+    } else if (dissector->ip_protocol == IPPROTO_UDP) {
+        struct udphdr *hdr = get_udp_header(dissector);
+        if (hdr == NULL || hdr + 1 > dissector->end) return;
+        payload = (void *)(hdr + 1);
+    } else {
+        return;
+    }
+    if (payload >= dissector->end) return;
+    __u32 len = dissector->end - payload;
+    if (len > FLOWBEE_PAYLOAD_BYTES) len = FLOWBEE_PAYLOAD_BYTES;
+
+    struct flowbee_payload_event *event = bpf_ringbuf_reserve(
+        &flowbee_payloads, sizeof(struct flowbee_payload_event), 0);
+    if (event == NULL) return;
+    event->key = *key;
+    event->effective_direction = rate_index;
+    if (bpf_probe_read_kernel(event->payload, len, payload) < 0) {
+        bpf_ringbuf_discard(event, 0);
+        return;
+    }
+    event->payload_len = len;
+    bpf_ringbuf_submit(event, 0);
+}
+
 // Note that this duplicates a lot of what we do for "snoop" - we're hoping
 // to replace both it and the old RTT system.
-static __always_inline void track_flows(
+// Returns true if the packet belongs to a flow that wasn't tracked yet.
+static __always_inline bool track_flows(
     struct dissector_t *dissector, // The packet dissector from the previous step
     u_int8_t direction, // The direction of the packet (1 = to internet, 2 = to local network)
+    const struct in6_addr *host, // The subscriber-side address, after CGNAT resolution
     struct ip_hash_info *out_mapping
 ) {
     // Default to "unshaped".
@@ -578,6 +653,7 @@ static __always_inline void track_flows(
     out_mapping->cpu = 0;
     out_mapping->circuit_id = 0;
     out_mapping->device_id = 0;
+    out_mapping->access_policy = 0;
 
     // We only track flowbee entries for these protocols. For everything else,
     // fall back to the (hotcache + LPM) lookup and skip flow tracking.
@@ -587,15 +663,16 @@ static __always_inline void track_flows(
         dissector->ip_protocol != IPPROTO_ICMP
     ) {
         struct ip_hash_key lookup_key;
-        struct ip_hash_info *ip_info = setup_lookup_key_and_tc_cpu(direction, &lookup_key, dissector);
+        struct ip_hash_info *ip_info = setup_lookup_key_and_tc_cpu(&lookup_key, host);
         if (ip_info) {
             out_mapping->tc_handle = ip_info->tc_handle;
             out_mapping->cpu = ip_info->cpu;
             out_mapping->circuit_id = ip_info->circuit_id;
             out_mapping->device_id = ip_info->device_id;
+            out_mapping->access_policy = ip_info->access_policy;
         }
         apply_stick_offset_to_mapping(direction, out_mapping);
-        return;
+        return false;
     }
 
     __u32 mapping_epoch = get_current_ip_mapping_epoch();
@@ -608,18 +685,28 @@ static __always_inline void track_flows(
         // If mappings changed, refresh per-flow mapping metadata from the hotcache/LPM.
         if (data->mapping_epoch != mapping_epoch) {
             struct ip_hash_key lookup_key;
-            struct ip_hash_info *ip_info = setup_lookup_key_and_tc_cpu(direction, &lookup_key, dissector);
+            struct ip_hash_info *ip_info = setup_lookup_key_and_tc_cpu(&lookup_key, host);
             __u32 tc_handle = 0;
             __u32 cpu = 0;
             __u64 circuit_hash = 0;
             __u64 device_hash = 0;
+            __u32 access_policy = 0;
             if (ip_info) {
                 tc_handle = ip_info->tc_handle;
                 cpu = ip_info->cpu;
                 circuit_hash = ip_info->circuit_id;
                 device_hash = ip_info->device_id;
+                access_policy = ip_info->access_policy;
             }
-            update_flow_metadata(data, tc_handle, cpu, circuit_hash, device_hash, mapping_epoch);
+            update_flow_metadata(
+                data,
+                tc_handle,
+                cpu,
+                circuit_hash,
+                device_hash,
+                access_policy,
+                mapping_epoch
+            );
         }
 
         // Populate out_mapping from the flow (fast path - no hotcache/LPM required).
@@ -627,15 +714,17 @@ static __always_inline void track_flows(
         out_mapping->cpu = data->cpu;
         out_mapping->circuit_id = data->circuit_hash;
         out_mapping->device_id = data->device_hash;
+        out_mapping->access_policy = data->access_policy;
     } else {
         // New flow (or untracked TCP before SYN). Do hotcache/LPM lookup.
         struct ip_hash_key lookup_key;
-        struct ip_hash_info *ip_info = setup_lookup_key_and_tc_cpu(direction, &lookup_key, dissector);
+        struct ip_hash_info *ip_info = setup_lookup_key_and_tc_cpu(&lookup_key, host);
         if (ip_info) {
             out_mapping->tc_handle = ip_info->tc_handle;
             out_mapping->cpu = ip_info->cpu;
             out_mapping->circuit_id = ip_info->circuit_id;
             out_mapping->device_id = ip_info->device_id;
+            out_mapping->access_policy = ip_info->access_policy;
         }
     }
 
@@ -650,6 +739,9 @@ static __always_inline void track_flows(
         other_rate_index = 1;
     }
 
+    // Sample early payloads for application classification
+    sample_flow_payload(dissector, direction, &key, data, rate_index);
+
     // Pass to the appropriate protocol handler
     switch (dissector->ip_protocol)
     {
@@ -667,4 +759,5 @@ static __always_inline void track_flows(
     // configured stick offset. We do this after flow processing so we only
     // cache the base mapping inside flowbee.
     apply_stick_offset_to_mapping(direction, out_mapping);
+    return data == NULL;
 }
@@ -10,6 +10,7 @@
 #include <linux/ipv6.h>
 #include "maximums.h"
 #include "dissector.h"
+#include "cgnat.h"
 
 // Data structure used for map_ip_hash
 struct ip_hash_info {
@@ -17,6 +18,8 @@ struct ip_hash_info {
 	__u32 tc_handle; // TC handle MAJOR:MINOR combined in __u32
 	__u64 circuit_id;
 	__u64 device_id;
+	__u32 access_policy; // ACCESS_POLICY_* from walled_garden.h
+	__u32 pad;
 };
 
 struct tc_classify_control {
@@ -123,22 +126,20 @@ static __always_inline void apply_stick_offset_to_mapping(
     mapping->tc_handle += stick_offset << 16;
 }
 
-// Performs an LPM lookup for an `ip_hash.h` encoded address, taking
-// into account redirection and "on a stick" setup.
+// Performs an LPM lookup for an `ip_hash.h` encoded address, using the
+// hot cache where possible.
 static __always_inline struct ip_hash_info * setup_lookup_key_and_tc_cpu(
-    // This must have been pre-calculated by `determine_effective_direction`.
-    u_int8_t direction,
-    // Pointer to the "lookup key", which should contain the IP address
-    // to search for. Prefix length will be set for you.
+    // Pointer to the "lookup key". Address and prefix length will be set
+    // for you.
     struct ip_hash_key * lookup_key,
-    // Pointer to the traffic dissector.
-    struct dissector_t * dissector
+    // The subscriber-side address of the packet, already resolved through
+    // `cgnat_resolve_xdp`.
+    const struct in6_addr * host
 ) 
 {
     struct ip_hash_info * ip_info;
 
-    lookup_key->address = (direction == 1) ? dissector->dst_ip :
-        dissector->src_ip;
+    lookup_key->address = *host;
 
     #ifdef USE_HOTCACHE
     // Try a hot cache search
@@ -179,6 +180,8 @@ static __always_inline struct ip_hash_info * setup_lookup_key_and_tc_cpu(
             .tc_handle = NEGATIVE_HIT,
             .circuit_id = 0,
             .device_id = 0,
+            .access_policy = 0,
+            .pad = 0,
         };
         bpf_map_update_elem(
             &ip_to_cpu_and_tc_hotcache,
@@ -218,6 +221,7 @@ static __always_inline struct ip_hash_info tc_setup_lookup_key_and_tc_cpu(
         lookup_key->address = (direction == 1) ? dissector->src_ip :
             dissector->dst_ip;
         *out_effective_direction = direction;
+        cgnat_resolve_tc(dissector, direction == 1, &lookup_key->address);
 
         struct ip_hash_info * ip_info = bpf_map_lookup_elem(
             &map_ip_to_cpu_and_tc, 
@@ -243,6 +247,7 @@ static __always_inline struct ip_hash_info tc_setup_lookup_key_and_tc_cpu(
             lookup_key->address = dissector->dst_ip;
             *out_effective_direction = 1;
         }
+        cgnat_resolve_tc(dissector, *out_effective_direction == 2, &lookup_key->address);
 
         // Regardless of effective direction, we look up the base mapping in the
         // primary map. Upload mapping is derived via stick_offset.
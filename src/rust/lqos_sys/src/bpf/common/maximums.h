@@ -20,4 +20,47 @@
 
 // Hot Cache Negative Hit Flag
 // If you have 4294967294 CPUs, I love you.
-#define NEGATIVE_HIT 4294967294
\ No newline at end of file
+#define NEGATIVE_HIT 4294967294
+// Maximum number of priority-lane match rules per circuit and direction.
+// Must match MAX_PRIORITY_RULES_PER_CIRCUIT in lqos_config.
+#define MAX_PRIORITY_LANE_RULES 16
+
+// Maximum number of allowlisted walled-garden prefixes.
+#define WALLED_GARDEN_PREFIXES_MAX 1024
+
+// Maximum number of concurrent walled-garden portal redirects.
+#define WALLED_GARDEN_NAT_MAX 65536
+
+// Maximum number of shared public addresses behind a CGNAT.
+// Must match MAX_CGNAT_PUBLIC_ADDRESSES in lqos_config.
+#define CGNAT_PUBLIC_ADDRESSES_MAX 65536
+
+// Maximum number of CGNAT port blocks.
+// Must match MAX_CGNAT_PORT_BLOCKS in lqos_config.
+#define CGNAT_PORT_BLOCKS_MAX 1048576
+
+// Maximum number of circuits with flood-detection counters.
+#define FLOOD_CIRCUITS_MAX 65536
+
+// Maximum number of concurrent flood mitigations.
+// Must match MAX_FLOOD_MITIGATIONS in lqos_sys.
+#define FLOOD_MITIGATIONS_MAX 4096
+
+// Maximum number of DSCP re-marking policies.
+// Must match MAX_DSCP_POLICIES in lqos_config.
+#define DSCP_POLICIES_MAX 64
+
+// Maximum number of rules per DSCP re-marking policy.
+// Must match MAX_DSCP_RULES_PER_POLICY in lqos_config.
+#define MAX_DSCP_RULES 16
+
+// Maximum number of remote prefixes used by DSCP re-marking ASN rules.
+#define DSCP_ASN_PREFIXES_MAX 131072
+
+// Maximum number of concurrent traffic mirror sessions.
+// Must match MAX_MIRROR_SESSIONS in lqos_config.
+#define MIRROR_SESSIONS_MAX 16
+
+// Maximum number of mirrored addresses and prefixes.
+// Must match MAX_MIRROR_PREFIXES in lqos_config.
+#define MIRROR_PREFIXES_MAX 1024
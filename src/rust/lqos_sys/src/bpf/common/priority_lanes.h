@@ -0,0 +1,171 @@
+#pragma once
+
+#include <linux/bpf.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_endian.h>
+#include <linux/in.h>
+#include <linux/in6.h>
+#include <linux/ip.h>
+#include <linux/ipv6.h>
+#include <linux/udp.h>
+#include <stdbool.h>
+#include "maximums.h"
+#include "dissector_tc.h"
+
+// Intra-circuit priority lanes.
+//
+// A circuit with priority classes has a nested HTB as its leaf qdisc. Each
+// class lane is selected by an `fw` filter on skb->mark, so all the
+// classifier needs to do is set the mark of the first matching rule. Packets
+// that match nothing keep their mark and fall into the default lane.
+
+// Key: the circuit TC handle (as written to skb->priority) and direction.
+struct priority_lane_key {
+    __u32 tc_handle;
+    __u32 upload;
+};
+
+// One flattened match rule. Every set field must match.
+struct priority_lane_rule {
+    // Bit n set means DSCP n matches. Zero matches any DSCP.
+    __u64 dscp_mask;
+    // Remote (non-subscriber) network, encoded like `ip_hash.h` addresses.
+    struct in6_addr prefix;
+    // Prefix length over the encoded address. Zero matches any address.
+    __u32 prefix_len;
+    // Source or destination port range, host order. 0-0 matches any port.
+    __u16 port_min;
+    __u16 port_max;
+    // IP protocol number, or 0 for any.
+    __u8 protocol;
+    __u8 pad[3];
+    // skb->mark to apply, which is the lane's class minor.
+    __u32 mark;
+};
+
+struct priority_lane_rules {
+    __u32 count;
+    __u32 pad;
+    struct priority_lane_rule rules[MAX_PRIORITY_LANE_RULES];
+};
+
+struct
+{
+    __uint(type, BPF_MAP_TYPE_HASH);
+    __type(key, struct priority_lane_key);
+    __type(value, struct priority_lane_rules);
+    __uint(max_entries, IP_HASH_ENTRIES_MAX);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} map_priority_lanes SEC(".maps");
+
+// Header fields the rules match on.
+struct priority_lane_packet {
+    __u8 dscp;
+    __u8 protocol;
+    __u16 src_port;
+    __u16 dst_port;
+};
+
+static __always_inline bool priority_lane_parse(
+    struct tc_dissector_t *dissector,
+    struct priority_lane_packet *out
+) {
+    void *l4 = NULL;
+    switch (dissector->eth_type) {
+    case ETH_P_IP: {
+        struct iphdr *iph = dissector->ip_header.iph;
+        if ((void *)(iph + 1) > dissector->end) return false;
+        __u32 ihl = iph->ihl * 4;
+        if (ihl < sizeof(struct iphdr)) return false;
+        out->dscp = iph->tos >> 2;
+        out->protocol = iph->protocol;
+        l4 = (void *)iph + ihl;
+    } break;
+    case ETH_P_IPV6: {
+        struct ipv6hdr *ip6h = dissector->ip_header.ip6h;
+        if ((void *)(ip6h + 1) > dissector->end) return false;
+        out->dscp = ((ip6h->priority << 4) | (ip6h->flow_lbl[0] >> 4)) >> 2;
+        out->protocol = ip6h->nexthdr;
+        l4 = (void *)(ip6h + 1);
+    } break;
+    default:
+        return false;
+    }
+
+    out->src_port = 0;
+    out->dst_port = 0;
+    if (out->protocol == IPPROTO_TCP || out->protocol == IPPROTO_UDP) {
+        // TCP and UDP both start with the source and destination ports.
+        struct udphdr *ports = l4;
+        if ((void *)(ports + 1) <= dissector->end) {
+            out->src_port = bpf_ntohs(ports->source);
+            out->dst_port = bpf_ntohs(ports->dest);
+        }
+    }
+    return true;
+}
+
+static __always_inline bool priority_lane_prefix_match(
+    const struct in6_addr *address,
+    const struct priority_lane_rule *rule
+) {
+    __u32 remaining = rule->prefix_len;
+    for (int i = 0; i < 4; i++) {
+        if (remaining == 0) return true;
+        __u32 mask = remaining >= 32 ? 0xFFFFFFFF : ~(0xFFFFFFFF >> remaining);
+        if ((address->in6_u.u6_addr32[i] ^ rule->prefix.in6_u.u6_addr32[i])
+            & bpf_htonl(mask)) {
+            return false;
+        }
+        remaining = remaining >= 32 ? remaining - 32 : 0;
+    }
+    return true;
+}
+
+static __always_inline bool priority_lane_port_match(
+    const struct priority_lane_packet *packet,
+    const struct priority_lane_rule *rule
+) {
+    if (rule->port_min == 0 && rule->port_max == 0) return true;
+    return (packet->src_port >= rule->port_min && packet->src_port <= rule->port_max)
+        || (packet->dst_port >= rule->port_min && packet->dst_port <= rule->port_max);
+}
+
+// Sets skb->mark to the first matching lane for a circuit with priority
+// lanes. `dissected` says whether `dissector` already located the IP header;
+// circuits without lanes never pay for a second dissection.
+static __always_inline void priority_lane_mark(
+    struct __sk_buff *skb,
+    struct tc_dissector_t *dissector,
+    bool dissected,
+    __u32 tc_handle,
+    __u32 upload
+) {
+    struct priority_lane_key key = {
+        .tc_handle = tc_handle,
+        .upload = upload,
+    };
+    struct priority_lane_rules *lanes = bpf_map_lookup_elem(&map_priority_lanes, &key);
+    if (!lanes || lanes->count == 0) return;
+
+    if (!dissected) {
+        if (!tc_dissector_new(skb, dissector)) return;
+        if (!tc_dissector_find_l3_offset(dissector)) return;
+        if (!tc_dissector_find_ip_header(dissector)) return;
+    }
+
+    struct priority_lane_packet packet = {0};
+    if (!priority_lane_parse(dissector, &packet)) return;
+    struct in6_addr *remote = upload ? &dissector->dst_ip : &dissector->src_ip;
+
+    for (__u32 i = 0; i < MAX_PRIORITY_LANE_RULES; i++) {
+        if (i >= lanes->count) break;
+        struct priority_lane_rule *rule = &lanes->rules[i];
+        if (rule->dscp_mask != 0 && !((rule->dscp_mask >> packet.dscp) & 1)) continue;
+        if (rule->protocol != 0 && rule->protocol != packet.protocol) continue;
+        if (!priority_lane_port_match(&packet, rule)) continue;
+        if (!priority_lane_prefix_match(remote, rule)) continue;
+        skb->mark = rule->mark;
+        return;
+    }
+}
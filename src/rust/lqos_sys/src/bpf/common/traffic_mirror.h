@@ -0,0 +1,201 @@
+#pragma once
+
+#include <linux/bpf.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_endian.h>
+#include <linux/if_ether.h>
+#include <linux/in.h>
+#include <linux/in6.h>
+#include <linux/ip.h>
+#include <linux/pkt_cls.h>
+#include <stdbool.h>
+#include "maximums.h"
+#include "dissector_tc.h"
+#include "lpm.h"
+
+// Traffic mirroring.
+//
+// lqosd loads the addresses and prefixes of active mirror sessions into
+// `map_mirror_prefixes`. The TC egress path clones every packet whose
+// source or destination matches to the mirror interface, tagging the clone
+// with MIRROR_MARK. `tc_mirror_encap`, attached to the mirror interface's
+// egress, truncates the tagged clones and adds the configured VLAN tag or
+// GRE header.
+
+#define MIRROR_MARK 0x4C514D52 // "LQMR"
+#define MIRROR_ENCAP_NONE 0
+#define MIRROR_ENCAP_VLAN 1
+#define MIRROR_ENCAP_GRE 2
+#define MIRROR_GRE_PROTO_TEB 0x6558
+
+struct mirror_config {
+    // Mirror interface; 0 when mirroring is off.
+    __u32 ifindex;
+    // Number of prefixes loaded; 0 skips the prefix lookup.
+    __u32 prefixes;
+    // Bytes kept from each clone, or 0 for whole packets.
+    __u32 snaplen;
+    __u16 vlan_id;
+    __u8 encap;
+    __u8 pad;
+    // GRE outer addresses, network order.
+    __u32 gre_src;
+    __u32 gre_dst;
+    __u8 src_mac[ETH_ALEN];
+    __u8 dst_mac[ETH_ALEN];
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_ARRAY);
+    __uint(max_entries, 1);
+    __type(key, __u32);
+    __type(value, struct mirror_config);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} map_mirror_config SEC(".maps");
+
+// Mirrored addresses and prefixes, keyed like `map_ip_to_cpu_and_tc`. The
+// value is the session slot.
+struct {
+    __uint(type, BPF_MAP_TYPE_LPM_TRIE);
+    __uint(max_entries, MIRROR_PREFIXES_MAX);
+    __type(key, struct ip_hash_key);
+    __type(value, __u32);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+    __uint(map_flags, BPF_F_NO_PREALLOC);
+} map_mirror_prefixes SEC(".maps");
+
+struct mirror_counter {
+    __u64 packets;
+    __u64 bytes;
+    // Clones that could not be sent or encapsulated.
+    __u64 errors;
+};
+
+// Per-session counters, indexed by session slot.
+struct {
+    __uint(type, BPF_MAP_TYPE_PERCPU_ARRAY);
+    __uint(max_entries, MIRROR_SESSIONS_MAX);
+    __type(key, __u32);
+    __type(value, struct mirror_counter);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} map_mirror_counters SEC(".maps");
+
+// Clones the packet to the mirror interface if either address belongs to
+// an active session. `dissected` says whether `dissector` already located
+// the IP header. The packet itself is not modified.
+static __always_inline void traffic_mirror(
+    struct __sk_buff *skb,
+    struct tc_dissector_t *dissector,
+    bool dissected
+) {
+    __u32 zero = 0;
+    struct mirror_config *config = bpf_map_lookup_elem(&map_mirror_config, &zero);
+    if (!config || config->ifindex == 0 || config->prefixes == 0) return;
+
+    if (!dissected) {
+        if (!tc_dissector_new(skb, dissector)) return;
+        if (!tc_dissector_find_l3_offset(dissector)) return;
+        if (!tc_dissector_find_ip_header(dissector)) return;
+    }
+
+    struct ip_hash_key key = {
+        .prefixlen = 128,
+        .address = dissector->src_ip,
+    };
+    __u32 *session = bpf_map_lookup_elem(&map_mirror_prefixes, &key);
+    if (!session) {
+        key.address = dissector->dst_ip;
+        session = bpf_map_lookup_elem(&map_mirror_prefixes, &key);
+    }
+    if (!session) return;
+
+    __u32 slot = *session;
+    struct mirror_counter *counter = bpf_map_lookup_elem(&map_mirror_counters, &slot);
+    __u32 len = skb->len;
+    __u32 mark = skb->mark;
+    skb->mark = MIRROR_MARK;
+    long err = bpf_clone_redirect(skb, config->ifindex, 0);
+    skb->mark = mark;
+    if (!counter) return;
+    if (err) {
+        counter->errors++;
+    } else {
+        counter->packets++;
+        counter->bytes += len;
+    }
+}
+
+static __always_inline __u16 mirror_ipv4_checksum(struct iphdr *iph) {
+    __u32 sum = 0;
+    __u16 *words = (__u16 *)iph;
+    for (int i = 0; i < sizeof(struct iphdr) / 2; i++) {
+        sum += words[i];
+    }
+    sum = (sum & 0xFFFF) + (sum >> 16);
+    sum = (sum & 0xFFFF) + (sum >> 16);
+    return ~sum;
+}
+
+// Wraps the clone in Ethernet + IPv4 + GRE (transparent Ethernet bridging),
+// keeping the original Ethernet header as the inner frame.
+static __always_inline bool mirror_gre_encap(
+    struct __sk_buff *skb,
+    struct mirror_config *config
+) {
+    struct ethhdr inner;
+    if (bpf_skb_load_bytes(skb, 0, &inner, sizeof(inner)) < 0) return false;
+
+    const __u32 room = sizeof(struct ethhdr) + sizeof(struct iphdr) + 4;
+    if (bpf_skb_adjust_room(skb, room, BPF_ADJ_ROOM_MAC, 0) < 0) return false;
+
+    struct ethhdr eth = {0};
+    __builtin_memcpy(eth.h_dest, config->dst_mac, ETH_ALEN);
+    __builtin_memcpy(eth.h_source, config->src_mac, ETH_ALEN);
+    eth.h_proto = bpf_htons(ETH_P_IP);
+
+    struct iphdr iph = {0};
+    iph.version = 4;
+    iph.ihl = 5;
+    iph.ttl = 64;
+    iph.protocol = IPPROTO_GRE;
+    iph.tot_len = bpf_htons(skb->len - sizeof(struct ethhdr));
+    iph.saddr = config->gre_src;
+    iph.daddr = config->gre_dst;
+    iph.check = mirror_ipv4_checksum(&iph);
+
+    // No checksum, key or sequence number.
+    __be16 gre[2] = { 0, bpf_htons(MIRROR_GRE_PROTO_TEB) };
+
+    __u32 offset = 0;
+    if (bpf_skb_store_bytes(skb, offset, &eth, sizeof(eth), 0) < 0) return false;
+    offset += sizeof(eth);
+    if (bpf_skb_store_bytes(skb, offset, &iph, sizeof(iph), 0) < 0) return false;
+    offset += sizeof(iph);
+    if (bpf_skb_store_bytes(skb, offset, gre, sizeof(gre), 0) < 0) return false;
+    offset += sizeof(gre);
+    if (bpf_skb_store_bytes(skb, offset, &inner, sizeof(inner), 0) < 0) return false;
+    return true;
+}
+
+// Egress of the mirror interface: finishes clones made by traffic_mirror()
+// and passes everything else untouched.
+static __always_inline int mirror_encap(struct __sk_buff *skb) {
+    if (skb->mark != MIRROR_MARK) return TC_ACT_OK;
+    skb->mark = 0;
+
+    __u32 zero = 0;
+    struct mirror_config *config = bpf_map_lookup_elem(&map_mirror_config, &zero);
+    if (!config) return TC_ACT_OK;
+
+    if (config->snaplen != 0 && skb->len > config->snaplen) {
+        if (bpf_skb_change_tail(skb, config->snaplen, 0) < 0) return TC_ACT_SHOT;
+    }
+    if (config->encap == MIRROR_ENCAP_VLAN) {
+        if (bpf_skb_vlan_push(skb, bpf_htons(ETH_P_8021Q), config->vlan_id) < 0) {
+            return TC_ACT_SHOT;
+        }
+    } else if (config->encap == MIRROR_ENCAP_GRE) {
+        if (!mirror_gre_encap(skb, config)) return TC_ACT_SHOT;
+    }
+    return TC_ACT_OK;
+}
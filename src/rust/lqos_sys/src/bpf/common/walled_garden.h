@@ -0,0 +1,228 @@
+#pragma once
+
+#include <linux/bpf.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_endian.h>
+#include <linux/if_ether.h>
+#include <linux/in.h>
+#include <linux/ip.h>
+#include <linux/tcp.h>
+#include <stdbool.h>
+#include "maximums.h"
+#include "dissector.h"
+#include "lpm.h"
+
+// Walled garden for suspended circuits.
+//
+// A circuit whose `ip_hash_info.access_policy` is not ACCESS_POLICY_OPEN may
+// only exchange traffic with the allowlisted prefixes, the portal and (if
+// enabled) DNS servers. Everything else is dropped in XDP. Under
+// ACCESS_POLICY_PORTAL, IPv4 HTTP is instead DNATed to the portal, and the
+// portal's replies are rewritten back so the browser sees the site it asked
+// for and follows the portal's redirect.
+
+#define ACCESS_POLICY_OPEN 0
+#define ACCESS_POLICY_BLOCK 1
+#define ACCESS_POLICY_PORTAL 2
+
+struct walled_garden_config {
+    // Portal address, or 0 for no portal (PORTAL then behaves like BLOCK).
+    __be32 portal_ipv4;
+    __be16 portal_port;
+    // Non-zero allows port 53 to any server.
+    __u8 allow_dns;
+    __u8 pad;
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_ARRAY);
+    __uint(max_entries, 1);
+    __type(key, __u32);
+    __type(value, struct walled_garden_config);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} walled_garden_config SEC(".maps");
+
+// Allowlisted remote prefixes, keyed like `map_ip_to_cpu_and_tc`.
+struct {
+    __uint(type, BPF_MAP_TYPE_LPM_TRIE);
+    __uint(max_entries, WALLED_GARDEN_PREFIXES_MAX);
+    __type(key, struct ip_hash_key);
+    __type(value, __u32);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+    __uint(map_flags, BPF_F_NO_PREALLOC);
+} map_walled_garden_allow SEC(".maps");
+
+// Portal redirects in progress, keyed by the subscriber's end of the
+// connection. The value is the destination the subscriber asked for.
+struct walled_garden_nat_key {
+    __be32 client_ip;
+    __be16 client_port;
+    __u16 pad;
+};
+
+struct walled_garden_nat_value {
+    __be32 original_ip;
+    __be16 original_port;
+    __u16 pad;
+};
+
+struct {
+    __uint(type, BPF_MAP_TYPE_LRU_HASH);
+    __uint(max_entries, WALLED_GARDEN_NAT_MAX);
+    __type(key, struct walled_garden_nat_key);
+    __type(value, struct walled_garden_nat_value);
+    __uint(pinning, LIBBPF_PIN_BY_NAME);
+} walled_garden_nat SEC(".maps");
+
+static __always_inline __u16 walled_garden_csum_fold(__u32 csum) {
+    csum = (csum & 0xFFFF) + (csum >> 16);
+    csum = (csum & 0xFFFF) + (csum >> 16);
+    return (__u16)~csum;
+}
+
+// RFC 1624 incremental checksum update for a changed 32-bit word.
+static __always_inline void walled_garden_csum_replace4(
+    __sum16 *sum,
+    __be32 from,
+    __be32 to
+) {
+    __u32 csum = (__u16)~(*sum);
+    csum += (~from >> 16) & 0xFFFF;
+    csum += ~from & 0xFFFF;
+    csum += to >> 16;
+    csum += to & 0xFFFF;
+    *sum = walled_garden_csum_fold(csum);
+}
+
+// RFC 1624 incremental checksum update for a changed 16-bit word.
+static __always_inline void walled_garden_csum_replace2(
+    __sum16 *sum,
+    __be16 from,
+    __be16 to
+) {
+    __u32 csum = (__u16)~(*sum);
+    csum += (__u16)~from;
+    csum += to;
+    *sum = walled_garden_csum_fold(csum);
+}
+
+static __always_inline struct tcphdr *walled_garden_tcp(
+    struct dissector_t *dissector
+) {
+    struct iphdr *iph = dissector->ip_header.iph;
+    if ((void *)(iph + 1) > dissector->end) return NULL;
+    struct tcphdr *tcp = get_tcp_header(dissector);
+    if (tcp == NULL || (void *)(tcp + 1) > dissector->end) return NULL;
+    return tcp;
+}
+
+// Subscriber -> Internet HTTP: remember where it was going and send it to
+// the portal instead.
+static __always_inline bool walled_garden_redirect(
+    struct dissector_t *dissector,
+    struct walled_garden_config *config
+) {
+    struct tcphdr *tcp = walled_garden_tcp(dissector);
+    if (!tcp) return false;
+    struct iphdr *iph = dissector->ip_header.iph;
+
+    struct walled_garden_nat_key key = {
+        .client_ip = iph->saddr,
+        .client_port = tcp->source,
+        .pad = 0,
+    };
+    struct walled_garden_nat_value value = {
+        .original_ip = iph->daddr,
+        .original_port = tcp->dest,
+        .pad = 0,
+    };
+    if (bpf_map_update_elem(&walled_garden_nat, &key, &value, BPF_ANY) != 0) {
+        return false;
+    }
+
+    walled_garden_csum_replace4(&iph->check, iph->daddr, config->portal_ipv4);
+    walled_garden_csum_replace4(&tcp->check, iph->daddr, config->portal_ipv4);
+    walled_garden_csum_replace2(&tcp->check, tcp->dest, config->portal_port);
+    iph->daddr = config->portal_ipv4;
+    tcp->dest = config->portal_port;
+    return true;
+}
+
+// Portal -> subscriber: make the reply appear to come from the original
+// destination. Returns false if this isn't a redirected connection.
+static __always_inline bool walled_garden_restore(
+    struct dissector_t *dissector
+) {
+    struct tcphdr *tcp = walled_garden_tcp(dissector);
+    if (!tcp) return false;
+    struct iphdr *iph = dissector->ip_header.iph;
+
+    struct walled_garden_nat_key key = {
+        .client_ip = iph->daddr,
+        .client_port = tcp->dest,
+        .pad = 0,
+    };
+    struct walled_garden_nat_value *value = bpf_map_lookup_elem(&walled_garden_nat, &key);
+    if (!value) return false;
+
+    walled_garden_csum_replace4(&iph->check, iph->saddr, value->original_ip);
+    walled_garden_csum_replace4(&tcp->check, iph->saddr, value->original_ip);
+    walled_garden_csum_replace2(&tcp->check, tcp->source, value->original_port);
+    iph->saddr = value->original_ip;
+    tcp->source = value->original_port;
+    return true;
+}
+
+// Applies a circuit's access policy to a packet. Returns XDP_PASS to carry
+// on with normal processing (the packet may have been rewritten), or
+// XDP_DROP. `effective_direction` is 1 for traffic from the Internet (the
+// subscriber is the destination) and 2 for traffic to it.
+static __always_inline int walled_garden_enforce(
+    struct dissector_t *dissector,
+    u_int8_t effective_direction,
+    __u32 access_policy
+) {
+    if (access_policy == ACCESS_POLICY_OPEN) return XDP_PASS;
+
+    __u32 zero = 0;
+    struct walled_garden_config *config = bpf_map_lookup_elem(&walled_garden_config, &zero);
+    bool upload = effective_direction == 2;
+    bool tcp = dissector->ip_protocol == IPPROTO_TCP;
+    bool has_ports = tcp || dissector->ip_protocol == IPPROTO_UDP;
+    bool portal = config && config->portal_ipv4 != 0 && dissector->eth_type == ETH_P_IP;
+
+    // Dissector ports are in network byte order.
+    __be16 remote_port = upload ? dissector->dst_port : dissector->src_port;
+    if (portal) {
+        struct iphdr *iph = dissector->ip_header.iph;
+        if ((void *)(iph + 1) > dissector->end) return XDP_DROP;
+        __be32 remote_ip = upload ? iph->daddr : iph->saddr;
+        if (remote_ip == config->portal_ipv4) {
+            if (!upload && tcp && remote_port == config->portal_port) {
+                // Replies to a redirect go back out as the original site.
+                walled_garden_restore(dissector);
+            }
+            return XDP_PASS;
+        }
+    }
+
+    if (config && config->allow_dns && has_ports && remote_port == bpf_htons(53)) {
+        return XDP_PASS;
+    }
+
+    struct ip_hash_key key = {
+        .prefixlen = 128,
+        .address = upload ? dissector->dst_ip : dissector->src_ip,
+    };
+    if (bpf_map_lookup_elem(&map_walled_garden_allow, &key)) {
+        return XDP_PASS;
+    }
+
+    if (access_policy == ACCESS_POLICY_PORTAL && portal && upload && tcp
+        && dissector->dst_port == bpf_htons(80)) {
+        if (walled_garden_redirect(dissector, config)) {
+            return XDP_PASS;
+        }
+    }
+    return XDP_DROP;
+}
@@ -26,6 +26,12 @@
 #include "common/bifrost.h"
 #include "common/heimdall.h"
 #include "common/flows.h"
+#include "common/priority_lanes.h"
+#include "common/dscp_remark.h"
+#include "common/traffic_mirror.h"
+#include "common/walled_garden.h"
+#include "common/cgnat.h"
+#include "common/flood_detection.h"
 
 //#define VERBOSE 1
 //#define TRACING 1
@@ -37,7 +43,10 @@
   * Dissect the packet to find VLANs and L3 offset
       * If VLAN redirection is enabled, change VLAN tags
       * to swap ingress/egress VLANs.
+  * Resolve CGNAT port blocks to the subscriber's address
   * Perform LPM lookup to determine CPU destination
+  * Enforce the walled garden for suspended circuits
+  * Count flood-detection anomalies and apply flood mitigations
   * Track traffic totals
   * Perform CPU redirection
 3. TC (ingress) starts
@@ -90,6 +99,15 @@ __u32 stick_offset = 0;
 #define round_up(x, y) ((((x) - 1) | __round_mask(x, y)) + 1)
 #define ctx_ptr(ctx, mem) (void *)(unsigned long)ctx->mem
 
+// Is this TC egress packet heading to the Internet (upload)? Interfaces
+// facing the Internet only egress upload traffic; on a stick, the VLAN decides.
+static __always_inline __u32 tc_egress_is_upload(struct __sk_buff *skb) {
+    if (direction == 3) {
+        return bpf_htons(skb->vlan_tci) == internet_vlan;
+    }
+    return direction == 1;
+}
+
 // Structure for passing metadata from XDP to TC
 struct metadata_pass_t {
     __u32 tc_handle; // The encoded TC handle
@@ -160,10 +178,16 @@ int xdp_prog(struct xdp_md *ctx)
     bpf_debug("(XDP) Spotted VLAN: %u", dissector.current_vlan);
 #endif
 
+    // Host key used for mapping and throughput tracking (customer-side IP).
+    // Behind a CGNAT, the shared public address is resolved to the
+    // subscriber by port block.
+    struct in6_addr host_key = (effective_direction == 1) ? dissector.dst_ip : dissector.src_ip;
+    cgnat_resolve_xdp(&dissector, effective_direction, &host_key);
+
     // Per-Flow RTT Tracking (also resolves mapping using flowbee first, falling
     // back to hotcache/LPM for new flows).
     struct ip_hash_info ip_info = {0};
-    track_flows(&dissector, effective_direction, &ip_info);
+    bool new_flow = track_flows(&dissector, effective_direction, &host_key, &ip_info);
 
     // Find the desired TC handle and CPU target
     __u32 tc_handle = ip_info.tc_handle;
@@ -171,8 +195,23 @@ int xdp_prog(struct xdp_md *ctx)
     __u64 circuit_id = ip_info.circuit_id;
     __u64 device_id = ip_info.device_id;
 
-    // Host key used for throughput tracking (customer-side IP).
-    struct in6_addr host_key = (effective_direction == 1) ? dissector.dst_ip : dissector.src_ip;
+    // Suspended circuits only reach the walled garden. This may rewrite
+    // the packet (portal redirects), but never moves or resizes it.
+    if (walled_garden_enforce(&dissector, effective_direction, ip_info.access_policy) == XDP_DROP) {
+        return XDP_DROP;
+    }
+
+    // Per-circuit flood counters; a mitigated direction may be capped or
+    // dropped here.
+    if (flood_detection_check(
+        &dissector,
+        effective_direction,
+        circuit_id,
+        ctx->data_end - ctx->data,
+        new_flow
+    ) == XDP_DROP) {
+        return XDP_DROP;
+    }
 
     // Update the traffic tracking buffers
     track_traffic(
@@ -325,6 +364,24 @@ int tc_iphash_to_cpu(struct __sk_buff *skb)
                 if (major != 0) {
                     skb->queue_mapping = major;
                 }
+                {
+                    // Only dissects when the circuit has a re-marking
+                    // policy or priority lanes.
+                    __u32 upload = tc_egress_is_upload(skb);
+                    struct tc_dissector_t remark_dissector = {0};
+                    dscp_remark(skb, &remark_dissector, false, skb->priority, upload);
+                    struct tc_dissector_t lane_dissector = {0};
+                    priority_lane_mark(
+                        skb,
+                        &lane_dissector,
+                        false,
+                        skb->priority,
+                        upload
+                    );
+                    // Only dissects while a mirror session is active.
+                    struct tc_dissector_t mirror_dissector = {0};
+                    traffic_mirror(skb, &mirror_dissector, false);
+                }
                 #ifdef TRACING
                 {
                     __u64 now = bpf_ktime_get_ns();
@@ -378,6 +435,23 @@ int tc_iphash_to_cpu(struct __sk_buff *skb)
         if (major != 0) {
             skb->queue_mapping = major;
         }
+        __u32 upload = tc_egress_is_upload(skb);
+        // Re-marking rewrites the packet, so the lanes dissect it afresh.
+        bool remarked = dscp_remark(
+            skb,
+            &dissector,
+            true,
+            ip_info.tc_handle,
+            upload
+        );
+        priority_lane_mark(
+            skb,
+            &dissector,
+            !remarked,
+            ip_info.tc_handle,
+            upload
+        );
+        traffic_mirror(skb, &dissector, !remarked);
         #ifdef TRACING
         {
             __u64 now = bpf_ktime_get_ns();
@@ -390,6 +464,7 @@ int tc_iphash_to_cpu(struct __sk_buff *skb)
 #ifdef VERBOSE
         bpf_debug("(TC) didn't map anything");
 #endif
+        traffic_mirror(skb, &dissector, true);
         #ifdef TRACING
         {
             __u64 now = bpf_ktime_get_ns();
@@ -402,6 +477,13 @@ int tc_iphash_to_cpu(struct __sk_buff *skb)
     return TC_ACT_OK;
 }
 
+// TC-Egress entry point for the traffic mirror interface.
+SEC("tc")
+int tc_mirror_encap(struct __sk_buff *skb)
+{
+    return mirror_encap(skb);
+}
+
 // Helper function to call the bpf_redirect function and note
 // errors from the TC-egress context.
 static __always_inline long do_tc_redirect(__u32 target) {
@@ -5,6 +5,13 @@ struct lqos_kern * lqos_kern_open() {
     return lqos_kern__open();
 }
 
+// Opens the skeleton with its pinned maps under `pin_root_path` instead of
+// /sys/fs/bpf, so tests can load a private copy alongside a live instance.
+struct lqos_kern * lqos_kern_open_pinned_at(const char * pin_root_path) {
+    DECLARE_LIBBPF_OPTS(bpf_object_open_opts, opts, .pin_root_path = pin_root_path);
+    return lqos_kern__open_opts(&opts);
+}
+
 int lqos_kern_load(struct lqos_kern * skel) {
     return lqos_kern__load(skel);
 }
@@ -141,6 +148,55 @@ out:
 	return err;
 }
 
+/* Attaches the traffic mirror encapsulation program to the egress of the
+ * mirror interface. */
+int tc_attach_mirror_egress(int ifindex, bool verbose, struct lqos_kern *obj)
+{
+	int err = 0;
+	int fd;
+	DECLARE_LIBBPF_OPTS(bpf_tc_hook, hook, .attach_point = BPF_TC_EGRESS);
+	DECLARE_LIBBPF_OPTS(bpf_tc_opts, attach_egress);
+
+	fd = bpf_program__fd(obj->progs.tc_mirror_encap);
+	if (fd < 0) {
+		fprintf(stderr, "Couldn't find mirror egress program\n");
+		err = -ENOENT;
+		goto out;
+	}
+	attach_egress.prog_fd = fd;
+
+	hook.ifindex = ifindex;
+
+	err = bpf_tc_hook_create(&hook);
+	if (err && err != -EEXIST) {
+		fprintf(stderr, "Couldn't create TC-BPF hook for "
+			"ifindex %d (err:%d)\n", ifindex, err);
+		goto out;
+	}
+	if (verbose && err == -EEXIST) {
+		printf("Success: TC-BPF hook already existed "
+		       "(Ignore: \"libbpf: Kernel error message\")\n");
+	}
+
+	hook.attach_point = BPF_TC_EGRESS;
+	attach_egress.flags    = BPF_TC_F_REPLACE;
+	attach_egress.handle   = EGRESS_HANDLE;
+	attach_egress.priority = EGRESS_PRIORITY;
+	err = bpf_tc_attach(&hook, &attach_egress);
+	if (err) {
+		fprintf(stderr, "Couldn't attach mirror egress program to "
+			"ifindex %d (err:%d)\n", hook.ifindex, err);
+		goto out;
+	}
+
+	if (verbose) {
+		printf("Attached TC-BPF program id:%d\n",
+		       attach_egress.prog_id);
+	}
+out:
+	return err;
+}
+
 int teardown_hook_ingress(int ifindex, const char * ifname, bool verbose)
 {
 	DECLARE_LIBBPF_OPTS(bpf_tc_hook, hook,
@@ -5,8 +5,10 @@
 #include <bpf/bpf.h>
 
 extern struct lqos_kern * lqos_kern_open();
+extern struct lqos_kern * lqos_kern_open_pinned_at(const char * pin_root_path);
 extern int lqos_kern_load(struct lqos_kern * skel);
 extern int tc_attach_egress(int ifindex, bool verbose, struct lqos_kern *obj);
+extern int tc_attach_mirror_egress(int ifindex, bool verbose, struct lqos_kern *obj);
 extern int tc_detach_egress(int ifindex, bool verbose, bool flush_hook, const char * ifname);
 extern int tc_attach_ingress(int ifindex, bool verbose, struct lqos_kern *obj);
 extern int tc_detach_ingress(int ifindex, bool verbose, bool flush_hook, const char * ifname);
@@ -0,0 +1,252 @@
+//! Userspace control of DSCP re-marking in the TC egress path.
+//!
+//! Policies live in a fixed array, indexed like
+//! `DscpRemarkingConfig::policies`. Circuit directions are assigned a policy
+//! by TC handle, and each assignment counts the packets it re-marked. Rules
+//! that match on ASNs refer to a group bit, which the remote address must
+//! carry in `map_dscp_asn_prefixes`.
+
+use crate::bpf_map::BpfMap;
+use crate::ip_mapping::IpHashKey;
+use anyhow::{Error, Result};
+use lqos_bus::TcHandle;
+use lqos_config::{
+    DscpMarkRule, DscpPolicyMode, DscpRemarkingConfig, MAX_DSCP_POLICIES, MAX_DSCP_RULES_PER_POLICY,
+};
+use lqos_utils::XdpIpAddress;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+
+const DSCP_POLICIES_PATH: &str = "/sys/fs/bpf/map_dscp_policies";
+const DSCP_CIRCUITS_PATH: &str = "/sys/fs/bpf/map_dscp_circuits";
+const DSCP_ASN_PREFIXES_PATH: &str = "/sys/fs/bpf/map_dscp_asn_prefixes";
+
+/// Must match `DSCP_ASN_PREFIXES_MAX` in `maximums.h`.
+pub const MAX_DSCP_ASN_PREFIXES: usize = 131_072;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct DscpRuleC {
+    asn_groups: u64,
+    port_min: u16,
+    port_max: u16,
+    protocol: u8,
+    dscp: u8,
+    pad: [u8; 2],
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct DscpPolicyC {
+    mode: u8,
+    wash_unmatched: u8,
+    count: u8,
+    uses_asns: u8,
+    pad: u32,
+    rules: [DscpRuleC; MAX_DSCP_RULES_PER_POLICY],
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct DscpCircuitKey {
+    tc_handle: u32,
+    upload: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct DscpCircuitC {
+    policy: u32,
+    pad: u32,
+    remarked: u64,
+}
+
+impl DscpRuleC {
+    fn new(rule: &DscpMarkRule) -> Self {
+        Self {
+            asn_groups: rule.asn_group.map_or(0, |group| 1u64 << group),
+            port_min: rule.port_min,
+            port_max: rule.port_max,
+            protocol: rule.protocol,
+            dscp: rule.dscp,
+            pad: [0; 2],
+        }
+    }
+}
+
+fn encode_policy(config: &DscpRemarkingConfig, index: usize) -> DscpPolicyC {
+    let Some(policy) = config.policies.get(index) else {
+        return DscpPolicyC::default();
+    };
+    let rules = config.match_rules(index);
+    let mut value = DscpPolicyC {
+        mode: match policy.mode {
+            DscpPolicyMode::Trust => 0,
+            DscpPolicyMode::Wash => 1,
+            DscpPolicyMode::Rules => 2,
+        },
+        wash_unmatched: u8::from(policy.wash_unmatched),
+        count: rules.len().min(MAX_DSCP_RULES_PER_POLICY) as u8,
+        uses_asns: u8::from(rules.iter().any(|rule| rule.asn_group.is_some())),
+        ..Default::default()
+    };
+    for (slot, rule) in value.rules.iter_mut().zip(rules.iter()) {
+        *slot = DscpRuleC::new(rule);
+    }
+    value
+}
+
+/// Programs every policy slot from the configuration. Unused slots are
+/// reset to `trust`.
+pub fn set_dscp_policies(config: &DscpRemarkingConfig) -> Result<()> {
+    if config.policies.len() > MAX_DSCP_POLICIES {
+        return Err(Error::msg(format!(
+            "{} DSCP policies exceed the limit of {MAX_DSCP_POLICIES}",
+            config.policies.len()
+        )));
+    }
+    let mut bpf_map = BpfMap::<u32, DscpPolicyC>::from_path(DSCP_POLICIES_PATH)?;
+    for index in 0..MAX_DSCP_POLICIES {
+        bpf_map.insert_or_update(&mut (index as u32), &mut encode_policy(config, index))?;
+    }
+    Ok(())
+}
+
+fn prefix_key(ip: IpAddr, prefix: u8) -> IpHashKey {
+    let prefixlen = match ip {
+        IpAddr::V4(_) => u32::from(prefix.min(32)) + 96,
+        IpAddr::V6(_) => u32::from(prefix.min(128)),
+    };
+    IpHashKey {
+        prefixlen,
+        address: XdpIpAddress::from_ip(ip).0,
+    }
+}
+
+/// Replaces the remote prefixes used by ASN rules. Each entry is a network,
+/// its prefix length and the mask of ASN groups it belongs to.
+pub fn set_dscp_asn_prefixes(prefixes: &[(IpAddr, u8, u64)]) -> Result<()> {
+    if prefixes.len() > MAX_DSCP_ASN_PREFIXES {
+        return Err(Error::msg(format!(
+            "{} DSCP ASN prefixes exceed the limit of {MAX_DSCP_ASN_PREFIXES}",
+            prefixes.len()
+        )));
+    }
+    let mut bpf_map = BpfMap::<IpHashKey, u64>::from_path(DSCP_ASN_PREFIXES_PATH)?;
+    bpf_map.clear()?;
+    for &(ip, prefix, mut groups) in prefixes {
+        bpf_map.insert_or_update(&mut prefix_key(ip, prefix), &mut groups)?;
+    }
+    Ok(())
+}
+
+/// Brings the circuit policy assignments in line with `circuits`, keyed by
+/// TC handle and upload flag. Unchanged assignments keep their counters.
+pub fn set_dscp_circuits(circuits: &HashMap<(TcHandle, bool), u32>) -> Result<()> {
+    let mut bpf_map = BpfMap::<DscpCircuitKey, DscpCircuitC>::from_path(DSCP_CIRCUITS_PATH)?;
+    let mut unchanged = HashSet::new();
+    for (mut key, value) in bpf_map.dump_vec() {
+        let wanted = circuits.get(&(TcHandle::from_u32(key.tc_handle), key.upload != 0));
+        match wanted {
+            Some(policy) if *policy == value.policy => {
+                unchanged.insert((key.tc_handle, key.upload));
+            }
+            _ => bpf_map.delete(&mut key)?,
+        }
+    }
+    for ((tc_handle, upload), policy) in circuits {
+        let mut key = DscpCircuitKey {
+            tc_handle: tc_handle.as_u32(),
+            upload: u32::from(*upload),
+        };
+        if unchanged.contains(&(key.tc_handle, key.upload)) {
+            continue;
+        }
+        bpf_map.insert_or_update(
+            &mut key,
+            &mut DscpCircuitC {
+                policy: *policy,
+                ..Default::default()
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads how many packets each assigned circuit direction has re-marked.
+pub fn dscp_remarked_counts() -> Result<Vec<(TcHandle, bool, u64)>> {
+    let bpf_map = BpfMap::<DscpCircuitKey, DscpCircuitC>::from_path(DSCP_CIRCUITS_PATH)?;
+    Ok(bpf_map
+        .dump_vec()
+        .into_iter()
+        .map(|(key, value)| {
+            (
+                TcHandle::from_u32(key.tc_handle),
+                key.upload != 0,
+                value.remarked,
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lqos_config::{DscpMarkRuleConfig, DscpPolicyConfig};
+
+    #[test]
+    fn layouts_match_the_c_structs() {
+        assert_eq!(std::mem::size_of::<DscpRuleC>(), 16);
+        assert_eq!(
+            std::mem::size_of::<DscpPolicyC>(),
+            8 + 16 * MAX_DSCP_RULES_PER_POLICY
+        );
+        assert_eq!(std::mem::size_of::<DscpCircuitC>(), 16);
+    }
+
+    #[test]
+    fn policies_are_encoded_for_the_dataplane() {
+        let config = DscpRemarkingConfig {
+            enabled: true,
+            default_policy: String::new(),
+            policies: vec![
+                DscpPolicyConfig {
+                    name: "wash".to_string(),
+                    mode: DscpPolicyMode::Wash,
+                    ..Default::default()
+                },
+                DscpPolicyConfig {
+                    name: "rules".to_string(),
+                    mode: DscpPolicyMode::Rules,
+                    wash_unmatched: true,
+                    rules: vec![
+                        DscpMarkRuleConfig {
+                            dscp: "AF41".to_string(),
+                            ports: vec!["udp:3478-3481".to_string()],
+                            asns: Vec::new(),
+                        },
+                        DscpMarkRuleConfig {
+                            dscp: "CS1".to_string(),
+                            ports: Vec::new(),
+                            asns: vec![32934],
+                        },
+                    ],
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let wash = encode_policy(&config, 0);
+        assert_eq!((wash.mode, wash.count), (1, 0));
+
+        let rules = encode_policy(&config, 1);
+        assert_eq!((rules.mode, rules.count, rules.uses_asns), (2, 2, 1));
+        assert_eq!(rules.wash_unmatched, 1);
+        assert_eq!(rules.rules[0].protocol, 17);
+        assert_eq!(rules.rules[0].dscp, 34);
+        assert_eq!(rules.rules[1].asn_groups, 1);
+        assert_eq!(rules.rules[1].dscp, 8);
+
+        assert_eq!(encode_policy(&config, 2), DscpPolicyC::default());
+    }
+}
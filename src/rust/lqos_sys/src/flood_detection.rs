@@ -0,0 +1,347 @@
+//! Userspace side of per-circuit flood detection.
+//!
+//! XDP counts per-circuit anomaly counters into the per-CPU
+//! `map_flood_counters`; this module sums them and estimates how many
+//! distinct remotes each circuit reached since the previous read.
+//! `map_flood_mitigations` holds the directions that are capped or dropped.
+
+use crate::bpf_map::BpfMap;
+use crate::lqos_kernel::bpf::libbpf_num_possible_cpus;
+use anyhow::{Error, Result};
+use libbpf_sys::{bpf_map_get_next_key, bpf_map_lookup_elem, bpf_obj_get};
+use lqos_utils::units::DownUpOrder;
+use nix::libc::close;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CString, c_void};
+use std::mem::size_of;
+use std::ptr::null_mut;
+
+const BPF_FS: &str = "/sys/fs/bpf";
+
+/// Must match `FLOOD_MITIGATIONS_MAX` in `maximums.h`.
+pub const MAX_FLOOD_MITIGATIONS: usize = 4096;
+
+/// Must match `FLOOD_REMOTE_SKETCH_WORDS` in `flood_detection.h`.
+const SKETCH_WORDS: usize = 4;
+const SKETCH_BITS: u32 = SKETCH_WORDS as u32 * 64;
+
+/// Rust mirror of `struct flood_control`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct FloodControlC {
+    enabled: u32,
+    sketch_epoch: u32,
+    mitigations: u32,
+    pad: u32,
+}
+
+/// Rust mirror of `struct flood_counter`. Arrays are [download, upload].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct FloodCounterC {
+    packets: [u64; 2],
+    tcp_packets: [u64; 2],
+    syn_packets: [u64; 2],
+    new_flows: [u64; 2],
+    small_packets: [u64; 2],
+    remotes: [[u64; SKETCH_WORDS]; 2],
+    last_seen: u64,
+    sketch_epoch: u32,
+    pad: u32,
+}
+
+/// Rust mirror of `struct flood_mitigation`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct FloodMitigationC {
+    action: [u8; 2],
+    pad: u16,
+    pps_cap: [u32; 2],
+    pad1: u32,
+    window_start: [u64; 2],
+    window_packets: [u64; 2],
+    dropped_packets: [u64; 2],
+}
+
+/// Per-circuit flood counters, summed over every CPU. Packet counters keep
+/// incrementing; `distinct_remotes` covers the time since the previous read.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FloodCounters {
+    /// Hashed circuit identifier.
+    pub circuit_hash: u64,
+    /// All packets.
+    pub packets: DownUpOrder<u64>,
+    /// TCP packets.
+    pub tcp_packets: DownUpOrder<u64>,
+    /// TCP SYNs without ACK.
+    pub syn_packets: DownUpOrder<u64>,
+    /// Packets that started a new TCP, UDP or ICMP flow.
+    pub new_flows: DownUpOrder<u64>,
+    /// Packets of 128 bytes or less.
+    pub small_packets: DownUpOrder<u64>,
+    /// Estimated distinct remote addresses since the previous read.
+    pub distinct_remotes: DownUpOrder<u64>,
+    /// Time last seen, in nanoseconds since kernel boot.
+    pub last_seen: u64,
+}
+
+/// What the dataplane does with one direction of a mitigated circuit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloodAction {
+    /// Pass normally.
+    #[default]
+    None,
+    /// Pass at most this many packets per second.
+    PpsCap(u32),
+    /// Drop everything.
+    Drop,
+}
+
+impl FloodAction {
+    fn dataplane_value(self) -> (u8, u32) {
+        match self {
+            Self::None => (0, 0),
+            Self::PpsCap(cap) => (1, cap),
+            Self::Drop => (2, 0),
+        }
+    }
+}
+
+/// A circuit whose traffic is capped or dropped in one or both directions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FloodMitigation {
+    /// Hashed circuit identifier.
+    pub circuit_hash: u64,
+    /// Action for download (towards the subscriber).
+    pub download: FloodAction,
+    /// Action for upload (from the subscriber).
+    pub upload: FloodAction,
+}
+
+/// Linear-counting estimate of the distinct remotes marked in a sketch.
+fn estimate_distinct(sketch: &[u64; SKETCH_WORDS]) -> u64 {
+    let bits = f64::from(SKETCH_BITS);
+    let zeros = SKETCH_BITS - sketch.iter().map(|word| word.count_ones()).sum::<u32>();
+    if zeros == 0 {
+        // Saturated: report the most the sketch can tell apart.
+        return (bits * bits.ln()).round() as u64;
+    }
+    (-bits * (f64::from(zeros) / bits).ln()).round() as u64
+}
+
+/// Sums the per-CPU values of one circuit. Sketches from CPUs that haven't
+/// seen the circuit since `epoch` began are stale and skipped.
+fn sum_per_cpu(circuit_hash: u64, values: &[FloodCounterC], epoch: u32) -> FloodCounters {
+    let mut sketches = [[0u64; SKETCH_WORDS]; 2];
+    let mut total = FloodCounterC::default();
+    for value in values {
+        for (dir, sketch) in sketches.iter_mut().enumerate() {
+            total.packets[dir] += value.packets[dir];
+            total.tcp_packets[dir] += value.tcp_packets[dir];
+            total.syn_packets[dir] += value.syn_packets[dir];
+            total.new_flows[dir] += value.new_flows[dir];
+            total.small_packets[dir] += value.small_packets[dir];
+            if value.sketch_epoch == epoch {
+                for (word, remotes) in sketch.iter_mut().zip(value.remotes[dir]) {
+                    *word |= remotes;
+                }
+            }
+        }
+        total.last_seen = total.last_seen.max(value.last_seen);
+    }
+    let pair = |values: [u64; 2]| DownUpOrder::new(values[0], values[1]);
+    FloodCounters {
+        circuit_hash,
+        packets: pair(total.packets),
+        tcp_packets: pair(total.tcp_packets),
+        syn_packets: pair(total.syn_packets),
+        new_flows: pair(total.new_flows),
+        small_packets: pair(total.small_packets),
+        distinct_remotes: DownUpOrder::new(
+            estimate_distinct(&sketches[0]),
+            estimate_distinct(&sketches[1]),
+        ),
+        last_seen: total.last_seen,
+    }
+}
+
+fn update_control(change: impl FnOnce(&mut FloodControlC)) -> Result<FloodControlC> {
+    let mut map = BpfMap::<u32, FloodControlC>::from_path(&format!("{BPF_FS}/flood_control"))?;
+    let mut control = map.lookup(&mut 0)?.unwrap_or_default();
+    change(&mut control);
+    map.insert_or_update(&mut 0, &mut control)?;
+    Ok(control)
+}
+
+/// Turns per-circuit counting on or off.
+pub fn set_flood_detection_enabled(enabled: bool) -> Result<()> {
+    update_control(|control| control.enabled = u32::from(enabled))?;
+    Ok(())
+}
+
+/// Reads every circuit's flood counters and starts a new distinct-remote
+/// interval.
+pub fn read_flood_counters() -> Result<Vec<FloodCounters>> {
+    let path = format!("{BPF_FS}/map_flood_counters");
+    let path_c = CString::new(path.clone())?;
+    let epoch = update_control(|_| {})?.sketch_epoch;
+
+    let cpu_count = unsafe { libbpf_num_possible_cpus() };
+    if cpu_count <= 0 {
+        return Err(Error::msg(format!(
+            "Unable to determine CPU count for {path}: libbpf_num_possible_cpus returned {cpu_count}"
+        )));
+    }
+    let fd = unsafe { bpf_obj_get(path_c.as_ptr()) };
+    if fd < 0 {
+        return Err(Error::msg(format!("Unable to open BPF map '{path}'")));
+    }
+
+    // Per-CPU values come back as one 8-byte-aligned value per CPU.
+    let stride = size_of::<FloodCounterC>().div_ceil(8) * 8;
+    let mut buffer = vec![0u8; stride * cpu_count as usize];
+    let mut values = vec![FloodCounterC::default(); cpu_count as usize];
+    let mut result = Vec::new();
+    let mut key = 0u64;
+    let mut prev_key: *mut u64 = null_mut();
+    unsafe {
+        while bpf_map_get_next_key(
+            fd,
+            prev_key as *mut c_void,
+            &mut key as *mut u64 as *mut c_void,
+        ) == 0
+        {
+            if bpf_map_lookup_elem(
+                fd,
+                &mut key as *mut u64 as *mut c_void,
+                buffer.as_mut_ptr() as *mut c_void,
+            ) == 0
+            {
+                for (cpu, value) in values.iter_mut().enumerate() {
+                    *value = std::ptr::read_unaligned(
+                        buffer.as_ptr().add(cpu * stride) as *const FloodCounterC
+                    );
+                }
+                result.push(sum_per_cpu(key, &values, epoch));
+            }
+            prev_key = &mut key;
+        }
+        close(fd);
+    }
+
+    update_control(|control| control.sketch_epoch = epoch.wrapping_add(1))?;
+    Ok(result)
+}
+
+/// Removes the counters of circuits that have gone quiet.
+pub fn expire_flood_counters(mut circuits: Vec<u64>) -> Result<()> {
+    if circuits.is_empty() {
+        return Ok(());
+    }
+    let mut map = BpfMap::<u64, FloodCounterC>::from_path(&format!("{BPF_FS}/map_flood_counters"))?;
+    map.clear_bulk_keys(&mut circuits)?;
+    Ok(())
+}
+
+/// Replaces the loaded mitigations. Unchanged entries keep their drop
+/// counters and rate windows.
+pub fn set_flood_mitigations(mitigations: &[FloodMitigation]) -> Result<()> {
+    if mitigations.len() > MAX_FLOOD_MITIGATIONS {
+        return Err(Error::msg(format!(
+            "{} flood mitigations exceed the limit of {MAX_FLOOD_MITIGATIONS}",
+            mitigations.len()
+        )));
+    }
+    let mut map =
+        BpfMap::<u64, FloodMitigationC>::from_path(&format!("{BPF_FS}/map_flood_mitigations"))?;
+    let existing: HashMap<u64, FloodMitigationC> = map.dump_vec().into_iter().collect();
+    let mut wanted = HashSet::with_capacity(mitigations.len());
+    for mitigation in mitigations {
+        let (download_action, download_cap) = mitigation.download.dataplane_value();
+        let (upload_action, upload_cap) = mitigation.upload.dataplane_value();
+        let action = [download_action, upload_action];
+        let pps_cap = [download_cap, upload_cap];
+        wanted.insert(mitigation.circuit_hash);
+        if let Some(current) = existing.get(&mitigation.circuit_hash)
+            && current.action == action
+            && current.pps_cap == pps_cap
+        {
+            continue;
+        }
+        let mut value = FloodMitigationC {
+            action,
+            pps_cap,
+            ..Default::default()
+        };
+        map.insert_or_update(&mut mitigation.circuit_hash.clone(), &mut value)?;
+    }
+    for mut circuit_hash in existing.into_keys() {
+        if !wanted.contains(&circuit_hash) {
+            map.delete(&mut circuit_hash)?;
+        }
+    }
+    update_control(|control| control.mitigations = mitigations.len() as u32)?;
+    Ok(())
+}
+
+/// Packets dropped by each loaded mitigation, keyed by circuit hash.
+pub fn flood_mitigation_drops() -> Result<HashMap<u64, DownUpOrder<u64>>> {
+    let map =
+        BpfMap::<u64, FloodMitigationC>::from_path(&format!("{BPF_FS}/map_flood_mitigations"))?;
+    Ok(map
+        .dump_vec()
+        .into_iter()
+        .map(|(circuit_hash, value)| {
+            (
+                circuit_hash,
+                DownUpOrder::new(value.dropped_packets[0], value.dropped_packets[1]),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_match_the_c_structs() {
+        assert_eq!(size_of::<FloodControlC>(), 16);
+        assert_eq!(size_of::<FloodCounterC>(), 160);
+        assert_eq!(size_of::<FloodMitigationC>(), 64);
+    }
+
+    #[test]
+    fn distinct_remote_estimates_track_the_bits_set() {
+        assert_eq!(estimate_distinct(&[0; SKETCH_WORDS]), 0);
+        assert_eq!(estimate_distinct(&[1, 0, 0, 0]), 1);
+        // 128 of 256 bits set is about 177 remotes.
+        assert_eq!(estimate_distinct(&[u64::MAX, u64::MAX, 0, 0]), 177);
+        assert_eq!(estimate_distinct(&[u64::MAX; SKETCH_WORDS]), 1420);
+    }
+
+    #[test]
+    fn per_cpu_values_sum_and_skip_stale_sketches() {
+        let current = FloodCounterC {
+            packets: [10, 1],
+            syn_packets: [4, 0],
+            remotes: [[1, 0, 0, 0], [0; SKETCH_WORDS]],
+            last_seen: 50,
+            sketch_epoch: 7,
+            ..Default::default()
+        };
+        let stale = FloodCounterC {
+            packets: [5, 2],
+            remotes: [[u64::MAX; SKETCH_WORDS], [0; SKETCH_WORDS]],
+            last_seen: 20,
+            sketch_epoch: 6,
+            ..Default::default()
+        };
+        let counters = sum_per_cpu(42, &[current, stale], 7);
+        assert_eq!(counters.circuit_hash, 42);
+        assert_eq!(counters.packets, DownUpOrder::new(15, 3));
+        assert_eq!(counters.syn_packets, DownUpOrder::new(4, 0));
+        assert_eq!(counters.distinct_remotes, DownUpOrder::new(1, 0));
+        assert_eq!(counters.last_seen, 50);
+    }
+}
@@ -88,8 +88,8 @@ pub struct FlowbeeData {
     pub device_hash: u64,
     /// Cached mapping epoch (from `ip_mapping_epoch`).
     pub mapping_epoch: u32,
-    /// Padding to keep struct alignment stable.
-    pub pad3: u32,
+    /// Cached circuit access policy from the `ip_info` match (0 = open).
+    pub access_policy: u32,
 }
 
 const _: [(); 40] = [(); core::mem::size_of::<FlowbeeKey>()];
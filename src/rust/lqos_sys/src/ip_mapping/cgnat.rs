@@ -0,0 +1,155 @@
+//! Loader for the CGNAT port-block maps.
+//!
+//! `map_cgnat_pools` says how each shared public address is split into port
+//! blocks, and `map_cgnat_blocks` points each allocated block at the
+//! subscriber's inside address. XDP and TC then look the inside address up in
+//! `map_ip_to_cpu_and_tc` as usual.
+
+use super::bump_mapping_epoch_at;
+use crate::bpf_map::BpfMap;
+use anyhow::{Error, Result};
+use lqos_config::{CgnatPool, CgnatPortBlock, MAX_CGNAT_PORT_BLOCKS, MAX_CGNAT_PUBLIC_ADDRESSES};
+use lqos_utils::XdpIpAddress;
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr};
+
+const BPF_FS: &str = "/sys/fs/bpf";
+
+/// Rust mirror of `struct cgnat_control`.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+struct CgnatControlC {
+    pools: u32,
+}
+
+/// Rust mirror of `struct cgnat_pool`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct CgnatPoolC {
+    first_port: u16,
+    block_size: u16,
+    pad: u32,
+}
+
+/// Rust mirror of `struct cgnat_block_key`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(C)]
+struct CgnatBlockKeyC {
+    public_ip: [u8; 16],
+    block: u32,
+}
+
+fn encode(ip: Ipv4Addr) -> [u8; 16] {
+    XdpIpAddress::from_ip(IpAddr::V4(ip)).0
+}
+
+fn block_key(public_ip: Ipv4Addr, block: u32) -> CgnatBlockKeyC {
+    CgnatBlockKeyC {
+        public_ip: encode(public_ip),
+        block,
+    }
+}
+
+/// Replaces the dataplane's CGNAT pools and port blocks. Entries that are
+/// kept are updated in place, so classification never sees an empty table.
+pub fn load_cgnat_mappings(pools: &[CgnatPool], blocks: &[CgnatPortBlock]) -> Result<()> {
+    if pools.len() > MAX_CGNAT_PUBLIC_ADDRESSES {
+        return Err(Error::msg(format!(
+            "{} CGNAT public addresses exceed the limit of {MAX_CGNAT_PUBLIC_ADDRESSES}",
+            pools.len()
+        )));
+    }
+    if blocks.len() > MAX_CGNAT_PORT_BLOCKS {
+        return Err(Error::msg(format!(
+            "{} CGNAT port blocks exceed the limit of {MAX_CGNAT_PORT_BLOCKS}",
+            blocks.len()
+        )));
+    }
+
+    let mut pool_map =
+        BpfMap::<[u8; 16], CgnatPoolC>::from_path(&format!("{BPF_FS}/map_cgnat_pools"))?;
+    let mut wanted_pools = HashSet::with_capacity(pools.len());
+    for pool in pools {
+        let mut key = encode(pool.public_ip);
+        let mut value = CgnatPoolC {
+            first_port: pool.first_port,
+            block_size: pool.block_size,
+            pad: 0,
+        };
+        pool_map.insert_or_update(&mut key, &mut value)?;
+        wanted_pools.insert(key);
+    }
+    for (mut key, _) in pool_map.dump_vec() {
+        if !wanted_pools.contains(&key) {
+            pool_map.delete(&mut key)?;
+        }
+    }
+
+    let mut block_map =
+        BpfMap::<CgnatBlockKeyC, [u8; 16]>::from_path(&format!("{BPF_FS}/map_cgnat_blocks"))?;
+    let mut wanted_blocks = HashSet::with_capacity(blocks.len());
+    for block in blocks {
+        let mut key = block_key(block.public_ip, block.block);
+        block_map.insert_or_update(&mut key, &mut encode(block.inside_ip))?;
+        wanted_blocks.insert(key);
+    }
+    for (mut key, _) in block_map.dump_vec() {
+        if !wanted_blocks.contains(&key) {
+            block_map.delete(&mut key)?;
+        }
+    }
+
+    let mut control_map =
+        BpfMap::<u32, CgnatControlC>::from_path(&format!("{BPF_FS}/cgnat_control"))?;
+    let mut control = CgnatControlC {
+        pools: pools.len() as u32,
+    };
+    control_map.insert_or_update(&mut 0, &mut control)?;
+
+    bump_mapping_epoch_at(BPF_FS)
+}
+
+/// Applies port-block allocations and releases reported by the CGNAT.
+/// `released` blocks are given as (public address, block).
+pub fn update_cgnat_port_blocks(
+    allocated: &[CgnatPortBlock],
+    released: &[(Ipv4Addr, u32)],
+) -> Result<()> {
+    if allocated.is_empty() && released.is_empty() {
+        return Ok(());
+    }
+    let mut block_map =
+        BpfMap::<CgnatBlockKeyC, [u8; 16]>::from_path(&format!("{BPF_FS}/map_cgnat_blocks"))?;
+    for (public_ip, block) in released {
+        // Deleting a block that was never loaded is not an error.
+        let _ = block_map.delete(&mut block_key(*public_ip, *block));
+    }
+    for block in allocated {
+        block_map.insert_or_update(
+            &mut block_key(block.public_ip, block.block),
+            &mut encode(block.inside_ip),
+        )?;
+    }
+    // Flows cache their mapping; make them pick up the new owners.
+    bump_mapping_epoch_at(BPF_FS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_match_the_c_structs() {
+        assert_eq!(std::mem::size_of::<CgnatControlC>(), 4);
+        assert_eq!(std::mem::size_of::<CgnatPoolC>(), 8);
+        assert_eq!(std::mem::size_of::<CgnatBlockKeyC>(), 20);
+    }
+
+    #[test]
+    fn block_keys_use_the_ip_hash_encoding() {
+        let key = block_key(Ipv4Addr::new(203, 0, 113, 9), 7);
+        assert_eq!(key.public_ip[..12], [0xFF; 12]);
+        assert_eq!(key.public_ip[12..], [203, 0, 113, 9]);
+        assert_eq!(key.block, 7);
+    }
+}
@@ -5,6 +5,8 @@ pub struct IpHashData {
     pub tc_handle: u32,
     pub circuit_id: u64,
     pub device_id: u64,
+    pub access_policy: u32,
+    pub pad: u32,
 }
 
 #[cfg(test)]
@@ -13,6 +15,6 @@ mod test {
 
     #[test]
     fn ip_hash_data_size() {
-        assert_eq!(std::mem::size_of::<IpHashData>(), 24);
+        assert_eq!(std::mem::size_of::<IpHashData>(), 32);
     }
 }
@@ -3,9 +3,11 @@ use anyhow::Result;
 use lqos_bus::TcHandle;
 use lqos_utils::XdpIpAddress;
 use std::net::IpAddr;
+mod cgnat;
 mod ip_hash_data;
 mod ip_hash_key;
 mod ip_to_map;
+pub use cgnat::{load_cgnat_mappings, update_cgnat_port_blocks};
 pub(crate) use ip_hash_data::IpHashData;
 pub(crate) use ip_hash_key::IpHashKey;
 use ip_to_map::IpToMap;
@@ -39,21 +41,27 @@ pub fn add_ip_to_tc(
     };
     let mut circuit_id = circuit_id;
     let mut device_id = device_id;
-    if (circuit_id == 0 || device_id == 0)
-        && let Some(existing) = bpf_map.lookup(&mut key)?
-    {
+    // The access policy is owned by `apply_circuit_access_policies`, so a
+    // remap must not lift a suspension.
+    let mut access_policy = 0;
+    if let Some(existing) = bpf_map.lookup(&mut key)? {
         if circuit_id == 0 {
             circuit_id = existing.circuit_id;
         }
         if device_id == 0 {
             device_id = existing.device_id;
         }
+        if existing.circuit_id == circuit_id {
+            access_policy = existing.access_policy;
+        }
     }
     let mut value = IpHashData {
         cpu: ip_to_add.cpu,
         tc_handle: ip_to_add.handle(),
         circuit_id,
         device_id,
+        access_policy,
+        pad: 0,
     };
     bpf_map.insert_or_update(&mut key, &mut value)?;
     // Removed because it should be cleared explicitly at the end of a batch operation
@@ -112,12 +120,23 @@ pub fn list_mapped_ips() -> Result<Vec<(IpHashKey, IpHashData)>> {
 /// mappings - because otherwise cached data will keep going to the previous
 /// destinations.
 pub fn clear_hot_cache() -> Result<()> {
-    let mut bpf_map =
-        BpfMap::<XdpIpAddress, IpHashData>::from_path("/sys/fs/bpf/ip_to_cpu_and_tc_hotcache")?;
+    clear_hot_cache_at("/sys/fs/bpf")
+}
+
+/// [`clear_hot_cache`] for maps pinned under `pin_root`.
+pub(crate) fn clear_hot_cache_at(pin_root: &str) -> Result<()> {
+    let mut bpf_map = BpfMap::<XdpIpAddress, IpHashData>::from_path(&format!(
+        "{pin_root}/ip_to_cpu_and_tc_hotcache"
+    ))?;
     bpf_map.clear_bulk()?;
 
-    // Bump the mapping epoch so the dataplane refreshes per-flow cached mapping metadata.
-    let mut epoch_map = BpfMap::<u32, u32>::from_path("/sys/fs/bpf/ip_mapping_epoch")?;
+    bump_mapping_epoch_at(pin_root)
+}
+
+/// Bumps the mapping epoch so the dataplane refreshes per-flow cached mapping
+/// metadata.
+pub(crate) fn bump_mapping_epoch_at(pin_root: &str) -> Result<()> {
+    let mut epoch_map = BpfMap::<u32, u32>::from_path(&format!("{pin_root}/ip_mapping_epoch"))?;
     let mut key = 0u32;
     let mut epoch = epoch_map.lookup(&mut key)?.unwrap_or(0);
     epoch = epoch.wrapping_add(1);
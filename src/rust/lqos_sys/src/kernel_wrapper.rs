@@ -56,11 +56,15 @@ impl LibreQoSKernels {
     /// * `to_isp` - the name of the ISP-network facing interface (e.g. `eth2`).
     /// * `heimdall_event_handler` - C function pointer to the ringbuffer
     ///   event handler exported by Heimdall.
+    /// * `flowbee_event_handler` - C function pointer to the flow RTT event handler.
+    /// * `flowbee_payload_event_handler` - C function pointer to the handler that
+    ///   receives early-packet payload samples used for application classification.
     pub fn new<S: ToString>(
         to_internet: S,
         to_isp: S,
         heimdall_event_handler: ring_buffer_sample_fn,
         flowbee_event_handler: ring_buffer_sample_fn,
+        flowbee_payload_event_handler: ring_buffer_sample_fn,
     ) -> anyhow::Result<Self> {
         let kernel = Self {
             to_internet: to_internet.to_string(),
@@ -81,6 +85,7 @@ impl LibreQoSKernels {
             InterfaceDirection::Internet,
             heimdall_event_handler,
             flowbee_event_handler,
+            flowbee_payload_event_handler,
         )?;
         attach_xdp_and_tc_to_interface(
             &kernel.to_isp,
@@ -90,6 +95,7 @@ impl LibreQoSKernels {
             InterfaceDirection::IspNetwork,
             heimdall_event_handler,
             flowbee_event_handler,
+            flowbee_payload_event_handler,
         )?;
         BPF_SKELETON.lock().replace(LqosKernBpfWrapper {
             ptr: skeleton,
@@ -116,6 +122,7 @@ impl LibreQoSKernels {
         stick_offset: u32,
         heimdall_event_handler: ring_buffer_sample_fn,
         flowbee_event_handler: ring_buffer_sample_fn,
+        flowbee_payload_event_handler: ring_buffer_sample_fn,
     ) -> anyhow::Result<Self> {
         let kernel = Self {
             to_internet: stick_interface.to_string(),
@@ -135,6 +142,7 @@ impl LibreQoSKernels {
             InterfaceDirection::OnAStick(internet_vlan, isp_vlan, stick_offset),
             heimdall_event_handler,
             flowbee_event_handler,
+            flowbee_payload_event_handler,
         )?;
         BPF_SKELETON.lock().replace(LqosKernBpfWrapper {
             ptr: skeleton,
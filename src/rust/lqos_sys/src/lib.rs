@@ -14,6 +14,8 @@ mod bpf_iterator;
 /// be handled with caution.
 pub mod bpf_map;
 mod cpu_map;
+mod dscp_remarking;
+mod flood_detection;
 /// Data shared between eBPF and Heimdall that needs local access
 /// for map control.
 pub mod flowbee_data;
@@ -22,13 +24,26 @@ mod ip_mapping;
 mod kernel_wrapper;
 mod linux;
 mod lqos_kernel;
+mod priority_lanes;
 mod tc_classify_control;
 mod throughput;
+mod traffic_mirror;
+mod walled_garden;
 
 pub use bpf_iterator::{end_flows, expire_throughput, iterate_flows};
+pub use dscp_remarking::{
+    MAX_DSCP_ASN_PREFIXES, dscp_remarked_counts, set_dscp_asn_prefixes, set_dscp_circuits,
+    set_dscp_policies,
+};
+pub use flood_detection::{
+    FloodAction, FloodCounters, FloodMitigation, MAX_FLOOD_MITIGATIONS, expire_flood_counters,
+    flood_mitigation_drops, read_flood_counters, set_flood_detection_enabled,
+    set_flood_mitigations,
+};
 pub use garbage_collector::bpf_garbage_collector;
 pub use ip_mapping::{
     add_ip_to_tc, clear_hot_cache, clear_ips_from_tc, del_ip_from_tc, list_mapped_ips,
+    load_cgnat_mappings, update_cgnat_port_blocks,
 };
 pub use kernel_wrapper::LibreQoSKernels;
 pub use linux::num_possible_cpus;
@@ -37,5 +52,15 @@ pub use lqos_kernel::ip_mapping_capacity;
 pub use lqos_kernel::ip_mapping_subsystem_ready;
 pub use lqos_kernel::max_tracked_ips;
 pub use lqos_kernel::unload_xdp_from_interface;
+pub use priority_lanes::{
+    MAX_PRIORITY_LANE_RULES, PriorityLaneRule, del_priority_lanes, list_priority_lanes,
+    set_priority_lanes,
+};
 pub use tc_classify_control::{initialize_tc_classify_bypass, set_tc_classify_bypass};
 pub use throughput::{HostCounter, throughput_for_each};
+pub use traffic_mirror::{
+    MirrorCounters, mirror_counters, reset_mirror_counters, set_traffic_mirror,
+};
+pub use walled_garden::{
+    MAX_WALLED_GARDEN_PREFIXES, apply_circuit_access_policies, set_walled_garden,
+};
@@ -450,6 +450,7 @@ pub(crate) struct AttachedPrograms {
     pub(crate) kprobe_link: *mut bpf::bpf_link,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn attach_xdp_and_tc_to_interface(
     interface_name: &str,
     to_internet_ifindex: i32,
@@ -458,6 +459,7 @@ pub fn attach_xdp_and_tc_to_interface(
     direction: InterfaceDirection,
     heimdall_event_handler: bpf::ring_buffer_sample_fn,
     flowbee_event_handler: bpf::ring_buffer_sample_fn,
+    flowbee_payload_event_handler: bpf::ring_buffer_sample_fn,
 ) -> Result<AttachedPrograms> {
     check_root()?;
     // If ABI changes were made to pinned maps, ensure we do not silently reuse
@@ -591,6 +593,36 @@ pub fn attach_xdp_and_tc_to_interface(
         .name(format!("FlowEvents_{}", interface_name))
         .spawn(|| poll_perf_events(handle))?;
 
+    // Find and attach the Flowbee payload sample handler
+    let flowbee_payloads_name = c"flowbee_payloads";
+    let flowbee_payloads_map = unsafe {
+        bpf::bpf_object__find_map_by_name((*skeleton).obj, flowbee_payloads_name.as_ptr())
+    };
+    let flowbee_payloads_fd = unsafe { bpf::bpf_map__fd(flowbee_payloads_map) };
+    if flowbee_payloads_fd < 0 {
+        error!("Unable to load Flowbee Payloads FD");
+        return Err(anyhow::Error::msg("Unable to load Flowbee Payloads FD"));
+    }
+    let opts: *const bpf::ring_buffer_opts = std::ptr::null();
+    let flowbee_payload_buffer = unsafe {
+        bpf::ring_buffer__new(
+            flowbee_payloads_fd,
+            flowbee_payload_event_handler,
+            opts as *mut c_void,
+            opts,
+        )
+    };
+    if unsafe { bpf::libbpf_get_error(flowbee_payload_buffer as *mut c_void) != 0 } {
+        error!("Failed to create Flowbee payload buffer");
+        return Err(anyhow::Error::msg(
+            "Failed to create Flowbee payload buffer",
+        ));
+    }
+    let handle = PerfBufferHandle(flowbee_payload_buffer);
+    std::thread::Builder::new()
+        .name(format!("FlowPayloads_{}", interface_name))
+        .spawn(|| poll_perf_events(handle))?;
+
     // Remove any previous entry
     let _r = Command::new("tc")
         .args(["qdisc", "del", "dev", interface_name, "clsact"])
@@ -658,6 +690,28 @@ pub fn attach_xdp_and_tc_to_interface(
                 return Err(Error::msg("Unable to attach TC Ingress to interface"));
             }
         }
+
+        // Attach the traffic mirror encapsulation program IF configured. A
+        // missing mirror interface must not stop shaping.
+        if etc.traffic_mirror.enabled {
+            match interface_name_to_index(&etc.traffic_mirror.interface) {
+                Ok(mirror_index) => {
+                    let error = unsafe {
+                        bpf::tc_attach_mirror_egress(mirror_index as i32, false, skeleton)
+                    };
+                    if error != 0 {
+                        warn!(
+                            "Unable to attach the traffic mirror program to {}",
+                            etc.traffic_mirror.interface
+                        );
+                    }
+                }
+                Err(e) => warn!(
+                    "Traffic mirror interface {} not found: {e:?}",
+                    etc.traffic_mirror.interface
+                ),
+            }
+        }
     }
 
     Ok(AttachedPrograms {
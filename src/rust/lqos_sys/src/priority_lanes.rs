@@ -0,0 +1,165 @@
+//! Userspace control of the intra-circuit priority-lane classifier map.
+//!
+//! Each entry is keyed by a circuit TC handle and direction, and holds the
+//! flattened match rules that select a lane by setting `skb->mark`.
+
+use crate::bpf_map::BpfMap;
+use anyhow::{Error, Result};
+use lqos_bus::TcHandle;
+use lqos_utils::XdpIpAddress;
+use std::net::IpAddr;
+
+const PRIORITY_LANES_PATH: &str = "/sys/fs/bpf/map_priority_lanes";
+
+/// Must match `MAX_PRIORITY_LANE_RULES` in `maximums.h`.
+pub const MAX_PRIORITY_LANE_RULES: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct PriorityLaneKey {
+    tc_handle: u32,
+    upload: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct PriorityLaneRuleC {
+    dscp_mask: u64,
+    prefix: [u8; 16],
+    prefix_len: u32,
+    port_min: u16,
+    port_max: u16,
+    protocol: u8,
+    pad: [u8; 3],
+    mark: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct PriorityLaneRules {
+    count: u32,
+    pad: u32,
+    rules: [PriorityLaneRuleC; MAX_PRIORITY_LANE_RULES],
+}
+
+/// One priority-lane match rule, as programmed into the classifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriorityLaneRule {
+    /// Bit `n` set means DSCP `n` matches; zero matches any DSCP.
+    pub dscp_mask: u64,
+    /// IP protocol number, or 0 for any.
+    pub protocol: u8,
+    /// Lowest matching source or destination port (0-0 for any).
+    pub port_min: u16,
+    /// Highest matching source or destination port.
+    pub port_max: u16,
+    /// Remote network and prefix length, or `None` for any address.
+    pub prefix: Option<(IpAddr, u8)>,
+    /// `skb->mark` to apply on a match (the lane's class minor).
+    pub mark: u32,
+}
+
+impl PriorityLaneRule {
+    fn to_c(self) -> PriorityLaneRuleC {
+        let (prefix, prefix_len) = match self.prefix {
+            None => ([0u8; 16], 0),
+            Some((ip, len)) => {
+                let encoded = XdpIpAddress::from_ip(ip).0;
+                let len = match ip {
+                    IpAddr::V4(_) => u32::from(len.min(32)) + 96,
+                    IpAddr::V6(_) => u32::from(len.min(128)),
+                };
+                (encoded, len)
+            }
+        };
+        PriorityLaneRuleC {
+            dscp_mask: self.dscp_mask,
+            prefix,
+            prefix_len,
+            port_min: self.port_min,
+            port_max: self.port_max,
+            protocol: self.protocol,
+            pad: [0; 3],
+            mark: self.mark,
+        }
+    }
+}
+
+fn lane_key(tc_handle: TcHandle, upload: bool) -> PriorityLaneKey {
+    PriorityLaneKey {
+        tc_handle: tc_handle.as_u32(),
+        upload: u32::from(upload),
+    }
+}
+
+/// Programs the priority-lane rules for one circuit direction.
+///
+/// Rules are evaluated in order and the first match wins. At most
+/// `MAX_PRIORITY_LANE_RULES` rules are accepted.
+pub fn set_priority_lanes(
+    tc_handle: TcHandle,
+    upload: bool,
+    rules: &[PriorityLaneRule],
+) -> Result<()> {
+    if rules.len() > MAX_PRIORITY_LANE_RULES {
+        return Err(Error::msg(format!(
+            "{} priority-lane rules exceed the limit of {MAX_PRIORITY_LANE_RULES}",
+            rules.len()
+        )));
+    }
+    let mut value = PriorityLaneRules {
+        count: rules.len() as u32,
+        ..Default::default()
+    };
+    for (slot, rule) in value.rules.iter_mut().zip(rules.iter()) {
+        *slot = rule.to_c();
+    }
+    let mut bpf_map = BpfMap::<PriorityLaneKey, PriorityLaneRules>::from_path(PRIORITY_LANES_PATH)?;
+    bpf_map.insert_or_update(&mut lane_key(tc_handle, upload), &mut value)
+}
+
+/// Removes the priority-lane rules for one circuit direction.
+pub fn del_priority_lanes(tc_handle: TcHandle, upload: bool) -> Result<()> {
+    let mut bpf_map = BpfMap::<PriorityLaneKey, PriorityLaneRules>::from_path(PRIORITY_LANES_PATH)?;
+    bpf_map.delete(&mut lane_key(tc_handle, upload))
+}
+
+/// Lists the circuit directions that currently have priority-lane rules.
+pub fn list_priority_lanes() -> Result<Vec<(TcHandle, bool)>> {
+    let bpf_map = BpfMap::<PriorityLaneKey, PriorityLaneRules>::from_path(PRIORITY_LANES_PATH)?;
+    Ok(bpf_map
+        .dump_vec()
+        .into_iter()
+        .map(|(key, _)| (TcHandle::from_u32(key.tc_handle), key.upload != 0))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rule_layout_matches_the_c_struct() {
+        // 8 (mask) + 16 (prefix) + 4 (len) + 2 + 2 (ports) + 1 + 3 (proto) + 4 (mark)
+        assert_eq!(std::mem::size_of::<PriorityLaneRuleC>(), 40);
+        assert_eq!(
+            std::mem::size_of::<PriorityLaneRules>(),
+            8 + 40 * MAX_PRIORITY_LANE_RULES
+        );
+    }
+
+    #[test]
+    fn ipv4_prefixes_are_offset_into_the_mapped_range() {
+        let rule = PriorityLaneRule {
+            dscp_mask: 0,
+            protocol: 17,
+            port_min: 5060,
+            port_max: 5061,
+            prefix: Some(("192.0.2.0".parse().expect("ip"), 24)),
+            mark: 0x11,
+        }
+        .to_c();
+        assert_eq!(rule.prefix_len, 120);
+        assert_eq!(&rule.prefix[12..], &[192, 0, 2, 0]);
+    }
+}
@@ -0,0 +1,268 @@
+//! Userspace control of traffic mirroring.
+//!
+//! `map_mirror_config` names the mirror interface and how clones are
+//! encapsulated; `map_mirror_prefixes` maps each mirrored address or prefix
+//! to its session slot. TC egress counts the clones it sends per slot in
+//! the per-CPU `map_mirror_counters`.
+
+use crate::bpf_map::BpfMap;
+use crate::ip_mapping::IpHashKey;
+use crate::lqos_kernel::bpf::libbpf_num_possible_cpus;
+use crate::lqos_kernel::interface_name_to_index;
+use anyhow::{Error, Result};
+use libbpf_sys::{bpf_map_lookup_elem, bpf_map_update_elem, bpf_obj_get};
+use lqos_config::{
+    MAX_MIRROR_PREFIXES, MAX_MIRROR_SESSIONS, MirrorEncapsulation, TrafficMirrorConfig,
+    parse_mac_address,
+};
+use lqos_utils::XdpIpAddress;
+use nix::libc::close;
+use std::ffi::{CString, c_void};
+use std::mem::size_of;
+use std::net::IpAddr;
+
+const MIRROR_CONFIG_PATH: &str = "/sys/fs/bpf/map_mirror_config";
+const MIRROR_PREFIXES_PATH: &str = "/sys/fs/bpf/map_mirror_prefixes";
+const MIRROR_COUNTERS_PATH: &str = "/sys/fs/bpf/map_mirror_counters";
+
+/// Rust mirror of `struct mirror_config`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct MirrorConfigC {
+    ifindex: u32,
+    prefixes: u32,
+    snaplen: u32,
+    vlan_id: u16,
+    encap: u8,
+    pad: u8,
+    gre_src: u32,
+    gre_dst: u32,
+    src_mac: [u8; 6],
+    dst_mac: [u8; 6],
+}
+
+/// Rust mirror of `struct mirror_counter`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct MirrorCounterC {
+    packets: u64,
+    bytes: u64,
+    errors: u64,
+}
+
+/// Clones sent for one session slot, summed across CPUs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MirrorCounters {
+    /// Packets cloned to the mirror interface.
+    pub packets: u64,
+    /// Bytes cloned, before truncation and encapsulation.
+    pub bytes: u64,
+    /// Clones that could not be sent or encapsulated.
+    pub errors: u64,
+}
+
+fn encode_config(
+    config: &TrafficMirrorConfig,
+    ifindex: u32,
+    src_mac: [u8; 6],
+    prefixes: usize,
+) -> Result<MirrorConfigC> {
+    let mut value = MirrorConfigC {
+        ifindex,
+        prefixes: prefixes as u32,
+        snaplen: config.snaplen,
+        src_mac,
+        ..Default::default()
+    };
+    match config.encapsulation {
+        MirrorEncapsulation::None => {}
+        MirrorEncapsulation::Vlan => {
+            value.encap = 1;
+            value.vlan_id = config.vlan_id;
+        }
+        MirrorEncapsulation::Gre => {
+            let (local, remote, mac) = config.gre_endpoints().map_err(Error::msg)?;
+            value.encap = 2;
+            // The dataplane writes these straight into the outer header.
+            value.gre_src = u32::from_ne_bytes(local.octets());
+            value.gre_dst = u32::from_ne_bytes(remote.octets());
+            value.dst_mac = mac;
+        }
+    }
+    Ok(value)
+}
+
+fn interface_mac(interface: &str) -> Result<[u8; 6]> {
+    let path = format!("/sys/class/net/{interface}/address");
+    let mac = std::fs::read_to_string(&path)?;
+    parse_mac_address(&mac).map_err(Error::msg)
+}
+
+fn prefix_key(ip: IpAddr, prefix: u8) -> IpHashKey {
+    let prefixlen = match ip {
+        IpAddr::V4(_) => u32::from(prefix.min(32)) + 96,
+        IpAddr::V6(_) => u32::from(prefix.min(128)),
+    };
+    IpHashKey {
+        prefixlen,
+        address: XdpIpAddress::from_ip(ip).0,
+    }
+}
+
+/// Replaces the mirrored prefixes, each given as a network, its prefix
+/// length and its session slot. Mirroring stops when `config` is disabled
+/// or `prefixes` is empty.
+pub fn set_traffic_mirror(
+    config: &TrafficMirrorConfig,
+    prefixes: &[(IpAddr, u8, u32)],
+) -> Result<()> {
+    if prefixes.len() > MAX_MIRROR_PREFIXES {
+        return Err(Error::msg(format!(
+            "{} mirrored prefixes exceed the limit of {MAX_MIRROR_PREFIXES}",
+            prefixes.len()
+        )));
+    }
+    if let Some((_, _, slot)) = prefixes
+        .iter()
+        .find(|(_, _, slot)| *slot as usize >= MAX_MIRROR_SESSIONS)
+    {
+        return Err(Error::msg(format!(
+            "Mirror session slot {slot} is out of range"
+        )));
+    }
+
+    let mut config_map = BpfMap::<u32, MirrorConfigC>::from_path(MIRROR_CONFIG_PATH)?;
+    // Stop cloning while the prefixes change.
+    config_map.insert_or_update(&mut 0, &mut MirrorConfigC::default())?;
+
+    let mut prefix_map = BpfMap::<IpHashKey, u32>::from_path(MIRROR_PREFIXES_PATH)?;
+    prefix_map.clear()?;
+    if !config.enabled || prefixes.is_empty() {
+        return Ok(());
+    }
+    for &(ip, prefix, mut slot) in prefixes {
+        prefix_map.insert_or_update(&mut prefix_key(ip, prefix), &mut slot)?;
+    }
+
+    let ifindex = interface_name_to_index(&config.interface)?;
+    let src_mac = interface_mac(&config.interface)?;
+    let mut value = encode_config(config, ifindex, src_mac, prefixes.len())?;
+    config_map.insert_or_update(&mut 0, &mut value)?;
+    Ok(())
+}
+
+fn open_counters() -> Result<(i32, usize)> {
+    let path_c = CString::new(MIRROR_COUNTERS_PATH)?;
+    let cpu_count = unsafe { libbpf_num_possible_cpus() };
+    if cpu_count <= 0 {
+        return Err(Error::msg(format!(
+            "Unable to determine CPU count for {MIRROR_COUNTERS_PATH}: libbpf_num_possible_cpus returned {cpu_count}"
+        )));
+    }
+    let fd = unsafe { bpf_obj_get(path_c.as_ptr()) };
+    if fd < 0 {
+        return Err(Error::msg(format!(
+            "Unable to open BPF map '{MIRROR_COUNTERS_PATH}'"
+        )));
+    }
+    Ok((fd, cpu_count as usize))
+}
+
+// Per-CPU values come back as one 8-byte-aligned value per CPU.
+const COUNTER_STRIDE: usize = size_of::<MirrorCounterC>().div_ceil(8) * 8;
+
+/// Zeroes a session slot's counters before the slot is reused.
+pub fn reset_mirror_counters(slot: u32) -> Result<()> {
+    let (fd, cpu_count) = open_counters()?;
+    let buffer = vec![0u8; COUNTER_STRIDE * cpu_count];
+    let mut key = slot;
+    let err = unsafe {
+        let err = bpf_map_update_elem(
+            fd,
+            &mut key as *mut u32 as *mut c_void,
+            buffer.as_ptr() as *mut c_void,
+            0,
+        );
+        close(fd);
+        err
+    };
+    if err != 0 {
+        return Err(Error::msg(format!(
+            "Unable to reset mirror counters for slot {slot}"
+        )));
+    }
+    Ok(())
+}
+
+/// Reads the counters of every session slot, indexed by slot.
+pub fn mirror_counters() -> Result<Vec<MirrorCounters>> {
+    let (fd, cpu_count) = open_counters()?;
+    let mut buffer = vec![0u8; COUNTER_STRIDE * cpu_count];
+    let mut result = vec![MirrorCounters::default(); MAX_MIRROR_SESSIONS];
+    unsafe {
+        for (slot, total) in result.iter_mut().enumerate() {
+            let mut key = slot as u32;
+            if bpf_map_lookup_elem(
+                fd,
+                &mut key as *mut u32 as *mut c_void,
+                buffer.as_mut_ptr() as *mut c_void,
+            ) != 0
+            {
+                continue;
+            }
+            for cpu in 0..cpu_count {
+                let value = std::ptr::read_unaligned(
+                    buffer.as_ptr().add(cpu * COUNTER_STRIDE) as *const MirrorCounterC
+                );
+                total.packets += value.packets;
+                total.bytes += value.bytes;
+                total.errors += value.errors;
+            }
+        }
+        close(fd);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn layouts_match_the_c_structs() {
+        assert_eq!(size_of::<MirrorConfigC>(), 36);
+        assert_eq!(size_of::<MirrorCounterC>(), 24);
+    }
+
+    #[test]
+    fn gre_settings_are_encoded_for_the_dataplane() {
+        let config = TrafficMirrorConfig {
+            enabled: true,
+            interface: "eth9".to_string(),
+            encapsulation: MirrorEncapsulation::Gre,
+            gre_local: "192.0.2.1".to_string(),
+            gre_remote: "192.0.2.50".to_string(),
+            gre_next_hop_mac: "02:00:5e:10:00:01".to_string(),
+            snaplen: 128,
+            ..Default::default()
+        };
+        let value = encode_config(&config, 7, [2, 0, 0, 0, 0, 9], 3).expect("encodes");
+        assert_eq!((value.ifindex, value.prefixes, value.snaplen), (7, 3, 128));
+        assert_eq!(value.encap, 2);
+        assert_eq!(
+            value.gre_src.to_ne_bytes(),
+            Ipv4Addr::new(192, 0, 2, 1).octets()
+        );
+        assert_eq!(value.dst_mac, [0x02, 0x00, 0x5e, 0x10, 0x00, 0x01]);
+        assert_eq!(value.src_mac, [2, 0, 0, 0, 0, 9]);
+
+        let vlan = TrafficMirrorConfig {
+            encapsulation: MirrorEncapsulation::Vlan,
+            vlan_id: 300,
+            ..config
+        };
+        let value = encode_config(&vlan, 7, [0; 6], 1).expect("encodes");
+        assert_eq!((value.encap, value.vlan_id), (1, 300));
+    }
+}
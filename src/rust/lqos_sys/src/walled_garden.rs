@@ -0,0 +1,399 @@
+//! Userspace control of the walled garden for suspended circuits.
+//!
+//! A circuit's access policy lives in its `map_ip_to_cpu_and_tc` entries, so
+//! XDP learns it from the same lookup that finds the TC handle. What a
+//! restricted circuit may still reach is held in `walled_garden_config` and
+//! `map_walled_garden_allow`.
+
+use crate::bpf_map::BpfMap;
+use crate::ip_mapping::{IpHashData, IpHashKey, clear_hot_cache_at};
+use anyhow::{Error, Result};
+use lqos_config::{CircuitAccessPolicy, WalledGardenConfig};
+use lqos_utils::XdpIpAddress;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+const BPF_FS: &str = "/sys/fs/bpf";
+
+/// Must match `WALLED_GARDEN_PREFIXES_MAX` in `maximums.h`.
+pub const MAX_WALLED_GARDEN_PREFIXES: usize = 1024;
+
+/// Rust mirror of `struct walled_garden_config`. Address and port are in
+/// network byte order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+struct WalledGardenConfigC {
+    portal_ipv4: u32,
+    portal_port: u16,
+    allow_dns: u8,
+    pad: u8,
+}
+
+impl WalledGardenConfigC {
+    fn new(config: &WalledGardenConfig) -> Self {
+        let (portal_ipv4, portal_port) = match config.portal() {
+            Some((address, port)) => (u32::from_ne_bytes(address.octets()), port.to_be()),
+            None => (0, 0),
+        };
+        Self {
+            portal_ipv4,
+            portal_port,
+            allow_dns: u8::from(config.allow_dns),
+            pad: 0,
+        }
+    }
+}
+
+fn allow_key(ip: IpAddr, prefix: u8) -> IpHashKey {
+    let prefixlen = match ip {
+        IpAddr::V4(_) => u32::from(prefix.min(32)) + 96,
+        IpAddr::V6(_) => u32::from(prefix.min(128)),
+    };
+    IpHashKey {
+        prefixlen,
+        address: XdpIpAddress::from_ip(ip).0,
+    }
+}
+
+/// Programs what restricted circuits may reach: the allowlisted prefixes,
+/// the portal and whether DNS is open.
+pub fn set_walled_garden(config: &WalledGardenConfig) -> Result<()> {
+    set_walled_garden_at(BPF_FS, config)
+}
+
+pub(crate) fn set_walled_garden_at(pin_root: &str, config: &WalledGardenConfig) -> Result<()> {
+    let allowed = config.allowed_networks();
+    if allowed.len() > MAX_WALLED_GARDEN_PREFIXES {
+        return Err(Error::msg(format!(
+            "{} walled-garden prefixes exceed the limit of {MAX_WALLED_GARDEN_PREFIXES}",
+            allowed.len()
+        )));
+    }
+
+    let mut config_map =
+        BpfMap::<u32, WalledGardenConfigC>::from_path(&format!("{pin_root}/walled_garden_config"))?;
+    config_map.insert_or_update(&mut 0, &mut WalledGardenConfigC::new(config))?;
+
+    let mut allow_map =
+        BpfMap::<IpHashKey, u32>::from_path(&format!("{pin_root}/map_walled_garden_allow"))?;
+    allow_map.clear()?;
+    for (ip, prefix) in allowed {
+        allow_map.insert_or_update(&mut allow_key(ip, prefix), &mut 1)?;
+    }
+    Ok(())
+}
+
+/// Brings every mapped IP's access policy in line with `policies`, keyed by
+/// circuit hash. Circuits not listed are open. Returns how many mappings
+/// changed; the hot cache is cleared if any did.
+pub fn apply_circuit_access_policies(
+    policies: &HashMap<u64, CircuitAccessPolicy>,
+) -> Result<usize> {
+    apply_circuit_access_policies_at(BPF_FS, policies)
+}
+
+pub(crate) fn apply_circuit_access_policies_at(
+    pin_root: &str,
+    policies: &HashMap<u64, CircuitAccessPolicy>,
+) -> Result<usize> {
+    let mut bpf_map =
+        BpfMap::<IpHashKey, IpHashData>::from_path(&format!("{pin_root}/map_ip_to_cpu_and_tc"))?;
+    let mut changed = 0;
+    for (mut key, mut value) in bpf_map.dump_vec() {
+        let wanted = policies
+            .get(&value.circuit_id)
+            .copied()
+            .unwrap_or_default()
+            .dataplane_value();
+        if value.access_policy == wanted {
+            continue;
+        }
+        value.access_policy = wanted;
+        bpf_map.insert_or_update(&mut key, &mut value)?;
+        changed += 1;
+    }
+    if changed > 0 {
+        clear_hot_cache_at(pin_root)?;
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn config_layout_matches_the_c_struct() {
+        assert_eq!(std::mem::size_of::<WalledGardenConfigC>(), 8);
+    }
+
+    #[test]
+    fn config_is_encoded_in_network_byte_order() {
+        let config = WalledGardenConfig {
+            portal_ipv4: Some("203.0.113.10".to_string()),
+            portal_port: 8080,
+            ..Default::default()
+        };
+        let encoded = WalledGardenConfigC::new(&config);
+        assert_eq!(encoded.portal_ipv4.to_ne_bytes(), [203, 0, 113, 10]);
+        assert_eq!(encoded.portal_port.to_ne_bytes(), 8080u16.to_be_bytes());
+        assert_eq!(encoded.allow_dns, 1);
+
+        let encoded = WalledGardenConfigC::new(&WalledGardenConfig::default());
+        assert_eq!(encoded.portal_ipv4, 0);
+    }
+
+    #[test]
+    fn ipv4_allow_keys_are_offset_into_the_mapped_range() {
+        let key = allow_key(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 0)), 24);
+        assert_eq!(key.prefixlen, 120);
+        assert_eq!(&key.address[12..], &[198, 51, 100, 0]);
+    }
+}
+
+/// `BPF_PROG_TEST_RUN` fixtures for the XDP walled garden. These load a
+/// private copy of the kernel with its maps pinned under a scratch
+/// directory, so they need root and a BPF-capable kernel.
+#[cfg(test)]
+mod prog_test_run {
+    use super::*;
+    use crate::lqos_kernel::bpf;
+    use libbpf_sys::{bpf_prog_test_run_opts, bpf_test_run_opts};
+    use std::ffi::{CString, c_void};
+    use std::net::Ipv4Addr;
+
+    const XDP_DROP: u32 = 1;
+    const XDP_PASS: u32 = 2;
+    const CIRCUIT: u64 = 0x5eed;
+    const SUBSCRIBER: Ipv4Addr = Ipv4Addr::new(100, 64, 0, 10);
+    const PORTAL: Ipv4Addr = Ipv4Addr::new(203, 0, 113, 10);
+    const PAYMENTS: Ipv4Addr = Ipv4Addr::new(198, 51, 100, 20);
+    const ELSEWHERE: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 80);
+
+    struct TestKernel {
+        skeleton: *mut bpf::lqos_kern,
+        pin_root: String,
+    }
+
+    impl TestKernel {
+        fn load(name: &str) -> Self {
+            let pin_root = format!("{BPF_FS}/lqos_test_{name}_{}", std::process::id());
+            std::fs::create_dir_all(&pin_root).expect("create the scratch pin root");
+            let pin_root_c = CString::new(pin_root.clone()).expect("pin root");
+            let skeleton = unsafe { bpf::lqos_kern_open_pinned_at(pin_root_c.as_ptr()) };
+            assert!(!skeleton.is_null(), "open the kernel skeleton");
+            unsafe {
+                (*(*skeleton).rodata).NUM_CPUS = bpf::libbpf_num_possible_cpus();
+                (*(*skeleton).data).direction = 2;
+                assert_eq!(bpf::lqos_kern_load(skeleton), 0, "load the kernel");
+            }
+            Self { skeleton, pin_root }
+        }
+
+        /// 1 runs packets as if they arrived from the Internet, 2 as if they
+        /// came from subscribers. `.data` is mmapped, so this applies after load.
+        fn set_direction(&self, direction: i32) {
+            unsafe {
+                (*(*self.skeleton).data).direction = direction;
+            }
+        }
+
+        fn restrict(&self, policy: CircuitAccessPolicy) {
+            let mut lpm = BpfMap::<IpHashKey, IpHashData>::from_path(&format!(
+                "{}/map_ip_to_cpu_and_tc",
+                self.pin_root
+            ))
+            .expect("open the LPM map");
+            let mut key = allow_key(IpAddr::V4(SUBSCRIBER), 32);
+            let mut value = IpHashData {
+                circuit_id: CIRCUIT,
+                ..Default::default()
+            };
+            lpm.insert_or_update(&mut key, &mut value)
+                .expect("map the subscriber");
+            let policies = HashMap::from([(CIRCUIT, policy)]);
+            assert_eq!(
+                apply_circuit_access_policies_at(&self.pin_root, &policies).expect("apply"),
+                1
+            );
+            let config = WalledGardenConfig {
+                allowed_prefixes: vec![format!("{PAYMENTS}/32")],
+                portal_ipv4: Some(PORTAL.to_string()),
+                ..Default::default()
+            };
+            set_walled_garden_at(&self.pin_root, &config).expect("program the walled garden");
+        }
+
+        /// Runs `xdp_prog` once, returning the verdict and the packet it left.
+        fn run(&self, packet: &[u8]) -> (u32, Vec<u8>) {
+            let mut out = vec![0u8; packet.len() + 256];
+            let mut opts = bpf_test_run_opts {
+                sz: std::mem::size_of::<bpf_test_run_opts>() as _,
+                data_in: packet.as_ptr() as *const c_void,
+                data_size_in: packet.len() as u32,
+                data_out: out.as_mut_ptr() as *mut c_void,
+                data_size_out: out.len() as u32,
+                repeat: 1,
+                ..Default::default()
+            };
+            let err = unsafe {
+                let prog_fd = bpf::bpf_program__fd((*self.skeleton).progs.xdp_prog);
+                bpf_prog_test_run_opts(prog_fd, &mut opts)
+            };
+            assert_eq!(err, 0, "BPF_PROG_TEST_RUN failed");
+            out.truncate(opts.data_size_out as usize);
+            (opts.retval, out)
+        }
+    }
+
+    impl Drop for TestKernel {
+        fn drop(&mut self) {
+            unsafe {
+                bpf::bpf_object__close((*self.skeleton).obj);
+            }
+            let _ = std::fs::remove_dir_all(&self.pin_root);
+        }
+    }
+
+    fn checksum(bytes: &[u8], initial: u32) -> u16 {
+        let mut sum = initial;
+        for chunk in bytes.chunks(2) {
+            let word = if chunk.len() == 2 {
+                u16::from_be_bytes([chunk[0], chunk[1]])
+            } else {
+                u16::from_be_bytes([chunk[0], 0])
+            };
+            sum += u32::from(word);
+        }
+        while sum > 0xFFFF {
+            sum = (sum & 0xFFFF) + (sum >> 16);
+        }
+        !(sum as u16)
+    }
+
+    fn l4_checksum(ip: &[u8], l4: &[u8]) -> u16 {
+        let mut pseudo = Vec::with_capacity(12);
+        pseudo.extend_from_slice(&ip[12..20]);
+        pseudo.extend_from_slice(&[0, ip[9]]);
+        pseudo.extend_from_slice(&(l4.len() as u16).to_be_bytes());
+        let partial = !checksum(&pseudo, 0);
+        checksum(l4, u32::from(partial))
+    }
+
+    /// Ethernet + IPv4 + TCP (SYN) or UDP, with valid checksums.
+    fn packet(tcp: bool, src: (Ipv4Addr, u16), dst: (Ipv4Addr, u16)) -> Vec<u8> {
+        let mut l4 = Vec::new();
+        l4.extend_from_slice(&src.1.to_be_bytes());
+        l4.extend_from_slice(&dst.1.to_be_bytes());
+        if tcp {
+            l4.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xFF, 0xFF, 0, 0, 0, 0]);
+        } else {
+            l4.extend_from_slice(&[0, 12, 0, 0, b'p', b'i', b'n', b'g']);
+        }
+
+        let protocol = if tcp { 6 } else { 17 };
+        let mut ip = vec![0x45, 0, 0, 0, 0, 1, 0x40, 0, 64, protocol, 0, 0];
+        ip.extend_from_slice(&src.0.octets());
+        ip.extend_from_slice(&dst.0.octets());
+        let total = (ip.len() + l4.len()) as u16;
+        ip[2..4].copy_from_slice(&total.to_be_bytes());
+        let ip_sum = checksum(&ip, 0);
+        ip[10..12].copy_from_slice(&ip_sum.to_be_bytes());
+
+        let sum_at = if tcp { 16 } else { 6 };
+        let l4_sum = l4_checksum(&ip, &l4);
+        l4[sum_at..sum_at + 2].copy_from_slice(&l4_sum.to_be_bytes());
+
+        let mut frame = vec![0x02, 0, 0, 0, 0, 1, 0x02, 0, 0, 0, 0, 2, 0x08, 0x00];
+        frame.extend_from_slice(&ip);
+        frame.extend_from_slice(&l4);
+        frame
+    }
+
+    fn assert_checksums_valid(frame: &[u8]) {
+        let ip = &frame[14..34];
+        assert_eq!(checksum(ip, 0), 0, "IPv4 header checksum");
+        assert_eq!(l4_checksum(ip, &frame[34..]), 0, "TCP checksum");
+    }
+
+    #[test]
+    #[ignore = "requires root and a BPF-capable kernel"]
+    fn blocked_circuits_only_reach_the_walled_garden() {
+        let kernel = TestKernel::load("wg_block");
+        kernel.restrict(CircuitAccessPolicy::Block);
+
+        let dns = packet(false, (SUBSCRIBER, 40000), (ELSEWHERE, 53));
+        assert_eq!(kernel.run(&dns).0, XDP_PASS, "DNS is allowed");
+        let payments = packet(true, (SUBSCRIBER, 40001), (PAYMENTS, 443));
+        assert_eq!(kernel.run(&payments).0, XDP_PASS, "allowlisted prefix");
+        let web = packet(true, (SUBSCRIBER, 40002), (ELSEWHERE, 80));
+        assert_eq!(
+            kernel.run(&web).0,
+            XDP_DROP,
+            "HTTP is not redirected under block"
+        );
+        let other = packet(false, (SUBSCRIBER, 40003), (ELSEWHERE, 4500));
+        assert_eq!(kernel.run(&other).0, XDP_DROP, "everything else is dropped");
+
+        kernel.set_direction(1);
+        let inbound = packet(true, (ELSEWHERE, 443), (SUBSCRIBER, 40004));
+        assert_eq!(
+            kernel.run(&inbound).0,
+            XDP_DROP,
+            "inbound from outside the garden"
+        );
+        let reply = packet(true, (PAYMENTS, 443), (SUBSCRIBER, 40001));
+        assert_eq!(kernel.run(&reply).0, XDP_PASS, "replies from the garden");
+    }
+
+    #[test]
+    #[ignore = "requires root and a BPF-capable kernel"]
+    fn open_circuits_are_untouched() {
+        let kernel = TestKernel::load("wg_open");
+        kernel.restrict(CircuitAccessPolicy::Block);
+        let lifted = HashMap::new();
+        assert_eq!(
+            apply_circuit_access_policies_at(&kernel.pin_root, &lifted).expect("apply"),
+            1
+        );
+
+        let other = packet(false, (SUBSCRIBER, 40010), (ELSEWHERE, 4500));
+        let (verdict, out) = kernel.run(&other);
+        assert_eq!(verdict, XDP_PASS);
+        assert_eq!(out, other);
+    }
+
+    #[test]
+    #[ignore = "requires root and a BPF-capable kernel"]
+    fn portal_circuits_have_http_redirected_and_restored() {
+        let kernel = TestKernel::load("wg_portal");
+        kernel.restrict(CircuitAccessPolicy::Portal);
+
+        let web = packet(true, (SUBSCRIBER, 40020), (ELSEWHERE, 80));
+        let (verdict, out) = kernel.run(&web);
+        assert_eq!(verdict, XDP_PASS);
+        assert_eq!(&out[30..34], &PORTAL.octets(), "destination is the portal");
+        assert_eq!(&out[36..38], &80u16.to_be_bytes());
+        assert_checksums_valid(&out);
+
+        let https = packet(true, (SUBSCRIBER, 40021), (ELSEWHERE, 443));
+        assert_eq!(
+            kernel.run(&https).0,
+            XDP_DROP,
+            "only plain HTTP is redirected"
+        );
+
+        kernel.set_direction(1);
+        let reply = packet(true, (PORTAL, 80), (SUBSCRIBER, 40020));
+        let (verdict, out) = kernel.run(&reply);
+        assert_eq!(verdict, XDP_PASS);
+        assert_eq!(
+            &out[26..30],
+            &ELSEWHERE.octets(),
+            "reply comes from the original site"
+        );
+        assert_eq!(&out[34..36], &80u16.to_be_bytes());
+        assert_checksums_valid(&out);
+    }
+}
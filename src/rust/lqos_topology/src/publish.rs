@@ -23,6 +23,36 @@ fn acquire_effective_publish_lock(config: &Config) -> Result<EffectivePublishLoc
     Ok(EffectivePublishLock { _lock: lock })
 }
 
+/// Builds and publishes effective topology artifacts for `config` once,
+/// outside the runtime thread. Attachment health and topology overrides are
+/// read from the paths `config` resolves; missing files are treated as empty.
+///
+/// Side effects: writes the effective topology artifacts under `config`'s
+/// state directory.
+pub fn publish_effective_topology_for_config(config: &Config) -> Result<()> {
+    let source_generation = lqos_config::compute_topology_source_generation(config)
+        .context("Unable to compute topology source generation")?;
+    let canonical = load_canonical_topology_state(config);
+    let overrides_path = TopologyOverridesFile::path_for_config(config);
+    let overrides = if overrides_path.exists() {
+        TopologyOverridesFile::load_from_explicit_path(&overrides_path)
+            .context("Unable to load topology overrides file")?
+    } else {
+        TopologyOverridesFile::default()
+    };
+    let health = TopologyAttachmentHealthStateFile::load(config).unwrap_or_default();
+    let artifacts = build_effective_topology_artifacts_from_canonical_with_runtime_queue_context(
+        config, &canonical, &overrides, &health,
+    )
+    .map_err(|errors| {
+        anyhow::anyhow!(
+            "Refusing to publish invalid effective topology: {}",
+            errors.join(" | ")
+        )
+    })?;
+    publish_effective_topology_artifacts(config, &artifacts, &source_generation)
+}
+
 /// Publishes effective topology artifacts and shaping inputs for one source generation.
 pub fn publish_effective_topology_artifacts(
     config: &Config,
@@ -86,6 +86,7 @@ fn forced_visible_node_names(overrides: &lqos_overrides::OverrideFile) -> HashSe
             NetworkAdjustment::SetNodeVirtual {
                 node_name,
                 virtual_node: false,
+                ..
             } => Some(node_name.trim()),
             _ => None,
         })
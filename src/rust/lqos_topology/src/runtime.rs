@@ -12,6 +12,7 @@ use tracing::{debug, info, warn};
 mod gate;
 mod health;
 mod probe;
+mod routing;
 
 use gate::{RoundHints, RuntimeBuildGate, topology_overrides_generation};
 use health::{
@@ -19,6 +20,7 @@ use health::{
     refresh_health_state,
 };
 use probe::probe_specs;
+use routing::RoutingRound;
 
 use crate::{
     build_effective_topology_artifacts_from_canonical_with_runtime_queue_context,
@@ -54,9 +56,17 @@ fn run_round(
     let specs = &gate.cached_probe_specs;
     let probes_enabled = specs.iter().any(|spec| spec.enabled);
     if probes_enabled {
+        let routing =
+            RoutingRound::observe(&config.integration_common.topology_attachment_health.routing);
         match probe_specs(bus_tx.clone(), specs, Duration::from_millis(750)) {
             Ok(probe_results) => {
-                refresh_health_state(config.as_ref(), health_state, specs, &probe_results)?;
+                refresh_health_state(
+                    config.as_ref(),
+                    health_state,
+                    specs,
+                    &probe_results,
+                    &routing,
+                )?;
             }
             Err(err) => {
                 let reason =
@@ -66,7 +76,13 @@ fn run_round(
             }
         }
     } else {
-        refresh_health_state(config.as_ref(), health_state, specs, &HashMap::new())?;
+        refresh_health_state(
+            config.as_ref(),
+            health_state,
+            specs,
+            &HashMap::new(),
+            &RoutingRound::default(),
+        )?;
     }
 
     let next_signature = health_effective_signature(health_state);
@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
+use super::routing::{RoutingRound, combine_with_routing};
 use crate::{AttachmentProbeSpec, is_health_state_fresh, parse_probe_ip, probe_unavailable_reason};
 
 pub(super) fn now_unix() -> Option<u64> {
@@ -62,6 +63,7 @@ fn health_entry_for_unprobeable_spec(
     entry.consecutive_successes = 0;
     entry.suppressed_until_unix = None;
     entry.endpoint_status = Vec::new();
+    entry.routing_state = None;
     entry
 }
 
@@ -84,6 +86,7 @@ fn build_health_entry(
     spec: &AttachmentProbeSpec,
     previous: Option<&TopologyAttachmentHealthEntry>,
     probe_result: Option<(bool, bool)>,
+    routing: &RoutingRound,
 ) -> TopologyAttachmentHealthEntry {
     let now = now_unix();
     let mut entry = base_health_entry(spec, previous);
@@ -106,7 +109,17 @@ fn build_health_entry(
         },
     ];
 
-    if local_reachable && remote_reachable {
+    entry.routing_state = routing.state_for_pair(
+        &spec.remote_ip,
+        previous.and_then(|previous| previous.routing_state.as_ref()),
+    );
+    let (healthy, routing_failure) = combine_with_routing(
+        routing.policy,
+        local_reachable && remote_reachable,
+        entry.routing_state.as_ref(),
+    );
+
+    if healthy {
         entry.consecutive_misses = 0;
         entry.consecutive_successes = entry.consecutive_successes.saturating_add(1);
         entry.last_success_unix = now;
@@ -140,7 +153,13 @@ fn build_health_entry(
             .fail_after_missed
     {
         entry.status = TopologyAttachmentHealthStatus::Suppressed;
-        entry.reason = Some(format!("{} missed probes", entry.consecutive_misses));
+        entry.reason = Some(match routing_failure {
+            Some(detail) => format!(
+                "Routing down for {} rounds: {detail}",
+                entry.consecutive_misses
+            ),
+            None => format!("{} missed probes", entry.consecutive_misses),
+        });
         entry.suppressed_until_unix = now.map(|ts| {
             ts.saturating_add(
                 config
@@ -218,6 +237,7 @@ pub(super) fn refresh_health_state(
     health_state: &mut TopologyAttachmentHealthStateFile,
     specs: &[AttachmentProbeSpec],
     probe_results: &HashMap<String, (bool, bool)>,
+    routing: &RoutingRound,
 ) -> Result<bool> {
     let previous_by_pair = health_state
         .attachments
@@ -232,6 +252,7 @@ pub(super) fn refresh_health_state(
                 spec,
                 previous_by_pair.get(spec.pair_id.as_str()).copied(),
                 probe_results.get(&spec.pair_id).copied(),
+                routing,
             )
         })
         .collect::<Vec<_>>();
@@ -295,10 +316,15 @@ pub(super) fn health_effective_signature(health_state: &TopologyAttachmentHealth
 #[cfg(test)]
 mod tests {
     use super::{
-        TopologyAttachmentHealthEntry, build_unobserved_health_entry, health_effective_signature,
+        TopologyAttachmentHealthEntry, build_health_entry, build_unobserved_health_entry,
+        health_effective_signature,
     };
     use crate::AttachmentProbeSpec;
-    use lqos_config::{TopologyAttachmentHealthStateFile, TopologyAttachmentHealthStatus};
+    use crate::runtime::routing::{RoutingNeighbor, RoutingRound, RoutingSnapshot};
+    use lqos_config::{
+        Config, TopologyAttachmentHealthStateFile, TopologyAttachmentHealthStatus,
+        TopologyRoutingHealthPolicy,
+    };
 
     fn probe_spec() -> AttachmentProbeSpec {
         AttachmentProbeSpec {
@@ -413,4 +439,63 @@ mod tests {
         assert_eq!(entry.status, TopologyAttachmentHealthStatus::Suppressed);
         assert_eq!(entry.reason.as_deref(), Some("2 missed probes"));
     }
+
+    fn routing_round(policy: TopologyRoutingHealthPolicy, state: &str, up: bool) -> RoutingRound {
+        let mut snapshot = RoutingSnapshot::default();
+        snapshot.add_neighbor(
+            "192.0.2.2".parse().expect("valid address"),
+            RoutingNeighbor {
+                protocol: "OSPF",
+                state: state.to_string(),
+                up,
+            },
+        );
+        RoutingRound::observed(policy, snapshot)
+    }
+
+    #[test]
+    fn routing_down_suppresses_pair_with_reachable_probes() {
+        let config = Config::default();
+        let routing = routing_round(TopologyRoutingHealthPolicy::Both, "Init/Other", false);
+        let mut previous = health_entry();
+        previous.consecutive_misses = config
+            .integration_common
+            .topology_attachment_health
+            .fail_after_missed
+            .saturating_sub(1);
+
+        let entry = build_health_entry(
+            &config,
+            &probe_spec(),
+            Some(&previous),
+            Some((true, true)),
+            &routing,
+        );
+
+        assert_eq!(entry.status, TopologyAttachmentHealthStatus::Suppressed);
+        assert!(
+            entry
+                .reason
+                .as_deref()
+                .is_some_and(|reason| reason.contains("OSPF adjacency Init/Other"))
+        );
+        assert!(entry.routing_state.is_some_and(|state| !state.up));
+    }
+
+    #[test]
+    fn routing_up_keeps_pair_healthy_under_either_policy() {
+        let config = Config::default();
+        let routing = routing_round(TopologyRoutingHealthPolicy::Either, "Full/PtP", true);
+
+        let entry = build_health_entry(
+            &config,
+            &probe_spec(),
+            Some(&health_entry()),
+            Some((true, false)),
+            &routing,
+        );
+
+        assert_eq!(entry.status, TopologyAttachmentHealthStatus::Healthy);
+        assert_eq!(entry.consecutive_misses, 0);
+    }
 }
@@ -0,0 +1,230 @@
+//! BIRD control-socket client.
+//!
+//! The socket speaks BIRD's line protocol: every reply line starts with a
+//! four-digit code, followed by `-` when more lines follow or a space on the
+//! last line. Continuation lines start with a space and inherit the previous
+//! code. `0xxx` ends a reply successfully; `8xxx`/`9xxx` are errors.
+
+use super::{RoutingNeighbor, RoutingSnapshot};
+use anyhow::{Context, Result, bail};
+use std::io::{BufRead, BufReader, Write};
+use std::net::IpAddr;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+const CODE_OSPF_NEIGHBORS: u16 = 1013;
+const CODE_PROTOCOL_ROW: u16 = 1002;
+const CODE_PROTOCOL_DETAIL: u16 = 1006;
+const CODE_ROUTE: u16 = 1007;
+
+pub(super) fn read_snapshot(
+    socket_path: &str,
+    check_next_hops: bool,
+    timeout: Duration,
+) -> Result<RoutingSnapshot> {
+    let stream = UnixStream::connect(socket_path)
+        .with_context(|| format!("Unable to connect to BIRD socket {socket_path}"))?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    read_reply(&mut reader).context("BIRD did not send a greeting")?;
+
+    let mut snapshot = RoutingSnapshot::default();
+    let mut command = |command: &str| -> Result<Vec<(u16, String)>> {
+        writer.write_all(format!("{command}\n").as_bytes())?;
+        read_reply(&mut reader).with_context(|| format!("BIRD command '{command}' failed"))
+    };
+    parse_ospf_neighbors(&command("show ospf neighbors")?, &mut snapshot);
+    parse_protocols(&command("show protocols all")?, &mut snapshot);
+    if check_next_hops {
+        parse_routes(&command("show route")?, &mut snapshot);
+    }
+    Ok(snapshot)
+}
+
+fn line_code(line: &str) -> Option<(u16, bool)> {
+    let code = line.get(..4)?.parse::<u16>().ok()?;
+    match line.as_bytes().get(4) {
+        Some(b'-') => Some((code, false)),
+        Some(b' ') | None => Some((code, true)),
+        _ => None,
+    }
+}
+
+/// Reads one reply, returning `(code, text)` for every line.
+fn read_reply(reader: &mut impl BufRead) -> Result<Vec<(u16, String)>> {
+    let mut lines = Vec::new();
+    let mut current_code = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("BIRD closed the control socket");
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if let Some(text) = line.strip_prefix(' ') {
+            lines.push((current_code, text.to_string()));
+            continue;
+        }
+        let Some((code, last)) = line_code(line) else {
+            bail!("Unexpected BIRD reply line: {line}");
+        };
+        current_code = code;
+        let text = line.get(5..).unwrap_or_default().to_string();
+        if last && code >= 8000 {
+            bail!("BIRD error {code}: {}", text.trim());
+        }
+        lines.push((code, text));
+        if last && code < 1000 {
+            return Ok(lines);
+        }
+    }
+}
+
+/// Splits the reply text from a recorded session into lines, for tests.
+#[cfg(test)]
+fn parse_recorded(text: &str) -> Vec<(u16, String)> {
+    let mut reader = BufReader::new(text.as_bytes());
+    read_reply(&mut reader).expect("recorded reply parses")
+}
+
+fn parse_address(raw: &str) -> Option<IpAddr> {
+    raw.split('%').next()?.parse().ok()
+}
+
+/// Parses `show ospf neighbors` rows:
+/// `Router ID  Pri  State  DTime  Interface  Router IP`.
+fn parse_ospf_neighbors(lines: &[(u16, String)], snapshot: &mut RoutingSnapshot) {
+    for (code, text) in lines {
+        if *code != CODE_OSPF_NEIGHBORS {
+            continue;
+        }
+        let fields = text.split_whitespace().collect::<Vec<_>>();
+        if fields.len() < 6 || fields[0].parse::<IpAddr>().is_err() {
+            continue;
+        }
+        let Some(address) = parse_address(fields[fields.len() - 1]) else {
+            continue;
+        };
+        let state = fields[2];
+        snapshot.add_neighbor(
+            address,
+            RoutingNeighbor {
+                protocol: "OSPF",
+                state: state.to_string(),
+                up: state.starts_with("Full"),
+            },
+        );
+    }
+}
+
+/// Parses BGP sessions from `show protocols all`.
+fn parse_protocols(lines: &[(u16, String)], snapshot: &mut RoutingSnapshot) {
+    let mut is_bgp = false;
+    let mut state: Option<String> = None;
+    let mut address: Option<IpAddr> = None;
+    let mut flush = |state: &mut Option<String>, address: &mut Option<IpAddr>| {
+        if let (Some(state), Some(address)) = (state.take(), address.take()) {
+            snapshot.add_neighbor(
+                address,
+                RoutingNeighbor {
+                    protocol: "BGP",
+                    up: state == "Established",
+                    state,
+                },
+            );
+        }
+    };
+    for (code, text) in lines {
+        match *code {
+            CODE_PROTOCOL_ROW => {
+                flush(&mut state, &mut address);
+                is_bgp = text.split_whitespace().nth(1) == Some("BGP");
+            }
+            CODE_PROTOCOL_DETAIL if is_bgp => {
+                let text = text.trim();
+                if let Some(value) = text.strip_prefix("BGP state:") {
+                    state = Some(value.trim().to_string());
+                } else if let Some(value) = text.strip_prefix("Neighbor address:") {
+                    address = parse_address(value.trim());
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut state, &mut address);
+}
+
+/// Collects next-hop addresses (`via <ip>`) from `show route`.
+fn parse_routes(lines: &[(u16, String)], snapshot: &mut RoutingSnapshot) {
+    for (code, text) in lines {
+        if *code != CODE_ROUTE {
+            continue;
+        }
+        let mut fields = text.split_whitespace();
+        while let Some(field) = fields.next() {
+            if field == "via"
+                && let Some(address) = fields.next().and_then(parse_address)
+            {
+                snapshot.next_hops.insert(address);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().expect("valid address")
+    }
+
+    #[test]
+    fn parses_recorded_ospf_neighbors() {
+        let mut snapshot = RoutingSnapshot::default();
+        parse_ospf_neighbors(
+            &parse_recorded(include_str!("bird_ospf_neighbors.test.txt")),
+            &mut snapshot,
+        );
+        assert_eq!(snapshot.neighbors.len(), 3);
+        let up = &snapshot.neighbors[&ip("192.0.2.2")][0];
+        assert_eq!(up.state, "Full/PtP");
+        assert!(up.up);
+        assert!(!snapshot.neighbors[&ip("192.0.2.10")][0].up);
+        assert!(snapshot.neighbors.contains_key(&ip("fe80::2")));
+    }
+
+    #[test]
+    fn parses_recorded_bgp_protocols() {
+        let mut snapshot = RoutingSnapshot::default();
+        parse_protocols(
+            &parse_recorded(include_str!("bird_protocols_all.test.txt")),
+            &mut snapshot,
+        );
+        assert_eq!(snapshot.neighbors.len(), 2);
+        assert!(snapshot.neighbors[&ip("198.51.100.1")][0].up);
+        let idle = &snapshot.neighbors[&ip("198.51.100.5")][0];
+        assert_eq!(idle.state, "Active");
+        assert!(!idle.up);
+    }
+
+    #[test]
+    fn parses_recorded_route_next_hops() {
+        let mut snapshot = RoutingSnapshot::default();
+        parse_routes(
+            &parse_recorded(include_str!("bird_show_route.test.txt")),
+            &mut snapshot,
+        );
+        assert!(snapshot.next_hops.contains(&ip("192.0.2.2")));
+        assert!(snapshot.next_hops.contains(&ip("192.0.2.6")));
+        assert!(!snapshot.next_hops.contains(&ip("192.0.2.10")));
+    }
+
+    #[test]
+    fn error_replies_are_reported() {
+        let mut reader =
+            BufReader::new("9001 syntax error, unexpected CF_SYM_UNDEFINED\n".as_bytes());
+        assert!(read_reply(&mut reader).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! FRR reader, using `vtysh -c "<command> json"`.
+
+use super::{RoutingNeighbor, RoutingSnapshot};
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::io::Read;
+use std::net::IpAddr;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+pub(super) fn read_snapshot(
+    vtysh_path: &str,
+    check_next_hops: bool,
+    timeout: Duration,
+) -> Result<RoutingSnapshot> {
+    let mut snapshot = RoutingSnapshot::default();
+    parse_ospf_neighbors(
+        &run_json(vtysh_path, "show ip ospf neighbor json", timeout)?,
+        &mut snapshot,
+    );
+    parse_bgp_neighbors(
+        &run_json(vtysh_path, "show bgp neighbors json", timeout)?,
+        &mut snapshot,
+    );
+    if check_next_hops {
+        for command in ["show ip route json", "show ipv6 route json"] {
+            parse_routes(&run_json(vtysh_path, command, timeout)?, &mut snapshot);
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Runs one vtysh command, killing it if it outlives `timeout`.
+fn run_json(vtysh_path: &str, command: &str, timeout: Duration) -> Result<Value> {
+    let mut child = Command::new(vtysh_path)
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Unable to run {vtysh_path}"))?;
+    // Drain stdout on a separate thread so a large reply cannot fill the pipe.
+    let mut stdout = child.stdout.take().context("vtysh stdout unavailable")?;
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("vtysh -c '{command}' timed out after {timeout:?}");
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let output = reader
+        .join()
+        .map_err(|_| anyhow::anyhow!("vtysh output reader panicked"))??;
+    if !status.success() {
+        bail!("vtysh -c '{command}' exited with {status}");
+    }
+    serde_json::from_slice(&output).with_context(|| format!("Invalid JSON from '{command}'"))
+}
+
+fn parse_address(raw: &str) -> Option<IpAddr> {
+    raw.split('%').next()?.parse().ok()
+}
+
+/// Parses `show ip ospf neighbor json`:
+/// `{"neighbors": {"<router-id>": [{"nbrState": "Full/DR", "ifaceAddress": "<ip>"}]}}`.
+///
+/// Older FRR releases use `state` and `address` instead.
+fn parse_ospf_neighbors(reply: &Value, snapshot: &mut RoutingSnapshot) {
+    let Some(neighbors) = reply.get("neighbors").and_then(Value::as_object) else {
+        return;
+    };
+    for entry in neighbors.values().filter_map(Value::as_array).flatten() {
+        let state = entry
+            .get("nbrState")
+            .or_else(|| entry.get("state"))
+            .and_then(Value::as_str);
+        let address = entry
+            .get("ifaceAddress")
+            .or_else(|| entry.get("address"))
+            .and_then(Value::as_str)
+            .and_then(parse_address);
+        if let (Some(state), Some(address)) = (state, address) {
+            snapshot.add_neighbor(
+                address,
+                RoutingNeighbor {
+                    protocol: "OSPF",
+                    state: state.to_string(),
+                    up: state.starts_with("Full"),
+                },
+            );
+        }
+    }
+}
+
+/// Parses `show bgp neighbors json`: `{"<peer-ip>": {"bgpState": "Established"}}`.
+///
+/// Unnumbered peers are keyed by interface name and are skipped.
+fn parse_bgp_neighbors(reply: &Value, snapshot: &mut RoutingSnapshot) {
+    let Some(peers) = reply.as_object() else {
+        return;
+    };
+    for (peer, details) in peers {
+        let Some(address) = parse_address(peer) else {
+            continue;
+        };
+        let Some(state) = details.get("bgpState").and_then(Value::as_str) else {
+            continue;
+        };
+        snapshot.add_neighbor(
+            address,
+            RoutingNeighbor {
+                protocol: "BGP",
+                state: state.to_string(),
+                up: state == "Established",
+            },
+        );
+    }
+}
+
+/// Collects active next-hops of selected routes from `show ip route json`:
+/// `{"<prefix>": [{"selected": true, "nexthops": [{"ip": "<ip>", "active": true}]}]}`.
+fn parse_routes(reply: &Value, snapshot: &mut RoutingSnapshot) {
+    let Some(prefixes) = reply.as_object() else {
+        return;
+    };
+    for route in prefixes.values().filter_map(Value::as_array).flatten() {
+        let selected = ["selected", "installed"]
+            .iter()
+            .any(|key| route.get(key).and_then(Value::as_bool) == Some(true));
+        if !selected {
+            continue;
+        }
+        let Some(next_hops) = route.get("nexthops").and_then(Value::as_array) else {
+            continue;
+        };
+        for next_hop in next_hops {
+            if next_hop.get("active").and_then(Value::as_bool) != Some(true) {
+                continue;
+            }
+            if let Some(address) = next_hop
+                .get("ip")
+                .and_then(Value::as_str)
+                .and_then(parse_address)
+            {
+                snapshot.next_hops.insert(address);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().expect("valid address")
+    }
+
+    fn fixture(text: &str) -> Value {
+        serde_json::from_str(text).expect("fixture is valid JSON")
+    }
+
+    #[test]
+    fn parses_recorded_ospf_neighbors() {
+        let mut snapshot = RoutingSnapshot::default();
+        parse_ospf_neighbors(
+            &fixture(include_str!("frr_ospf_neighbors.test.json")),
+            &mut snapshot,
+        );
+        assert_eq!(snapshot.neighbors.len(), 3);
+        let up = &snapshot.neighbors[&ip("192.0.2.2")][0];
+        assert_eq!(up.state, "Full/DR");
+        assert!(up.up);
+        assert!(!snapshot.neighbors[&ip("192.0.2.10")][0].up);
+        // Older releases report `state`/`address`.
+        assert!(snapshot.neighbors[&ip("192.0.2.14")][0].up);
+    }
+
+    #[test]
+    fn parses_recorded_bgp_neighbors() {
+        let mut snapshot = RoutingSnapshot::default();
+        parse_bgp_neighbors(
+            &fixture(include_str!("frr_bgp_neighbors.test.json")),
+            &mut snapshot,
+        );
+        assert_eq!(snapshot.neighbors.len(), 2);
+        assert!(snapshot.neighbors[&ip("198.51.100.1")][0].up);
+        assert!(!snapshot.neighbors[&ip("2001:db8::5")][0].up);
+    }
+
+    #[test]
+    fn parses_recorded_route_next_hops() {
+        let mut snapshot = RoutingSnapshot::default();
+        parse_routes(
+            &fixture(include_str!("frr_ip_route.test.json")),
+            &mut snapshot,
+        );
+        assert!(snapshot.next_hops.contains(&ip("192.0.2.2")));
+        assert!(snapshot.next_hops.contains(&ip("198.51.100.1")));
+        // Inactive next-hops and unselected routes are ignored.
+        assert!(!snapshot.next_hops.contains(&ip("192.0.2.6")));
+        assert!(!snapshot.next_hops.contains(&ip("192.0.2.10")));
+    }
+}
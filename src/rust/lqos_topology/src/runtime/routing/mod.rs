@@ -0,0 +1,271 @@
+//! Routing-protocol attachment health.
+//!
+//! Reads OSPF/BGP neighbor state (and optionally installed route next-hops)
+//! from a local BIRD or FRR daemon, and maps it onto attachment pairs by
+//! their remote probe IP: the neighbor address on the attachment's link.
+
+mod bird;
+mod frr;
+
+use lqos_config::{
+    TopologyAttachmentRoutingState, TopologyRoutingHealthConfig, TopologyRoutingHealthPolicy,
+    TopologyRoutingHealthSource,
+};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::parse_probe_ip;
+
+/// One routing adjacency reported by the daemon.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct RoutingNeighbor {
+    /// Routing protocol, e.g. `OSPF` or `BGP`.
+    pub(super) protocol: &'static str,
+    /// Daemon-reported state, e.g. `Full/PtP` or `Established`.
+    pub(super) state: String,
+    /// Whether the adjacency is usable for forwarding.
+    pub(super) up: bool,
+}
+
+/// Neighbor and next-hop state read from the routing daemon in one round.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct RoutingSnapshot {
+    pub(super) neighbors: HashMap<IpAddr, Vec<RoutingNeighbor>>,
+    pub(super) next_hops: HashSet<IpAddr>,
+}
+
+impl RoutingSnapshot {
+    pub(super) fn add_neighbor(&mut self, address: IpAddr, neighbor: RoutingNeighbor) {
+        self.neighbors.entry(address).or_default().push(neighbor);
+    }
+
+    /// Routing state for a remote probe IP.
+    ///
+    /// Returns `None` for an address the daemon has never reported, unless
+    /// `previously_seen` is set: a neighbor that disappears after its
+    /// adjacency drops is then reported as down.
+    pub(super) fn state_for(
+        &self,
+        remote_ip: IpAddr,
+        previously_seen: bool,
+    ) -> Option<TopologyAttachmentRoutingState> {
+        if let Some(neighbors) = self.neighbors.get(&remote_ip) {
+            let chosen = neighbors
+                .iter()
+                .find(|neighbor| neighbor.up)
+                .unwrap_or(&neighbors[0]);
+            return Some(TopologyAttachmentRoutingState {
+                up: chosen.up,
+                detail: format!("{} adjacency {}", chosen.protocol, chosen.state),
+            });
+        }
+        if self.next_hops.contains(&remote_ip) {
+            return Some(TopologyAttachmentRoutingState {
+                up: true,
+                detail: "Installed route next-hop".to_string(),
+            });
+        }
+        previously_seen.then(|| TopologyAttachmentRoutingState {
+            up: false,
+            detail: "No adjacency or next-hop".to_string(),
+        })
+    }
+}
+
+/// Routing input for one health round.
+#[derive(Debug, Default)]
+pub(super) struct RoutingRound {
+    pub(super) policy: TopologyRoutingHealthPolicy,
+    /// `None` when routing health is disabled or the daemon could not be read.
+    snapshot: Option<RoutingSnapshot>,
+    /// The daemon could not be read; pairs keep their previous routing state.
+    unavailable: bool,
+}
+
+impl RoutingRound {
+    /// Reads the configured routing daemon, if any.
+    pub(super) fn observe(config: &TopologyRoutingHealthConfig) -> Self {
+        let timeout = Duration::from_secs(config.command_timeout_seconds.max(1));
+        let snapshot = match config.source {
+            TopologyRoutingHealthSource::Disabled => return Self::default(),
+            TopologyRoutingHealthSource::Bird => {
+                bird::read_snapshot(&config.bird_socket, config.check_next_hops, timeout)
+            }
+            TopologyRoutingHealthSource::Frr => {
+                frr::read_snapshot(&config.vtysh_path, config.check_next_hops, timeout)
+            }
+        };
+        match snapshot {
+            Ok(snapshot) => Self {
+                policy: config.policy,
+                snapshot: Some(snapshot),
+                unavailable: false,
+            },
+            Err(err) => {
+                warn!("Topology routing health could not read routing state: {err:#}");
+                Self {
+                    policy: config.policy,
+                    snapshot: None,
+                    unavailable: true,
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub(super) fn observed(policy: TopologyRoutingHealthPolicy, snapshot: RoutingSnapshot) -> Self {
+        Self {
+            policy,
+            snapshot: Some(snapshot),
+            unavailable: false,
+        }
+    }
+
+    /// Routing state for a pair's remote probe IP this round.
+    pub(super) fn state_for_pair(
+        &self,
+        remote_ip: &str,
+        previous: Option<&TopologyAttachmentRoutingState>,
+    ) -> Option<TopologyAttachmentRoutingState> {
+        if self.unavailable {
+            return previous.cloned();
+        }
+        let snapshot = self.snapshot.as_ref()?;
+        let remote_ip = parse_probe_ip(remote_ip)?;
+        snapshot.state_for(remote_ip, previous.is_some())
+    }
+}
+
+/// Combines a probe round with routing state for one pair.
+///
+/// Returns whether the round counts as a success, plus the routing detail
+/// responsible for a failure that the probes alone would not have caused.
+pub(super) fn combine_with_routing(
+    policy: TopologyRoutingHealthPolicy,
+    probes_ok: bool,
+    routing: Option<&TopologyAttachmentRoutingState>,
+) -> (bool, Option<String>) {
+    let Some(routing) = routing else {
+        return (probes_ok, None);
+    };
+    let healthy = match policy {
+        TopologyRoutingHealthPolicy::Both => probes_ok && routing.up,
+        TopologyRoutingHealthPolicy::Either => probes_ok || routing.up,
+        TopologyRoutingHealthPolicy::RoutingOnly => routing.up,
+    };
+    let routing_failure = (!healthy && !routing.up).then(|| routing.detail.clone());
+    (healthy, routing_failure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().expect("valid address")
+    }
+
+    fn snapshot() -> RoutingSnapshot {
+        let mut snapshot = RoutingSnapshot::default();
+        snapshot.add_neighbor(
+            ip("192.0.2.2"),
+            RoutingNeighbor {
+                protocol: "OSPF",
+                state: "Init".to_string(),
+                up: false,
+            },
+        );
+        snapshot.add_neighbor(
+            ip("192.0.2.2"),
+            RoutingNeighbor {
+                protocol: "BGP",
+                state: "Established".to_string(),
+                up: true,
+            },
+        );
+        snapshot.next_hops.insert(ip("192.0.2.6"));
+        snapshot
+    }
+
+    #[test]
+    fn state_prefers_an_up_adjacency_then_next_hops() {
+        let snapshot = snapshot();
+        let state = snapshot
+            .state_for(ip("192.0.2.2"), false)
+            .expect("neighbor state");
+        assert!(state.up);
+        assert_eq!(state.detail, "BGP adjacency Established");
+        assert!(
+            snapshot
+                .state_for(ip("192.0.2.6"), false)
+                .is_some_and(|state| state.up)
+        );
+        assert!(snapshot.state_for(ip("192.0.2.10"), false).is_none());
+        assert!(
+            snapshot
+                .state_for(ip("192.0.2.10"), true)
+                .is_some_and(|state| !state.up)
+        );
+    }
+
+    #[test]
+    fn unreadable_daemon_keeps_previous_state() {
+        let previous = TopologyAttachmentRoutingState {
+            up: true,
+            detail: "OSPF adjacency Full/PtP".to_string(),
+        };
+        let unavailable = RoutingRound {
+            unavailable: true,
+            ..RoutingRound::default()
+        };
+        assert_eq!(
+            unavailable.state_for_pair("192.0.2.2", Some(&previous)),
+            Some(previous.clone())
+        );
+        assert!(
+            RoutingRound::default()
+                .state_for_pair("192.0.2.2", Some(&previous))
+                .is_none()
+        );
+        let observed = RoutingRound::observed(TopologyRoutingHealthPolicy::Both, snapshot());
+        assert!(
+            observed
+                .state_for_pair("192.0.2.2/30", None)
+                .is_some_and(|state| state.up)
+        );
+    }
+
+    #[test]
+    fn policies_combine_probe_and_routing_results() {
+        let down = TopologyAttachmentRoutingState {
+            up: false,
+            detail: "OSPF adjacency Init".to_string(),
+        };
+        let up = TopologyAttachmentRoutingState {
+            up: true,
+            detail: "OSPF adjacency Full/PtP".to_string(),
+        };
+        assert_eq!(
+            combine_with_routing(TopologyRoutingHealthPolicy::Both, true, Some(&down)),
+            (false, Some("OSPF adjacency Init".to_string()))
+        );
+        assert_eq!(
+            combine_with_routing(TopologyRoutingHealthPolicy::Both, false, Some(&up)),
+            (false, None)
+        );
+        assert_eq!(
+            combine_with_routing(TopologyRoutingHealthPolicy::Either, false, Some(&up)),
+            (true, None)
+        );
+        assert_eq!(
+            combine_with_routing(TopologyRoutingHealthPolicy::RoutingOnly, false, Some(&up)),
+            (true, None)
+        );
+        assert_eq!(
+            combine_with_routing(TopologyRoutingHealthPolicy::RoutingOnly, true, None),
+            (true, None)
+        );
+    }
+}
@@ -330,6 +330,15 @@ fn duplicate_circuit_shape_conflicts(
     if circuit.sqm_override != device.sqm_override {
         conflicts.push("sqm");
     }
+    if circuit.priority_classes != device.priority_classes {
+        conflicts.push("Priority Classes");
+    }
+    if circuit.access_policy != device.access_policy {
+        conflicts.push("Access Policy");
+    }
+    if circuit.qoo_profile != device.qoo_profile {
+        conflicts.push("QoO Profile");
+    }
     conflicts
 }
 
@@ -702,6 +711,9 @@ fn build_shaping_inputs(
                 upload_max_mbps: device.upload_max_mbps,
                 comment: device.comment.clone(),
                 sqm_override: device.sqm_override.clone(),
+                priority_classes: device.priority_classes.clone(),
+                access_policy: device.access_policy,
+                qoo_profile: device.qoo_profile.clone(),
                 devices: Vec::new(),
             });
             index
@@ -790,6 +802,7 @@ fn apply_runtime_shaped_device_overrides(
                 max_download_bandwidth,
                 min_upload_bandwidth,
                 max_upload_bandwidth,
+                ..
             } => {
                 for device in devices
                     .iter_mut()
@@ -815,6 +828,7 @@ fn apply_runtime_shaped_device_overrides(
                 max_download_bandwidth,
                 min_upload_bandwidth,
                 max_upload_bandwidth,
+                ..
             } => {
                 for device in devices
                     .iter_mut()
@@ -837,6 +851,7 @@ fn apply_runtime_shaped_device_overrides(
             CircuitAdjustment::DeviceAdjustSqm {
                 device_id,
                 sqm_override,
+                ..
             } => {
                 for device in devices
                     .iter_mut()
@@ -848,15 +863,40 @@ fn apply_runtime_shaped_device_overrides(
                         .filter(|value| !value.is_empty());
                 }
             }
-            CircuitAdjustment::RemoveCircuit { circuit_id } => {
+            CircuitAdjustment::CircuitAdjustPriorityClasses {
+                circuit_id,
+                priority_classes,
+                ..
+            } => {
+                for device in devices
+                    .iter_mut()
+                    .filter(|device| device.circuit_id == *circuit_id)
+                {
+                    device.priority_classes = priority_classes.clone();
+                }
+            }
+            CircuitAdjustment::CircuitAdjustAccessPolicy {
+                circuit_id,
+                access_policy,
+                ..
+            } => {
+                for device in devices
+                    .iter_mut()
+                    .filter(|device| device.circuit_id == *circuit_id)
+                {
+                    device.access_policy = *access_policy;
+                }
+            }
+            CircuitAdjustment::RemoveCircuit { circuit_id, .. } => {
                 devices.retain(|device| device.circuit_id != *circuit_id);
             }
-            CircuitAdjustment::RemoveDevice { device_id } => {
+            CircuitAdjustment::RemoveDevice { device_id, .. } => {
                 devices.retain(|device| device.device_id != *device_id);
             }
             CircuitAdjustment::ReparentCircuit {
                 circuit_id,
                 parent_node,
+                ..
             } => {
                 for device in devices
                     .iter_mut()
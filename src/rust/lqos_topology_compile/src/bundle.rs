@@ -452,6 +452,9 @@ mod tests {
                 upload_max_mbps: 100.0,
                 comment: String::new(),
                 sqm_override: None,
+                priority_classes: Vec::new(),
+                access_policy: lqos_config::CircuitAccessPolicy::Open,
+                qoo_profile: None,
                 circuit_hash: 0,
                 device_hash: 0,
                 parent_hash: 0,
@@ -0,0 +1,98 @@
+//! Per-circuit access policies: the walled garden for suspended circuits.
+//!
+//! A circuit's policy comes from its `ShapedDevices.csv` row, which already
+//! carries operator overrides once the topology is rebuilt. Active operator
+//! overrides are also overlaid here, so a bus request takes effect without
+//! waiting for a reload.
+
+use crate::override_writer;
+use fxhash::FxHashMap;
+use lqos_bus::{BusResponse, OverrideLayerSelection, OverrideMutation};
+use lqos_config::{CircuitAccessPolicy, Config};
+use lqos_overrides::{CircuitAdjustment, OverrideLayer, OverrideStore};
+use lqos_utils::hash_to_i64;
+use lqos_utils::unix_time::unix_now;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// Programs the walled garden from the config and applies current policies.
+pub fn start_walled_garden(config: &Config) {
+    program_walled_garden(config);
+    reconcile();
+}
+
+/// Programs what restricted circuits may still reach.
+pub fn program_walled_garden(config: &Config) {
+    if let Err(err) = lqos_sys::set_walled_garden(&config.walled_garden) {
+        warn!("Unable to program the walled garden: {err:?}");
+    }
+}
+
+fn desired_policies() -> HashMap<u64, CircuitAccessPolicy> {
+    let mut by_circuit: FxHashMap<i64, CircuitAccessPolicy> = FxHashMap::default();
+    let shaped = lqos_network_devices::shaped_devices_snapshot();
+    for device in shaped.devices.iter() {
+        if !device.access_policy.is_open() {
+            by_circuit.insert(device.circuit_hash, device.access_policy);
+        }
+    }
+
+    match OverrideStore::load_layer(OverrideLayer::Operator) {
+        Ok(overrides) => {
+            let now = unix_now().unwrap_or(0);
+            for adjustment in overrides.circuit_adjustments() {
+                if let CircuitAdjustment::CircuitAdjustAccessPolicy {
+                    circuit_id,
+                    access_policy,
+                    schedule,
+                } = adjustment
+                    && schedule.is_active_at(now)
+                {
+                    by_circuit.insert(hash_to_i64(circuit_id), *access_policy);
+                }
+            }
+        }
+        Err(err) => warn!("Unable to load operator overrides for access policies: {err:?}"),
+    }
+
+    by_circuit
+        .into_iter()
+        .filter(|(_, policy)| !policy.is_open())
+        .map(|(hash, policy)| (hash as u64, policy))
+        .collect()
+}
+
+/// Brings the dataplane's per-IP access policies in line with the shaped
+/// devices and operator overrides.
+pub fn reconcile() {
+    let policies = desired_policies();
+    match lqos_sys::apply_circuit_access_policies(&policies) {
+        Ok(0) => {}
+        Ok(changed) => info!(
+            "Access policies updated on {changed} mapped IPs ({} restricted circuits)",
+            policies.len()
+        ),
+        Err(err) => warn!("Unable to apply circuit access policies: {err:?}"),
+    }
+}
+
+/// Handles `BusRequest::SetCircuitAccessPolicy`.
+pub fn set_circuit_access_policy(
+    circuit_id: &str,
+    policy: Option<CircuitAccessPolicy>,
+) -> BusResponse {
+    if circuit_id.trim().is_empty() {
+        return BusResponse::Fail("circuit_id is required".to_string());
+    }
+    let mutation = OverrideMutation::SetCircuitAccessPolicy {
+        circuit_id: circuit_id.to_string(),
+        policy,
+    };
+    match override_writer::apply_mutation_batch(OverrideLayerSelection::Operator, vec![mutation]) {
+        Ok(_) => {
+            reconcile();
+            BusResponse::Ack
+        }
+        Err(err) => BusResponse::Fail(err.to_string()),
+    }
+}
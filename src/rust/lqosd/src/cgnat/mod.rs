@@ -0,0 +1,343 @@
+//! CGNAT-aware shaping: shared public addresses classified by port block.
+//!
+//! Port blocks come from the deterministic NAT rules in `[cgnat]` and, when
+//! `syslog_listen` is set, from the CGNAT's port-block-allocation syslog. The
+//! table here mirrors what is loaded into the dataplane; throughput tracking
+//! uses it to attribute flows on a shared public address to the subscriber.
+
+mod pba_log;
+
+use fxhash::{FxHashMap, FxHashSet};
+use lqos_config::{CgnatPool, CgnatPortBlock, Config};
+use lqos_sys::flowbee_data::FlowbeeKey;
+use lqos_utils::XdpIpAddress;
+use once_cell::sync::Lazy;
+use parking_lot::{RwLock, RwLockReadGuard};
+use pba_log::{PortBlockAction, PortBlockEvent, parse_port_block_event};
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// How long allocation events are batched before the dataplane is updated.
+const SYSLOG_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+type BlockKey = (Ipv4Addr, u32);
+
+#[derive(Default)]
+pub(crate) struct CgnatTable {
+    pools: FxHashMap<Ipv4Addr, CgnatPool>,
+    deterministic: FxHashMap<BlockKey, Ipv4Addr>,
+    logged: FxHashMap<BlockKey, Ipv4Addr>,
+}
+
+static TABLE: Lazy<RwLock<CgnatTable>> = Lazy::new(|| RwLock::new(CgnatTable::default()));
+
+impl CgnatTable {
+    fn from_config(config: &Config) -> Self {
+        if !config.cgnat.enabled {
+            return Self::default();
+        }
+        Self {
+            pools: config
+                .cgnat
+                .pools()
+                .into_iter()
+                .map(|pool| (pool.public_ip, pool))
+                .collect(),
+            deterministic: config
+                .cgnat
+                .deterministic_blocks()
+                .into_iter()
+                .map(|block| ((block.public_ip, block.block), block.inside_ip))
+                .collect(),
+            logged: FxHashMap::default(),
+        }
+    }
+
+    /// The subscriber using `port` on `public_ip`.
+    fn subscriber(&self, public_ip: Ipv4Addr, port: u16) -> Option<Ipv4Addr> {
+        let block = self.pools.get(&public_ip)?.block_for_port(port)?;
+        let key = (public_ip, block);
+        self.deterministic
+            .get(&key)
+            .or_else(|| self.logged.get(&key))
+            .copied()
+    }
+
+    /// The address a flow belongs to: the subscriber behind a shared public
+    /// address, otherwise the flow's local address.
+    pub(crate) fn flow_host(&self, key: &FlowbeeKey) -> XdpIpAddress {
+        if self.pools.is_empty() || !matches!(key.ip_protocol, 6 | 17) {
+            return key.local_ip;
+        }
+        // Flow keys hold the local port as `dst_port`.
+        match key.local_ip.as_ip() {
+            IpAddr::V4(public_ip) => self
+                .subscriber(public_ip, key.dst_port)
+                .map(|inside| XdpIpAddress::from_ip(IpAddr::V4(inside)))
+                .unwrap_or(key.local_ip),
+            IpAddr::V6(_) => key.local_ip,
+        }
+    }
+
+    /// Every block, with deterministic rules winning over logged ones.
+    fn blocks(&self) -> Vec<CgnatPortBlock> {
+        let logged = self
+            .logged
+            .iter()
+            .filter(|(key, _)| !self.deterministic.contains_key(key));
+        self.deterministic
+            .iter()
+            .chain(logged)
+            .map(|(&(public_ip, block), &inside_ip)| CgnatPortBlock {
+                public_ip,
+                block,
+                inside_ip,
+            })
+            .collect()
+    }
+
+    /// Records an allocation log event. Returns the blocks it touched.
+    fn apply_event(&mut self, event: &PortBlockEvent) -> Vec<BlockKey> {
+        let Some(pool) = self.pools.get(&event.public_ip).copied() else {
+            return Vec::new();
+        };
+        let (Some(first), Some(last)) = (
+            pool.block_for_port(event.first_port),
+            pool.block_for_port(event.last_port),
+        ) else {
+            return Vec::new();
+        };
+        let mut touched = Vec::new();
+        for block in first..=last {
+            let key = (event.public_ip, block);
+            match event.action {
+                PortBlockAction::Allocate => {
+                    self.logged.insert(key, event.inside_ip);
+                }
+                PortBlockAction::Release => {
+                    // A late release must not free a block that was already
+                    // handed to someone else.
+                    if self.logged.get(&key) != Some(&event.inside_ip) {
+                        continue;
+                    }
+                    self.logged.remove(&key);
+                }
+            }
+            touched.push(key);
+        }
+        touched
+    }
+}
+
+/// Read access to the port-block table, held for one pass over the flows.
+pub(crate) fn table() -> RwLockReadGuard<'static, CgnatTable> {
+    TABLE.read()
+}
+
+fn program_dataplane(table: &CgnatTable) {
+    let pools: Vec<CgnatPool> = table.pools.values().copied().collect();
+    if let Err(err) = lqos_sys::load_cgnat_mappings(&pools, &table.blocks()) {
+        warn!("Unable to load CGNAT port blocks: {err:?}");
+    }
+}
+
+/// Loads the `[cgnat]` pools and deterministic blocks into the dataplane and
+/// starts the allocation syslog listener. Changing `syslog_listen` needs a
+/// restart.
+pub(crate) fn start_cgnat(config: &Config) {
+    reload(config);
+    if !config.cgnat.enabled {
+        return;
+    }
+    let listen = config.cgnat.syslog_listen.trim().to_string();
+    if listen.is_empty() {
+        return;
+    }
+    let socket = match UdpSocket::bind(&listen) {
+        Ok(socket) => socket,
+        Err(err) => {
+            warn!("Unable to listen for CGNAT allocation syslog on {listen}: {err}");
+            return;
+        }
+    };
+    match std::thread::Builder::new()
+        .name("CGNAT Syslog".to_string())
+        .spawn(move || syslog_listener(socket))
+    {
+        Ok(_) => info!("Listening for CGNAT port-block allocations on {listen}"),
+        Err(err) => warn!("Failed to start the CGNAT syslog listener: {err:?}"),
+    }
+}
+
+/// Rebuilds the table from `config`, keeping logged blocks whose public
+/// address is still in a pool, and reloads the dataplane.
+pub(crate) fn reload(config: &Config) {
+    let mut table = TABLE.write();
+    let mut fresh = CgnatTable::from_config(config);
+    fresh.logged = std::mem::take(&mut table.logged)
+        .into_iter()
+        .filter(|((public_ip, _), _)| fresh.pools.contains_key(public_ip))
+        .collect();
+    *table = fresh;
+    if table.pools.is_empty() && table.logged.is_empty() && !config.cgnat.enabled {
+        // Still clear anything left from an earlier config.
+        program_dataplane(&table);
+        return;
+    }
+    info!(
+        "CGNAT: {} public addresses, {} deterministic and {} logged port blocks",
+        table.pools.len(),
+        table.deterministic.len(),
+        table.logged.len()
+    );
+    program_dataplane(&table);
+}
+
+fn syslog_listener(socket: UdpSocket) {
+    let _ = socket.set_read_timeout(Some(SYSLOG_FLUSH_INTERVAL));
+    let mut buffer = vec![0u8; 8192];
+    let mut touched: FxHashSet<BlockKey> = FxHashSet::default();
+    let mut last_flush = Instant::now();
+    loop {
+        if let Ok((len, _)) = socket.recv_from(&mut buffer) {
+            let text = String::from_utf8_lossy(&buffer[..len]);
+            let mut table = TABLE.write();
+            for line in text.lines() {
+                let event = parse_port_block_event(line, |ip| table.pools.contains_key(&ip));
+                match event {
+                    Some(event) => touched.extend(table.apply_event(&event)),
+                    None => debug!("Ignoring CGNAT syslog line: {line}"),
+                }
+            }
+        }
+        if last_flush.elapsed() >= SYSLOG_FLUSH_INTERVAL {
+            flush(&mut touched);
+            last_flush = Instant::now();
+        }
+    }
+}
+
+/// Sends the net effect of the batched events to the dataplane.
+fn flush(touched: &mut FxHashSet<BlockKey>) {
+    if touched.is_empty() {
+        return;
+    }
+    let table = TABLE.read();
+    let mut allocated = Vec::new();
+    let mut released = Vec::new();
+    for key in touched.drain() {
+        if table.deterministic.contains_key(&key) {
+            continue;
+        }
+        match table.logged.get(&key) {
+            Some(&inside_ip) => allocated.push(CgnatPortBlock {
+                public_ip: key.0,
+                block: key.1,
+                inside_ip,
+            }),
+            None => released.push(key),
+        }
+    }
+    if let Err(err) = lqos_sys::update_cgnat_port_blocks(&allocated, &released) {
+        warn!("Unable to update CGNAT port blocks: {err:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> CgnatTable {
+        let config = Config {
+            cgnat: toml::from_str(
+                r#"
+            enabled = true
+            [[deterministic]]
+            inside = "100.64.0.0/31"
+            outside = "203.0.113.8/32"
+            ports_per_subscriber = 32256
+            [[port_block_pools]]
+            outside = "203.0.113.16/32"
+            block_size = 512
+            "#,
+            )
+            .expect("section parses"),
+            ..Default::default()
+        };
+        CgnatTable::from_config(&config)
+    }
+
+    fn flow(local: &str, local_port: u16) -> FlowbeeKey {
+        let mut key = FlowbeeKey::default();
+        key.local_ip = XdpIpAddress::from_ip(local.parse().expect("test address"));
+        key.dst_port = local_port;
+        key.ip_protocol = 6;
+        key
+    }
+
+    fn host(address: &str) -> XdpIpAddress {
+        XdpIpAddress::from_ip(address.parse().expect("test address"))
+    }
+
+    #[test]
+    fn flows_on_shared_addresses_belong_to_the_subscriber() {
+        let table = table();
+        assert_eq!(
+            table.flow_host(&flow("203.0.113.8", 2000)),
+            host("100.64.0.0")
+        );
+        assert_eq!(
+            table.flow_host(&flow("203.0.113.8", 40000)),
+            host("100.64.0.1")
+        );
+        // Below the first port, unallocated blocks and other addresses keep
+        // the flow's own address.
+        assert_eq!(
+            table.flow_host(&flow("203.0.113.8", 80)),
+            host("203.0.113.8")
+        );
+        assert_eq!(
+            table.flow_host(&flow("203.0.113.16", 2000)),
+            host("203.0.113.16")
+        );
+        assert_eq!(table.flow_host(&flow("192.0.2.1", 2000)), host("192.0.2.1"));
+    }
+
+    #[test]
+    fn logged_blocks_follow_allocations_and_releases() {
+        let mut table = table();
+        let allocate = PortBlockEvent {
+            action: PortBlockAction::Allocate,
+            inside_ip: Ipv4Addr::new(100, 64, 1, 5),
+            public_ip: Ipv4Addr::new(203, 0, 113, 16),
+            first_port: 1536,
+            last_port: 2559,
+        };
+        // 1536-2559 spans blocks 1 and 2 of 512 ports from 1024.
+        assert_eq!(table.apply_event(&allocate).len(), 2);
+        assert_eq!(
+            table.flow_host(&flow("203.0.113.16", 2000)),
+            host("100.64.1.5")
+        );
+        assert_eq!(table.blocks().len(), 4);
+
+        // A stale release from a previous owner leaves the block alone.
+        let stale = PortBlockEvent {
+            action: PortBlockAction::Release,
+            inside_ip: Ipv4Addr::new(100, 64, 1, 9),
+            ..allocate
+        };
+        assert!(table.apply_event(&stale).is_empty());
+
+        let release = PortBlockEvent {
+            action: PortBlockAction::Release,
+            ..allocate
+        };
+        assert_eq!(table.apply_event(&release).len(), 2);
+        assert_eq!(
+            table.flow_host(&flow("203.0.113.16", 2000)),
+            host("203.0.113.16")
+        );
+    }
+}
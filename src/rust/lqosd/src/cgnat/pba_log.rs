@@ -0,0 +1,184 @@
+//! Parsing of port-block-allocation syslog lines.
+//!
+//! Vendors word these differently, so the parser looks for the parts every
+//! format shares: an allocate or release verb, the subscriber's inside
+//! address, the public address and a `first-last` port range. For example:
+//!
+//! ```text
+//! JSERVICES_NAT_PORT_BLOCK_ALLOC: 100.64.0.10 -> 203.0.113.1:2048-2559 0x5f3a
+//! RT_SRC_NAT_PBA_RELEASE: Subscriber 100.64.0.10 used/maximum [0/8] blocks, releases port block [2048-2559] from 203.0.113.1 in source pool cgn
+//! ```
+
+use std::net::Ipv4Addr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PortBlockAction {
+    Allocate,
+    Release,
+}
+
+/// One allocation or release reported by the CGNAT.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PortBlockEvent {
+    pub(crate) action: PortBlockAction,
+    pub(crate) inside_ip: Ipv4Addr,
+    pub(crate) public_ip: Ipv4Addr,
+    pub(crate) first_port: u16,
+    pub(crate) last_port: u16,
+}
+
+fn parse_port_range(token: &str) -> Option<(u16, u16)> {
+    let (first, last) = token.split_once('-')?;
+    let first = first.parse::<u16>().ok()?;
+    let last = last.parse::<u16>().ok()?;
+    (first <= last).then_some((first, last))
+}
+
+/// Parses one syslog line. `is_public` says whether an address belongs to a
+/// configured port-block pool, which tells the public address apart from the
+/// subscriber's (and from any address in the syslog header).
+pub(crate) fn parse_port_block_event(
+    line: &str,
+    is_public: impl Fn(Ipv4Addr) -> bool,
+) -> Option<PortBlockEvent> {
+    let lower = line.to_ascii_lowercase();
+    // "dealloc" contains "alloc", so releases are checked first.
+    let action = if ["releas", "dealloc", "free"]
+        .iter()
+        .any(|verb| lower.contains(verb))
+    {
+        PortBlockAction::Release
+    } else if lower.contains("alloc") {
+        PortBlockAction::Allocate
+    } else {
+        return None;
+    };
+
+    let mut public_ip = None;
+    let mut inside_ip = None;
+    let mut ports = None;
+    let tokens = line
+        .split(|c: char| c.is_whitespace() || matches!(c, '[' | ']' | '(' | ')' | ',' | ';' | '='))
+        .filter(|token| !token.is_empty());
+    for token in tokens {
+        // `203.0.113.1:2048-2559` carries the address and the range together.
+        let (address, range) = match token.split_once(':') {
+            Some((address, range)) => (address, Some(range)),
+            None => (token, None),
+        };
+        if let Ok(ip) = address.parse::<Ipv4Addr>() {
+            if is_public(ip) {
+                public_ip.get_or_insert(ip);
+            } else if public_ip.is_none() {
+                // The subscriber is the last other address before the public
+                // one; earlier ones belong to the syslog header.
+                inside_ip = Some(ip);
+            } else {
+                inside_ip.get_or_insert(ip);
+            }
+            if let Some(range) = range.and_then(parse_port_range) {
+                ports.get_or_insert(range);
+            }
+        } else if ports.is_none() {
+            ports = parse_port_range(token);
+        }
+    }
+
+    let (first_port, last_port) = ports?;
+    Some(PortBlockEvent {
+        action,
+        inside_ip: inside_ip?,
+        public_ip: public_ip?,
+        first_port,
+        last_port,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_public(ip: Ipv4Addr) -> bool {
+        ip.octets()[..3] == [203, 0, 113]
+    }
+
+    fn event(
+        action: PortBlockAction,
+        inside: &str,
+        public: &str,
+        ports: (u16, u16),
+    ) -> PortBlockEvent {
+        PortBlockEvent {
+            action,
+            inside_ip: inside.parse().expect("test address"),
+            public_ip: public.parse().expect("test address"),
+            first_port: ports.0,
+            last_port: ports.1,
+        }
+    }
+
+    #[test]
+    fn parses_arrow_style_allocations() {
+        let line = "<134>Oct 19 10:00:00 10.1.1.1 cgnat1 JSERVICES_NAT_PORT_BLOCK_ALLOC: 100.64.0.10 -> 203.0.113.1:2048-2559 0x5f3a";
+        assert_eq!(
+            parse_port_block_event(line, is_public),
+            Some(event(
+                PortBlockAction::Allocate,
+                "100.64.0.10",
+                "203.0.113.1",
+                (2048, 2559)
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_subscriber_style_allocations_and_releases() {
+        let alloc = "RT_SRC_NAT_PBA_ALLOC: Subscriber 100.64.0.11 used/maximum [1/8] blocks, allocates port block [2560-3071] from 203.0.113.2 in source pool cgn lsys_id: 0";
+        assert_eq!(
+            parse_port_block_event(alloc, is_public),
+            Some(event(
+                PortBlockAction::Allocate,
+                "100.64.0.11",
+                "203.0.113.2",
+                (2560, 3071)
+            ))
+        );
+        let release = "RT_SRC_NAT_PBA_RELEASE: Subscriber 100.64.0.11 used/maximum [0/8] blocks, releases port block [2560-3071] from 203.0.113.2 in source pool cgn lsys_id: 0";
+        assert_eq!(
+            parse_port_block_event(release, is_public).map(|e| e.action),
+            Some(PortBlockAction::Release)
+        );
+        let dealloc =
+            "NAT port block deallocated: inside=100.64.0.12 outside=203.0.113.3 ports=4096-4607";
+        assert_eq!(
+            parse_port_block_event(dealloc, is_public),
+            Some(event(
+                PortBlockAction::Release,
+                "100.64.0.12",
+                "203.0.113.3",
+                (4096, 4607)
+            ))
+        );
+    }
+
+    #[test]
+    fn ignores_unrelated_or_incomplete_lines() {
+        assert_eq!(
+            parse_port_block_event("sshd[1]: Accepted publickey for admin", is_public),
+            None
+        );
+        // No address from a configured pool.
+        assert_eq!(
+            parse_port_block_event(
+                "PBA_ALLOC: 100.64.0.10 -> 198.51.100.1:2048-2559",
+                is_public
+            ),
+            None
+        );
+        // No port range.
+        assert_eq!(
+            parse_port_block_event("PBA_ALLOC: 100.64.0.10 -> 203.0.113.1", is_public),
+            None
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! DHCP lease ingestion: lease files and hook events applied as dynamic circuits.
+//!
+//! One thread re-reads the configured lease files when they change and merges
+//! them with hook events from the optional Unix socket. Every cycle the active
+//! leases are resolved against `ShapedDevices.csv` and the range rules, and the
+//! result is reconciled with the dynamic circuits this module created: new and
+//! changed circuits are upserted, circuits whose last lease expired or was
+//! released are removed, and unchanged ones are refreshed before the dynamic
+//! circuit TTL would prune them.
+
+use crate::dynamic_circuits::{create_dynamic_circuit, remove_dynamic_circuit};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TrySendError};
+use lqos_bus::BusResponse;
+use lqos_config::{
+    Config, DhcpLeaseFileConfig, DhcpLeaseFileFormat, DynamicCircuitRangeRule, ShapedDevice,
+};
+use lqos_dhcp::{
+    HookEvent, LeaseMatchOptions, LeaseResolver, LeaseTable, parse_dnsmasq_leases,
+    parse_kea_memfile,
+};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+
+/// Circuit ID prefix of rule-based circuits, used to adopt them after a restart.
+const RULE_CIRCUIT_PREFIX: &str = "dhcp:";
+const HOOK_QUEUE_CAPACITY: usize = 4096;
+const HOOK_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Starts DHCP lease ingestion when `[dhcp_leases]` is enabled.
+///
+/// Lease files, rules and matching options are re-read every cycle; enabling
+/// the section or changing `hook_socket` needs a restart.
+pub(crate) fn start_dhcp_leases() {
+    let Ok(config) = lqos_config::load_config() else {
+        return;
+    };
+    if !config.dhcp_leases.enabled {
+        return;
+    }
+    if !dynamic_circuits_enabled(&config) {
+        warn!(
+            "dhcp_leases is enabled but dynamic_circuits.enabled is false; leases are read but not applied"
+        );
+    }
+
+    let (tx, rx) = crossbeam_channel::bounded(HOOK_QUEUE_CAPACITY);
+    let hook_socket = config.dhcp_leases.hook_socket.trim().to_string();
+    if !hook_socket.is_empty() {
+        match bind_hook_socket(&hook_socket) {
+            Ok(listener) => {
+                if let Err(err) = std::thread::Builder::new()
+                    .name("DHCP Hook Socket".to_string())
+                    .spawn(move || hook_listener(listener, tx))
+                {
+                    warn!("Failed to start the DHCP hook listener: {err:?}");
+                }
+            }
+            Err(err) => warn!("Unable to listen for DHCP hook events on {hook_socket}: {err}"),
+        }
+    }
+
+    match std::thread::Builder::new()
+        .name("DHCP Leases".to_string())
+        .spawn(move || DhcpLeaseRuntime::new().run(rx))
+    {
+        Ok(_) => info!(
+            "DHCP lease ingestion started with {} lease file(s)",
+            config.dhcp_leases.files.len()
+        ),
+        Err(err) => warn!("Failed to start DHCP lease ingestion: {err:?}"),
+    }
+}
+
+fn dynamic_circuits_enabled(config: &Config) -> bool {
+    config
+        .dynamic_circuits
+        .as_ref()
+        .is_some_and(|dynamic| dynamic.enabled)
+}
+
+fn bind_hook_socket(path: &str) -> std::io::Result<UnixListener> {
+    // Only replace a socket left by a previous run, never a regular file.
+    if let Ok(metadata) = std::fs::symlink_metadata(path)
+        && metadata.file_type().is_socket()
+    {
+        std::fs::remove_file(path)?;
+    }
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o660))?;
+    Ok(listener)
+}
+
+fn hook_listener(listener: UnixListener, tx: Sender<HookEvent>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => read_hook_events(stream, &tx),
+            Err(err) => warn!("DHCP hook socket accept failed: {err}"),
+        }
+    }
+}
+
+fn read_hook_events(stream: UnixStream, tx: &Sender<HookEvent>) {
+    let _ = stream.set_read_timeout(Some(HOOK_READ_TIMEOUT));
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match HookEvent::parse(&line) {
+            Ok(event) => match tx.try_send(event) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    warn!("DHCP hook event queue is full; dropping an event");
+                }
+                Err(TrySendError::Disconnected(_)) => return,
+            },
+            Err(err) => warn!("Ignoring DHCP hook event: {err}"),
+        }
+    }
+}
+
+struct LeaseFileState {
+    source: DhcpLeaseFileConfig,
+    modified: Option<SystemTime>,
+    failing: bool,
+}
+
+struct AppliedCircuit {
+    device: ShapedDevice,
+    refreshed: Option<Instant>,
+}
+
+struct CachedResolver {
+    resolver: LeaseResolver,
+    shaped_devices: Arc<lqos_config::ConfigShapedDevices>,
+    rules: Vec<DynamicCircuitRangeRule>,
+    options: LeaseMatchOptions,
+}
+
+struct DhcpLeaseRuntime {
+    table: LeaseTable,
+    files: Vec<LeaseFileState>,
+    resolver: Option<CachedResolver>,
+    applied: HashMap<String, AppliedCircuit>,
+    failures: HashMap<String, String>,
+    unresolved: usize,
+}
+
+impl DhcpLeaseRuntime {
+    fn new() -> Self {
+        // Adopt rule circuits left by a previous run, so that leases which
+        // ended while lqosd was down are removed rather than left to the TTL.
+        let applied = lqos_network_devices::dynamic_circuits_snapshot()
+            .iter()
+            .filter(|circuit| circuit.shaped.circuit_id.starts_with(RULE_CIRCUIT_PREFIX))
+            .map(|circuit| {
+                (
+                    circuit.shaped.circuit_id.clone(),
+                    AppliedCircuit {
+                        device: circuit.shaped.clone(),
+                        refreshed: None,
+                    },
+                )
+            })
+            .collect();
+        Self {
+            table: LeaseTable::new(),
+            files: Vec::new(),
+            resolver: None,
+            applied,
+            failures: HashMap::new(),
+            unresolved: 0,
+        }
+    }
+
+    fn run(mut self, rx: Receiver<HookEvent>) {
+        loop {
+            let poll_interval = match lqos_config::load_config() {
+                Ok(config) => {
+                    self.cycle(&config);
+                    config.dhcp_leases.poll_interval_seconds.max(1)
+                }
+                Err(err) => {
+                    warn!("DHCP lease ingestion unable to load the configuration: {err:?}");
+                    30
+                }
+            };
+            // Wake early for hook events, then take whatever else is queued.
+            let poll_interval = Duration::from_secs(poll_interval);
+            match rx.recv_timeout(poll_interval) {
+                Ok(event) => {
+                    self.apply_hook(&event);
+                    for event in rx.try_iter() {
+                        self.apply_hook(&event);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                // No hook socket is listening; only the lease files are polled.
+                Err(RecvTimeoutError::Disconnected) => std::thread::sleep(poll_interval),
+            }
+        }
+    }
+
+    fn apply_hook(&mut self, event: &HookEvent) {
+        if let Err(err) = self.table.apply_hook(event, unix_now()) {
+            warn!("Ignoring DHCP hook event for {}: {err}", event.address);
+        }
+    }
+
+    fn cycle(&mut self, config: &Config) {
+        let settings = &config.dhcp_leases;
+        if !settings.enabled || !dynamic_circuits_enabled(config) {
+            self.remove_all();
+            return;
+        }
+        // The active peer applies leases and replicates the circuits.
+        if crate::ha_sync::is_standby() {
+            self.applied.clear();
+            return;
+        }
+
+        self.reload_files(&settings.files);
+        let shaped_devices = lqos_network_devices::shaped_devices_snapshot();
+        let options = LeaseMatchOptions {
+            by_mac: settings.match_shaped_devices_by_mac,
+            by_option82: settings.match_shaped_devices_by_option82,
+            by_client_id: settings.match_shaped_devices_by_client_id,
+        };
+        // Only re-index ShapedDevices.csv when it, the rules or the options change.
+        let stale = self.resolver.as_ref().is_none_or(|cached| {
+            !Arc::ptr_eq(&cached.shaped_devices, &shaped_devices)
+                || cached.rules != settings.rules
+                || cached.options != options
+        });
+        if stale {
+            self.resolver = Some(CachedResolver {
+                resolver: LeaseResolver::new(
+                    &shaped_devices.devices,
+                    settings.rules.clone(),
+                    options,
+                ),
+                shaped_devices,
+                rules: settings.rules.clone(),
+                options,
+            });
+        }
+        let Some(cached) = self.resolver.as_ref() else {
+            return;
+        };
+
+        let now = unix_now();
+        self.table.prune(now);
+        let resolution = cached.resolver.resolve(&self.table.active_leases(now));
+
+        if resolution.unresolved.len() != self.unresolved {
+            self.unresolved = resolution.unresolved.len();
+            for lease in &resolution.unresolved {
+                debug!(
+                    "DHCP lease {}/{} not applied: {:?}",
+                    lease.key.address, lease.key.prefix_len, lease.reason
+                );
+            }
+            if self.unresolved > 0 {
+                info!(
+                    "{} DHCP lease(s) could not be placed on a circuit",
+                    self.unresolved
+                );
+            }
+        }
+
+        let refresh_after = Duration::from_secs(
+            config
+                .dynamic_circuits
+                .as_ref()
+                .map_or(300, |dynamic| dynamic.ttl_seconds)
+                / 2,
+        )
+        .max(Duration::from_secs(1));
+        self.reconcile(resolution.circuits, refresh_after);
+    }
+
+    fn reload_files(&mut self, sources: &[DhcpLeaseFileConfig]) {
+        if self.files.len() != sources.len()
+            || self
+                .files
+                .iter()
+                .zip(sources)
+                .any(|(state, source)| state.source != *source)
+        {
+            // Drop leases read from the old list; hook events stay.
+            for index in 0..self.files.len() {
+                self.table.replace_file(index, Vec::new());
+            }
+            self.files = sources
+                .iter()
+                .map(|source| LeaseFileState {
+                    source: source.clone(),
+                    modified: None,
+                    failing: false,
+                })
+                .collect();
+        }
+
+        for (index, state) in self.files.iter_mut().enumerate() {
+            let path = state.source.path.trim();
+            let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified());
+            if let Ok(modified) = modified
+                && state.modified == Some(modified)
+            {
+                continue;
+            }
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| match state.source.format {
+                    DhcpLeaseFileFormat::KeaMemfile => {
+                        parse_kea_memfile(&text).map_err(|err| err.to_string())
+                    }
+                    DhcpLeaseFileFormat::Dnsmasq => Ok(parse_dnsmasq_leases(&text)),
+                });
+            match parsed {
+                Ok(file) => {
+                    if file.skipped_rows > 0 {
+                        debug!(
+                            "Skipped {} unreadable row(s) in DHCP lease file {path}",
+                            file.skipped_rows
+                        );
+                    }
+                    self.table.replace_file(index, file.leases);
+                    state.modified = modified.ok();
+                    state.failing = false;
+                }
+                Err(err) => {
+                    // Keep the last good leases; they still expire on time.
+                    if !state.failing {
+                        warn!("Unable to read DHCP lease file {path}: {err}");
+                        state.failing = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn reconcile(&mut self, desired: Vec<ShapedDevice>, refresh_after: Duration) {
+        let mut wanted = HashSet::with_capacity(desired.len());
+        for device in desired {
+            let circuit_id = device.circuit_id.clone();
+            let current = self.applied.get(&circuit_id).is_some_and(|applied| {
+                applied.device == device
+                    && applied
+                        .refreshed
+                        .is_some_and(|refreshed| refreshed.elapsed() < refresh_after)
+            });
+            if !current {
+                match create_dynamic_circuit(device.clone()) {
+                    BusResponse::Fail(err) => self.record_failure(&circuit_id, err),
+                    _ => {
+                        self.failures.remove(&circuit_id);
+                        self.applied.insert(
+                            circuit_id.clone(),
+                            AppliedCircuit {
+                                device,
+                                refreshed: Some(Instant::now()),
+                            },
+                        );
+                    }
+                }
+            }
+            wanted.insert(circuit_id);
+        }
+
+        let ended: Vec<String> = self
+            .applied
+            .keys()
+            .filter(|circuit_id| !wanted.contains(*circuit_id))
+            .cloned()
+            .collect();
+        for circuit_id in ended {
+            self.remove(&circuit_id);
+        }
+        self.failures
+            .retain(|circuit_id, _| wanted.contains(circuit_id));
+    }
+
+    fn remove_all(&mut self) {
+        let applied: Vec<String> = self.applied.keys().cloned().collect();
+        for circuit_id in applied {
+            self.remove(&circuit_id);
+        }
+    }
+
+    fn remove(&mut self, circuit_id: &str) {
+        match remove_dynamic_circuit(circuit_id) {
+            BusResponse::Fail(err) => self.record_failure(circuit_id, err),
+            _ => {
+                self.applied.remove(circuit_id);
+                self.failures.remove(circuit_id);
+            }
+        }
+    }
+
+    fn record_failure(&mut self, circuit_id: &str, err: String) {
+        if self.failures.get(circuit_id) != Some(&err) {
+            warn!("DHCP lease circuit {circuit_id} could not be applied: {err}");
+            self.failures.insert(circuit_id.to_string(), err);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    lqos_utils::unix_time::unix_now().unwrap_or(0)
+}
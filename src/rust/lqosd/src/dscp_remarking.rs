@@ -0,0 +1,412 @@
+//! DSCP re-marking policies.
+//!
+//! Policies from `[dscp_remarking]` are loaded into the TC egress path, and
+//! every circuit direction in the queue structure is pointed at the policy
+//! assigned to the circuit, its nearest site or the default. Rules that
+//! match remote ASNs need the ASN table, so their prefixes are loaded once
+//! it is available and refreshed hourly. Each second the dataplane counters
+//! become per-circuit re-marking rates for circuit telemetry.
+
+use fxhash::{FxHashMap, FxHashSet};
+use lqos_bus::TcHandle;
+use lqos_config::{Config, DscpPolicyMode, DscpRemarkingConfig};
+use lqos_queue_tracker::{QUEUE_STRUCTURE, QueueNode, QueueStructure};
+use lqos_utils::units::DownUpOrder;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const TICK: Duration = Duration::from_secs(1);
+/// The ASN table reloads hourly, so the ASN prefixes follow.
+const ASN_REFRESH: Duration = Duration::from_secs(60 * 60);
+
+type DirectionKey = (TcHandle, bool);
+
+struct RemarkState {
+    config: DscpRemarkingConfig,
+    /// Bumped by `reload`, so the loop knows to reprogram.
+    generation: u64,
+}
+
+static STATE: Lazy<Mutex<RemarkState>> = Lazy::new(|| {
+    Mutex::new(RemarkState {
+        config: DscpRemarkingConfig::default(),
+        generation: 0,
+    })
+});
+
+/// Re-marked packets per second, by circuit ID.
+static RATES: Lazy<RwLock<FxHashMap<String, DownUpOrder<u64>>>> =
+    Lazy::new(|| RwLock::new(FxHashMap::default()));
+
+/// Packets per second the circuit's policy re-marked over the last second.
+pub(crate) fn remarked_per_second(circuit_id: &str) -> DownUpOrder<u64> {
+    RATES.read().get(circuit_id).copied().unwrap_or_default()
+}
+
+/// Circuit directions to program, with the policy index of each, and the
+/// circuit ID each direction belongs to.
+#[derive(Debug, Default, PartialEq)]
+struct Assignments {
+    policies: HashMap<DirectionKey, u32>,
+    circuits: FxHashMap<DirectionKey, String>,
+}
+
+fn assign_circuits(config: &DscpRemarkingConfig, queues: &[QueueNode]) -> Assignments {
+    let mut assignments = Assignments::default();
+    let mut sites = Vec::new();
+    for node in queues {
+        assign_node(config, node, &mut sites, &mut assignments);
+    }
+    assignments
+}
+
+fn assign_node<'a>(
+    config: &DscpRemarkingConfig,
+    node: &'a QueueNode,
+    sites: &mut Vec<&'a str>,
+    assignments: &mut Assignments,
+) {
+    if let Some(circuit_id) = node.circuit_id.as_deref() {
+        if node.device_id.is_some() {
+            return;
+        }
+        let nearest_first: Vec<&str> = sites.iter().rev().copied().collect();
+        let Some(index) = config.resolve_policy(circuit_id, &nearest_first) else {
+            return;
+        };
+        let policy = &config.policies[index];
+        if policy.mode == DscpPolicyMode::Trust {
+            return;
+        }
+        for (handle, upload) in [(node.class_id, false), (node.up_class_id, true)] {
+            if !policy.directions.includes(upload) || handle.as_u32() == 0 {
+                continue;
+            }
+            assignments.policies.insert((handle, upload), index as u32);
+            assignments
+                .circuits
+                .insert((handle, upload), circuit_id.to_string());
+        }
+        return;
+    }
+
+    let pushed = node.name.as_deref().map(|name| sites.push(name)).is_some();
+    for child in node.children.iter().chain(node.circuits.iter()) {
+        assign_node(config, child, sites, assignments);
+    }
+    if pushed {
+        sites.pop();
+    }
+}
+
+/// Maps every prefix of the ASNs used by rules to the mask of ASN groups it
+/// belongs to.
+fn asn_prefix_masks(
+    config: &DscpRemarkingConfig,
+    prefixes: &[(IpAddr, u8, u32)],
+) -> Vec<(IpAddr, u8, u64)> {
+    let mut masks: FxHashMap<u32, u64> = FxHashMap::default();
+    for (group, asns) in config.asn_groups().iter().enumerate() {
+        for asn in asns {
+            *masks.entry(*asn).or_default() |= 1u64 << group;
+        }
+    }
+    let mut by_prefix: FxHashMap<(IpAddr, u8), u64> = FxHashMap::default();
+    for (network, prefix, asn) in prefixes {
+        if let Some(mask) = masks.get(asn) {
+            *by_prefix.entry((*network, *prefix)).or_default() |= mask;
+        }
+    }
+    let mut result: Vec<(IpAddr, u8, u64)> = by_prefix
+        .into_iter()
+        .map(|((network, prefix), mask)| (network, prefix, mask))
+        .collect();
+    result.sort_unstable();
+    result
+}
+
+/// Turns two readings of the dataplane counters into per-circuit rates.
+fn circuit_rates(
+    previous: &FxHashMap<DirectionKey, u64>,
+    current: &FxHashMap<DirectionKey, u64>,
+    circuits: &FxHashMap<DirectionKey, String>,
+    seconds: f64,
+) -> FxHashMap<String, DownUpOrder<u64>> {
+    let mut rates: FxHashMap<String, DownUpOrder<u64>> = FxHashMap::default();
+    if seconds <= 0.0 {
+        return rates;
+    }
+    for (key, count) in current {
+        let (Some(before), Some(circuit_id)) = (previous.get(key), circuits.get(key)) else {
+            continue;
+        };
+        let per_second = (count.saturating_sub(*before) as f64 / seconds).round() as u64;
+        if per_second == 0 {
+            continue;
+        }
+        let rate = rates.entry(circuit_id.clone()).or_default();
+        if key.1 {
+            rate.up += per_second;
+        } else {
+            rate.down += per_second;
+        }
+    }
+    rates
+}
+
+/// Loads the configured policies and starts applying them to circuits.
+pub(crate) fn start_dscp_remarking(config: &Config) {
+    reload(config);
+    if let Err(err) = std::thread::Builder::new()
+        .name("DSCP Remarking".to_string())
+        .spawn(dscp_remarking_loop)
+    {
+        warn!("Failed to start DSCP re-marking: {err:?}");
+    }
+}
+
+/// Applies a changed `[dscp_remarking]` section on the next tick.
+pub(crate) fn reload(config: &Config) {
+    let mut state = STATE.lock();
+    if state.config != config.dscp_remarking {
+        state.config = config.dscp_remarking.clone();
+        state.generation += 1;
+    }
+}
+
+#[derive(Default)]
+struct LoopState {
+    generation: u64,
+    queues: Option<Arc<QueueStructure>>,
+    circuits: FxHashMap<DirectionKey, String>,
+    asn_refreshed: Option<Instant>,
+    counts: FxHashMap<DirectionKey, u64>,
+    counted: Option<Instant>,
+}
+
+fn dscp_remarking_loop() {
+    let mut local = LoopState::default();
+    loop {
+        std::thread::sleep(TICK);
+        let (config, generation) = {
+            let state = STATE.lock();
+            (state.config.clone(), state.generation)
+        };
+        let reloaded = generation != local.generation;
+        local.generation = generation;
+        if reloaded {
+            if let Err(err) = lqos_sys::set_dscp_policies(&config) {
+                warn!("Unable to load DSCP policies: {err:?}");
+            }
+            local.asn_refreshed = None;
+        }
+
+        let queues = QUEUE_STRUCTURE.load_full();
+        let queues_changed = local
+            .queues
+            .as_ref()
+            .is_none_or(|previous| !Arc::ptr_eq(previous, &queues));
+        if reloaded || queues_changed {
+            let assignments = match (config.enabled, queues.maybe_queues.as_ref()) {
+                (true, Some(nodes)) => assign_circuits(&config, nodes),
+                _ => Assignments::default(),
+            };
+            match lqos_sys::set_dscp_circuits(&assignments.policies) {
+                Ok(()) => {
+                    if reloaded && config.enabled {
+                        info!(
+                            "DSCP re-marking applied to {} circuit directions",
+                            assignments.policies.len()
+                        );
+                    }
+                    local.queues = Some(queues);
+                    local.circuits = assignments.circuits;
+                }
+                Err(err) => warn!("Unable to assign DSCP policies: {err:?}"),
+            }
+        }
+
+        if config.enabled
+            && local
+                .asn_refreshed
+                .is_none_or(|at| at.elapsed() >= ASN_REFRESH)
+        {
+            refresh_asn_prefixes(&config, &mut local);
+        }
+
+        if !config.enabled {
+            if !local.counts.is_empty() {
+                local.counts.clear();
+                RATES.write().clear();
+            }
+            continue;
+        }
+        match lqos_sys::dscp_remarked_counts() {
+            Ok(counts) => {
+                let counts: FxHashMap<DirectionKey, u64> = counts
+                    .into_iter()
+                    .map(|(handle, upload, count)| ((handle, upload), count))
+                    .collect();
+                let seconds = local.counted.map_or(0.0, |at| at.elapsed().as_secs_f64());
+                *RATES.write() = circuit_rates(&local.counts, &counts, &local.circuits, seconds);
+                local.counts = counts;
+                local.counted = Some(Instant::now());
+            }
+            Err(err) => warn!("Unable to read DSCP re-marking counters: {err:?}"),
+        }
+    }
+}
+
+fn refresh_asn_prefixes(config: &DscpRemarkingConfig, local: &mut LoopState) {
+    let asns: FxHashSet<u32> = config.asn_groups().into_iter().flatten().collect();
+    let prefixes = if asns.is_empty() {
+        Vec::new()
+    } else {
+        // Retried next tick while the ASN table loads.
+        let Some(prefixes) = crate::throughput_tracker::flow_data::asn_prefixes(&asns) else {
+            return;
+        };
+        asn_prefix_masks(config, &prefixes)
+    };
+    match lqos_sys::set_dscp_asn_prefixes(&prefixes) {
+        Ok(()) => {
+            if !prefixes.is_empty() {
+                info!("Loaded {} prefixes for DSCP ASN rules", prefixes.len());
+            }
+        }
+        Err(err) => warn!("Unable to load DSCP ASN prefixes: {err:?}"),
+    }
+    local.asn_refreshed = Some(Instant::now());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lqos_config::{DscpPolicyConfig, DscpPolicyDirections};
+
+    fn config() -> DscpRemarkingConfig {
+        DscpRemarkingConfig {
+            enabled: true,
+            default_policy: "trust".to_string(),
+            policies: vec![
+                DscpPolicyConfig {
+                    name: "trust".to_string(),
+                    ..Default::default()
+                },
+                DscpPolicyConfig {
+                    name: "wash".to_string(),
+                    mode: DscpPolicyMode::Wash,
+                    sites: vec!["Tower".to_string()],
+                    ..Default::default()
+                },
+                DscpPolicyConfig {
+                    name: "upload-only".to_string(),
+                    mode: DscpPolicyMode::Wash,
+                    directions: DscpPolicyDirections::Upload,
+                    circuits: vec!["c-2".to_string()],
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    fn circuit(circuit_id: &str, minor: u32) -> QueueNode {
+        QueueNode {
+            circuit_id: Some(circuit_id.to_string()),
+            class_id: TcHandle::from_u32(0x1_0000 | minor),
+            up_class_id: TcHandle::from_u32(0x2_0000 | minor),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn circuits_inherit_the_nearest_site_policy() {
+        let queues = vec![
+            QueueNode {
+                name: Some("Tower".to_string()),
+                children: vec![QueueNode {
+                    name: Some("AP".to_string()),
+                    circuits: vec![circuit("c-1", 3), circuit("c-2", 4)],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            QueueNode {
+                name: Some("Elsewhere".to_string()),
+                circuits: vec![circuit("c-3", 5)],
+                ..Default::default()
+            },
+        ];
+        let assignments = assign_circuits(&config(), &queues);
+
+        let c1_down = (TcHandle::from_u32(0x1_0003), false);
+        let c1_up = (TcHandle::from_u32(0x2_0003), true);
+        assert_eq!(assignments.policies.get(&c1_down), Some(&1));
+        assert_eq!(assignments.policies.get(&c1_up), Some(&1));
+        assert_eq!(
+            assignments.circuits.get(&c1_up).map(String::as_str),
+            Some("c-1")
+        );
+
+        // The circuit's own policy only re-marks upload.
+        assert!(
+            !assignments
+                .policies
+                .contains_key(&(TcHandle::from_u32(0x1_0004), false))
+        );
+        assert_eq!(
+            assignments
+                .policies
+                .get(&(TcHandle::from_u32(0x2_0004), true)),
+            Some(&2)
+        );
+
+        // The default policy trusts marks, so nothing is programmed.
+        assert_eq!(assignments.policies.len(), 3);
+    }
+
+    #[test]
+    fn asn_prefixes_carry_every_group_they_belong_to() {
+        let mut config = config();
+        config.policies[1].mode = DscpPolicyMode::Rules;
+        config.policies[1].rules = vec![
+            lqos_config::DscpMarkRuleConfig {
+                dscp: "AF41".to_string(),
+                ports: Vec::new(),
+                asns: vec![64500, 64501],
+            },
+            lqos_config::DscpMarkRuleConfig {
+                dscp: "CS1".to_string(),
+                ports: Vec::new(),
+                asns: vec![64501],
+            },
+        ];
+        let net_a: IpAddr = "198.51.100.0".parse().expect("ip");
+        let net_b: IpAddr = "2001:db8::".parse().expect("ip");
+        let masks = asn_prefix_masks(
+            &config,
+            &[(net_a, 24, 64500), (net_b, 32, 64501), (net_b, 48, 64999)],
+        );
+        assert_eq!(masks, vec![(net_a, 24, 0b01), (net_b, 32, 0b11)]);
+    }
+
+    #[test]
+    fn counters_become_per_circuit_rates() {
+        let down = (TcHandle::from_u32(0x1_0003), false);
+        let up = (TcHandle::from_u32(0x2_0003), true);
+        let mut circuits = FxHashMap::default();
+        circuits.insert(down, "c-1".to_string());
+        circuits.insert(up, "c-1".to_string());
+        let previous: FxHashMap<DirectionKey, u64> = [(down, 100), (up, 10)].into_iter().collect();
+        let current: FxHashMap<DirectionKey, u64> = [(down, 300), (up, 10)].into_iter().collect();
+
+        let rates = circuit_rates(&previous, &current, &circuits, 2.0);
+        assert_eq!(rates.get("c-1"), Some(&DownUpOrder::new(100, 0)));
+        assert!(circuit_rates(&previous, &current, &circuits, 0.0).is_empty());
+    }
+}
@@ -0,0 +1,368 @@
+//! Combines per-node summaries into the fleet view.
+//!
+//! Everything here is pure so ranking, totals and drill-through links can be
+//! tested without polling real shapers.
+
+use super::summary::{
+    FleetBakeryStatus, FleetEntityKind, FleetNodeSummary, FleetStormguardStatus, FleetThroughput,
+    url_encode,
+};
+use crate::node_manager::SearchResult;
+use serde::Serialize;
+
+/// Most rows shown in each fleet-wide ranking.
+const FLEET_RANKING_LIMIT: usize = 15;
+/// Most urgent issues shown fleet-wide.
+const FLEET_URGENT_LIMIT: usize = 25;
+
+/// The last poll of one node.
+#[derive(Clone, Debug)]
+pub(crate) struct FleetNodeState {
+    pub name: String,
+    /// Base URL of the node's web UI, or `None` for this node.
+    pub url: Option<String>,
+    pub last_poll_unix: Option<u64>,
+    pub last_success_unix: Option<u64>,
+    pub latency_ms: Option<u64>,
+    /// Why the last poll failed; `None` once a poll succeeds.
+    pub last_error: Option<String>,
+    /// The last summary received, kept while the node is unreachable.
+    pub summary: Option<FleetNodeSummary>,
+}
+
+impl FleetNodeState {
+    /// A node that has not been polled yet.
+    pub fn new(name: String, url: Option<String>) -> Self {
+        Self {
+            name,
+            url,
+            last_poll_unix: None,
+            last_success_unix: None,
+            latency_ms: None,
+            last_error: None,
+            summary: None,
+        }
+    }
+
+    fn reachable(&self) -> bool {
+        self.last_error.is_none() && self.summary.is_some()
+    }
+
+    fn link(&self, path: &str) -> String {
+        match &self.url {
+            Some(url) => format!("{}/{path}", url.trim_end_matches('/')),
+            None => path.to_string(),
+        }
+    }
+}
+
+/// Health of one node in the fleet view.
+#[derive(Clone, Debug, Serialize)]
+pub struct FleetNodeHealth {
+    pub name: String,
+    pub local: bool,
+    pub reachable: bool,
+    pub link: String,
+    pub last_poll_unix: Option<u64>,
+    pub last_success_unix: Option<u64>,
+    pub latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+    pub version: Option<String>,
+    pub throughput: Option<FleetThroughput>,
+    pub urgent_count: usize,
+    pub bakery: Option<FleetBakeryStatus>,
+    pub stormguard: Option<FleetStormguardStatus>,
+}
+
+/// A site or circuit ranked across the fleet.
+#[derive(Clone, Debug, Serialize)]
+pub struct FleetRankedRow {
+    pub node: String,
+    pub kind: FleetEntityKind,
+    pub label: String,
+    pub value: f32,
+    pub link: String,
+}
+
+/// An urgent issue raised on one node.
+#[derive(Clone, Debug, Serialize)]
+pub struct FleetUrgentRow {
+    pub node: String,
+    pub ts: u64,
+    pub severity: String,
+    pub code: String,
+    pub message: String,
+    pub link: String,
+}
+
+/// The combined view of every node.
+#[derive(Clone, Debug, Serialize)]
+pub struct FleetOverview {
+    pub generated_at_unix: u64,
+    pub nodes: Vec<FleetNodeHealth>,
+    pub nodes_reachable: usize,
+    /// Summed over reachable nodes.
+    pub throughput: FleetThroughput,
+    pub active_circuits: usize,
+    pub worst_by_rtt: Vec<FleetRankedRow>,
+    pub worst_by_retransmits: Vec<FleetRankedRow>,
+    pub urgent_count: usize,
+    pub urgent: Vec<FleetUrgentRow>,
+}
+
+/// One search result and the node it was found on.
+#[derive(Debug, Serialize)]
+pub struct FleetSearchHit {
+    pub node: String,
+    pub result: SearchResult,
+    pub link: String,
+}
+
+/// Builds the fleet view. Unreachable nodes appear in `nodes` with their last
+/// known state but are left out of totals, rankings and urgent issues, which
+/// would otherwise show stale data as current.
+pub(crate) fn aggregate(nodes: &[FleetNodeState], now_unix: u64) -> FleetOverview {
+    let mut overview = FleetOverview {
+        generated_at_unix: now_unix,
+        nodes: Vec::with_capacity(nodes.len()),
+        nodes_reachable: 0,
+        throughput: FleetThroughput::default(),
+        active_circuits: 0,
+        worst_by_rtt: Vec::new(),
+        worst_by_retransmits: Vec::new(),
+        urgent_count: 0,
+        urgent: Vec::new(),
+    };
+
+    for node in nodes {
+        let summary = node.summary.as_ref();
+        overview.nodes.push(FleetNodeHealth {
+            name: node.name.clone(),
+            local: node.url.is_none(),
+            reachable: node.reachable(),
+            link: node.link("index.html"),
+            last_poll_unix: node.last_poll_unix,
+            last_success_unix: node.last_success_unix,
+            latency_ms: node.latency_ms,
+            last_error: node.last_error.clone(),
+            version: summary.map(|summary| summary.version.clone()),
+            throughput: summary.map(|summary| summary.throughput.clone()),
+            urgent_count: summary.map_or(0, |summary| summary.urgent_count),
+            bakery: summary.map(|summary| summary.bakery.clone()),
+            stormguard: summary.map(|summary| summary.stormguard.clone()),
+        });
+        let Some(summary) = summary.filter(|_| node.reachable()) else {
+            continue;
+        };
+
+        overview.nodes_reachable += 1;
+        let total = &mut overview.throughput;
+        total
+            .bits_per_second
+            .checked_add(summary.throughput.bits_per_second);
+        total
+            .shaped_bits_per_second
+            .checked_add(summary.throughput.shaped_bits_per_second);
+        total
+            .packets_per_second
+            .checked_add(summary.throughput.packets_per_second);
+        overview.active_circuits += summary.bakery.active_circuits;
+
+        for (rows, ranked) in [
+            (&mut overview.worst_by_rtt, &summary.worst_by_rtt),
+            (
+                &mut overview.worst_by_retransmits,
+                &summary.worst_by_retransmits,
+            ),
+        ] {
+            rows.extend(ranked.iter().map(|entity| FleetRankedRow {
+                node: node.name.clone(),
+                kind: entity.kind,
+                label: entity.label.clone(),
+                value: entity.value,
+                link: node.link(&entity.path),
+            }));
+        }
+        overview.urgent_count += summary.urgent_count;
+        overview
+            .urgent
+            .extend(summary.urgent.iter().map(|issue| FleetUrgentRow {
+                node: node.name.clone(),
+                ts: issue.ts,
+                severity: issue.severity.clone(),
+                code: issue.code.clone(),
+                message: issue.message.clone(),
+                link: node.link("index.html"),
+            }));
+    }
+
+    for rows in [
+        &mut overview.worst_by_rtt,
+        &mut overview.worst_by_retransmits,
+    ] {
+        rows.sort_by(|left, right| {
+            right
+                .value
+                .total_cmp(&left.value)
+                .then_with(|| left.node.cmp(&right.node))
+        });
+        rows.truncate(FLEET_RANKING_LIMIT);
+    }
+    overview.urgent.sort_by_key(|row| std::cmp::Reverse(row.ts));
+    overview.urgent.truncate(FLEET_URGENT_LIMIT);
+    overview
+}
+
+/// The page a search result opens on the node it came from.
+pub(crate) fn search_result_path(result: &SearchResult) -> String {
+    match result {
+        SearchResult::Circuit { id, .. } => format!("circuit.html?id={}", url_encode(id)),
+        SearchResult::Device { circuit_id, .. } => {
+            format!("circuit.html?id={}", url_encode(circuit_id))
+        }
+        SearchResult::Site { idx, .. } => format!("tree.html?parent={idx}"),
+    }
+}
+
+/// Tags one node's search results with the node and a drill-through link.
+pub(crate) fn search_hits(
+    node: &FleetNodeState,
+    results: Vec<SearchResult>,
+) -> Vec<FleetSearchHit> {
+    results
+        .into_iter()
+        .map(|result| FleetSearchHit {
+            node: node.name.clone(),
+            link: node.link(&search_result_path(&result)),
+            result,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fleet::summary::{FleetRankedEntity, FleetUrgentIssue};
+    use lqos_utils::units::DownUpOrder;
+
+    fn summary(down: u64, rtt: &[(&str, f32)], urgent_ts: &[u64]) -> FleetNodeSummary {
+        FleetNodeSummary {
+            node_id: String::new(),
+            node_name: String::new(),
+            version: "2.0".to_string(),
+            generated_at_unix: 100,
+            throughput: FleetThroughput {
+                bits_per_second: DownUpOrder::new(down, down / 10),
+                ..FleetThroughput::default()
+            },
+            worst_by_rtt: rtt
+                .iter()
+                .map(|(circuit, value)| FleetRankedEntity {
+                    kind: FleetEntityKind::Circuit,
+                    label: circuit.to_string(),
+                    value: *value,
+                    path: format!("circuit.html?id={circuit}"),
+                })
+                .collect(),
+            worst_by_retransmits: Vec::new(),
+            urgent_count: urgent_ts.len(),
+            urgent: urgent_ts
+                .iter()
+                .map(|ts| FleetUrgentIssue {
+                    ts: *ts,
+                    severity: "Error".to_string(),
+                    code: "TEST".to_string(),
+                    message: String::new(),
+                })
+                .collect(),
+            bakery: FleetBakeryStatus {
+                active_circuits: 10,
+                ..FleetBakeryStatus::default()
+            },
+            stormguard: FleetStormguardStatus::default(),
+        }
+    }
+
+    fn node(
+        name: &str,
+        url: Option<&str>,
+        summary: FleetNodeSummary,
+        error: Option<&str>,
+    ) -> FleetNodeState {
+        FleetNodeState {
+            name: name.to_string(),
+            url: url.map(str::to_string),
+            last_poll_unix: Some(100),
+            last_success_unix: Some(90),
+            latency_ms: Some(12),
+            last_error: error.map(str::to_string),
+            summary: Some(summary),
+        }
+    }
+
+    #[test]
+    fn unreachable_nodes_are_listed_but_not_counted() {
+        let nodes = [
+            node("local", None, summary(1_000, &[("a", 40.0)], &[5]), None),
+            node(
+                "pop2",
+                Some("https://pop2:9123/"),
+                summary(2_000, &[("b", 90.0), ("c", 10.0)], &[7, 3]),
+                None,
+            ),
+            node(
+                "pop3",
+                Some("https://pop3:9123"),
+                summary(5_000, &[("d", 500.0)], &[9]),
+                Some("timed out"),
+            ),
+        ];
+        let overview = aggregate(&nodes, 200);
+
+        assert_eq!(overview.nodes.len(), 3);
+        assert_eq!(overview.nodes_reachable, 2);
+        assert!(!overview.nodes[2].reachable);
+        assert_eq!(overview.throughput.bits_per_second.down, 3_000);
+        assert_eq!(overview.active_circuits, 20);
+        assert_eq!(overview.urgent_count, 3);
+        assert_eq!(
+            overview.urgent.iter().map(|row| row.ts).collect::<Vec<_>>(),
+            vec![7, 5, 3]
+        );
+
+        let ranked: Vec<(&str, &str)> = overview
+            .worst_by_rtt
+            .iter()
+            .map(|row| (row.node.as_str(), row.link.as_str()))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("pop2", "https://pop2:9123/circuit.html?id=b"),
+                ("local", "circuit.html?id=a"),
+                ("pop2", "https://pop2:9123/circuit.html?id=c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn search_results_link_to_the_node_they_came_from() {
+        let pop2 = node("pop2", Some("http://pop2:9123"), summary(0, &[], &[]), None);
+        let hits = search_hits(
+            &pop2,
+            vec![
+                SearchResult::Device {
+                    circuit_id: "c 1".to_string(),
+                    name: "cpe".to_string(),
+                    circuit_name: "Circuit 1".to_string(),
+                },
+                SearchResult::Site {
+                    idx: 4,
+                    name: "Tower".to_string(),
+                },
+            ],
+        );
+        assert_eq!(hits[0].link, "http://pop2:9123/circuit.html?id=c%201");
+        assert_eq!(hits[1].link, "http://pop2:9123/tree.html?parent=4");
+    }
+}
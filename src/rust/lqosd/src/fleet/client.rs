@@ -0,0 +1,79 @@
+//! HTTP client for another node's fleet API.
+
+use super::API_KEY_HEADER;
+use super::summary::FleetNodeSummary;
+use crate::node_manager::SearchResult;
+use lqos_config::FleetMemberConfig;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Requests to one fleet member, authenticated with its API key.
+pub(super) struct FleetClient {
+    agent: ureq::Agent,
+    base_url: String,
+    api_key: String,
+}
+
+impl FleetClient {
+    /// Prepares requests to `member`.
+    ///
+    /// Side effects: reads the member's API key file.
+    pub fn new(member: &FleetMemberConfig, timeout: Duration) -> Result<Self, String> {
+        let api_key = std::fs::read_to_string(&member.api_key_file)
+            .map_err(|err| format!("Unable to read {}: {err}", member.api_key_file))?
+            .trim()
+            .to_string();
+        if api_key.is_empty() {
+            return Err(format!("{} is empty", member.api_key_file));
+        }
+        let tls = native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(member.accept_invalid_certificates)
+            .danger_accept_invalid_hostnames(member.accept_invalid_certificates)
+            .build()
+            .map_err(|err| format!("Unable to build a TLS connector: {err}"))?;
+        Ok(Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(timeout)
+                .tls_connector(Arc::new(tls))
+                .build(),
+            base_url: member.url.trim_end_matches('/').to_string(),
+            api_key,
+        })
+    }
+
+    /// Fetches the member's summary.
+    pub fn summary(&self) -> Result<FleetNodeSummary, String> {
+        self.get("summary")
+            .call()
+            .map_err(describe_error)?
+            .into_json()
+            .map_err(|err| format!("Invalid fleet summary: {err}"))
+    }
+
+    /// Searches the member's circuits, devices and sites.
+    pub fn search(&self, term: &str) -> Result<Vec<SearchResult>, String> {
+        self.get("search")
+            .query("term", term)
+            .call()
+            .map_err(describe_error)?
+            .into_json()
+            .map_err(|err| format!("Invalid fleet search results: {err}"))
+    }
+
+    fn get(&self, endpoint: &str) -> ureq::Request {
+        self.agent
+            .get(&format!("{}/fleet-api/{endpoint}", self.base_url))
+            .set(API_KEY_HEADER, &self.api_key)
+    }
+}
+
+fn describe_error(err: ureq::Error) -> String {
+    match err {
+        ureq::Error::Status(401, _) => "The member rejected its API key".to_string(),
+        ureq::Error::Status(404, _) => {
+            "The member does not serve the fleet API; is it running this version?".to_string()
+        }
+        ureq::Error::Status(code, _) => format!("The member answered HTTP {code}"),
+        ureq::Error::Transport(transport) => transport.to_string(),
+    }
+}
@@ -0,0 +1,243 @@
+//! Fleet view: one shaper aggregating several others.
+//!
+//! Every node serves a summary of its throughput, worst sites and circuits,
+//! urgent issues and Bakery/StormGuard state on `/fleet-api`, authenticated by
+//! one of its named local API keys. A node with `[fleet]` enabled polls the
+//! configured members in the background and combines their summaries with its
+//! own; searches fan out to the members on demand.
+
+mod aggregate;
+mod client;
+pub(crate) mod summary;
+
+use crate::node_manager::{SearchRequest, search_results};
+use aggregate::FleetNodeState;
+pub(crate) use aggregate::{FleetOverview, FleetSearchHit};
+use client::FleetClient;
+use lqos_config::{FleetConfig, FleetMemberConfig};
+use lqos_utils::unix_time::unix_now;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Header carrying the API key on fleet API requests, as with `lqos_api`.
+pub(crate) const API_KEY_HEADER: &str = "x-bearer";
+
+/// The last poll of each member, in configuration order.
+static MEMBERS: Lazy<Mutex<Vec<FleetNodeState>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Search results from every reachable node.
+#[derive(Debug, Serialize)]
+pub struct FleetSearchResults {
+    pub term: String,
+    pub hits: Vec<FleetSearchHit>,
+    /// Members that could not be searched, with the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Starts polling the fleet members when `[fleet]` is enabled.
+///
+/// Members are re-read from the configuration on every poll, so they can be
+/// edited, or the view disabled, without restarting; enabling it needs a restart.
+pub fn start_fleet_poller() {
+    let Ok(config) = lqos_config::load_config() else {
+        return;
+    };
+    if !config.fleet.enabled {
+        return;
+    }
+    match std::thread::Builder::new()
+        .name("Fleet Poller".to_string())
+        .spawn(poll_loop)
+    {
+        Ok(_) => info!(
+            "Fleet poller started for {} members",
+            config.fleet.members.len()
+        ),
+        Err(err) => warn!("Failed to start the fleet poller: {err:?}"),
+    }
+}
+
+fn poll_loop() {
+    loop {
+        let fleet = match lqos_config::load_config() {
+            Ok(config) => config.fleet.clone(),
+            Err(err) => {
+                warn!("Fleet poller unable to load the configuration: {err:?}");
+                std::thread::sleep(Duration::from_secs(30));
+                continue;
+            }
+        };
+        if fleet.enabled {
+            poll_members(&fleet);
+        } else {
+            MEMBERS.lock().clear();
+        }
+        std::thread::sleep(Duration::from_secs(fleet.poll_interval_seconds.max(1)));
+    }
+}
+
+/// Polls every member in parallel and replaces the stored states.
+fn poll_members(fleet: &FleetConfig) {
+    let timeout = Duration::from_secs(fleet.request_timeout_seconds.max(1));
+    let previous = MEMBERS.lock().clone();
+    let polled: Vec<FleetNodeState> = std::thread::scope(|scope| {
+        let handles: Vec<_> = fleet
+            .members
+            .iter()
+            .map(|member| {
+                let previous = previous
+                    .iter()
+                    .find(|state| state.name == member.name)
+                    .cloned();
+                scope.spawn(move || poll_member(member, timeout, previous))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .zip(&fleet.members)
+            .map(|(handle, member)| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| failed_state(member, None, "The poll panicked".to_string()))
+            })
+            .collect()
+    });
+    *MEMBERS.lock() = polled;
+}
+
+fn poll_member(
+    member: &FleetMemberConfig,
+    timeout: Duration,
+    previous: Option<FleetNodeState>,
+) -> FleetNodeState {
+    let started = Instant::now();
+    match FleetClient::new(member, timeout).and_then(|client| client.summary()) {
+        Ok(summary) => {
+            let now = unix_now().ok();
+            FleetNodeState {
+                name: member.name.clone(),
+                url: Some(member.url.clone()),
+                last_poll_unix: now,
+                last_success_unix: now,
+                latency_ms: Some(started.elapsed().as_millis() as u64),
+                last_error: None,
+                summary: Some(summary),
+            }
+        }
+        Err(err) => {
+            if previous
+                .as_ref()
+                .is_none_or(|state| state.last_error.is_none())
+            {
+                warn!("Fleet member {} is unreachable: {err}", member.name);
+            }
+            failed_state(member, previous, err)
+        }
+    }
+}
+
+/// A member's state after a failed poll, keeping what was last known.
+fn failed_state(
+    member: &FleetMemberConfig,
+    previous: Option<FleetNodeState>,
+    error: String,
+) -> FleetNodeState {
+    let previous = previous.unwrap_or_else(|| FleetNodeState::new(member.name.clone(), None));
+    FleetNodeState {
+        url: Some(member.url.clone()),
+        last_poll_unix: unix_now().ok(),
+        latency_ms: None,
+        last_error: Some(error),
+        ..previous
+    }
+}
+
+/// This node, summarized now.
+fn local_state() -> FleetNodeState {
+    let now = unix_now().ok();
+    FleetNodeState {
+        last_poll_unix: now,
+        last_success_unix: now,
+        latency_ms: Some(0),
+        summary: Some(summary::local_summary()),
+        ..local_node_identity()
+    }
+}
+
+/// The combined view of this node and the last poll of every member.
+///
+/// Side effects: summarizes this node, which may rebuild the executive
+/// dashboard cache; call it from a blocking context.
+pub(crate) fn fleet_overview() -> FleetOverview {
+    let mut nodes = vec![local_state()];
+    nodes.extend(MEMBERS.lock().iter().cloned());
+    aggregate::aggregate(&nodes, unix_now().unwrap_or_default())
+}
+
+/// Searches this node and every configured member in parallel.
+///
+/// Side effects: makes one request to each member; call it from a blocking
+/// context.
+pub(crate) fn fleet_search(term: &str) -> FleetSearchResults {
+    let local = local_node_identity();
+    let mut hits = aggregate::search_hits(
+        &local,
+        search_results(SearchRequest {
+            term: term.to_string(),
+        }),
+    );
+    let mut failed = Vec::new();
+
+    let Ok(config) = lqos_config::load_config() else {
+        return FleetSearchResults {
+            term: term.to_string(),
+            hits,
+            failed,
+        };
+    };
+    let timeout = Duration::from_secs(config.fleet.request_timeout_seconds.max(1));
+    let members = if config.fleet.enabled {
+        config.fleet.members.as_slice()
+    } else {
+        &[]
+    };
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = members
+            .iter()
+            .map(|member| {
+                scope.spawn(move || {
+                    FleetClient::new(member, timeout).and_then(|client| client.search(term))
+                })
+            })
+            .collect();
+        for (handle, member) in handles.into_iter().zip(members) {
+            match handle.join() {
+                Ok(Ok(results)) => {
+                    let node = FleetNodeState::new(member.name.clone(), Some(member.url.clone()));
+                    hits.extend(aggregate::search_hits(&node, results));
+                }
+                Ok(Err(err)) => failed.push((member.name.clone(), err)),
+                Err(_) => failed.push((member.name.clone(), "The search panicked".to_string())),
+            }
+        }
+    });
+
+    FleetSearchResults {
+        term: term.to_string(),
+        hits,
+        failed,
+    }
+}
+
+/// This node's name, without a summary.
+fn local_node_identity() -> FleetNodeState {
+    let name = lqos_config::load_config()
+        .ok()
+        .map(|config| config.node_name.clone())
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| "This node".to_string());
+    FleetNodeState::new(name, None)
+}
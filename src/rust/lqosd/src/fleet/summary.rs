@@ -0,0 +1,222 @@
+//! What one shaper reports to the fleet view.
+//!
+//! The same summary is served to other nodes over `/fleet-api/summary` and
+//! used directly for this node, so every node in the fleet is aggregated from
+//! the same shape of data.
+
+use crate::node_manager::local_api::executive::{
+    ExecutiveDashboardMetricRow, ExecutiveEntityKind, executive_dashboard_summary,
+};
+use crate::node_manager::local_api::urgent::urgent_list_data;
+use crate::throughput_tracker;
+use lqos_bakery::{BakeryMode, bakery_status_snapshot};
+use lqos_bus::BusResponse;
+use lqos_utils::units::DownUpOrder;
+use lqos_utils::unix_time::unix_now;
+use serde::{Deserialize, Serialize};
+
+const VERSION_STRING: &str = include_str!("../../../../VERSION_STRING");
+
+/// Most rows of each ranking and urgent issues a node reports.
+pub(crate) const FLEET_NODE_ROW_LIMIT: usize = 10;
+
+/// Whether a ranked row is a site or a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FleetEntityKind {
+    Site,
+    Circuit,
+}
+
+/// One site or circuit ranked by a quality metric on its own node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FleetRankedEntity {
+    pub kind: FleetEntityKind,
+    pub label: String,
+    /// Latest value: milliseconds for RTT, percent for retransmits.
+    pub value: f32,
+    /// Page on the reporting node, relative to its web UI root.
+    pub path: String,
+}
+
+/// Headline throughput of one node.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FleetThroughput {
+    pub bits_per_second: DownUpOrder<u64>,
+    pub shaped_bits_per_second: DownUpOrder<u64>,
+    pub packets_per_second: DownUpOrder<u64>,
+}
+
+/// An urgent issue raised on one node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FleetUrgentIssue {
+    pub ts: u64,
+    pub severity: String,
+    pub code: String,
+    pub message: String,
+}
+
+/// Bakery state of one node.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FleetBakeryStatus {
+    pub mode: String,
+    pub active_circuits: usize,
+    pub last_success_unix: Option<u64>,
+    pub last_failure_summary: Option<String>,
+    pub reload_required: bool,
+    pub passthrough_degraded: bool,
+}
+
+/// StormGuard state of one node.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FleetStormguardStatus {
+    pub enabled: bool,
+    pub mode: String,
+    pub phase: String,
+    pub last_error: Option<String>,
+}
+
+/// Everything one node contributes to the fleet view.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FleetNodeSummary {
+    pub node_id: String,
+    pub node_name: String,
+    pub version: String,
+    pub generated_at_unix: u64,
+    pub throughput: FleetThroughput,
+    pub worst_by_rtt: Vec<FleetRankedEntity>,
+    pub worst_by_retransmits: Vec<FleetRankedEntity>,
+    pub urgent_count: usize,
+    pub urgent: Vec<FleetUrgentIssue>,
+    pub bakery: FleetBakeryStatus,
+    pub stormguard: FleetStormguardStatus,
+}
+
+/// Builds this node's summary.
+///
+/// Side effects: may rebuild the executive dashboard cache, so call it from a
+/// blocking context.
+pub(crate) fn local_summary() -> FleetNodeSummary {
+    let config = lqos_config::load_config().ok();
+    let throughput = match throughput_tracker::current_throughput() {
+        BusResponse::CurrentThroughput {
+            bits_per_second,
+            shaped_bits_per_second,
+            packets_per_second,
+            ..
+        } => FleetThroughput {
+            bits_per_second,
+            shaped_bits_per_second,
+            packets_per_second,
+        },
+        _ => FleetThroughput::default(),
+    };
+    let dashboard = executive_dashboard_summary();
+    let urgent = urgent_list_data().items;
+    let bakery = bakery_status_snapshot();
+    let stormguard = lqos_stormguard::runtime_status();
+
+    FleetNodeSummary {
+        node_id: config
+            .as_ref()
+            .map(|config| config.node_id.clone())
+            .unwrap_or_default(),
+        node_name: config
+            .as_ref()
+            .map(|config| config.node_name.clone())
+            .unwrap_or_default(),
+        version: VERSION_STRING.trim().to_string(),
+        generated_at_unix: unix_now().unwrap_or_default(),
+        throughput,
+        worst_by_rtt: ranked_entities(&dashboard.top_rtt, rtt_latest),
+        worst_by_retransmits: ranked_entities(&dashboard.top_retransmit, scalar_latest),
+        urgent_count: urgent.len(),
+        urgent: urgent
+            .into_iter()
+            .take(FLEET_NODE_ROW_LIMIT)
+            .map(|item| FleetUrgentIssue {
+                ts: item.ts,
+                severity: item.severity,
+                code: item.code,
+                message: item.message,
+            })
+            .collect(),
+        bakery: FleetBakeryStatus {
+            mode: match bakery.mode {
+                BakeryMode::Idle => "Idle",
+                BakeryMode::ApplyingFullReload => "ApplyingFullReload",
+                BakeryMode::ApplyingLiveChange => "ApplyingLiveChange",
+            }
+            .to_string(),
+            active_circuits: bakery.active_circuits,
+            last_success_unix: bakery.last_success_unix,
+            last_failure_summary: bakery.last_failure_summary,
+            reload_required: bakery.reload_required,
+            passthrough_degraded: bakery.passthrough_degraded,
+        },
+        stormguard: FleetStormguardStatus {
+            enabled: stormguard.configured_enabled,
+            mode: stormguard.mode,
+            phase: stormguard.phase,
+            last_error: stormguard.last_error,
+        },
+    }
+}
+
+fn rtt_latest(row: &ExecutiveDashboardMetricRow) -> Option<f32> {
+    row.rtt_blocks
+        .as_ref()
+        .and_then(|blocks| blocks.rtt.iter().rev().flatten().copied().next())
+}
+
+fn scalar_latest(row: &ExecutiveDashboardMetricRow) -> Option<f32> {
+    row.scalar_blocks
+        .as_ref()
+        .and_then(|blocks| blocks.values.iter().rev().flatten().copied().next())
+}
+
+/// Converts executive dashboard rows, which are already ranked worst first,
+/// into fleet rows with a page path on this node.
+fn ranked_entities(
+    rows: &[ExecutiveDashboardMetricRow],
+    latest: fn(&ExecutiveDashboardMetricRow) -> Option<f32>,
+) -> Vec<FleetRankedEntity> {
+    rows.iter()
+        .filter_map(|row| {
+            let value = latest(row)?;
+            let (kind, path) = match row.entity_kind {
+                ExecutiveEntityKind::Circuit => (
+                    FleetEntityKind::Circuit,
+                    format!("circuit.html?id={}", url_encode(row.circuit_id.as_ref()?)),
+                ),
+                ExecutiveEntityKind::Site => (
+                    FleetEntityKind::Site,
+                    match row.tree.as_ref().and_then(|tree| tree.parent_index) {
+                        Some(index) => format!("tree.html?parent={index}"),
+                        None => "tree.html?parent=0".to_string(),
+                    },
+                ),
+                ExecutiveEntityKind::Asn => return None,
+            };
+            Some(FleetRankedEntity {
+                kind,
+                label: row.label.clone(),
+                value,
+                path,
+            })
+        })
+        .take(FLEET_NODE_ROW_LIMIT)
+        .collect()
+}
+
+/// Percent-encodes a query value.
+pub(crate) fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
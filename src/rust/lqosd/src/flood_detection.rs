@@ -0,0 +1,663 @@
+//! Per-circuit flood and abuse detection.
+//!
+//! Every second the per-circuit counters kept by XDP are turned into rates
+//! and compared with the `[flood_detection]` thresholds. A direction that
+//! stays over a threshold for `sustain_seconds` raises an urgent issue naming
+//! the circuit and, when a mitigation is configured, is capped or dropped for
+//! `mitigation_minutes`. A mitigation is extended while the flood continues.
+//! Operators lift mitigations over the bus, which also holds off automatic
+//! mitigation of the cleared circuits for `mitigation_minutes`.
+
+use crate::urgent;
+use fxhash::{FxHashMap, FxHashSet};
+use lqos_bus::{BusResponse, FloodMitigationEntry, UrgentSeverity, UrgentSource};
+use lqos_config::{Config, FloodDetectionConfig, FloodMitigationMode};
+use lqos_sys::{FloodAction, FloodCounters, FloodMitigation, MAX_FLOOD_MITIGATIONS};
+use lqos_utils::hash_to_i64;
+use lqos_utils::units::DownUpOrder;
+use lqos_utils::unix_time::unix_now;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const TICK: Duration = Duration::from_secs(1);
+/// Counters of circuits that sent nothing for this long leave the dataplane.
+const IDLE_EXPIRY: Duration = Duration::from_secs(300);
+const URGENT_CODE: &str = "FLOOD_DETECTED";
+const DIRECTIONS: [&str; 2] = ["download", "upload"];
+
+/// One direction's rates over the last interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct DirectionRates {
+    tcp_pps: f64,
+    syn_pps: f64,
+    new_flows_per_second: f64,
+    distinct_remotes_per_second: f64,
+    small_pps: f64,
+}
+
+struct Sample {
+    counters: FloodCounters,
+    taken: Instant,
+    last_active: Instant,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct DirectionMitigation {
+    action: FloodAction,
+    reason: String,
+    since_unix: u64,
+    expires_unix: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct CircuitMitigation {
+    circuit_id: String,
+    circuit_name: String,
+    /// [download, upload]
+    directions: [Option<DirectionMitigation>; 2],
+}
+
+#[derive(Default)]
+struct FloodState {
+    config: FloodDetectionConfig,
+    exempt: FxHashSet<u64>,
+    samples: FxHashMap<u64, Sample>,
+    /// Consecutive intervals each (circuit, direction) was over a threshold.
+    strikes: FxHashMap<(u64, usize), u32>,
+    mitigations: FxHashMap<u64, CircuitMitigation>,
+    /// Circuits cleared by an operator, left alone until the given time.
+    held_until: FxHashMap<u64, u64>,
+}
+
+static STATE: Lazy<Mutex<FloodState>> = Lazy::new(|| Mutex::new(FloodState::default()));
+
+/// The first threshold `rates` exceeds, described for the operator.
+fn exceeded_threshold(config: &FloodDetectionConfig, rates: &DirectionRates) -> Option<String> {
+    if config.syn_ratio > 0.0
+        && rates.tcp_pps > 0.0
+        && rates.tcp_pps >= config.syn_min_packets_per_second as f64
+    {
+        let ratio = rates.syn_pps / rates.tcp_pps;
+        if ratio >= config.syn_ratio {
+            return Some(format!(
+                "SYN ratio {ratio:.2} at {:.0} TCP packets/s",
+                rates.tcp_pps
+            ));
+        }
+    }
+    if config.new_flows_per_second > 0
+        && rates.new_flows_per_second >= config.new_flows_per_second as f64
+    {
+        return Some(format!("{:.0} new flows/s", rates.new_flows_per_second));
+    }
+    if config.distinct_remotes_per_second > 0
+        && rates.distinct_remotes_per_second >= config.distinct_remotes_per_second as f64
+    {
+        return Some(format!(
+            "{:.0} distinct remote addresses/s",
+            rates.distinct_remotes_per_second
+        ));
+    }
+    if config.small_packets_per_second > 0
+        && rates.small_pps >= config.small_packets_per_second as f64
+    {
+        return Some(format!("{:.0} small packets/s", rates.small_pps));
+    }
+    None
+}
+
+fn direction_value(value: &DownUpOrder<u64>, dir: usize) -> u64 {
+    if dir == 0 { value.down } else { value.up }
+}
+
+/// Rates between two reads of a circuit's counters, [download, upload].
+fn interval_rates(
+    previous: &FloodCounters,
+    current: &FloodCounters,
+    seconds: f64,
+) -> [DirectionRates; 2] {
+    let rate = |now: &DownUpOrder<u64>, before: &DownUpOrder<u64>, dir: usize| {
+        direction_value(now, dir).saturating_sub(direction_value(before, dir)) as f64 / seconds
+    };
+    [0, 1].map(|dir| DirectionRates {
+        tcp_pps: rate(&current.tcp_packets, &previous.tcp_packets, dir),
+        syn_pps: rate(&current.syn_packets, &previous.syn_packets, dir),
+        new_flows_per_second: rate(&current.new_flows, &previous.new_flows, dir),
+        // The distinct-remote estimate already covers just this interval.
+        distinct_remotes_per_second: direction_value(&current.distinct_remotes, dir) as f64
+            / seconds,
+        small_pps: rate(&current.small_packets, &previous.small_packets, dir),
+    })
+}
+
+/// The circuit ID and name for a circuit hash.
+fn circuit_identity(circuit_hash: u64) -> (String, String) {
+    let shaped = lqos_network_devices::shaped_devices_snapshot();
+    shaped
+        .devices
+        .iter()
+        .find(|device| device.circuit_hash as u64 == circuit_hash)
+        .map(|device| (device.circuit_id.clone(), device.circuit_name.clone()))
+        .unwrap_or_else(|| (format!("{circuit_hash:#x}"), String::new()))
+}
+
+fn dedupe_key(circuit_id: &str, dir: usize) -> String {
+    format!("{URGENT_CODE}_{circuit_id}_{}", DIRECTIONS[dir])
+}
+
+impl FloodState {
+    /// Records one interval of a circuit direction. Once the direction has
+    /// been over a threshold for `sustain_seconds`, returns the reason and
+    /// whether this is the first interval it has been.
+    fn observe(
+        &mut self,
+        circuit: u64,
+        dir: usize,
+        rates: &DirectionRates,
+    ) -> Option<(String, bool)> {
+        let key = (circuit, dir);
+        let Some(reason) = exceeded_threshold(&self.config, rates) else {
+            self.strikes.remove(&key);
+            return None;
+        };
+        let strikes = self.strikes.entry(key).or_default();
+        *strikes = strikes.saturating_add(1);
+        if *strikes < self.config.sustain_seconds {
+            return None;
+        }
+        Some((reason, *strikes == self.config.sustain_seconds))
+    }
+
+    /// Mitigates a flagged direction, or extends the mitigation already in
+    /// place. Returns true when the dataplane needs updating.
+    fn mitigate(
+        &mut self,
+        circuit: u64,
+        dir: usize,
+        reason: &str,
+        identity: impl FnOnce() -> (String, String),
+        now: u64,
+    ) -> bool {
+        let action = match self.config.mitigation {
+            FloodMitigationMode::None => return false,
+            FloodMitigationMode::PpsCap => FloodAction::PpsCap(self.config.mitigation_pps_cap),
+            FloodMitigationMode::Drop => FloodAction::Drop,
+        };
+        if self.exempt.contains(&circuit)
+            || self
+                .held_until
+                .get(&circuit)
+                .is_some_and(|until| *until > now)
+        {
+            return false;
+        }
+        if !self.mitigations.contains_key(&circuit)
+            && self.mitigations.len() >= MAX_FLOOD_MITIGATIONS
+        {
+            warn!("Flood mitigation limit of {MAX_FLOOD_MITIGATIONS} circuits reached");
+            return false;
+        }
+        let expires_unix = now + u64::from(self.config.mitigation_minutes) * 60;
+        let entry = self.mitigations.entry(circuit).or_insert_with(|| {
+            let (circuit_id, circuit_name) = identity();
+            CircuitMitigation {
+                circuit_id,
+                circuit_name,
+                directions: Default::default(),
+            }
+        });
+        let slot = &mut entry.directions[dir];
+        if let Some(existing) = slot.as_mut() {
+            existing.expires_unix = expires_unix;
+            return false;
+        }
+        *slot = Some(DirectionMitigation {
+            action,
+            reason: reason.to_string(),
+            since_unix: now,
+            expires_unix,
+        });
+        true
+    }
+
+    /// Lifts mitigations that have run their course. Returns true when any
+    /// did.
+    fn expire(&mut self, now: u64) -> bool {
+        let mut changed = false;
+        self.mitigations.retain(|_, mitigation| {
+            for slot in mitigation.directions.iter_mut() {
+                if slot.as_ref().is_some_and(|d| d.expires_unix <= now) {
+                    *slot = None;
+                    changed = true;
+                }
+            }
+            mitigation.directions.iter().any(Option::is_some)
+        });
+        self.held_until.retain(|_, until| *until > now);
+        changed
+    }
+
+    /// Lifts the mitigations of `circuit`, or of every circuit, and holds off
+    /// mitigating them again. Returns the cleared mitigations.
+    fn clear(&mut self, circuit: Option<u64>, now: u64) -> Vec<CircuitMitigation> {
+        let cleared: Vec<(u64, CircuitMitigation)> = match circuit {
+            Some(circuit) => self
+                .mitigations
+                .remove(&circuit)
+                .map(|mitigation| vec![(circuit, mitigation)])
+                .unwrap_or_default(),
+            None => self.mitigations.drain().collect(),
+        };
+        let until = now + u64::from(self.config.mitigation_minutes) * 60;
+        cleared
+            .into_iter()
+            .map(|(circuit, mitigation)| {
+                self.held_until.insert(circuit, until);
+                mitigation
+            })
+            .collect()
+    }
+
+    fn dataplane_mitigations(&self) -> Vec<FloodMitigation> {
+        self.mitigations
+            .iter()
+            .map(|(&circuit_hash, mitigation)| {
+                let action = |dir: usize| {
+                    mitigation.directions[dir]
+                        .as_ref()
+                        .map_or(FloodAction::None, |d| d.action)
+                };
+                FloodMitigation {
+                    circuit_hash,
+                    download: action(0),
+                    upload: action(1),
+                }
+            })
+            .collect()
+    }
+
+    /// Evaluates one read of the dataplane counters. Returns true when the
+    /// mitigations changed.
+    fn evaluate(&mut self, counters: Vec<FloodCounters>, now: u64) -> bool {
+        let taken = Instant::now();
+        let mut changed = false;
+        for current in counters {
+            let circuit = current.circuit_hash;
+            let Some(previous) = self.samples.remove(&circuit) else {
+                // Nothing to compare with yet.
+                self.samples.insert(
+                    circuit,
+                    Sample {
+                        counters: current,
+                        taken,
+                        last_active: taken,
+                    },
+                );
+                continue;
+            };
+            let seconds = taken
+                .duration_since(previous.taken)
+                .as_secs_f64()
+                .max(0.001);
+            let rates = interval_rates(&previous.counters, &current, seconds);
+            let last_active = if current.packets != previous.counters.packets {
+                taken
+            } else {
+                previous.last_active
+            };
+            self.samples.insert(
+                circuit,
+                Sample {
+                    counters: current,
+                    taken,
+                    last_active,
+                },
+            );
+
+            for (dir, rates) in rates.iter().enumerate() {
+                let Some((reason, newly_flagged)) = self.observe(circuit, dir, rates) else {
+                    continue;
+                };
+                let mitigated =
+                    self.mitigate(circuit, dir, &reason, || circuit_identity(circuit), now);
+                changed |= mitigated;
+                if newly_flagged {
+                    self.report(circuit, dir, &reason, mitigated);
+                }
+            }
+        }
+
+        let idle: Vec<u64> = self
+            .samples
+            .iter()
+            .filter(|(_, sample)| taken.duration_since(sample.last_active) >= IDLE_EXPIRY)
+            .map(|(&circuit, _)| circuit)
+            .collect();
+        for circuit in idle.iter() {
+            self.samples.remove(circuit);
+            self.strikes.remove(&(*circuit, 0));
+            self.strikes.remove(&(*circuit, 1));
+        }
+        if let Err(err) = lqos_sys::expire_flood_counters(idle) {
+            warn!("Unable to expire idle flood counters: {err:?}");
+        }
+        changed
+    }
+
+    /// Raises the urgent issue for a newly flagged direction.
+    fn report(&self, circuit: u64, dir: usize, reason: &str, mitigated: bool) {
+        let (circuit_id, circuit_name) = match self.mitigations.get(&circuit) {
+            Some(mitigation) => (
+                mitigation.circuit_id.clone(),
+                mitigation.circuit_name.clone(),
+            ),
+            None => circuit_identity(circuit),
+        };
+        let direction = DIRECTIONS[dir];
+        let (action, consequence) = match (mitigated, self.config.mitigation) {
+            (true, FloodMitigationMode::PpsCap) => (
+                "pps_cap",
+                format!(
+                    " Capped at {} packets/s for {} minutes.",
+                    self.config.mitigation_pps_cap, self.config.mitigation_minutes
+                ),
+            ),
+            (true, FloodMitigationMode::Drop) => (
+                "drop",
+                format!(
+                    " Dropping {direction} traffic for {} minutes.",
+                    self.config.mitigation_minutes
+                ),
+            ),
+            _ => ("none", String::new()),
+        };
+        let message = format!(
+            "Possible {direction} flood on circuit '{circuit_name}' ({circuit_id}): {reason}.{consequence}"
+        );
+        warn!("{message}");
+        let context = serde_json::json!({
+            "circuit_id": circuit_id,
+            "circuit_name": circuit_name,
+            "direction": direction,
+            "reason": reason,
+            "mitigation": action,
+        });
+        urgent::submit(
+            UrgentSource::System,
+            UrgentSeverity::Warning,
+            URGENT_CODE.to_string(),
+            message,
+            Some(context.to_string()),
+            Some(dedupe_key(&circuit_id, dir)),
+        );
+    }
+}
+
+fn program_dataplane(state: &FloodState) {
+    if let Err(err) = lqos_sys::set_flood_mitigations(&state.dataplane_mitigations()) {
+        warn!("Unable to load flood mitigations: {err:?}");
+    }
+}
+
+/// Clears mitigations left by an earlier run, applies the config and starts
+/// evaluating the counters every second.
+pub(crate) fn start_flood_detection(config: &Config) {
+    if let Err(err) = lqos_sys::set_flood_mitigations(&[]) {
+        warn!("Unable to clear flood mitigations: {err:?}");
+    }
+    reload(config);
+    if let Err(err) = std::thread::Builder::new()
+        .name("Flood Detection".to_string())
+        .spawn(flood_detection_loop)
+    {
+        warn!("Failed to start flood detection: {err:?}");
+    }
+}
+
+/// Applies a changed `[flood_detection]` section. Mitigations of circuits
+/// that are now exempt, or that are no longer allowed at all, are lifted.
+pub(crate) fn reload(config: &Config) {
+    let flood = &config.flood_detection;
+    let mut state = STATE.lock();
+    if let Err(err) = lqos_sys::set_flood_detection_enabled(flood.enabled) {
+        warn!("Unable to configure flood detection: {err:?}");
+    }
+    if !flood.enabled {
+        let circuits: Vec<u64> = state.samples.drain().map(|(circuit, _)| circuit).collect();
+        state.strikes.clear();
+        if let Err(err) = lqos_sys::expire_flood_counters(circuits) {
+            warn!("Unable to clear flood counters: {err:?}");
+        }
+    }
+    let exempt: FxHashSet<u64> = flood
+        .exempt_circuits
+        .iter()
+        .map(|circuit_id| hash_to_i64(circuit_id) as u64)
+        .collect();
+    let allowed = flood.enabled && flood.mitigation != FloodMitigationMode::None;
+    let before = state.mitigations.len();
+    state
+        .mitigations
+        .retain(|circuit, _| allowed && !exempt.contains(circuit));
+    let lifted = before - state.mitigations.len();
+    state.exempt = exempt;
+    state.config = flood.clone();
+    if lifted > 0 {
+        info!("Lifted {lifted} flood mitigations after a config change");
+        program_dataplane(&state);
+    }
+}
+
+fn flood_detection_loop() {
+    let mut read_failed = false;
+    loop {
+        std::thread::sleep(TICK);
+        let now = unix_now().unwrap_or(0);
+        let mut state = STATE.lock();
+        let mut changed = state.expire(now);
+        if state.config.enabled {
+            match lqos_sys::read_flood_counters() {
+                Ok(counters) => {
+                    read_failed = false;
+                    changed |= state.evaluate(counters, now);
+                }
+                Err(err) => {
+                    if !read_failed {
+                        warn!("Unable to read flood counters: {err:?}");
+                    }
+                    read_failed = true;
+                }
+            }
+        }
+        if changed {
+            program_dataplane(&state);
+        }
+    }
+}
+
+/// Lists the active flood mitigations.
+pub(crate) fn list_mitigations() -> BusResponse {
+    let drops = lqos_sys::flood_mitigation_drops().unwrap_or_default();
+    let state = STATE.lock();
+    let mut entries = Vec::new();
+    for (circuit, mitigation) in state.mitigations.iter() {
+        for (dir, slot) in mitigation.directions.iter().enumerate() {
+            let Some(direction) = slot else {
+                continue;
+            };
+            let (action, pps_cap) = match direction.action {
+                FloodAction::PpsCap(cap) => ("pps_cap", cap),
+                FloodAction::Drop => ("drop", 0),
+                FloodAction::None => continue,
+            };
+            entries.push(FloodMitigationEntry {
+                circuit_id: mitigation.circuit_id.clone(),
+                circuit_name: mitigation.circuit_name.clone(),
+                direction: DIRECTIONS[dir].to_string(),
+                action: action.to_string(),
+                pps_cap,
+                reason: direction.reason.clone(),
+                since_unix: direction.since_unix,
+                expires_unix: direction.expires_unix,
+                dropped_packets: drops
+                    .get(circuit)
+                    .map_or(0, |dropped| direction_value(dropped, dir)),
+            });
+        }
+    }
+    entries.sort_by(|a, b| (&a.circuit_id, &a.direction).cmp(&(&b.circuit_id, &b.direction)));
+    BusResponse::FloodMitigations(entries)
+}
+
+/// Lifts the mitigation of one circuit, or of every circuit.
+pub(crate) fn clear_mitigation(circuit_id: Option<&str>) -> BusResponse {
+    let now = unix_now().unwrap_or(0);
+    let mut state = STATE.lock();
+    let cleared = state.clear(circuit_id.map(|id| hash_to_i64(id) as u64), now);
+    if cleared.is_empty() {
+        return match circuit_id {
+            Some(id) => BusResponse::Fail(format!("Circuit {id} has no flood mitigation")),
+            None => BusResponse::Ack,
+        };
+    }
+    for mitigation in cleared.iter() {
+        for dir in 0..DIRECTIONS.len() {
+            urgent::clear_by_identity(URGENT_CODE, &dedupe_key(&mitigation.circuit_id, dir));
+        }
+    }
+    info!(
+        "Operator cleared flood mitigations for {} circuits",
+        cleared.len()
+    );
+    program_dataplane(&state);
+    BusResponse::Ack
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> FloodDetectionConfig {
+        FloodDetectionConfig {
+            enabled: true,
+            sustain_seconds: 2,
+            mitigation: FloodMitigationMode::PpsCap,
+            ..Default::default()
+        }
+    }
+
+    fn state() -> FloodState {
+        FloodState {
+            config: config(),
+            ..Default::default()
+        }
+    }
+
+    fn identity() -> (String, String) {
+        ("circuit-1".to_string(), "Circuit One".to_string())
+    }
+
+    #[test]
+    fn thresholds_flag_the_matching_metric() {
+        let config = config();
+        assert_eq!(
+            exceeded_threshold(&config, &DirectionRates::default()),
+            None
+        );
+
+        // A high SYN ratio only counts above the TCP packet floor.
+        let quiet_syns = DirectionRates {
+            tcp_pps: 100.0,
+            syn_pps: 90.0,
+            ..Default::default()
+        };
+        assert_eq!(exceeded_threshold(&config, &quiet_syns), None);
+        let syn_flood = DirectionRates {
+            tcp_pps: 4_000.0,
+            syn_pps: 3_600.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            exceeded_threshold(&config, &syn_flood).as_deref(),
+            Some("SYN ratio 0.90 at 4000 TCP packets/s")
+        );
+
+        let udp_flood = DirectionRates {
+            small_pps: 50_000.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            exceeded_threshold(&config, &udp_flood).as_deref(),
+            Some("50000 small packets/s")
+        );
+        let disabled = FloodDetectionConfig {
+            small_packets_per_second: 0,
+            ..config
+        };
+        assert_eq!(exceeded_threshold(&disabled, &udp_flood), None);
+    }
+
+    #[test]
+    fn directions_must_stay_over_a_threshold_to_be_flagged() {
+        let mut state = state();
+        let flood = DirectionRates {
+            new_flows_per_second: 5_000.0,
+            ..Default::default()
+        };
+        assert_eq!(state.observe(1, 1, &flood), None);
+        assert_eq!(
+            state.observe(1, 1, &flood),
+            Some(("5000 new flows/s".to_string(), true))
+        );
+        assert_eq!(
+            state.observe(1, 1, &flood),
+            Some(("5000 new flows/s".to_string(), false))
+        );
+        // A quiet interval starts the count again.
+        assert_eq!(state.observe(1, 1, &DirectionRates::default()), None);
+        assert_eq!(state.observe(1, 1, &flood), None);
+        // Directions are tracked separately.
+        assert_eq!(state.observe(1, 0, &flood), None);
+    }
+
+    #[test]
+    fn mitigations_expire_and_respect_exemptions_and_holds() {
+        let mut state = state();
+        assert!(state.mitigate(1, 1, "flood", identity, 1_000));
+        // Still flooding extends the mitigation without reprogramming.
+        assert!(!state.mitigate(1, 1, "flood", identity, 1_300));
+        let upload = state.mitigations[&1].directions[1]
+            .clone()
+            .expect("upload is mitigated");
+        assert_eq!(upload.action, FloodAction::PpsCap(1_000));
+        assert_eq!(upload.since_unix, 1_000);
+        assert_eq!(upload.expires_unix, 1_900);
+        assert_eq!(
+            state.dataplane_mitigations(),
+            vec![FloodMitigation {
+                circuit_hash: 1,
+                download: FloodAction::None,
+                upload: FloodAction::PpsCap(1_000),
+            }]
+        );
+        assert!(!state.expire(1_899));
+        assert!(state.expire(1_900));
+        assert!(state.mitigations.is_empty());
+
+        state.exempt.insert(2);
+        assert!(!state.mitigate(2, 0, "flood", identity, 1_000));
+
+        assert!(state.mitigate(3, 0, "flood", identity, 1_000));
+        let cleared = state.clear(Some(3), 1_100);
+        assert_eq!(cleared.len(), 1);
+        assert_eq!(cleared[0].circuit_id, "circuit-1");
+        assert!(!state.mitigate(3, 0, "flood", identity, 1_200));
+        state.expire(1_700);
+        assert!(state.mitigate(3, 0, "flood", identity, 1_700));
+
+        state.config.mitigation = FloodMitigationMode::None;
+        assert!(!state.mitigate(4, 0, "flood", identity, 1_000));
+    }
+}
@@ -0,0 +1,201 @@
+//! The kinds of runtime state replicated to a standby.
+//!
+//! Each component is captured as a canonical JSON payload, so both nodes hash
+//! identical state identically, and applied on the standby by diffing against
+//! what it already has.
+
+use lqos_bus::BusResponse;
+use lqos_config::ShapedDevice;
+use lqos_overrides::{OverrideFile, OverrideLayer, OverrideStore};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One kind of replicated state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Component {
+    OperatorOverrides,
+    StormguardOverrides,
+    TreeguardOverrides,
+    DynamicCircuits,
+    TreeguardVirtualization,
+}
+
+pub(super) const ALL_COMPONENTS: [Component; 5] = [
+    Component::OperatorOverrides,
+    Component::StormguardOverrides,
+    Component::TreeguardOverrides,
+    Component::DynamicCircuits,
+    Component::TreeguardVirtualization,
+];
+
+/// A component's current payload and its hash.
+pub(super) struct Captured {
+    pub hash: String,
+    pub payload: String,
+}
+
+/// What applying a component changed.
+pub(super) struct Applied {
+    /// The shaping inputs changed and LibreQoS must be reloaded.
+    pub needs_reload: bool,
+    /// Individual changes that failed; the component will be offered again.
+    pub errors: Vec<String>,
+}
+
+impl Component {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::OperatorOverrides => "operator_overrides",
+            Self::StormguardOverrides => "stormguard_overrides",
+            Self::TreeguardOverrides => "treeguard_overrides",
+            Self::DynamicCircuits => "dynamic_circuits",
+            Self::TreeguardVirtualization => "treeguard_virtualization",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ALL_COMPONENTS
+            .into_iter()
+            .find(|component| component.name() == name)
+    }
+
+    fn override_layer(self) -> Option<OverrideLayer> {
+        match self {
+            Self::OperatorOverrides => Some(OverrideLayer::Operator),
+            Self::StormguardOverrides => Some(OverrideLayer::Stormguard),
+            Self::TreeguardOverrides => Some(OverrideLayer::Treeguard),
+            Self::DynamicCircuits | Self::TreeguardVirtualization => None,
+        }
+    }
+
+    /// Captures this node's copy of the component.
+    ///
+    /// `virtualized_nodes` is the virtualization set this node currently owns.
+    ///
+    /// Side effects: reads the overrides files under the overrides lock.
+    pub fn capture(self, virtualized_nodes: &[String]) -> Result<Captured, String> {
+        let payload = match self.override_layer() {
+            Some(layer) => canonical_json(
+                &OverrideStore::load_layer(layer)
+                    .map_err(|err| format!("Unable to load {layer:?} overrides: {err}"))?,
+            )?,
+            None if self == Self::DynamicCircuits => canonical_json(&dynamic_circuit_devices())?,
+            None => canonical_json(&virtualized_nodes)?,
+        };
+        Ok(Captured {
+            hash: sha256::digest(payload.as_bytes()),
+            payload,
+        })
+    }
+
+    /// Makes this node's copy match `payload`.
+    ///
+    /// `virtualized_nodes` is the virtualization set replicated so far and is
+    /// updated to what was actually applied.
+    ///
+    /// Side effects: writes overrides files, creates or removes dynamic
+    /// circuits, or submits Bakery virtualization changes.
+    pub fn apply(
+        self,
+        payload: &str,
+        virtualized_nodes: &mut Vec<String>,
+    ) -> Result<Applied, String> {
+        if let Some(layer) = self.override_layer() {
+            let overrides: OverrideFile = serde_json::from_str(payload)
+                .map_err(|err| format!("Invalid replicated {layer:?} overrides: {err}"))?;
+            OverrideStore::save_layer(layer, &overrides)
+                .map_err(|err| format!("Unable to save replicated {layer:?} overrides: {err}"))?;
+            return Ok(Applied {
+                needs_reload: layer == OverrideLayer::Operator,
+                errors: Vec::new(),
+            });
+        }
+        if self == Self::DynamicCircuits {
+            let target: Vec<ShapedDevice> = serde_json::from_str(payload)
+                .map_err(|err| format!("Invalid replicated dynamic circuits: {err}"))?;
+            return Ok(apply_dynamic_circuits(target));
+        }
+        let target: Vec<String> = serde_json::from_str(payload)
+            .map_err(|err| format!("Invalid replicated virtualization set: {err}"))?;
+        Ok(apply_virtualization(target, virtualized_nodes))
+    }
+}
+
+/// Serializes through `serde_json::Value`, whose maps are ordered, so hash
+/// maps inside the state do not make identical state hash differently.
+fn canonical_json(value: &impl Serialize) -> Result<String, String> {
+    serde_json::to_value(value)
+        .map(|value| value.to_string())
+        .map_err(|err| format!("Unable to serialize replicated state: {err}"))
+}
+
+/// The dynamic circuit overlay without its activity timestamps, sorted by circuit.
+fn dynamic_circuit_devices() -> Vec<ShapedDevice> {
+    let mut devices: Vec<ShapedDevice> = lqos_network_devices::dynamic_circuits_snapshot()
+        .iter()
+        .map(|circuit| circuit.shaped.clone())
+        .collect();
+    devices.sort_by(|a, b| a.circuit_id.cmp(&b.circuit_id));
+    devices
+}
+
+fn apply_dynamic_circuits(target: Vec<ShapedDevice>) -> Applied {
+    let current: BTreeMap<String, ShapedDevice> = dynamic_circuit_devices()
+        .into_iter()
+        .map(|device| (device.circuit_id.clone(), device))
+        .collect();
+    let target: BTreeMap<String, ShapedDevice> = target
+        .into_iter()
+        .map(|device| (device.circuit_id.clone(), device))
+        .collect();
+
+    let mut errors = Vec::new();
+    for circuit_id in current.keys().filter(|id| !target.contains_key(*id)) {
+        if let BusResponse::Fail(err) = crate::dynamic_circuits::remove_dynamic_circuit(circuit_id)
+        {
+            errors.push(format!("remove dynamic circuit {circuit_id}: {err}"));
+        }
+    }
+    for (circuit_id, device) in &target {
+        if current.get(circuit_id) == Some(device) {
+            continue;
+        }
+        if let BusResponse::Fail(err) =
+            crate::dynamic_circuits::create_dynamic_circuit(device.clone())
+        {
+            errors.push(format!("create dynamic circuit {circuit_id}: {err}"));
+        }
+    }
+    Applied {
+        needs_reload: false,
+        errors,
+    }
+}
+
+fn apply_virtualization(target: Vec<String>, applied: &mut Vec<String>) -> Applied {
+    let target: BTreeSet<String> = target.into_iter().collect();
+    let mut current: BTreeSet<String> = applied.iter().cloned().collect();
+    let mut errors = Vec::new();
+
+    let changes: Vec<(String, bool)> = current
+        .difference(&target)
+        .map(|node| (node.clone(), false))
+        .chain(target.difference(&current).map(|node| (node.clone(), true)))
+        .collect();
+    for (node, virtualized) in changes {
+        match crate::treeguard::bakery::submit_node_virtualization_live(&node, virtualized) {
+            Ok(()) if virtualized => {
+                current.insert(node);
+            }
+            Ok(()) => {
+                current.remove(&node);
+            }
+            Err(err) => errors.push(format!("virtualize {node}={virtualized}: {err}")),
+        }
+    }
+    *applied = current.into_iter().collect();
+    Applied {
+        needs_reload: false,
+        errors,
+    }
+}
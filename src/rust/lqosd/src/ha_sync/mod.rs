@@ -0,0 +1,713 @@
+//! Active/standby replication of runtime state between two shapers.
+//!
+//! The active node owns dynamic circuits, RADIUS sessions, TreeGuard
+//! virtualization decisions and the operator, StormGuard and TreeGuard
+//! override layers, and copies them to its standby peer over an authenticated
+//! TLS channel. The standby applies what it receives and holds StormGuard,
+//! TreeGuard, dynamic-circuit expiry, RADIUS circuit application and the
+//! override schedule, so that it changes nothing on its own until promoted.
+//!
+//! Roles are decided by [`role::decide`]; promotions persist a higher epoch
+//! under the state directory so a restarted node cannot win a stale claim.
+
+mod components;
+mod protocol;
+mod role;
+mod transport;
+
+use crate::program_control;
+use crate::throughput_tracker::THROUGHPUT_TRACKER;
+use components::{ALL_COMPONENTS, Captured, Component};
+use lqos_bus::{BusResponse, HaComponentStatus, HaRole, HaStatus};
+use lqos_config::{Config, HaConfig};
+use lqos_radius::{AccountingEvent, AcctStatusType};
+use lqos_utils::unix_time::unix_now;
+use native_tls::{TlsAcceptor, TlsConnector};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use protocol::{HaMessage, write_message};
+use role::{PeerReport, RoleDecision, RoleInputs, RoleState};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+const ROLE_FILENAME: &str = "role.json";
+const RADIUS_REPLICA_QUEUE_CAPACITY: usize = 4096;
+const RADIUS_JOURNAL_LIMIT: usize = 100_000;
+const RADIUS_SESSION_RETENTION_SECONDS: u64 = 24 * 60 * 60;
+const RADIUS_STOP_RETENTION_SECONDS: u64 = 10 * 60;
+
+/// A RADIUS Accounting-Request replicated from the active node.
+pub(crate) struct RadiusReplica {
+    pub source: SocketAddr,
+    pub bytes: Vec<u8>,
+}
+
+/// Settings fixed for the lifetime of the daemon.
+struct HaSettings {
+    config: HaConfig,
+    node_name: String,
+    secret: Vec<u8>,
+    state_directory: PathBuf,
+    acceptor: TlsAcceptor,
+    connector: TlsConnector,
+}
+
+impl HaSettings {
+    fn load(config: &Config) -> Result<Self, String> {
+        let ha = config.ha.clone();
+        let secret = std::fs::read_to_string(&ha.shared_secret_file)
+            .map_err(|err| format!("Unable to read {}: {err}", ha.shared_secret_file))?
+            .trim()
+            .as_bytes()
+            .to_vec();
+        if secret.is_empty() {
+            return Err(format!("{} is empty", ha.shared_secret_file));
+        }
+        let node_name = if config.node_name.trim().is_empty() {
+            config.node_id.clone()
+        } else {
+            config.node_name.clone()
+        };
+        Ok(Self {
+            acceptor: transport::build_acceptor(&ha)?,
+            connector: transport::build_connector(&ha)?,
+            state_directory: config.resolved_state_directory().join("ha"),
+            config: ha,
+            node_name,
+            secret,
+        })
+    }
+
+    fn failover_timeout(&self) -> Duration {
+        Duration::from_secs(self.config.failover_seconds)
+    }
+}
+
+#[derive(Default)]
+struct HaState {
+    role: Option<RoleState>,
+    carrying_traffic: bool,
+    traffic_since_unix: Option<u64>,
+    peer: Option<PeerReport>,
+    peer_carrying_traffic: Option<bool>,
+    peer_hashes: BTreeMap<String, String>,
+    local_hashes: BTreeMap<String, String>,
+    /// Standby only: the local hash right after each component was applied.
+    applied_hashes: BTreeMap<String, String>,
+    last_applied_unix: BTreeMap<String, u64>,
+    /// Standby only: the virtualization set applied from the peer.
+    replicated_virtualized_nodes: Vec<String>,
+    radius_packets_sent: u64,
+    radius_packets_applied: u64,
+    conflicts: u64,
+    last_conflict: Option<String>,
+    last_error: Option<String>,
+    reload_pending: bool,
+}
+
+/// The newest Accounting-Request per session, replayed to a peer on connect.
+#[derive(Default)]
+struct RadiusJournal {
+    next_seq: u64,
+    last_pruned_unix: u64,
+    entries: HashMap<String, RadiusJournalEntry>,
+}
+
+struct RadiusJournalEntry {
+    seq: u64,
+    recorded_unix: u64,
+    stop: bool,
+    source: SocketAddr,
+    bytes: Vec<u8>,
+}
+
+static SETTINGS: OnceLock<Arc<HaSettings>> = OnceLock::new();
+static STANDBY: AtomicBool = AtomicBool::new(false);
+static STATE: Lazy<Mutex<HaState>> = Lazy::new(|| Mutex::new(HaState::default()));
+static RADIUS_JOURNAL: Lazy<Mutex<RadiusJournal>> =
+    Lazy::new(|| Mutex::new(RadiusJournal::default()));
+static WAKE: Lazy<(
+    crossbeam_channel::Sender<()>,
+    crossbeam_channel::Receiver<()>,
+)> = Lazy::new(|| crossbeam_channel::bounded(1));
+type RadiusReplicaChannel = (
+    mpsc::Sender<RadiusReplica>,
+    Mutex<Option<mpsc::Receiver<RadiusReplica>>>,
+);
+static RADIUS_REPLICAS: Lazy<RadiusReplicaChannel> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel(RADIUS_REPLICA_QUEUE_CAPACITY);
+    (tx, Mutex::new(Some(rx)))
+});
+
+/// Starts replication when `[ha]` is enabled.
+///
+/// Side effects: applies the persisted or configured role (holding StormGuard
+/// and dynamic-circuit expiry on a standby), binds the replication listener
+/// and starts the sync thread. Must run before StormGuard, TreeGuard and the
+/// RADIUS listener start so a standby never acts as active.
+pub fn start_ha_sync() {
+    let Ok(config) = lqos_config::load_config() else {
+        return;
+    };
+    if !config.ha.enabled {
+        return;
+    }
+    let settings = match HaSettings::load(&config) {
+        Ok(settings) => Arc::new(settings),
+        Err(err) => {
+            warn!("HA replication is enabled but cannot start: {err}");
+            STATE.lock().last_error = Some(err);
+            return;
+        }
+    };
+    let role = load_role(&settings).unwrap_or_else(|| RoleState::initial(settings.config.role));
+    apply_role(&settings, role, false);
+    info!(
+        "HA replication starting as {:?} (epoch {}) with peer {}",
+        role.role, role.epoch, settings.config.peer
+    );
+    let _ = SETTINGS.set(settings.clone());
+
+    let listener_settings = settings.clone();
+    if let Err(err) = std::thread::Builder::new()
+        .name("HA Listener".to_string())
+        .spawn(move || transport::run_listener(listener_settings))
+    {
+        warn!("Failed to start the HA listener: {err:?}");
+    }
+    match std::thread::Builder::new()
+        .name("HA Sync".to_string())
+        .spawn(move || sync_loop(settings))
+    {
+        Ok(_) => info!("HA replication started"),
+        Err(err) => warn!("Failed to start HA replication: {err:?}"),
+    }
+}
+
+/// True while this node is an HA standby.
+pub fn is_standby() -> bool {
+    STANDBY.load(Ordering::Relaxed)
+}
+
+/// The TreeGuard virtualization set replicated from the active peer.
+pub fn replicated_virtualized_nodes() -> Vec<String> {
+    STATE.lock().replicated_virtualized_nodes.clone()
+}
+
+/// Takes the receiver for RADIUS packets replicated from the active peer.
+/// Only the first caller gets it.
+pub(crate) fn take_radius_replicas() -> Option<mpsc::Receiver<RadiusReplica>> {
+    RADIUS_REPLICAS.1.lock().take()
+}
+
+/// Queues an accepted Accounting-Request for the standby peer.
+///
+/// Only the newest packet per session is kept, so a peer that reconnects is
+/// brought up to date without replaying every interim update.
+pub(crate) fn replicate_radius_packet(source: SocketAddr, event: &AccountingEvent, bytes: Vec<u8>) {
+    if SETTINGS.get().is_none() || is_standby() {
+        return;
+    }
+    let Ok(now) = unix_now() else {
+        return;
+    };
+    let nas = event
+        .nas_identifier
+        .clone()
+        .or_else(|| event.nas_ip_address.map(|ip| ip.to_string()))
+        .or_else(|| event.nas_ipv6_address.map(|ip| ip.to_string()))
+        .unwrap_or_else(|| source.ip().to_string());
+    let key = format!(
+        "{nas}|{}",
+        event.acct_session_id.as_deref().unwrap_or_default()
+    );
+    {
+        let mut journal = RADIUS_JOURNAL.lock();
+        journal.next_seq += 1;
+        let entry = RadiusJournalEntry {
+            seq: journal.next_seq,
+            recorded_unix: now,
+            stop: event.status_type == Some(AcctStatusType::Stop),
+            source,
+            bytes,
+        };
+        journal.entries.insert(key, entry);
+        journal.prune(now);
+    }
+    let _ = WAKE.0.try_send(());
+}
+
+/// Returns the replication status as a bus response.
+pub fn ha_status() -> BusResponse {
+    BusResponse::HaStatus(status_snapshot())
+}
+
+/// Promotes this node to active.
+///
+/// Side effects: persists a higher epoch, releases the standby holds and
+/// schedules a LibreQoS reload so the replicated override layers take effect.
+/// The peer demotes itself when it sees the higher epoch.
+pub fn promote() -> BusResponse {
+    let Some(settings) = SETTINGS.get() else {
+        return BusResponse::Fail("HA replication is not running".to_string());
+    };
+    let (current, peer_epoch) = {
+        let state = STATE.lock();
+        (state.role, state.peer.as_ref().map(|peer| peer.epoch))
+    };
+    let Some(current) = current else {
+        return BusResponse::Fail("HA role is not initialized".to_string());
+    };
+    if current.role != HaRole::Active {
+        let promoted = current.promoted(peer_epoch);
+        apply_role(settings, promoted, true);
+        record_role_change(format!("Promoted by operator to epoch {}", promoted.epoch));
+        let _ = WAKE.0.try_send(());
+    }
+    ha_status()
+}
+
+/// Records a replication error for the status view.
+fn record_error(error: String) {
+    warn!("HA replication: {error}");
+    STATE.lock().last_error = Some(error);
+}
+
+fn record_role_change(message: String) {
+    info!("HA role: {message}");
+    STATE.lock().last_conflict = Some(message);
+}
+
+/// Switches this node to `role` and applies the standby holds.
+///
+/// Side effects: writes `role.json` when `persist` is set; toggles the
+/// StormGuard hold and dynamic-circuit expiry; schedules a reload on promotion.
+fn apply_role(settings: &HaSettings, role: RoleState, persist: bool) {
+    let standby = role.role != HaRole::Active;
+    let was_standby = STANDBY.swap(standby, Ordering::Relaxed);
+    lqos_stormguard::set_standby_hold(standby);
+    lqos_network_devices::set_dynamic_circuit_expiry_paused(standby);
+    {
+        let mut state = STATE.lock();
+        if state.role.is_some() && was_standby && !standby {
+            state.reload_pending = true;
+        }
+        if !standby {
+            state.applied_hashes.clear();
+        }
+        state.role = Some(role);
+    }
+    if persist && let Err(err) = save_role(settings, role) {
+        record_error(format!("Unable to persist the HA role: {err}"));
+    }
+}
+
+fn load_role(settings: &HaSettings) -> Option<RoleState> {
+    let raw = std::fs::read_to_string(settings.state_directory.join(ROLE_FILENAME)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn save_role(settings: &HaSettings, role: RoleState) -> std::io::Result<()> {
+    std::fs::create_dir_all(&settings.state_directory)?;
+    let path = settings.state_directory.join(ROLE_FILENAME);
+    let temporary = path.with_extension("json.tmp");
+    std::fs::write(&temporary, serde_json::to_vec_pretty(&role)?)?;
+    std::fs::rename(&temporary, &path)
+}
+
+/// Per-connection record of what the peer has been sent.
+#[derive(Default)]
+struct SentState {
+    components: HashMap<&'static str, (String, u64)>,
+    radius_seq: u64,
+}
+
+fn sync_loop(settings: Arc<HaSettings>) {
+    let interval = Duration::from_secs(settings.config.sync_interval_seconds);
+    let mut connection: Option<transport::PeerStream> = None;
+    let mut sent = SentState::default();
+    let mut next_tick = Instant::now();
+    let mut next_connect = Instant::now();
+
+    loop {
+        let captures = if Instant::now() >= next_tick {
+            next_tick = Instant::now() + interval;
+            Some(tick(&settings))
+        } else {
+            None
+        };
+
+        if connection.is_none() && Instant::now() >= next_connect {
+            match transport::connect(&settings) {
+                Ok(stream) => {
+                    connection = Some(stream);
+                    sent = SentState::default();
+                }
+                Err(err) => {
+                    record_error(err);
+                    next_connect = Instant::now() + interval;
+                }
+            }
+        }
+        if let Some(stream) = connection.as_mut()
+            && let Err(err) = send_updates(stream, &settings, &mut sent, captures.as_deref())
+        {
+            record_error(format!("Lost the HA peer connection: {err}"));
+            connection = None;
+            next_connect = Instant::now() + interval;
+        }
+
+        let wait = next_tick.saturating_duration_since(Instant::now());
+        let _ = WAKE.1.recv_timeout(wait);
+    }
+}
+
+/// Re-evaluates traffic, role and local state.
+///
+/// Side effects: may change role, counts standby drift as conflicts and runs
+/// a pending LibreQoS reload.
+fn tick(settings: &HaSettings) -> Vec<(Component, Captured)> {
+    let now = unix_now().unwrap_or(0);
+    let bits = THROUGHPUT_TRACKER.actual_bits_per_second();
+    let carrying =
+        (bits.down + bits.up) as f64 / 1_000_000.0 > settings.config.traffic_threshold_mbps;
+
+    let decision = {
+        let mut state = STATE.lock();
+        state.carrying_traffic = carrying;
+        state.traffic_since_unix = match (carrying, state.traffic_since_unix) {
+            (true, Some(since)) => Some(since),
+            (true, None) => Some(now),
+            (false, _) => None,
+        };
+        state.role.map(|current| {
+            let inputs = RoleInputs {
+                mode: settings.config.role,
+                node_name: &settings.node_name,
+                failover_seconds: settings.config.failover_seconds,
+                now_unix: now,
+                traffic_since_unix: state.traffic_since_unix,
+                peer: state.peer.as_ref(),
+            };
+            (
+                current,
+                state.peer.as_ref().map(|peer| peer.epoch),
+                role::decide(current, &inputs),
+            )
+        })
+    };
+    match decision {
+        Some((current, peer_epoch, RoleDecision::Promote(reason))) => {
+            let promoted = current.promoted(peer_epoch);
+            apply_role(settings, promoted, true);
+            record_role_change(format!("Promoted to epoch {}: {reason}", promoted.epoch));
+        }
+        Some((current, _, RoleDecision::Demote(reason))) => {
+            apply_role(
+                settings,
+                RoleState {
+                    role: HaRole::Standby,
+                    epoch: current.epoch,
+                },
+                true,
+            );
+            record_role_change(format!("Demoted to standby: {reason}"));
+        }
+        Some((_, _, RoleDecision::Keep)) | None => {}
+    }
+
+    let standby = is_standby();
+    let virtualized = if standby {
+        replicated_virtualized_nodes()
+    } else {
+        crate::treeguard::actor::runtime_virtualized_nodes()
+    };
+    let mut captures = Vec::new();
+    for component in ALL_COMPONENTS {
+        match component.capture(&virtualized) {
+            Ok(captured) => captures.push((component, captured)),
+            Err(err) => record_error(err),
+        }
+    }
+
+    let reload = {
+        let mut state = STATE.lock();
+        for (component, captured) in &captures {
+            let name = component.name();
+            if standby
+                && let Some(applied) = state.applied_hashes.get(name)
+                && *applied != captured.hash
+            {
+                state.conflicts += 1;
+                state.last_conflict = Some(format!(
+                    "{name} changed locally on the standby; the active copy will replace it"
+                ));
+                state
+                    .applied_hashes
+                    .insert(name.to_string(), captured.hash.clone());
+            }
+            state
+                .local_hashes
+                .insert(name.to_string(), captured.hash.clone());
+        }
+        std::mem::take(&mut state.reload_pending)
+    };
+    if reload {
+        match program_control::reload_libre_qos() {
+            BusResponse::Fail(message) => record_error(format!(
+                "Reloading LibreQoS after an HA change failed: {message}"
+            )),
+            _ => info!("Reloaded LibreQoS after an HA change"),
+        }
+    }
+    captures
+}
+
+/// Sends status on each tick and, while active, any state the standby lacks.
+fn send_updates(
+    stream: &mut transport::PeerStream,
+    settings: &HaSettings,
+    sent: &mut SentState,
+    captures: Option<&[(Component, Captured)]>,
+) -> std::io::Result<()> {
+    let now = unix_now().unwrap_or(0);
+    let (role, carrying_traffic, peer_is_standby, peer_hashes, local_hashes) = {
+        let state = STATE.lock();
+        (
+            state.role,
+            state.carrying_traffic,
+            state
+                .peer
+                .as_ref()
+                .is_some_and(|peer| peer.role == HaRole::Standby),
+            state.peer_hashes.clone(),
+            state.local_hashes.clone(),
+        )
+    };
+    let Some(role) = role else {
+        return Ok(());
+    };
+    let active = role.role == HaRole::Active;
+
+    if let Some(captures) = captures {
+        write_message(
+            stream,
+            &HaMessage::Status {
+                node_name: settings.node_name.clone(),
+                role: role.role,
+                epoch: role.epoch,
+                carrying_traffic,
+                component_hashes: local_hashes,
+            },
+        )?;
+        if active && peer_is_standby {
+            for (component, captured) in captures {
+                let name = component.name();
+                if peer_hashes.get(name) == Some(&captured.hash) {
+                    continue;
+                }
+                // Give the standby a failover window to report the new hash
+                // before offering the same state again.
+                let recently_sent = sent.components.get(name).is_some_and(|(hash, at)| {
+                    *hash == captured.hash
+                        && now.saturating_sub(*at) < settings.config.failover_seconds
+                });
+                if recently_sent {
+                    continue;
+                }
+                write_message(
+                    stream,
+                    &HaMessage::Component {
+                        name: name.to_string(),
+                        hash: captured.hash.clone(),
+                        payload: captured.payload.clone(),
+                    },
+                )?;
+                sent.components.insert(name, (captured.hash.clone(), now));
+            }
+        }
+    }
+
+    if active {
+        let pending: Vec<(u64, SocketAddr, Vec<u8>)> = {
+            let journal = RADIUS_JOURNAL.lock();
+            let mut pending: Vec<_> = journal
+                .entries
+                .values()
+                .filter(|entry| entry.seq > sent.radius_seq)
+                .map(|entry| (entry.seq, entry.source, entry.bytes.clone()))
+                .collect();
+            pending.sort_by_key(|(seq, _, _)| *seq);
+            pending
+        };
+        for (seq, source, bytes) in pending {
+            write_message(stream, &HaMessage::Radius { source, bytes })?;
+            sent.radius_seq = seq;
+            STATE.lock().radius_packets_sent += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Handles one authenticated message from the peer.
+fn receive(message: HaMessage) {
+    let now = unix_now().unwrap_or(0);
+    match message {
+        HaMessage::Status {
+            node_name,
+            role,
+            epoch,
+            carrying_traffic,
+            component_hashes,
+        } => {
+            let mut state = STATE.lock();
+            let idle_since_unix = if carrying_traffic {
+                None
+            } else {
+                state
+                    .peer
+                    .as_ref()
+                    .and_then(|peer| peer.idle_since_unix)
+                    .or(Some(now))
+            };
+            state.peer = Some(PeerReport {
+                node_name,
+                role,
+                epoch,
+                last_seen_unix: now,
+                idle_since_unix,
+            });
+            state.peer_carrying_traffic = Some(carrying_traffic);
+            state.peer_hashes = component_hashes;
+        }
+        HaMessage::Component {
+            name,
+            hash: _,
+            payload,
+        } => {
+            if !is_standby() {
+                return;
+            }
+            let Some(component) = Component::from_name(&name) else {
+                record_error(format!("Peer sent unknown component {name}"));
+                return;
+            };
+            apply_component(component, &payload, now);
+        }
+        HaMessage::Radius { source, bytes } => {
+            if !is_standby() {
+                return;
+            }
+            match RADIUS_REPLICAS.0.try_send(RadiusReplica { source, bytes }) {
+                Ok(()) => STATE.lock().radius_packets_applied += 1,
+                Err(err) => record_error(format!("Dropped a replicated RADIUS packet: {err}")),
+            }
+        }
+        HaMessage::Challenge { .. } | HaMessage::Auth { .. } => {}
+    }
+}
+
+fn apply_component(component: Component, payload: &str, now: u64) {
+    let name = component.name();
+    let mut virtualized = replicated_virtualized_nodes();
+    let applied = component.apply(payload, &mut virtualized);
+    STATE.lock().replicated_virtualized_nodes = virtualized.clone();
+    let applied = match applied {
+        Ok(applied) => applied,
+        Err(err) => {
+            record_error(err);
+            return;
+        }
+    };
+    for err in applied.errors {
+        record_error(err);
+    }
+    match component.capture(&virtualized) {
+        Ok(captured) => {
+            let mut state = STATE.lock();
+            state
+                .applied_hashes
+                .insert(name.to_string(), captured.hash.clone());
+            state.local_hashes.insert(name.to_string(), captured.hash);
+            state.last_applied_unix.insert(name.to_string(), now);
+            state.reload_pending |= applied.needs_reload;
+        }
+        Err(err) => record_error(err),
+    }
+    info!("Applied replicated {name} from the HA peer");
+}
+
+fn status_snapshot() -> HaStatus {
+    let Some(settings) = SETTINGS.get() else {
+        return HaStatus {
+            last_error: STATE.lock().last_error.clone(),
+            ..Default::default()
+        };
+    };
+    let now = unix_now().unwrap_or(0);
+    let state = STATE.lock();
+    let peer = state.peer.as_ref();
+    HaStatus {
+        enabled: true,
+        node_name: settings.node_name.clone(),
+        role: state.role.map_or(HaRole::Disabled, |role| role.role),
+        epoch: state.role.map_or(0, |role| role.epoch),
+        carrying_traffic: state.carrying_traffic,
+        peer_address: settings.config.peer.clone(),
+        peer_connected: peer.is_some_and(|peer| {
+            now.saturating_sub(peer.last_seen_unix) < settings.config.failover_seconds
+        }),
+        peer_name: peer.map(|peer| peer.node_name.clone()),
+        peer_role: peer.map(|peer| peer.role),
+        peer_epoch: peer.map(|peer| peer.epoch),
+        peer_carrying_traffic: state.peer_carrying_traffic,
+        peer_last_seen_unix: peer.map(|peer| peer.last_seen_unix),
+        components: ALL_COMPONENTS
+            .iter()
+            .map(|component| {
+                let name = component.name();
+                HaComponentStatus {
+                    name: name.to_string(),
+                    hash: state.local_hashes.get(name).cloned().unwrap_or_default(),
+                    peer_hash: state.peer_hashes.get(name).cloned(),
+                    last_applied_unix: state.last_applied_unix.get(name).copied(),
+                }
+            })
+            .collect(),
+        radius_packets_sent: state.radius_packets_sent,
+        radius_packets_applied: state.radius_packets_applied,
+        conflicts: state.conflicts,
+        last_conflict: state.last_conflict.clone(),
+        last_error: state.last_error.clone(),
+    }
+}
+
+impl RadiusJournal {
+    /// Drops finished and idle sessions, then the oldest entries beyond the cap.
+    fn prune(&mut self, now: u64) {
+        if now == self.last_pruned_unix && self.entries.len() <= RADIUS_JOURNAL_LIMIT {
+            return;
+        }
+        self.last_pruned_unix = now;
+        self.entries.retain(|_, entry| {
+            let retention = if entry.stop {
+                RADIUS_STOP_RETENTION_SECONDS
+            } else {
+                RADIUS_SESSION_RETENTION_SECONDS
+            };
+            now.saturating_sub(entry.recorded_unix) < retention
+        });
+        if self.entries.len() > RADIUS_JOURNAL_LIMIT {
+            let mut seqs: Vec<u64> = self.entries.values().map(|entry| entry.seq).collect();
+            seqs.sort_unstable();
+            let cutoff = seqs[self.entries.len() - RADIUS_JOURNAL_LIMIT];
+            self.entries.retain(|_, entry| entry.seq >= cutoff);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Wire format for the replication channel.
+//!
+//! Each frame is a big-endian `u32` length followed by a JSON `HaMessage`. The
+//! listener opens every connection with a random challenge, and the dialer
+//! answers with an HMAC of that challenge and its node name keyed by the
+//! shared secret before any state is accepted.
+
+use hmac::{Hmac, Mac};
+use lqos_bus::HaRole;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Largest accepted frame once the peer has authenticated. Override files and
+/// dynamic circuit lists for large networks fit comfortably.
+pub(super) const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+
+/// Largest accepted frame before authentication. Challenges and answers are a
+/// few hundred bytes, so an unauthenticated client can't make either side
+/// allocate more than this.
+pub(super) const MAX_AUTH_FRAME_BYTES: usize = 4 * 1024;
+
+/// One message on the replication channel.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) enum HaMessage {
+    /// Sent by the listener when a connection opens.
+    Challenge { nonce: Vec<u8> },
+    /// The dialer's answer to a challenge.
+    Auth { node_name: String, mac: Vec<u8> },
+    /// Periodic role, traffic and state summary.
+    Status {
+        node_name: String,
+        role: HaRole,
+        epoch: u64,
+        carrying_traffic: bool,
+        component_hashes: BTreeMap<String, String>,
+    },
+    /// A full copy of one kind of runtime state.
+    Component {
+        name: String,
+        hash: String,
+        payload: String,
+    },
+    /// A RADIUS Accounting-Request accepted by the active node.
+    Radius { source: SocketAddr, bytes: Vec<u8> },
+}
+
+/// Writes one frame.
+pub(super) fn write_message(stream: &mut impl Write, message: &HaMessage) -> std::io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    let length = u32::try_from(body.len())
+        .ok()
+        .filter(|length| *length as usize <= MAX_FRAME_BYTES)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "frame too large"))?;
+    stream.write_all(&length.to_be_bytes())?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+/// Reads one frame of at most `max_bytes`.
+pub(super) fn read_message(stream: &mut impl Read, max_bytes: usize) -> std::io::Result<HaMessage> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > max_bytes {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame of {length} bytes exceeds the limit"),
+        ));
+    }
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Computes the answer to `nonce` for `node_name`.
+pub(super) fn auth_mac(secret: &[u8], nonce: &[u8], node_name: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(nonce);
+    mac.update(node_name.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Checks an answer to `nonce` in constant time.
+pub(super) fn verify_auth_mac(secret: &[u8], nonce: &[u8], node_name: &str, answer: &[u8]) -> bool {
+    let Ok(mut mac) = HmacSha256::new_from_slice(secret) else {
+        return false;
+    };
+    mac.update(nonce);
+    mac.update(node_name.as_bytes());
+    mac.verify_slice(answer).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_round_trip_and_oversized_frames_are_rejected() {
+        let message = HaMessage::Radius {
+            source: "192.0.2.10:1813".parse().expect("address"),
+            bytes: vec![4, 1, 0, 20],
+        };
+        let mut wire = Vec::new();
+        write_message(&mut wire, &message).expect("write");
+        assert_eq!(
+            read_message(&mut wire.as_slice(), MAX_FRAME_BYTES).expect("read"),
+            message
+        );
+
+        let mut oversized = ((MAX_FRAME_BYTES + 1) as u32).to_be_bytes().to_vec();
+        oversized.extend_from_slice(b"{}");
+        assert!(read_message(&mut oversized.as_slice(), MAX_FRAME_BYTES).is_err());
+    }
+
+    #[test]
+    fn frames_before_authentication_are_kept_small() {
+        let auth = HaMessage::Auth {
+            node_name: "shaper-a".to_string(),
+            mac: auth_mac(b"secret", &[7u8; 32], "shaper-a"),
+        };
+        let mut wire = Vec::new();
+        write_message(&mut wire, &auth).expect("write");
+        assert_eq!(
+            read_message(&mut wire.as_slice(), MAX_AUTH_FRAME_BYTES).expect("read"),
+            auth
+        );
+
+        let component = HaMessage::Component {
+            name: "overrides".to_string(),
+            hash: String::new(),
+            payload: "x".repeat(MAX_AUTH_FRAME_BYTES),
+        };
+        let mut wire = Vec::new();
+        write_message(&mut wire, &component).expect("write");
+        assert!(read_message(&mut wire.as_slice(), MAX_AUTH_FRAME_BYTES).is_err());
+        assert!(read_message(&mut wire.as_slice(), MAX_FRAME_BYTES).is_ok());
+    }
+
+    #[test]
+    fn auth_is_bound_to_secret_nonce_and_node_name() {
+        let answer = auth_mac(b"secret", b"nonce", "shaper-a");
+        assert!(verify_auth_mac(b"secret", b"nonce", "shaper-a", &answer));
+        assert!(!verify_auth_mac(b"other", b"nonce", "shaper-a", &answer));
+        assert!(!verify_auth_mac(b"secret", b"replay", "shaper-a", &answer));
+        assert!(!verify_auth_mac(b"secret", b"nonce", "shaper-b", &answer));
+    }
+}
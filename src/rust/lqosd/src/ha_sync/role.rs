@@ -0,0 +1,231 @@
+//! Role selection for an active/standby pair.
+//!
+//! Everything here is pure so the failover and conflict rules can be tested
+//! without a peer or a shaping tree.
+
+use lqos_bus::HaRole;
+use lqos_config::HaRoleMode;
+use serde::{Deserialize, Serialize};
+
+/// The persisted part of a node's role.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct RoleState {
+    pub role: HaRole,
+    pub epoch: u64,
+}
+
+impl RoleState {
+    /// The role a node starts in when nothing was persisted.
+    pub fn initial(mode: HaRoleMode) -> Self {
+        let role = match mode {
+            HaRoleMode::Active => HaRole::Active,
+            HaRoleMode::Auto | HaRoleMode::Standby => HaRole::Standby,
+        };
+        Self { role, epoch: 0 }
+    }
+
+    /// The state after this node is promoted past `peer_epoch`.
+    pub fn promoted(self, peer_epoch: Option<u64>) -> Self {
+        Self {
+            role: HaRole::Active,
+            epoch: self.epoch.max(peer_epoch.unwrap_or(0)) + 1,
+        }
+    }
+}
+
+/// The last status a peer reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct PeerReport {
+    pub node_name: String,
+    pub role: HaRole,
+    pub epoch: u64,
+    pub last_seen_unix: u64,
+    /// When the peer started reporting no traffic, while it still does.
+    pub idle_since_unix: Option<u64>,
+}
+
+/// Everything a role decision depends on.
+pub(super) struct RoleInputs<'a> {
+    pub mode: HaRoleMode,
+    pub node_name: &'a str,
+    pub failover_seconds: u64,
+    pub now_unix: u64,
+    /// When this node started carrying traffic, while it still does.
+    pub traffic_since_unix: Option<u64>,
+    pub peer: Option<&'a PeerReport>,
+}
+
+/// What a node should do with its role.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum RoleDecision {
+    Keep,
+    Promote(String),
+    Demote(String),
+}
+
+/// Decides whether this node keeps, takes or gives up the active role.
+///
+/// Two active nodes resolve to the higher epoch, then to the node carrying
+/// traffic, then to the lower node name. A standby only promotes itself once
+/// it has carried traffic for `failover_seconds`, so a standby that is merely
+/// cut off from its peer never takes over a path it is not in.
+pub(super) fn decide(state: RoleState, inputs: &RoleInputs) -> RoleDecision {
+    let sustained = |since: Option<u64>| {
+        since.is_some_and(|since| inputs.now_unix.saturating_sub(since) >= inputs.failover_seconds)
+    };
+    let peer = inputs.peer.filter(|peer| {
+        inputs.now_unix.saturating_sub(peer.last_seen_unix) < inputs.failover_seconds
+    });
+
+    match state.role {
+        HaRole::Active => {
+            let Some(peer) = peer.filter(|peer| peer.role == HaRole::Active) else {
+                return RoleDecision::Keep;
+            };
+            let local_traffic = inputs.traffic_since_unix.is_some();
+            let peer_traffic = peer.idle_since_unix.is_none();
+            let peer_wins = if peer.epoch != state.epoch {
+                peer.epoch > state.epoch
+            } else if local_traffic != peer_traffic {
+                peer_traffic
+            } else {
+                peer.node_name.as_str() < inputs.node_name
+            };
+            if peer_wins {
+                RoleDecision::Demote(format!(
+                    "Both nodes were active; {} (epoch {}) won over epoch {}",
+                    peer.node_name, peer.epoch, state.epoch
+                ))
+            } else {
+                RoleDecision::Keep
+            }
+        }
+        HaRole::Standby | HaRole::Disabled => {
+            if inputs.mode == HaRoleMode::Standby || !sustained(inputs.traffic_since_unix) {
+                return RoleDecision::Keep;
+            }
+            match peer {
+                None => RoleDecision::Promote(
+                    "Carrying traffic while the peer is unreachable".to_string(),
+                ),
+                Some(peer) if peer.role != HaRole::Active => RoleDecision::Promote(
+                    "Carrying traffic while neither node is active".to_string(),
+                ),
+                Some(peer) if sustained(peer.idle_since_unix) => RoleDecision::Promote(format!(
+                    "Carrying traffic while the active peer {} is idle",
+                    peer.node_name
+                )),
+                Some(_) => RoleDecision::Keep,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 10_000;
+
+    fn peer(role: HaRole, epoch: u64, idle: bool) -> PeerReport {
+        PeerReport {
+            node_name: "shaper-b".to_string(),
+            role,
+            epoch,
+            last_seen_unix: NOW - 1,
+            idle_since_unix: idle.then_some(NOW - 60),
+        }
+    }
+
+    fn inputs<'a>(
+        mode: HaRoleMode,
+        carrying: bool,
+        peer: Option<&'a PeerReport>,
+    ) -> RoleInputs<'a> {
+        RoleInputs {
+            mode,
+            node_name: "shaper-a",
+            failover_seconds: 15,
+            now_unix: NOW,
+            traffic_since_unix: carrying.then_some(NOW - 60),
+            peer,
+        }
+    }
+
+    fn standby() -> RoleState {
+        RoleState {
+            role: HaRole::Standby,
+            epoch: 3,
+        }
+    }
+
+    fn active(epoch: u64) -> RoleState {
+        RoleState {
+            role: HaRole::Active,
+            epoch,
+        }
+    }
+
+    #[test]
+    fn standby_promotes_only_while_carrying_traffic() {
+        let busy_active = peer(HaRole::Active, 3, false);
+        let idle_active = peer(HaRole::Active, 3, true);
+        let other_standby = peer(HaRole::Standby, 3, true);
+        let mut stale = peer(HaRole::Active, 3, false);
+        stale.last_seen_unix = NOW - 15;
+
+        for (peer, carrying, promotes) in [
+            (None, false, false),
+            (None, true, true),
+            (Some(&stale), true, true),
+            (Some(&busy_active), true, false),
+            (Some(&idle_active), true, true),
+            (Some(&idle_active), false, false),
+            (Some(&other_standby), true, true),
+        ] {
+            let decision = decide(standby(), &inputs(HaRoleMode::Auto, carrying, peer));
+            assert_eq!(
+                matches!(decision, RoleDecision::Promote(_)),
+                promotes,
+                "peer={peer:?} carrying={carrying}"
+            );
+        }
+    }
+
+    #[test]
+    fn standby_mode_never_promotes_itself() {
+        let decision = decide(standby(), &inputs(HaRoleMode::Standby, true, None));
+        assert_eq!(decision, RoleDecision::Keep);
+    }
+
+    #[test]
+    fn dual_active_resolves_by_epoch_then_traffic_then_name() {
+        let newer = peer(HaRole::Active, 5, true);
+        let older = peer(HaRole::Active, 3, false);
+        let same_busy = peer(HaRole::Active, 4, false);
+        let same_idle = peer(HaRole::Active, 4, true);
+
+        for (peer, carrying, demotes) in [
+            (&newer, true, true),
+            (&older, false, false),
+            (&same_busy, false, true),
+            (&same_idle, true, false),
+            // Same epoch and traffic: "shaper-a" sorts before "shaper-b" and stays.
+            (&same_busy, true, false),
+        ] {
+            let decision = decide(active(4), &inputs(HaRoleMode::Auto, carrying, Some(peer)));
+            assert_eq!(
+                matches!(decision, RoleDecision::Demote(_)),
+                demotes,
+                "peer={peer:?} carrying={carrying}"
+            );
+        }
+    }
+
+    #[test]
+    fn promotion_moves_past_both_epochs() {
+        assert_eq!(standby().promoted(Some(7)), active(8));
+        assert_eq!(standby().promoted(None), active(4));
+        assert_eq!(RoleState::initial(HaRoleMode::Active), active(0));
+    }
+}
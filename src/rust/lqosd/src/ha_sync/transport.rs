@@ -0,0 +1,245 @@
+//! TLS connections between the two nodes.
+//!
+//! Each node dials its peer to send and accepts its peer's connection to
+//! receive, so either side can restart without the other noticing more than a
+//! reconnect. The dialer trusts only the configured peer certificate; the
+//! listener trusts only dialers that answer its challenge with the shared
+//! secret. Until a dialer has answered, the listener reads only small frames
+//! under a short timeout, and it serves a handful of connections at once.
+
+use super::HaSettings;
+use super::protocol::{
+    HaMessage, MAX_AUTH_FRAME_BYTES, MAX_FRAME_BYTES, auth_mac, read_message, verify_auth_mac,
+    write_message,
+};
+use lqos_config::HaConfig;
+use native_tls::{Certificate, Identity, TlsAcceptor, TlsConnector, TlsStream};
+use rand::RngCore;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time a new connection has to finish the TLS handshake and authenticate.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections served at once. Only the peer should connect; the spare slots
+/// cover a reconnect while a dead connection is still timing out.
+const MAX_CONNECTIONS: usize = 4;
+const NONCE_BYTES: usize = 32;
+
+pub(super) type PeerStream = TlsStream<TcpStream>;
+
+/// Builds the listener's TLS acceptor from the configured certificate and key.
+///
+/// Side effects: reads the certificate and key files.
+pub(super) fn build_acceptor(ha: &HaConfig) -> Result<TlsAcceptor, String> {
+    let certificate = read_file(&ha.tls_certificate)?;
+    let key = read_file(&ha.tls_private_key)?;
+    let identity = Identity::from_pkcs8(&certificate, &key)
+        .map_err(|err| format!("Invalid HA certificate or key: {err}"))?;
+    TlsAcceptor::new(identity).map_err(|err| format!("Unable to build HA TLS acceptor: {err}"))
+}
+
+/// Builds a TLS connector that trusts only the configured peer certificate.
+///
+/// The peer is usually addressed by IP, so the certificate itself is pinned
+/// instead of checking a host name.
+///
+/// Side effects: reads the peer certificate file.
+pub(super) fn build_connector(ha: &HaConfig) -> Result<TlsConnector, String> {
+    let peer = Certificate::from_pem(&read_file(&ha.peer_certificate)?)
+        .map_err(|err| format!("Invalid HA peer certificate: {err}"))?;
+    TlsConnector::builder()
+        .add_root_certificate(peer)
+        .disable_built_in_roots(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|err| format!("Unable to build HA TLS connector: {err}"))
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|err| format!("Unable to read {path}: {err}"))
+}
+
+/// Connects and authenticates to the peer's listener.
+///
+/// Side effects: opens a TCP connection to the peer.
+pub(super) fn connect(settings: &HaSettings) -> Result<PeerStream, String> {
+    let peer = &settings.config.peer;
+    let address = peer
+        .to_socket_addrs()
+        .map_err(|err| format!("Unable to resolve HA peer {peer}: {err}"))?
+        .next()
+        .ok_or_else(|| format!("HA peer {peer} did not resolve"))?;
+    let tcp = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .map_err(|err| format!("Unable to connect to HA peer {peer}: {err}"))?;
+    let io_timeout = Some(settings.failover_timeout());
+    tcp.set_read_timeout(io_timeout)
+        .and_then(|()| tcp.set_write_timeout(io_timeout))
+        .map_err(|err| format!("Unable to configure HA peer connection: {err}"))?;
+
+    let host = peer
+        .rsplit_once(':')
+        .map_or(peer.as_str(), |(host, _)| host)
+        .trim_matches(['[', ']']);
+    let mut stream = settings
+        .connector
+        .connect(host, tcp)
+        .map_err(|err| format!("TLS handshake with HA peer {peer} failed: {err}"))?;
+
+    let nonce = match read_message(&mut stream, MAX_AUTH_FRAME_BYTES) {
+        Ok(HaMessage::Challenge { nonce }) => nonce,
+        Ok(_) => return Err(format!("HA peer {peer} did not send a challenge")),
+        Err(err) => return Err(format!("Unable to read the HA peer challenge: {err}")),
+    };
+    let auth = HaMessage::Auth {
+        node_name: settings.node_name.clone(),
+        mac: auth_mac(&settings.secret, &nonce, &settings.node_name),
+    };
+    write_message(&mut stream, &auth)
+        .map_err(|err| format!("Unable to authenticate to HA peer {peer}: {err}"))?;
+    info!("Connected to HA peer {peer}");
+    Ok(stream)
+}
+
+/// Accepts peer connections forever, handing each authenticated message to
+/// [`super::receive`].
+///
+/// Side effects: binds the configured listen address and spawns a thread per
+/// connection, up to [`MAX_CONNECTIONS`]; further connections are closed.
+pub(super) fn run_listener(settings: Arc<HaSettings>) {
+    let listener = match TcpListener::bind(&settings.config.listen) {
+        Ok(listener) => listener,
+        Err(err) => {
+            super::record_error(format!(
+                "Unable to bind the HA listener on {}: {err}",
+                settings.config.listen
+            ));
+            return;
+        }
+    };
+    info!("HA listener started on {}", settings.config.listen);
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                debug!("HA listener accept failed: {err}");
+                continue;
+            }
+        };
+        let Some(slot) = ConnectionSlot::claim(&active) else {
+            warn!(
+                "Refusing HA connection from {:?}: {MAX_CONNECTIONS} connections already open",
+                stream.peer_addr().ok()
+            );
+            continue;
+        };
+        let settings = settings.clone();
+        let spawned = std::thread::Builder::new()
+            .name("HA Peer".to_string())
+            .spawn(move || {
+                let _slot = slot;
+                if let Err(err) = serve(stream, &settings) {
+                    super::record_error(err);
+                }
+            });
+        if let Err(err) = spawned {
+            warn!("Unable to start an HA peer connection thread: {err:?}");
+        }
+    }
+}
+
+/// One of the listener's [`MAX_CONNECTIONS`] slots, released on drop.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn claim(active: &Arc<AtomicUsize>) -> Option<Self> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |open| {
+                (open < MAX_CONNECTIONS).then_some(open + 1)
+            })
+            .ok()
+            .map(|_| Self(active.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn set_timeouts(tcp: &TcpStream, timeout: Duration) -> std::io::Result<()> {
+    tcp.set_read_timeout(Some(timeout))?;
+    tcp.set_write_timeout(Some(timeout))
+}
+
+fn serve(tcp: TcpStream, settings: &HaSettings) -> Result<(), String> {
+    let remote = tcp
+        .peer_addr()
+        .map(|address| address.to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    set_timeouts(&tcp, AUTH_TIMEOUT.min(settings.failover_timeout()))
+        .map_err(|err| format!("Unable to configure HA connection from {remote}: {err}"))?;
+    let mut stream = settings
+        .acceptor
+        .accept(tcp)
+        .map_err(|err| format!("TLS handshake from {remote} failed: {err}"))?;
+
+    let mut nonce = vec![0u8; NONCE_BYTES];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    write_message(
+        &mut stream,
+        &HaMessage::Challenge {
+            nonce: nonce.clone(),
+        },
+    )
+    .map_err(|err| format!("Unable to challenge HA connection from {remote}: {err}"))?;
+    let peer_name = match read_message(&mut stream, MAX_AUTH_FRAME_BYTES) {
+        Ok(HaMessage::Auth { node_name, mac })
+            if verify_auth_mac(&settings.secret, &nonce, &node_name, &mac) =>
+        {
+            node_name
+        }
+        Ok(_) => return Err(format!("HA connection from {remote} failed authentication")),
+        Err(err) => {
+            return Err(format!(
+                "HA connection from {remote} did not authenticate: {err}"
+            ));
+        }
+    };
+    set_timeouts(stream.get_ref(), settings.failover_timeout())
+        .map_err(|err| format!("Unable to configure HA connection from {remote}: {err}"))?;
+    info!("Accepted HA peer {peer_name} from {remote}");
+
+    loop {
+        match read_message(&mut stream, MAX_FRAME_BYTES) {
+            Ok(message) => super::receive(message),
+            Err(err) => {
+                return Err(format!(
+                    "HA connection from {peer_name} ({remote}) closed: {err}"
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_slots_are_capped_and_released() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let slots: Vec<_> = (0..MAX_CONNECTIONS)
+            .map(|_| ConnectionSlot::claim(&active).expect("slot"))
+            .collect();
+        assert!(ConnectionSlot::claim(&active).is_none());
+        drop(slots);
+        assert_eq!(active.load(Ordering::Acquire), 0);
+        assert!(ConnectionSlot::claim(&active).is_some());
+    }
+}
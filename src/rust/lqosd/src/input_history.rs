@@ -0,0 +1,126 @@
+//! Records revisions of the shaping input files and restores them on request.
+//!
+//! The thread below polls the size and modification time of `network.json`,
+//! `ShapedDevices.csv` and `lqos_overrides.json`, and only hashes them when one
+//! of those moved. Writers mark themselves with
+//! `lqos_config::mark_input_change_source` so revisions name who changed what.
+
+use crate::program_control;
+use lqos_bus::BusResponse;
+use lqos_config::InputRevision;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+const CHECK_INTERVAL_SECONDS: u64 = 10;
+
+type Fingerprint = Vec<Option<(u64, SystemTime)>>;
+
+/// Spawns the input history thread.
+///
+/// Side effects: this function starts a background thread that writes to the
+/// input history store under the state directory.
+pub fn start_input_history() {
+    match std::thread::Builder::new()
+        .name("Input History".to_string())
+        .spawn(history_loop)
+    {
+        Ok(_) => info!("Input history recorder started"),
+        Err(err) => warn!("Failed to start input history recorder: {err:?}"),
+    }
+}
+
+fn history_loop() {
+    let mut last_seen: Option<Fingerprint> = None;
+    loop {
+        if let Ok(config) = lqos_config::load_config()
+            && config.input_history.enabled
+        {
+            let paths = lqos_config::input_history_tracked_paths(&config)
+                .into_iter()
+                .map(|(_, path)| path)
+                .collect::<Vec<_>>();
+            let current = fingerprint(&paths);
+            if last_seen.as_ref() != Some(&current) {
+                match lqos_config::record_input_snapshot(&config, None) {
+                    Ok(Some(revision)) => info!(
+                        "Recorded input revision {} ({}): {}",
+                        revision.id,
+                        revision.source,
+                        revision.changed_files.join(", ")
+                    ),
+                    Ok(None) => {}
+                    Err(err) => warn!("Unable to record input revision: {err}"),
+                }
+                last_seen = Some(current);
+            }
+        }
+        std::thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECONDS));
+    }
+}
+
+fn fingerprint(paths: &[PathBuf]) -> Fingerprint {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok()?;
+            Some((metadata.len(), metadata.modified().ok()?))
+        })
+        .collect()
+}
+
+/// Restores input revision `id` and reloads LibreQoS, returning the revision
+/// that records the restore.
+///
+/// Side effects: overwrites the tracked input files and runs `LibreQoS.py`.
+pub fn restore_and_reload(id: u64) -> Result<InputRevision, String> {
+    let config =
+        lqos_config::load_config().map_err(|err| format!("Unable to load configuration: {err}"))?;
+    let revision =
+        lqos_config::restore_input_revision(&config, id).map_err(|err| err.to_string())?;
+    info!("Restored input revision {id} as revision {}", revision.id);
+    match program_control::reload_libre_qos() {
+        BusResponse::Fail(message) => Err(format!(
+            "Restored revision {id}, but reloading LibreQoS failed: {message}"
+        )),
+        _ => Ok(revision),
+    }
+}
+
+/// Restores input revision `id` and reloads LibreQoS, as a bus response.
+///
+/// Side effects: overwrites the tracked input files and runs `LibreQoS.py`.
+pub fn restore_input_revision(id: u64) -> BusResponse {
+    match restore_and_reload(id) {
+        Ok(revision) => BusResponse::InputRevisionRestored(revision),
+        Err(err) => BusResponse::Fail(err),
+    }
+}
+
+/// Lists retained input revisions as a bus response.
+///
+/// Side effects: reads the input history manifest.
+pub fn list_input_revisions() -> BusResponse {
+    match lqos_config::load_config()
+        .map_err(|err| err.to_string())
+        .and_then(|config| {
+            lqos_config::list_input_revisions(&config).map_err(|err| err.to_string())
+        }) {
+        Ok(revisions) => BusResponse::InputRevisions(revisions),
+        Err(err) => BusResponse::Fail(err),
+    }
+}
+
+/// Compares two input revisions as a bus response.
+///
+/// Side effects: reads the input history store.
+pub fn diff_input_revisions(from: u64, to: u64) -> BusResponse {
+    match lqos_config::load_config()
+        .map_err(|err| err.to_string())
+        .and_then(|config| {
+            lqos_config::diff_input_revisions(&config, from, to).map_err(|err| err.to_string())
+        }) {
+        Ok(diff) => BusResponse::InputRevisionDiff(diff),
+        Err(err) => BusResponse::Fail(err),
+    }
+}
@@ -51,6 +51,7 @@ pub(crate) fn list_mapped_ips() -> BusResponse {
                 cpu: ip_data.cpu,
                 circuit_id: ip_data.circuit_id,
                 device_id: ip_data.device_id,
+                access_policy: ip_data.access_policy,
             })
             .collect();
         BusResponse::MappedIps(data)
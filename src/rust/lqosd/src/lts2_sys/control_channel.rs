@@ -5,12 +5,14 @@ use std::{collections::HashMap, time::Duration};
 use tokio::net::TcpStream;
 use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::oneshot;
+use tokio::task::spawn_blocking;
 use tokio::time::timeout;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 use tracing::{debug, error, info, warn};
 use tungstenite::Message;
 
 use crate::lts2_sys::capabilities;
+use crate::lts2_sys::ingest_spool;
 use crate::lts2_sys::license_grant;
 use crate::lts2_sys::lts2_client::{LicenseStatus, set_license_status};
 use lqos_probe::ProbeClass;
@@ -28,6 +30,9 @@ const SHAPER_VERSION_STRING: &str = include_str!("../../../../VERSION_STRING");
 const CONTROL_CHANNEL_QUEUE_DEPTH: usize = 256;
 const CONNECTION_COMMAND_QUEUE_DEPTH: usize = 1024;
 const SOCKET_SENDER_QUEUE_DEPTH: usize = 32;
+/// Upper bound on spooled batches replayed per flush, so a long backlog
+/// doesn't starve the rest of the message pump.
+const SPOOL_REPLAY_BATCHES_PER_FLUSH: usize = 32;
 const STREAMING_CIRCUIT_FLOW_WINDOW: Duration = Duration::from_secs(300);
 
 type ControlSocketWriter = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
@@ -49,6 +54,8 @@ pub enum ControlChannelCommand {
         serial: usize,
         chunks: Vec<Vec<u8>>,
     },
+    /// Sends any batches waiting in the on-disk ingest spool.
+    FlushSpool,
     FetchHistory {
         request: messages::RemoteInsightRequest,
         responder: oneshot::Sender<Result<HistoryQueryResultPayload, ()>>,
@@ -94,6 +101,8 @@ pub enum ConnectionCommand {
         serial: usize,
         chunks: Vec<Vec<u8>>,
     },
+    /// Sends any batches waiting in the on-disk ingest spool.
+    FlushSpool,
     FetchHistory {
         request: messages::RemoteInsightRequest,
         responder: oneshot::Sender<Result<HistoryQueryResultPayload, ()>>,
@@ -164,6 +173,9 @@ async fn control_channel_loop(mut builder: ControlChannelBuilder) -> Result<()>
             ControlChannelCommand::SubmitChunks { serial, chunks } => {
                 let _ = tx.try_send(ConnectionCommand::SubmitChunks { serial, chunks });
             }
+            ControlChannelCommand::FlushSpool => {
+                let _ = tx.try_send(ConnectionCommand::FlushSpool);
+            }
             ControlChannelCommand::FetchHistory { request, responder } => {
                 if let Err(err) =
                     tx.try_send(ConnectionCommand::FetchHistory { request, responder })
@@ -347,6 +359,37 @@ async fn send_ingest_batch(
     Ok(byte_count)
 }
 
+/// Replays spooled ingest batches oldest-first, skipping those already sent
+/// on this connection (`sent_through` is the newest such id). Batches stay
+/// spooled until Insight acknowledges them with `IngestAck`, so anything
+/// unacknowledged is sent again after a reconnect. Returns the number of
+/// batches sent.
+async fn flush_spool(
+    write: &mut ControlSocketWriter,
+    sent_through: &mut u64,
+) -> std::result::Result<usize, ()> {
+    let mut sent = 0;
+    while sent < SPOOL_REPLAY_BATCHES_PER_FLUSH {
+        let after = *sent_through;
+        let batch = match spawn_blocking(move || ingest_spool::oldest_after(after)).await {
+            Ok(Some(batch)) => batch,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Insight spool read failed: {e}");
+                break;
+            }
+        };
+        let chunks = ingest_spool::chunk_payload(&batch.data);
+        send_ingest_batch(write, batch.id as usize, chunks).await?;
+        *sent_through = batch.id;
+        sent += 1;
+    }
+    if sent > 0 {
+        debug!("Replayed {sent} spooled Insight batch(es)");
+    }
+    Ok(sent)
+}
+
 async fn persistent_connection(
     mut rx: tokio::sync::mpsc::Receiver<ConnectionCommand>,
 ) -> std::result::Result<(), String> {
@@ -397,6 +440,9 @@ async fn persistent_connection(
             let mut pending_chatbot_messages: Vec<Vec<u8>> = Vec::new();
             let mut next_history_request_id: u64 = 1;
             let mut next_support_request_id: u64 = 1;
+            // Newest spooled batch sent on this connection; reset on reconnect
+            // so unacknowledged batches are replayed.
+            let mut spool_sent_through: u64 = 0;
             let queue_license_grant_request = |socket_sender_tx: &tokio::sync::mpsc::Sender<
                 Message,
             >| {
@@ -444,6 +490,15 @@ async fn persistent_connection(
                                 };
                                 debug!("Submitted {} bytes for ingestion", byte_count);
                             }
+                            Some(ConnectionCommand::FlushSpool) => {
+                                if !permitted {
+                                    debug!("Not permitted to send chunks yet; keeping them spooled");
+                                    continue 'message_pump;
+                                }
+                                if flush_spool(&mut write, &mut spool_sent_through).await.is_err() {
+                                    break 'message_pump;
+                                }
+                            }
                             Some(ConnectionCommand::FetchHistory { request, responder }) => {
                                 if !permitted {
                                     warn!("Not permitted to request history yet");
@@ -830,6 +885,10 @@ async fn persistent_connection(
                                             capabilities::set_control_service_reachable(true);
                                             permitted = true;
                                             sleep_seconds = 60;
+                                            // Replay batches spooled while we were disconnected
+                                            if flush_spool(&mut write, &mut spool_sent_through).await.is_err() {
+                                                break 'message_pump;
+                                            }
                                             // Flush any pending chatbot messages now that we're permitted
                                             if !pending_chatbot_messages.is_empty() {
                                                 debug!(
@@ -892,6 +951,13 @@ async fn persistent_connection(
                                             }
                                         }
                                     }
+                                    // Only spooled batches replayed on this connection
+                                    // are deleted; anything else isn't ours to drop.
+                                    messages::WsMessage::IngestAck { unique_id }
+                                        if unique_id <= spool_sent_through =>
+                                    {
+                                        spawn_blocking(move || ingest_spool::remove(unique_id));
+                                    }
                                     messages::WsMessage::Heartbeat { timestamp } => {
                                         // Send a heartbeat reply
                                         debug!("Received heartbeat, sending reply");
@@ -1094,6 +1160,10 @@ async fn persistent_connection(
                         };
                     }
                     _ = ping_interval.tick() => {
+                        // Keep draining any spool backlog a little at a time
+                        if permitted && flush_spool(&mut write, &mut spool_sent_through).await.is_err() {
+                            break 'message_pump;
+                        }
                         // Send a WsMessage::Ping
                         debug!("Sending Ping message");
                         let bytes = vec![1u8; 4];
@@ -129,6 +129,10 @@ pub enum WsMessage {
     YouMaySubmit {
         ingestion_id: u64,
     },
+    /// Insight has stored the ingest batch sent with this `unique_id`.
+    IngestAck {
+        unique_id: u64,
+    },
     RemoteCommands {
         commands: Vec<RemoteCommand>,
     },
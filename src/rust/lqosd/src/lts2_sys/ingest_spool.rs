@@ -0,0 +1,314 @@
+//! Bounded on-disk spool for Insight ingest batches.
+//!
+//! Every finished ingest batch is written here before it is offered to the
+//! control channel, and deleted only once Insight acknowledges it. Batches left
+//! over from a WAN outage or an `lqosd` restart, and batches that were sent but
+//! never acknowledged, are replayed oldest-first when the control channel is
+//! permitted again. The spool is capped by total size and
+//! by age; the oldest batches are evicted first.
+
+use lqos_config::load_config;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+const SPOOL_DIRECTORY: &str = "lts2_spool";
+const SPOOL_EXTENSION: &str = "batch";
+/// Insight accepts ingest payloads in chunks of at most this many bytes.
+const CHUNK_SIZE: usize = 60 * 1024;
+
+static SPOOL: Lazy<Mutex<Option<IngestSpool>>> = Lazy::new(|| Mutex::new(None));
+
+/// Spool depth, as reported alongside the Insight shaper status.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct IngestSpoolStatus {
+    /// Batches waiting to be sent.
+    pub batches: u64,
+    /// Bytes waiting to be sent.
+    pub bytes: u64,
+    /// Creation time (unix seconds) of the oldest waiting batch.
+    pub oldest_unix: Option<u64>,
+    /// Batches dropped by the size or age cap since `lqosd` started.
+    pub evicted_batches: u64,
+}
+
+/// One spooled ingest payload.
+pub(crate) struct SpooledBatch {
+    pub(crate) id: u64,
+    pub(crate) data: Vec<u8>,
+}
+
+struct SpoolEntry {
+    id: u64,
+    created_unix: u64,
+    bytes: u64,
+}
+
+struct IngestSpool {
+    directory: PathBuf,
+    entries: VecDeque<SpoolEntry>,
+    next_id: u64,
+    evicted: u64,
+}
+
+impl IngestSpool {
+    fn open(directory: PathBuf) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&directory)?;
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&directory)?.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(SPOOL_EXTENSION) {
+                // Leftover temporary files from an interrupted write.
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+            let Some((id, created_unix)) = parse_file_name(&path) else {
+                continue;
+            };
+            let bytes = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+            entries.push(SpoolEntry {
+                id,
+                created_unix,
+                bytes,
+            });
+        }
+        entries.sort_by_key(|entry| entry.id);
+        let next_id = entries.last().map_or(1, |entry| entry.id + 1);
+        Ok(Self {
+            directory,
+            entries: entries.into(),
+            next_id,
+            evicted: 0,
+        })
+    }
+
+    fn path_for(&self, id: u64, created_unix: u64) -> PathBuf {
+        self.directory
+            .join(format!("{id:020}-{created_unix}.{SPOOL_EXTENSION}"))
+    }
+
+    fn push(
+        &mut self,
+        payload: &[u8],
+        now: u64,
+        max_bytes: u64,
+        max_age_seconds: u64,
+    ) -> std::io::Result<()> {
+        let id = self.next_id;
+        let path = self.path_for(id, now);
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, payload)?;
+        std::fs::rename(&temp_path, &path)?;
+        self.next_id += 1;
+        self.entries.push_back(SpoolEntry {
+            id,
+            created_unix: now,
+            bytes: payload.len() as u64,
+        });
+        self.enforce_caps(now, max_bytes, max_age_seconds);
+        Ok(())
+    }
+
+    /// Evicts the oldest batches until the spool fits both caps. The newest
+    /// batch is always kept.
+    fn enforce_caps(&mut self, now: u64, max_bytes: u64, max_age_seconds: u64) {
+        let cutoff = now.saturating_sub(max_age_seconds);
+        while self.entries.len() > 1 {
+            let total = self.entries.iter().map(|entry| entry.bytes).sum::<u64>();
+            let Some(oldest) = self.entries.front() else {
+                break;
+            };
+            if total <= max_bytes && oldest.created_unix >= cutoff {
+                break;
+            }
+            let (id, created_unix) = (oldest.id, oldest.created_unix);
+            let _ = std::fs::remove_file(self.path_for(id, created_unix));
+            self.entries.pop_front();
+            self.evicted += 1;
+        }
+    }
+
+    fn oldest_after(&mut self, after: u64) -> Option<SpooledBatch> {
+        while let Some(position) = self.entries.iter().position(|entry| entry.id > after) {
+            let entry = &self.entries[position];
+            let path = self.path_for(entry.id, entry.created_unix);
+            match std::fs::read(&path) {
+                Ok(data) => {
+                    return Some(SpooledBatch { id: entry.id, data });
+                }
+                Err(e) => {
+                    warn!(
+                        "Dropping unreadable Insight spool file {}: {e}",
+                        path.display()
+                    );
+                    let _ = std::fs::remove_file(&path);
+                    self.entries.remove(position);
+                }
+            }
+        }
+        None
+    }
+
+    fn remove(&mut self, id: u64) {
+        let Some(position) = self.entries.iter().position(|entry| entry.id == id) else {
+            return;
+        };
+        if let Some(entry) = self.entries.remove(position) {
+            let _ = std::fs::remove_file(self.path_for(entry.id, entry.created_unix));
+        }
+    }
+
+    fn status(&self) -> IngestSpoolStatus {
+        IngestSpoolStatus {
+            batches: self.entries.len() as u64,
+            bytes: self.entries.iter().map(|entry| entry.bytes).sum(),
+            oldest_unix: self.entries.front().map(|entry| entry.created_unix),
+            evicted_batches: self.evicted,
+        }
+    }
+}
+
+fn parse_file_name(path: &Path) -> Option<(u64, u64)> {
+    let stem = path.file_stem()?.to_str()?;
+    let (id, created_unix) = stem.split_once('-')?;
+    Some((id.parse().ok()?, created_unix.parse().ok()?))
+}
+
+/// Runs `f` against the spool, opening it under the state directory on first use.
+fn with_spool<T>(f: impl FnOnce(&mut IngestSpool) -> T) -> Option<T> {
+    let mut lock = SPOOL.lock();
+    if lock.is_none() {
+        let config = load_config().ok()?;
+        let directory = config.resolved_state_directory().join(SPOOL_DIRECTORY);
+        match IngestSpool::open(directory) {
+            Ok(spool) => {
+                debug!(
+                    "Opened Insight spool with {} pending batch(es)",
+                    spool.entries.len()
+                );
+                *lock = Some(spool);
+            }
+            Err(e) => {
+                warn!("Unable to open the Insight spool: {e}");
+                return None;
+            }
+        }
+    }
+    lock.as_mut().map(f)
+}
+
+/// Persists an ingest payload until Insight acknowledges it.
+///
+/// Side effects: writes a file under `state/lts2_spool` and may evict the
+/// oldest spooled batches to honor the configured caps.
+pub(crate) fn push(payload: &[u8]) -> anyhow::Result<()> {
+    let config = load_config()?;
+    let max_bytes = config
+        .long_term_stats
+        .spool_max_mb
+        .saturating_mul(1024 * 1024);
+    let max_age_seconds = config
+        .long_term_stats
+        .spool_max_age_hours
+        .saturating_mul(3600);
+    let now = lqos_utils::unix_time::unix_now().unwrap_or(0);
+    with_spool(|spool| spool.push(payload, now, max_bytes, max_age_seconds))
+        .ok_or_else(|| anyhow::anyhow!("Insight spool is unavailable"))??;
+    Ok(())
+}
+
+/// Returns the oldest spooled batch with an id greater than `after`, if any.
+/// Pass `0` to start from the oldest batch.
+///
+/// Side effects: reads (and drops, if unreadable) spool files. Blocks on file
+/// I/O, so async callers should run it on the blocking pool.
+pub(crate) fn oldest_after(after: u64) -> Option<SpooledBatch> {
+    with_spool(|spool| spool.oldest_after(after)).flatten()
+}
+
+/// Deletes a batch that Insight has acknowledged.
+///
+/// Side effects: removes the batch file from the spool directory.
+pub(crate) fn remove(id: u64) {
+    with_spool(|spool| spool.remove(id));
+}
+
+/// Returns the current spool depth.
+pub(crate) fn status() -> IngestSpoolStatus {
+    with_spool(|spool| spool.status()).unwrap_or_default()
+}
+
+/// Splits an ingest payload into the chunk sizes Insight accepts.
+///
+/// This function is pure: it has no side effects.
+pub(crate) fn chunk_payload(payload: &[u8]) -> Vec<Vec<u8>> {
+    payload
+        .chunks(CHUNK_SIZE)
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_spool(label: &str) -> PathBuf {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock should be after UNIX_EPOCH")
+            .as_nanos();
+        std::env::temp_dir().join(format!("libreqos-lts2-spool-{label}-{stamp}"))
+    }
+
+    #[test]
+    fn batches_replay_in_order_and_survive_reopen() {
+        let directory = temp_spool("order");
+        let mut spool = IngestSpool::open(directory.clone()).expect("spool opens");
+        spool.push(b"first", 100, u64::MAX, u64::MAX).expect("push");
+        spool
+            .push(b"second", 101, u64::MAX, u64::MAX)
+            .expect("push");
+        drop(spool);
+
+        let mut spool = IngestSpool::open(directory).expect("spool reopens");
+        assert_eq!(spool.status().batches, 2);
+        let first = spool.oldest_after(0).expect("first batch");
+        assert_eq!(first.data, b"first");
+        // Sent but not yet acknowledged: skipped by the next read on this
+        // connection, but still spooled for a replay after reconnecting.
+        let second = spool.oldest_after(first.id).expect("second batch");
+        assert_eq!(second.data, b"second");
+        assert!(spool.oldest_after(second.id).is_none());
+        spool.remove(first.id);
+        assert_eq!(spool.oldest_after(0).expect("second batch").data, b"second");
+        assert_eq!(spool.next_id, 3);
+    }
+
+    #[test]
+    fn caps_evict_oldest_first_but_keep_newest() {
+        let mut spool = IngestSpool::open(temp_spool("caps")).expect("spool opens");
+        spool.push(&[0; 10], 100, 25, u64::MAX).expect("push");
+        spool.push(&[1; 10], 101, 25, u64::MAX).expect("push");
+        spool.push(&[2; 10], 102, 25, u64::MAX).expect("push");
+        assert_eq!(spool.status().batches, 2);
+        assert_eq!(spool.status().evicted_batches, 1);
+        assert_eq!(spool.oldest_after(0).expect("batch").data, vec![1; 10]);
+
+        spool.push(&[3; 30], 5_000, 25, 3_600).expect("push");
+        let status = spool.status();
+        assert_eq!(status.batches, 1);
+        assert_eq!(status.oldest_unix, Some(5_000));
+    }
+
+    #[test]
+    fn payloads_split_into_insight_chunks() {
+        let payload = vec![7u8; CHUNK_SIZE * 2 + 1];
+        let chunks = chunk_payload(&payload);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].len(), 1);
+    }
+}
@@ -3,7 +3,8 @@ mod message_queue;
 
 use crate::lts2_sys::lts2_client::ingestor::message_queue::MessageQueue;
 use crate::lts2_sys::{
-    control_channel::ControlChannelCommand, lts2_client::ingestor::commands::IngestorCommand,
+    control_channel::ControlChannelCommand, ingest_spool,
+    lts2_client::ingestor::commands::IngestorCommand,
 };
 use parking_lot::Mutex;
 use std::sync::Arc;
@@ -35,13 +36,27 @@ fn ingestor_loop(rx: std::sync::mpsc::Receiver<IngestorCommand>) {
                 data
             };
             if !session_data.is_empty() {
-                let Ok(chunks) = session_data.build_chunks() else {
+                let Ok(payload) = session_data.build_payload() else {
                     tracing::error!("Failed to build chunks");
                     continue;
                 };
-                // If we have chunks, submit them
+                if payload.is_empty() {
+                    continue;
+                }
+                // Spool the batch first so it survives a WAN outage, then ask
+                // the control channel to drain the spool.
+                let command = match ingest_spool::push(&payload) {
+                    Ok(()) => ControlChannelCommand::FlushSpool,
+                    Err(e) => {
+                        warn!("Unable to spool Insight batch, sending directly: {e}");
+                        ControlChannelCommand::SubmitChunks {
+                            serial,
+                            chunks: ingest_spool::chunk_payload(&payload),
+                        }
+                    }
+                };
                 if let Ok(permit) = submit.try_reserve() {
-                    permit.send(ControlChannelCommand::SubmitChunks { serial, chunks });
+                    permit.send(command);
                 }
                 serial += 1;
             }
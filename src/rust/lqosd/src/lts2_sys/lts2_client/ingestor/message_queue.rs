@@ -110,7 +110,9 @@ impl MessageQueue {
         }
     }
 
-    pub(crate) fn build_chunks(&mut self) -> Result<Vec<Vec<u8>>> {
+    /// Builds the compressed ingest payload for everything queued so far.
+    /// Returns an empty payload when there is nothing to submit.
+    pub(crate) fn build_payload(&mut self) -> Result<Vec<u8>> {
         if !crate::lts2_sys::can_submit_long_term_stats() {
             self.clear();
             return Ok(vec![]);
@@ -160,16 +162,8 @@ impl MessageQueue {
             return Ok(vec![]);
         };
         let compressed_bytes = miniz_oxide::deflate::compress_to_vec(&raw_bytes, 10);
-
-        // Divide into chunks. Chunk size is 60k
-        const CHUNK_SIZE: usize = 60 * 1024;
-        let message_chunks = compressed_bytes.chunks(CHUNK_SIZE);
-        let n_chunks = message_chunks.len();
-        let chunks = message_chunks
-            .map(|chunk| chunk.to_vec())
-            .collect::<Vec<_>>();
-        debug!("Submitting {} chunks of data", n_chunks);
-        Ok(chunks)
+        debug!("Built {} byte ingest payload", compressed_bytes.len());
+        Ok(compressed_bytes)
     }
 
     pub(crate) fn clear(&mut self) {
@@ -3,9 +3,17 @@
 
 #![deny(clippy::unwrap_used)]
 
+mod access_policy;
 mod blackboard;
+mod cgnat;
+mod dhcp_leases;
+mod dscp_remarking;
 mod dynamic_circuits;
 mod file_lock;
+mod fleet;
+mod flood_detection;
+mod ha_sync;
+mod input_history;
 mod ip_mapping;
 #[cfg(feature = "equinix_tests")]
 mod lqos_daht_test;
@@ -13,23 +21,30 @@ pub mod lts2_sys;
 mod memory_watchdog;
 mod network_devices_hooks;
 mod node_manager;
+mod override_schedule_reaper;
 mod override_writer;
+mod planner_traffic;
 mod preflight_checks;
 mod probe_provider;
 mod program_control;
+mod qoo_assignment;
 mod radius_accounting;
 mod remote_commands;
 mod rtt_exclusions;
 mod scheduler_control;
 mod shaped_devices_tracker;
+mod shaping_plan;
 mod shaping_runtime;
+mod snmp_agent;
 mod stats;
 mod stick;
+mod subscriber_portal;
 mod system_stats;
 #[cfg(test)]
 mod test_support;
 mod throughput_tracker;
 mod tool_status;
+mod traffic_mirror;
 mod treeguard;
 mod tuning;
 mod urgent;
@@ -41,11 +56,15 @@ use std::io::Write;
 use std::net::IpAddr;
 
 use crate::ip_mapping::clear_hot_cache;
+use crate::node_manager::local_api::circuit_activity::{
+    CircuitTopApplicationsQuery, circuit_top_applications_data,
+};
 use crate::{
     file_lock::FileLock,
     ip_mapping::{clear_ip_flows, del_ip_flow, list_mapped_ips, map_ip_to_flow},
     throughput_tracker::flow_data::{
-        FlowActor, flowbee_handle_events, live_active_flow_count, setup_netflow_tracker,
+        FlowActor, flowbee_handle_events, flowbee_payload_handle_events, live_active_flow_count,
+        query_flow_archive, setup_netflow_tracker, traffic_group_report,
     },
 };
 use anyhow::Result;
@@ -262,6 +281,12 @@ fn main() -> Result<()> {
         network_devices_hooks::LqosdNetworkDevicesHooks,
     )))?;
     override_writer::start_override_writer_actor()?;
+    override_schedule_reaper::start_override_schedule_reaper();
+    input_history::start_input_history();
+    ha_sync::start_ha_sync();
+    fleet::start_fleet_poller();
+    snmp_agent::start_snmp_agent();
+    dhcp_leases::start_dhcp_leases();
     let system_usage_tx = system_stats::start_system_stats()?;
 
     // Handle signals
@@ -269,6 +294,7 @@ fn main() -> Result<()> {
     // Memory Debugging
     memory_debug();
     memory_watchdog::start_memory_watchdog();
+    planner_traffic::start_planner_traffic_sampler();
 
     // Prepare the bus path and clear any stale socket before fatal attach checks.
     // The bus does not bind or listen until after shaping startup below.
@@ -292,6 +318,7 @@ fn main() -> Result<()> {
                                 stick_offset,
                                 Some(heimdall_handle_events),
                                 Some(flowbee_handle_events),
+                                Some(flowbee_payload_handle_events),
                             )
                         } else {
                             LibreQoSKernels::new(
@@ -299,12 +326,18 @@ fn main() -> Result<()> {
                                 &config.isp_interface(),
                                 Some(heimdall_handle_events),
                                 Some(flowbee_handle_events),
+                                Some(flowbee_payload_handle_events),
                             )
                         };
 
                         match kernel_result {
                             Ok(started_kernels) => {
                                 kernels = Some(started_kernels);
+                                access_policy::start_walled_garden(&config);
+                                cgnat::start_cgnat(&config);
+                                flood_detection::start_flood_detection(&config);
+                                dscp_remarking::start_dscp_remarking(&config);
+                                traffic_mirror::start_traffic_mirror(&config);
                                 blackboard::start_blackboard();
                                 start_remote_commands();
                                 let flow_tx = setup_netflow_tracker()?;
@@ -447,6 +480,12 @@ fn main() -> Result<()> {
 
                 lqos_topology::start_topology_thread(bus_tx.clone());
 
+                if let Err(err) =
+                    subscriber_portal::start_subscriber_portal(&web_config.subscriber_portal).await
+                {
+                    error!("Subscriber portal was not started: {err:?}");
+                }
+
                 let webserver_disabled = web_config.disable_webserver.unwrap_or(false);
                 if !webserver_disabled {
                     let control_tx_for_webserver = control_tx_for_webserver.clone();
@@ -546,6 +585,12 @@ fn update_lqosd_config_from_bus(config: &lqos_config::Config) -> BusResponse {
         Ok(()) => {
             if let Ok(cfg) = lqos_config::load_config() {
                 let _ = stick::recompute_stick_offset(&cfg);
+                access_policy::program_walled_garden(&cfg);
+                cgnat::reload(&cfg);
+                flood_detection::reload(&cfg);
+                dscp_remarking::reload(&cfg);
+                traffic_mirror::reload(&cfg);
+                throughput_tracker::flow_data::reload_passive_dns(&cfg);
             }
             BusResponse::Ack
         }
@@ -556,6 +601,19 @@ fn update_lqosd_config_from_bus(config: &lqos_config::Config) -> BusResponse {
     }
 }
 
+/// Queues a `BakeryMqSetup`, `BakeryAddSite` or `BakeryAddCircuit` request
+/// into the Bakery's live batch.
+fn send_bakery_batch_command(request: &BusRequest) -> BusResponse {
+    let Some(sender) = lqos_bakery::BAKERY_SENDER.get() else {
+        return BusResponse::Fail("Bakery not initialized".to_string());
+    };
+    let Some(command) = lqos_bakery::BakeryCommands::from_batch_request(request) else {
+        return BusResponse::Fail(format!("{} is not a Bakery batch command", request.kind()));
+    };
+    let _ = sender.send(command);
+    BusResponse::Ack
+}
+
 fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>) {
     for req in requests.iter() {
         //println!("Request: {:?}", req);
@@ -616,6 +674,8 @@ fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>
                 if let Some(sender) = lqos_bakery::BAKERY_SENDER.get() {
                     let _ = sender.send(lqos_bakery::BakeryCommands::CommitMappings);
                 }
+                // New mappings start open; restore any restricted circuits.
+                access_policy::reconcile();
                 clear_hot_cache()
             }
             BusRequest::DelIpFlow { ip_address, upload: _ } => {
@@ -638,6 +698,9 @@ fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>
                 resp
             }
             BusRequest::ListIpFlow => list_mapped_ips(),
+            BusRequest::SetCircuitAccessPolicy { circuit_id, policy } => {
+                access_policy::set_circuit_access_policy(circuit_id, *policy)
+            }
             BusRequest::XdpPping => throughput_tracker::xdp_pping_compat(),
             BusRequest::RttHistogram => throughput_tracker::rtt_histogram::<50>(),
             BusRequest::ProbeBatch {
@@ -653,6 +716,48 @@ fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>
             BusRequest::HostCounts => throughput_tracker::host_counts(),
             BusRequest::AllUnknownIps => throughput_tracker::all_unknown_ips(),
             BusRequest::ReloadLibreQoS => program_control::reload_libre_qos(),
+            BusRequest::ListInputRevisions => input_history::list_input_revisions(),
+            BusRequest::DiffInputRevisions { from, to } => {
+                input_history::diff_input_revisions(*from, *to)
+            }
+            BusRequest::RestoreInputRevision { id } => input_history::restore_input_revision(*id),
+            BusRequest::PlanShapingChange {
+                network_json,
+                shaped_devices_csv,
+                operator_overrides,
+            } => shaping_plan::plan_shaping_change_response(shaping_plan::PlanCandidate {
+                network_json: network_json.clone(),
+                shaped_devices_csv: shaped_devices_csv.clone(),
+                operator_overrides: operator_overrides.clone(),
+            }),
+            BusRequest::GetHaStatus => ha_sync::ha_status(),
+            BusRequest::HaPromote => ha_sync::promote(),
+            BusRequest::ListFloodMitigations => flood_detection::list_mitigations(),
+            BusRequest::ClearFloodMitigation { circuit_id } => {
+                flood_detection::clear_mitigation(circuit_id.as_deref())
+            }
+            BusRequest::ListTrafficMirrors => traffic_mirror::list_sessions(),
+            BusRequest::StartTrafficMirror {
+                circuit_id,
+                prefixes,
+                minutes,
+                requested_by,
+                reason,
+            } => traffic_mirror::start_session(
+                circuit_id.as_deref(),
+                prefixes,
+                *minutes,
+                requested_by,
+                reason,
+            ),
+            BusRequest::StopTrafficMirror {
+                session_id,
+                requested_by,
+            } => traffic_mirror::stop_session(*session_id, requested_by),
+            BusRequest::IssueSubscriberPortalLink {
+                circuit_id,
+                valid_hours,
+            } => subscriber_portal::issue_link_response(circuit_id, *valid_hours),
             BusRequest::GetRawQueueData(circuit_id) => get_raw_circuit_data(circuit_id),
             BusRequest::WatchQueue(circuit_id) => {
                 add_watched_queue(circuit_id);
@@ -779,6 +884,11 @@ fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>
                     BusResponse::Fail("Bakery not initialized".to_string())
                 }
             }
+            BusRequest::BakeryPlanStart { plan_id } => shaping_plan::start_plan_batch(*plan_id),
+            BusRequest::BakeryPlanAdd { plan_id, commands } => {
+                shaping_plan::add_to_plan_batch(*plan_id, commands)
+            }
+            BusRequest::BakeryPlan { plan_id } => shaping_plan::plan_pending_batch(*plan_id),
             BusRequest::BakeryChangeSiteSpeedLive { site_hash, download_bandwidth_min, upload_bandwidth_min, download_bandwidth_max, upload_bandwidth_max } => {
                 if let Some(sender) = lqos_bakery::BAKERY_SENDER.get() {
                     let sender = sender.clone();
@@ -793,63 +903,16 @@ fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>
                 }
                 BusResponse::Ack
             }
-            BusRequest::BakeryMqSetup {
-                queues_available,
-                stick_offset,
-            } => {
-                if let Some(sender) = lqos_bakery::BAKERY_SENDER.get() {
-                    let sender = sender.clone();
-                    let _ = sender.send(lqos_bakery::BakeryCommands::MqSetup {
-                        queues_available: *queues_available,
-                        stick_offset: *stick_offset,
-                    });
-                    BusResponse::Ack
-                } else {
-                    BusResponse::Fail("Bakery not initialized".to_string())
-                }
-            }
-            BusRequest::BakeryAddSite {
-                site_hash,
-                parent_class_id,
-                up_parent_class_id,
-                class_minor,
-                download_bandwidth_min,
-                upload_bandwidth_min,
-                download_bandwidth_max,
-                upload_bandwidth_max,
-            } => {
-                if let Some(sender) = lqos_bakery::BAKERY_SENDER.get() {
-                    let sender = sender.clone();
-                    let _ = sender.send(lqos_bakery::BakeryCommands::AddSite {
-                        site_hash: *site_hash,
-                        parent_class_id: *parent_class_id,
-                        up_parent_class_id: *up_parent_class_id,
-                        class_minor: *class_minor,
-                        download_bandwidth_min: *download_bandwidth_min,
-                        upload_bandwidth_min: *upload_bandwidth_min,
-                        download_bandwidth_max: *download_bandwidth_max,
-                        upload_bandwidth_max: *upload_bandwidth_max,
-                    });
-                    BusResponse::Ack
-                } else {
-                    BusResponse::Fail("Bakery not initialized".to_string())
-                }
+            BusRequest::BakeryMqSetup { .. } | BusRequest::BakeryAddSite { .. } => {
+                send_bakery_batch_command(req)
             }
             BusRequest::BakeryAddCircuit {
                 circuit_hash,
-                circuit_name,
-                site_name,
                 parent_class_id,
                 up_parent_class_id,
                 class_minor,
-                download_bandwidth_min,
-                upload_bandwidth_min,
-                download_bandwidth_max,
-                upload_bandwidth_max,
-                class_major,
-                up_class_major,
-                ip_addresses,
                 sqm_override,
+                ..
             } => {
                 if let Some(s) = sqm_override.as_ref()
                     && s.eq_ignore_ascii_case("fq_codel") {
@@ -861,30 +924,7 @@ fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>
                             class_minor
                         );
                     }
-                if let Some(sender) = lqos_bakery::BAKERY_SENDER.get() {
-                    let sender = sender.clone();
-                    let _ = sender.send(lqos_bakery::BakeryCommands::AddCircuit {
-                        circuit_hash: *circuit_hash,
-                        circuit_name: circuit_name.clone(),
-                        site_name: site_name.clone(),
-                        parent_class_id: *parent_class_id,
-                        up_parent_class_id: *up_parent_class_id,
-                        class_minor: *class_minor,
-                        download_bandwidth_min: *download_bandwidth_min,
-                        upload_bandwidth_min: *upload_bandwidth_min,
-                        download_bandwidth_max: *download_bandwidth_max,
-                        upload_bandwidth_max: *upload_bandwidth_max,
-                        class_major: *class_major,
-                        up_class_major: *up_class_major,
-                        down_qdisc_handle: None,
-                        up_qdisc_handle: None,
-                        ip_addresses: ip_addresses.clone(),
-                        sqm_override: sqm_override.clone(),
-                    });
-                    BusResponse::Ack
-                } else {
-                    BusResponse::Fail("Bakery not initialized".to_string())
-                }
+                send_bakery_batch_command(req)
             }
             BusRequest::GetStormguardStats => {
                 let cloned = {
@@ -1160,10 +1200,35 @@ fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>
                     .map(|entry| lqos_bus::ProtocolListEntry {
                         count: entry.count,
                         protocol: entry.protocol,
+                        category: entry.category,
                     })
                     .collect();
                 BusResponse::ProtocolList(entries)
             }
+            BusRequest::GetCircuitTopApplications { circuit_id } => {
+                let data = circuit_top_applications_data(&CircuitTopApplicationsQuery {
+                    circuit: circuit_id.clone(),
+                    hide_small: false,
+                });
+                let entries = data
+                    .rows
+                    .into_iter()
+                    .map(|row| lqos_bus::CircuitApplicationEntry {
+                        application: row.application,
+                        category: row.category,
+                        bits_per_second: lqos_utils::units::DownUpOrder::new(
+                            row.down_bps,
+                            row.up_bps,
+                        ),
+                        bytes: lqos_utils::units::DownUpOrder::new(
+                            row.bytes_sent_down,
+                            row.bytes_sent_up,
+                        ),
+                        flow_count: row.flow_count,
+                    })
+                    .collect();
+                BusResponse::CircuitTopApplications(entries)
+            }
             BusRequest::GetAsnFlowTimeline { asn } => {
                 let data = node_manager::flow_timeline_data(*asn)
                     .unwrap_or_else(|err| {
@@ -1197,6 +1262,11 @@ fn handle_bus_requests(requests: &[BusRequest], responses: &mut Vec<BusResponse>
                     .collect();
                 BusResponse::ProtocolFlowTimeline(data)
             }
+            BusRequest::QueryFlowArchive(query) => match query_flow_archive(query) {
+                Ok(flows) => BusResponse::FlowArchive(flows),
+                Err(e) => BusResponse::Fail(e.to_string()),
+            },
+            BusRequest::GetTrafficGroups => BusResponse::TrafficGroups(traffic_group_report()),
             BusRequest::GetSchedulerDetails => {
                 let details = node_manager::scheduler_details_data();
                 BusResponse::SchedulerDetails(lqos_bus::SchedulerDetails {
@@ -1550,4 +1620,19 @@ mod tests {
             BusResponse::Qoo(None),
         ] if global.key == "global"));
     }
+
+    #[test]
+    fn bus_circuit_top_applications_request_dispatches() {
+        let requests = [BusRequest::GetCircuitTopApplications {
+            circuit_id: "missing-circuit".to_string(),
+        }];
+        let mut responses = Vec::new();
+
+        handle_bus_requests(&requests, &mut responses);
+
+        assert!(matches!(
+            responses.as_slice(),
+            [BusResponse::CircuitTopApplications(rows)] if rows.is_empty()
+        ));
+    }
 }
@@ -178,6 +178,7 @@ impl lqos_network_devices::DaemonHooks for LqosdNetworkDevicesHooks {
         lqos_network_devices::with_network_json_read(|net_json| {
             THROUGHPUT_TRACKER.refresh_circuit_ids(net_json);
         });
+        crate::access_policy::reconcile();
     }
 
     fn on_network_json_updated(&self) {
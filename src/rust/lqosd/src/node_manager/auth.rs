@@ -722,6 +722,16 @@ pub async fn login_from_cookie_header(cookie_header: Option<&str>) -> LoginResul
     login_from_token(token).await
 }
 
+/// The signed-in username from an HTTP Cookie header, for audit trails.
+pub fn username_from_cookie_header(cookie_header: Option<&str>) -> Option<String> {
+    let token = session_token_from_cookie_header(cookie_header)?;
+    let key = session_key().ok()?;
+    verify_signed_session(&key, token, &auth_snapshot())
+        .ok()
+        .flatten()
+        .map(|user| user.username)
+}
+
 fn session_token_from_cookie_header(cookie_header: Option<&str>) -> Option<&str> {
     let header = cookie_header?;
     header.split(';').find_map(|entry| {
@@ -776,7 +776,9 @@ function requestProtocolList() {
             itemRenderer: (row) => {
                 const li = document.createElement("li");
                 li.className = "dropdown-item";
-                li.innerText = `${row.protocol} (${row.count})`;
+                li.innerText = row.category
+                    ? `${row.protocol} · ${row.category} (${row.count})`
+                    : `${row.protocol} (${row.count})`;
                 li.onclick = () => {
                     state.evidenceScope = { type: "protocol", value: row.protocol, label: row.protocol };
                     loadEvidenceForScope(state.evidenceScope);
@@ -883,6 +885,7 @@ function renderFlowRows(allRows) {
     pageRows.forEach((row, index) => {
         const tr = document.createElement("tr");
         const remoteIp = String(row.remote_ip || "").trim();
+        const remoteHostname = String(row.remote_hostname || "").trim();
         const timelineId = `asnAnalysisFlowCanvas${state.evidencePage}_${index}`;
         const bytesDown = scaleNumber(toNumber(row?.total_bytes?.down, 0), 0);
         const bytesUp = scaleNumber(toNumber(row?.total_bytes?.up, 0), 0);
@@ -904,7 +907,7 @@ function renderFlowRows(allRows) {
             </td>
             <td class="redactable">
                 <div class="asn-analysis-flow-meta">
-                    <strong>${escapeHtml(remoteIp || "—")}</strong>
+                    <strong title="${escapeHtml(remoteIp)}">${escapeHtml(remoteHostname || remoteIp || "—")}</strong>
                     <span>Started ${escapeHtml(startLabel)}</span>
                 </div>
             </td>
@@ -199,7 +199,9 @@ function protocolDropdown() {
             buildItem: (row) => {
                 if (!row) return null;
                 let li = document.createElement("li");
-                li.innerText = `${row.protocol} (${row.count})`;
+                li.innerText = row.category
+                    ? `${row.protocol} · ${row.category} (${row.count})`
+                    : `${row.protocol} (${row.count})`;
                 li.classList.add("dropdown-item");
                 li.onclick = () => {
                     renderMode = "protocol";
@@ -391,7 +393,11 @@ function renderAsn(asn, data) {
         let remoteCol = document.createElement("div");
         remoteCol.classList.add("col-1", "text-secondary", "small");
         const remoteIp = String(row.remote_ip || "").trim();
-        remoteCol.appendChild(document.createTextNode(remoteIp));
+        const remoteHostname = String(row.remote_hostname || "").trim();
+        remoteCol.appendChild(document.createTextNode(remoteHostname || remoteIp));
+        if (remoteHostname) {
+            remoteCol.title = remoteIp;
+        }
         if (remoteIp) {
             const btn = document.createElement("button");
             btn.type = "button";
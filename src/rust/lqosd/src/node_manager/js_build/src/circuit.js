@@ -81,6 +81,7 @@ let latestCircuitSummary = null;
 let latestCircuitQooScore = null;
 let latestSankeyFlowMsg = { flows: [] };
 let latestTopAsnData = { total_asns: 0, rows: [] };
+let latestTopAppData = { total_applications: 0, rows: [] };
 let latestTrafficPage = null;
 let queuingActivityDirection = "down";
 let deviceGraphSpecs = [];
@@ -91,6 +92,8 @@ let sankeyPollTimer = null;
 let sankeyRequestInFlight = false;
 let topAsnPollTimer = null;
 let topAsnRequestInFlight = false;
+let topAppPollTimer = null;
+let topAppRequestInFlight = false;
 let trafficPollTimer = null;
 let trafficRequestInFlight = false;
 let circuitRatePollTimer = null;
@@ -491,6 +494,10 @@ function isTopAsnTabActive() {
     return document.getElementById("top-asns-tab")?.classList.contains("active") ?? false;
 }
 
+function isTopAppTabActive() {
+    return document.getElementById("top-apps-tab")?.classList.contains("active") ?? false;
+}
+
 function isSankeyTabActive() {
     return document.getElementById("sankey-tab")?.classList.contains("active") ?? false;
 }
@@ -1056,6 +1063,11 @@ function initTabLifecycle(parentNode) {
                     syncCircuitDetailSubscriptions();
                     return;
                 }
+                if (target === "#top-apps") {
+                    renderTopAppTab();
+                    syncCircuitDetailSubscriptions();
+                    return;
+                }
                 if (target === "#traffic") {
                     renderTrafficTab();
                     syncCircuitDetailSubscriptions();
@@ -1510,6 +1522,33 @@ function requestCircuitTopAsns() {
     });
 }
 
+function requestCircuitTopApps() {
+    if (topAppRequestInFlight) {
+        return;
+    }
+    topAppRequestInFlight = true;
+    listenOnce("CircuitTopApplicationsResult", (msg) => {
+        topAppRequestInFlight = false;
+        if (msg?.circuit_id !== circuit_id) {
+            return;
+        }
+        latestTopAppData = msg.data || { total_applications: 0, rows: [] };
+        if (isTopAppTabActive()) {
+            renderTopAppTab();
+        } else {
+            updateTopAppCountBadge();
+        }
+    });
+    wsClient.send({
+        CircuitTopApplications: {
+            query: {
+                circuit: circuit_id,
+                hide_small: hideSmallFlowsEnabled(),
+            },
+        },
+    });
+}
+
 function requestTrafficFlowsPage() {
     if (trafficRequestInFlight) {
         return;
@@ -1574,6 +1613,15 @@ function syncCircuitDetailSubscriptions(circuits = null) {
         topAsnPollTimer = clearPollingTimer(topAsnPollTimer);
     }
 
+    if (isTopAppTabActive()) {
+        requestCircuitTopApps();
+        if (topAppPollTimer === null) {
+            topAppPollTimer = window.setInterval(requestCircuitTopApps, 1000);
+        }
+    } else {
+        topAppPollTimer = clearPollingTimer(topAppPollTimer);
+    }
+
     if (isTrafficTabActive()) {
         requestTrafficFlowsPage();
         if (trafficPollTimer === null) {
@@ -1605,6 +1653,9 @@ function initFlowFilters() {
             } else {
                 updateTopAsnCountBadge();
             }
+            if (isTopAppTabActive()) {
+                requestCircuitTopApps();
+            }
             if (isSankeyTabActive()) {
                 requestCircuitFlowSankey();
             }
@@ -1672,6 +1723,15 @@ const TOP_ASN_TABLE_COLUMNS = [
     { group: "quality", numeric: true },
     { group: "flows", numeric: true },
 ];
+const TOP_APP_TABLE_COLUMNS = [
+    { group: "attribution" },
+    { group: "attribution" },
+    { group: "rates", numeric: true },
+    { group: "rates", numeric: true },
+    { group: "rates", numeric: true },
+    { group: "rates", numeric: true },
+    { group: "flows", numeric: true },
+];
 const TRAFFIC_TABLE_COLUMNS = [
     { group: "identity" },
     { group: "rates", numeric: true },
@@ -2015,6 +2075,66 @@ function renderTopAsnTab() {
     updateTopAsnCountBadge();
 }
 
+function updateTopAppCountBadge() {
+    $("#topAppCount").text(toNumber(latestTopAppData?.total_applications, 0));
+}
+
+function renderTopAppTab() {
+    const target = document.getElementById("topAppsTable");
+    if (!target) {
+        return;
+    }
+
+    const displayRows = Array.isArray(latestTopAppData?.rows) ? latestTopAppData.rows : [];
+
+    const tableWrap = document.createElement("div");
+    tableWrap.classList.add("lqos-table-wrap");
+
+    const table = document.createElement("table");
+    table.classList.add("lqos-table", "lqos-table-tight", "lqos-circuit-traffic-table", "lqos-circuit-data-table");
+    const thead = document.createElement("thead", "small");
+    thead.style.fontSize = "0.8em";
+    thead.appendChild(theading("Application"));
+    thead.appendChild(theading("Category"));
+    thead.appendChild(theading("Current Rate (d/u)", 2));
+    thead.appendChild(theading("Bytes (d/u)", 2));
+    thead.appendChild(theading("Flows"));
+    table.appendChild(thead);
+
+    const tbody = document.createElement("tbody");
+    if (displayRows.length === 0) {
+        const empty = document.createElement("tr");
+        const td = document.createElement("td");
+        td.colSpan = 7;
+        td.classList.add("text-center", "text-muted", "small");
+        td.textContent = "No recent application activity available for this circuit.";
+        empty.appendChild(td);
+        tbody.appendChild(empty);
+    } else {
+        displayRows.forEach((rowData) => {
+            const row = document.createElement("tr");
+            row.classList.add("small");
+
+            row.appendChild(truncatedTrafficCell(rowData.application, "lqos-circuit-traffic-asn-cell"));
+            row.appendChild(truncatedTrafficCell(rowData.category || "-", "lqos-circuit-traffic-country-cell"));
+            row.appendChild(simpleRowTrustedHtml(formatThroughput(rowData.down_bps, plan.down)));
+            row.appendChild(simpleRowTrustedHtml(formatThroughput(rowData.up_bps, plan.up)));
+            row.appendChild(simpleRow(scaleNumber(rowData.bytes_sent_down)));
+            row.appendChild(simpleRow(scaleNumber(rowData.bytes_sent_up)));
+            row.appendChild(simpleRow(scaleNumber(rowData.flow_count)));
+
+            tbody.appendChild(row);
+        });
+    }
+
+    table.appendChild(tbody);
+    annotateCircuitDataTable(table, TOP_APP_TABLE_COLUMNS);
+    tableWrap.appendChild(table);
+    clearDiv(target);
+    target.appendChild(tableWrap);
+    updateTopAppCountBadge();
+}
+
 function renderTrafficTab() {
     const target = document.getElementById("allTraffic");
     if (!target) {
@@ -2062,7 +2182,7 @@ function renderTrafficTab() {
     thead.appendChild(createSortableHeader("QoO (d/u)", "qoo", 2));
     thead.appendChild(createSortableHeader("ASN", "asn"));
     thead.appendChild(createSortableHeader("Country", "country"));
-    thead.appendChild(createSortableHeader("Remote IP", "ip"));
+    thead.appendChild(createSortableHeader("Remote", "ip"));
     thead.appendChild(theading("RTT Exclude"));
     table.appendChild(thead);
 
@@ -2096,7 +2216,13 @@ function renderTrafficTab() {
             row.appendChild(simpleRowTrustedHtml(formatQooScore(rowData.qoo_up)));
             row.appendChild(truncatedTrafficCell(rowData.asn_name, "lqos-circuit-traffic-asn-cell"));
             row.appendChild(truncatedTrafficCell(rowData.asn_country, "lqos-circuit-traffic-country-cell"));
-            row.appendChild(simpleRow(rowData.remote_ip));
+            if (rowData.remote_hostname) {
+                const remoteCell = truncatedTrafficCell(rowData.remote_hostname);
+                remoteCell.title = `${rowData.remote_hostname} (${rowData.remote_ip})`;
+                row.appendChild(remoteCell);
+            } else {
+                row.appendChild(simpleRow(rowData.remote_ip));
+            }
 
             const td = document.createElement("td");
             td.classList.add("text-center");
@@ -2704,6 +2830,7 @@ function cleanupCircuitPage() {
     devicePollTimer = clearPollingTimer(devicePollTimer);
     sankeyPollTimer = clearPollingTimer(sankeyPollTimer);
     topAsnPollTimer = clearPollingTimer(topAsnPollTimer);
+    topAppPollTimer = clearPollingTimer(topAppPollTimer);
     trafficPollTimer = clearPollingTimer(trafficPollTimer);
     circuitRatePollTimer = clearPollingTimer(circuitRatePollTimer);
     if (funnelSubscription) {
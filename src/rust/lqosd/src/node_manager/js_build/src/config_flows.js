@@ -108,7 +108,8 @@ function getSubnetsFromList(listId) {
 }
 
 function validateDoNotTrackList() {
-    const items = getSubnetsFromList('doNotTrackSubnets');
+    const items = getSubnetsFromList('doNotTrackSubnets')
+        .concat(getSubnetsFromList('passiveDnsExcludeSubnets'));
     return items.filter((cidr) => !isValidCIDR(cidr));
 }
 
@@ -188,6 +189,8 @@ function updateConfig() {
         netflow_version: document.getElementById("netflowVersion").value ?
             parseInt(document.getElementById("netflowVersion").value) : null,
         do_not_track_subnets: getSubnetsFromList('doNotTrackSubnets'),
+        passive_dns: document.getElementById("passiveDns").checked,
+        passive_dns_exclude_subnets: getSubnetsFromList('passiveDnsExcludeSubnets'),
     };
 }
 
@@ -199,6 +202,8 @@ function bindControls() {
     const removeBtn = document.getElementById('removeDoNotTrackSubnet');
     const saveBtn = document.getElementById('saveButton');
     const input = document.getElementById('newDoNotTrackSubnet');
+    const addPassiveDnsBtn = document.getElementById('addPassiveDnsExcludeSubnet');
+    const removePassiveDnsBtn = document.getElementById('removePassiveDnsExcludeSubnet');
 
     if (addBtn) {
         addBtn.addEventListener('click', () => {
@@ -221,6 +226,18 @@ function bindControls() {
             }
         });
     }
+    if (addPassiveDnsBtn) {
+        addPassiveDnsBtn.addEventListener('click', () => {
+            addSubnet('passiveDnsExcludeSubnets', 'newPassiveDnsExcludeSubnet');
+            updateDoNotTrackValidationUi();
+        });
+    }
+    if (removePassiveDnsBtn) {
+        removePassiveDnsBtn.addEventListener('click', () => {
+            removeSubnet('passiveDnsExcludeSubnets');
+            updateDoNotTrackValidationUi();
+        });
+    }
     if (saveBtn) {
         saveBtn.addEventListener('click', () => {
             if (!configLoaded) {
@@ -254,6 +271,8 @@ loadConfig(() => {
             netflow_ip: null,
             netflow_version: null,
             do_not_track_subnets: [],
+            passive_dns: null,
+            passive_dns_exclude_subnets: [],
         };
         
         // Required fields
@@ -267,6 +286,8 @@ loadConfig(() => {
 
         // Populate do not track list
         populateDoNotTrackList('doNotTrackSubnets', flows.do_not_track_subnets || []);
+        document.getElementById("passiveDns").checked = flows.passive_dns ?? false;
+        populateDoNotTrackList('passiveDnsExcludeSubnets', flows.passive_dns_exclude_subnets || []);
         configLoaded = true;
         setDoNotTrackLoadStatus("");
         updateDoNotTrackValidationUi();
@@ -55,6 +55,17 @@ function requestRuntimeSnapshot() {
     });
 }
 
+function requestPlannerProjection() {
+    return new Promise((resolve) => {
+        const timeout = setTimeout(() => resolve(null), 10000);
+        listenOnce("CpuAffinityPlannerProjection", (msg) => {
+            clearTimeout(timeout);
+            resolve(msg && msg.data ? msg.data : null);
+        });
+        wsClient.send({ CpuAffinityPlannerProjection: {} });
+    });
+}
+
 function fmtMbps(x) {
     if (x === null || x === undefined) return "-";
     let s = Number(x).toFixed(2);
@@ -635,6 +646,61 @@ function fetchCircuits() {
     });
 }
 
+function renderPlannerProjection(projection) {
+    const target = document.getElementById("plannerProjection");
+    const meta = document.getElementById("plannerProjectionMeta");
+    if (!target) return;
+    clearDiv(target);
+    if (meta) meta.textContent = "";
+
+    if (!projection) {
+        target.innerHTML = '<p class="text-muted">Planner projection is unavailable.</p>';
+        return;
+    }
+    if (!projection.enabled) {
+        target.innerHTML = '<p class="text-muted">Enable <code>[traffic_planner]</code> in <code>/etc/lqos.conf</code> to weight CPU placement by observed traffic.</p>';
+        return;
+    }
+    const cores = Array.isArray(projection.cores) ? projection.cores : [];
+    if (cores.length === 0) {
+        target.innerHTML = '<p class="text-muted">No top-level sites are available to plan.</p>';
+        return;
+    }
+    if (meta) {
+        const moves = Array.isArray(projection.moves) ? projection.moves.length : 0;
+        meta.textContent = `${toNumber(projection.observed_sites, 0)} of ${toNumber(projection.total_sites, 0)} sites observed · ${moves} pending move${moves === 1 ? "" : "s"}`;
+    }
+
+    const tableWrap = document.createElement("div");
+    tableWrap.className = "lqos-table-wrap";
+    const table = document.createElement("table");
+    table.className = "lqos-table lqos-table-compact";
+    const thead = document.createElement("thead");
+    thead.appendChild(theading("CPU"));
+    thead.appendChild(theading("Load Now", 0, "Weighted load (Mbps-equivalent) with the current placement, including NIC-locality cost."));
+    thead.appendChild(theading("Load After"));
+    thead.appendChild(theading("Sites Now"));
+    thead.appendChild(theading("Sites After"));
+    table.appendChild(thead);
+
+    const tbody = document.createElement("tbody");
+    cores.forEach((core) => {
+        const tr = document.createElement("tr");
+        if (toNumber(core.sites_before, 0) !== toNumber(core.sites_after, 0)) {
+            tr.classList.add("cpu-affinity-row-change");
+        }
+        tr.appendChild(simpleRow(`CPU ${core.cpu}`));
+        tr.appendChild(simpleRow(fmtMbps(core.before)));
+        tr.appendChild(simpleRow(fmtMbps(core.after)));
+        tr.appendChild(simpleRow(toNumber(core.sites_before, 0).toLocaleString()));
+        tr.appendChild(simpleRow(toNumber(core.sites_after, 0).toLocaleString()));
+        tbody.appendChild(tr);
+    });
+    table.appendChild(tbody);
+    tableWrap.appendChild(table);
+    target.appendChild(tableWrap);
+}
+
 async function refreshAll() {
     const overview = document.getElementById("cpuOverview");
     const showExcludedCores = document.getElementById("showExcludedCores");
@@ -661,6 +727,7 @@ async function refreshAll() {
     selectDefaultCpu();
     renderOverview();
     renderSelectedCore();
+    renderPlannerProjection(await requestPlannerProjection());
     enableTooltips();
 }
 
@@ -55,6 +55,7 @@ import {ShaperWorldMapDown} from "./world_map_down";
 import {ShaperWorldMapUp} from "./world_map_up";
 import {ExecutiveSnapshotDashlet} from "./executive_snapshot";
 import {ExecutiveHelpersDashlet} from "./executive_helpers";
+import {TrafficGroupsDashlet} from "./traffic_groups_dash";
 import {
     ExecutiveDownloadHeatmapDashlet,
     ExecutiveGlobalHeatmapDashlet,
@@ -126,6 +127,7 @@ export const DashletMenu = [
     { name: "TCP Retransmits Heatmap", tag: "executiveHeatmapRetrans", size: 6, category: "Executive" },
     { name: "Utilization Heatmap", tag: "executiveHeatmapDownload", size: 6, category: "Executive" },
     { name: "QoO Heatmap", tag: "executiveHeatmapUpload", size: 6, category: "Executive" },
+    { name: "Traffic by Peering Group", tag: "executiveTrafficGroups", size: 6, category: "Executive" },
 ];
 
 export function widgetFactory(widgetName, count) {
@@ -193,6 +195,7 @@ export function widgetFactory(widgetName, count) {
         case "executiveHeatmapRetrans": widget = new ExecutiveRetransmitsHeatmapDashlet(count); break;
         case "executiveHeatmapDownload": widget = new ExecutiveDownloadHeatmapDashlet(count); break;
         case "executiveHeatmapUpload": widget = new ExecutiveUploadHeatmapDashlet(count); break;
+        case "executiveTrafficGroups": widget = new TrafficGroupsDashlet(count); break;
         default: {
             console.log("I don't know how to construct a widget of type [" + widgetName + "]");
             return null;
@@ -11,6 +11,17 @@ const listenOnce = (eventName, handler) => {
     wsClient.on(eventName, wrapped);
 };
 
+function formatSpool(spool) {
+    if (!spool) {
+        return "";
+    }
+    if (spool.batches === 0) {
+        return "—";
+    }
+    const mb = (spool.bytes / (1024 * 1024)).toFixed(1);
+    return `${spool.batches} (${mb} MB)`;
+}
+
 export class LtsShaperStatus extends BaseDashlet {
     constructor(slot) {
         super(slot);
@@ -25,7 +36,7 @@ export class LtsShaperStatus extends BaseDashlet {
     }
 
     tooltip() {
-        return "<h5>Shaper Status</h5><p>Status from each of the LibreQoS shapers you are running.</p>";
+        return "<h5>Shaper Status</h5><p>Status from each of the LibreQoS shapers you are running. Spooled shows batches this shaper is holding on disk until Insight is reachable.</p>";
     }
 
     subscribeTo() {
@@ -62,6 +73,7 @@ export class LtsShaperStatus extends BaseDashlet {
                     thead.appendChild(theading(""));
                     thead.appendChild(theading("Shaper"));
                     thead.appendChild(theading("Last Seen (seconds)"));
+                    thead.appendChild(theading("Spooled"));
                     table.appendChild(thead);
                     let tbody = document.createElement("tbody");
 
@@ -77,6 +89,7 @@ export class LtsShaperStatus extends BaseDashlet {
                         tr.appendChild(simpleRowTrustedHtml(`<span style="color: ${color}">■</span>`));
                         tr.appendChild(simpleRow(row.name));
                         tr.appendChild(simpleRow(row.last_seen_seconds_ago + "s"));
+                        tr.appendChild(simpleRow(formatSpool(row.local_spool)));
                         tbody.appendChild(tr);
                     })
                     table.appendChild(tbody);
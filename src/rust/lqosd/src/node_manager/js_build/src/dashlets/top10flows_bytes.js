@@ -86,7 +86,7 @@ export class Top10FlowsBytes extends DashletBaseInsight {
             const totalHeading = theading("Total");
             totalHeading.classList.add("lqos-topflow-total-cell");
             th.appendChild(totalHeading);
-            const asnHeading = theading("Remote ASN");
+            const asnHeading = theading("Remote");
             asnHeading.classList.add("lqos-asn-cell");
             th.appendChild(asnHeading);
             t.appendChild(th);
@@ -145,13 +145,14 @@ export class Top10FlowsBytes extends DashletBaseInsight {
                 let asn = document.createElement("td");
                 asn.classList.add("lqos-asn-cell");
                 const asnLabel = (r.remote_asn_name && r.remote_asn_name.length > 0) ? r.remote_asn_name : r.remote_ip;
+                const remoteLabel = r.remote_hostname ? r.remote_hostname : asnLabel;
                 const asnText = document.createElement("span");
                 asnText.classList.add("lqos-table-cell-ellipsis");
-                if (asnLabel && asnLabel.length > MAX_VISIBLE_ASN_CHARS) {
+                if (remoteLabel && remoteLabel.length > MAX_VISIBLE_ASN_CHARS) {
                     asnText.classList.add("tiny");
                 }
-                asnText.textContent = compactAsnLabel(asnLabel || "");
-                asnText.title = asnLabel || "";
+                asnText.textContent = compactAsnLabel(remoteLabel || "");
+                asnText.title = r.remote_hostname ? `${r.remote_hostname} (${asnLabel})` : (asnLabel || "");
                 asn.appendChild(asnText);
                 row.appendChild(asn);
 
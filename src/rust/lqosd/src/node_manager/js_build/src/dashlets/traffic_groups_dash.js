@@ -0,0 +1,67 @@
+import {BaseDashlet} from "../lq_js_common/dashboard/base_dashlet";
+import {clearDashDiv, simpleRow, theading} from "../helpers/builders";
+import {scaleNumber} from "../lq_js_common/helpers/scaling";
+
+export class TrafficGroupsDashlet extends BaseDashlet {
+    constructor(slot) {
+        super(slot);
+    }
+
+    canBeSlowedDown() { return true; }
+    title() { return "Traffic by Peering Group"; }
+    tooltip() {
+        return "<h5>Traffic by Peering Group</h5><p>Current rate and total bytes exchanged with each configured traffic group (on-net, caches, IX peers and so on). Traffic matching no group is shown as Other. Groups are defined with <code>[[traffic_groups]]</code> in <code>/etc/lqos.conf</code>.</p>";
+    }
+    subscribeTo() { return ["TrafficGroups"]; }
+
+    buildContainer() {
+        let base = super.buildContainer();
+        base.style.height = "250px";
+        base.style.overflow = "auto";
+        return base;
+    }
+
+    onMessage(msg) {
+        if (msg.event !== "TrafficGroups") return;
+        let target = document.getElementById(this.id);
+        let rows = msg.data || [];
+
+        clearDashDiv(this.id, target);
+        if (rows.length === 0) {
+            let empty = document.createElement("div");
+            empty.classList.add("text-center", "text-muted", "small");
+            empty.textContent = "No traffic groups configured";
+            target.appendChild(empty);
+            return;
+        }
+
+        let t = document.createElement("table");
+        t.classList.add("dash-table", "lqos-table", "lqos-table-compact", "small");
+
+        let th = document.createElement("thead");
+        th.classList.add("small");
+        th.appendChild(theading("Group"));
+        th.appendChild(theading("⬇️ bps"));
+        th.appendChild(theading("⬆️ bps"));
+        th.appendChild(theading("⬇️ Total"));
+        th.appendChild(theading("⬆️ Total"));
+        t.appendChild(th);
+
+        let tbody = document.createElement("tbody");
+        rows
+            .slice()
+            .sort((a, b) => (b.bits_per_second.down + b.bits_per_second.up) - (a.bits_per_second.down + a.bits_per_second.up))
+            .forEach((row) => {
+                let tr = document.createElement("tr");
+                tr.classList.add("small");
+                tr.appendChild(simpleRow(row.group));
+                tr.appendChild(simpleRow(scaleNumber(row.bits_per_second.down)));
+                tr.appendChild(simpleRow(scaleNumber(row.bits_per_second.up)));
+                tr.appendChild(simpleRow(scaleNumber(row.bytes.down)));
+                tr.appendChild(simpleRow(scaleNumber(row.bytes.up)));
+                tbody.appendChild(tr);
+            });
+        t.appendChild(tbody);
+        target.appendChild(t);
+    }
+}
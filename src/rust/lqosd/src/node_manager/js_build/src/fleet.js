@@ -0,0 +1,209 @@
+import {get_ws_client} from "./pubsub/ws";
+import {scaleNumber} from "./lq_js_common/helpers/scaling";
+
+const wsClient = get_ws_client();
+const REFRESH_MS = 10000;
+
+function sendWsRequest(responseEvent, request) {
+    return new Promise((resolve, reject) => {
+        let done = false;
+        const responseHandler = (msg) => {
+            if (done) return;
+            done = true;
+            wsClient.off(responseEvent, responseHandler);
+            wsClient.off("Error", errorHandler);
+            resolve(msg);
+        };
+        const errorHandler = (msg) => {
+            if (done) return;
+            done = true;
+            wsClient.off(responseEvent, responseHandler);
+            wsClient.off("Error", errorHandler);
+            reject(msg);
+        };
+        wsClient.on(responseEvent, responseHandler);
+        wsClient.on("Error", errorHandler);
+        wsClient.send(request);
+    });
+}
+
+function escapeHtml(text) {
+    return String(text ?? "")
+        .replaceAll("&", "&amp;")
+        .replaceAll("<", "&lt;")
+        .replaceAll(">", "&gt;")
+        .replaceAll('"', "&quot;")
+        .replaceAll("'", "&#39;");
+}
+
+function setStatus(label, badgeClass) {
+    const status = document.getElementById("fleetStatus");
+    if (!status) {
+        return;
+    }
+    status.className = `badge ${badgeClass}`;
+    status.textContent = label;
+}
+
+function setText(id, text) {
+    const element = document.getElementById(id);
+    if (element) {
+        element.textContent = text;
+    }
+}
+
+function formatUnix(unix) {
+    if (!unix) {
+        return "—";
+    }
+    return new Date(unix * 1000).toLocaleString();
+}
+
+function bps(value) {
+    return `${scaleNumber(value || 0, 1)}bps`;
+}
+
+// Local links are relative; links to other nodes open in a new tab.
+function nodeLink(link, label, extraClass = "") {
+    const external = /^https?:\/\//.test(link);
+    const target = external ? ' target="_blank" rel="noopener noreferrer"' : "";
+    return `<a class="${extraClass}" href="${escapeHtml(link)}"${target}>${escapeHtml(label)}</a>`;
+}
+
+function renderNodes(nodes) {
+    const table = document.getElementById("fleetNodes");
+    if (!table) {
+        return;
+    }
+    table.innerHTML = nodes.map((node) => {
+        const health = node.reachable
+            ? `<span class="badge bg-success">Up</span> <span class="text-muted small">${node.latency_ms ?? 0} ms</span>`
+            : `<span class="badge bg-danger">Down</span> <div class="text-muted small">${escapeHtml(node.last_error || "Not polled yet")}</div>
+               <div class="text-muted small">Last seen ${escapeHtml(formatUnix(node.last_success_unix))}</div>`;
+        const bakery = node.bakery
+            ? `${escapeHtml(node.bakery.mode)}${node.bakery.passthrough_degraded ? ' <span class="badge bg-danger">Pass-through</span>' : ""}${node.bakery.reload_required ? ' <span class="badge bg-warning text-dark">Reload required</span>' : ""}
+               <div class="text-muted small">${node.bakery.active_circuits} circuits</div>`
+            : "—";
+        const stormguard = node.stormguard
+            ? (node.stormguard.enabled
+                ? `${escapeHtml(node.stormguard.mode)} / ${escapeHtml(node.stormguard.phase)}${node.stormguard.last_error ? `<div class="text-danger small">${escapeHtml(node.stormguard.last_error)}</div>` : ""}`
+                : '<span class="text-muted">Disabled</span>')
+            : "—";
+        return `
+        <tr>
+            <td class="fw-semibold">${nodeLink(node.link, node.name)}${node.local ? ' <span class="badge bg-secondary">This node</span>' : ""}</td>
+            <td>${health}</td>
+            <td>${node.throughput ? bps(node.throughput.bits_per_second.down) : "—"}</td>
+            <td>${node.throughput ? bps(node.throughput.bits_per_second.up) : "—"}</td>
+            <td class="small">${bakery}</td>
+            <td class="small">${stormguard}</td>
+            <td>${node.urgent_count > 0 ? `<span class="badge bg-danger">${node.urgent_count}</span>` : "0"}</td>
+            <td class="small">${escapeHtml(node.version || "—")}</td>
+        </tr>`;
+    }).join("");
+}
+
+function renderRanking(id, rows, digits) {
+    const table = document.getElementById(id);
+    if (!table) {
+        return;
+    }
+    if (rows.length === 0) {
+        table.innerHTML = '<tr><td colspan="3" class="text-muted">No data yet.</td></tr>';
+        return;
+    }
+    table.innerHTML = rows.map((row) => `
+        <tr>
+            <td class="small">${escapeHtml(row.node)}</td>
+            <td>${nodeLink(row.link, row.label, "redactable")} <span class="text-muted small">${row.kind === "Site" ? "site" : "circuit"}</span></td>
+            <td class="text-end">${Number(row.value).toFixed(digits)}</td>
+        </tr>`).join("");
+}
+
+function renderUrgent(rows) {
+    const table = document.getElementById("fleetUrgent");
+    if (!table) {
+        return;
+    }
+    if (rows.length === 0) {
+        table.innerHTML = '<tr><td colspan="4" class="text-muted">No urgent issues on reachable nodes.</td></tr>';
+        return;
+    }
+    table.innerHTML = rows.map((row) => `
+        <tr>
+            <td class="small">${nodeLink(row.link, row.node)}</td>
+            <td class="small">${escapeHtml(formatUnix(row.ts))}</td>
+            <td class="small">${escapeHtml(row.severity)}</td>
+            <td class="small"><span class="fw-semibold">${escapeHtml(row.code)}</span> ${escapeHtml(row.message)}</td>
+        </tr>`).join("");
+}
+
+function render(overview) {
+    setText("fleetDown", bps(overview.throughput.bits_per_second.down));
+    setText("fleetUp", bps(overview.throughput.bits_per_second.up));
+    setText("fleetCircuits", String(overview.active_circuits));
+    setText("fleetUrgentCount", String(overview.urgent_count));
+    setText(
+        "fleetSummary",
+        `${overview.nodes_reachable} of ${overview.nodes.length} node${overview.nodes.length === 1 ? "" : "s"} reachable, updated ${formatUnix(overview.generated_at_unix)}`,
+    );
+    renderNodes(overview.nodes);
+    renderRanking("fleetWorstRtt", overview.worst_by_rtt, 1);
+    renderRanking("fleetWorstRetransmits", overview.worst_by_retransmits, 2);
+    renderUrgent(overview.urgent);
+}
+
+function describeSearchResult(result) {
+    if (result.Circuit) {
+        return `Circuit ${result.Circuit.name}`;
+    }
+    if (result.Device) {
+        return `Device ${result.Device.name} (${result.Device.circuit_name})`;
+    }
+    if (result.Site) {
+        return `Site ${result.Site.name}`;
+    }
+    return "Result";
+}
+
+async function search(term) {
+    const target = document.getElementById("fleetSearchResults");
+    if (!target) {
+        return;
+    }
+    target.textContent = "Searching…";
+    try {
+        const response = await sendWsRequest("FleetSearchResults", {FleetSearch: {term}});
+        const {hits, failed} = response.data;
+        const rows = hits.map((hit) => `
+            <div><span class="badge bg-secondary me-1">${escapeHtml(hit.node)}</span>${nodeLink(hit.link, describeSearchResult(hit.result), "redactable")}</div>`);
+        const failures = failed.map(([node, error]) => `
+            <div class="text-warning">${escapeHtml(node)} could not be searched: ${escapeHtml(error)}</div>`);
+        target.innerHTML = rows.concat(failures).join("") || "No matches.";
+    } catch (error) {
+        target.textContent = error?.message || "Search failed";
+    }
+}
+
+async function refresh() {
+    try {
+        const response = await sendWsRequest("GetFleetOverview", {GetFleetOverview: {}});
+        render(response.data);
+        const down = response.data.nodes.length - response.data.nodes_reachable;
+        setStatus(down > 0 ? `${down} down` : "All up", down > 0 ? "bg-warning text-dark" : "bg-success");
+    } catch (error) {
+        setStatus("Error", "bg-danger");
+        setText("fleetSummary", error?.message || "Unable to load the fleet view");
+    }
+}
+
+document.getElementById("fleetSearchForm")?.addEventListener("submit", (event) => {
+    event.preventDefault();
+    const term = document.getElementById("fleetSearchTerm")?.value.trim();
+    if (term) {
+        search(term);
+    }
+});
+
+refresh();
+setInterval(refresh, REFRESH_MS);
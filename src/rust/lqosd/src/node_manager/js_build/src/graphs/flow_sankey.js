@@ -52,7 +52,7 @@ export class FlowsSankey extends DashboardGraph {
             let proto = flow.protocol_name;
             let asn = "ASN: " + flow.asn_id;
             if (flow.asn_name !== "") asn += " " + flow.asn_name;
-            let remoteDevice = flow.remote_ip;
+            let remoteDevice = flow.remote_hostname || flow.remote_ip;
         
             // Ensure all members are present. The arrays hold links to subsequent
             // columns.
@@ -0,0 +1,173 @@
+import {get_ws_client} from "./pubsub/ws";
+
+const wsClient = get_ws_client();
+let revisions = null;
+
+function sendWsRequest(responseEvent, request) {
+    return new Promise((resolve, reject) => {
+        let done = false;
+        const responseHandler = (msg) => {
+            if (done) return;
+            done = true;
+            wsClient.off(responseEvent, responseHandler);
+            wsClient.off("Error", errorHandler);
+            resolve(msg);
+        };
+        const errorHandler = (msg) => {
+            if (done) return;
+            done = true;
+            wsClient.off(responseEvent, responseHandler);
+            wsClient.off("Error", errorHandler);
+            reject(msg);
+        };
+        wsClient.on(responseEvent, responseHandler);
+        wsClient.on("Error", errorHandler);
+        wsClient.send(request);
+    });
+}
+
+function escapeHtml(text) {
+    return String(text ?? "")
+        .replaceAll("&", "&amp;")
+        .replaceAll("<", "&lt;")
+        .replaceAll(">", "&gt;")
+        .replaceAll('"', "&quot;")
+        .replaceAll("'", "&#39;");
+}
+
+function setStatus(label, badgeClass) {
+    const status = document.getElementById("inputHistoryStatus");
+    if (!status) {
+        return;
+    }
+    status.className = `badge ${badgeClass}`;
+    status.textContent = label;
+}
+
+function formatUnix(unix) {
+    if (!unix) {
+        return "—";
+    }
+    return new Date(unix * 1000).toLocaleString();
+}
+
+function renderTable() {
+    const table = document.getElementById("inputHistoryTable");
+    const summary = document.getElementById("inputHistorySummary");
+    if (!table || !summary) {
+        return;
+    }
+    summary.textContent = `${revisions.length} revision${revisions.length === 1 ? "" : "s"}`;
+    if (revisions.length === 0) {
+        table.innerHTML = '<tr><td colspan="5" class="text-muted">No revisions recorded yet.</td></tr>';
+        return;
+    }
+
+    table.innerHTML = revisions.map((revision, index) => {
+        const hasPrevious = index + 1 < revisions.length;
+        const isNewest = index === 0;
+        return `
+        <tr>
+            <td class="fw-semibold">#${revision.id}</td>
+            <td class="small">${escapeHtml(formatUnix(revision.created_unix))}</td>
+            <td class="small">${escapeHtml(revision.source)}</td>
+            <td class="small">${escapeHtml((revision.changed_files || []).join(", ") || "—")}</td>
+            <td class="text-end text-nowrap">
+                <button class="btn btn-sm btn-outline-secondary" type="button" data-diff-index="${index}" ${hasPrevious ? "" : "disabled"}>
+                    <i class="fa fa-code-compare"></i> Changes
+                </button>
+                <button class="btn btn-sm btn-outline-warning" type="button" data-restore-id="${revision.id}" ${isNewest ? "disabled" : ""}>
+                    <i class="fa fa-rotate-left"></i> Roll back
+                </button>
+            </td>
+        </tr>`;
+    }).join("");
+
+    table.querySelectorAll("[data-diff-index]").forEach((button) => {
+        button.addEventListener("click", () => showDiff(Number(button.dataset.diffIndex)));
+    });
+    table.querySelectorAll("[data-restore-id]").forEach((button) => {
+        button.addEventListener("click", () => restoreRevision(Number(button.dataset.restoreId)));
+    });
+}
+
+function diffList(label, items) {
+    if (!items || items.length === 0) {
+        return "";
+    }
+    const shown = items.slice(0, 200).map(escapeHtml).join(", ");
+    const more = items.length > 200 ? ` and ${items.length - 200} more` : "";
+    return `<div class="mb-1"><span class="fw-semibold">${label} (${items.length}):</span> ${shown}${more}</div>`;
+}
+
+async function showDiff(index) {
+    const to = revisions[index];
+    const from = revisions[index + 1];
+    const title = document.getElementById("inputHistoryDiffTitle");
+    const target = document.getElementById("inputHistoryDiff");
+    if (!to || !from || !target) {
+        return;
+    }
+    if (title) {
+        title.textContent = `Changes from #${from.id} to #${to.id}`;
+    }
+    target.textContent = "Loading…";
+    try {
+        const response = await sendWsRequest("GetInputRevisionDiff", {
+            GetInputRevisionDiff: {from: from.id, to: to.id},
+        });
+        const diff = response.data;
+        const body = [
+            diffList("Files changed", diff.changed_files),
+            diffList("Nodes added", diff.nodes_added),
+            diffList("Nodes removed", diff.nodes_removed),
+            diffList("Nodes changed", diff.nodes_changed),
+            diffList("Circuits added", diff.circuits_added),
+            diffList("Circuits removed", diff.circuits_removed),
+            diffList("Circuits changed", diff.circuits_changed),
+        ].join("");
+        target.innerHTML = body || "No differences.";
+    } catch (error) {
+        target.textContent = error?.message || "Unable to compare revisions";
+    }
+}
+
+async function restoreRevision(id) {
+    if (!window.confirm(`Restore all shaping inputs to revision #${id} and reload LibreQoS?`)) {
+        return;
+    }
+    setStatus("Rolling back…", "bg-warning");
+    try {
+        const response = await sendWsRequest("RestoreInputRevisionResult", {
+            RestoreInputRevision: {id},
+        });
+        await loadPage();
+        setStatus(response.ok ? "Restored" : "Error", response.ok ? "bg-success" : "bg-danger");
+        const summary = document.getElementById("inputHistorySummary");
+        if (summary) {
+            summary.textContent = response.message;
+        }
+    } catch (error) {
+        setStatus("Error", "bg-danger");
+    }
+}
+
+async function loadPage() {
+    try {
+        const response = await sendWsRequest("GetInputHistory", {GetInputHistory: {}});
+        revisions = response.data || [];
+        renderTable();
+        setStatus("Loaded", "bg-success");
+    } catch (error) {
+        revisions = [];
+        renderTable();
+        setStatus("Error", "bg-danger");
+        const summary = document.getElementById("inputHistorySummary");
+        if (summary) {
+            summary.textContent = error?.message || "Unable to load input history";
+        }
+    }
+}
+
+setStatus("Loading…", "bg-secondary");
+loadPage();
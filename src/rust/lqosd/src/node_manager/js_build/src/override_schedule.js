@@ -0,0 +1,143 @@
+import {get_ws_client} from "./pubsub/ws";
+
+const wsClient = get_ws_client();
+let scheduleState = null;
+
+function sendWsRequest(responseEvent, request) {
+    return new Promise((resolve, reject) => {
+        let done = false;
+        const responseHandler = (msg) => {
+            if (done) return;
+            done = true;
+            wsClient.off(responseEvent, responseHandler);
+            wsClient.off("Error", errorHandler);
+            resolve(msg);
+        };
+        const errorHandler = (msg) => {
+            if (done) return;
+            done = true;
+            wsClient.off(responseEvent, responseHandler);
+            wsClient.off("Error", errorHandler);
+            reject(msg);
+        };
+        wsClient.on(responseEvent, responseHandler);
+        wsClient.on("Error", errorHandler);
+        wsClient.send(request);
+    });
+}
+
+function escapeHtml(text) {
+    return String(text ?? "")
+        .replaceAll("&", "&amp;")
+        .replaceAll("<", "&lt;")
+        .replaceAll(">", "&gt;")
+        .replaceAll('"', "&quot;")
+        .replaceAll("'", "&#39;");
+}
+
+function setStatus(label, badgeClass) {
+    const status = document.getElementById("overrideScheduleStatus");
+    if (!status) {
+        return;
+    }
+    status.className = `badge ${badgeClass}`;
+    status.textContent = label;
+}
+
+function formatUnix(unix) {
+    if (!unix) {
+        return "—";
+    }
+    return new Date(unix * 1000).toLocaleString();
+}
+
+function stateBadge(entry) {
+    switch (entry.state) {
+    case "upcoming":
+        return "<span class='badge bg-info-subtle text-info-emphasis'>Upcoming</span>";
+    case "active":
+        return "<span class='badge bg-success-subtle text-success'>Active</span>";
+    default:
+        return entry.retired
+            ? "<span class='badge bg-secondary-subtle text-secondary'>Retired</span>"
+            : "<span class='badge bg-warning-subtle text-warning-emphasis'>Expiring</span>";
+    }
+}
+
+function filteredEntries() {
+    const entries = scheduleState?.entries || [];
+    const search = (document.getElementById("overrideScheduleSearch")?.value || "").trim().toLowerCase();
+    const state = document.getElementById("overrideScheduleStateFilter")?.value || "all";
+    return entries.filter((entry) => {
+        if (state !== "all" && entry.state !== state) {
+            return false;
+        }
+        if (!search) {
+            return true;
+        }
+        return [entry.description, entry.schedule?.reason, entry.schedule?.owner]
+            .some((value) => String(value || "").toLowerCase().includes(search));
+    });
+}
+
+function renderTable() {
+    const table = document.getElementById("overrideScheduleTable");
+    const summary = document.getElementById("overrideScheduleSummary");
+    if (!table || !summary) {
+        return;
+    }
+    if (!scheduleState) {
+        table.innerHTML = '<tr><td colspan="6" class="text-muted">Loading scheduled overrides…</td></tr>';
+        return;
+    }
+    const entries = filteredEntries();
+    const total = scheduleState.entries?.length || 0;
+    summary.textContent = `${entries.length} of ${total} override${total === 1 ? "" : "s"}`;
+
+    if (entries.length === 0) {
+        table.innerHTML = '<tr><td colspan="6" class="text-muted">No scheduled overrides match the current filters.</td></tr>';
+        return;
+    }
+
+    table.innerHTML = entries.map((entry) => `
+        <tr>
+            <td>
+                <div class="fw-semibold">${escapeHtml(entry.description)}</div>
+                <div class="small text-muted">${escapeHtml(entry.section)} #${entry.index}</div>
+            </td>
+            <td>${stateBadge(entry)}</td>
+            <td class="small">${escapeHtml(formatUnix(entry.schedule?.not_before))}</td>
+            <td class="small">${escapeHtml(formatUnix(entry.schedule?.expires_at))}</td>
+            <td class="small">${escapeHtml(entry.schedule?.reason || "—")}</td>
+            <td class="small">${escapeHtml(entry.schedule?.owner || "—")}</td>
+        </tr>
+    `).join("");
+}
+
+async function loadPage() {
+    try {
+        const response = await sendWsRequest("GetOverrideSchedule", {GetOverrideSchedule: {}});
+        scheduleState = response.data || {entries: []};
+        const updated = document.getElementById("overrideScheduleUpdated");
+        if (updated) {
+            updated.textContent = `Updated ${formatUnix(scheduleState.now_unix)}`;
+        }
+        renderTable();
+        setStatus("Loaded", "bg-success");
+    } catch (error) {
+        scheduleState = {entries: []};
+        renderTable();
+        setStatus("Error", "bg-danger");
+        const summary = document.getElementById("overrideScheduleSummary");
+        if (summary) {
+            summary.textContent = error?.message || "Unable to load scheduled overrides";
+        }
+    }
+}
+
+document.getElementById("overrideScheduleSearch")?.addEventListener("input", renderTable);
+document.getElementById("overrideScheduleStateFilter")?.addEventListener("change", renderTable);
+
+setStatus("Loading…", "bg-secondary");
+loadPage();
+window.setInterval(loadPage, 30000);
@@ -211,6 +211,12 @@ function renderTable() {
         if (entry.suppressed_until_unix) {
             healthBits.push(`<div class="text-muted">Suppressed until ${escapeHtml(formatUnix(entry.suppressed_until_unix))}</div>`);
         }
+        if (entry.routing_state) {
+            const routing = entry.routing_state.up
+                ? "<span class='badge bg-success-subtle text-success'>Up</span>"
+                : "<span class='badge bg-danger-subtle text-danger'>Down</span>";
+            healthBits.push(`<div>Routing ${routing} <span class="text-muted">${escapeHtml(entry.routing_state.detail)}</span></div>`);
+        }
         return `
             <tr>
                 <td>
@@ -10,8 +10,11 @@ pub(crate) mod directories;
 pub(crate) mod ethernet_caps;
 pub(crate) mod executive;
 pub(crate) mod executive_cache;
+pub(crate) mod fleet;
+pub(crate) mod flow_archive;
 pub(crate) mod flow_explorer;
 pub(crate) mod flow_map;
+pub(crate) mod input_history;
 pub(crate) mod local_api_keys;
 pub mod lts;
 pub(crate) mod network_mode;
@@ -19,16 +22,21 @@ pub(crate) mod network_tree;
 pub(crate) mod network_tree_lite;
 pub(crate) mod node_rate_overrides;
 pub(crate) mod node_topology_overrides;
+pub(crate) mod override_schedule;
 pub(crate) mod packet_analysis;
 pub(crate) mod reload_libreqos;
 pub(crate) mod scheduler;
 pub(crate) mod search;
 pub(crate) mod shaped_device_api;
 pub(crate) mod shaped_devices_page;
+pub(crate) mod shaping_plan;
 pub(crate) mod ssl;
+pub(crate) mod subscriber_portal;
 pub(crate) mod throughput_attribution_debug;
 pub(crate) mod topology_manager;
 pub(crate) mod topology_probes;
+pub(crate) mod traffic_groups;
+pub(crate) mod traffic_mirror;
 pub(crate) mod tree_attached_circuits;
 pub(crate) mod unknown_ips;
 pub(crate) mod urgent;
@@ -42,6 +50,10 @@ use axum::{Extension, Router};
 pub fn local_api(shaper_query: tokio::sync::mpsc::Sender<ShaperQueryCommand>) -> Router {
     Router::new()
         .route("/pcapDump/:id", get(packet_analysis::pcap_dump))
+        .route("/flowArchive/query", get(flow_archive::query))
+        .route("/flowArchive/export", get(flow_archive::export))
+        .route("/trafficGroups", get(traffic_groups::traffic_groups))
+        .route("/trafficGroups/metrics", get(traffic_groups::metrics))
         .route(
             "/throughputAttributionDebug",
             get(throughput_attribution_debug::throughput_attribution_debug),
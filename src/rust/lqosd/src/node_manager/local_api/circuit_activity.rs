@@ -12,6 +12,7 @@ const RECENT_CIRCUIT_FLOWS_WINDOW_NANOS: u64 = 30 * 1_000_000_000;
 const SANKEY_RECENT_FLOW_WINDOW_NANOS: u64 = 10 * 1_000_000_000;
 const SANKEY_TOP_FLOW_LIMIT: usize = 20;
 const TOP_ASN_LIMIT: usize = 10;
+const TOP_APPLICATION_LIMIT: usize = 10;
 const FLOW_RATE_SANITY_MULTIPLIER: f64 = 2.0;
 const FLOW_RATE_SANITY_FLOOR_BPS: u64 = 25_000_000;
 const TRAFFIC_FLOW_HIDE_THRESHOLD_BPS: u32 = 1_048_576;
@@ -63,6 +64,8 @@ pub struct CircuitTrafficFlowRow {
     pub asn_name: String,
     pub asn_country: String,
     pub remote_ip: String,
+    #[serde(default)]
+    pub remote_hostname: String,
     pub opacity: f64,
     pub sort_rate_bps: f64,
 }
@@ -106,6 +109,33 @@ pub struct CircuitTopAsnsData {
     pub rows: Vec<CircuitTopAsnRow>,
 }
 
+/// Server-side query for the circuit `Top Applications` table.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CircuitTopApplicationsQuery {
+    pub circuit: String,
+    pub hide_small: bool,
+}
+
+/// Aggregated row for the circuit `Top Applications` table. Flows that no
+/// application matched are grouped under their port-based protocol label.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CircuitTopApplicationRow {
+    pub application: String,
+    pub category: String,
+    pub down_bps: u64,
+    pub up_bps: u64,
+    pub bytes_sent_down: u64,
+    pub bytes_sent_up: u64,
+    pub flow_count: usize,
+}
+
+/// Server-side payload for the circuit `Top Applications` table.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CircuitTopApplicationsData {
+    pub total_applications: usize,
+    pub rows: Vec<CircuitTopApplicationRow>,
+}
+
 /// Compact flow row for the circuit `Flow Sankey` tab.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct CircuitFlowSankeyRow {
@@ -114,6 +144,8 @@ pub struct CircuitFlowSankeyRow {
     pub asn_name: String,
     pub protocol_name: String,
     pub remote_ip: String,
+    #[serde(default)]
+    pub remote_hostname: String,
     pub down_bps: u32,
     pub up_bps: u32,
     /// Legacy UI field name; payload value is the flow age at snapshot time.
@@ -128,7 +160,9 @@ struct CircuitFlowSnapshotRow {
     asn_name: String,
     asn_country: String,
     protocol_name: String,
+    application_category: String,
     remote_ip: String,
+    remote_hostname: String,
     down_bps: u32,
     up_bps: u32,
     bytes_sent_down: u64,
@@ -236,7 +270,9 @@ fn circuit_flow_snapshot_row_from_flow(
         asn_name: display.remote_asn_name.clone(),
         asn_country: display.remote_asn_country.clone(),
         protocol_name: display.analysis.clone(),
+        application_category: display.application_category.clone(),
         remote_ip: display.remote_ip.clone(),
+        remote_hostname: display.remote_hostname.clone(),
         down_bps: display_rate.down,
         up_bps: display_rate.up,
         bytes_sent_down: flow.bytes_sent.down,
@@ -445,6 +481,7 @@ pub fn circuit_traffic_flows_page(query: &CircuitTrafficFlowsQuery) -> CircuitTr
             asn_name: row.asn_name,
             asn_country: row.asn_country,
             remote_ip: row.remote_ip,
+            remote_hostname: row.remote_hostname,
             opacity: row.opacity,
             sort_rate_bps: row.sort_rate_bps,
         })
@@ -573,6 +610,57 @@ pub fn circuit_top_asns_data(query: &CircuitTopAsnsQuery) -> CircuitTopAsnsData
     }
 }
 
+pub fn circuit_top_applications_data(
+    query: &CircuitTopApplicationsQuery,
+) -> CircuitTopApplicationsData {
+    let mut rows = flow_snapshot_rows(&query.circuit);
+    if query.hide_small {
+        rows.retain(|row| {
+            row.down_bps > TRAFFIC_FLOW_HIDE_THRESHOLD_BPS
+                || row.up_bps > TRAFFIC_FLOW_HIDE_THRESHOLD_BPS
+        });
+    }
+
+    let mut buckets: fxhash::FxHashMap<String, CircuitTopApplicationRow> =
+        fxhash::FxHashMap::default();
+    for row in rows {
+        let entry = buckets.entry(row.protocol_name.clone());
+        let bucket = entry.or_insert_with(|| CircuitTopApplicationRow {
+            application: row.protocol_name.clone(),
+            category: row.application_category.clone(),
+            down_bps: 0,
+            up_bps: 0,
+            bytes_sent_down: 0,
+            bytes_sent_up: 0,
+            flow_count: 0,
+        });
+        bucket.down_bps += row.down_bps as u64;
+        bucket.up_bps += row.up_bps as u64;
+        bucket.bytes_sent_down += row.bytes_sent_down;
+        bucket.bytes_sent_up += row.bytes_sent_up;
+        bucket.flow_count += 1;
+    }
+
+    let total_applications = buckets.len();
+    let mut bucket_rows: Vec<CircuitTopApplicationRow> = buckets.into_values().collect();
+    bucket_rows.sort_by(|a, b| {
+        let a_rate = a.down_bps + a.up_bps;
+        let b_rate = b.down_bps + b.up_bps;
+        b_rate
+            .cmp(&a_rate)
+            .then_with(|| {
+                (b.bytes_sent_down + b.bytes_sent_up).cmp(&(a.bytes_sent_down + a.bytes_sent_up))
+            })
+            .then_with(|| a.application.cmp(&b.application))
+    });
+    bucket_rows.truncate(TOP_APPLICATION_LIMIT);
+
+    CircuitTopApplicationsData {
+        total_applications,
+        rows: bucket_rows,
+    }
+}
+
 pub fn circuit_flow_sankey_rows(circuit_id: &str) -> Vec<CircuitFlowSankeyRow> {
     let mut rows = flow_snapshot_rows(circuit_id);
     rows.retain(|row| row.age_nanos <= SANKEY_RECENT_FLOW_WINDOW_NANOS);
@@ -585,6 +673,7 @@ pub fn circuit_flow_sankey_rows(circuit_id: &str) -> Vec<CircuitFlowSankeyRow> {
             asn_name: row.asn_name,
             protocol_name: row.protocol_name,
             remote_ip: row.remote_ip,
+            remote_hostname: row.remote_hostname,
             down_bps: row.down_bps,
             up_bps: row.up_bps,
             age_nanos_wire: row.age_nanos,
@@ -595,10 +684,10 @@ pub fn circuit_flow_sankey_rows(circuit_id: &str) -> Vec<CircuitFlowSankeyRow> {
 #[cfg(test)]
 mod tests {
     use super::{
-        CircuitFlowSankeyRow, CircuitTopAsnsQuery, CircuitTrafficFlowsQuery,
-        circuit_flow_counts, circuit_flow_sankey_rows, circuit_flow_snapshot_row_from_flow,
-        circuit_top_asns_data, circuit_traffic_flows_page, flow_snapshot_rows, median_f32,
-        median_u64,
+        CircuitFlowSankeyRow, CircuitTopApplicationsQuery, CircuitTopAsnsQuery,
+        CircuitTrafficFlowsQuery, circuit_flow_counts, circuit_flow_sankey_rows,
+        circuit_flow_snapshot_row_from_flow, circuit_top_applications_data, circuit_top_asns_data,
+        circuit_traffic_flows_page, flow_snapshot_rows, median_f32, median_u64,
     };
     use crate::test_support::{ActiveFlowSnapshotTestContext, active_flow_entry};
     use crate::throughput_tracker::flow_data::{
@@ -838,6 +927,89 @@ mod tests {
         assert_eq!(circuit_flow_counts("circuit-asn-count"), (2, 2));
     }
 
+    #[test]
+    fn circuit_top_applications_groups_flows_by_application() {
+        let _ctx = ActiveFlowSnapshotTestContext::with_shaped_devices(
+            "circuit-top-apps-test",
+            vec![ShapedDevice {
+                circuit_id: "circuit-top-apps".to_string(),
+                circuit_name: "Circuit Top Apps".to_string(),
+                device_id: "device-top-apps".to_string(),
+                device_name: "Device Top Apps".to_string(),
+                parent_node: "Parent".to_string(),
+                ipv4: vec![(Ipv4Addr::new(192, 0, 2, 0), 24)],
+                download_max_mbps: 500.0,
+                upload_max_mbps: 100.0,
+                ..Default::default()
+            }],
+        );
+        let now_nanos =
+            std::time::Duration::from(lqos_utils::unix_time::time_since_boot().unwrap())
+                .as_nanos() as u64;
+        let fresh = now_nanos.saturating_sub(1_000_000_000);
+
+        let mut video_a = active_flow_entry(
+            [192, 0, 2, 42],
+            [198, 51, 100, 10],
+            50_000,
+            fresh,
+            DownUpOrder::new(40_000_000, 1_000_000),
+            DownUpOrder::new(5_000, 1_000),
+            DownUpOrder::new(50, 10),
+        );
+        video_a.1.1.apply_hostname("video.netflix.com");
+        let mut video_b = active_flow_entry(
+            [192, 0, 2, 42],
+            [198, 51, 100, 11],
+            50_001,
+            fresh,
+            DownUpOrder::new(30_000_000, 1_000_000),
+            DownUpOrder::new(3_000, 1_000),
+            DownUpOrder::new(30, 10),
+        );
+        video_b.1.1.apply_hostname("assets.nflxvideo.net");
+        let unclassified = active_flow_entry(
+            [192, 0, 2, 43],
+            [198, 51, 100, 12],
+            50_002,
+            fresh,
+            DownUpOrder::new(2_000_000, 500_000),
+            DownUpOrder::new(1_000, 1_000),
+            DownUpOrder::new(10, 10),
+        );
+        let unclassified_label = unclassified.1.1.label();
+        let small = active_flow_entry(
+            [192, 0, 2, 44],
+            [198, 51, 100, 13],
+            50_003,
+            fresh,
+            DownUpOrder::new(1_000, 1_000),
+            DownUpOrder::new(1_000, 1_000),
+            DownUpOrder::new(10, 10),
+        );
+        replace_active_flows_for_test(vec![video_a, video_b, unclassified, small]);
+
+        let all = circuit_top_applications_data(&CircuitTopApplicationsQuery {
+            circuit: "circuit-top-apps".to_string(),
+            hide_small: false,
+        });
+        assert_eq!(all.total_applications, 2);
+        assert_eq!(all.rows[0].application, "Netflix");
+        assert_eq!(all.rows[0].category, "Streaming Video");
+        assert_eq!(all.rows[0].flow_count, 2);
+        assert_eq!(all.rows[0].down_bps, 70_000_000);
+        assert_eq!(all.rows[0].bytes_sent_down, 8_000);
+        assert_eq!(all.rows[1].application, unclassified_label);
+        assert_eq!(all.rows[1].category, "");
+        assert_eq!(all.rows[1].flow_count, 2);
+
+        let hidden_small = circuit_top_applications_data(&CircuitTopApplicationsQuery {
+            circuit: "circuit-top-apps".to_string(),
+            hide_small: true,
+        });
+        assert_eq!(hidden_small.rows[1].flow_count, 1);
+    }
+
     #[test]
     fn circuit_flow_snapshot_row_uses_cached_flow_fields_and_clamps_display_rate() {
         let mut key = FlowbeeKey::default();
@@ -857,6 +1029,8 @@ mod tests {
                 remote_asn_name: "Example ASN".to_string(),
                 remote_asn_country: "US".to_string(),
                 analysis: "HTTPS".to_string(),
+                application_category: String::new(),
+                remote_hostname: "video.example.com".to_string(),
             },
             bytes_sent: DownUpOrder::new(10_000, 20_000),
             packets_sent: DownUpOrder::new(100, 200),
@@ -890,6 +1064,7 @@ mod tests {
         assert_eq!(row.asn_country, "US");
         assert_eq!(row.protocol_name, "HTTPS");
         assert_eq!(row.remote_ip, "198.51.100.20");
+        assert_eq!(row.remote_hostname, "video.example.com");
         assert_eq!(row.down_bps, 50_000_000);
         assert_eq!(row.up_bps, 15_000_000);
         assert_eq!(row.bytes_sent_down, 10_000);
@@ -19,6 +19,7 @@ pub struct CircuitLiveMetrics {
     pub rtt_current_p50_nanos: DownUpOrder<Option<u64>>,
     pub qoo: DownUpOrder<Option<f32>>,
     pub tcp_retransmit_sample: DownUpOrder<TcpRetransmitSample>,
+    pub dscp_remarked_per_second: DownUpOrder<u64>,
     pub last_seen_nanos: u64,
 }
 
@@ -43,6 +44,7 @@ pub fn circuit_live_metrics(query: &CircuitMetricsQuery) -> Vec<CircuitLiveMetri
                     rtt_current_p50_nanos: row.rtt_current_p50_nanos,
                     qoo: row.qoo,
                     tcp_retransmit_sample: row.tcp_retransmit_sample,
+                    dscp_remarked_per_second: row.dscp_remarked_per_second,
                     last_seen_nanos: row.last_seen_nanos,
                 })
         })
@@ -630,6 +630,9 @@ fn validate_network_json(value: &Value) -> Result<(), String> {
     }
 }
 
+/// Source recorded in the input revision history for topology and device edits.
+const WEB_UI_HISTORY_SOURCE: &str = "web UI";
+
 fn persist_network_json(network_json: &Value) -> Result<(), String> {
     validate_network_json(network_json)?;
     let config =
@@ -643,6 +646,7 @@ fn persist_network_json(network_json: &Value) -> Result<(), String> {
         std::fs::copy(&net_json_path, net_json_backup_path)
             .map_err(|e| format!("Unable to create network.json backup: {e}"))?;
     }
+    let _ = lqos_config::mark_input_change_source(&config, WEB_UI_HISTORY_SOURCE);
     std::fs::write(net_json_path, serialized_string)
         .map_err(|e| format!("Unable to write network.json: {e}"))?;
     Ok(())
@@ -688,7 +692,7 @@ fn validate_sqm_override(raw: &Option<String>) -> Result<(), String> {
     let Some(token) = normalize_sqm_override(raw) else {
         return Ok(());
     };
-    let valid = |value: &str| matches!(value, "" | "cake" | "fq_codel" | "none");
+    let valid = lqos_config::is_valid_profile_name;
     if token.contains('/') {
         let mut parts = token.splitn(2, '/');
         let down = parts.next().unwrap_or("").trim();
@@ -700,7 +704,7 @@ fn validate_sqm_override(raw: &Option<String>) -> Result<(), String> {
         return Ok(());
     }
     Err(format!(
-        "Invalid SQM override '{token}'. Allowed values: cake, fq_codel, none, or directional down/up tokens."
+        "Invalid SQM override '{token}'. Allowed values: cake, fq_codel, fq_pie, none, an SQM profile name, or directional down/up tokens."
     ))
 }
 
@@ -798,6 +802,7 @@ fn persist_shaped_devices(mut devices: Vec<ShapedDevice>) -> Result<(), String>
         std::fs::copy(&sd_path, sd_backup_path)
             .map_err(|e| format!("Unable to create ShapedDevices.csv backup: {e}"))?;
     }
+    let _ = lqos_config::mark_input_change_source(&config, WEB_UI_HISTORY_SOURCE);
     let mut copied = ConfigShapedDevices::default();
     copied.replace_with_new_data(devices);
     copied
@@ -2,10 +2,14 @@ use crate::node_manager::local_api::network_tree_lite::{
     NetworkTreeLiteNode, network_tree_lite_data,
 };
 use crate::system_stats::{CPU_USAGE, NUM_CPUS};
-use lqos_config::{ShapingCpuDetection, detect_shaping_cpus, load_config};
+use lqos_config::{
+    ShapingCpuDetection, TopLevelPlannerItem, TopLevelPlannerMode, TopLevelPlannerParams,
+    detect_shaping_cpus, load_config, plan_traffic_weighted_assignments,
+    shaping_queue_locality_costs,
+};
 use serde::Serialize;
 use serde_json::Value;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -1054,6 +1058,164 @@ pub fn cpu_affinity_runtime_snapshot_data() -> CpuAffinityRuntimeSnapshot {
     }
 }
 
+/// Projected effect of the traffic-weighted planner on top-level CPU placement.
+#[derive(Serialize, Clone, Debug)]
+pub struct CpuAffinityPlannerProjection {
+    /// True when `[traffic_planner]` is enabled in the configuration.
+    pub enabled: bool,
+    /// Number of top-level nodes with enough observed samples to use traffic weights.
+    pub observed_sites: usize,
+    /// Number of top-level nodes considered by the planner.
+    pub total_sites: usize,
+    /// Top-level nodes the planner would move on its next run.
+    pub moves: Vec<String>,
+    /// Per-CPU projected load before and after the next planner run.
+    pub cores: Vec<CpuAffinityPlannerProjectionCore>,
+}
+
+/// Projected planner load for one shaping CPU.
+#[derive(Serialize, Clone, Debug)]
+pub struct CpuAffinityPlannerProjectionCore {
+    /// Zero-based CPU index.
+    pub cpu: u32,
+    /// Planner bin name backing this CPU.
+    pub bin: String,
+    /// Weighted load (Mbps-equivalent) with the current placement.
+    pub before: f64,
+    /// Weighted load (Mbps-equivalent) after the projected moves.
+    pub after: f64,
+    /// Top-level nodes on this CPU with the current placement.
+    pub sites_before: usize,
+    /// Top-level nodes on this CPU after the projected moves.
+    pub sites_after: usize,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct PlannerStateFile {
+    #[serde(default)]
+    assignments: BTreeMap<String, String>,
+    #[serde(default)]
+    last_change_ts: BTreeMap<String, f64>,
+    /// Top-level node weights `LibreQoS.py` planned with on its last run.
+    #[serde(default)]
+    weights: BTreeMap<String, f64>,
+}
+
+fn load_planner_state_file(cfg: &lqos_config::Config) -> PlannerStateFile {
+    std::fs::read_to_string(cfg.shaping_state_read_path("planner_state.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// The weight `LibreQoS.py` last planned `id` with, or `1.0` when it has none.
+fn planned_weight(state: &PlannerStateFile, id: &str) -> f64 {
+    state
+        .weights
+        .get(id)
+        .copied()
+        .filter(|weight| weight.is_finite() && *weight > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// Runs the traffic-weighted planner against current placement without applying it.
+///
+/// Mirrors the parameters `LibreQoS.py` passes to the planner so operators can preview
+/// the next reload from the CPU affinity page. Items are weighted with the weights the
+/// last planner run saved to `planner_state.json`; nodes it hasn't seen weigh `1.0`, as
+/// they do in `LibreQoS.py`.
+pub fn cpu_affinity_planner_projection_data() -> CpuAffinityPlannerProjection {
+    let empty = CpuAffinityPlannerProjection {
+        enabled: false,
+        observed_sites: 0,
+        total_sites: 0,
+        moves: Vec::new(),
+        cores: Vec::new(),
+    };
+    let Ok(cfg) = load_config() else {
+        return empty;
+    };
+    let settings = cfg.traffic_planner.clone().unwrap_or_default();
+    let shaping = detect_shaping_cpus(cfg.as_ref()).shaping;
+    let bins: Vec<String> = (0..shaping.len().max(1))
+        .map(|queue| format!("CpueQueue{queue}"))
+        .collect();
+
+    let state = load_planner_state_file(cfg.as_ref());
+    let items: Vec<TopLevelPlannerItem> =
+        lqos_network_devices::with_network_json_read(|net_json| {
+            net_json
+                .get_nodes_when_ready()
+                .iter()
+                .filter(|node| node.immediate_parent == Some(0))
+                .map(|node| TopLevelPlannerItem {
+                    id: node.name.clone(),
+                    weight: planned_weight(&state, &node.name),
+                })
+                .collect()
+        });
+
+    let prev_assign: BTreeMap<String, String> = state
+        .assignments
+        .into_iter()
+        .filter(|(id, bin)| items.iter().any(|item| &item.id == id) && bins.contains(bin))
+        .collect();
+    let now_ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or(0.0);
+    let params = TopLevelPlannerParams {
+        mode: TopLevelPlannerMode::TrafficWeighted,
+        hysteresis_threshold: 0.03,
+        cooldown_seconds: 3600.0,
+        move_budget_per_run: (items.len() / 100).clamp(1, 32),
+    };
+    let mut options = settings.weight_options();
+    options.bin_cost_multipliers =
+        shaping_queue_locality_costs(cfg.as_ref(), bins.len(), settings.numa_remote_penalty)
+            .into_iter()
+            .filter_map(|(queue, cost)| bins.get(queue).map(|bin| (bin.clone(), cost)))
+            .collect();
+
+    let output = plan_traffic_weighted_assignments(
+        &items,
+        &crate::planner_traffic::planner_traffic_samples(),
+        &bins,
+        &prev_assign,
+        &state.last_change_ts,
+        now_ts,
+        &params,
+        &options,
+    );
+    let moves = output
+        .plan
+        .changed
+        .into_iter()
+        .filter(|id| prev_assign.contains_key(id))
+        .collect();
+    let cores = output
+        .projection
+        .into_iter()
+        .enumerate()
+        .map(|(queue, row)| CpuAffinityPlannerProjectionCore {
+            cpu: shaping.get(queue).copied().unwrap_or(queue as u32),
+            bin: row.bin,
+            before: row.before,
+            after: row.after,
+            sites_before: row.items_before,
+            sites_after: row.items_after,
+        })
+        .collect();
+
+    CpuAffinityPlannerProjection {
+        enabled: settings.enabled,
+        observed_sites: output.observed.len(),
+        total_sites: items.len(),
+        moves,
+        cores,
+    }
+}
+
 pub fn cpu_affinity_summary_data() -> Vec<CpuAffinitySummaryEntry> {
     let circuits = load_all_circuits();
     let mut down: HashMap<u32, (usize, f64, f64, f64)> = HashMap::new();
@@ -1328,8 +1490,8 @@ pub fn cpu_affinity_preview_weights_data(q: CircuitsQuery) -> Vec<PreviewWeightI
 #[cfg(test)]
 mod tests {
     use super::{
-        PlannedCoreMetrics, RuntimeNodePlacement, derive_runtime_node_placements, is_cpu_root_node,
-        resolve_snapshot_cpu_sets,
+        PlannedCoreMetrics, PlannerStateFile, RuntimeNodePlacement, derive_runtime_node_placements,
+        is_cpu_root_node, planned_weight, resolve_snapshot_cpu_sets,
     };
     use crate::node_manager::local_api::network_tree_lite::NetworkTreeLiteNode;
     use lqos_config::{ShapingCpuDetection, ShapingCpuSource};
@@ -1521,4 +1683,15 @@ mod tests {
         assert_eq!(excluded, vec![2, 3]);
         assert!(!has_hybrid_split);
     }
+
+    #[test]
+    fn projection_uses_the_weights_the_planner_saved() {
+        let state: PlannerStateFile = serde_json::from_str(
+            r#"{"assignments": {"Site A": "CpueQueue0"}, "weights": {"Site A": 250.0, "Site B": 0.0}}"#,
+        )
+        .expect("planner state parses");
+        assert_eq!(planned_weight(&state, "Site A"), 250.0);
+        assert_eq!(planned_weight(&state, "Site B"), 1.0);
+        assert_eq!(planned_weight(&state, "Site C"), 1.0);
+    }
 }
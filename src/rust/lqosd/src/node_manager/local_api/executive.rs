@@ -223,9 +223,23 @@ fn qoo_data(
         circuit_id,
         latest: blocks.latest_values(),
         blocks,
+        profile_id: None,
+        profile_reason: None,
     }
 }
 
+/// Records which QoO profile produced the scores, and why.
+fn with_profile(
+    mut data: lqos_bus::QooData,
+    choice: Option<crate::qoo_assignment::ProfileChoice>,
+) -> lqos_bus::QooData {
+    if let Some(choice) = choice {
+        data.profile_id = Some(choice.profile_id);
+        data.profile_reason = Some(choice.reason);
+    }
+    data
+}
+
 fn entity_qoo_data(entity: &ExecutiveEntitySnapshot) -> Option<lqos_bus::QooData> {
     let blocks = entity.qoq_blocks.clone()?;
     match entity.entity_kind {
@@ -415,26 +429,35 @@ pub fn executive_dashboard_summary() -> ExecutiveDashboardSummary {
 pub(crate) fn qoo_global() -> lqos_bus::QooData {
     let snapshot = fresh_executive_cache_snapshot();
     let blocks = snapshot.dashboard.global_qoq.clone();
-    qoo_data(
+    let data = qoo_data(
         "global".to_string(),
         "global",
         "Global QoO".to_string(),
         None,
         None,
         blocks,
-    )
+    );
+    with_profile(data, crate::qoo_assignment::assignments().default_choice())
 }
 
 /// Returns the current QoO history for a site name.
 pub(crate) fn qoo_site(site_name: &str) -> Option<lqos_bus::QooData> {
     let snapshot = fresh_executive_cache_snapshot();
-    qoo_site_from_snapshot(&snapshot, site_name)
+    let data = qoo_site_from_snapshot(&snapshot, site_name)?;
+    Some(with_profile(
+        data,
+        crate::qoo_assignment::assignments().site_choice(site_name),
+    ))
 }
 
 /// Returns the current QoO history for a circuit ID.
 pub(crate) fn qoo_circuit(circuit_id: &str) -> Option<lqos_bus::QooData> {
     let snapshot = fresh_executive_cache_snapshot();
-    qoo_circuit_from_snapshot(&snapshot, circuit_id)
+    let data = qoo_circuit_from_snapshot(&snapshot, circuit_id)?;
+    Some(with_profile(
+        data,
+        crate::qoo_assignment::assignments().circuit_choice(circuit_id),
+    ))
 }
 
 /// Returns one filtered, sorted executive heatmap detail page.
@@ -0,0 +1,73 @@
+use crate::fleet::summary::{FleetNodeSummary, local_summary};
+use crate::fleet::{
+    API_KEY_HEADER, FleetOverview, FleetSearchResults, fleet_overview, fleet_search,
+};
+use crate::node_manager::auth::LoginResult;
+use crate::node_manager::local_api::local_api_keys::key_authorizes;
+use crate::node_manager::{SearchRequest, SearchResult, search_results};
+use axum::extract::{Query, Request};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use tokio::task::spawn_blocking;
+
+/// Query-string parameters for a fleet member search.
+#[derive(Deserialize)]
+pub struct FleetSearchQuery {
+    pub term: String,
+}
+
+/// Routes other nodes poll for the fleet view, authenticated by a named local
+/// API key instead of a web UI session.
+pub fn fleet_api() -> Router {
+    Router::new()
+        .route("/summary", get(summary))
+        .route("/search", get(search))
+        .route_layer(axum::middleware::from_fn(api_key_layer))
+}
+
+/// Rejects requests that don't carry one of this node's named local API keys.
+async fn api_key_layer(req: Request, next: Next) -> Response {
+    let presented = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let authorized =
+        lqos_config::load_config().is_ok_and(|config| key_authorizes(&config, presented));
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, "A valid API key is required").into_response();
+    }
+    next.run(req).await
+}
+
+async fn summary() -> Result<Json<FleetNodeSummary>, StatusCode> {
+    spawn_blocking(local_summary)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn search(Query(query): Query<FleetSearchQuery>) -> Json<Vec<SearchResult>> {
+    Json(search_results(SearchRequest { term: query.term }))
+}
+
+/// This node and the last poll of every fleet member, combined.
+pub async fn get_fleet_overview(_login: LoginResult) -> Result<FleetOverview, String> {
+    spawn_blocking(fleet_overview)
+        .await
+        .map_err(|_| "Failed to spawn blocking thread".to_string())
+}
+
+/// Searches this node and every fleet member.
+pub async fn search_fleet(_login: LoginResult, term: String) -> Result<FleetSearchResults, String> {
+    if term.trim().is_empty() {
+        return Err("Enter a circuit, device, site or IP address to search for".to_string());
+    }
+    spawn_blocking(move || fleet_search(term.trim()))
+        .await
+        .map_err(|_| "Failed to spawn blocking thread".to_string())
+}
@@ -0,0 +1,199 @@
+//! Local authenticated query and export endpoints for the finished-flow archive.
+
+use crate::node_manager::auth::LoginResult;
+use crate::throughput_tracker::flow_data::query_flow_archive;
+use axum::extract::Query;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use lqos_bus::{FlowArchiveQuery, FlowArchiveRecord};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Query-string parameters accepted by the archive endpoints.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct FlowArchiveParams {
+    circuit: Option<String>,
+    ip: Option<IpAddr>,
+    asn: Option<u32>,
+    port: Option<u16>,
+    start: u64,
+    end: Option<u64>,
+    limit: Option<usize>,
+    format: Option<String>,
+}
+
+impl FlowArchiveParams {
+    fn to_query(&self) -> FlowArchiveQuery {
+        FlowArchiveQuery {
+            circuit_id: self.circuit.clone(),
+            ip: self.ip,
+            asn: self.asn,
+            port: self.port,
+            start: self.start,
+            end: self
+                .end
+                .unwrap_or_else(|| lqos_utils::unix_time::unix_now().unwrap_or(u64::MAX)),
+            limit: self.limit,
+        }
+    }
+}
+
+/// Flat CSV row for an archived flow.
+#[derive(Debug, Serialize)]
+struct FlowArchiveCsvRow<'a> {
+    start: u64,
+    end: u64,
+    local_ip: String,
+    remote_ip: String,
+    src_port: u16,
+    dst_port: u16,
+    ip_protocol: u8,
+    bytes_down: u64,
+    bytes_up: u64,
+    packets_down: u64,
+    packets_up: u64,
+    tcp_retransmits_down: u16,
+    tcp_retransmits_up: u16,
+    circuit_id: &'a str,
+    circuit_name: &'a str,
+    remote_asn: u32,
+    remote_asn_name: &'a str,
+    application: &'a str,
+    remote_hostname: &'a str,
+}
+
+impl<'a> From<&'a FlowArchiveRecord> for FlowArchiveCsvRow<'a> {
+    fn from(flow: &'a FlowArchiveRecord) -> Self {
+        Self {
+            start: flow.start,
+            end: flow.end,
+            local_ip: flow.local_ip.to_string(),
+            remote_ip: flow.remote_ip.to_string(),
+            src_port: flow.src_port,
+            dst_port: flow.dst_port,
+            ip_protocol: flow.ip_protocol,
+            bytes_down: flow.bytes.down,
+            bytes_up: flow.bytes.up,
+            packets_down: flow.packets.down,
+            packets_up: flow.packets.up,
+            tcp_retransmits_down: flow.tcp_retransmits.down,
+            tcp_retransmits_up: flow.tcp_retransmits.up,
+            circuit_id: &flow.circuit_id,
+            circuit_name: &flow.circuit_name,
+            remote_asn: flow.remote_asn,
+            remote_asn_name: &flow.remote_asn_name,
+            application: &flow.application,
+            remote_hostname: &flow.remote_hostname,
+        }
+    }
+}
+
+fn flows_to_csv(flows: &[FlowArchiveRecord]) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for flow in flows {
+        writer.serialize(FlowArchiveCsvRow::from(flow))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|err| csv::Error::from(err.into_error()))
+}
+
+async fn run_query(
+    login: LoginResult,
+    params: &FlowArchiveParams,
+) -> Result<Vec<FlowArchiveRecord>, (StatusCode, String)> {
+    if login != LoginResult::Admin {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Administrator access is required.".into(),
+        ));
+    }
+    let query = params.to_query();
+    tokio::task::spawn_blocking(move || query_flow_archive(&query))
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))
+}
+
+/// Returns archived flows matching the query string as JSON.
+pub(crate) async fn query(
+    Extension(login): Extension<LoginResult>,
+    Query(params): Query<FlowArchiveParams>,
+) -> Result<Json<Vec<FlowArchiveRecord>>, (StatusCode, String)> {
+    run_query(login, &params).await.map(Json)
+}
+
+/// Downloads archived flows matching the query string as CSV (the default) or JSON.
+pub(crate) async fn export(
+    Extension(login): Extension<LoginResult>,
+    Query(params): Query<FlowArchiveParams>,
+) -> Result<Response, (StatusCode, String)> {
+    let format = params.format.as_deref().unwrap_or("csv");
+    if format != "csv" && format != "json" {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "format must be \"csv\" or \"json\"".into(),
+        ));
+    }
+    let flows = run_query(login, &params).await?;
+    let (content_type, body) = if format == "csv" {
+        let body = flows_to_csv(&flows)
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        ("text/csv", body)
+    } else {
+        let body = serde_json::to_vec(&flows)
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        ("application/json", body)
+    };
+    let disposition = format!("attachment; filename=\"flow_archive.{format}\"");
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lqos_utils::units::DownUpOrder;
+
+    #[test]
+    fn csv_export_flattens_directional_counters() {
+        let flow = FlowArchiveRecord {
+            start: 100,
+            end: 130,
+            local_ip: "100.64.0.10".parse().expect("valid address"),
+            remote_ip: "192.0.2.1".parse().expect("valid address"),
+            src_port: 51_000,
+            dst_port: 443,
+            ip_protocol: 6,
+            bytes: DownUpOrder::new(1000, 100),
+            packets: DownUpOrder::new(10, 5),
+            tcp_retransmits: DownUpOrder::new(1, 0),
+            circuit_id: "c1".to_string(),
+            circuit_name: "Subscriber, One".to_string(),
+            remote_asn: 64_500,
+            remote_asn_name: "Example".to_string(),
+            application: "HTTPS".to_string(),
+            remote_hostname: "www.example.com".to_string(),
+        };
+        let csv =
+            String::from_utf8(flows_to_csv(&[flow]).expect("csv encodes")).expect("csv is utf-8");
+        let mut lines = csv.lines();
+        assert!(
+            lines
+                .next()
+                .expect("header row")
+                .starts_with("start,end,local_ip,remote_ip")
+        );
+        assert_eq!(
+            lines.next().expect("data row"),
+            "100,130,100.64.0.10,192.0.2.1,51000,443,6,1000,100,10,5,1,0,c1,\"Subscriber, One\",64500,Example,HTTPS,www.example.com"
+        );
+    }
+}
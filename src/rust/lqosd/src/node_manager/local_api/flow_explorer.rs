@@ -36,6 +36,7 @@ pub struct FlowTimeline {
     pub circuit_id: String,
     pub circuit_name: String,
     pub remote_ip: String,
+    pub remote_hostname: String,
 }
 
 pub fn flow_timeline_data(asn_id: u32) -> Result<Vec<FlowTimeline>, TimeError> {
@@ -97,6 +98,7 @@ fn all_flows_to_transport(
                 circuit_id,
                 circuit_name,
                 remote_ip: flow.0.remote_ip.as_ip().to_string(),
+                remote_hostname: flow.1.remote_hostname.clone().unwrap_or_default(),
             }
         })
         .collect::<Vec<_>>()
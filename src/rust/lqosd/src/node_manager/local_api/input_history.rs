@@ -0,0 +1,33 @@
+use crate::node_manager::auth::LoginResult;
+use axum::http::StatusCode;
+use lqos_config::{InputHistoryError, InputRevision, InputRevisionDiff};
+use tokio::task::spawn_blocking;
+
+/// Lists retained revisions of the shaping input files, newest first.
+pub fn get_input_history(_login: LoginResult) -> Result<Vec<InputRevision>, StatusCode> {
+    let config = lqos_config::load_config().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    lqos_config::list_input_revisions(&config).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Compares two retained input revisions.
+pub fn get_input_revision_diff(
+    _login: LoginResult,
+    from: u64,
+    to: u64,
+) -> Result<InputRevisionDiff, StatusCode> {
+    let config = lqos_config::load_config().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    lqos_config::diff_input_revisions(&config, from, to).map_err(|err| match err {
+        InputHistoryError::UnknownRevision(_) => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })
+}
+
+/// Restores a revision and reloads LibreQoS for administrative callers.
+pub async fn restore_input_revision(login: LoginResult, id: u64) -> Result<InputRevision, String> {
+    if login != LoginResult::Admin {
+        return Err("You must be an admin to roll back shaping inputs".to_string());
+    }
+    spawn_blocking(move || crate::input_history::restore_and_reload(id))
+        .await
+        .map_err(|_| "Failed to spawn blocking thread".to_string())?
+}
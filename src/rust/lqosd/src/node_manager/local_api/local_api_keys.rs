@@ -142,6 +142,44 @@ fn remove_legacy_from_config(config: &mut Config) -> Result<(), String> {
         .ok_or_else(|| "No legacy local API key is configured".to_string())
 }
 
+fn presented_digest(presented: &str) -> Option<String> {
+    let presented = presented.trim();
+    (!presented.is_empty()).then(|| bytes_to_lower_hex(&Sha256::digest(presented.as_bytes())))
+}
+
+/// Whether `presented` is one of the named local API keys or the legacy
+/// bearer token. Both sides are compared as SHA-256 digests, so the
+/// comparison does not leak how much of a key matched.
+pub(crate) fn key_authorizes(config: &Config, presented: &str) -> bool {
+    let Some(digest) = presented_digest(presented) else {
+        return false;
+    };
+    let named = config
+        .local_api
+        .keys
+        .iter()
+        .any(|key| key.token_sha256 == digest);
+    let legacy = config
+        .local_api
+        .bearer_token
+        .as_deref()
+        .filter(|token| !token.is_empty())
+        .is_some_and(|token| bytes_to_lower_hex(&Sha256::digest(token.as_bytes())) == digest);
+    named || legacy
+}
+
+/// The ID of the named local API key `presented` matches, if any. The legacy
+/// bearer token has no ID, so it never matches.
+pub(crate) fn named_key_id<'a>(config: &'a Config, presented: &str) -> Option<&'a str> {
+    let digest = presented_digest(presented)?;
+    config
+        .local_api
+        .keys
+        .iter()
+        .find(|key| key.token_sha256 == digest)
+        .map(|key| key.id.as_str())
+}
+
 /// Serializes Node Manager configuration read-modify-write transactions.
 pub(crate) async fn lock_config_update() -> MutexGuard<'static, ()> {
     CONFIG_UPDATE_LOCK.lock().await
@@ -251,8 +289,8 @@ pub async fn remove_legacy(login: LoginResult) -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::{
-        append_key, build_key, create, preserve_api_credentials, remove_legacy,
-        remove_legacy_from_config, revoke, revoke_from_config,
+        append_key, build_key, create, key_authorizes, named_key_id, preserve_api_credentials,
+        remove_legacy, remove_legacy_from_config, revoke, revoke_from_config,
     };
     use crate::node_manager::auth::LoginResult;
     use lqos_config::{Config, MAX_LOCAL_API_KEYS};
@@ -342,6 +380,28 @@ mod tests {
         assert_eq!(config.local_api.keys.len(), 1);
     }
 
+    #[test]
+    fn named_and_legacy_keys_authorize_until_revoked() {
+        let mut config = Config::default();
+        let creation = append_key(&mut config, "Fleet", Uuid::from_u128(3), &[3; 32], 3)
+            .expect("key should be created");
+        assert!(key_authorizes(&config, &creation.api_key));
+        assert!(!key_authorizes(&config, "lqos_api_wrong"));
+        assert!(!key_authorizes(&config, ""));
+
+        config.local_api.bearer_token = Some("legacy-token".to_string());
+        assert!(key_authorizes(&config, "legacy-token"));
+        assert_eq!(
+            named_key_id(&config, &creation.api_key),
+            Some(creation.id.as_str())
+        );
+        assert_eq!(named_key_id(&config, "legacy-token"), None);
+
+        revoke_from_config(&mut config, &creation.id).expect("key should be revoked");
+        assert!(!key_authorizes(&config, &creation.api_key));
+        assert_eq!(named_key_id(&config, &creation.api_key), None);
+    }
+
     #[tokio::test]
     async fn management_requires_an_administrator() {
         assert!(matches!(
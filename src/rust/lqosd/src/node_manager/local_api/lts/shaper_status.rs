@@ -1,5 +1,6 @@
+use crate::lts2_sys::ingest_spool::{self, IngestSpoolStatus};
 use axum::http::StatusCode;
-use lqos_config::load_config;
+use lqos_config::{Config, load_config};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
@@ -7,11 +8,37 @@ use tracing::{debug, error};
 pub struct ShaperStatus {
     name: String,
     last_seen_seconds_ago: f32,
+    /// Depth of this shaper's on-disk ingest spool. Only set on the local shaper's row.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    local_spool: Option<IngestSpoolStatus>,
 }
 
 pub async fn shaper_status_data() -> Result<Vec<ShaperStatus>, StatusCode> {
     super::insight_gate().await?;
     let config = load_config().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let spool = ingest_spool::status();
+    match fetch_shaper_status(&config).await {
+        Ok(mut shapers) => {
+            if let Some(local) = shapers.iter_mut().find(|s| s.name == config.node_name) {
+                local.local_spool = Some(spool);
+            }
+            Ok(shapers)
+        }
+        // Insight is unreachable; still report what this shaper is holding back.
+        Err(_) if spool.batches > 0 => {
+            let now = lqos_utils::unix_time::unix_now().unwrap_or(0);
+            let oldest = spool.oldest_unix.unwrap_or(now);
+            Ok(vec![ShaperStatus {
+                name: config.node_name.clone(),
+                last_seen_seconds_ago: now.saturating_sub(oldest) as f32,
+                local_spool: Some(spool),
+            }])
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn fetch_shaper_status(config: &Config) -> Result<Vec<ShaperStatus>, StatusCode> {
     let url = format!(
         "https://{}/shaper_api/status",
         config
@@ -0,0 +1,29 @@
+use crate::node_manager::auth::LoginResult;
+use axum::http::StatusCode;
+use lqos_overrides::{OverrideLayer, OverrideStore, ScheduledOverrideSummary};
+use lqos_utils::unix_time::unix_now;
+use serde::{Deserialize, Serialize};
+
+/// Page data for the scheduled overrides page.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OverrideScheduleData {
+    /// Unix time the states below were evaluated at.
+    pub now_unix: u64,
+    /// Timed operator overrides plus the retired history.
+    pub entries: Vec<ScheduledOverrideSummary>,
+}
+
+/// Lists upcoming, timed and expired operator overrides.
+pub fn get_override_schedule(_login: LoginResult) -> Result<OverrideScheduleData, StatusCode> {
+    let now_unix = unix_now().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let overrides = OverrideStore::load_layer(OverrideLayer::Operator)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut entries = overrides.scheduled_overrides(now_unix);
+    entries.sort_by_key(|entry| {
+        (
+            entry.state,
+            entry.schedule.not_before.or(entry.schedule.expires_at),
+        )
+    });
+    Ok(OverrideScheduleData { now_unix, entries })
+}
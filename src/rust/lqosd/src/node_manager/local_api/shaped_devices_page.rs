@@ -220,6 +220,7 @@ mod tests {
                     rtt_current_p50_nanos: DownUpOrder::default(),
                     qoo: DownUpOrder::default(),
                     tcp_retransmit_sample: DownUpOrder::default(),
+                    dscp_remarked_per_second: DownUpOrder::default(),
                     last_seen_nanos: *last_seen_nanos,
                 },
             );
@@ -0,0 +1,17 @@
+use crate::node_manager::auth::LoginResult;
+use crate::shaping_plan::PlanCandidate;
+use lqos_bus::BakeryPlanReport;
+use tokio::task::spawn_blocking;
+
+/// Plans a candidate shaping change without applying it, for administrative callers.
+pub async fn plan_shaping_change(
+    login: LoginResult,
+    candidate: PlanCandidate,
+) -> Result<BakeryPlanReport, String> {
+    if login != LoginResult::Admin {
+        return Err("You must be an admin to plan shaping changes".to_string());
+    }
+    spawn_blocking(move || crate::shaping_plan::plan_shaping_change(candidate))
+        .await
+        .map_err(|_| "Failed to spawn blocking thread".to_string())?
+}
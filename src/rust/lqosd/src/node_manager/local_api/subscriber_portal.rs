@@ -0,0 +1,103 @@
+use crate::fleet::API_KEY_HEADER;
+use crate::node_manager::local_api::local_api_keys::named_key_id;
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use lqos_config::Config;
+use serde::Deserialize;
+use tokio::task::spawn_blocking;
+
+/// Body of a portal link request.
+#[derive(Deserialize)]
+pub struct PortalLinkRequest {
+    pub circuit_id: String,
+    /// Link lifetime; omitted uses `subscriber_portal.default_link_hours`.
+    #[serde(default)]
+    pub valid_hours: Option<u32>,
+}
+
+/// Routes a CRM calls to issue subscriber portal links, authenticated by a
+/// named local API key listed in `subscriber_portal.link_api_keys` instead of
+/// a web UI session.
+pub fn portal_api() -> Router {
+    Router::new()
+        .route("/links", post(issue_link))
+        .route_layer(axum::middleware::from_fn(link_key_layer))
+}
+
+/// Whether `presented` is a named local API key allowed to issue links.
+fn may_issue_links(config: &Config, presented: &str) -> bool {
+    named_key_id(config, presented).is_some_and(|id| {
+        config
+            .subscriber_portal
+            .link_api_keys
+            .iter()
+            .any(|allowed| allowed == id)
+    })
+}
+
+/// Rejects requests that don't carry a key allowed to issue links.
+async fn link_key_layer(req: Request, next: Next) -> Response {
+    let presented = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let authorized =
+        lqos_config::load_config().is_ok_and(|config| may_issue_links(&config, presented));
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "An API key allowed to issue portal links is required",
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
+
+async fn issue_link(Json(request): Json<PortalLinkRequest>) -> Response {
+    let result = spawn_blocking(move || {
+        crate::subscriber_portal::issue_link(&request.circuit_id, request.valid_hours)
+    })
+    .await;
+    match result {
+        Ok(Ok(link)) => Json(link).into_response(),
+        Ok(Err(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::may_issue_links;
+    use lqos_config::{Config, LocalApiKeyConfig};
+    use sha2::{Digest, Sha256};
+
+    fn key(id: &str, secret: &str) -> LocalApiKeyConfig {
+        LocalApiKeyConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            token_sha256: format!("{:x}", Sha256::digest(secret.as_bytes())),
+            created_at_unix: 1,
+        }
+    }
+
+    #[test]
+    fn only_listed_named_keys_may_issue_links() {
+        let crm = "00000000-0000-0000-0000-000000000001";
+        let fleet = "00000000-0000-0000-0000-000000000002";
+        let mut config = Config::default();
+        config.local_api.keys = vec![key(crm, "crm-key"), key(fleet, "fleet-key")];
+        config.local_api.bearer_token = Some("legacy-token".to_string());
+        assert!(!may_issue_links(&config, "crm-key"));
+
+        config.subscriber_portal.link_api_keys = vec![crm.to_string()];
+        assert!(may_issue_links(&config, "crm-key"));
+        assert!(!may_issue_links(&config, "fleet-key"));
+        assert!(!may_issue_links(&config, "legacy-token"));
+        assert!(!may_issue_links(&config, ""));
+    }
+}
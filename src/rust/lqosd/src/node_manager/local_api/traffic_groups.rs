@@ -0,0 +1,154 @@
+//! Per-group (on-net, off-net, peering partner) traffic accounting, as JSON
+//! and in the Prometheus text exposition format.
+
+use crate::throughput_tracker::flow_data::traffic_group_report;
+use axum::Json;
+use axum::http::header;
+use axum::response::IntoResponse;
+use lqos_bus::{TrafficGroupCounter, TrafficGroupReport};
+use std::fmt::Write;
+
+/// Returns cumulative and current per-group traffic for the whole network,
+/// each site and each circuit.
+pub(crate) async fn traffic_groups() -> Json<TrafficGroupReport> {
+    Json(traffic_group_report())
+}
+
+/// Exposes the traffic group counters for Prometheus-compatible scrapers.
+pub(crate) async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render_metrics(&traffic_group_report()),
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn write_counters(out: &mut String, name: &str, labels: &str, counters: &[TrafficGroupCounter]) {
+    for counter in counters {
+        let group = escape_label(&counter.group);
+        for (direction, bytes) in [("down", counter.bytes.down), ("up", counter.bytes.up)] {
+            let _ = writeln!(
+                out,
+                "{name}{{{labels}group=\"{group}\",direction=\"{direction}\"}} {bytes}"
+            );
+        }
+    }
+}
+
+fn render_metrics(report: &TrafficGroupReport) -> String {
+    let mut out = String::new();
+
+    write_family(
+        &mut out,
+        "lqos_traffic_group_bytes_total",
+        "counter",
+        "Bytes exchanged with each traffic group since lqosd started.",
+    );
+    write_counters(
+        &mut out,
+        "lqos_traffic_group_bytes_total",
+        "",
+        &report.totals,
+    );
+
+    write_family(
+        &mut out,
+        "lqos_traffic_group_bits_per_second",
+        "gauge",
+        "Current rate of traffic exchanged with each traffic group.",
+    );
+    for counter in &report.totals {
+        let group = escape_label(&counter.group);
+        let rate = counter.bits_per_second;
+        for (direction, bps) in [("down", rate.down), ("up", rate.up)] {
+            let _ = writeln!(
+                out,
+                "lqos_traffic_group_bits_per_second{{group=\"{group}\",direction=\"{direction}\"}} {bps}"
+            );
+        }
+    }
+
+    write_family(
+        &mut out,
+        "lqos_traffic_group_site_bytes_total",
+        "counter",
+        "Bytes exchanged with each traffic group, per network.json site.",
+    );
+    for site in &report.sites {
+        let labels = format!("site=\"{}\",", escape_label(&site.site));
+        write_counters(
+            &mut out,
+            "lqos_traffic_group_site_bytes_total",
+            &labels,
+            &site.groups,
+        );
+    }
+
+    write_family(
+        &mut out,
+        "lqos_traffic_group_circuit_bytes_total",
+        "counter",
+        "Bytes exchanged with each traffic group, per circuit.",
+    );
+    for circuit in &report.circuits {
+        let labels = format!(
+            "circuit_id=\"{}\",circuit_name=\"{}\",",
+            escape_label(&circuit.circuit_id),
+            escape_label(&circuit.circuit_name)
+        );
+        write_counters(
+            &mut out,
+            "lqos_traffic_group_circuit_bytes_total",
+            &labels,
+            &circuit.groups,
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lqos_bus::TrafficGroupCircuit;
+    use lqos_utils::units::DownUpOrder;
+
+    #[test]
+    fn metrics_escape_labels() {
+        let counter = TrafficGroupCounter {
+            group: "IX \"peers\"".to_string(),
+            bytes: DownUpOrder::new(10, 2),
+            bits_per_second: DownUpOrder::new(80, 16),
+        };
+        let report = TrafficGroupReport {
+            totals: vec![counter.clone()],
+            sites: Vec::new(),
+            circuits: vec![TrafficGroupCircuit {
+                circuit_id: "c1".to_string(),
+                circuit_name: "Subscriber\\One".to_string(),
+                groups: vec![counter],
+            }],
+        };
+        let text = render_metrics(&report);
+        assert!(text.contains(
+            "lqos_traffic_group_bytes_total{group=\"IX \\\"peers\\\"\",direction=\"down\"} 10\n"
+        ));
+        assert!(text.contains(
+            "lqos_traffic_group_bits_per_second{group=\"IX \\\"peers\\\"\",direction=\"up\"} 16\n"
+        ));
+        assert!(text.contains(
+            "lqos_traffic_group_circuit_bytes_total{circuit_id=\"c1\",circuit_name=\"Subscriber\\\\One\",group=\"IX \\\"peers\\\"\",direction=\"up\"} 2\n"
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+use crate::node_manager::auth::LoginResult;
+use lqos_bus::TrafficMirrorSession;
+use tokio::task::spawn_blocking;
+
+fn require_admin(login: LoginResult) -> Result<(), String> {
+    if login != LoginResult::Admin {
+        return Err("You must be an admin to manage traffic mirroring".to_string());
+    }
+    Ok(())
+}
+
+/// Lists the running traffic mirror sessions, for administrative callers.
+pub async fn list_traffic_mirrors(login: LoginResult) -> Result<Vec<TrafficMirrorSession>, String> {
+    require_admin(login)?;
+    spawn_blocking(crate::traffic_mirror::sessions)
+        .await
+        .map_err(|_| "Failed to spawn blocking thread".to_string())
+}
+
+/// Starts a traffic mirror session on behalf of the signed-in admin.
+pub async fn start_traffic_mirror(
+    login: LoginResult,
+    username: String,
+    circuit_id: Option<String>,
+    prefixes: Vec<String>,
+    minutes: Option<u32>,
+    reason: String,
+) -> Result<TrafficMirrorSession, String> {
+    require_admin(login)?;
+    spawn_blocking(move || {
+        crate::traffic_mirror::start(
+            circuit_id.as_deref(),
+            &prefixes,
+            minutes,
+            &username,
+            &reason,
+        )
+    })
+    .await
+    .map_err(|_| "Failed to spawn blocking thread".to_string())?
+}
+
+/// Stops a traffic mirror session on behalf of the signed-in admin.
+pub async fn stop_traffic_mirror(
+    login: LoginResult,
+    username: String,
+    session_id: u64,
+) -> Result<(), String> {
+    require_admin(login)?;
+    spawn_blocking(move || crate::traffic_mirror::stop(session_id, &username))
+        .await
+        .map_err(|_| "Failed to spawn blocking thread".to_string())?
+}
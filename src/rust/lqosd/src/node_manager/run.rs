@@ -1,5 +1,7 @@
 use crate::lts2_sys::control_channel::ControlChannelCommand;
+use crate::node_manager::local_api::fleet::fleet_api;
 use crate::node_manager::local_api::local_api;
+use crate::node_manager::local_api::subscriber_portal::portal_api;
 use crate::node_manager::shaper_queries_actor::shaper_queries_actor;
 use crate::node_manager::{
     auth,
@@ -82,6 +84,8 @@ pub async fn spawn_webserver(
         .nest("/vendor", vendor_route()?) // Serve /vendor as purely static
         .nest("/", static_routes()?)
         .nest("/local-api", local_api(shaper_tx))
+        .nest("/fleet-api", fleet_api())
+        .nest("/portal-api", portal_api())
         .fallback_service(ServeDir::new(static_path));
 
     info!("Webserver listening on: [{listen_address}]");
@@ -33,6 +33,9 @@ pub(super) fn static_routes() -> Result<Router> {
         "tree.html",
         "topology_manager.html",
         "topology_probes.html",
+        "override_schedule.html",
+        "input_history.html",
+        "fleet.html",
         "help.html",
         "unknown_ips.html",
         "circuit.html",
@@ -68,6 +68,14 @@ const CHAT_LINK_ACTIVE: &str = r#"
     </a>
 </li>"#;
 
+// HTML template for the fleet link, shown when `[fleet]` is enabled
+const FLEET_LINK: &str = r#"
+<li class="nav-item">
+    <a class="nav-link" href="fleet.html">
+        <i class="fa fa-fw fa-centerline fa-network-wired nav-icon"></i> Fleet
+    </a>
+</li>"#;
+
 static GIT_HASH: &str = env!("GIT_HASH");
 
 fn cobrand_logo_html(config: &lqos_config::Config) -> String {
@@ -214,6 +222,9 @@ pub async fn apply_templates(
         let chat_link = CHAT_LINK_ACTIVE;
         let byte_string = byte_string.replace("%%CHAT_LINK%%", chat_link);
 
+        let fleet_link = if config.fleet.enabled { FLEET_LINK } else { "" };
+        let byte_string = byte_string.replace("%%FLEET_LINK%%", fleet_link);
+
         // Replace SCHEDULER_STATUS with a simple placeholder for client-side rendering
         // The client JS will fetch status and populate this container.
         let scheduler_placeholder = r##"
@@ -10,13 +10,16 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::lts2_sys::control_channel::ControlChannelCommand;
-use crate::node_manager::auth::{LoginResult, login_from_cookie_header};
+use crate::node_manager::auth::{
+    LoginResult, login_from_cookie_header, username_from_cookie_header,
+};
 use crate::node_manager::local_api::{
     circuit, circuit_count, config, cpu_affinity, dashboard_themes, device_counts, directories,
-    ethernet_caps, executive, flow_explorer, flow_map, local_api_keys, lts, network_tree,
-    network_tree_lite, node_rate_overrides, node_topology_overrides, packet_analysis,
-    reload_libreqos, scheduler, search, shaped_device_api, shaped_devices_page, topology_manager,
-    topology_probes, unknown_ips, urgent, warnings,
+    ethernet_caps, executive, fleet, flow_explorer, flow_map, input_history, local_api_keys, lts,
+    network_tree, network_tree_lite, node_rate_overrides, node_topology_overrides,
+    override_schedule, packet_analysis, reload_libreqos, scheduler, search, shaped_device_api,
+    shaped_devices_page, shaping_plan, topology_manager, topology_probes, traffic_mirror,
+    unknown_ips, urgent, warnings,
 };
 use crate::node_manager::shaper_queries_actor::ShaperQueryCommand;
 use crate::node_manager::ws::messages::{
@@ -28,7 +31,8 @@ use crate::node_manager::ws::single_user_channels::circuit::{
     circuit_devices_result, circuit_devices_snapshot,
 };
 use crate::node_manager::ws::single_user_channels::flows_by_circuit::{
-    circuit_flow_sankey_result, circuit_top_asns_result, circuit_traffic_flows_result,
+    circuit_flow_sankey_result, circuit_top_applications_result, circuit_top_asns_result,
+    circuit_traffic_flows_result,
 };
 use crate::node_manager::ws::ticker::channel_ticker;
 use crate::system_stats::SystemStats;
@@ -74,6 +78,7 @@ struct WsUpgradeContext {
     shaper_query: Sender<ShaperQueryCommand>,
     browser_language: Option<String>,
     login: LoginResult,
+    username: String,
 }
 
 async fn send_control_command(
@@ -220,12 +225,12 @@ async fn ws_handler(
         .get(header::ACCEPT_LANGUAGE)
         .and_then(|h| h.to_str().ok())
         .map(|s| s.to_string());
-    let login = login_from_cookie_header(
-        headers
-            .get(header::COOKIE)
-            .and_then(|value| value.to_str().ok()),
-    )
-    .await;
+    let cookie_header = headers
+        .get(header::COOKIE)
+        .and_then(|value| value.to_str().ok());
+    let login = login_from_cookie_header(cookie_header).await;
+    let username =
+        username_from_cookie_header(cookie_header).unwrap_or_else(|| "Anonymous".to_string());
     ws.on_upgrade(move |socket| async move {
         handle_socket(
             socket,
@@ -237,6 +242,7 @@ async fn ws_handler(
                 shaper_query,
                 browser_language,
                 login,
+                username,
             },
         )
         .await;
@@ -287,6 +293,7 @@ async fn handle_socket(socket: WebSocket, context: WsUpgradeContext) {
         shaper_query,
         browser_language,
         mut login,
+        username,
     } = context;
 
     let (mut ws_tx, mut ws_rx) = socket.split();
@@ -347,6 +354,7 @@ async fn handle_socket(socket: WebSocket, context: WsUpgradeContext) {
                                 &mut WsRequestState {
                                     private_state: &mut private_state,
                                     login: &mut login,
+                                    username: &username,
                                     shaper_query: shaper_query.clone(),
                                 },
                             ),
@@ -397,6 +405,8 @@ fn is_benign_recv_error(err: &axum::Error, handshake_complete: bool) -> bool {
 struct WsRequestState<'a> {
     private_state: &'a mut single_user_channels::PrivateState,
     login: &'a mut LoginResult,
+    /// Signed-in user, recorded by actions that keep an audit trail.
+    username: &'a str,
     shaper_query: Sender<ShaperQueryCommand>,
 }
 
@@ -668,6 +678,15 @@ async fn receive_channel_message(
                 return true;
             }
         }
+        WsRequest::CircuitTopApplications { query } => {
+            let response = WsResponse::CircuitTopApplicationsResult {
+                circuit_id: query.circuit.clone(),
+                data: circuit_top_applications_result(&query),
+            };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
         WsRequest::CircuitTrafficFlowsPage { query } => {
             let response = WsResponse::CircuitTrafficFlowsPageResult {
                 circuit_id: query.circuit.clone(),
@@ -732,6 +751,14 @@ async fn receive_channel_message(
                 return true;
             }
         }
+        WsRequest::CpuAffinityPlannerProjection => {
+            let response = WsResponse::CpuAffinityPlannerProjection {
+                data: cpu_affinity::cpu_affinity_planner_projection_data(),
+            };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
         WsRequest::CpuAffinityCircuits {
             cpu,
             direction,
@@ -2065,6 +2092,208 @@ async fn receive_channel_message(
                 }
             }
         }
+        WsRequest::GetInputHistory => {
+            match input_history::get_input_history(*request_state.login) {
+                Ok(data) => {
+                    let response = WsResponse::GetInputHistory { data };
+                    if send_ws_response(&tx, response).await {
+                        return true;
+                    }
+                }
+                Err(_) => {
+                    let response = WsResponse::Error {
+                        message: "Unable to load input history".to_string(),
+                    };
+                    if send_ws_response(&tx, response).await {
+                        return true;
+                    }
+                }
+            }
+        }
+        WsRequest::GetInputRevisionDiff { from, to } => {
+            match input_history::get_input_revision_diff(*request_state.login, from, to) {
+                Ok(data) => {
+                    let response = WsResponse::GetInputRevisionDiff { data };
+                    if send_ws_response(&tx, response).await {
+                        return true;
+                    }
+                }
+                Err(StatusCode::NOT_FOUND) => {
+                    let response = WsResponse::Error {
+                        message: "Input revision not found".to_string(),
+                    };
+                    if send_ws_response(&tx, response).await {
+                        return true;
+                    }
+                }
+                Err(_) => {
+                    let response = WsResponse::Error {
+                        message: "Unable to compare input revisions".to_string(),
+                    };
+                    if send_ws_response(&tx, response).await {
+                        return true;
+                    }
+                }
+            }
+        }
+        WsRequest::RestoreInputRevision { id } => {
+            let response = match input_history::restore_input_revision(*request_state.login, id)
+                .await
+            {
+                Ok(revision) => WsResponse::RestoreInputRevisionResult {
+                    ok: true,
+                    message: format!(
+                        "Restored revision {id} (recorded as revision {}) and reloaded LibreQoS",
+                        revision.id
+                    ),
+                },
+                Err(message) => WsResponse::RestoreInputRevisionResult { ok: false, message },
+            };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
+        WsRequest::PlanShapingChange {
+            network_json,
+            shaped_devices_csv,
+            operator_overrides,
+        } => {
+            let candidate = crate::shaping_plan::PlanCandidate {
+                network_json,
+                shaped_devices_csv,
+                operator_overrides,
+            };
+            let response =
+                match shaping_plan::plan_shaping_change(*request_state.login, candidate).await {
+                    Ok(data) => WsResponse::PlanShapingChangeResult {
+                        ok: true,
+                        message: if data.full_reload {
+                            "Applying this change would trigger a full reload".to_string()
+                        } else {
+                            format!(
+                                "Applying this change would make {} live change(s)",
+                                data.live_changes
+                            )
+                        },
+                        data: Some(data),
+                    },
+                    Err(message) => WsResponse::PlanShapingChangeResult {
+                        ok: false,
+                        message,
+                        data: None,
+                    },
+                };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
+        WsRequest::ListTrafficMirrors => {
+            let response = match traffic_mirror::list_traffic_mirrors(*request_state.login).await {
+                Ok(data) => WsResponse::TrafficMirrors { data },
+                Err(message) => WsResponse::Error { message },
+            };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
+        WsRequest::StartTrafficMirror {
+            circuit_id,
+            prefixes,
+            minutes,
+            reason,
+        } => {
+            let response = match traffic_mirror::start_traffic_mirror(
+                *request_state.login,
+                request_state.username.to_string(),
+                circuit_id,
+                prefixes,
+                minutes,
+                reason,
+            )
+            .await
+            {
+                Ok(data) => WsResponse::TrafficMirrorResult {
+                    ok: true,
+                    message: format!("Mirror session {} started", data.session_id),
+                    data: Some(data),
+                },
+                Err(message) => WsResponse::TrafficMirrorResult {
+                    ok: false,
+                    message,
+                    data: None,
+                },
+            };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
+        WsRequest::StopTrafficMirror { session_id } => {
+            let response = match traffic_mirror::stop_traffic_mirror(
+                *request_state.login,
+                request_state.username.to_string(),
+                session_id,
+            )
+            .await
+            {
+                Ok(()) => WsResponse::TrafficMirrorResult {
+                    ok: true,
+                    message: format!("Mirror session {session_id} stopped"),
+                    data: None,
+                },
+                Err(message) => WsResponse::TrafficMirrorResult {
+                    ok: false,
+                    message,
+                    data: None,
+                },
+            };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
+        WsRequest::GetFleetOverview => {
+            let response = match fleet::get_fleet_overview(*request_state.login).await {
+                Ok(data) => WsResponse::GetFleetOverview { data },
+                Err(message) => WsResponse::Error { message },
+            };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
+        WsRequest::FleetSearch { term } => {
+            let response = match fleet::search_fleet(*request_state.login, term).await {
+                Ok(data) => WsResponse::FleetSearchResults { data },
+                Err(message) => WsResponse::Error { message },
+            };
+            if send_ws_response(&tx, response).await {
+                return true;
+            }
+        }
+        WsRequest::GetOverrideSchedule => {
+            match override_schedule::get_override_schedule(*request_state.login) {
+                Ok(data) => {
+                    let response = WsResponse::GetOverrideSchedule { data };
+                    if send_ws_response(&tx, response).await {
+                        return true;
+                    }
+                }
+                Err(StatusCode::FORBIDDEN) => {
+                    let response = WsResponse::Error {
+                        message: "Unauthorized".to_string(),
+                    };
+                    if send_ws_response(&tx, response).await {
+                        return true;
+                    }
+                }
+                Err(_) => {
+                    let response = WsResponse::Error {
+                        message: "Unable to load scheduled overrides".to_string(),
+                    };
+                    if send_ws_response(&tx, response).await {
+                        return true;
+                    }
+                }
+            }
+        }
         WsRequest::GetTopologyProbesState => {
             match topology_probes::get_topology_probes_state(*request_state.login) {
                 Ok(data) => {
@@ -1,9 +1,11 @@
+use crate::fleet::{FleetOverview, FleetSearchResults};
 use crate::lts2_sys::control_channel::{SupportTicket, SupportTicketSummary};
 use crate::node_manager::WarningLevel;
 use crate::node_manager::local_api::circuit::CircuitByIdData;
 use crate::node_manager::local_api::circuit_activity::{
-    CircuitFlowSankeyRow, CircuitSummaryData, CircuitTopAsnsData, CircuitTopAsnsQuery,
-    CircuitTrafficFlowsPage, CircuitTrafficFlowsQuery,
+    CircuitFlowSankeyRow, CircuitSummaryData, CircuitTopApplicationsData,
+    CircuitTopApplicationsQuery, CircuitTopAsnsData, CircuitTopAsnsQuery, CircuitTrafficFlowsPage,
+    CircuitTrafficFlowsQuery,
 };
 use crate::node_manager::local_api::config::{ConfigSecretClearRequest, ConfigView};
 use crate::node_manager::local_api::dashboard_themes::{DashletIdentity, ThemeEntry};
@@ -24,6 +26,7 @@ use crate::node_manager::local_api::node_rate_overrides::{
 use crate::node_manager::local_api::node_topology_overrides::{
     NodeTopologyOverrideData, NodeTopologyOverrideQuery,
 };
+use crate::node_manager::local_api::override_schedule::OverrideScheduleData;
 use crate::node_manager::local_api::packet_analysis::RequestAnalysisResult;
 use crate::node_manager::local_api::scheduler::{SchedulerDetails, SchedulerStatus};
 use crate::node_manager::local_api::search::SearchResult;
@@ -46,8 +49,9 @@ use crate::node_manager::local_api::{
     circuit_count::CircuitCount,
     circuit_live::{CircuitLiveMetrics, CircuitMetricsQuery},
     cpu_affinity::{
-        CircuitBrief, CpuAffinityCircuitsPage, CpuAffinityRuntimeSnapshot, CpuAffinitySiteTreeNode,
-        CpuAffinitySummaryEntry, PreviewWeightItem,
+        CircuitBrief, CpuAffinityCircuitsPage, CpuAffinityPlannerProjection,
+        CpuAffinityRuntimeSnapshot, CpuAffinitySiteTreeNode, CpuAffinitySummaryEntry,
+        PreviewWeightItem,
     },
     flow_explorer::FlowTimeline,
     lts::{
@@ -63,11 +67,13 @@ use crate::throughput_tracker::flow_data::{
     AsnCountryListEntry, AsnListEntry, AsnProtocolListEntry,
 };
 use lqos_bus::{
-    Circuit, FlowbeeSummaryData, LtsCapabilitiesSummary, QueueStoreTransit, StormguardDebugEntry,
-    StormguardRuntimeStatus,
+    BakeryPlanReport, Circuit, FlowbeeSummaryData, LtsCapabilitiesSummary, QueueStoreTransit,
+    StormguardDebugEntry, StormguardRuntimeStatus, TrafficGroupCounter, TrafficMirrorSession,
 };
 use lqos_config::QooProfileInfo;
-use lqos_config::{Config, NetworkJsonTransport, ShapedDevice, WebUser};
+use lqos_config::{
+    Config, InputRevision, InputRevisionDiff, NetworkJsonTransport, ShapedDevice, WebUser,
+};
 use lqos_utils::units::DownUpOrder;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -222,6 +228,36 @@ pub enum WsRequest {
     },
     GetTopologyManagerState,
     GetTopologyProbesState,
+    GetOverrideSchedule,
+    GetInputHistory,
+    GetInputRevisionDiff {
+        from: u64,
+        to: u64,
+    },
+    RestoreInputRevision {
+        id: u64,
+    },
+    PlanShapingChange {
+        network_json: Option<String>,
+        shaped_devices_csv: Option<String>,
+        operator_overrides: Option<String>,
+    },
+    ListTrafficMirrors,
+    StartTrafficMirror {
+        circuit_id: Option<String>,
+        #[serde(default)]
+        prefixes: Vec<String>,
+        minutes: Option<u32>,
+        #[serde(default)]
+        reason: String,
+    },
+    StopTrafficMirror {
+        session_id: u64,
+    },
+    GetFleetOverview,
+    FleetSearch {
+        term: String,
+    },
     SetTopologyManagerOverride {
         update: TopologyManagerUpdate,
     },
@@ -293,6 +329,9 @@ pub enum WsRequest {
     CircuitTopAsns {
         query: CircuitTopAsnsQuery,
     },
+    CircuitTopApplications {
+        query: CircuitTopApplicationsQuery,
+    },
     CircuitTrafficFlowsPage {
         query: CircuitTrafficFlowsQuery,
     },
@@ -305,6 +344,7 @@ pub enum WsRequest {
     },
     CpuAffinitySummary,
     CpuAffinityRuntimeSnapshot,
+    CpuAffinityPlannerProjection,
     CpuAffinityCircuits {
         cpu: u32,
         direction: Option<String>,
@@ -767,6 +807,38 @@ pub enum WsResponse {
     GetTopologyProbesState {
         data: TopologyProbesStateData,
     },
+    GetOverrideSchedule {
+        data: OverrideScheduleData,
+    },
+    GetInputHistory {
+        data: Vec<InputRevision>,
+    },
+    GetInputRevisionDiff {
+        data: InputRevisionDiff,
+    },
+    RestoreInputRevisionResult {
+        ok: bool,
+        message: String,
+    },
+    PlanShapingChangeResult {
+        ok: bool,
+        message: String,
+        data: Option<BakeryPlanReport>,
+    },
+    TrafficMirrors {
+        data: Vec<TrafficMirrorSession>,
+    },
+    TrafficMirrorResult {
+        ok: bool,
+        message: String,
+        data: Option<TrafficMirrorSession>,
+    },
+    GetFleetOverview {
+        data: FleetOverview,
+    },
+    FleetSearchResults {
+        data: FleetSearchResults,
+    },
     SetTopologyManagerOverrideResult {
         ok: bool,
         message: String,
@@ -888,6 +960,9 @@ pub enum WsResponse {
     ExecutiveDashboardSummary {
         data: ExecutiveDashboardSummary,
     },
+    TrafficGroups {
+        data: Vec<TrafficGroupCounter>,
+    },
     ExecutiveHeatmapPage {
         data: ExecutiveHeatmapPage,
     },
@@ -911,6 +986,9 @@ pub enum WsResponse {
     CpuAffinityRuntimeSnapshot {
         data: CpuAffinityRuntimeSnapshot,
     },
+    CpuAffinityPlannerProjection {
+        data: CpuAffinityPlannerProjection,
+    },
     CpuAffinityCircuits {
         data: CpuAffinityCircuitsPage,
     },
@@ -1092,6 +1170,10 @@ pub enum WsResponse {
         circuit_id: String,
         data: CircuitTopAsnsData,
     },
+    CircuitTopApplicationsResult {
+        circuit_id: String,
+        data: CircuitTopApplicationsData,
+    },
     CircuitTrafficFlowsPageResult {
         circuit_id: String,
         data: CircuitTrafficFlowsPage,
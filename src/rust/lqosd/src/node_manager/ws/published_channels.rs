@@ -54,4 +54,5 @@ pub enum PublishedChannels {
     AsnTopDownload,
     AsnTopUpload,
     ExecutiveDashboardSummary,
+    TrafficGroups,
 }
@@ -1,6 +1,7 @@
 use crate::node_manager::local_api::circuit_activity::{
-    CircuitFlowSankeyRow, CircuitTopAsnsData, CircuitTopAsnsQuery, CircuitTrafficFlowsPage,
-    CircuitTrafficFlowsQuery, circuit_flow_sankey_rows, circuit_top_asns_data,
+    CircuitFlowSankeyRow, CircuitTopApplicationsData, CircuitTopApplicationsQuery,
+    CircuitTopAsnsData, CircuitTopAsnsQuery, CircuitTrafficFlowsPage, CircuitTrafficFlowsQuery,
+    circuit_flow_sankey_rows, circuit_top_applications_data, circuit_top_asns_data,
     circuit_traffic_flows_page,
 };
 
@@ -12,6 +13,12 @@ pub fn circuit_top_asns_result(query: &CircuitTopAsnsQuery) -> CircuitTopAsnsDat
     circuit_top_asns_data(query)
 }
 
+pub fn circuit_top_applications_result(
+    query: &CircuitTopApplicationsQuery,
+) -> CircuitTopApplicationsData {
+    circuit_top_applications_data(query)
+}
+
 pub fn circuit_traffic_flows_result(query: &CircuitTrafficFlowsQuery) -> CircuitTrafficFlowsPage {
     circuit_traffic_flows_page(query)
 }
@@ -31,6 +31,7 @@ pub mod system_info;
 mod throughput;
 mod top_10;
 mod top_flows;
+mod traffic_groups;
 mod tree_capacity;
 mod tree_summary;
 mod tree_summary_l2;
@@ -544,6 +545,10 @@ async fn one_second_cadence(
                 "executive_dashboard_summary",
                 executive_dashboard_summary::executive_dashboard_summary(channels.clone())
             ),
+            ticker_with_timeout(
+                "traffic_groups",
+                traffic_groups::traffic_groups(channels.clone())
+            ),
         );
 
         channels.clean().await;
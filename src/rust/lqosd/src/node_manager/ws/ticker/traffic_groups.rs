@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use crate::node_manager::ws::messages::WsResponse;
+use crate::node_manager::ws::publish_subscribe::PubSub;
+use crate::node_manager::ws::published_channels::PublishedChannels;
+use crate::throughput_tracker::flow_data::traffic_group_totals;
+
+pub async fn traffic_groups(channels: Arc<PubSub>) {
+    if !channels
+        .is_channel_alive(PublishedChannels::TrafficGroups)
+        .await
+    {
+        return;
+    }
+
+    let payload = WsResponse::TrafficGroups {
+        data: traffic_group_totals(),
+    };
+    channels
+        .send(PublishedChannels::TrafficGroups, payload)
+        .await;
+}
@@ -0,0 +1,69 @@
+//! Applies and retires scheduled operator overrides.
+//!
+//! Overrides with `not_before`/`expires_at` are only counted by the effective
+//! overrides view while their window is open. This thread keeps the operator
+//! file in step with that window, through the normal override writer, and
+//! reloads LibreQoS whenever an entry was activated or retired so the shaping
+//! tree picks up the change without operator action.
+
+use crate::override_writer;
+use crate::program_control;
+use lqos_bus::{BusResponse, OverrideLayerSelection, OverrideMutation};
+use lqos_utils::unix_time::unix_now;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const CHECK_INTERVAL_SECONDS: u64 = 30;
+
+/// Spawns the override schedule reaper thread.
+///
+/// Side effects: this function starts a background thread. The thread edits
+/// `lqos_overrides.json` through the override writer and may reload LibreQoS.
+pub fn start_override_schedule_reaper() {
+    match std::thread::Builder::new()
+        .name("Override Schedule".to_string())
+        .spawn(reaper_loop)
+    {
+        Ok(_) => info!("Override schedule reaper started"),
+        Err(err) => warn!("Failed to start override schedule reaper: {err:?}"),
+    }
+}
+
+fn reaper_loop() {
+    loop {
+        apply_schedule_once();
+        std::thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECONDS));
+    }
+}
+
+fn apply_schedule_once() {
+    // The active peer applies the schedule and replicates the result.
+    if crate::ha_sync::is_standby() {
+        return;
+    }
+    let Ok(now_unix) = unix_now() else {
+        return;
+    };
+    let result = match override_writer::apply_mutation_batch(
+        OverrideLayerSelection::Operator,
+        vec![OverrideMutation::ApplyOverrideSchedule { now_unix }],
+    ) {
+        Ok(result) => result,
+        Err(err) => {
+            warn!("Unable to apply the override schedule: {err}");
+            return;
+        }
+    };
+    if !result.changed {
+        return;
+    }
+    for change in &result.changed_entities {
+        info!("Override schedule: {change}");
+    }
+    match program_control::reload_libre_qos() {
+        BusResponse::Fail(message) => {
+            warn!("Override schedule changed, but reloading LibreQoS failed: {message}")
+        }
+        _ => info!("Reloaded LibreQoS after override schedule changes"),
+    }
+}
@@ -242,6 +242,20 @@ fn apply_one_mutation(
         OverrideMutation::ClearNodeVirtualBatch { node_names } => {
             changed_entities.extend(overrides.remove_network_node_virtual_by_names(node_names));
         }
+        OverrideMutation::ApplyOverrideSchedule { now_unix } => {
+            changed_entities.extend(overrides.apply_schedule_return_changes(*now_unix));
+        }
+        OverrideMutation::SetCircuitAccessPolicy { circuit_id, policy } => {
+            let changed = match policy {
+                Some(policy) => {
+                    overrides.set_circuit_access_policy_return_changed(circuit_id.clone(), *policy)
+                }
+                None => overrides.remove_circuit_access_policy_count(circuit_id) > 0,
+            };
+            if changed {
+                changed_entities.push(circuit_id.clone());
+            }
+        }
     }
 }
 
@@ -252,7 +266,7 @@ mod tests {
         retry_lock_contention,
     };
     use lqos_bus::{BusResponse, OverrideLayerSelection, OverrideMutation};
-    use lqos_overrides::OverrideFile;
+    use lqos_overrides::{CircuitAdjustment, OverrideFile, OverrideSchedule};
     use std::cell::Cell;
 
     #[test]
@@ -280,6 +294,52 @@ mod tests {
         assert!(overrides.network_adjustments().is_empty());
     }
 
+    #[test]
+    fn apply_override_schedule_retires_expired_entries() {
+        let mut overrides = OverrideFile::default();
+        overrides.add_circuit_adjustment(CircuitAdjustment::RemoveCircuit {
+            circuit_id: "c1".to_string(),
+            schedule: OverrideSchedule {
+                expires_at: Some(100),
+                ..OverrideSchedule::default()
+            },
+        });
+
+        let result = apply_mutations_to_file(
+            &mut overrides,
+            &[OverrideMutation::ApplyOverrideSchedule { now_unix: 100 }],
+        );
+
+        assert!(result.changed);
+        assert!(overrides.circuit_adjustments().is_empty());
+        assert_eq!(overrides.expired_circuit_adjustments().len(), 1);
+    }
+
+    #[test]
+    fn set_circuit_access_policy_reports_only_real_changes() {
+        let mut overrides = OverrideFile::default();
+        let set = OverrideMutation::SetCircuitAccessPolicy {
+            circuit_id: "c1".to_string(),
+            policy: Some(lqos_config::CircuitAccessPolicy::Portal),
+        };
+
+        let result = apply_mutations_to_file(&mut overrides, std::slice::from_ref(&set));
+        assert_eq!(result.changed_entities, vec!["c1".to_string()]);
+        assert_eq!(
+            overrides.circuit_access_policy("c1"),
+            Some(lqos_config::CircuitAccessPolicy::Portal)
+        );
+        assert!(!apply_mutations_to_file(&mut overrides, &[set]).changed);
+
+        let clear = OverrideMutation::SetCircuitAccessPolicy {
+            circuit_id: "c1".to_string(),
+            policy: None,
+        };
+        assert!(apply_mutations_to_file(&mut overrides, std::slice::from_ref(&clear)).changed);
+        assert_eq!(overrides.circuit_access_policy("c1"), None);
+        assert!(!apply_mutations_to_file(&mut overrides, &[clear]).changed);
+    }
+
     #[test]
     fn lock_contention_detection_is_specific_to_override_lock_errors() {
         let lock_error = anyhow::anyhow!(
@@ -0,0 +1,116 @@
+//! Observed top-level site load sampling for the traffic-weighted CPU planner.
+//!
+//! When `[traffic_planner]` is enabled, a background thread records each top-level
+//! network node's combined throughput and packet rate into a bucketed history file.
+//! `LibreQoS.py` reads that file through `liblqos_python` when planning CPU bins.
+
+use lqos_config::{
+    PlannerTrafficHistoryFile, TopLevelTrafficSample, load_config, load_planner_traffic_history,
+    store_planner_traffic_history,
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const BUCKET_SECONDS: u64 = 900;
+const PERSIST_INTERVAL_SECONDS: u64 = 300;
+const DISABLED_POLL_SECONDS: u64 = 60;
+
+static HISTORY: Lazy<Mutex<Option<PlannerTrafficHistoryFile>>> = Lazy::new(|| Mutex::new(None));
+
+/// Spawns the planner traffic sampler thread.
+///
+/// Side effects: this function starts a background thread that periodically
+/// writes `planner_traffic_history.json` while the traffic planner is enabled.
+pub fn start_planner_traffic_sampler() {
+    match std::thread::Builder::new()
+        .name("Planner Traffic".to_string())
+        .spawn(sampler_loop)
+    {
+        Ok(_) => debug!("Planner traffic sampler started"),
+        Err(err) => warn!("Failed to start planner traffic sampler: {err:?}"),
+    }
+}
+
+/// Returns the in-memory planner traffic samples, or the persisted ones if the sampler
+/// has not run yet.
+pub fn planner_traffic_samples() -> Vec<TopLevelTrafficSample> {
+    if let Some(history) = HISTORY.lock().as_ref() {
+        return history.samples();
+    }
+    let Ok(config) = load_config() else {
+        return Vec::new();
+    };
+    load_planner_traffic_history(config.as_ref())
+        .ok()
+        .flatten()
+        .map(|history| history.samples())
+        .unwrap_or_default()
+}
+
+fn sampler_loop() {
+    let mut last_persist = 0u64;
+    loop {
+        let Ok(config) = load_config() else {
+            std::thread::sleep(Duration::from_secs(DISABLED_POLL_SECONDS));
+            continue;
+        };
+        let Some(settings) = config.traffic_planner.clone().filter(|tp| tp.enabled) else {
+            std::thread::sleep(Duration::from_secs(DISABLED_POLL_SECONDS));
+            continue;
+        };
+        std::thread::sleep(Duration::from_secs(settings.sample_interval_seconds.max(1)));
+
+        let Ok(now) = lqos_utils::unix_time::unix_now() else {
+            continue;
+        };
+        let loads = top_level_loads();
+        if loads.is_empty() {
+            continue;
+        }
+
+        let mut lock = HISTORY.lock();
+        let history = lock.get_or_insert_with(|| match load_planner_traffic_history(&config) {
+            Ok(Some(history)) if history.bucket_seconds == BUCKET_SECONDS => {
+                info!(
+                    "Loaded planner traffic history for {} sites",
+                    history.sites.len()
+                );
+                history
+            }
+            Ok(_) => PlannerTrafficHistoryFile::new(BUCKET_SECONDS),
+            Err(err) => {
+                warn!("Discarding unreadable planner traffic history: {err}");
+                PlannerTrafficHistoryFile::new(BUCKET_SECONDS)
+            }
+        });
+        for (site, bits_per_second, packets_per_second) in loads {
+            history.record(&site, now, bits_per_second, packets_per_second);
+        }
+
+        if now.saturating_sub(last_persist) >= PERSIST_INTERVAL_SECONDS {
+            history.prune(now.saturating_sub(u64::from(settings.window_hours) * 3600));
+            if let Err(err) = store_planner_traffic_history(&config, history) {
+                warn!("Unable to store planner traffic history: {err}");
+            }
+            last_persist = now;
+        }
+    }
+}
+
+/// Returns `(name, bits per second, packets per second)` for each top-level network node.
+fn top_level_loads() -> Vec<(String, f64, f64)> {
+    lqos_network_devices::with_network_json_read(|net_json| {
+        net_json
+            .get_nodes_when_ready()
+            .iter()
+            .filter(|node| node.immediate_parent == Some(0))
+            .map(|node| {
+                let bytes = node.current_throughput.sum();
+                let packets = node.current_packets.sum();
+                (node.name.clone(), bytes as f64 * 8.0, packets as f64)
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,707 @@
+//! Per-circuit and per-site QoO profiles.
+//!
+//! A circuit is scored with the first profile found in: its entry in the
+//! overrides file's `qoo_profiles.circuits`, the `QoO Profile` column of
+//! `ShapedDevices.csv`, the nearest site in `qoo_profiles.sites`, and finally
+//! the global `qoo_profile_id`. Sites use their own or the nearest parent's
+//! site entry. An assignment of `auto` picks the profile from the traffic mix
+//! the flow analysis (application catalog, protocol) has recently seen for
+//! the circuit, or for every circuit below the site.
+//!
+//! Assignments are rebuilt every few seconds on a background thread, so the
+//! per-second tracking code only reads a snapshot.
+
+use arc_swap::ArcSwap;
+use fxhash::{FxHashMap, FxHashSet};
+use lqos_overrides::{OverrideFile, QooProfileOverrides};
+use lqos_queue_tracker::{QUEUE_STRUCTURE, QueueNode};
+use lqos_utils::hash_to_i64;
+use lqos_utils::qoo::{QooProfile, QooProfilesFile};
+use lqos_utils::units::DownUpOrder;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::throughput_tracker::flow_data::{AppId, FlowAnalysis};
+
+/// Assignment value that selects the profile from the traffic mix.
+pub(crate) const AUTO_PROFILE: &str = "auto";
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// Traffic mix counters halve every ten minutes, sampled once per second.
+const MIX_HALF_LIFE_SECONDS: f64 = 600.0;
+/// Below this many (decayed) bytes the mix is too thin to judge.
+const MIN_AUTO_BYTES: f64 = 50_000_000.0;
+/// Interactive traffic is latency-critical even as a small share of bytes.
+const INTERACTIVE_SHARE: f64 = 0.10;
+
+/// Traffic classes that map onto the shipped QoO profiles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MixClass {
+    Web,
+    Video,
+    Audio,
+    Conferencing,
+    Gaming,
+}
+
+impl MixClass {
+    fn profile_id(self) -> &'static str {
+        match self {
+            MixClass::Web => "web_browsing",
+            MixClass::Video => "video_streaming",
+            MixClass::Audio => "audio_streaming",
+            MixClass::Conferencing => "video_conferencing",
+            MixClass::Gaming => "real_time_gaming",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            MixClass::Web => "web and other traffic",
+            MixClass::Video => "video streaming",
+            MixClass::Audio => "audio streaming",
+            MixClass::Conferencing => "video conferencing",
+            MixClass::Gaming => "real-time gaming",
+        }
+    }
+
+    /// Classifies a flow. Game stores and updates download over TCP, so only
+    /// UDP counts as gameplay.
+    fn of_flow(category: &str, ip_protocol: u8) -> Self {
+        match category {
+            "Streaming Video" => MixClass::Video,
+            "Streaming Audio" => MixClass::Audio,
+            "Video Conferencing" => MixClass::Conferencing,
+            "Gaming" if ip_protocol == 17 => MixClass::Gaming,
+            _ => MixClass::Web,
+        }
+    }
+}
+
+/// Recent bytes per traffic class.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct TrafficMix([f64; 5]);
+
+impl TrafficMix {
+    fn add(&mut self, class: MixClass, bytes: f64) {
+        self.0[class as usize] += bytes;
+    }
+
+    fn merge(&mut self, other: &TrafficMix) {
+        for (total, bytes) in self.0.iter_mut().zip(other.0) {
+            *total += bytes;
+        }
+    }
+
+    fn decay(&mut self, factor: f64) {
+        self.0.iter_mut().for_each(|bytes| *bytes *= factor);
+    }
+
+    fn total(&self) -> f64 {
+        self.0.iter().sum()
+    }
+
+    fn bytes(&self, class: MixClass) -> f64 {
+        self.0[class as usize]
+    }
+
+    /// The class to score with and its share of the traffic, or `None` if
+    /// there is too little traffic to tell.
+    fn dominant(&self) -> Option<(MixClass, f64)> {
+        let total = self.total();
+        if total < MIN_AUTO_BYTES {
+            return None;
+        }
+        let share = |class| self.bytes(class) / total;
+        let largest = |classes: &[MixClass]| {
+            classes
+                .iter()
+                .copied()
+                .max_by(|a, b| share(*a).total_cmp(&share(*b)))
+                .unwrap_or(MixClass::Web)
+        };
+        let interactive = largest(&[MixClass::Conferencing, MixClass::Gaming]);
+        let class = if share(interactive) >= INTERACTIVE_SHARE {
+            interactive
+        } else {
+            largest(&[MixClass::Video, MixClass::Audio, MixClass::Web])
+        };
+        Some((class, share(class)))
+    }
+}
+
+/// The profile used for a circuit or site, and why.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ProfileChoice {
+    pub profile_id: String,
+    pub reason: String,
+}
+
+struct Assigned {
+    choice: ProfileChoice,
+    profile: Arc<QooProfile>,
+}
+
+/// Snapshot of every QoO profile assignment.
+#[derive(Default)]
+pub(crate) struct QooAssignments {
+    default: Option<Arc<Assigned>>,
+    circuits: FxHashMap<i64, Arc<Assigned>>,
+    sites: FxHashMap<String, Arc<Assigned>>,
+    /// Circuits whose traffic mix feeds an `auto` assignment.
+    mix_circuits: FxHashSet<i64>,
+}
+
+impl QooAssignments {
+    /// Profile for scoring a circuit; the global profile if it has none.
+    pub(crate) fn circuit_profile(&self, circuit_hash: Option<i64>) -> Option<&QooProfile> {
+        circuit_hash
+            .and_then(|hash| self.circuits.get(&hash))
+            .or(self.default.as_ref())
+            .map(|assigned| assigned.profile.as_ref())
+    }
+
+    /// Profile for scoring a site; the global profile if it has none.
+    pub(crate) fn site_profile(&self, site_name: &str) -> Option<Arc<QooProfile>> {
+        self.sites
+            .get(site_name)
+            .or(self.default.as_ref())
+            .map(|assigned| assigned.profile.clone())
+    }
+
+    /// Global profile, used for the network-wide score.
+    pub(crate) fn default_profile(&self) -> Option<&QooProfile> {
+        self.default
+            .as_ref()
+            .map(|assigned| assigned.profile.as_ref())
+    }
+
+    pub(crate) fn circuit_choice(&self, circuit_id: &str) -> Option<ProfileChoice> {
+        self.circuits
+            .get(&hash_to_i64(circuit_id))
+            .or(self.default.as_ref())
+            .map(|assigned| assigned.choice.clone())
+    }
+
+    pub(crate) fn site_choice(&self, site_name: &str) -> Option<ProfileChoice> {
+        self.sites
+            .get(site_name)
+            .or(self.default.as_ref())
+            .map(|assigned| assigned.choice.clone())
+    }
+
+    pub(crate) fn default_choice(&self) -> Option<ProfileChoice> {
+        self.default
+            .as_ref()
+            .map(|assigned| assigned.choice.clone())
+    }
+
+    fn tracks_mix(&self, circuit_hash: i64) -> bool {
+        self.mix_circuits.contains(&circuit_hash)
+    }
+}
+
+static ASSIGNMENTS: Lazy<ArcSwap<QooAssignments>> =
+    Lazy::new(|| ArcSwap::from_pointee(QooAssignments::default()));
+
+/// Decayed traffic mix per circuit hash, for circuits in `mix_circuits`.
+static MIXES: Lazy<Mutex<FxHashMap<i64, TrafficMix>>> =
+    Lazy::new(|| Mutex::new(FxHashMap::default()));
+
+/// Current QoO profile assignments.
+pub(crate) fn assignments() -> Arc<QooAssignments> {
+    ASSIGNMENTS.load_full()
+}
+
+/// One tracking cycle's flow bytes for circuits that use `auto`.
+#[derive(Default)]
+pub(crate) struct TrafficMixSample {
+    mixes: FxHashMap<i64, TrafficMix>,
+    categories: FxHashMap<AppId, MixClass>,
+}
+
+impl TrafficMixSample {
+    /// Adds a flow's bytes from this cycle to its circuit's mix.
+    pub(crate) fn add_flow(
+        &mut self,
+        assignments: &QooAssignments,
+        circuit_hash: Option<i64>,
+        analysis: &FlowAnalysis,
+        ip_protocol: u8,
+        bytes: DownUpOrder<u64>,
+    ) {
+        let Some(circuit_hash) = circuit_hash.filter(|hash| assignments.tracks_mix(*hash)) else {
+            return;
+        };
+        let bytes = bytes.down.saturating_add(bytes.up);
+        if bytes == 0 {
+            return;
+        }
+        let class = match analysis.application {
+            Some(app) => *self
+                .categories
+                .entry(app)
+                .or_insert_with(|| MixClass::of_flow(&app.category(), ip_protocol)),
+            None => MixClass::Web,
+        };
+        self.mixes
+            .entry(circuit_hash)
+            .or_default()
+            .add(class, bytes as f64);
+    }
+}
+
+/// Folds a cycle's sample into the decayed per-circuit mixes.
+pub(crate) fn record_traffic_mix(sample: TrafficMixSample) {
+    let assignments = ASSIGNMENTS.load();
+    let factor = 0.5f64.powf(1.0 / MIX_HALF_LIFE_SECONDS);
+    let mut mixes = MIXES.lock();
+    mixes.retain(|hash, _| assignments.tracks_mix(*hash));
+    mixes.values_mut().for_each(|mix| mix.decay(factor));
+    for (hash, mix) in sample.mixes {
+        mixes.entry(hash).or_default().merge(&mix);
+    }
+}
+
+/// Where a circuit's or site's requested profile came from.
+struct Request<'a> {
+    profile_id: &'a str,
+    source: String,
+}
+
+struct Resolver<'a> {
+    profiles: &'a QooProfilesFile,
+    default: Option<Arc<Assigned>>,
+    mixes: &'a FxHashMap<i64, TrafficMix>,
+    /// Cached runtime profiles by id.
+    runtime: FxHashMap<String, Arc<QooProfile>>,
+}
+
+impl Resolver<'_> {
+    fn profile(&mut self, profile_id: &str) -> Option<Arc<QooProfile>> {
+        if let Some(profile) = self.runtime.get(profile_id) {
+            return Some(profile.clone());
+        }
+        let spec = self.profiles.profiles.iter().find(|p| p.id == profile_id)?;
+        let profile = Arc::new(spec.to_runtime());
+        self.runtime.insert(profile_id.to_string(), profile.clone());
+        Some(profile)
+    }
+
+    fn fallback(&self, why: String) -> Option<Arc<Assigned>> {
+        let default = self.default.as_ref()?;
+        Some(Arc::new(Assigned {
+            choice: ProfileChoice {
+                profile_id: default.choice.profile_id.clone(),
+                reason: format!("{why}; using the global default"),
+            },
+            profile: default.profile.clone(),
+        }))
+    }
+
+    fn resolve(&mut self, request: &Request, mix: &TrafficMix) -> Option<Arc<Assigned>> {
+        let (profile_id, reason) = if request.profile_id == AUTO_PROFILE {
+            let Some((class, share)) = mix.dominant() else {
+                return self.fallback(format!(
+                    "auto ({}): not enough recent traffic",
+                    request.source
+                ));
+            };
+            (
+                class.profile_id().to_string(),
+                format!(
+                    "auto ({}): {:.0}% of recent traffic was {}",
+                    request.source,
+                    share * 100.0,
+                    class.describe()
+                ),
+            )
+        } else {
+            (request.profile_id.to_string(), request.source.clone())
+        };
+        match self.profile(&profile_id) {
+            Some(profile) => Some(Arc::new(Assigned {
+                choice: ProfileChoice { profile_id, reason },
+                profile,
+            })),
+            None => self.fallback(format!(
+                "{reason}: profile '{profile_id}' is not in qoo_profiles.json"
+            )),
+        }
+    }
+
+    fn circuit_mix(&self, circuit_id: &str) -> TrafficMix {
+        self.mixes
+            .get(&hash_to_i64(circuit_id))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+fn circuit_request<'a>(
+    overrides: &'a QooProfileOverrides,
+    shaped: &'a FxHashMap<String, String>,
+    circuit_id: &str,
+) -> Option<Request<'a>> {
+    if let Some(profile_id) = overrides.circuits.get(circuit_id) {
+        return Some(Request {
+            profile_id,
+            source: "circuit override".to_string(),
+        });
+    }
+    shaped.get(circuit_id).map(|profile_id| Request {
+        profile_id,
+        source: "ShapedDevices.csv".to_string(),
+    })
+}
+
+struct Walk<'a, 'r> {
+    resolver: Resolver<'r>,
+    overrides: &'a QooProfileOverrides,
+    shaped: &'a FxHashMap<String, String>,
+    assignments: QooAssignments,
+}
+
+impl<'a> Walk<'a, '_> {
+    /// Assigns profiles below `node`, returning the subtree's traffic mix.
+    fn node(
+        &mut self,
+        node: &QueueNode,
+        site: Option<&Request<'a>>,
+        in_auto_site: bool,
+    ) -> TrafficMix {
+        if let Some(circuit_id) = node.circuit_id.as_deref() {
+            if node.device_id.is_some() {
+                return TrafficMix::default();
+            }
+            let hash = hash_to_i64(circuit_id);
+            let request = circuit_request(self.overrides, self.shaped, circuit_id);
+            let request = request.as_ref().or(site);
+            let mix = self.resolver.circuit_mix(circuit_id);
+            if in_auto_site || request.is_some_and(|r| r.profile_id == AUTO_PROFILE) {
+                self.assignments.mix_circuits.insert(hash);
+            }
+            if let Some(request) = request
+                && let Some(assigned) = self.resolver.resolve(request, &mix)
+            {
+                self.assignments.circuits.insert(hash, assigned);
+            }
+            return mix;
+        }
+
+        let own = node.name.as_deref().and_then(|name| {
+            self.overrides.sites.get(name).map(|profile_id| Request {
+                profile_id,
+                source: format!("site override ({name})"),
+            })
+        });
+        let site = own.as_ref().or(site);
+        let in_auto_site = in_auto_site || site.is_some_and(|r| r.profile_id == AUTO_PROFILE);
+        let mut mix = TrafficMix::default();
+        for child in node.children.iter().chain(node.circuits.iter()) {
+            mix.merge(&self.node(child, site, in_auto_site));
+        }
+        if let (Some(name), Some(request)) = (node.name.as_deref(), site)
+            && let Some(assigned) = self.resolver.resolve(request, &mix)
+        {
+            self.assignments.sites.insert(name.to_string(), assigned);
+        }
+        mix
+    }
+}
+
+/// Builds the assignment snapshot. `shaped` maps circuit IDs to the
+/// `QoO Profile` column of `ShapedDevices.csv`.
+fn build_assignments(
+    profiles: &QooProfilesFile,
+    default_id: &str,
+    overrides: &QooProfileOverrides,
+    shaped: &FxHashMap<String, String>,
+    queues: &[QueueNode],
+    mixes: &FxHashMap<i64, TrafficMix>,
+) -> QooAssignments {
+    let mut resolver = Resolver {
+        profiles,
+        default: None,
+        mixes,
+        runtime: FxHashMap::default(),
+    };
+    resolver.default = resolver.profile(default_id).map(|profile| {
+        Arc::new(Assigned {
+            choice: ProfileChoice {
+                profile_id: default_id.to_string(),
+                reason: "global default".to_string(),
+            },
+            profile,
+        })
+    });
+    let mut walk = Walk {
+        resolver,
+        overrides,
+        shaped,
+        assignments: QooAssignments::default(),
+    };
+    for node in queues {
+        walk.node(node, None, false);
+    }
+
+    // Circuits missing from the queue structure (not yet mapped) still honour
+    // their own assignment.
+    let unplaced: Vec<&String> = overrides
+        .circuits
+        .keys()
+        .chain(shaped.keys())
+        .filter(|id| !walk.assignments.circuits.contains_key(&hash_to_i64(id)))
+        .collect();
+    for circuit_id in unplaced {
+        let Some(request) = circuit_request(overrides, shaped, circuit_id) else {
+            continue;
+        };
+        let hash = hash_to_i64(circuit_id);
+        if request.profile_id == AUTO_PROFILE {
+            walk.assignments.mix_circuits.insert(hash);
+        }
+        let mix = walk.resolver.circuit_mix(circuit_id);
+        if let Some(assigned) = walk.resolver.resolve(&request, &mix) {
+            walk.assignments.circuits.insert(hash, assigned);
+        }
+    }
+
+    walk.assignments.default = walk.resolver.default.clone();
+    walk.assignments
+}
+
+fn refresh() {
+    let (profiles, default_id) = match (
+        lqos_config::load_qoo_profiles_file(),
+        lqos_config::active_qoo_profile_id(),
+    ) {
+        (Ok(profiles), Ok(default_id)) => (profiles, default_id),
+        (Err(err), _) | (_, Err(err)) => {
+            warn!("Unable to load QoO profiles: {err}");
+            ASSIGNMENTS.store(Arc::new(QooAssignments::default()));
+            return;
+        }
+    };
+    let overrides = match OverrideFile::load() {
+        Ok(file) => file.qoo_profiles().clone(),
+        Err(err) => {
+            warn!("Unable to load lqos_overrides.json for QoO profiles: {err:?}");
+            QooProfileOverrides::default()
+        }
+    };
+    let shaped: FxHashMap<String, String> = lqos_network_devices::shaped_devices_snapshot()
+        .devices
+        .iter()
+        .filter_map(|device| {
+            device
+                .qoo_profile
+                .as_ref()
+                .map(|profile| (device.circuit_id.clone(), profile.clone()))
+        })
+        .collect();
+    let queues = QUEUE_STRUCTURE.load_full();
+    let mixes = MIXES.lock().clone();
+    let assignments = build_assignments(
+        &profiles,
+        &default_id,
+        &overrides,
+        &shaped,
+        queues.maybe_queues.as_deref().unwrap_or_default(),
+        &mixes,
+    );
+    ASSIGNMENTS.store(Arc::new(assignments));
+}
+
+/// Loads the assignments and keeps them current in the background.
+pub(crate) fn start_qoo_assignment() {
+    refresh();
+    if let Err(err) = std::thread::Builder::new()
+        .name("QoO Profiles".to_string())
+        .spawn(|| {
+            loop {
+                std::thread::sleep(REFRESH_INTERVAL);
+                refresh();
+            }
+        })
+    {
+        warn!("Failed to start QoO profile assignment: {err:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles() -> QooProfilesFile {
+        serde_json::from_str(include_str!("../../../qoo_profiles.json")).expect("qoo_profiles.json")
+    }
+
+    fn circuit(circuit_id: &str) -> QueueNode {
+        QueueNode {
+            circuit_id: Some(circuit_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn queues() -> Vec<QueueNode> {
+        vec![QueueNode {
+            name: Some("Tower".to_string()),
+            children: vec![QueueNode {
+                name: Some("AP".to_string()),
+                circuits: vec![circuit("c-1"), circuit("c-2"), circuit("c-3")],
+                ..Default::default()
+            }],
+            circuits: vec![circuit("c-4")],
+            ..Default::default()
+        }]
+    }
+
+    fn mix(pairs: &[(MixClass, f64)]) -> TrafficMix {
+        let mut mix = TrafficMix::default();
+        for (class, bytes) in pairs {
+            mix.add(*class, *bytes);
+        }
+        mix
+    }
+
+    #[test]
+    fn circuit_override_beats_shaped_devices_beats_site() {
+        let mut overrides = QooProfileOverrides::default();
+        overrides
+            .circuits
+            .insert("c-1".to_string(), "real_time_gaming".to_string());
+        overrides
+            .sites
+            .insert("AP".to_string(), "video_streaming".to_string());
+        let shaped: FxHashMap<String, String> = [
+            ("c-1".to_string(), "audio_streaming".to_string()),
+            ("c-2".to_string(), "video_conferencing".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let assignments = build_assignments(
+            &profiles(),
+            "web_browsing",
+            &overrides,
+            &shaped,
+            &queues(),
+            &FxHashMap::default(),
+        );
+
+        let choice = |id: &str| assignments.circuit_choice(id).expect("choice");
+        assert_eq!(choice("c-1").profile_id, "real_time_gaming");
+        assert_eq!(choice("c-1").reason, "circuit override");
+        assert_eq!(choice("c-2").profile_id, "video_conferencing");
+        assert_eq!(choice("c-2").reason, "ShapedDevices.csv");
+        assert_eq!(choice("c-3").profile_id, "video_streaming");
+        assert_eq!(choice("c-3").reason, "site override (AP)");
+        assert_eq!(choice("c-4").profile_id, "web_browsing");
+        assert_eq!(choice("c-4").reason, "global default");
+
+        let site = assignments.site_choice("AP").expect("site choice");
+        assert_eq!(site.profile_id, "video_streaming");
+        assert_eq!(
+            assignments.site_choice("Tower").expect("tower").reason,
+            "global default"
+        );
+        assert!(assignments.mix_circuits.is_empty());
+    }
+
+    #[test]
+    fn unknown_profiles_fall_back_to_the_default_with_a_reason() {
+        let mut overrides = QooProfileOverrides::default();
+        overrides
+            .circuits
+            .insert("c-9".to_string(), "telepathy".to_string());
+        let assignments = build_assignments(
+            &profiles(),
+            "web_browsing",
+            &overrides,
+            &FxHashMap::default(),
+            &queues(),
+            &FxHashMap::default(),
+        );
+        let choice = assignments.circuit_choice("c-9").expect("choice");
+        assert_eq!(choice.profile_id, "web_browsing");
+        assert!(
+            choice
+                .reason
+                .contains("'telepathy' is not in qoo_profiles.json")
+        );
+    }
+
+    #[test]
+    fn auto_sites_pick_from_the_traffic_mix() {
+        let mut overrides = QooProfileOverrides::default();
+        overrides
+            .sites
+            .insert("AP".to_string(), AUTO_PROFILE.to_string());
+        let mixes: FxHashMap<i64, TrafficMix> = [
+            (
+                hash_to_i64("c-1"),
+                mix(&[(MixClass::Video, 80e6), (MixClass::Web, 20e6)]),
+            ),
+            (
+                hash_to_i64("c-2"),
+                mix(&[(MixClass::Gaming, 15e6), (MixClass::Video, 85e6)]),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let assignments = build_assignments(
+            &profiles(),
+            "web_browsing",
+            &overrides,
+            &FxHashMap::default(),
+            &queues(),
+            &mixes,
+        );
+
+        let c1 = assignments.circuit_choice("c-1").expect("c-1");
+        assert_eq!(c1.profile_id, "video_streaming");
+        assert_eq!(
+            c1.reason,
+            "auto (site override (AP)): 80% of recent traffic was video streaming"
+        );
+        assert_eq!(
+            assignments.circuit_choice("c-2").expect("c-2").profile_id,
+            "real_time_gaming"
+        );
+        let c3 = assignments.circuit_choice("c-3").expect("c-3");
+        assert_eq!(c3.profile_id, "web_browsing");
+        assert!(c3.reason.contains("not enough recent traffic"));
+
+        // The site's own mix is the sum of its circuits: 7.5% gaming.
+        let site = assignments.site_choice("AP").expect("AP");
+        assert_eq!(site.profile_id, "video_streaming");
+        assert!(assignments.mix_circuits.contains(&hash_to_i64("c-3")));
+        assert!(!assignments.mix_circuits.contains(&hash_to_i64("c-4")));
+    }
+
+    #[test]
+    fn flows_are_classified_by_application_category() {
+        assert_eq!(MixClass::of_flow("Streaming Video", 6), MixClass::Video);
+        assert_eq!(MixClass::of_flow("Gaming", 17), MixClass::Gaming);
+        assert_eq!(MixClass::of_flow("Gaming", 6), MixClass::Web);
+        assert_eq!(MixClass::of_flow("", 17), MixClass::Web);
+        for class in [
+            MixClass::Web,
+            MixClass::Video,
+            MixClass::Audio,
+            MixClass::Conferencing,
+            MixClass::Gaming,
+        ] {
+            assert!(
+                profiles()
+                    .profiles
+                    .iter()
+                    .any(|profile| profile.id == class.profile_id())
+            );
+        }
+    }
+}
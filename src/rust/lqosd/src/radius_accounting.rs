@@ -256,6 +256,7 @@ async fn run_radius_accounting_listener(
         dynamic_circuit_bus_tx,
     );
     let mut expiry_timer = RadiusExpiryTimer::new(&sessions, radius_accounting_now());
+    let mut ha_replicas = crate::ha_sync::take_radius_replicas();
 
     loop {
         tokio::select! {
@@ -286,6 +287,26 @@ async fn run_radius_accounting_listener(
                     }
                 }
             }
+            replica = next_ha_replica(&mut ha_replicas) => {
+                let Some(replica) = replica else {
+                    ha_replicas = None;
+                    continue;
+                };
+                let now = radius_accounting_now();
+                expire_due_before_packet(
+                    &mut sessions,
+                    &mut expiry_timer,
+                    now,
+                    &mut applying_sink,
+                );
+                handle_ha_replica_with_application_sink(
+                    replica,
+                    &mut sessions,
+                    &mut expiry_timer,
+                    now,
+                    &mut applying_sink,
+                );
+            }
             _ = expiry_timer.sleep_mut() => {
                 let now = radius_accounting_now();
                 expire_due_after_timer_wake(
@@ -388,6 +409,46 @@ fn dynamic_circuit_application_sink(
     apply_dynamic_circuits.then(|| ApplyingDynamicCircuitSink::new(dynamic_circuit_bus_tx))
 }
 
+/// Waits for the next RADIUS packet replicated from an HA peer, forever when
+/// replication is not running.
+async fn next_ha_replica(
+    replicas: &mut Option<mpsc::Receiver<crate::ha_sync::RadiusReplica>>,
+) -> Option<crate::ha_sync::RadiusReplica> {
+    match replicas {
+        Some(replicas) => replicas.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Applies an Accounting-Request that the active HA peer already verified.
+fn handle_ha_replica_with_application_sink(
+    replica: crate::ha_sync::RadiusReplica,
+    sessions: &mut RadiusAccountingSessions,
+    expiry_timer: &mut RadiusExpiryTimer,
+    now: Instant,
+    applying_sink: &mut Option<ApplyingDynamicCircuitSink>,
+) {
+    let request = match lqos_radius::handle_accounting_request(&replica.bytes) {
+        Ok(request) => request,
+        Err(err) => {
+            warn!(peer = %replica.source, error = %err, "rejected replicated RADIUS accounting packet");
+            return;
+        }
+    };
+    handle_accounting_event_with_application_sink_and_expiry_schedule(
+        AccountingEvent::from_request(&request),
+        sessions,
+        expiry_timer,
+        now,
+        applying_sink,
+        AccountingPacketLogContext {
+            peer: replica.source,
+            received_len: replica.bytes.len(),
+            response_len: 0,
+        },
+    );
+}
+
 fn handle_listener_outcome_with_application_sink(
     outcome: AccountingListenerOutcome,
     sessions: &mut RadiusAccountingSessions,
@@ -399,6 +460,11 @@ fn handle_listener_outcome_with_application_sink(
         AccountingListenerOutcome::Accepted(accepted) => {
             sessions.record_packet_accepted();
             let event = AccountingEvent::from_verified(&accepted.request);
+            crate::ha_sync::replicate_radius_packet(
+                accepted.peer,
+                &event,
+                accepted.request.packet().to_bytes(),
+            );
             handle_accounting_event_with_application_sink_and_expiry_schedule(
                 event,
                 sessions,
@@ -553,6 +619,13 @@ impl DynamicCircuitCommandSink for SelectedDynamicCircuitSink<'_> {
 fn selected_dynamic_circuit_sink(
     applying_sink: &mut Option<ApplyingDynamicCircuitSink>,
 ) -> SelectedDynamicCircuitSink<'_> {
+    // An HA standby tracks sessions but leaves circuits to the active peer,
+    // which replicates the circuits it creates.
+    if crate::ha_sync::is_standby() {
+        return SelectedDynamicCircuitSink::Deferred(
+            DeferredDynamicCircuitSink::application_disabled(),
+        );
+    }
     applying_sink.as_mut().map_or_else(
         || SelectedDynamicCircuitSink::Deferred(DeferredDynamicCircuitSink::application_disabled()),
         SelectedDynamicCircuitSink::Applying,
@@ -5668,6 +5741,9 @@ circuit-b,Runtime Circuit B,device-b,Runtime Device B,Parent,ParentId,AnchorId,A
             upload_max_mbps: 20.0,
             comment: "matched from shaped devices".to_string(),
             sqm_override: Some("cake/none".to_string()),
+            priority_classes: Vec::new(),
+            access_policy: lqos_config::CircuitAccessPolicy::Open,
+            qoo_profile: None,
             circuit_hash: 0,
             device_hash: 0,
             parent_hash: 0,
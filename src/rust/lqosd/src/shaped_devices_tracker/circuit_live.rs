@@ -24,6 +24,7 @@ pub struct CircuitLiveRollup {
     pub rtt_current_p50_nanos: DownUpOrder<Option<u64>>,
     pub qoo: DownUpOrder<Option<f32>>,
     pub tcp_retransmit_sample: DownUpOrder<TcpRetransmitSample>,
+    pub dscp_remarked_per_second: DownUpOrder<u64>,
     pub last_seen_nanos: u64,
 }
 
@@ -45,6 +46,7 @@ fn rollup_to_bus(rollup: &CircuitLiveRollup) -> lqos_bus::CircuitRollup {
         rtt_current_p50_nanos: rollup.rtt_current_p50_nanos,
         qoo: rollup.qoo,
         tcp_retransmit_sample: rollup.tcp_retransmit_sample,
+        dscp_remarked_per_second: rollup.dscp_remarked_per_second,
         last_seen_nanos: rollup.last_seen_nanos,
     }
 }
@@ -181,6 +183,7 @@ pub fn rebuild_circuit_live_snapshot() -> Arc<CircuitLiveSnapshot> {
             .circuit_hash
             .map(circuit_current_qoo)
             .unwrap_or_default();
+        let dscp_remarked_per_second = crate::dscp_remarking::remarked_per_second(&circuit_id);
         finalized.insert(
             circuit_id.clone(),
             CircuitLiveRollup {
@@ -197,6 +200,7 @@ pub fn rebuild_circuit_live_snapshot() -> Arc<CircuitLiveSnapshot> {
                     value.tcp_retransmits,
                     value.tcp_packets,
                 ),
+                dscp_remarked_per_second,
                 last_seen_nanos: value.last_seen_nanos.unwrap_or(u64::MAX),
             },
         );
@@ -275,6 +279,7 @@ mod tests {
                 down: TcpRetransmitSample::new(2, 100),
                 up: TcpRetransmitSample::new(1, 50),
             },
+            dscp_remarked_per_second: DownUpOrder::default(),
             last_seen_nanos: 10,
         }
     }
@@ -200,6 +200,9 @@ fn shaped_devices_from_runtime_inputs(
                     device.comment.clone()
                 },
                 sqm_override: circuit.sqm_override.clone(),
+                priority_classes: circuit.priority_classes.clone(),
+                access_policy: circuit.access_policy,
+                qoo_profile: circuit.qoo_profile.clone(),
                 ..ShapedDevice::default()
             });
         }
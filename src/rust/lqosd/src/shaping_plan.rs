@@ -0,0 +1,282 @@
+//! Dry-run planning of shaping changes.
+//!
+//! A plan runs the normal `LibreQoS.py` pipeline against a staged copy of the
+//! inputs and runtime state, with any candidate files swapped in, and asks the
+//! Bakery to plan the resulting batch instead of committing it. Nothing in the
+//! live input directory, state directory or shaping tree is changed.
+//!
+//! `LibreQoS.py --plan` sends its commands as a plan batch (`BakeryPlanStart`,
+//! `BakeryPlanAdd`, `BakeryPlan`), which is collected here rather than in the
+//! Bakery's live batch. A scheduler or integration refresh that starts or
+//! commits a live batch at the same time is therefore unaffected by a plan.
+
+use lqos_bakery::BakeryCommands;
+use lqos_bus::{BakeryPlanReport, BusRequest, BusResponse};
+use lqos_config::Config;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+use tracing::info;
+
+const STAGING_DIRECTORY: &str = "bakery_plan";
+const STATE_SUBDIRECTORIES: [&str; 2] = ["topology", "shaping"];
+const PLAN_TIMEOUT_SECONDS: u64 = 60;
+
+/// Only one plan may use the staging directory at a time.
+static PLAN_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// The plan batch being collected. Starting a new plan batch discards any
+/// unfinished one.
+static PLAN_BATCH: Lazy<Mutex<Option<PlanBatch>>> = Lazy::new(|| Mutex::new(None));
+
+struct PlanBatch {
+    plan_id: u64,
+    commands: Vec<BakeryCommands>,
+}
+
+/// Candidate replacements for the live shaping inputs. `None` keeps the live file.
+#[derive(Clone, Debug, Default)]
+pub struct PlanCandidate {
+    pub network_json: Option<String>,
+    pub shaped_devices_csv: Option<String>,
+    pub operator_overrides: Option<String>,
+}
+
+/// Starts collecting a plan batch.
+///
+/// Side effects: discards any unfinished plan batch.
+pub fn start_plan_batch(plan_id: u64) -> BusResponse {
+    *PLAN_BATCH.lock() = Some(PlanBatch {
+        plan_id,
+        commands: Vec::new(),
+    });
+    BusResponse::Ack
+}
+
+/// Adds Bakery batch requests to the plan batch `plan_id`.
+pub fn add_to_plan_batch(plan_id: u64, commands: &[BusRequest]) -> BusResponse {
+    let mut lock = PLAN_BATCH.lock();
+    let Some(batch) = lock.as_mut().filter(|batch| batch.plan_id == plan_id) else {
+        return BusResponse::Fail(format!("No plan batch {plan_id} has been started"));
+    };
+    for command in commands {
+        let Some(command) = BakeryCommands::from_batch_request(command) else {
+            return BusResponse::Fail(format!("{} can't be part of a plan batch", command.kind()));
+        };
+        batch.commands.push(command);
+    }
+    BusResponse::Ack
+}
+
+/// Asks the Bakery to plan the plan batch `plan_id`, discarding the batch
+/// afterwards.
+///
+/// Side effects: consumes the plan batch. The Bakery's live batch is untouched.
+pub fn plan_pending_batch(plan_id: u64) -> BusResponse {
+    let batch = {
+        let mut lock = PLAN_BATCH.lock();
+        match lock.take() {
+            Some(batch) if batch.plan_id == plan_id => batch.commands,
+            other => {
+                *lock = other;
+                return BusResponse::Fail(format!("No plan batch {plan_id} has been started"));
+            }
+        }
+    };
+    let Some(sender) = lqos_bakery::BAKERY_SENDER.get() else {
+        return BusResponse::Fail("Bakery not initialized".to_string());
+    };
+    let (tx, rx) = mpsc::channel();
+    if let Err(e) = sender.send(BakeryCommands::PlanBatch { batch, reply: tx }) {
+        return BusResponse::Fail(format!("send to bakery failed: {e}"));
+    }
+    match rx.recv_timeout(Duration::from_secs(PLAN_TIMEOUT_SECONDS)) {
+        Ok(report) => BusResponse::BakeryPlan(report),
+        Err(e) => BusResponse::Fail(format!("bakery plan timeout: {e}")),
+    }
+}
+
+/// Plans a shaping change as a bus response.
+///
+/// Side effects: see [`plan_shaping_change`].
+pub fn plan_shaping_change_response(candidate: PlanCandidate) -> BusResponse {
+    match plan_shaping_change(candidate) {
+        Ok(report) => BusResponse::BakeryPlan(report),
+        Err(err) => BusResponse::Fail(err),
+    }
+}
+
+/// Plans what applying `candidate` would do to the shaping tree.
+///
+/// Side effects: rebuilds the staging directory under the state directory and
+/// runs `LibreQoS.py --plan` against it.
+pub fn plan_shaping_change(candidate: PlanCandidate) -> Result<BakeryPlanReport, String> {
+    let _guard = PLAN_LOCK
+        .try_lock()
+        .ok_or_else(|| "Another shaping plan is already running".to_string())?;
+    let config =
+        lqos_config::load_config().map_err(|err| format!("Unable to load configuration: {err}"))?;
+    let staging = config.resolved_state_directory().join(STAGING_DIRECTORY);
+    let staged = stage(&config, &staging, &candidate)
+        .map_err(|err| format!("Unable to stage shaping inputs: {err}"))?;
+
+    if let Err(err) = lqos_topology::publish_effective_topology_for_config(&staged) {
+        return Err(format!("Unable to build the planned topology: {err:#}"));
+    }
+    let plan = lqos_config::plan_libreqos(&staged, &staging).map_err(|err| err.to_string())?;
+    let report = serde_json::from_str::<BakeryPlanReport>(&plan)
+        .map_err(|err| format!("Unable to read the shaping plan: {err}"))?;
+    info!(
+        "Planned shaping change: full_reload={}, live_changes={}, tc_commands={}",
+        report.full_reload, report.live_changes, report.estimated_tc_commands
+    );
+    Ok(report)
+}
+
+/// Copies the live inputs and shaping state into `staging`, writes the
+/// candidate files over them and returns a configuration pointing at the copy.
+fn stage(config: &Config, staging: &Path, candidate: &PlanCandidate) -> std::io::Result<Config> {
+    if staging.exists() {
+        std::fs::remove_dir_all(staging)?;
+    }
+    let inputs = staging.join("inputs");
+    let state = staging.join("state");
+    std::fs::create_dir_all(&inputs)?;
+    std::fs::create_dir_all(&state)?;
+
+    // Top-level JSON and CSV files are the operator inputs, overrides and
+    // integration outputs LibreQoS.py reads from the input directory.
+    for entry in std::fs::read_dir(&config.lqos_directory)?.flatten() {
+        let path = entry.path();
+        let is_input = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| matches!(ext, "json" | "csv"));
+        if is_input && path.is_file() {
+            std::fs::copy(&path, inputs.join(entry.file_name()))?;
+        }
+    }
+    let live_state = config.resolved_state_directory();
+    for subdirectory in STATE_SUBDIRECTORIES {
+        copy_directory(&live_state.join(subdirectory), &state.join(subdirectory))?;
+    }
+
+    for (name, contents) in [
+        ("network.json", &candidate.network_json),
+        ("ShapedDevices.csv", &candidate.shaped_devices_csv),
+        ("lqos_overrides.json", &candidate.operator_overrides),
+    ] {
+        if let Some(contents) = contents {
+            std::fs::write(inputs.join(name), contents)?;
+        }
+    }
+
+    let mut staged = config.clone();
+    staged.lqos_directory = inputs.to_string_lossy().to_string();
+    staged.state_directory = Some(state.to_string_lossy().to_string());
+    staged.input_history.enabled = false;
+    Ok(staged)
+}
+
+fn copy_directory(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            copy_directory(&path, &to.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn plan_batches_only_collect_bakery_batch_commands_for_their_plan() {
+        assert!(matches!(start_plan_batch(11), BusResponse::Ack));
+        let mq_setup = BusRequest::BakeryMqSetup {
+            queues_available: 4,
+            stick_offset: 0,
+        };
+        assert!(matches!(
+            add_to_plan_batch(12, std::slice::from_ref(&mq_setup)),
+            BusResponse::Fail(_)
+        ));
+        assert!(matches!(
+            add_to_plan_batch(11, &[BusRequest::BakeryCommit]),
+            BusResponse::Fail(_)
+        ));
+        assert!(matches!(
+            add_to_plan_batch(11, &[mq_setup]),
+            BusResponse::Ack
+        ));
+        let lock = PLAN_BATCH.lock();
+        let batch = lock.as_ref().expect("plan batch is still open");
+        assert_eq!(batch.plan_id, 11);
+        assert_eq!(batch.commands.len(), 1);
+        assert!(matches!(
+            batch.commands[0],
+            BakeryCommands::MqSetup {
+                queues_available: 4,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn staging_swaps_in_candidates_and_leaves_live_inputs_alone() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock should be after UNIX_EPOCH")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("libreqos-shaping-plan-{stamp}"));
+        let live = root.join("live");
+        let live_state = root.join("state");
+        std::fs::create_dir_all(live_state.join("topology")).expect("live state");
+        std::fs::create_dir_all(&live).expect("live inputs");
+        std::fs::write(live.join("network.json"), "{}").expect("network.json");
+        std::fs::write(live.join("ShapedDevices.csv"), "old").expect("ShapedDevices.csv");
+        std::fs::write(live.join("LibreQoS.py"), "").expect("LibreQoS.py");
+        std::fs::write(live_state.join("topology/canonical.json"), "{}").expect("state");
+
+        let config = Config {
+            lqos_directory: live.to_string_lossy().to_string(),
+            state_directory: Some(live_state.to_string_lossy().to_string()),
+            ..Config::default()
+        };
+        let staging = live_state.join(STAGING_DIRECTORY);
+        let candidate = PlanCandidate {
+            shaped_devices_csv: Some("new".to_string()),
+            ..Default::default()
+        };
+        let staged = stage(&config, &staging, &candidate).expect("staging succeeds");
+
+        let inputs = Path::new(&staged.lqos_directory);
+        assert_eq!(
+            std::fs::read_to_string(inputs.join("ShapedDevices.csv")).expect("staged csv"),
+            "new"
+        );
+        assert!(inputs.join("network.json").exists());
+        assert!(!inputs.join("LibreQoS.py").exists());
+        assert!(
+            staged
+                .resolved_state_directory()
+                .join("topology/canonical.json")
+                .exists()
+        );
+        assert_eq!(
+            std::fs::read_to_string(live.join("ShapedDevices.csv")).expect("live csv"),
+            "old"
+        );
+    }
+}
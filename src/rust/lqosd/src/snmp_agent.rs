@@ -0,0 +1,422 @@
+//! SNMP monitoring: serves the LIBREQOS-MIB to a local master agent over AgentX.
+//!
+//! A refresh thread rebuilds the served view every `refresh_seconds` from the
+//! throughput tracker, CAKE queue statistics, circuit RTT buffers and component
+//! health, keeping its own monotonic counters. A session thread registers with
+//! the master agent (usually net-snmp's `snmpd`), answers its requests from the
+//! latest view, and reconnects whenever the master restarts.
+
+use crate::node_manager::local_api::urgent::urgent_list_data;
+use crate::shaped_devices_tracker::{effective_parent_for_circuit, full_network_map_lite_snapshot};
+use crate::throughput_tracker::{CIRCUIT_RTT_BUFFERS, THROUGHPUT_TRACKER, current_throughput};
+use arc_swap::ArcSwap;
+use fxhash::{FxHashMap, FxHashSet};
+use lqos_bakery::{BakeryMode, bakery_status_snapshot};
+use lqos_bus::BusResponse;
+use lqos_config::Config;
+use lqos_queue_tracker::ALL_QUEUE_SUMMARY;
+use lqos_snmp::{
+    AgentxAddress, AgentxSession, DeltaTracker, LibreQosSnapshot, MibView, Oid, SnmpBakeryMode,
+    SnmpCircuitRow, SnmpHealth, SnmpSiteRow, SnmpTraffic,
+};
+use lqos_utils::hash_to_i64;
+use lqos_utils::rtt::{FlowbeeEffectiveDirection, RttBucket, RttBuffer};
+use lqos_utils::units::DownUpOrder;
+use once_cell::sync::Lazy;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const VERSION_STRING: &str = include_str!("../../../VERSION_STRING");
+
+/// Refresh cycles a host or queue may be missing before its counter history
+/// is dropped.
+const FORGET_AFTER_CYCLES: u64 = 60;
+/// How long a session may be silent before the master agent is pinged.
+const SESSION_IDLE: Duration = Duration::from_secs(30);
+/// Wait between attempts to reach the master agent.
+const RECONNECT_DELAY: Duration = Duration::from_secs(15);
+
+/// The view the AgentX session answers from, replaced whole on every refresh.
+static VIEW: Lazy<ArcSwap<MibView>> = Lazy::new(|| ArcSwap::from_pointee(MibView::new()));
+
+/// Starts the SNMP subagent when `[snmp]` is enabled.
+///
+/// The refresh interval is re-read every cycle and disabling the section
+/// empties the served view; enabling it, or changing the AgentX address or
+/// base OID, needs a restart.
+pub fn start_snmp_agent() {
+    let Ok(config) = lqos_config::load_config() else {
+        return;
+    };
+    if !config.snmp.enabled {
+        return;
+    }
+    let address: AgentxAddress = match config.snmp.agentx_address.parse() {
+        Ok(address) => address,
+        Err(err) => {
+            warn!("SNMP subagent not started: {err}");
+            return;
+        }
+    };
+    let base: Oid = match config.snmp.base_oid.parse() {
+        Ok(base) => base,
+        Err(err) => {
+            warn!("SNMP subagent not started: snmp.base_oid: {err}");
+            return;
+        }
+    };
+
+    let refresh_base = base.clone();
+    if let Err(err) = std::thread::Builder::new()
+        .name("SNMP Refresh".to_string())
+        .spawn(move || refresh_loop(refresh_base))
+    {
+        warn!("Failed to start the SNMP refresh thread: {err:?}");
+        return;
+    }
+    match std::thread::Builder::new()
+        .name("SNMP AgentX".to_string())
+        .spawn(move || session_loop(address, base))
+    {
+        Ok(_) => info!(
+            "SNMP subagent starting for master agent {}",
+            config.snmp.agentx_address
+        ),
+        Err(err) => warn!("Failed to start the SNMP AgentX session: {err:?}"),
+    }
+}
+
+fn refresh_loop(base: Oid) {
+    let mut collector = Collector::new();
+    loop {
+        let refresh_seconds = match lqos_config::load_config() {
+            Ok(config) => {
+                let view = if config.snmp.enabled {
+                    collector.collect(&config).to_view(&base)
+                } else {
+                    MibView::new()
+                };
+                VIEW.store(Arc::new(view));
+                config.snmp.refresh_seconds
+            }
+            Err(err) => {
+                warn!("SNMP refresh unable to load the configuration: {err:?}");
+                30
+            }
+        };
+        std::thread::sleep(Duration::from_secs(refresh_seconds.max(1)));
+    }
+}
+
+fn session_loop(address: AgentxAddress, base: Oid) {
+    let mut failing = false;
+    loop {
+        match AgentxSession::open(&address, &base, "LibreQoS lqosd", SESSION_IDLE) {
+            Ok(mut session) => {
+                info!("SNMP subagent registered {base} with the master agent");
+                failing = false;
+                if let Err(err) = session.serve(|| VIEW.load_full()) {
+                    warn!("SNMP subagent session ended: {err}");
+                }
+            }
+            Err(err) => {
+                if !failing {
+                    warn!("SNMP subagent unable to register with the master agent: {err}");
+                    failing = true;
+                }
+            }
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+/// Counters accumulated since lqosd started.
+#[derive(Default)]
+struct Totals {
+    octets: DownUpOrder<u64>,
+    packets: DownUpOrder<u64>,
+    drops: DownUpOrder<u64>,
+    marks: DownUpOrder<u64>,
+}
+
+impl Totals {
+    fn add_traffic(&mut self, octets: DownUpOrder<u64>, packets: DownUpOrder<u64>) {
+        self.octets.checked_add(octets);
+        self.packets.checked_add(packets);
+    }
+
+    fn add_queue(&mut self, drops: DownUpOrder<u64>, marks: DownUpOrder<u64>) {
+        self.drops.checked_add(drops);
+        self.marks.checked_add(marks);
+    }
+}
+
+struct DirectionalTracker<K> {
+    down: DeltaTracker<K>,
+    up: DeltaTracker<K>,
+}
+
+impl<K: Hash + Eq + Copy> DirectionalTracker<K> {
+    fn new() -> Self {
+        Self {
+            down: DeltaTracker::new(FORGET_AFTER_CYCLES),
+            up: DeltaTracker::new(FORGET_AFTER_CYCLES),
+        }
+    }
+
+    fn delta(&mut self, key: K, raw: &DownUpOrder<u64>) -> DownUpOrder<u64> {
+        DownUpOrder::new(self.down.delta(key, raw.down), self.up.delta(key, raw.up))
+    }
+
+    fn end_cycle(&mut self) {
+        self.down.end_cycle();
+        self.up.end_cycle();
+    }
+}
+
+/// Turns live tracker state into snapshots with monotonic counters.
+struct Collector {
+    octets: DirectionalTracker<lqos_utils::XdpIpAddress>,
+    packets: DirectionalTracker<lqos_utils::XdpIpAddress>,
+    drops: DirectionalTracker<i64>,
+    marks: DirectionalTracker<i64>,
+    global: Totals,
+    circuits: FxHashMap<i64, Totals>,
+    sites: FxHashMap<i64, Totals>,
+}
+
+impl Collector {
+    fn new() -> Self {
+        Self {
+            octets: DirectionalTracker::new(),
+            packets: DirectionalTracker::new(),
+            drops: DirectionalTracker::new(),
+            marks: DirectionalTracker::new(),
+            global: Totals::default(),
+            circuits: FxHashMap::default(),
+            sites: FxHashMap::default(),
+        }
+    }
+
+    fn collect(&mut self, config: &Config) -> LibreQosSnapshot {
+        let tree = full_network_map_lite_snapshot();
+        // Index 0 is the synthetic root, which is the whole shaper.
+        let site_hashes: FxHashMap<usize, i64> = tree
+            .iter()
+            .filter(|(index, _)| *index > 0)
+            .map(|(index, node)| (*index, hash_to_i64(&node.name)))
+            .collect();
+
+        // Site hashes above each circuit, as seen on its hosts this cycle.
+        let mut circuit_sites: FxHashMap<i64, Vec<i64>> = FxHashMap::default();
+        let mut circuit_rates: FxHashMap<i64, DownUpOrder<u64>> = FxHashMap::default();
+        for (ip, entry) in THROUGHPUT_TRACKER.raw_data.lock().iter() {
+            let octets = self.octets.delta(*ip, &entry.bytes);
+            let packets = self.packets.delta(*ip, &entry.packets);
+            self.global.add_traffic(octets, packets);
+            let Some(circuit_hash) = entry.circuit_hash else {
+                continue;
+            };
+            self.circuits
+                .entry(circuit_hash)
+                .or_default()
+                .add_traffic(octets, packets);
+            circuit_rates
+                .entry(circuit_hash)
+                .or_default()
+                .checked_add(DownUpOrder::new(
+                    entry.bytes_per_second.down.saturating_mul(8),
+                    entry.bytes_per_second.up.saturating_mul(8),
+                ));
+            let sites = circuit_sites.entry(circuit_hash).or_insert_with(|| {
+                entry
+                    .network_json_parents
+                    .iter()
+                    .flatten()
+                    .filter_map(|index| site_hashes.get(index).copied())
+                    .collect()
+            });
+            for site in sites.iter() {
+                self.sites
+                    .entry(*site)
+                    .or_default()
+                    .add_traffic(octets, packets);
+            }
+        }
+
+        ALL_QUEUE_SUMMARY.iterate_queue_counters(|circuit_hash, drops, marks| {
+            let drops = self.drops.delta(circuit_hash, drops);
+            let marks = self.marks.delta(circuit_hash, marks);
+            self.global.add_queue(drops, marks);
+            self.circuits
+                .entry(circuit_hash)
+                .or_default()
+                .add_queue(drops, marks);
+            for site in circuit_sites.get(&circuit_hash).into_iter().flatten() {
+                self.sites.entry(*site).or_default().add_queue(drops, marks);
+            }
+        });
+
+        self.octets.end_cycle();
+        self.packets.end_cycle();
+        self.drops.end_cycle();
+        self.marks.end_cycle();
+
+        let circuit_rtts = CIRCUIT_RTT_BUFFERS.load();
+        let mut global_rtt = RttBuffer::default();
+        let mut site_rtts: FxHashMap<i64, RttBuffer> = FxHashMap::default();
+        for (circuit_hash, rtt) in circuit_rtts.iter() {
+            global_rtt.accumulate(rtt);
+            for site in circuit_sites.get(circuit_hash).into_iter().flatten() {
+                site_rtts.entry(*site).or_default().accumulate(rtt);
+            }
+        }
+
+        let catalog = lqos_network_devices::network_devices_catalog();
+        let mut circuits: FxHashMap<i64, SnmpCircuitRow> = FxHashMap::default();
+        for device in catalog.iter_all_devices() {
+            if device.circuit_id.trim().is_empty() {
+                continue;
+            }
+            let row = circuits.entry(device.circuit_hash).or_insert_with(|| {
+                let parent = effective_parent_for_circuit(&device.circuit_id)
+                    .map(|parent| parent.name)
+                    .filter(|name| !name.trim().is_empty())
+                    .unwrap_or_else(|| device.parent_node.clone());
+                SnmpCircuitRow {
+                    hash: device.circuit_hash,
+                    circuit_id: device.circuit_id.clone(),
+                    name: device.circuit_name.clone(),
+                    parent,
+                    ..Default::default()
+                }
+            });
+            row.plan_mbps.down = row
+                .plan_mbps
+                .down
+                .max(device.download_max_mbps.round() as u64);
+            row.plan_mbps.up = row.plan_mbps.up.max(device.upload_max_mbps.round() as u64);
+        }
+        self.circuits.retain(|hash, _| circuits.contains_key(hash));
+        for (hash, row) in circuits.iter_mut() {
+            row.traffic = traffic(
+                self.circuits.get(hash),
+                circuit_rates.get(hash).copied().unwrap_or_default(),
+                circuit_rtts.get(hash),
+            );
+        }
+
+        let names: FxHashMap<usize, &str> = tree
+            .iter()
+            .map(|(index, node)| (*index, node.name.as_str()))
+            .collect();
+        let sites: Vec<SnmpSiteRow> = tree
+            .iter()
+            .filter_map(|(index, node)| {
+                let hash = *site_hashes.get(index)?;
+                Some(SnmpSiteRow {
+                    hash,
+                    name: node.name.clone(),
+                    parent: node
+                        .immediate_parent
+                        .filter(|parent| *parent > 0)
+                        .and_then(|parent| names.get(&parent))
+                        .map(|name| name.to_string())
+                        .unwrap_or_default(),
+                    max_mbps: DownUpOrder::new(
+                        node.max_throughput.0.max(0.0).round() as u64,
+                        node.max_throughput.1.max(0.0).round() as u64,
+                    ),
+                    traffic: traffic(
+                        self.sites.get(&hash),
+                        DownUpOrder::new(
+                            node.current_throughput.0.saturating_mul(8),
+                            node.current_throughput.1.saturating_mul(8),
+                        ),
+                        site_rtts.get(&hash),
+                    ),
+                })
+            })
+            .collect();
+        let live_sites: FxHashSet<i64> = site_hashes.values().copied().collect();
+        self.sites.retain(|hash, _| live_sites.contains(hash));
+
+        let global_rate = match current_throughput() {
+            BusResponse::CurrentThroughput {
+                bits_per_second, ..
+            } => bits_per_second,
+            _ => DownUpOrder::default(),
+        };
+
+        LibreQosSnapshot {
+            version: VERSION_STRING.trim().to_string(),
+            node_name: config.node_name.clone(),
+            node_id: config.node_id.clone(),
+            global: traffic(Some(&self.global), global_rate, Some(&global_rtt)),
+            active_circuits: circuit_rates
+                .values()
+                .filter(|rate| rate.down > 0 || rate.up > 0)
+                .count() as u64,
+            health: health(),
+            sites,
+            circuits: circuits.into_values().collect(),
+        }
+    }
+}
+
+fn traffic(
+    totals: Option<&Totals>,
+    bits_per_second: DownUpOrder<u64>,
+    rtt: Option<&RttBuffer>,
+) -> SnmpTraffic {
+    let totals = totals.map(|totals| (totals.octets, totals.packets, totals.drops, totals.marks));
+    let (octets, packets, drops, marks) = totals.unwrap_or_default();
+    SnmpTraffic {
+        octets,
+        packets,
+        drops,
+        marks,
+        bits_per_second,
+        rtt_p50_micros: rtt_micros(rtt, 50),
+        rtt_p90_micros: rtt_micros(rtt, 90),
+    }
+}
+
+fn rtt_micros(rtt: Option<&RttBuffer>, percentile: u8) -> DownUpOrder<Option<u64>> {
+    let pick = |direction| {
+        rtt.and_then(|rtt| rtt.percentile(RttBucket::Current, direction, percentile))
+            .map(|rtt| rtt.as_nanos() / 1_000)
+    };
+    DownUpOrder::new(
+        pick(FlowbeeEffectiveDirection::Download),
+        pick(FlowbeeEffectiveDirection::Upload),
+    )
+}
+
+fn health() -> SnmpHealth {
+    let bakery = bakery_status_snapshot();
+    let stormguard = lqos_stormguard::runtime_status();
+    let urgent = urgent_list_data().items;
+    SnmpHealth {
+        bakery_mode: match bakery.mode {
+            BakeryMode::Idle => SnmpBakeryMode::Idle,
+            BakeryMode::ApplyingFullReload => SnmpBakeryMode::ApplyingFullReload,
+            BakeryMode::ApplyingLiveChange => SnmpBakeryMode::ApplyingLiveChange,
+        },
+        bakery_reload_required: bakery.reload_required,
+        bakery_passthrough_degraded: bakery.passthrough_degraded,
+        bakery_last_success_unix: bakery.last_success_unix,
+        bakery_last_failure: bakery.last_failure_summary,
+        stormguard_enabled: stormguard.configured_enabled,
+        stormguard_mode: stormguard.mode,
+        stormguard_phase: stormguard.phase,
+        stormguard_last_error: stormguard.last_error,
+        urgent_issues: urgent.len() as u64,
+        urgent_errors: urgent
+            .iter()
+            .filter(|item| item.severity == "Error")
+            .count() as u64,
+    }
+}
@@ -0,0 +1,245 @@
+//! 24-hour per-circuit history for the portal.
+//!
+//! While the portal is enabled, every active circuit is sampled every few
+//! seconds into 15-minute buckets, keeping the last day. Alongside the
+//! averages each bucket keeps the lowest RTT seen (the idle baseline) and
+//! the average RTT while the circuit was busy, from which the bufferbloat
+//! grade is derived.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+pub(super) const BUCKET_SECS: u64 = 15 * 60;
+const HISTORY_SECS: u64 = 24 * 60 * 60;
+/// Share of the plan, in either direction, above which a circuit counts as
+/// busy for the loaded-latency measurement.
+const BUSY_FRACTION: f32 = 0.5;
+
+/// One reading of a circuit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct Sample {
+    pub down_mbps: f32,
+    pub up_mbps: f32,
+    pub plan_down_mbps: f32,
+    pub plan_up_mbps: f32,
+    pub rtt_ms: Option<f32>,
+    pub qoo: Option<f32>,
+}
+
+impl Sample {
+    fn busy(&self) -> bool {
+        let over = |rate: f32, plan: f32| plan > 0.0 && rate >= plan * BUSY_FRACTION;
+        over(self.down_mbps, self.plan_down_mbps) || over(self.up_mbps, self.plan_up_mbps)
+    }
+}
+
+/// A finished (or the current, partial) 15-minute bucket.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub(super) struct HistoryPoint {
+    pub start_unix: u64,
+    pub down_mbps: f32,
+    pub up_mbps: f32,
+    pub rtt_ms: Option<f32>,
+    pub qoo: Option<f32>,
+    #[serde(skip)]
+    pub idle_rtt_ms: Option<f32>,
+    #[serde(skip)]
+    pub loaded_rtt_ms: Option<f32>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Accumulator {
+    start_unix: u64,
+    samples: u32,
+    down_mbps: f64,
+    up_mbps: f64,
+    rtt_ms: f64,
+    rtt_samples: u32,
+    qoo: f64,
+    qoo_samples: u32,
+    idle_rtt_ms: Option<f32>,
+    loaded_rtt_ms: f64,
+    loaded_samples: u32,
+}
+
+fn mean(sum: f64, count: u32) -> Option<f32> {
+    (count > 0).then(|| (sum / count as f64) as f32)
+}
+
+impl Accumulator {
+    fn add(&mut self, sample: &Sample) {
+        self.samples += 1;
+        self.down_mbps += sample.down_mbps as f64;
+        self.up_mbps += sample.up_mbps as f64;
+        if let Some(rtt) = sample.rtt_ms {
+            self.rtt_ms += rtt as f64;
+            self.rtt_samples += 1;
+            self.idle_rtt_ms = Some(self.idle_rtt_ms.map_or(rtt, |idle| idle.min(rtt)));
+            if sample.busy() {
+                self.loaded_rtt_ms += rtt as f64;
+                self.loaded_samples += 1;
+            }
+        }
+        if let Some(qoo) = sample.qoo {
+            self.qoo += qoo as f64;
+            self.qoo_samples += 1;
+        }
+    }
+
+    fn point(&self) -> HistoryPoint {
+        HistoryPoint {
+            start_unix: self.start_unix,
+            down_mbps: mean(self.down_mbps, self.samples).unwrap_or(0.0),
+            up_mbps: mean(self.up_mbps, self.samples).unwrap_or(0.0),
+            rtt_ms: mean(self.rtt_ms, self.rtt_samples),
+            qoo: mean(self.qoo, self.qoo_samples),
+            idle_rtt_ms: self.idle_rtt_ms,
+            loaded_rtt_ms: mean(self.loaded_rtt_ms, self.loaded_samples),
+        }
+    }
+}
+
+/// The last day of one circuit.
+#[derive(Clone, Debug, Default)]
+pub(super) struct CircuitHistory {
+    closed: VecDeque<HistoryPoint>,
+    open: Accumulator,
+}
+
+impl CircuitHistory {
+    pub(super) fn record(&mut self, now: u64, sample: &Sample) {
+        let bucket_start = now - now % BUCKET_SECS;
+        if self.open.samples > 0 && self.open.start_unix != bucket_start {
+            self.closed.push_back(self.open.point());
+            self.open = Accumulator::default();
+        }
+        if self.open.samples == 0 {
+            self.open.start_unix = bucket_start;
+        }
+        self.open.add(sample);
+        self.expire(now);
+    }
+
+    fn expire(&mut self, now: u64) {
+        let oldest = now.saturating_sub(HISTORY_SECS);
+        while self
+            .closed
+            .front()
+            .is_some_and(|point| point.start_unix + BUCKET_SECS <= oldest)
+        {
+            self.closed.pop_front();
+        }
+    }
+
+    /// True once nothing has been recorded for a day.
+    pub(super) fn is_stale(&self, now: u64) -> bool {
+        self.open.start_unix + BUCKET_SECS + HISTORY_SECS <= now
+    }
+
+    /// The last day, oldest first, ending with the current partial bucket.
+    pub(super) fn points(&self, now: u64) -> Vec<HistoryPoint> {
+        let oldest = now.saturating_sub(HISTORY_SECS);
+        self.closed
+            .iter()
+            .copied()
+            .chain((self.open.samples > 0).then(|| self.open.point()))
+            .filter(|point| point.start_unix + BUCKET_SECS > oldest)
+            .collect()
+    }
+}
+
+/// A bufferbloat grade: how much latency rises when the line is busy.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub(super) struct BufferbloatGrade {
+    pub grade: &'static str,
+    pub latency_increase_ms: f32,
+}
+
+/// Grades the latency increase under load over the given history, on the
+/// scale popularised by public bufferbloat tests. `None` until the circuit
+/// has been busy at least once.
+pub(super) fn bufferbloat_grade(points: &[HistoryPoint]) -> Option<BufferbloatGrade> {
+    let idle = points
+        .iter()
+        .filter_map(|point| point.idle_rtt_ms)
+        .min_by(f32::total_cmp)?;
+    let loaded: Vec<f32> = points
+        .iter()
+        .filter_map(|point| point.loaded_rtt_ms)
+        .collect();
+    if loaded.is_empty() {
+        return None;
+    }
+    let loaded = loaded.iter().sum::<f32>() / loaded.len() as f32;
+    let increase = (loaded - idle).max(0.0);
+    let grade = match increase {
+        x if x < 5.0 => "A+",
+        x if x < 30.0 => "A",
+        x if x < 60.0 => "B",
+        x if x < 200.0 => "C",
+        x if x < 400.0 => "D",
+        _ => "F",
+    };
+    Some(BufferbloatGrade {
+        grade,
+        latency_increase_ms: increase,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(down_mbps: f32, rtt_ms: f32) -> Sample {
+        Sample {
+            down_mbps,
+            up_mbps: 1.0,
+            plan_down_mbps: 100.0,
+            plan_up_mbps: 20.0,
+            rtt_ms: Some(rtt_ms),
+            qoo: Some(90.0),
+        }
+    }
+
+    #[test]
+    fn buckets_samples_and_keeps_one_day() {
+        let mut history = CircuitHistory::default();
+        let start = 1_000 * BUCKET_SECS;
+        history.record(start, &sample(10.0, 20.0));
+        history.record(start + 60, &sample(30.0, 40.0));
+        history.record(start + BUCKET_SECS, &sample(5.0, 10.0));
+
+        let points = history.points(start + BUCKET_SECS);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].start_unix, start);
+        assert_eq!(points[0].down_mbps, 20.0);
+        assert_eq!(points[0].rtt_ms, Some(30.0));
+        assert_eq!(points[0].idle_rtt_ms, Some(20.0));
+        assert_eq!(points[1].down_mbps, 5.0);
+
+        let later = start + HISTORY_SECS + 2 * BUCKET_SECS;
+        history.record(later, &sample(1.0, 10.0));
+        let points = history.points(later);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].start_unix, later);
+        assert!(!history.is_stale(later));
+        assert!(history.is_stale(later + HISTORY_SECS + BUCKET_SECS));
+    }
+
+    #[test]
+    fn grades_latency_under_load() {
+        let mut history = CircuitHistory::default();
+        let start = 1_000 * BUCKET_SECS;
+        history.record(start, &sample(1.0, 12.0));
+        assert_eq!(bufferbloat_grade(&history.points(start)), None);
+
+        history.record(start + 10, &sample(80.0, 52.0));
+        let grade = bufferbloat_grade(&history.points(start + 10)).expect("busy period");
+        assert_eq!(grade.grade, "B");
+        assert_eq!(grade.latency_increase_ms, 40.0);
+
+        history.record(start + 20, &sample(90.0, 14.0));
+        let grade = bufferbloat_grade(&history.points(start + 20)).expect("busy period");
+        assert_eq!(grade.grade, "A");
+    }
+}
@@ -0,0 +1,240 @@
+//! Read-only subscriber status portal.
+//!
+//! With `[subscriber_portal]` enabled, lqosd runs a second, minimal web
+//! listener that answers "is it your Wi-Fi or our network?" for a single
+//! circuit: throughput against plan, latency, QoO, a bufferbloat grade and
+//! the last 24 hours. Access is by a signed link naming one circuit, issued
+//! over the bus or the key-authenticated `/portal-api`; the listener serves
+//! nothing else, so it can be exposed to subscribers or framed by a CRM.
+
+mod history;
+mod server;
+mod token;
+
+use crate::shaped_devices_tracker::circuit_live::{CircuitLiveRollup, fresh_circuit_live_snapshot};
+use fxhash::FxHashMap;
+use history::{
+    BUCKET_SECS, BufferbloatGrade, CircuitHistory, HistoryPoint, Sample, bufferbloat_grade,
+};
+use lqos_bus::{BusResponse, SubscriberPortalLink};
+use lqos_config::SubscriberPortalConfig;
+use lqos_utils::units::DownUpOrder;
+use lqos_utils::unix_time::unix_now;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The last day of every recently active circuit, by circuit ID.
+static HISTORY: Lazy<Mutex<FxHashMap<String, CircuitHistory>>> =
+    Lazy::new(|| Mutex::new(FxHashMap::default()));
+
+/// Everything the portal page shows for one circuit.
+#[derive(Debug, Serialize)]
+struct PortalStatus {
+    circuit_name: String,
+    plan_mbps: DownUpOrder<f32>,
+    current_mbps: DownUpOrder<f32>,
+    rtt_ms: Option<f32>,
+    qoo: Option<f32>,
+    bufferbloat: Option<BufferbloatGrade>,
+    bucket_seconds: u64,
+    history: Vec<HistoryPoint>,
+}
+
+fn bytes_to_mbps(bytes_per_second: u64) -> f32 {
+    (bytes_per_second as f64 * 8.0 / 1_000_000.0) as f32
+}
+
+/// The slower direction's median RTT, in milliseconds.
+fn worst_rtt_ms(rtt: DownUpOrder<Option<u64>>) -> Option<f32> {
+    rtt.down
+        .into_iter()
+        .chain(rtt.up)
+        .max()
+        .map(|nanos| nanos as f32 / 1_000_000.0)
+}
+
+/// The worse direction's QoO score.
+fn worst_qoo(qoo: DownUpOrder<Option<f32>>) -> Option<f32> {
+    qoo.down.into_iter().chain(qoo.up).min_by(f32::total_cmp)
+}
+
+fn sample_from(rollup: &CircuitLiveRollup) -> Sample {
+    Sample {
+        down_mbps: bytes_to_mbps(rollup.bytes_per_second.down),
+        up_mbps: bytes_to_mbps(rollup.bytes_per_second.up),
+        plan_down_mbps: rollup.plan_mbps.down,
+        plan_up_mbps: rollup.plan_mbps.up,
+        rtt_ms: worst_rtt_ms(rollup.rtt_current_p50_nanos),
+        qoo: worst_qoo(rollup.qoo),
+    }
+}
+
+/// Starts the portal listener and history sampler when the portal is
+/// enabled. Enabling the portal later needs a restart; disabling it stops
+/// answering and recording at once.
+pub(crate) async fn start_subscriber_portal(config: &SubscriberPortalConfig) -> anyhow::Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let listener = TcpListener::bind(config.listen.trim()).await?;
+    if let Err(err) = std::thread::Builder::new()
+        .name("Subscriber Portal".to_string())
+        .spawn(sample_loop)
+    {
+        warn!("Failed to start the subscriber portal sampler: {err:?}");
+    }
+    info!("Subscriber portal listening on: [{}]", config.listen.trim());
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, server::router()).await {
+            error!("Subscriber portal stopped: {err:?}");
+        }
+    });
+    Ok(())
+}
+
+fn portal_enabled() -> bool {
+    lqos_config::load_config().is_ok_and(|config| config.subscriber_portal.enabled)
+}
+
+fn sample_loop() {
+    loop {
+        std::thread::sleep(SAMPLE_INTERVAL);
+        if !portal_enabled() {
+            HISTORY.lock().clear();
+            continue;
+        }
+        let now = unix_now().unwrap_or(0);
+        let snapshot = fresh_circuit_live_snapshot();
+        let mut history = HISTORY.lock();
+        for (circuit_id, rollup) in snapshot.by_circuit_id.iter() {
+            history
+                .entry(circuit_id.clone())
+                .or_default()
+                .record(now, &sample_from(rollup));
+        }
+        history.retain(|_, circuit| !circuit.is_stale(now));
+    }
+}
+
+/// The portal view of a circuit, or `None` if it is no longer shaped.
+fn circuit_status(circuit_id: &str, now: u64) -> Option<PortalStatus> {
+    let shaped = lqos_network_devices::shaped_devices_snapshot();
+    let mut devices = shaped
+        .devices
+        .iter()
+        .filter(|device| device.circuit_id == circuit_id)
+        .peekable();
+    let circuit_name = devices.peek()?.circuit_name.clone();
+    let plan_mbps = devices.fold(DownUpOrder::default(), |plan: DownUpOrder<f32>, device| {
+        DownUpOrder {
+            down: plan.down.max(device.download_max_mbps),
+            up: plan.up.max(device.upload_max_mbps),
+        }
+    });
+
+    let live = fresh_circuit_live_snapshot()
+        .by_circuit_id
+        .get(circuit_id)
+        .map(sample_from)
+        .unwrap_or_default();
+    let history = HISTORY
+        .lock()
+        .get(circuit_id)
+        .map(|circuit| circuit.points(now))
+        .unwrap_or_default();
+
+    Some(PortalStatus {
+        circuit_name,
+        plan_mbps,
+        current_mbps: DownUpOrder {
+            down: live.down_mbps,
+            up: live.up_mbps,
+        },
+        rtt_ms: live.rtt_ms,
+        qoo: live.qoo,
+        bufferbloat: bufferbloat_grade(&history),
+        bucket_seconds: BUCKET_SECS,
+        history,
+    })
+}
+
+/// Issues a signed portal link for a circuit.
+pub(crate) fn issue_link(
+    circuit_id: &str,
+    valid_hours: Option<u32>,
+) -> Result<SubscriberPortalLink, String> {
+    let config = lqos_config::load_config()
+        .map_err(|err| format!("Unable to load the configuration: {err:?}"))?;
+    let portal = &config.subscriber_portal;
+    if !portal.enabled {
+        return Err("The subscriber portal is disabled in the configuration".to_string());
+    }
+    let circuit_id = circuit_id.trim();
+    if circuit_id.is_empty() {
+        return Err("A circuit ID is required".to_string());
+    }
+    let shaped = lqos_network_devices::shaped_devices_snapshot();
+    if !shaped
+        .devices
+        .iter()
+        .any(|device| device.circuit_id == circuit_id)
+    {
+        return Err(format!("Circuit {circuit_id} was not found"));
+    }
+
+    let hours = portal.link_hours(valid_hours);
+    let now = unix_now().unwrap_or(0);
+    let expires_unix = now + u64::from(hours) * 3_600;
+    let key = token::portal_key()?;
+    let token = token::sign(&key, circuit_id, now, expires_unix);
+    info!("Issued a subscriber portal link for circuit {circuit_id}, valid for {hours} hours");
+    Ok(SubscriberPortalLink {
+        circuit_id: circuit_id.to_string(),
+        url: portal.link_for(&token),
+        token,
+        expires_unix,
+    })
+}
+
+/// Bus wrapper for [`issue_link`].
+pub(crate) fn issue_link_response(circuit_id: &str, valid_hours: Option<u32>) -> BusResponse {
+    match issue_link(circuit_id, valid_hours) {
+        Ok(link) => BusResponse::SubscriberPortalLink(link),
+        Err(err) => BusResponse::Fail(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_use_the_worse_direction() {
+        let rtt = DownUpOrder {
+            down: Some(12_000_000),
+            up: Some(30_000_000),
+        };
+        assert_eq!(worst_rtt_ms(rtt), Some(30.0));
+        assert_eq!(
+            worst_rtt_ms(DownUpOrder {
+                down: None,
+                up: Some(5_000_000)
+            }),
+            Some(5.0)
+        );
+        assert_eq!(worst_rtt_ms(DownUpOrder::default()), None);
+
+        let qoo = DownUpOrder {
+            down: Some(92.0),
+            up: Some(71.5),
+        };
+        assert_eq!(worst_qoo(qoo), Some(71.5));
+        assert_eq!(bytes_to_mbps(12_500_000), 100.0);
+    }
+}
@@ -0,0 +1,175 @@
+"use strict";
+
+// Subscriber status page. The link carries its token in the fragment
+// (#t=...), which browsers never send to the server or in Referer headers.
+(function () {
+    const REFRESH_MS = 10000;
+    const DAY_SECS = 24 * 60 * 60;
+    const SVG_NS = "http://www.w3.org/2000/svg";
+    const WIDTH = 600;
+    const HEIGHT = 160;
+    const PAD = { left: 36, right: 8, top: 8, bottom: 18 };
+
+    const token = new URLSearchParams(window.location.hash.slice(1)).get("t");
+    let timer = null;
+
+    function byId(id) {
+        return document.getElementById(id);
+    }
+
+    function setText(id, text) {
+        byId(id).textContent = text;
+    }
+
+    function showMessage(text) {
+        setText("message", text);
+        byId("message").hidden = false;
+    }
+
+    function formatMbps(mbps) {
+        if (mbps >= 1000) {
+            return (mbps / 1000).toFixed(2) + " Gbps";
+        }
+        return (mbps >= 10 ? mbps.toFixed(0) : mbps.toFixed(1)) + " Mbps";
+    }
+
+    function svgElement(name, attributes) {
+        const element = document.createElementNS(SVG_NS, name);
+        for (const [key, value] of Object.entries(attributes)) {
+            element.setAttribute(key, String(value));
+        }
+        return element;
+    }
+
+    // Draws one chart over the last day. Each series is split wherever
+    // buckets are missing, so quiet periods show as gaps.
+    function drawChart(svg, points, series, bucketSeconds, now, floor) {
+        while (svg.firstChild) {
+            svg.removeChild(svg.firstChild);
+        }
+        const start = now - DAY_SECS;
+        let max = floor;
+        for (const s of series) {
+            for (const p of points) {
+                const v = s.value(p);
+                if (v !== null && v !== undefined && v > max) {
+                    max = v;
+                }
+            }
+        }
+        max = max > 0 ? max * 1.1 : 1;
+        const plotWidth = WIDTH - PAD.left - PAD.right;
+        const plotHeight = HEIGHT - PAD.top - PAD.bottom;
+        const x = (t) => PAD.left + ((t - start) / DAY_SECS) * plotWidth;
+        const y = (v) => PAD.top + plotHeight - (v / max) * plotHeight;
+
+        for (const fraction of [0, 0.5, 1]) {
+            const value = max * fraction;
+            svg.appendChild(svgElement("line", {
+                class: "grid", x1: PAD.left, x2: WIDTH - PAD.right, y1: y(value), y2: y(value),
+            }));
+            const label = svgElement("text", { x: 2, y: y(value) + 3 });
+            label.textContent = value >= 10 ? value.toFixed(0) : value.toFixed(1);
+            svg.appendChild(label);
+        }
+        for (const [t, text, anchor] of [[start, "24h ago", "start"], [now, "now", "end"]]) {
+            const label = svgElement("text", { x: x(t), y: HEIGHT - 4, "text-anchor": anchor });
+            label.textContent = text;
+            svg.appendChild(label);
+        }
+
+        for (const s of series) {
+            let segment = [];
+            let previous = null;
+            const flush = () => {
+                if (segment.length > 0) {
+                    svg.appendChild(svgElement("polyline", { class: s.cls, points: segment.join(" ") }));
+                }
+                segment = [];
+            };
+            for (const p of points) {
+                const v = s.value(p);
+                if (v === null || v === undefined || (previous !== null && p.start_unix - previous > bucketSeconds)) {
+                    flush();
+                }
+                if (v !== null && v !== undefined) {
+                    const mid = Math.min(p.start_unix + bucketSeconds / 2, now);
+                    segment.push(x(mid).toFixed(1) + "," + y(v).toFixed(1));
+                }
+                previous = p.start_unix;
+            }
+            flush();
+        }
+    }
+
+    function render(status) {
+        const now = Date.now() / 1000;
+        setText("circuit", status.circuit_name);
+        setText("down", formatMbps(status.current_mbps.down));
+        setText("up", formatMbps(status.current_mbps.up));
+        setText("down-plan", "of " + formatMbps(status.plan_mbps.down) + " plan");
+        setText("up-plan", "of " + formatMbps(status.plan_mbps.up) + " plan");
+        setText("rtt", status.rtt_ms === null ? "idle" : status.rtt_ms.toFixed(0) + " ms");
+        setText("qoo", status.qoo === null ? "idle" : status.qoo.toFixed(0));
+        if (status.bufferbloat) {
+            setText("grade", status.bufferbloat.grade);
+            setText("grade-detail",
+                "+" + status.bufferbloat.latency_increase_ms.toFixed(0) + " ms latency when busy");
+        } else {
+            setText("grade", "–");
+            setText("grade-detail", "Graded once the connection has been busy");
+        }
+
+        const plan = status.plan_mbps;
+        drawChart(byId("throughput-chart"), status.history, [
+            { cls: "plan", value: () => plan.down },
+            { cls: "down", value: (p) => p.down_mbps },
+            { cls: "up", value: (p) => p.up_mbps },
+        ], status.bucket_seconds, now, plan.down);
+        drawChart(byId("latency-chart"), status.history, [
+            { cls: "rtt", value: (p) => p.rtt_ms },
+        ], status.bucket_seconds, now, 10);
+
+        setText("updated", "Updated " + new Date().toLocaleTimeString());
+        byId("message").hidden = true;
+        byId("status").hidden = false;
+    }
+
+    function stop(text) {
+        if (timer !== null) {
+            clearInterval(timer);
+            timer = null;
+        }
+        byId("status").hidden = true;
+        showMessage(text);
+    }
+
+    async function refresh() {
+        let response;
+        try {
+            response = await fetch("api/status", {
+                headers: { Authorization: "Bearer " + token },
+                cache: "no-store",
+            });
+        } catch (e) {
+            showMessage("Unable to reach the status service. Retrying…");
+            return;
+        }
+        if (response.status === 401 || response.status === 404) {
+            stop(await response.text());
+            return;
+        }
+        if (!response.ok) {
+            showMessage("The status service is unavailable. Retrying…");
+            return;
+        }
+        render(await response.json());
+    }
+
+    if (!token) {
+        stop("This page needs the status link from your provider.");
+        return;
+    }
+    refresh();
+    timer = setInterval(refresh, REFRESH_MS);
+})();
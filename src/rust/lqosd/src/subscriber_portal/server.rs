@@ -0,0 +1,162 @@
+//! The portal listener: one page, its assets and one status endpoint.
+
+use super::{circuit_status, portal_enabled, token};
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use lqos_utils::unix_time::unix_now;
+use tokio::task::spawn_blocking;
+use tracing::warn;
+
+const PAGE: &str = include_str!("portal.html");
+const SCRIPT: &str = include_str!("portal.js");
+const STYLE: &str = include_str!("portal.css");
+
+pub(super) fn router() -> Router {
+    Router::new()
+        .route("/", get(page))
+        .route("/portal.js", get(script))
+        .route("/portal.css", get(style))
+        .route("/api/status", get(status))
+        .fallback(not_found)
+        .layer(axum::middleware::from_fn(security_headers))
+}
+
+/// The Content-Security-Policy for the portal: nothing but its own assets,
+/// framed only by the configured origins.
+fn content_security_policy(frame_ancestors: &[String]) -> String {
+    let ancestors = if frame_ancestors.is_empty() {
+        "'none'".to_string()
+    } else {
+        frame_ancestors
+            .iter()
+            .map(|origin| origin.trim())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    format!(
+        "default-src 'none'; script-src 'self'; style-src 'self'; img-src 'self' data:; \
+         connect-src 'self'; base-uri 'none'; form-action 'none'; frame-ancestors {ancestors}"
+    )
+}
+
+async fn security_headers(req: Request, next: Next) -> Response {
+    let frame_ancestors = lqos_config::load_config()
+        .map(|config| config.subscriber_portal.frame_ancestors.clone())
+        .unwrap_or_default();
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    if let Ok(csp) = HeaderValue::from_str(&content_security_policy(&frame_ancestors)) {
+        headers.insert(header::CONTENT_SECURITY_POLICY, csp);
+    }
+    headers.insert(
+        header::REFERRER_POLICY,
+        HeaderValue::from_static("no-referrer"),
+    );
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    headers.insert(
+        HeaderName::from_static("x-robots-tag"),
+        HeaderValue::from_static("noindex"),
+    );
+    response
+}
+
+async fn page() -> Html<&'static str> {
+    Html(PAGE)
+}
+
+async fn script() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+        SCRIPT,
+    )
+}
+
+async fn style() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/css; charset=utf-8")], STYLE)
+}
+
+async fn not_found() -> Response {
+    StatusCode::NOT_FOUND.into_response()
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+async fn status(headers: HeaderMap) -> Response {
+    if !portal_enabled() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The status portal is disabled",
+        )
+            .into_response();
+    }
+    let now = unix_now().unwrap_or(0);
+    let key = match token::portal_key() {
+        Ok(key) => key,
+        Err(err) => {
+            warn!("Subscriber portal key unavailable: {err}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let Some(circuit_id) = bearer_token(&headers).and_then(|t| token::verify(&key, t, now)) else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "This status link is not valid or has expired",
+        )
+            .into_response();
+    };
+    match spawn_blocking(move || circuit_status(&circuit_id, now)).await {
+        Ok(Some(status)) => Json(status).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            "This service is no longer active on the network",
+        )
+            .into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_limits_framing_to_configured_origins() {
+        let csp = content_security_policy(&[]);
+        assert!(csp.contains("frame-ancestors 'none'"));
+        assert!(csp.contains("default-src 'none'"));
+        assert!(csp.contains("connect-src 'self'"));
+
+        let csp = content_security_policy(&[
+            "https://crm.example.net".to_string(),
+            " https://my.example.net ".to_string(),
+        ]);
+        assert!(csp.ends_with("frame-ancestors https://crm.example.net https://my.example.net"));
+    }
+
+    #[test]
+    fn reads_only_bearer_authorization() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer p1.abc.def"),
+        );
+        assert_eq!(bearer_token(&headers), Some("p1.abc.def"));
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+        assert_eq!(bearer_token(&headers), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! Signed, circuit-scoped portal tokens.
+//!
+//! A token is `p1.<claims>.<signature>`: base64url JSON naming one circuit
+//! and an expiry, signed with HMAC-SHA256 under a key kept in
+//! `subscriber_portal.key` in the state directory. Nothing is stored per
+//! link. The key file is read on every use, so deleting it revokes every
+//! link issued so far without a restart; a new key is created in its place.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const TOKEN_VERSION: &str = "p1";
+const KEY_FILE_NAME: &str = "subscriber_portal.key";
+
+/// Held while reading or creating the key file.
+static KEY_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct PortalClaims {
+    /// Circuit ID.
+    c: String,
+    iat: u64,
+    exp: u64,
+}
+
+fn key_path() -> Result<PathBuf, String> {
+    let config = lqos_config::load_config()
+        .map_err(|_| "Unable to load /etc/lqos.conf while locating the portal key".to_string())?;
+    Ok(config.resolved_state_directory().join(KEY_FILE_NAME))
+}
+
+/// The portal signing key, created if the key file doesn't exist.
+///
+/// The file is read on every call, so a deleted or replaced key takes effect
+/// on the next request.
+pub(super) fn portal_key() -> Result<Vec<u8>, String> {
+    key_at(&key_path()?)
+}
+
+fn key_at(path: &Path) -> Result<Vec<u8>, String> {
+    let _lock = KEY_LOCK.lock();
+    match std::fs::read(path) {
+        Ok(key) if key.is_empty() => Err(format!("Portal key file {path:?} is empty")),
+        Ok(key) => Ok(key),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => create_key(path),
+        Err(e) => Err(format!("Unable to read {path:?}: {e}")),
+    }
+}
+
+/// Writes a new random key readable only by `lqosd`'s user.
+fn create_key(path: &Path) -> Result<Vec<u8>, String> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .map_err(|e| format!("Unable to create {directory:?}: {e}"))?;
+    }
+    let mut new_key = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut new_key);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Unable to create {path:?}: {e}"))?;
+
+    file.write_all(&new_key)
+        .map_err(|e| format!("Unable to write {path:?}: {e}"))?;
+    Ok(new_key)
+}
+
+/// Signs a token for `circuit_id`, valid from `now` until `expires_unix`.
+pub(super) fn sign(key: &[u8], circuit_id: &str, now: u64, expires_unix: u64) -> String {
+    let claims = PortalClaims {
+        c: circuit_id.to_string(),
+        iat: now,
+        exp: expires_unix,
+    };
+    let payload_b64 = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap_or_default());
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(payload_b64.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{TOKEN_VERSION}.{payload_b64}.{signature}")
+}
+
+/// The circuit a token grants access to, if it is genuine and unexpired.
+pub(super) fn verify(key: &[u8], token: &str, now: u64) -> Option<String> {
+    let (version, remainder) = token.trim().split_once('.')?;
+    if version != TOKEN_VERSION {
+        return None;
+    }
+    let (payload_b64, signature_b64) = remainder.rsplit_once('.')?;
+
+    let mut mac = HmacSha256::new_from_slice(key).ok()?;
+    mac.update(payload_b64.as_bytes());
+    let signature = URL_SAFE_NO_PAD.decode(signature_b64).ok()?;
+    mac.verify_slice(&signature).ok()?;
+
+    let payload = URL_SAFE_NO_PAD.decode(payload_b64).ok()?;
+    let claims: PortalClaims = serde_json::from_slice(&payload).ok()?;
+    if claims.exp <= now || claims.c.is_empty() {
+        return None;
+    }
+    Some(claims.c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn verifies_its_own_tokens_until_they_expire() {
+        let token = sign(KEY, "circuit-1", 1_000, 2_000);
+        assert_eq!(verify(KEY, &token, 1_500).as_deref(), Some("circuit-1"));
+        assert_eq!(verify(KEY, &token, 2_000), None);
+    }
+
+    #[test]
+    fn rejects_forged_or_foreign_tokens() {
+        let token = sign(KEY, "circuit-1", 1_000, 2_000);
+        assert_eq!(verify(b"another key", &token, 1_500), None);
+
+        // Swapping in another circuit's claims breaks the signature.
+        let other = sign(KEY, "circuit-2", 1_000, 2_000);
+        let (_, signature) = token.rsplit_once('.').expect("signed");
+        let (forged_payload, _) = other.rsplit_once('.').expect("signed");
+        assert_eq!(
+            verify(KEY, &format!("{forged_payload}.{signature}"), 1_500),
+            None
+        );
+
+        assert_eq!(verify(KEY, &token.replacen("p1", "v1", 1), 1_500), None);
+        assert_eq!(verify(KEY, "p1.garbage", 1_500), None);
+        assert_eq!(verify(KEY, "", 1_500), None);
+    }
+
+    #[test]
+    fn deleting_the_key_file_revokes_links() {
+        let directory =
+            std::env::temp_dir().join(format!("libreqos-portal-key-{}", std::process::id()));
+        let path = directory.join(KEY_FILE_NAME);
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let first = key_at(&path).expect("key is created");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)
+                .expect("key file")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(key_at(&path).expect("key is reused"), first);
+        let token = sign(&first, "circuit-1", 1_000, 2_000);
+
+        std::fs::remove_file(&path).expect("key file is removed");
+        let second = key_at(&path).expect("key is replaced");
+        assert_ne!(second, first);
+        assert_eq!(verify(&second, &token, 1_500), None);
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,304 @@
+//! Maps host names, ASNs and IP prefixes to named applications.
+//!
+//! A default catalog is compiled in. Operators can extend or override it by
+//! placing an `app_catalog.json` file (same format) in the LibreQoS
+//! directory; entries with the same name replace the built-in ones. The file
+//! is re-read when its modification time changes.
+
+use allocative_derive::Allocative;
+use fxhash::FxHashMap;
+use ip_network::IpNetwork;
+use ip_network_table::IpNetworkTable;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{info, warn};
+
+const DEFAULT_CATALOG: &str = include_str!("app_catalog.json");
+const CATALOG_FILENAME: &str = "app_catalog.json";
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Interned application identifier. Ids are never reused, so flows keep a
+/// valid label across catalog reloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Allocative)]
+pub struct AppId(u16);
+
+#[derive(Debug, Clone)]
+struct AppInfo {
+    name: String,
+    category: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CatalogEntry {
+    name: String,
+    #[serde(default)]
+    category: String,
+    #[serde(default)]
+    domains: Vec<String>,
+    #[serde(default)]
+    asns: Vec<u32>,
+    #[serde(default)]
+    prefixes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogFile {
+    applications: Vec<CatalogEntry>,
+}
+
+struct AppCatalog {
+    domains: FxHashMap<String, AppId>,
+    asns: FxHashMap<u32, AppId>,
+    prefixes: IpNetworkTable<AppId>,
+}
+
+impl AppCatalog {
+    fn new() -> Self {
+        Self {
+            domains: FxHashMap::default(),
+            asns: FxHashMap::default(),
+            prefixes: IpNetworkTable::new(),
+        }
+    }
+}
+
+struct CatalogState {
+    catalog: AppCatalog,
+    loaded_mtime: Option<SystemTime>,
+    last_check: Option<Instant>,
+}
+
+static APPS: Lazy<RwLock<Vec<AppInfo>>> = Lazy::new(|| RwLock::new(Vec::new()));
+static CATALOG: Lazy<Mutex<CatalogState>> = Lazy::new(|| {
+    Mutex::new(CatalogState {
+        catalog: AppCatalog::new(),
+        loaded_mtime: None,
+        last_check: None,
+    })
+});
+
+/// Returns the interned id for an application, adding it if needed.
+fn intern(name: &str, category: &str) -> Option<AppId> {
+    let mut apps = APPS.write();
+    if let Some(index) = apps.iter().position(|app| app.name == name) {
+        apps[index].category = category.to_string();
+        return u16::try_from(index).ok().map(AppId);
+    }
+    let id = u16::try_from(apps.len()).ok()?;
+    apps.push(AppInfo {
+        name: name.to_string(),
+        category: category.to_string(),
+    });
+    Some(AppId(id))
+}
+
+impl AppId {
+    /// Display name of the application.
+    pub fn name(&self) -> String {
+        APPS.read()
+            .get(self.0 as usize)
+            .map(|app| app.name.clone())
+            .unwrap_or_default()
+    }
+
+    /// Category of the application (e.g. "Streaming Video").
+    pub fn category(&self) -> String {
+        APPS.read()
+            .get(self.0 as usize)
+            .map(|app| app.category.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn merge_entries(defaults: Vec<CatalogEntry>, overrides: Vec<CatalogEntry>) -> Vec<CatalogEntry> {
+    let mut merged = defaults;
+    for entry in overrides {
+        match merged.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => merged.push(entry),
+        }
+    }
+    merged
+}
+
+fn build_catalog(entries: &[CatalogEntry]) -> AppCatalog {
+    let mut catalog = AppCatalog::new();
+    for entry in entries {
+        let Some(id) = intern(&entry.name, &entry.category) else {
+            warn!("Application catalog is full; ignoring {}", entry.name);
+            continue;
+        };
+        for domain in &entry.domains {
+            let domain = domain.trim().trim_matches('.').to_ascii_lowercase();
+            if !domain.is_empty() {
+                catalog.domains.insert(domain, id);
+            }
+        }
+        for asn in &entry.asns {
+            catalog.asns.insert(*asn, id);
+        }
+        for prefix in &entry.prefixes {
+            match prefix.parse::<IpNetwork>() {
+                Ok(network) => {
+                    catalog.prefixes.insert(network, id);
+                }
+                Err(_) => warn!("Invalid prefix {prefix} for application {}", entry.name),
+            }
+        }
+    }
+    catalog
+}
+
+fn catalog_override_path() -> Option<PathBuf> {
+    let config = lqos_config::load_config().ok()?;
+    Some(PathBuf::from(&config.lqos_directory).join(CATALOG_FILENAME))
+}
+
+fn default_entries() -> Vec<CatalogEntry> {
+    serde_json::from_str::<CatalogFile>(DEFAULT_CATALOG)
+        .map(|file| file.applications)
+        .unwrap_or_default()
+}
+
+/// Rebuilds the catalog on first use and whenever the override file changes.
+fn refresh(state: &mut CatalogState) {
+    if state
+        .last_check
+        .is_some_and(|checked| checked.elapsed() < RELOAD_CHECK_INTERVAL)
+    {
+        return;
+    }
+    let first_load = state.last_check.is_none();
+    state.last_check = Some(Instant::now());
+
+    let path = catalog_override_path();
+    let mtime = path
+        .as_ref()
+        .and_then(|path| std::fs::metadata(path).ok())
+        .and_then(|meta| meta.modified().ok());
+    if !first_load && mtime == state.loaded_mtime {
+        return;
+    }
+
+    let mut overrides = Vec::new();
+    if let (Some(path), Some(_)) = (&path, mtime) {
+        match std::fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|raw| Ok(serde_json::from_str::<CatalogFile>(&raw)?))
+        {
+            Ok(file) => {
+                info!(
+                    "Loaded {} application catalog entries from {}",
+                    file.applications.len(),
+                    path.display()
+                );
+                overrides = file.applications;
+            }
+            Err(e) => warn!("Unable to read application catalog {}: {e}", path.display()),
+        }
+    }
+    state.catalog = build_catalog(&merge_entries(default_entries(), overrides));
+    state.loaded_mtime = mtime;
+}
+
+/// Finds the application for a host name, matching the longest catalog
+/// domain suffix (so `rr1.googlevideo.com` matches `googlevideo.com`).
+pub fn application_for_hostname(hostname: &str) -> Option<AppId> {
+    let mut state = CATALOG.lock();
+    refresh(&mut state);
+    lookup_hostname(&state.catalog, hostname)
+}
+
+fn lookup_hostname(catalog: &AppCatalog, hostname: &str) -> Option<AppId> {
+    let mut candidate = hostname;
+    loop {
+        if let Some(id) = catalog.domains.get(candidate) {
+            return Some(*id);
+        }
+        let (_, rest) = candidate.split_once('.')?;
+        candidate = rest;
+    }
+}
+
+/// Finds the application for a remote address, by prefix first and then ASN.
+pub fn application_for_address(ip: IpAddr, asn: u32) -> Option<AppId> {
+    let mut state = CATALOG.lock();
+    refresh(&mut state);
+    if let Some((_, id)) = state.catalog.prefixes.longest_match(ip) {
+        return Some(*id);
+    }
+    state.catalog.asns.get(&asn).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, domains: &[&str], asns: &[u32], prefixes: &[&str]) -> CatalogEntry {
+        CatalogEntry {
+            name: name.to_string(),
+            category: "Test".to_string(),
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            asns: asns.to_vec(),
+            prefixes: prefixes.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn default_catalog_parses() {
+        let entries = default_entries();
+        assert!(entries.iter().any(|e| e.name == "Netflix"));
+    }
+
+    #[test]
+    fn hostname_matches_longest_suffix() {
+        let catalog = build_catalog(&[
+            entry("Catalog Test Cloud", &["cloud-test.example"], &[], &[]),
+            entry(
+                "Catalog Test Meetings",
+                &["meet.cloud-test.example"],
+                &[],
+                &[],
+            ),
+        ]);
+        let meet = lookup_hostname(&catalog, "eu.meet.cloud-test.example").expect("meeting match");
+        let cloud = lookup_hostname(&catalog, "cdn.cloud-test.example").expect("cloud match");
+        assert_eq!(meet.name(), "Catalog Test Meetings");
+        assert_eq!(cloud.name(), "Catalog Test Cloud");
+        assert!(lookup_hostname(&catalog, "example").is_none());
+    }
+
+    #[test]
+    fn overrides_replace_entries_by_name() {
+        let merged = merge_entries(
+            vec![entry(
+                "Catalog Test Video",
+                &["video.example"],
+                &[64500],
+                &[],
+            )],
+            vec![
+                entry(
+                    "Catalog Test Video",
+                    &["video2.example"],
+                    &[],
+                    &["198.51.100.0/24"],
+                ),
+                entry("Catalog Test Extra", &[], &[64501], &[]),
+            ],
+        );
+        assert_eq!(merged.len(), 2);
+        let catalog = build_catalog(&merged);
+        assert!(!catalog.asns.contains_key(&64500));
+        assert!(catalog.domains.contains_key("video2.example"));
+        let ip: IpAddr = "198.51.100.7".parse().expect("valid address");
+        assert_eq!(
+            catalog.prefixes.longest_match(ip).map(|(_, id)| id.name()),
+            Some("Catalog Test Video".to_string())
+        );
+    }
+}
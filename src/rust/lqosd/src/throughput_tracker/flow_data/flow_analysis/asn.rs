@@ -1,7 +1,7 @@
 //! Obtain ASN and geo mappings from IP addresses for flow
 //! analysis.
 
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use serde::Deserialize;
 use std::{io::Read, net::IpAddr, path::Path};
 use tracing::{debug, info};
@@ -205,6 +205,15 @@ impl GeoTable {
         (0.0, 0.0)
     }
 
+    /// Lists the networks announced by any of `asns`, with the ASN of each.
+    pub fn prefixes_for_asns(&self, asns: &FxHashSet<u32>) -> Vec<(IpAddr, u8, u32)> {
+        self.asn_trie
+            .iter()
+            .filter(|(_, entry)| asns.contains(&entry.asn))
+            .map(|(_, entry)| (entry.network, entry.prefix, entry.asn))
+            .collect()
+    }
+
     pub fn find_name_by_id(&self, id: u32) -> String {
         self.asn_lookup
             .get(&id)
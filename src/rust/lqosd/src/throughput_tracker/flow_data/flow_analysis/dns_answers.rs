@@ -0,0 +1,176 @@
+//! Parses DNS responses seen on the wire, so that later flows to an answered
+//! address can be attributed to the name the subscriber looked up.
+
+use super::tls_sni::normalize_hostname;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const MAX_POINTER_HOPS: usize = 16;
+
+/// One address record from a DNS response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsAnswer {
+    /// The name originally queried (not the CNAME target).
+    pub query_name: String,
+    /// The answered address.
+    pub address: IpAddr,
+    /// Record TTL in seconds.
+    pub ttl: u32,
+}
+
+/// Extracts A/AAAA answers from a DNS response message.
+///
+/// Every address is attributed to the question name, so CNAME chains
+/// (e.g. `www.example.com` -> `example.cdn.net`) keep the name the user asked for.
+pub fn parse_dns_answers(message: &[u8]) -> Vec<DnsAnswer> {
+    let mut answers = Vec::new();
+    let Some(header) = message.get(..12) else {
+        return answers;
+    };
+    let flags = u16::from_be_bytes([header[2], header[3]]);
+    let is_response = flags & 0x8000 != 0;
+    let rcode = flags & 0x000f;
+    let question_count = u16::from_be_bytes([header[4], header[5]]);
+    let answer_count = u16::from_be_bytes([header[6], header[7]]);
+    if !is_response || rcode != 0 || question_count != 1 || answer_count == 0 {
+        return answers;
+    }
+
+    let mut position = 12usize;
+    let Some(query_name) = read_name(message, &mut position).and_then(|n| normalize_hostname(&n))
+    else {
+        return answers;
+    };
+    position += 4; // QTYPE, QCLASS
+
+    for _ in 0..answer_count {
+        if read_name(message, &mut position).is_none() {
+            break;
+        }
+        let Some(fixed) = message.get(position..position + 10) else {
+            break;
+        };
+        let record_type = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let class = u16::from_be_bytes([fixed[2], fixed[3]]);
+        let ttl = u32::from_be_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]);
+        let data_len = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        position += 10;
+        let Some(data) = message.get(position..position + data_len) else {
+            break;
+        };
+        position += data_len;
+        if class != CLASS_IN {
+            continue;
+        }
+        let address = match (record_type, data.len()) {
+            (TYPE_A, 4) => IpAddr::V4(Ipv4Addr::new(data[0], data[1], data[2], data[3])),
+            (TYPE_AAAA, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(data);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => continue,
+        };
+        answers.push(DnsAnswer {
+            query_name: query_name.clone(),
+            address,
+            ttl,
+        });
+    }
+    answers
+}
+
+/// Reads a (possibly compressed) DNS name, advancing `position` past it.
+fn read_name(message: &[u8], position: &mut usize) -> Option<Vec<u8>> {
+    let mut name = Vec::new();
+    let mut cursor = *position;
+    let mut jumped = false;
+    for _ in 0..MAX_POINTER_HOPS * 8 {
+        let len = *message.get(cursor)?;
+        match len {
+            0 => {
+                if !jumped {
+                    *position = cursor + 1;
+                }
+                return Some(name);
+            }
+            l if l & 0xC0 == 0xC0 => {
+                let pointer = (u16::from_be_bytes([l & 0x3f, *message.get(cursor + 1)?])) as usize;
+                if !jumped {
+                    *position = cursor + 2;
+                }
+                jumped = true;
+                cursor = pointer;
+            }
+            l if l & 0xC0 == 0 => {
+                let label = message.get(cursor + 1..cursor + 1 + l as usize)?;
+                if !name.is_empty() {
+                    name.push(b'.');
+                }
+                name.extend_from_slice(label);
+                cursor += 1 + l as usize;
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> Vec<u8> {
+        let mut msg = vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 3, 0, 0, 0, 0];
+        // Question: www.example.com A IN
+        for label in ["www", "example", "com"] {
+            msg.push(label.len() as u8);
+            msg.extend_from_slice(label.as_bytes());
+        }
+        msg.extend_from_slice(&[0, 0, 1, 0, 1]);
+        // CNAME www.example.com -> edge.cdn (pointer to question name)
+        msg.extend_from_slice(&[0xC0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 10]);
+        msg.extend_from_slice(&[4, b'e', b'd', b'g', b'e', 3, b'c', b'd', b'n', 0]);
+        // A record for the CNAME target (pointer to offset of "edge.cdn")
+        let target_offset = 12 + 17 + 4 + 12;
+        msg.extend_from_slice(&[0xC0, target_offset as u8, 0, 1, 0, 1, 0, 0, 1, 0, 0, 4]);
+        msg.extend_from_slice(&[192, 0, 2, 10]);
+        // AAAA record
+        msg.extend_from_slice(&[0xC0, target_offset as u8, 0, 28, 0, 1, 0, 0, 0, 30, 0, 16]);
+        msg.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        msg
+    }
+
+    #[test]
+    fn attributes_addresses_to_the_question_name() {
+        let answers = parse_dns_answers(&response());
+        assert_eq!(answers.len(), 2);
+        assert_eq!(answers[0].query_name, "www.example.com");
+        assert_eq!(
+            answers[0].address,
+            "192.0.2.10".parse::<IpAddr>().expect("valid address")
+        );
+        assert_eq!(answers[0].ttl, 256);
+        assert_eq!(
+            answers[1].address,
+            "2001:db8::1".parse::<IpAddr>().expect("valid address")
+        );
+    }
+
+    #[test]
+    fn queries_and_truncated_messages_are_ignored() {
+        let mut query = response();
+        query[2] = 0x01; // Clear the QR bit
+        assert!(parse_dns_answers(&query).is_empty());
+        assert!(parse_dns_answers(&response()[..20]).is_empty());
+    }
+
+    #[test]
+    fn pointer_loops_do_not_hang() {
+        let mut msg = vec![0, 0, 0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0];
+        msg.extend_from_slice(&[0xC0, 12]);
+        assert!(parse_dns_answers(&msg).is_empty());
+    }
+}
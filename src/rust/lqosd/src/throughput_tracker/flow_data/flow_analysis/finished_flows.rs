@@ -52,6 +52,7 @@ pub struct AsnCountryListEntry {
 pub struct AsnProtocolListEntry {
     pub count: usize,
     pub protocol: String,
+    pub category: String,
 }
 
 impl TimeBuffer {
@@ -258,7 +259,7 @@ impl TimeBuffer {
 
         buffer.iter().for_each(|v| {
             let (_key, data, analysis) = &v.data;
-            let proto = analysis.protocol_analysis.to_string();
+            let proto = analysis.label();
             let entry = results.entry(proto).or_insert(DownUpOrder::zeroed());
             entry.checked_add(data.bytes_sent);
         });
@@ -316,7 +317,7 @@ impl TimeBuffer {
         let buffer = self.buffer.lock();
         buffer
             .iter()
-            .filter(|flow| flow.data.2.protocol_analysis.to_string() == protocol_name)
+            .filter(|flow| flow.data.2.label() == protocol_name)
             .map(|flow| flow.data.clone())
             .collect()
     }
@@ -401,13 +402,13 @@ impl TimeBuffer {
         };
 
         // Filter out the short flows and get the country & flag
-        let mut buffer: Vec<String> = buffer
+        let mut buffer: Vec<(String, String)> = buffer
             .into_iter()
             .filter(|flow| {
                 // Total flow time > 3 seconds
                 flow.data.1.last_seen - flow.data.1.start_time > 3_000_000_000
             })
-            .map(|flow| flow.data.2.protocol_analysis.to_string())
+            .map(|flow| (flow.data.2.label(), flow.data.2.category()))
             .collect();
 
         // Sort the buffer
@@ -418,7 +419,11 @@ impl TimeBuffer {
             .into_iter()
             .sorted()
             .dedup_with_count()
-            .map(|(count, protocol)| AsnProtocolListEntry { count, protocol })
+            .map(|(count, (protocol, category))| AsnProtocolListEntry {
+                count,
+                protocol,
+                category,
+            })
             .collect()
     }
 
@@ -498,13 +503,10 @@ fn build_two_way_flow(
     }
 }
 
-fn enqueue(key: FlowbeeKey, data: FlowbeeLocalData, analysis: FlowAnalysis) {
-    debug!("Finished flow analysis");
-    let start_time = boot_time_nanos_to_unix_now(data.start_time).unwrap_or(0);
-    let last_seen = boot_time_nanos_to_unix_now(data.last_seen).unwrap_or(0);
-
-    let one_way = data.bytes_sent.down == 0 || data.bytes_sent.up == 0;
-    let circuit_hash = data.circuit_hash.or_else(|| {
+/// Resolves the circuit a finished flow belongs to: the hash recorded on the
+/// flow, then the throughput tracker entry, then the shaped devices catalog.
+pub fn finished_flow_circuit_hash(key: &FlowbeeKey, data: &FlowbeeLocalData) -> Option<i64> {
+    data.circuit_hash.or_else(|| {
         crate::throughput_tracker::THROUGHPUT_TRACKER
             .raw_data
             .lock()
@@ -513,7 +515,16 @@ fn enqueue(key: FlowbeeKey, data: FlowbeeLocalData, analysis: FlowAnalysis) {
             .or_else(|| {
                 lqos_network_devices::shaped_devices_catalog().circuit_hash_for_ip(&key.local_ip)
             })
-    });
+    })
+}
+
+fn enqueue(key: FlowbeeKey, data: FlowbeeLocalData, analysis: FlowAnalysis) {
+    debug!("Finished flow analysis");
+    let start_time = boot_time_nanos_to_unix_now(data.start_time).unwrap_or(0);
+    let last_seen = boot_time_nanos_to_unix_now(data.last_seen).unwrap_or(0);
+
+    let one_way = data.bytes_sent.down == 0 || data.bytes_sent.up == 0;
+    let circuit_hash = finished_flow_circuit_hash(&key, &data);
 
     if !one_way {
         let flow = build_two_way_flow(&key, &data, start_time, last_seen, circuit_hash.unwrap_or(0));
@@ -33,92 +33,102 @@ impl FlowTracker {
             error!("Unable to read configuration. Flow tracker cannot run.");
             anyhow::bail!("Unable to build flow tracker");
         };
-        let mut ignore_subnets = ip_network_table::IpNetworkTable::new();
-        if let Some(flows) = &config.flows
-            && let Some(subnets) = &flows.do_not_track_subnets
+        let ignore_subnets = match config
+            .flows
+            .as_ref()
+            .and_then(|flows| flows.do_not_track_subnets.as_ref())
         {
-            // Subnets are in CIDR notation
-            for subnet in subnets.iter() {
-                let subnet = subnet.trim();
-                if subnet.is_empty() {
-                    continue;
-                }
+            Some(subnets) => subnet_table(subnets, "do_not_track_subnets"),
+            None => ip_network_table::IpNetworkTable::new(),
+        };
 
-                // Allow host-only entries by defaulting to /32 (v4) or /128 (v6).
-                let (ip_part, mask_part) = match subnet.split_once('/') {
-                    Some((ip, mask)) => (ip.trim(), Some(mask.trim())),
-                    None => (subnet, None),
-                };
+        Ok(Self {
+            flow_rtt: FxHashMap::default(),
+            ignore_subnets,
+        })
+    }
+}
 
-                if ip_part.contains(':') {
-                    let mask = match mask_part {
-                        Some(mask) => match mask.parse::<u8>() {
-                            Ok(mask) if mask <= 128 => mask,
-                            _ => {
-                                error!(
-                                    "Invalid IPv6 subnet mask in do_not_track_subnets: {subnet}"
-                                );
-                                continue;
-                            }
-                        },
-                        None => 128,
-                    };
-
-                    let ip: Ipv6Addr = match ip_part.parse() {
-                        Ok(ip) => ip,
-                        Err(_) => {
-                            error!("Invalid IPv6 subnet in do_not_track_subnets: {subnet}");
-                            continue;
-                        }
-                    };
+/// Parses a list of CIDR subnets (host-only entries allowed) into a lookup
+/// table. IPv4 subnets are stored as IPv6-mapped addresses. `setting` names
+/// the configuration key in error messages.
+pub(crate) fn subnet_table(
+    subnets: &[String],
+    setting: &str,
+) -> ip_network_table::IpNetworkTable<bool> {
+    let mut table = ip_network_table::IpNetworkTable::new();
+    // Subnets are in CIDR notation
+    for subnet in subnets.iter() {
+        let subnet = subnet.trim();
+        if subnet.is_empty() {
+            continue;
+        }
 
-                    match ip_network::IpNetwork::new(ip, mask) {
-                        Ok(addr) => {
-                            ignore_subnets.insert(addr, true);
-                        }
-                        Err(_) => {
-                            error!("Invalid IPv6 subnet in do_not_track_subnets: {subnet}");
-                        }
+        // Allow host-only entries by defaulting to /32 (v4) or /128 (v6).
+        let (ip_part, mask_part) = match subnet.split_once('/') {
+            Some((ip, mask)) => (ip.trim(), Some(mask.trim())),
+            None => (subnet, None),
+        };
+
+        if ip_part.contains(':') {
+            let mask = match mask_part {
+                Some(mask) => match mask.parse::<u8>() {
+                    Ok(mask) if mask <= 128 => mask,
+                    _ => {
+                        error!("Invalid IPv6 subnet mask in {setting}: {subnet}");
+                        continue;
                     }
-                } else {
-                    let mask = match mask_part {
-                        Some(mask) => match mask.parse::<u8>() {
-                            Ok(mask) if mask <= 32 => mask,
-                            _ => {
-                                error!(
-                                    "Invalid IPv4 subnet mask in do_not_track_subnets: {subnet}"
-                                );
-                                continue;
-                            }
-                        },
-                        None => 32,
-                    };
-
-                    let ip: Ipv4Addr = match ip_part.parse() {
-                        Ok(ip) => ip,
-                        Err(_) => {
-                            error!("Invalid IPv4 subnet in do_not_track_subnets: {subnet}");
-                            continue;
-                        }
-                    };
+                },
+                None => 128,
+            };
+
+            let ip: Ipv6Addr = match ip_part.parse() {
+                Ok(ip) => ip,
+                Err(_) => {
+                    error!("Invalid IPv6 subnet in {setting}: {subnet}");
+                    continue;
+                }
+            };
 
-                    match ip_network::IpNetwork::new(ip.to_ipv6_mapped(), mask + 96) {
-                        Ok(addr) => {
-                            ignore_subnets.insert(addr, true);
-                        }
-                        Err(_) => {
-                            error!("Invalid IPv4 subnet in do_not_track_subnets: {subnet}");
-                        }
+            match ip_network::IpNetwork::new(ip, mask) {
+                Ok(addr) => {
+                    table.insert(addr, true);
+                }
+                Err(_) => {
+                    error!("Invalid IPv6 subnet in {setting}: {subnet}");
+                }
+            }
+        } else {
+            let mask = match mask_part {
+                Some(mask) => match mask.parse::<u8>() {
+                    Ok(mask) if mask <= 32 => mask,
+                    _ => {
+                        error!("Invalid IPv4 subnet mask in {setting}: {subnet}");
+                        continue;
                     }
+                },
+                None => 32,
+            };
+
+            let ip: Ipv4Addr = match ip_part.parse() {
+                Ok(ip) => ip,
+                Err(_) => {
+                    error!("Invalid IPv4 subnet in {setting}: {subnet}");
+                    continue;
+                }
+            };
+
+            match ip_network::IpNetwork::new(ip.to_ipv6_mapped(), mask + 96) {
+                Ok(addr) => {
+                    table.insert(addr, true);
+                }
+                Err(_) => {
+                    error!("Invalid IPv4 subnet in {setting}: {subnet}");
                 }
             }
         }
-
-        Ok(Self {
-            flow_rtt: FxHashMap::default(),
-            ignore_subnets,
-        })
     }
+    table
 }
 
 /// Provides an actor-model approach to flow tracking storage.
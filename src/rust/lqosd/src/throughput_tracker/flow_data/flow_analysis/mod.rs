@@ -1,4 +1,5 @@
 use allocative_derive::Allocative;
+use fxhash::FxHashSet;
 use lqos_sys::flowbee_data::FlowbeeKey;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
@@ -6,18 +7,33 @@ use serde::Serialize;
 use std::net::IpAddr;
 use tracing::error;
 
+mod app_catalog;
 mod asn;
+mod dns_answers;
 mod protocol;
+mod quic_initial;
+mod tls_sni;
+mod traffic_groups;
 use super::AsnId;
 pub use protocol::FlowProtocol;
 mod finished_flows;
 pub use finished_flows::FinishedFlowAnalysis;
 pub use finished_flows::RECENT_FLOWS;
 mod kernel_ringbuffer;
+mod passive_dns;
+mod payload_ringbuffer;
 use crate::throughput_tracker::flow_data::flow_analysis::asn::AsnNameCountryFlag;
-pub use finished_flows::{AsnCountryListEntry, AsnListEntry, AsnProtocolListEntry};
+pub use app_catalog::AppId;
+pub use finished_flows::{
+    AsnCountryListEntry, AsnListEntry, AsnProtocolListEntry, finished_flow_circuit_hash,
+};
 pub use kernel_ringbuffer::*;
 pub use lqos_utils::rtt::{FlowbeeEffectiveDirection, RttBuffer, RttData};
+pub use passive_dns::{
+    dns_application, flow_hostname, passive_dns_enabled, reload as reload_passive_dns,
+};
+pub use payload_ringbuffer::{flowbee_payload_handle_events, take_flow_hostnames};
+pub use traffic_groups::{TrafficGroupId, traffic_groups_enabled};
 
 static ANALYSIS: Lazy<FlowAnalysisSystem> = Lazy::new(FlowAnalysisSystem::new);
 
@@ -73,17 +89,50 @@ pub fn setup_flow_analysis() -> anyhow::Result<()> {
 pub struct FlowAnalysis {
     pub asn_id: AsnId,
     pub protocol_analysis: FlowProtocol,
+    /// Application identified from SNI, DNS answers or the ASN/prefix catalog.
+    pub application: Option<AppId>,
+    /// Configured traffic group (on-net, peering partner, ...) of the remote endpoint.
+    pub traffic_group: Option<TrafficGroupId>,
 }
 
 impl FlowAnalysis {
     pub fn new(key: &FlowbeeKey) -> Self {
-        let asn_id = lookup_asn_id(key.remote_ip.as_ip());
+        let remote_ip = key.remote_ip.as_ip();
+        let asn_id = lookup_asn_id(remote_ip).unwrap_or(0);
         let protocol_analysis = FlowProtocol::new(key);
+        let application = app_catalog::application_for_address(remote_ip, asn_id);
+        let traffic_group = traffic_groups::traffic_group_for_address(remote_ip, asn_id);
         Self {
-            asn_id: AsnId(asn_id.unwrap_or(0)),
+            asn_id: AsnId(asn_id),
             protocol_analysis,
+            application,
+            traffic_group,
         }
     }
+
+    /// Re-classifies the flow from the host name of its remote endpoint.
+    /// Host names are more specific than ASN or prefix matches, so they win.
+    pub fn apply_hostname(&mut self, hostname: &str) {
+        if let Some(application) = app_catalog::application_for_hostname(hostname) {
+            self.application = Some(application);
+        }
+    }
+
+    /// Display label: the application name when known, otherwise the
+    /// port-based protocol.
+    pub fn label(&self) -> String {
+        match self.application {
+            Some(application) => application.name(),
+            None => self.protocol_analysis.to_string(),
+        }
+    }
+
+    /// Application category, or an empty string if the application is unknown.
+    pub fn category(&self) -> String {
+        self.application
+            .map(|application| application.category())
+            .unwrap_or_default()
+    }
 }
 
 pub fn lookup_asn_id(ip: IpAddr) -> Option<u32> {
@@ -110,6 +159,15 @@ pub fn get_asn_lat_lon(ip: IpAddr) -> (f64, f64) {
     (0.0, 0.0)
 }
 
+/// Lists the networks announced by any of `asns`, or `None` while the ASN
+/// table is still loading.
+pub fn asn_prefixes(asns: &FxHashSet<u32>) -> Option<Vec<(IpAddr, u8, u32)>> {
+    let table_lock = ANALYSIS.asn_table.lock();
+    table_lock
+        .as_ref()
+        .map(|table| table.prefixes_for_asns(asns))
+}
+
 pub fn get_asn_name_by_id(id: u32) -> String {
     let table_lock = ANALYSIS.asn_table.lock();
     if let Some(table) = table_lock.as_ref() {
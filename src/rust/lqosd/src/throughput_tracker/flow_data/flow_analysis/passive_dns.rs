@@ -0,0 +1,405 @@
+//! DNS answers seen by the shaper. Each answer maps an address to the
+//! application its queried name belongs to, so later flows to that address
+//! are classified even without a TLS/QUIC handshake. With passive DNS
+//! enabled, the name itself is kept too, so those flows can show a host name
+//! instead of a bare address.
+//!
+//! Answers are scoped to the circuit that received them (or to the local
+//! address, for unshaped hosts), so one subscriber's lookups never name or
+//! classify another subscriber's flows. Entries honour the record TTL
+//! (clamped), and both the per-circuit and the total number of entries are
+//! bounded.
+//!
+//! Names are kept only when `passive_dns = true` is set in the `[flows]`
+//! configuration section; `passive_dns_exclude_subnets` exempts subscriber
+//! subnets. Both are re-read when the configuration is updated. Flows from
+//! excluded subnets are not named at all, including from their own TLS/QUIC
+//! handshake, but are still classified.
+
+use super::app_catalog::{self, AppId};
+use super::dns_answers::{DnsAnswer, parse_dns_answers};
+use super::kernel_ringbuffer::subnet_table;
+use arc_swap::ArcSwap;
+use fxhash::FxHashMap;
+use ip_network_table::IpNetworkTable;
+use lqos_config::Config;
+use lqos_sys::flowbee_data::FlowbeeKey;
+use lqos_utils::XdpIpAddress;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+const MAX_ENTRIES: usize = 131_072;
+const MAX_ENTRIES_PER_SCOPE: usize = 4096;
+const MIN_TTL_SECONDS: u32 = 300;
+const MAX_TTL_SECONDS: u32 = 3600;
+
+/// Who a cached answer belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum DnsScope {
+    Circuit(i64),
+    Host(XdpIpAddress),
+}
+
+struct DnsCacheEntry {
+    /// The queried name, kept only where passive DNS is allowed.
+    hostname: Option<String>,
+    application: Option<AppId>,
+    expires: Instant,
+}
+
+struct PassiveDnsSettings {
+    enabled: bool,
+    /// The configured exclusions, to tell whether a reload changed them.
+    exclude_subnets: Vec<String>,
+    excluded: IpNetworkTable<bool>,
+}
+
+impl PassiveDnsSettings {
+    fn from_config(config: &Config) -> Self {
+        let flows = config.flows.as_ref();
+        let enabled = flows.and_then(|flows| flows.passive_dns).unwrap_or(false);
+        let exclude_subnets = flows
+            .and_then(|flows| flows.passive_dns_exclude_subnets.clone())
+            .unwrap_or_default();
+        let excluded = subnet_table(&exclude_subnets, "passive_dns_exclude_subnets");
+        Self {
+            enabled,
+            exclude_subnets,
+            excluded,
+        }
+    }
+
+    fn load() -> Self {
+        match lqos_config::load_config() {
+            Ok(config) => Self::from_config(&config),
+            Err(_) => Self {
+                enabled: false,
+                exclude_subnets: Vec::new(),
+                excluded: IpNetworkTable::new(),
+            },
+        }
+    }
+
+    fn allows(&self, local_ip: &XdpIpAddress) -> bool {
+        let lookup = match local_ip.as_ip() {
+            IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+            IpAddr::V6(ip) => ip,
+        };
+        self.enabled && self.excluded.longest_match(lookup).is_none()
+    }
+}
+
+#[derive(Default)]
+struct PassiveDnsCache {
+    scopes: FxHashMap<DnsScope, FxHashMap<IpAddr, DnsCacheEntry>>,
+    entries: usize,
+}
+
+impl PassiveDnsCache {
+    /// Records answers with the application each name belongs to. Without
+    /// `keep_names`, only answers naming a known application are kept, and
+    /// the names are dropped.
+    fn insert(
+        &mut self,
+        scope: DnsScope,
+        answers: Vec<(DnsAnswer, Option<AppId>)>,
+        keep_names: bool,
+        now: Instant,
+    ) {
+        if self.entries + answers.len() > MAX_ENTRIES {
+            self.purge_expired(now);
+        }
+        let names = self.scopes.entry(scope).or_default();
+        for (answer, application) in answers {
+            if !keep_names && application.is_none() {
+                continue;
+            }
+            let replacing = names.contains_key(&answer.address);
+            if !replacing {
+                if self.entries >= MAX_ENTRIES {
+                    break;
+                }
+                if names.len() >= MAX_ENTRIES_PER_SCOPE {
+                    // Make room by dropping the entry closest to expiry
+                    let Some(oldest) = names
+                        .iter()
+                        .min_by_key(|(_, entry)| entry.expires)
+                        .map(|(ip, _)| *ip)
+                    else {
+                        break;
+                    };
+                    names.remove(&oldest);
+                    self.entries -= 1;
+                }
+                self.entries += 1;
+            }
+            let ttl = answer.ttl.clamp(MIN_TTL_SECONDS, MAX_TTL_SECONDS);
+            names.insert(
+                answer.address,
+                DnsCacheEntry {
+                    hostname: keep_names.then_some(answer.query_name),
+                    application,
+                    expires: now + Duration::from_secs(u64::from(ttl)),
+                },
+            );
+        }
+    }
+
+    fn lookup(&self, scope: &DnsScope, address: IpAddr, now: Instant) -> Option<&DnsCacheEntry> {
+        self.scopes
+            .get(scope)?
+            .get(&address)
+            .filter(|entry| entry.expires > now)
+    }
+
+    /// Drops every recorded name, keeping the application of each address.
+    fn forget_names(&mut self) {
+        let mut entries = 0;
+        self.scopes.retain(|_, names| {
+            names.retain(|_, entry| {
+                entry.hostname = None;
+                entry.application.is_some()
+            });
+            entries += names.len();
+            !names.is_empty()
+        });
+        self.entries = entries;
+    }
+
+    fn purge_expired(&mut self, now: Instant) {
+        let mut entries = 0;
+        self.scopes.retain(|_, names| {
+            names.retain(|_, entry| entry.expires > now);
+            entries += names.len();
+            !names.is_empty()
+        });
+        self.entries = entries;
+    }
+}
+
+static SETTINGS: Lazy<ArcSwap<PassiveDnsSettings>> =
+    Lazy::new(|| ArcSwap::from_pointee(PassiveDnsSettings::load()));
+static CACHE: Lazy<Mutex<PassiveDnsCache>> = Lazy::new(|| Mutex::new(PassiveDnsCache::default()));
+
+/// Applies changed `[flows]` passive DNS settings. Turning passive DNS off,
+/// or changing the excluded subnets, forgets every recorded name; the
+/// application classification of each address is kept.
+pub fn reload(config: &Config) {
+    let settings = PassiveDnsSettings::from_config(config);
+    let previous = SETTINGS.swap(std::sync::Arc::new(settings));
+    let current = SETTINGS.load();
+    if !current.enabled || previous.exclude_subnets != current.exclude_subnets {
+        CACHE.lock().forget_names();
+    }
+}
+
+/// Whether passive DNS is turned on. Exporters use this to decide whether
+/// to advertise a host name field at all.
+pub fn passive_dns_enabled() -> bool {
+    SETTINGS.load().enabled
+}
+
+fn scope_for(local_ip: &XdpIpAddress) -> DnsScope {
+    let catalog = lqos_network_devices::network_devices_catalog();
+    match catalog.device_longest_match_for_ip(local_ip) {
+        Some((_, device)) => DnsScope::Circuit(device.circuit_hash),
+        None => DnsScope::Host(*local_ip),
+    }
+}
+
+/// Records the answers in a DNS response delivered to `local_ip`. The names
+/// are kept only if passive DNS is allowed for `local_ip`.
+pub fn record_dns_response(local_ip: &XdpIpAddress, message: &[u8]) {
+    let keep_names = SETTINGS.load().allows(local_ip);
+    let answers: Vec<_> = parse_dns_answers(message)
+        .into_iter()
+        .map(|answer| {
+            let application = app_catalog::application_for_hostname(&answer.query_name);
+            (answer, application)
+        })
+        .collect();
+    if answers.is_empty() {
+        return;
+    }
+    let scope = scope_for(local_ip);
+    CACHE
+        .lock()
+        .insert(scope, answers, keep_names, Instant::now());
+}
+
+/// Returns the name that `local_ip`'s circuit most recently resolved to
+/// `remote_ip`, if it has not expired.
+pub fn hostname_for(local_ip: &XdpIpAddress, remote_ip: IpAddr) -> Option<String> {
+    if !SETTINGS.load().allows(local_ip) {
+        return None;
+    }
+    let scope = scope_for(local_ip);
+    CACHE
+        .lock()
+        .lookup(&scope, remote_ip, Instant::now())
+        .and_then(|entry| entry.hostname.clone())
+}
+
+/// The application `key`'s circuit most recently resolved the remote
+/// address under, whether or not passive DNS is enabled.
+pub fn dns_application(key: &FlowbeeKey) -> Option<AppId> {
+    let scope = scope_for(&key.local_ip);
+    CACHE
+        .lock()
+        .lookup(&scope, key.remote_ip.as_ip(), Instant::now())
+        .and_then(|entry| entry.application)
+}
+
+/// Picks the host name shown for a flow: the name from its own TLS/QUIC
+/// handshake when known, otherwise the passive DNS answer. Flows from
+/// excluded subnets (or with passive DNS disabled) are never named.
+pub fn flow_hostname(key: &FlowbeeKey, handshake_name: Option<String>) -> Option<String> {
+    if !SETTINGS.load().allows(&key.local_ip) {
+        return None;
+    }
+    handshake_name.or_else(|| hostname_for(&key.local_ip, key.remote_ip.as_ip()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(name: &str, address: &str, ttl: u32) -> (DnsAnswer, Option<AppId>) {
+        let answer = DnsAnswer {
+            query_name: name.to_string(),
+            address: address.parse().expect("valid address"),
+            ttl,
+        };
+        (answer, None)
+    }
+
+    fn hostname<'a>(
+        cache: &'a PassiveDnsCache,
+        scope: &DnsScope,
+        address: IpAddr,
+        now: Instant,
+    ) -> Option<&'a str> {
+        cache
+            .lookup(scope, address, now)
+            .and_then(|entry| entry.hostname.as_deref())
+    }
+
+    #[test]
+    fn passive_dns_is_opt_in() {
+        let mut config = Config::default();
+        assert!(!PassiveDnsSettings::from_config(&config).enabled);
+
+        let mut flows = config.flows.clone().unwrap_or_default();
+        flows.passive_dns = Some(true);
+        flows.passive_dns_exclude_subnets = Some(vec!["100.64.10.0/24".to_string()]);
+        config.flows = Some(flows);
+        let settings = PassiveDnsSettings::from_config(&config);
+        assert!(settings.allows(&XdpIpAddress::from_ip("192.0.2.5".parse().expect("valid"))));
+        assert!(!settings.allows(&XdpIpAddress::from_ip(
+            "100.64.10.5".parse().expect("valid")
+        )));
+    }
+
+    #[test]
+    fn answers_are_scoped_to_the_resolving_circuit() {
+        let mut cache = PassiveDnsCache::default();
+        let now = Instant::now();
+        let remote: IpAddr = "192.0.2.10".parse().expect("valid address");
+        cache.insert(
+            DnsScope::Circuit(1),
+            vec![answer("video.example.com", "192.0.2.10", 600)],
+            true,
+            now,
+        );
+        assert_eq!(
+            hostname(&cache, &DnsScope::Circuit(1), remote, now),
+            Some("video.example.com")
+        );
+        assert_eq!(hostname(&cache, &DnsScope::Circuit(2), remote, now), None);
+    }
+
+    #[test]
+    fn entries_expire_after_clamped_ttl() {
+        let mut cache = PassiveDnsCache::default();
+        let now = Instant::now();
+        let remote: IpAddr = "192.0.2.10".parse().expect("valid address");
+        cache.insert(
+            DnsScope::Circuit(1),
+            vec![answer("short.example.com", "192.0.2.10", 5)],
+            true,
+            now,
+        );
+        let scope = DnsScope::Circuit(1);
+        assert!(
+            cache
+                .lookup(&scope, remote, now + Duration::from_secs(60))
+                .is_some()
+        );
+        let later = now + Duration::from_secs(u64::from(MIN_TTL_SECONDS) + 1);
+        assert!(cache.lookup(&scope, remote, later).is_none());
+        cache.purge_expired(later);
+        assert_eq!(cache.entries, 0);
+        assert!(cache.scopes.is_empty());
+    }
+
+    #[test]
+    fn per_scope_limit_evicts_soonest_expiry() {
+        let mut cache = PassiveDnsCache::default();
+        let now = Instant::now();
+        let scope = DnsScope::Circuit(7);
+        let answers = (0..MAX_ENTRIES_PER_SCOPE)
+            .map(|i| {
+                let address = format!("10.{}.{}.1", i / 256, i % 256);
+                answer("bulk.example.com", &address, 600 + i as u32)
+            })
+            .collect();
+        cache.insert(scope, answers, true, now);
+        cache.insert(
+            scope,
+            vec![answer("new.example.com", "192.0.2.1", 600)],
+            true,
+            now,
+        );
+        assert_eq!(cache.entries, MAX_ENTRIES_PER_SCOPE);
+        let first: IpAddr = "10.0.0.1".parse().expect("valid address");
+        let newest: IpAddr = "192.0.2.1".parse().expect("valid address");
+        assert!(cache.lookup(&scope, first, now).is_none());
+        assert_eq!(
+            hostname(&cache, &scope, newest, now),
+            Some("new.example.com")
+        );
+    }
+
+    #[test]
+    fn applications_are_kept_without_names() {
+        let mut cache = PassiveDnsCache::default();
+        let now = Instant::now();
+        let scope = DnsScope::Circuit(3);
+        let video = app_catalog::application_for_hostname("www.netflix.com");
+        assert!(video.is_some());
+        let (named, _) = answer("www.netflix.com", "192.0.2.20", 600);
+        let (unknown, _) = answer("unknown.example", "192.0.2.21", 600);
+        cache.insert(scope, vec![(named, video), (unknown, None)], false, now);
+
+        let address: IpAddr = "192.0.2.20".parse().expect("valid address");
+        let entry = cache.lookup(&scope, address, now).expect("classified");
+        assert_eq!(entry.application, video);
+        assert_eq!(entry.hostname, None);
+        assert_eq!(cache.entries, 1);
+
+        let (named, _) = answer("www.netflix.com", "192.0.2.22", 600);
+        let (unknown, _) = answer("unknown.example", "192.0.2.23", 600);
+        cache.insert(scope, vec![(named, video), (unknown, None)], true, now);
+        assert_eq!(cache.entries, 3);
+        cache.forget_names();
+        assert_eq!(cache.entries, 2);
+        let address: IpAddr = "192.0.2.22".parse().expect("valid address");
+        let entry = cache
+            .lookup(&scope, address, now)
+            .expect("still classified");
+        assert_eq!(entry.hostname, None);
+        assert_eq!(entry.application, video);
+    }
+}
@@ -0,0 +1,194 @@
+//! Connects to the "flowbee_payloads" ring buffer and turns early-packet
+//! payload samples into host name hints for application classification.
+//!
+//! * TLS ClientHellos (TCP/443) and QUIC Initials (UDP/443) yield the SNI
+//!   of that flow.
+//! * DNS answers (UDP/53) are handed to the passive DNS cache, which
+//!   classifies (and, if enabled, names) later flows to those addresses.
+//!
+//! Samples are rare (a handful per flow), so they are parsed directly on the
+//! ring buffer polling thread.
+
+use super::passive_dns::record_dns_response;
+use super::quic_initial::initial_crypto_frames;
+use super::tls_sni::{SniScan, scan_handshake, scan_tls_stream};
+use fxhash::FxHashMap;
+use lqos_sys::flowbee_data::FlowbeeKey;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    ffi::c_void,
+    slice,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+use zerocopy::FromBytes;
+
+const PAYLOAD_BYTES: usize = 1500;
+const EVENT_SIZE: usize = size_of::<FlowbeePayloadEvent>();
+const MAX_STREAM_BYTES: usize = 8192;
+const MAX_PENDING_STREAMS: usize = 16_384;
+const PENDING_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_FLOW_HOSTNAMES: usize = 65_536;
+
+#[repr(C)]
+#[derive(FromBytes, Debug, Clone)]
+struct FlowbeePayloadEvent {
+    key: FlowbeeKey,
+    effective_direction: u32,
+    payload_len: u32,
+    payload: [u8; PAYLOAD_BYTES],
+}
+
+/// Partially received handshake data for one flow.
+struct PendingStream {
+    started: Instant,
+    /// TCP: raw stream bytes. QUIC: contiguous CRYPTO stream bytes.
+    bytes: Vec<u8>,
+    /// QUIC CRYPTO frames that arrived ahead of the contiguous stream.
+    fragments: Vec<(u64, Vec<u8>)>,
+}
+
+impl PendingStream {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            bytes: Vec::new(),
+            fragments: Vec::new(),
+        }
+    }
+
+    fn push_tcp(&mut self, payload: &[u8]) {
+        let room = MAX_STREAM_BYTES.saturating_sub(self.bytes.len());
+        self.bytes
+            .extend_from_slice(&payload[..payload.len().min(room)]);
+    }
+
+    fn push_crypto(&mut self, offset: u64, data: Vec<u8>) {
+        self.fragments.push((offset, data));
+        self.fragments.sort_by_key(|(offset, _)| *offset);
+        let mut progressed = true;
+        while progressed {
+            progressed = false;
+            let have = self.bytes.len() as u64;
+            self.fragments.retain(|(offset, data)| {
+                let end = offset + data.len() as u64;
+                if *offset <= have && end > have && self.bytes.len() < MAX_STREAM_BYTES {
+                    let skip = (have - offset) as usize;
+                    self.bytes.extend_from_slice(&data[skip..]);
+                    self.bytes.truncate(MAX_STREAM_BYTES);
+                    progressed = true;
+                    false
+                } else {
+                    // Drop fragments that are already covered
+                    end > have
+                }
+            });
+        }
+    }
+}
+
+static PENDING: Lazy<Mutex<FxHashMap<FlowbeeKey, PendingStream>>> =
+    Lazy::new(|| Mutex::new(FxHashMap::default()));
+static FLOW_HOSTNAMES: Lazy<Mutex<FxHashMap<FlowbeeKey, String>>> =
+    Lazy::new(|| Mutex::new(FxHashMap::default()));
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn flowbee_payload_handle_events(
+    _ctx: *mut c_void,
+    data: *mut c_void,
+    data_size: usize,
+) -> i32 {
+    if data_size < EVENT_SIZE {
+        warn!("Flow payload ringbuffer data is too small. Dropping it.");
+        return 0;
+    }
+    let data_slice: &[u8] = unsafe { slice::from_raw_parts(data as *const u8, EVENT_SIZE) };
+    let Ok(event) = FlowbeePayloadEvent::read_from_bytes(data_slice) else {
+        return 0;
+    };
+    let len = (event.payload_len as usize).min(PAYLOAD_BYTES);
+    handle_payload(&event.key, event.effective_direction, &event.payload[..len]);
+    0
+}
+
+/// `direction` is 0 for download (from the Internet) and 1 for upload.
+fn handle_payload(key: &FlowbeeKey, direction: u32, payload: &[u8]) {
+    match (key.ip_protocol, key.src_port, direction) {
+        (17, 53, 0) => record_dns_response(&key.local_ip, payload),
+        (6, 443, 1) => {
+            with_pending(key, |pending| {
+                pending.push_tcp(payload);
+                scan_tls_stream(&pending.bytes)
+            });
+        }
+        (17, 443, 1) => {
+            let Some(frames) = initial_crypto_frames(payload) else {
+                return;
+            };
+            if frames.is_empty() {
+                return;
+            }
+            with_pending(key, |pending| {
+                for (offset, data) in frames {
+                    pending.push_crypto(offset, data);
+                }
+                if pending.bytes.is_empty() {
+                    SniScan::NeedMore
+                } else {
+                    scan_handshake(&pending.bytes)
+                }
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Feeds a sample into the flow's pending handshake, and records the host name
+/// once the scan completes.
+fn with_pending(key: &FlowbeeKey, scan: impl FnOnce(&mut PendingStream) -> SniScan) {
+    let mut pending = PENDING.lock();
+    if !pending.contains_key(key) && pending.len() >= MAX_PENDING_STREAMS {
+        pending.retain(|_, stream| stream.started.elapsed() < PENDING_TIMEOUT);
+        if pending.len() >= MAX_PENDING_STREAMS {
+            return;
+        }
+    }
+    let stream = pending.entry(*key).or_insert_with(PendingStream::new);
+    match scan(stream) {
+        SniScan::NeedMore if stream.bytes.len() < MAX_STREAM_BYTES => {}
+        SniScan::Found(hostname) => {
+            pending.remove(key);
+            drop(pending);
+            let mut hostnames = FLOW_HOSTNAMES.lock();
+            if hostnames.len() < MAX_FLOW_HOSTNAMES {
+                hostnames.insert(*key, hostname);
+            }
+        }
+        _ => {
+            pending.remove(key);
+        }
+    }
+}
+
+/// Takes the host names learned from TLS/QUIC handshakes since the last call.
+pub fn take_flow_hostnames() -> FxHashMap<FlowbeeKey, String> {
+    std::mem::take(&mut *FLOW_HOSTNAMES.lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PendingStream;
+
+    #[test]
+    fn crypto_fragments_reassemble_out_of_order() {
+        let mut stream = PendingStream::new();
+        stream.push_crypto(4, b"5678".to_vec());
+        assert!(stream.bytes.is_empty());
+        stream.push_crypto(0, b"1234".to_vec());
+        assert_eq!(stream.bytes, b"12345678");
+        stream.push_crypto(2, b"3456789".to_vec());
+        assert_eq!(stream.bytes, b"123456789");
+        assert!(stream.fragments.is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+//! Decrypts QUIC v1 client Initial packets far enough to read their CRYPTO
+//! frames (RFC 9001 section 5).
+//!
+//! Initial packet keys are derived from the client's Destination Connection
+//! ID, so anyone on the path can read them. Samples may be truncated, so the
+//! AEAD tag is never verified: the payload is decrypted as AES-128-CTR, which
+//! is the keystream AES-128-GCM uses.
+
+use aes::Aes128;
+use aes::cipher::{BlockEncrypt, KeyInit, KeyIvInit, StreamCipher, generic_array::GenericArray};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+type Aes128Ctr = ctr::Ctr32BE<Aes128>;
+
+const QUIC_V1: u32 = 0x0000_0001;
+const QUIC_V1_INITIAL_SALT: [u8; 20] = [
+    0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17, 0x9a, 0xe6, 0xa4, 0xc8, 0x0c, 0xad,
+    0xcc, 0xbb, 0x7f, 0x0a,
+];
+const AEAD_TAG_LEN: usize = 16;
+const HP_SAMPLE_LEN: usize = 16;
+const MAX_CONNECTION_ID_LEN: usize = 20;
+
+/// Client Initial packet protection keys.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InitialKeys {
+    pub key: [u8; 16],
+    pub iv: [u8; 12],
+    pub hp: [u8; 16],
+}
+
+impl InitialKeys {
+    /// Derives the client Initial keys for a Destination Connection ID.
+    pub fn client(dcid: &[u8]) -> Self {
+        let initial_secret = hkdf_extract(&QUIC_V1_INITIAL_SALT, dcid);
+        let client_secret: [u8; 32] = hkdf_expand_label(&initial_secret, b"client in");
+        Self {
+            key: hkdf_expand_label(&client_secret, b"quic key"),
+            iv: hkdf_expand_label(&client_secret, b"quic iv"),
+            hp: hkdf_expand_label(&client_secret, b"quic hp"),
+        }
+    }
+}
+
+fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> [u8; 32] {
+    let Ok(mut mac) = <HmacSha256 as Mac>::new_from_slice(salt) else {
+        return [0; 32];
+    };
+    mac.update(ikm);
+    mac.finalize().into_bytes().into()
+}
+
+/// TLS 1.3 HKDF-Expand-Label with an empty context. Outputs are at most one
+/// SHA-256 block long, so a single HMAC round is enough.
+fn hkdf_expand_label<const N: usize>(secret: &[u8], label: &[u8]) -> [u8; N] {
+    let mut out = [0u8; N];
+    let Ok(mut mac) = <HmacSha256 as Mac>::new_from_slice(secret) else {
+        return out;
+    };
+    mac.update(&(N as u16).to_be_bytes());
+    mac.update(&[(6 + label.len()) as u8]);
+    mac.update(b"tls13 ");
+    mac.update(label);
+    mac.update(&[0, 1]);
+    out.copy_from_slice(&mac.finalize().into_bytes()[..N]);
+    out
+}
+
+fn read_varint(data: &[u8], position: &mut usize) -> Option<u64> {
+    let first = *data.get(*position)?;
+    let len = 1usize << (first >> 6);
+    let bytes = data.get(*position..*position + len)?;
+    let mut value = u64::from(first & 0x3f);
+    for b in &bytes[1..] {
+        value = (value << 8) | u64::from(*b);
+    }
+    *position += len;
+    Some(value)
+}
+
+/// Returns the `(offset, data)` CRYPTO frames carried by a client Initial
+/// packet, or `None` if the datagram is not a readable QUIC v1 Initial.
+pub fn initial_crypto_frames(datagram: &[u8]) -> Option<Vec<(u64, Vec<u8>)>> {
+    let first = *datagram.first()?;
+    // Long header, fixed bit set, packet type Initial
+    if first & 0xC0 != 0xC0 || (first >> 4) & 0x03 != 0 {
+        return None;
+    }
+    let version = u32::from_be_bytes(datagram.get(1..5)?.try_into().ok()?);
+    if version != QUIC_V1 {
+        return None;
+    }
+    let mut position = 5usize;
+    let dcid_len = *datagram.get(position)? as usize;
+    if dcid_len > MAX_CONNECTION_ID_LEN {
+        return None;
+    }
+    let dcid = datagram.get(position + 1..position + 1 + dcid_len)?;
+    position += 1 + dcid_len;
+    let scid_len = *datagram.get(position)? as usize;
+    position += 1 + scid_len;
+    let token_len = read_varint(datagram, &mut position)? as usize;
+    position = position.checked_add(token_len)?;
+    let length = read_varint(datagram, &mut position)? as usize;
+    let pn_offset = position;
+
+    let keys = InitialKeys::client(dcid);
+    let sample = datagram.get(pn_offset + 4..pn_offset + 4 + HP_SAMPLE_LEN)?;
+    let mut mask = GenericArray::clone_from_slice(sample);
+    Aes128::new(GenericArray::from_slice(&keys.hp)).encrypt_block(&mut mask);
+
+    let first = first ^ (mask[0] & 0x0f);
+    let pn_len = (first & 0x03) as usize + 1;
+    let mut packet_number = 0u64;
+    for (i, b) in datagram
+        .get(pn_offset..pn_offset + pn_len)?
+        .iter()
+        .enumerate()
+    {
+        packet_number = (packet_number << 8) | u64::from(b ^ mask[1 + i]);
+    }
+
+    let payload_start = pn_offset + pn_len;
+    let packet_end = pn_offset + length;
+    let payload_end = if packet_end <= datagram.len() {
+        packet_end.checked_sub(AEAD_TAG_LEN)?
+    } else {
+        datagram.len()
+    };
+    let mut payload = datagram.get(payload_start..payload_end)?.to_vec();
+
+    let mut nonce = keys.iv;
+    for (i, b) in packet_number.to_be_bytes().iter().enumerate() {
+        nonce[4 + i] ^= b;
+    }
+    let mut counter_block = [0u8; 16];
+    counter_block[..12].copy_from_slice(&nonce);
+    counter_block[15] = 2;
+    let mut cipher = Aes128Ctr::new(
+        GenericArray::from_slice(&keys.key),
+        GenericArray::from_slice(&counter_block),
+    );
+    cipher.apply_keystream(&mut payload);
+
+    Some(parse_crypto_frames(&payload))
+}
+
+fn parse_crypto_frames(payload: &[u8]) -> Vec<(u64, Vec<u8>)> {
+    let mut frames = Vec::new();
+    let mut position = 0usize;
+    while let Some(frame_type) = read_varint(payload, &mut position) {
+        match frame_type {
+            // PADDING, PING
+            0x00 | 0x01 => {}
+            // ACK, ACK with ECN counts
+            0x02 | 0x03 => {
+                let mut skip = || read_varint(payload, &mut position);
+                let (Some(_largest), Some(_delay), Some(ranges), Some(_first)) =
+                    (skip(), skip(), skip(), skip())
+                else {
+                    break;
+                };
+                let extra = ranges.saturating_mul(2) + if frame_type == 0x03 { 3 } else { 0 };
+                if (0..extra).any(|_| skip().is_none()) {
+                    break;
+                }
+            }
+            // CRYPTO
+            0x06 => {
+                let (Some(offset), Some(len)) = (
+                    read_varint(payload, &mut position),
+                    read_varint(payload, &mut position),
+                ) else {
+                    break;
+                };
+                let end = position.saturating_add(len as usize).min(payload.len());
+                frames.push((offset, payload[position..end].to_vec()));
+                position = end;
+            }
+            _ => break,
+        }
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("valid hex"))
+            .collect()
+    }
+
+    #[test]
+    fn client_initial_keys_match_rfc9001_vectors() {
+        let keys = InitialKeys::client(&hex("8394c8f03e515708"));
+        assert_eq!(keys.key.to_vec(), hex("1f369613dd76d5467730efcbe3b1a22d"));
+        assert_eq!(keys.iv.to_vec(), hex("fa044b2f42a3fd3b46fb255c"));
+        assert_eq!(keys.hp.to_vec(), hex("9f50449e04a0e810283a1e9933adedd2"));
+    }
+
+    /// Builds a protected client Initial the way a QUIC client would, minus a
+    /// valid AEAD tag (which the reader ignores).
+    fn protect_initial(dcid: &[u8], packet_number: u16, frames: &[u8]) -> Vec<u8> {
+        let keys = InitialKeys::client(dcid);
+        let mut packet = vec![0xC1, 0x00, 0x00, 0x00, 0x01, dcid.len() as u8];
+        packet.extend_from_slice(dcid);
+        packet.push(0); // SCID length
+        packet.push(0); // Token length
+        let length = 2 + frames.len() + AEAD_TAG_LEN;
+        packet.extend_from_slice(&(0x4000u16 | length as u16).to_be_bytes());
+        let pn_offset = packet.len();
+        packet.extend_from_slice(&packet_number.to_be_bytes());
+
+        let mut payload = frames.to_vec();
+        let mut nonce = keys.iv;
+        for (i, b) in u64::from(packet_number).to_be_bytes().iter().enumerate() {
+            nonce[4 + i] ^= b;
+        }
+        let mut counter_block = [0u8; 16];
+        counter_block[..12].copy_from_slice(&nonce);
+        counter_block[15] = 2;
+        Aes128Ctr::new(
+            GenericArray::from_slice(&keys.key),
+            GenericArray::from_slice(&counter_block),
+        )
+        .apply_keystream(&mut payload);
+        packet.extend_from_slice(&payload);
+        packet.extend_from_slice(&[0u8; AEAD_TAG_LEN]);
+
+        let mut mask =
+            GenericArray::clone_from_slice(&packet[pn_offset + 4..pn_offset + 4 + HP_SAMPLE_LEN]);
+        Aes128::new(GenericArray::from_slice(&keys.hp)).encrypt_block(&mut mask);
+        packet[0] ^= mask[0] & 0x0f;
+        packet[pn_offset] ^= mask[1];
+        packet[pn_offset + 1] ^= mask[2];
+        packet
+    }
+
+    #[test]
+    fn reads_crypto_frames_from_protected_initial() {
+        let hello =
+            crate::throughput_tracker::flow_data::flow_analysis::tls_sni::build_client_hello(
+                "quic.example.org",
+            );
+        let mut frames = vec![0x06, 0x00];
+        frames.extend_from_slice(&(0x4000u16 | hello.len() as u16).to_be_bytes());
+        frames.extend_from_slice(&hello);
+        frames.extend_from_slice(&[0u8; 64]); // PADDING
+
+        let packet = protect_initial(&hex("8394c8f03e515708"), 2, &frames);
+        let crypto = initial_crypto_frames(&packet).expect("initial should decode");
+        assert_eq!(crypto, vec![(0, hello)]);
+    }
+
+    #[test]
+    fn short_header_packets_are_ignored() {
+        assert!(initial_crypto_frames(&[0x40, 0x01, 0x02, 0x03]).is_none());
+    }
+}
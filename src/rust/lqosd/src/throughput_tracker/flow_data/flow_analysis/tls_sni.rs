@@ -0,0 +1,241 @@
+//! Extracts the Server Name Indication (SNI) from a TLS ClientHello.
+//!
+//! Payload samples only contain the first bytes of a flow, so the parsers
+//! accept truncated input and succeed as long as the `server_name`
+//! extension itself was captured.
+
+/// Outcome of scanning a (possibly partial) ClientHello.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SniScan {
+    /// The server name was found.
+    Found(String),
+    /// The data looks like a ClientHello, but more bytes are needed.
+    NeedMore,
+    /// The data is not a ClientHello, or has no server name.
+    NotPresent,
+}
+
+const TLS_HANDSHAKE_RECORD: u8 = 0x16;
+const CLIENT_HELLO: u8 = 0x01;
+const SERVER_NAME_EXTENSION: u16 = 0x0000;
+const HOST_NAME_TYPE: u8 = 0x00;
+
+/// Scans a TCP byte stream that starts with TLS records.
+///
+/// Handshake bytes are gathered from consecutive handshake records, so a
+/// ClientHello split across records (e.g. by large post-quantum key shares)
+/// is still readable.
+pub fn scan_tls_stream(stream: &[u8]) -> SniScan {
+    let mut handshake = Vec::new();
+    let mut cursor = 0usize;
+    while cursor < stream.len() {
+        if stream[cursor] != TLS_HANDSHAKE_RECORD {
+            if handshake.is_empty() {
+                return SniScan::NotPresent;
+            }
+            break;
+        }
+        let Some(header) = stream.get(cursor..cursor + 5) else {
+            break;
+        };
+        if header[1] != 0x03 {
+            return SniScan::NotPresent;
+        }
+        let record_len = u16::from_be_bytes([header[3], header[4]]) as usize;
+        let body_start = cursor + 5;
+        let body_end = (body_start + record_len).min(stream.len());
+        handshake.extend_from_slice(&stream[body_start..body_end]);
+        cursor = body_start + record_len;
+    }
+    if handshake.is_empty() {
+        return SniScan::NeedMore;
+    }
+    scan_handshake(&handshake)
+}
+
+/// Scans raw handshake messages, as carried in QUIC CRYPTO frames.
+pub fn scan_handshake(handshake: &[u8]) -> SniScan {
+    let mut reader = Reader::new(handshake);
+    match reader.u8() {
+        Some(CLIENT_HELLO) => {}
+        Some(_) => return SniScan::NotPresent,
+        None => return SniScan::NeedMore,
+    }
+    // Handshake length (24 bits), legacy version, random
+    if reader.skip(3 + 2 + 32).is_none() {
+        return SniScan::NeedMore;
+    }
+    // Session id, cipher suites, compression methods
+    let Some(session_len) = reader.u8() else {
+        return SniScan::NeedMore;
+    };
+    if reader.skip(session_len as usize).is_none() {
+        return SniScan::NeedMore;
+    }
+    let Some(suites_len) = reader.u16() else {
+        return SniScan::NeedMore;
+    };
+    if reader.skip(suites_len as usize).is_none() {
+        return SniScan::NeedMore;
+    }
+    let Some(compression_len) = reader.u8() else {
+        return SniScan::NeedMore;
+    };
+    if reader.skip(compression_len as usize).is_none() {
+        return SniScan::NeedMore;
+    }
+    let Some(extensions_len) = reader.u16() else {
+        return SniScan::NeedMore;
+    };
+    let extensions_end = reader.position + extensions_len as usize;
+    while reader.position < extensions_end {
+        let (Some(extension_type), Some(extension_len)) = (reader.u16(), reader.u16()) else {
+            return SniScan::NeedMore;
+        };
+        if extension_type != SERVER_NAME_EXTENSION {
+            if reader.skip(extension_len as usize).is_none() {
+                return SniScan::NeedMore;
+            }
+            continue;
+        }
+        let Some(extension) = reader.take(extension_len as usize) else {
+            return SniScan::NeedMore;
+        };
+        return parse_server_name_list(extension).map_or(SniScan::NotPresent, SniScan::Found);
+    }
+    SniScan::NotPresent
+}
+
+fn parse_server_name_list(extension: &[u8]) -> Option<String> {
+    let mut reader = Reader::new(extension);
+    let list_len = reader.u16()? as usize;
+    let list_end = reader.position + list_len;
+    while reader.position < list_end {
+        let name_type = reader.u8()?;
+        let name_len = reader.u16()? as usize;
+        let name = reader.take(name_len)?;
+        if name_type == HOST_NAME_TYPE {
+            return normalize_hostname(name);
+        }
+    }
+    None
+}
+
+/// Lower-cases a host name and rejects anything that is not printable DNS text.
+pub fn normalize_hostname(raw: &[u8]) -> Option<String> {
+    let name = std::str::from_utf8(raw).ok()?.trim_end_matches('.');
+    if name.is_empty()
+        || name.len() > 253
+        || !name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.' || b == b'_')
+    {
+        return None;
+    }
+    Some(name.to_ascii_lowercase())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self
+            .data
+            .get(self.position..self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+}
+
+#[cfg(test)]
+pub(super) fn build_client_hello(server_name: &str) -> Vec<u8> {
+    let mut sni = Vec::new();
+    let name = server_name.as_bytes();
+    sni.extend_from_slice(&((name.len() + 3) as u16).to_be_bytes());
+    sni.push(HOST_NAME_TYPE);
+    sni.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    sni.extend_from_slice(name);
+
+    let mut extensions = Vec::new();
+    // An unrelated extension first (supported_groups)
+    extensions.extend_from_slice(&[0x00, 0x0a, 0x00, 0x04, 0x00, 0x02, 0x00, 0x1d]);
+    extensions.extend_from_slice(&SERVER_NAME_EXTENSION.to_be_bytes());
+    extensions.extend_from_slice(&(sni.len() as u16).to_be_bytes());
+    extensions.extend_from_slice(&sni);
+
+    let mut body = vec![0x03, 0x03];
+    body.extend_from_slice(&[0xAB; 32]);
+    body.push(0);
+    body.extend_from_slice(&[0x00, 0x02, 0x13, 0x01]);
+    body.extend_from_slice(&[0x01, 0x00]);
+    body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+    body.extend_from_slice(&extensions);
+
+    let mut handshake = vec![CLIENT_HELLO];
+    handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend_from_slice(&body);
+    handshake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn as_record(handshake: &[u8]) -> Vec<u8> {
+        let mut record = vec![TLS_HANDSHAKE_RECORD, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(handshake);
+        record
+    }
+
+    #[test]
+    fn finds_sni_in_client_hello_record() {
+        let record = as_record(&build_client_hello("Video.Example.COM"));
+        assert_eq!(
+            scan_tls_stream(&record),
+            SniScan::Found("video.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn truncated_client_hello_needs_more() {
+        let record = as_record(&build_client_hello("video.example.com"));
+        assert_eq!(scan_tls_stream(&record[..60]), SniScan::NeedMore);
+    }
+
+    #[test]
+    fn non_tls_payload_is_rejected() {
+        assert_eq!(scan_tls_stream(b"GET / HTTP/1.1\r\n"), SniScan::NotPresent);
+    }
+
+    #[test]
+    fn client_hello_split_across_records_is_reassembled() {
+        let handshake = build_client_hello("split.example.net");
+        let (first, second) = handshake.split_at(50);
+        let mut stream = as_record(first);
+        stream.extend_from_slice(&as_record(second));
+        assert_eq!(
+            scan_tls_stream(&stream),
+            SniScan::Found("split.example.net".to_string())
+        );
+    }
+}
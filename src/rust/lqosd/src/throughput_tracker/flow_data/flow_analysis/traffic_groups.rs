@@ -0,0 +1,175 @@
+//! Matches flow remote addresses against the operator's `[[traffic_groups]]`
+//! (e.g. "Netflix OCA", "IX peers", "on-net").
+//!
+//! The classifier is rebuilt when the configured groups change, checked at
+//! most once a minute. Prefix matches win over ASN matches.
+
+use allocative_derive::Allocative;
+use fxhash::FxHashMap;
+use ip_network::IpNetwork;
+use ip_network_table::IpNetworkTable;
+use lqos_config::TrafficGroupConfig;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Interned traffic group identifier. Ids are never reused, so flows keep a
+/// valid group across configuration reloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Allocative)]
+pub struct TrafficGroupId(u16);
+
+struct GroupClassifier {
+    asns: FxHashMap<u32, TrafficGroupId>,
+    prefixes: IpNetworkTable<TrafficGroupId>,
+}
+
+impl GroupClassifier {
+    fn new() -> Self {
+        Self {
+            asns: FxHashMap::default(),
+            prefixes: IpNetworkTable::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.asns.is_empty() && self.prefixes.is_empty()
+    }
+
+    fn lookup(&self, ip: IpAddr, asn: u32) -> Option<TrafficGroupId> {
+        if let Some((_, id)) = self.prefixes.longest_match(ip) {
+            return Some(*id);
+        }
+        self.asns.get(&asn).copied()
+    }
+}
+
+struct ClassifierState {
+    classifier: GroupClassifier,
+    loaded: Vec<TrafficGroupConfig>,
+    last_check: Option<Instant>,
+}
+
+static GROUPS: Lazy<RwLock<Vec<String>>> = Lazy::new(|| RwLock::new(Vec::new()));
+static CLASSIFIER: Lazy<Mutex<ClassifierState>> = Lazy::new(|| {
+    Mutex::new(ClassifierState {
+        classifier: GroupClassifier::new(),
+        loaded: Vec::new(),
+        last_check: None,
+    })
+});
+
+/// Returns the interned id for a group name, adding it if needed.
+fn intern(name: &str) -> Option<TrafficGroupId> {
+    let mut groups = GROUPS.write();
+    if let Some(index) = groups.iter().position(|group| group == name) {
+        return u16::try_from(index).ok().map(TrafficGroupId);
+    }
+    let id = u16::try_from(groups.len()).ok()?;
+    groups.push(name.to_string());
+    Some(TrafficGroupId(id))
+}
+
+impl TrafficGroupId {
+    /// Display name of the group.
+    pub fn name(&self) -> String {
+        GROUPS
+            .read()
+            .get(self.0 as usize)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+fn build_classifier(groups: &[TrafficGroupConfig]) -> GroupClassifier {
+    let mut classifier = GroupClassifier::new();
+    for group in groups {
+        let name = group.name.trim();
+        let Some(id) = intern(name) else {
+            warn!("Too many traffic groups; ignoring {name}");
+            continue;
+        };
+        for asn in &group.asns {
+            classifier.asns.insert(*asn, id);
+        }
+        for prefix in &group.prefixes {
+            match prefix.trim().parse::<IpNetwork>() {
+                Ok(network) => {
+                    classifier.prefixes.insert(network, id);
+                }
+                Err(_) => warn!("Invalid prefix {prefix} for traffic group {name}"),
+            }
+        }
+    }
+    classifier
+}
+
+/// Rebuilds the classifier on first use and whenever the configured groups change.
+fn refresh(state: &mut ClassifierState) {
+    if state
+        .last_check
+        .is_some_and(|checked| checked.elapsed() < RELOAD_CHECK_INTERVAL)
+    {
+        return;
+    }
+    state.last_check = Some(Instant::now());
+    let Ok(config) = lqos_config::load_config() else {
+        return;
+    };
+    if config.traffic_groups == state.loaded {
+        return;
+    }
+    state.classifier = build_classifier(&config.traffic_groups);
+    state.loaded = config.traffic_groups.clone();
+    info!("Loaded {} traffic groups", state.loaded.len());
+}
+
+/// True when at least one traffic group is configured.
+pub fn traffic_groups_enabled() -> bool {
+    let mut state = CLASSIFIER.lock();
+    refresh(&mut state);
+    !state.classifier.is_empty()
+}
+
+/// Finds the traffic group for a remote address, by prefix first and then ASN.
+pub fn traffic_group_for_address(ip: IpAddr, asn: u32) -> Option<TrafficGroupId> {
+    let mut state = CLASSIFIER.lock();
+    refresh(&mut state);
+    state.classifier.lookup(ip, asn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_win_over_asns() {
+        let classifier = build_classifier(&[
+            TrafficGroupConfig {
+                name: "Group Test Peers".to_string(),
+                prefixes: Vec::new(),
+                asns: vec![64500],
+            },
+            TrafficGroupConfig {
+                name: "Group Test Cache".to_string(),
+                prefixes: vec!["198.51.100.0/24".to_string()],
+                asns: Vec::new(),
+            },
+        ]);
+        let cache: IpAddr = "198.51.100.9".parse().expect("valid address");
+        let other: IpAddr = "203.0.113.9".parse().expect("valid address");
+        assert_eq!(
+            classifier.lookup(cache, 64500).map(|id| id.name()),
+            Some("Group Test Cache".to_string())
+        );
+        assert_eq!(
+            classifier.lookup(other, 64500).map(|id| id.name()),
+            Some("Group Test Peers".to_string())
+        );
+        assert!(classifier.lookup(other, 64501).is_none());
+    }
+}
@@ -0,0 +1,217 @@
+//! Optional on-disk archive of finished flows, so that questions such as
+//! "who talked to X at time T" can be answered long after a flow has left
+//! `RECENT_FLOWS`.
+//!
+//! Enabled by the `[flow_archive]` configuration section (read at startup).
+//! Flows with either endpoint in `do_not_track_subnets` are never archived.
+//! Queries read the files directly, so they are served from the bus and the
+//! local API without involving the writer thread.
+
+mod storage;
+
+use super::flow_analysis::{FlowAnalysis, finished_flow_circuit_hash, subnet_table};
+use super::{FlowbeeLocalData, get_asn_name_by_id};
+use anyhow::{Result, bail};
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use ip_network_table::IpNetworkTable;
+use lqos_bus::{FlowArchiveQuery, FlowArchiveRecord};
+use lqos_config::{Config, FlowArchiveConfig};
+use lqos_sys::flowbee_data::FlowbeeKey;
+use lqos_utils::XdpIpAddress;
+use lqos_utils::units::DownUpOrder;
+use lqos_utils::unix_time::{boot_time_nanos_to_unix_now, unix_now};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use storage::{ArchiveFilter, ArchiveStore, ArchivedFlow};
+use tracing::{debug, error, info, warn};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+const FLUSH_FLOWS: usize = 4096;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(600);
+const DEFAULT_QUERY_LIMIT: usize = 1000;
+const MAX_QUERY_LIMIT: usize = 100_000;
+
+fn archive_directory(config: &Config, archive: &FlowArchiveConfig) -> PathBuf {
+    archive.resolved_directory(&config.resolved_state_directory())
+}
+
+fn is_excluded(excluded: &IpNetworkTable<bool>, ip: &XdpIpAddress) -> bool {
+    let lookup = match ip.as_ip() {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    };
+    excluded.longest_match(lookup).is_some()
+}
+
+fn archived_flow(
+    key: &FlowbeeKey,
+    data: &FlowbeeLocalData,
+    analysis: &FlowAnalysis,
+) -> ArchivedFlow {
+    let circuit_hash = finished_flow_circuit_hash(key, data);
+    let catalog = lqos_network_devices::shaped_devices_catalog();
+    let (circuit_id, circuit_name) = match catalog.device_by_hashes(data.device_hash, circuit_hash)
+    {
+        Some(device) => (device.circuit_id.clone(), device.circuit_name.clone()),
+        None => (
+            data.circuit_id_hint.clone().unwrap_or_default(),
+            String::new(),
+        ),
+    };
+    ArchivedFlow {
+        start: boot_time_nanos_to_unix_now(data.start_time).unwrap_or(0),
+        end: boot_time_nanos_to_unix_now(data.last_seen).unwrap_or(0),
+        local_ip: key.local_ip.as_ip(),
+        remote_ip: key.remote_ip.as_ip(),
+        src_port: key.src_port,
+        dst_port: key.dst_port,
+        ip_protocol: key.ip_protocol,
+        bytes_down: data.bytes_sent.down,
+        bytes_up: data.bytes_sent.up,
+        packets_down: data.packets_sent.down,
+        packets_up: data.packets_sent.up,
+        tcp_retransmits_down: data.tcp_retransmits.down,
+        tcp_retransmits_up: data.tcp_retransmits.up,
+        circuit_hash,
+        circuit_id,
+        circuit_name,
+        remote_asn: analysis.asn_id.0,
+        application: analysis.label(),
+        remote_hostname: data.remote_hostname.clone().unwrap_or_default(),
+    }
+}
+
+fn record_from_archived(flow: ArchivedFlow) -> FlowArchiveRecord {
+    FlowArchiveRecord {
+        start: flow.start,
+        end: flow.end,
+        local_ip: flow.local_ip,
+        remote_ip: flow.remote_ip,
+        src_port: flow.src_port,
+        dst_port: flow.dst_port,
+        ip_protocol: flow.ip_protocol,
+        bytes: DownUpOrder::new(flow.bytes_down, flow.bytes_up),
+        packets: DownUpOrder::new(flow.packets_down, flow.packets_up),
+        tcp_retransmits: DownUpOrder::new(flow.tcp_retransmits_down, flow.tcp_retransmits_up),
+        circuit_id: flow.circuit_id,
+        circuit_name: flow.circuit_name,
+        remote_asn: flow.remote_asn,
+        remote_asn_name: get_asn_name_by_id(flow.remote_asn),
+        application: flow.application,
+        remote_hostname: flow.remote_hostname,
+    }
+}
+
+pub struct FlowArchive {}
+
+impl FlowArchive {
+    /// Starts the archive writer and returns the endpoint that finished flows are sent to.
+    pub fn start(
+        config: &Config,
+        archive: &FlowArchiveConfig,
+    ) -> Result<Sender<(FlowbeeKey, (FlowbeeLocalData, FlowAnalysis))>> {
+        let (tx, rx) =
+            crossbeam_channel::bounded::<(FlowbeeKey, (FlowbeeLocalData, FlowAnalysis))>(65535);
+        let directory = archive_directory(config, archive);
+        let excluded = match config
+            .flows
+            .as_ref()
+            .and_then(|flows| flows.do_not_track_subnets.as_ref())
+        {
+            Some(subnets) => subnet_table(subnets, "do_not_track_subnets"),
+            None => IpNetworkTable::new(),
+        };
+        let retention_seconds = u64::from(archive.retention_days) * 86_400;
+        let max_bytes = archive.max_size_bytes();
+        info!("Archiving finished flows to {}", directory.display());
+        let store = ArchiveStore::new(directory);
+
+        std::thread::Builder::new()
+            .name("Flow Archive".to_string())
+            .spawn(move || {
+                debug!("Created Flow Archive Endpoint");
+                let mut pending = Vec::with_capacity(FLUSH_FLOWS);
+                let mut last_flush = Instant::now();
+                let mut last_prune: Option<Instant> = None;
+                loop {
+                    let disconnected = match rx.recv_timeout(Duration::from_secs(1)) {
+                        Ok((key, (data, analysis))) => {
+                            if !is_excluded(&excluded, &key.local_ip)
+                                && !is_excluded(&excluded, &key.remote_ip)
+                            {
+                                pending.push(archived_flow(&key, &data, &analysis));
+                            }
+                            false
+                        }
+                        Err(RecvTimeoutError::Timeout) => false,
+                        Err(RecvTimeoutError::Disconnected) => true,
+                    };
+
+                    if pending.len() >= FLUSH_FLOWS
+                        || (!pending.is_empty()
+                            && (disconnected || last_flush.elapsed() >= FLUSH_INTERVAL))
+                    {
+                        if let Err(e) = store.append(std::mem::take(&mut pending)) {
+                            warn!("Unable to write to the flow archive: {e}");
+                        }
+                        last_flush = Instant::now();
+                    }
+                    if disconnected {
+                        break;
+                    }
+
+                    if last_prune.is_none_or(|t| t.elapsed() >= PRUNE_INTERVAL) {
+                        if let Ok(now) = unix_now()
+                            && let Err(e) =
+                                store.prune(now.saturating_sub(retention_seconds), max_bytes, now)
+                        {
+                            warn!("Unable to prune the flow archive: {e}");
+                        }
+                        last_prune = Some(Instant::now());
+                    }
+                }
+                error!("Flow Archive thread has stopped");
+            })?;
+
+        Ok(tx)
+    }
+}
+
+/// Searches the finished-flow archive. Fails if the archive is not enabled.
+pub fn query_flow_archive(query: &FlowArchiveQuery) -> Result<Vec<FlowArchiveRecord>> {
+    let config = lqos_config::load_config()?;
+    let Some(archive) = config
+        .flow_archive
+        .as_ref()
+        .filter(|archive| archive.enabled)
+    else {
+        bail!("The flow archive is not enabled");
+    };
+    if query.end < query.start {
+        bail!("The query end time is before its start time");
+    }
+    let filter = ArchiveFilter {
+        circuit_hash: query
+            .circuit_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(lqos_utils::hash_to_i64),
+        ip: query.ip,
+        asn: query.asn,
+        port: query.port,
+        start: query.start,
+        end: query.end,
+        limit: query
+            .limit
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .clamp(1, MAX_QUERY_LIMIT),
+    };
+    let store = ArchiveStore::new(archive_directory(&config, archive));
+    Ok(store
+        .query(&filter)?
+        .into_iter()
+        .map(record_from_archived)
+        .collect())
+}
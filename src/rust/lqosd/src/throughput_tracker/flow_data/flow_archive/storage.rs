@@ -0,0 +1,457 @@
+//! On-disk layout of the finished-flow archive.
+//!
+//! Flows are partitioned by the UTC hour in which they ended. Each hour has a
+//! `.flows` file of gzip-compressed bincode frames and an `.index` file with
+//! one length-prefixed entry per frame. Index entries carry the frame's time
+//! span and the circuits, addresses, ASNs and ports it contains, so queries
+//! only decompress frames that can match. The data frame is always written
+//! before its index entry, so a crash leaves at most an unreferenced frame.
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use tracing::warn;
+
+const FORMAT_VERSION: u8 = 1;
+const SECONDS_PER_HOUR: u64 = 3600;
+const DATA_EXTENSION: &str = "flows";
+const INDEX_EXTENSION: &str = "index";
+
+/// One completed flow, as stored in the archive.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct ArchivedFlow {
+    pub start: u64,
+    pub end: u64,
+    pub local_ip: IpAddr,
+    pub remote_ip: IpAddr,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub ip_protocol: u8,
+    pub bytes_down: u64,
+    pub bytes_up: u64,
+    pub packets_down: u64,
+    pub packets_up: u64,
+    pub tcp_retransmits_down: u16,
+    pub tcp_retransmits_up: u16,
+    pub circuit_hash: Option<i64>,
+    pub circuit_id: String,
+    pub circuit_name: String,
+    pub remote_asn: u32,
+    pub application: String,
+    pub remote_hostname: String,
+}
+
+/// Summary of one data frame, used to skip frames during queries.
+#[derive(Debug, Serialize, Deserialize)]
+struct FrameIndex {
+    version: u8,
+    offset: u64,
+    length: u64,
+    min_start: u64,
+    max_end: u64,
+    circuits: Vec<i64>,
+    ips: Vec<IpAddr>,
+    asns: Vec<u32>,
+    ports: Vec<u16>,
+}
+
+impl FrameIndex {
+    fn new(offset: u64, length: u64, flows: &[ArchivedFlow]) -> Self {
+        let mut index = Self {
+            version: FORMAT_VERSION,
+            offset,
+            length,
+            min_start: flows.iter().map(|f| f.start).min().unwrap_or(0),
+            max_end: flows.iter().map(|f| f.end).max().unwrap_or(0),
+            circuits: flows.iter().filter_map(|f| f.circuit_hash).collect(),
+            ips: flows
+                .iter()
+                .flat_map(|f| [f.local_ip, f.remote_ip])
+                .collect(),
+            asns: flows.iter().map(|f| f.remote_asn).collect(),
+            ports: flows
+                .iter()
+                .flat_map(|f| [f.src_port, f.dst_port])
+                .collect(),
+        };
+        index.circuits.sort_unstable();
+        index.circuits.dedup();
+        index.ips.sort_unstable();
+        index.ips.dedup();
+        index.asns.sort_unstable();
+        index.asns.dedup();
+        index.ports.sort_unstable();
+        index.ports.dedup();
+        index
+    }
+}
+
+/// Query filters. Unset filters match every flow.
+#[derive(Debug, Clone, Default)]
+pub(super) struct ArchiveFilter {
+    pub circuit_hash: Option<i64>,
+    pub ip: Option<IpAddr>,
+    pub asn: Option<u32>,
+    pub port: Option<u16>,
+    /// Flows that ended before this time (unix seconds) are excluded.
+    pub start: u64,
+    /// Flows that started after this time (unix seconds) are excluded.
+    pub end: u64,
+    pub limit: usize,
+}
+
+impl ArchiveFilter {
+    fn matches_frame(&self, frame: &FrameIndex) -> bool {
+        frame.version == FORMAT_VERSION
+            && frame.min_start <= self.end
+            && frame.max_end >= self.start
+            && self
+                .circuit_hash
+                .is_none_or(|hash| frame.circuits.binary_search(&hash).is_ok())
+            && self
+                .ip
+                .is_none_or(|ip| frame.ips.binary_search(&ip).is_ok())
+            && self
+                .asn
+                .is_none_or(|asn| frame.asns.binary_search(&asn).is_ok())
+            && self
+                .port
+                .is_none_or(|port| frame.ports.binary_search(&port).is_ok())
+    }
+
+    fn matches(&self, flow: &ArchivedFlow) -> bool {
+        flow.start <= self.end
+            && flow.end >= self.start
+            && self
+                .circuit_hash
+                .is_none_or(|hash| flow.circuit_hash == Some(hash))
+            && self
+                .ip
+                .is_none_or(|ip| flow.local_ip == ip || flow.remote_ip == ip)
+            && self.asn.is_none_or(|asn| flow.remote_asn == asn)
+            && self
+                .port
+                .is_none_or(|port| flow.src_port == port || flow.dst_port == port)
+    }
+}
+
+/// One hour of archived flows.
+struct Partition {
+    hour: u64,
+    size: u64,
+}
+
+/// A flow archive rooted at a directory.
+pub(super) struct ArchiveStore {
+    root: PathBuf,
+}
+
+impl ArchiveStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn data_path(&self, hour: u64) -> PathBuf {
+        self.root
+            .join(format!("{}.{DATA_EXTENSION}", hour * SECONDS_PER_HOUR))
+    }
+
+    fn index_path(&self, hour: u64) -> PathBuf {
+        self.root
+            .join(format!("{}.{INDEX_EXTENSION}", hour * SECONDS_PER_HOUR))
+    }
+
+    /// Appends flows to the partitions for the hours in which they ended.
+    pub fn append(&self, flows: Vec<ArchivedFlow>) -> std::io::Result<()> {
+        if flows.is_empty() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.root)?;
+        let mut by_hour: BTreeMap<u64, Vec<ArchivedFlow>> = BTreeMap::new();
+        for flow in flows {
+            by_hour
+                .entry(flow.end / SECONDS_PER_HOUR)
+                .or_default()
+                .push(flow);
+        }
+        for (hour, flows) in by_hour {
+            self.write_frame(hour, &flows)?;
+        }
+        Ok(())
+    }
+
+    fn write_frame(&self, hour: u64, flows: &[ArchivedFlow]) -> std::io::Result<()> {
+        let encoded = bincode::serialize(flows).map_err(std::io::Error::other)?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&encoded)?;
+        let compressed = encoder.finish()?;
+
+        let mut data = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.data_path(hour))?;
+        let offset = data.seek(SeekFrom::End(0))?;
+        data.write_all(&compressed)?;
+        data.sync_data()?;
+
+        let index = FrameIndex::new(offset, compressed.len() as u64, flows);
+        let index = bincode::serialize(&index).map_err(std::io::Error::other)?;
+        let length = u32::try_from(index.len()).map_err(std::io::Error::other)?;
+        let mut entry = Vec::with_capacity(index.len() + 4);
+        entry.extend_from_slice(&length.to_le_bytes());
+        entry.extend_from_slice(&index);
+        let mut index_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.index_path(hour))?;
+        index_file.write_all(&entry)
+    }
+
+    /// Lists partitions, oldest first.
+    fn partitions(&self) -> std::io::Result<Vec<Partition>> {
+        let mut partitions: BTreeMap<u64, u64> = BTreeMap::new();
+        let entries = match std::fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+                continue;
+            };
+            if extension != DATA_EXTENSION && extension != INDEX_EXTENSION {
+                continue;
+            }
+            let Some(hour) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u64>().ok())
+                .map(|start| start / SECONDS_PER_HOUR)
+            else {
+                continue;
+            };
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            *partitions.entry(hour).or_default() += size;
+        }
+        Ok(partitions
+            .into_iter()
+            .map(|(hour, size)| Partition { hour, size })
+            .collect())
+    }
+
+    fn remove_partition(&self, hour: u64) {
+        for path in [self.data_path(hour), self.index_path(hour)] {
+            if let Err(e) = std::fs::remove_file(&path)
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                warn!("Unable to remove flow archive file {}: {e}", path.display());
+            }
+        }
+    }
+
+    /// Deletes partitions that ended before `oldest` (unix seconds), then the
+    /// oldest remaining partitions until the archive fits in `max_bytes`. The
+    /// partition for `now` is never deleted.
+    pub fn prune(&self, oldest: u64, max_bytes: u64, now: u64) -> std::io::Result<()> {
+        let current_hour = now / SECONDS_PER_HOUR;
+        let mut partitions = self.partitions()?;
+        partitions.retain(|partition| {
+            let expired = (partition.hour + 1) * SECONDS_PER_HOUR <= oldest;
+            if expired && partition.hour != current_hour {
+                self.remove_partition(partition.hour);
+                return false;
+            }
+            true
+        });
+        let mut total: u64 = partitions.iter().map(|p| p.size).sum();
+        for partition in partitions {
+            if total <= max_bytes || partition.hour == current_hour {
+                break;
+            }
+            self.remove_partition(partition.hour);
+            total = total.saturating_sub(partition.size);
+        }
+        Ok(())
+    }
+
+    fn read_index(&self, hour: u64) -> std::io::Result<Vec<FrameIndex>> {
+        let bytes = std::fs::read(self.index_path(hour))?;
+        let mut frames = Vec::new();
+        let mut cursor = 0;
+        while cursor + 4 <= bytes.len() {
+            let mut length = [0u8; 4];
+            length.copy_from_slice(&bytes[cursor..cursor + 4]);
+            let length = u32::from_le_bytes(length) as usize;
+            let Some(entry) = bytes.get(cursor + 4..cursor + 4 + length) else {
+                // Truncated trailing entry from an interrupted write
+                break;
+            };
+            match bincode::deserialize::<FrameIndex>(entry) {
+                Ok(frame) => frames.push(frame),
+                Err(e) => {
+                    warn!("Skipping unreadable flow archive index entry: {e}");
+                }
+            }
+            cursor += 4 + length;
+        }
+        Ok(frames)
+    }
+
+    fn read_frame(data: &mut File, frame: &FrameIndex) -> std::io::Result<Vec<ArchivedFlow>> {
+        data.seek(SeekFrom::Start(frame.offset))?;
+        let mut decoder = GzDecoder::new(data.take(frame.length));
+        let mut encoded = Vec::new();
+        decoder.read_to_end(&mut encoded)?;
+        bincode::deserialize(&encoded).map_err(std::io::Error::other)
+    }
+
+    /// Returns matching flows ordered by end time, up to `filter.limit`.
+    pub fn query(&self, filter: &ArchiveFilter) -> std::io::Result<Vec<ArchivedFlow>> {
+        let mut results = Vec::new();
+        for partition in self.partitions()? {
+            // Everything in this partition ended before the query window opened
+            if (partition.hour + 1) * SECONDS_PER_HOUR <= filter.start {
+                continue;
+            }
+            let frames = match self.read_index(partition.hour) {
+                Ok(frames) => frames,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            let mut data: Option<File> = None;
+            for frame in frames.iter().filter(|f| filter.matches_frame(f)) {
+                let file = match &mut data {
+                    Some(file) => file,
+                    None => data.insert(File::open(self.data_path(partition.hour))?),
+                };
+                let flows = match Self::read_frame(file, frame) {
+                    Ok(flows) => flows,
+                    Err(e) => {
+                        warn!("Skipping unreadable flow archive frame: {e}");
+                        continue;
+                    }
+                };
+                results.extend(flows.into_iter().filter(|flow| filter.matches(flow)));
+            }
+            if results.len() >= filter.limit {
+                break;
+            }
+        }
+        results.sort_by_key(|flow| flow.end);
+        results.truncate(filter.limit);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow(end: u64, remote: &str, circuit: i64, port: u16) -> ArchivedFlow {
+        ArchivedFlow {
+            start: end.saturating_sub(30),
+            end,
+            local_ip: "100.64.0.10".parse().expect("valid address"),
+            remote_ip: remote.parse().expect("valid address"),
+            src_port: 51_000,
+            dst_port: port,
+            ip_protocol: 6,
+            bytes_down: 1000,
+            bytes_up: 100,
+            packets_down: 10,
+            packets_up: 5,
+            tcp_retransmits_down: 0,
+            tcp_retransmits_up: 0,
+            circuit_hash: Some(circuit),
+            circuit_id: format!("circuit-{circuit}"),
+            circuit_name: String::new(),
+            remote_asn: 64_500,
+            application: "HTTPS".to_string(),
+            remote_hostname: String::new(),
+        }
+    }
+
+    fn test_store(name: &str) -> ArchiveStore {
+        let root =
+            std::env::temp_dir().join(format!("lqos_flow_archive_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        ArchiveStore::new(root)
+    }
+
+    fn everything() -> ArchiveFilter {
+        ArchiveFilter {
+            end: u64::MAX,
+            limit: usize::MAX,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn query_filters_by_address_circuit_and_time() {
+        let store = test_store("query");
+        let hour = 1_760_000_400;
+        store
+            .append(vec![
+                flow(hour + 10, "192.0.2.1", 1, 443),
+                flow(hour + 20, "192.0.2.2", 2, 80),
+                flow(hour + 3700, "192.0.2.1", 2, 443),
+            ])
+            .expect("append succeeds");
+
+        let by_ip = store
+            .query(&ArchiveFilter {
+                ip: Some("192.0.2.1".parse().expect("valid address")),
+                ..everything()
+            })
+            .expect("query succeeds");
+        assert_eq!(by_ip.len(), 2);
+
+        let by_circuit_and_port = store
+            .query(&ArchiveFilter {
+                circuit_hash: Some(2),
+                port: Some(443),
+                ..everything()
+            })
+            .expect("query succeeds");
+        assert_eq!(by_circuit_and_port.len(), 1);
+        assert_eq!(by_circuit_and_port[0].end, hour + 3700);
+
+        let first_hour = store
+            .query(&ArchiveFilter {
+                start: hour,
+                end: hour + 100,
+                ..everything()
+            })
+            .expect("query succeeds");
+        assert_eq!(first_hour.len(), 2);
+        let _ = std::fs::remove_dir_all(&store.root);
+    }
+
+    #[test]
+    fn prune_removes_expired_and_oversized_hours() {
+        let store = test_store("prune");
+        let hour = 1_760_000_400;
+        for offset in 0..3 {
+            store
+                .append(vec![flow(hour + offset * 3600, "192.0.2.1", 1, 443)])
+                .expect("append succeeds");
+        }
+        store
+            .prune(hour + 3600, u64::MAX, hour + 2 * 3600)
+            .expect("prune succeeds");
+        assert_eq!(store.partitions().expect("list").len(), 2);
+
+        store.prune(0, 1, hour + 2 * 3600).expect("prune succeeds");
+        let remaining = store.partitions().expect("list");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].hour, (hour + 2 * 3600) / 3600);
+        let _ = std::fs::remove_dir_all(&store.root);
+    }
+}
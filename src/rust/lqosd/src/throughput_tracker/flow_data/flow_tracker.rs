@@ -61,6 +61,10 @@ pub(crate) struct ActiveFlowDisplayFields {
     pub(crate) remote_asn_country: String,
     /// Flow protocol/application analysis label.
     pub(crate) analysis: String,
+    /// Application category, or empty if the application is unknown.
+    pub(crate) application_category: String,
+    /// Remote endpoint host name, or empty if unknown.
+    pub(crate) remote_hostname: String,
 }
 
 /// Copied view of an active flow for read-heavy UI, bus, and Insight paths.
@@ -110,7 +114,9 @@ impl ActiveFlowSnapshot {
                 remote_asn: analysis.asn_id.0,
                 remote_asn_name: String::new(),
                 remote_asn_country: String::new(),
-                analysis: analysis.protocol_analysis.to_string(),
+                analysis: analysis.label(),
+                application_category: analysis.category(),
+                remote_hostname: local.remote_hostname.clone().unwrap_or_default(),
             },
             bytes_sent: local.bytes_sent,
             packets_sent: local.packets_sent,
@@ -370,6 +376,8 @@ pub struct FlowbeeLocalData {
     pub device_hash: Option<i64>,
     /// Last-known circuit ID copied from the throughput table when catalog metadata is available.
     pub circuit_id_hint: Option<String>,
+    /// Host name of the remote endpoint, from the flow's TLS/QUIC handshake or passive DNS.
+    pub remote_hostname: Option<String>,
     /// TCP-only data. Boxed for now; TODO: use a slab/slot type setup for coherence in the future.
     pub tcp_info: Option<Box<FlowbeeLocalDataTcp>>,
 }
@@ -389,6 +397,9 @@ impl Serialize for FlowbeeLocalData {
         if let Some(display_rate_bps) = &self.display_rate_bps {
             state.serialize_field("display_rate_bps", display_rate_bps)?;
         }
+        if let Some(remote_hostname) = &self.remote_hostname {
+            state.serialize_field("remote_hostname", remote_hostname)?;
+        }
         state.serialize_field("tcp_retransmits", &self.tcp_retransmits)?;
         state.serialize_field("end_status", &self.end_status)?;
         state.serialize_field("tos", &self.tos)?;
@@ -431,6 +442,7 @@ impl FlowbeeLocalData {
                 Some(data.device_hash as i64)
             },
             circuit_id_hint: None,
+            remote_hostname: None,
             tcp_info: if key.ip_protocol == 6 {
                 Some(Box::new(FlowbeeLocalDataTcp {
                     flags: data.flags,
@@ -711,6 +723,8 @@ mod tests {
         let analysis = FlowAnalysis {
             asn_id: AsnId(64_512),
             protocol_analysis: FlowProtocol::Https,
+            application: None,
+            traffic_group: None,
         };
         (key, local, analysis)
     }
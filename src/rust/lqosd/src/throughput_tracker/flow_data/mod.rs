@@ -3,34 +3,45 @@
 
 mod asn_heatmap;
 mod flow_analysis;
+mod flow_archive;
 mod flow_tracker;
 mod netflow5;
 mod netflow9;
 mod netflow_common;
+mod traffic_group_totals;
 
 use crate::throughput_tracker::flow_data::{
-    flow_analysis::FinishedFlowAnalysis, netflow5::Netflow5, netflow9::Netflow9,
+    flow_analysis::FinishedFlowAnalysis, flow_archive::FlowArchive, netflow5::Netflow5,
+    netflow9::Netflow9,
 };
 use anyhow::Result;
 pub(crate) use asn_heatmap::{AsnAggregate, snapshot_asn_heatmaps, update_asn_heatmaps};
 use crossbeam_channel::Sender;
 pub(crate) use flow_analysis::{
-    AsnCountryListEntry, AsnListEntry, AsnProtocolListEntry, FlowActor, FlowAnalysis,
-    FlowbeeEffectiveDirection, RECENT_FLOWS, RttBuffer, RttData, expire_rtt_flows,
-    flowbee_handle_events, flowbee_rtt_map, get_asn_name_by_id, get_flowbee_event_count_and_reset,
-    get_rtt_events_per_second, setup_flow_analysis,
+    AppId, AsnCountryListEntry, AsnListEntry, AsnProtocolListEntry, FlowActor, FlowAnalysis,
+    FlowbeeEffectiveDirection, RECENT_FLOWS, RttBuffer, RttData, asn_prefixes, dns_application,
+    expire_rtt_flows, flow_hostname, flowbee_handle_events, flowbee_payload_handle_events,
+    flowbee_rtt_map, get_asn_name_by_id, get_flowbee_event_count_and_reset,
+    get_rtt_events_per_second, passive_dns_enabled, reload_passive_dns, setup_flow_analysis, take_flow_hostnames,
+    traffic_groups_enabled,
+};
+pub(crate) use flow_archive::query_flow_archive;
+pub(crate) use flow_tracker::{
+    ActiveFlowDisplayFields, ActiveFlowSnapshot, AsnId, FlowbeeLocalData, active_flow_snapshot,
+    for_each_active_flow_for_circuit, live_active_flow_count, retry_times_to_unix_seconds,
 };
 #[cfg(test)]
 pub(crate) use flow_tracker::{
     active_flow_test_lock, replace_active_flows_for_test, replace_active_flows_live_for_test,
 };
-pub(crate) use flow_tracker::{
-    ActiveFlowDisplayFields, ActiveFlowSnapshot, AsnId, FlowbeeLocalData, active_flow_snapshot,
-    for_each_active_flow_for_circuit, live_active_flow_count, retry_times_to_unix_seconds,
+pub(in crate::throughput_tracker) use flow_tracker::{
+    mutate_all_flows, refresh_active_flow_snapshot,
 };
-pub(in crate::throughput_tracker) use flow_tracker::{mutate_all_flows, refresh_active_flow_snapshot};
 use lqos_sys::flowbee_data::FlowbeeKey;
 use tracing::{debug, error, info};
+pub(crate) use traffic_group_totals::{
+    TrafficGroupAggregate, traffic_group_report, traffic_group_totals, update_traffic_groups,
+};
 
 // Creates the netflow tracker and returns the sender
 pub fn setup_netflow_tracker() -> Result<Sender<(FlowbeeKey, (FlowbeeLocalData, FlowAnalysis))>> {
@@ -49,6 +60,16 @@ pub fn setup_netflow_tracker() -> Result<Sender<(FlowbeeKey, (FlowbeeLocalData,
                 Vec::new();
             endpoints.push(FinishedFlowAnalysis::start());
 
+            if let Some(archive) = config.flow_archive.as_ref().filter(|a| a.enabled) {
+                match FlowArchive::start(&config, archive) {
+                    Ok(endpoint) => {
+                        endpoints.push(endpoint);
+                        info!("Flow archive endpoint added");
+                    }
+                    Err(e) => error!("Unable to start the flow archive: {e}"),
+                }
+            }
+
             if let Some(flow_config) = &config.flows
                 && let (Some(ip), Some(port), Some(version)) = (
                     flow_config.netflow_ip.clone(),
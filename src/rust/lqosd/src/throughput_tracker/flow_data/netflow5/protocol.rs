@@ -179,6 +179,7 @@ mod tests {
             circuit_hash: None,
             device_hash: None,
             circuit_id_hint: None,
+            remote_hostname: None,
             tcp_info: None,
         }
     }
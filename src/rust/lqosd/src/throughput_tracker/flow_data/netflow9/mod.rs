@@ -1,5 +1,5 @@
 use self::protocol::to_netflow_9;
-use super::{FlowAnalysis, FlowbeeLocalData};
+use super::{FlowAnalysis, FlowbeeLocalData, passive_dns_enabled};
 use crate::throughput_tracker::flow_data::netflow9::protocol::{
     header::Netflow9Header, template_ipv4::template_data_ipv4, template_ipv6::template_data_ipv6,
 };
@@ -68,8 +68,10 @@ impl Netflow9 {
                 std::mem::size_of::<Netflow9Header>(),
             )
         };
-        let template1 = template_data_ipv4();
-        let template2 = template_data_ipv6();
+        // Host names are only advertised while passive DNS is on
+        let named = passive_dns_enabled();
+        let template1 = template_data_ipv4(named);
+        let template2 = template_data_ipv6(named);
         let mut buffer = Vec::with_capacity(
             header_bytes.len() + template1.len() + template2.len() + (num_records as usize * 140),
         );
@@ -77,8 +79,8 @@ impl Netflow9 {
         buffer.extend_from_slice(&template1);
         buffer.extend_from_slice(&template2);
 
-        for (key, (data, _)) in accumulator {
-            if let Ok((packet1, packet2)) = to_netflow_9(key, data) {
+        for (key, (data, analysis)) in accumulator {
+            if let Ok((packet1, packet2)) = to_netflow_9(key, data, analysis, named) {
                 buffer.extend_from_slice(&packet1);
                 buffer.extend_from_slice(&packet2);
             }
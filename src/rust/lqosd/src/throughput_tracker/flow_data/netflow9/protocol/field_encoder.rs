@@ -1,5 +1,5 @@
 use super::field_types::*;
-use crate::throughput_tracker::flow_data::FlowbeeLocalData;
+use crate::throughput_tracker::flow_data::{FlowAnalysis, FlowbeeLocalData};
 use lqos_sys::flowbee_data::FlowbeeKey;
 use std::net::IpAddr;
 
@@ -8,6 +8,7 @@ pub(crate) fn encode_fields_from_template(
     direction: usize,
     key: &FlowbeeKey,
     data: &FlowbeeLocalData,
+    analysis: &FlowAnalysis,
 ) -> anyhow::Result<Vec<u8>> {
     let src_port = if direction == 0 {
         key.src_port
@@ -34,6 +35,12 @@ pub(crate) fn encode_fields_from_template(
             IPV4_DST_ADDR => encode_ipv4(1, key, &mut result)?,
             IPV6_SRC_ADDR => encode_ipv6(0, key, &mut result)?,
             IPV6_DST_ADDR => encode_ipv6(1, key, &mut result)?,
+            APPLICATION_NAME => encode_fixed_string(&analysis.label(), *field_length, &mut result),
+            REMOTE_HOSTNAME => encode_fixed_string(
+                data.remote_hostname.as_deref().unwrap_or_default(),
+                *field_length,
+                &mut result,
+            ),
             _ => anyhow::bail!("Don't know how to encode field type {} yet", field_type),
         }
     }
@@ -48,6 +55,15 @@ fn encode_u16(value: u16, target: &mut Vec<u8>) {
     target.extend_from_slice(&value.to_be_bytes());
 }
 
+/// Writes a NUL-padded (and, if needed, truncated) fixed-length string.
+fn encode_fixed_string(value: &str, length: u16, target: &mut Vec<u8>) {
+    let length = length as usize;
+    let bytes = value.as_bytes();
+    let used = bytes.len().min(length);
+    target.extend_from_slice(&bytes[..used]);
+    target.resize(target.len() + length - used, 0);
+}
+
 fn encode_ipv4(direction: usize, key: &FlowbeeKey, target: &mut Vec<u8>) -> anyhow::Result<()> {
     let local = key.local_ip.as_ip();
     let remote = key.remote_ip.as_ip();
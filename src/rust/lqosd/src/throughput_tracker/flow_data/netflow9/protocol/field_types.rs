@@ -80,3 +80,7 @@ pub(crate) const IF_DESC: (u16, u16) = (83, 0);
 pub(crate) const SAMPLER_NAME: (u16, u16) = (84, 0);
 pub(crate) const IN_PERMANENT_BYTES: (u16, u16) = (85, 4);
 pub(crate) const IN_PERMANENT_PKTS: (u16, u16) = (86, 4);
+pub(crate) const APPLICATION_NAME: (u16, u16) = (96, 32);
+/// Remote host name. v9 has no host name field of its own, so this borrows
+/// the IPFIX `httpRequestHost` element, which IPFIX-aware collectors decode.
+pub(crate) const REMOTE_HOSTNAME: (u16, u16) = (460, 64);
@@ -5,7 +5,7 @@ use lqos_sys::flowbee_data::FlowbeeKey;
 mod field_types;
 use field_types::*;
 
-use crate::throughput_tracker::flow_data::FlowbeeLocalData;
+use crate::throughput_tracker::flow_data::{FlowAnalysis, FlowbeeLocalData};
 pub(crate) mod field_encoder;
 pub(crate) mod header;
 pub(crate) mod template_ipv4;
@@ -19,13 +19,21 @@ fn add_field(bytes: &mut Vec<u8>, field_type: u16, field_length: u16) {
 pub(crate) fn to_netflow_9(
     key: &FlowbeeKey,
     data: &FlowbeeLocalData,
+    analysis: &FlowAnalysis,
+    named: bool,
 ) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
     if key.local_ip.is_v4() && key.remote_ip.is_v4() {
         // Return IPv4 records
-        Ok((ipv4_record(key, data, 0)?, ipv4_record(key, data, 1)?))
+        Ok((
+            ipv4_record(key, data, analysis, 0, named)?,
+            ipv4_record(key, data, analysis, 1, named)?,
+        ))
     } else if (!key.local_ip.is_v4()) && (!key.remote_ip.is_v4()) {
         // Return IPv6 records
-        Ok((ipv6_record(key, data, 0)?, ipv6_record(key, data, 1)?))
+        Ok((
+            ipv6_record(key, data, analysis, 0, named)?,
+            ipv6_record(key, data, analysis, 1, named)?,
+        ))
     } else {
         anyhow::bail!("Mixing IPv4 and IPv6 is not supported");
     }
@@ -34,19 +42,18 @@ pub(crate) fn to_netflow_9(
 fn ipv4_record(
     key: &FlowbeeKey,
     data: &FlowbeeLocalData,
+    analysis: &FlowAnalysis,
     direction: usize,
+    named: bool,
 ) -> anyhow::Result<Vec<u8>> {
-    let field_bytes = field_encoder::encode_fields_from_template(
-        &template_ipv4::FIELDS_IPV4,
-        direction,
-        key,
-        data,
-    )?;
+    let (template_id, fields) = template_ipv4::fields_ipv4(named);
+    let field_bytes =
+        field_encoder::encode_fields_from_template(fields, direction, key, data, analysis)?;
 
     // Build the actual record
     let mut bytes = Vec::new();
-    // Add the flowset_id. Template ID is 256
-    bytes.extend_from_slice(&(256u16).to_be_bytes());
+    // Add the flowset_id, which is the template ID
+    bytes.extend_from_slice(&template_id.to_be_bytes());
 
     // Add the length. Length includes 2 bytes for flowset and 2 bytes for the length field
     // itself. That's odd.
@@ -70,19 +77,18 @@ fn ipv4_record(
 fn ipv6_record(
     key: &FlowbeeKey,
     data: &FlowbeeLocalData,
+    analysis: &FlowAnalysis,
     direction: usize,
+    named: bool,
 ) -> anyhow::Result<Vec<u8>> {
-    let field_bytes = field_encoder::encode_fields_from_template(
-        &template_ipv6::FIELDS_IPV6,
-        direction,
-        key,
-        data,
-    )?;
+    let (template_id, fields) = template_ipv6::fields_ipv6(named);
+    let field_bytes =
+        field_encoder::encode_fields_from_template(fields, direction, key, data, analysis)?;
 
     // Build the actual record
     let mut bytes = Vec::new();
-    // Add the flowset_id. Template ID is 257
-    bytes.extend_from_slice(&(257u16).to_be_bytes());
+    // Add the flowset_id, which is the template ID
+    bytes.extend_from_slice(&template_id.to_be_bytes());
 
     // Add the length. Length includes 2 bytes for flowset and 2 bytes for the length field
     // itself. That's odd.
@@ -100,3 +106,64 @@ fn ipv6_record(
 
     Ok(bytes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::throughput_tracker::flow_data::{AsnId, flow_analysis::FlowProtocol};
+    use lqos_utils::{XdpIpAddress, units::DownUpOrder};
+    use std::net::IpAddr;
+
+    fn test_flow() -> (FlowbeeKey, FlowbeeLocalData, FlowAnalysis) {
+        let mut key = FlowbeeKey::default();
+        key.local_ip = XdpIpAddress::from_ip(IpAddr::from([192, 0, 2, 10]));
+        key.remote_ip = XdpIpAddress::from_ip(IpAddr::from([198, 51, 100, 20]));
+        key.src_port = 12345;
+        key.dst_port = 443;
+        key.ip_protocol = 6;
+        let data = FlowbeeLocalData {
+            start_time: 0,
+            last_seen: 0,
+            bytes_sent: DownUpOrder::new(10, 20),
+            packets_sent: DownUpOrder::new(1, 2),
+            rate_estimate_bps: DownUpOrder::new(0, 0),
+            display_rate_bps: None,
+            tcp_retransmits: DownUpOrder::new(0, 0),
+            end_status: 0,
+            tos: 0,
+            tc_handle: 0,
+            cpu: 0,
+            circuit_hash: None,
+            device_hash: None,
+            circuit_id_hint: None,
+            remote_hostname: Some("video.example.com".to_string()),
+            tcp_info: None,
+        };
+        let analysis = FlowAnalysis {
+            asn_id: AsnId(0),
+            protocol_analysis: FlowProtocol::new(&key),
+            application: None,
+            traffic_group: None,
+        };
+        (key, data, analysis)
+    }
+
+    #[test]
+    fn host_names_are_only_exported_with_the_named_template() {
+        let (key, data, analysis) = test_flow();
+
+        let (plain, _) = to_netflow_9(&key, &data, &analysis, false).unwrap();
+        assert_eq!(&plain[..2], &256u16.to_be_bytes());
+        assert!(!plain.windows(17).any(|w| w == b"video.example.com"));
+
+        let (named, _) = to_netflow_9(&key, &data, &analysis, true).unwrap();
+        assert_eq!(&named[..2], &258u16.to_be_bytes());
+        assert!(named.windows(17).any(|w| w == b"video.example.com"));
+        let field_bytes: u16 = template_ipv4::FIELDS_IPV4_NAMED
+            .iter()
+            .map(|(_, len)| len)
+            .sum();
+        assert!(named.len() >= field_bytes as usize + 4);
+        assert_eq!(named.len() % 4, 0);
+    }
+}
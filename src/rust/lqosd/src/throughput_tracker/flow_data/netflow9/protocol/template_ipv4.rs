@@ -1,6 +1,6 @@
 use crate::throughput_tracker::flow_data::netflow9::protocol::*;
 
-pub(crate) const FIELDS_IPV4: [(u16, u16); 8] = [
+pub(crate) const FIELDS_IPV4: [(u16, u16); 9] = [
     IN_BYTES,
     IN_PKTS,
     PROTOCOL,
@@ -9,9 +9,39 @@ pub(crate) const FIELDS_IPV4: [(u16, u16); 8] = [
     L4_DST_PORT,
     IPV4_DST_ADDR,
     DST_TOS,
+    APPLICATION_NAME,
 ];
 
-pub fn template_data_ipv4() -> Vec<u8> {
+/// [`FIELDS_IPV4`] plus the remote host name, used while passive DNS is on.
+pub(crate) const FIELDS_IPV4_NAMED: [(u16, u16); 10] = [
+    IN_BYTES,
+    IN_PKTS,
+    PROTOCOL,
+    L4_SRC_PORT,
+    IPV4_SRC_ADDR,
+    L4_DST_PORT,
+    IPV4_DST_ADDR,
+    DST_TOS,
+    APPLICATION_NAME,
+    REMOTE_HOSTNAME,
+];
+
+pub(crate) const TEMPLATE_ID_IPV4: u16 = 256;
+pub(crate) const TEMPLATE_ID_IPV4_NAMED: u16 = 258;
+
+/// Returns the template id and fields for IPv4 records. The named template
+/// has its own id, so a collector never sees one id change shape.
+pub(crate) fn fields_ipv4(named: bool) -> (u16, &'static [(u16, u16)]) {
+    if named {
+        (TEMPLATE_ID_IPV4_NAMED, &FIELDS_IPV4_NAMED)
+    } else {
+        (TEMPLATE_ID_IPV4, &FIELDS_IPV4)
+    }
+}
+
+pub fn template_data_ipv4(named: bool) -> Vec<u8> {
+    let (template_id, fields) = fields_ipv4(named);
+
     // Build the header
     let mut bytes = Vec::new();
 
@@ -21,22 +51,19 @@ pub fn template_data_ipv4() -> Vec<u8> {
     bytes.push(0);
 
     // Add the length of the flowset, 4 bytes
-    const LENGTH: u16 = 8 + (FIELDS_IPV4.len() * 4) as u16; // TODO: Fixme
-    bytes.extend_from_slice(LENGTH.to_be_bytes().as_ref());
+    let length = 8 + (fields.len() * 4) as u16;
+    bytes.extend_from_slice(length.to_be_bytes().as_ref());
 
-    // Add the TemplateID. We're going to use 256 for IPv4.
-    const TEMPLATE_ID: u16 = 256;
-    bytes.extend_from_slice(TEMPLATE_ID.to_be_bytes().as_ref());
+    // Add the TemplateID: 256 for IPv4, 258 when it carries host names.
+    bytes.extend_from_slice(template_id.to_be_bytes().as_ref());
 
     // Add the number of fields in the template
-    const FIELD_COUNT: u16 = FIELDS_IPV4.len() as u16;
-    bytes.extend_from_slice(FIELD_COUNT.to_be_bytes().as_ref());
+    let field_count = fields.len() as u16;
+    bytes.extend_from_slice(field_count.to_be_bytes().as_ref());
 
-    for (field_type, field_length) in FIELDS_IPV4.iter() {
+    for (field_type, field_length) in fields.iter() {
         add_field(&mut bytes, *field_type, *field_length);
     }
 
-    //println!("Templatev4 Size {} = {}", bytes.len(), 8 + (FIELDS_IPV4.len() * 2));
-
     bytes
 }
@@ -1,6 +1,6 @@
 use crate::throughput_tracker::flow_data::netflow9::protocol::*;
 
-pub(crate) const FIELDS_IPV6: [(u16, u16); 8] = [
+pub(crate) const FIELDS_IPV6: [(u16, u16); 9] = [
     IN_BYTES,
     IN_PKTS,
     PROTOCOL,
@@ -9,9 +9,39 @@ pub(crate) const FIELDS_IPV6: [(u16, u16); 8] = [
     L4_DST_PORT,
     IPV6_DST_ADDR,
     DST_TOS,
+    APPLICATION_NAME,
 ];
 
-pub fn template_data_ipv6() -> Vec<u8> {
+/// [`FIELDS_IPV6`] plus the remote host name, used while passive DNS is on.
+pub(crate) const FIELDS_IPV6_NAMED: [(u16, u16); 10] = [
+    IN_BYTES,
+    IN_PKTS,
+    PROTOCOL,
+    L4_SRC_PORT,
+    IPV6_SRC_ADDR,
+    L4_DST_PORT,
+    IPV6_DST_ADDR,
+    DST_TOS,
+    APPLICATION_NAME,
+    REMOTE_HOSTNAME,
+];
+
+pub(crate) const TEMPLATE_ID_IPV6: u16 = 257;
+pub(crate) const TEMPLATE_ID_IPV6_NAMED: u16 = 259;
+
+/// Returns the template id and fields for IPv6 records. The named template
+/// has its own id, so a collector never sees one id change shape.
+pub(crate) fn fields_ipv6(named: bool) -> (u16, &'static [(u16, u16)]) {
+    if named {
+        (TEMPLATE_ID_IPV6_NAMED, &FIELDS_IPV6_NAMED)
+    } else {
+        (TEMPLATE_ID_IPV6, &FIELDS_IPV6)
+    }
+}
+
+pub fn template_data_ipv6(named: bool) -> Vec<u8> {
+    let (template_id, fields) = fields_ipv6(named);
+
     // Build the header
     let mut bytes = Vec::new();
 
@@ -21,18 +51,17 @@ pub fn template_data_ipv6() -> Vec<u8> {
     bytes.push(0);
 
     // Add the length of the flowset, 4 bytes
-    const LENGTH: u16 = 8 + (FIELDS_IPV6.len() * 4) as u16; // TODO: Fixme
-    bytes.extend_from_slice(LENGTH.to_be_bytes().as_ref());
+    let length = 8 + (fields.len() * 4) as u16;
+    bytes.extend_from_slice(length.to_be_bytes().as_ref());
 
-    // Add the TemplateID. We're going to use 257 for IPv6.
-    const TEMPLATE_ID: u16 = 257;
-    bytes.extend_from_slice(TEMPLATE_ID.to_be_bytes().as_ref());
+    // Add the TemplateID: 257 for IPv6, 259 when it carries host names.
+    bytes.extend_from_slice(template_id.to_be_bytes().as_ref());
 
     // Add the number of fields in the template
-    const FIELD_COUNT: u16 = FIELDS_IPV6.len() as u16;
-    bytes.extend_from_slice(FIELD_COUNT.to_be_bytes().as_ref());
+    let field_count = fields.len() as u16;
+    bytes.extend_from_slice(field_count.to_be_bytes().as_ref());
 
-    for (field_type, field_length) in FIELDS_IPV6.iter() {
+    for (field_type, field_length) in fields.iter() {
         add_field(&mut bytes, *field_type, *field_length);
     }
 
@@ -0,0 +1,252 @@
+//! Per-circuit and per-site byte counters for the configured traffic groups.
+//!
+//! Samples are gathered once per flow cycle from the flow deltas, then merged
+//! into cumulative counters. Traffic matching no group is counted under
+//! [`TRAFFIC_GROUP_OTHER`].
+
+use super::flow_analysis::TrafficGroupId;
+use fxhash::FxHashMap;
+use lqos_bus::{TrafficGroupCircuit, TrafficGroupCounter, TrafficGroupReport, TrafficGroupSite};
+use lqos_config::TRAFFIC_GROUP_OTHER;
+use lqos_utils::units::DownUpOrder;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+const EXPIRE_CYCLES: u64 = 60 * 60; // 1 hour
+
+/// Bytes seen in one flow cycle, keyed by traffic group.
+#[derive(Default)]
+pub struct TrafficGroupAggregate {
+    totals: FxHashMap<Option<TrafficGroupId>, DownUpOrder<u64>>,
+    circuits: FxHashMap<(i64, Option<TrafficGroupId>), DownUpOrder<u64>>,
+    sites: FxHashMap<(usize, Option<TrafficGroupId>), DownUpOrder<u64>>,
+}
+
+impl TrafficGroupAggregate {
+    /// Adds a flow's byte delta to the group totals, its circuit and every
+    /// network.json parent of that circuit.
+    pub fn add(
+        &mut self,
+        group: Option<TrafficGroupId>,
+        circuit_hash: Option<i64>,
+        parents: Option<&[usize]>,
+        bytes: DownUpOrder<u64>,
+    ) {
+        if bytes.down == 0 && bytes.up == 0 {
+            return;
+        }
+        self.totals.entry(group).or_default().checked_add(bytes);
+        if let Some(circuit_hash) = circuit_hash {
+            self.circuits
+                .entry((circuit_hash, group))
+                .or_default()
+                .checked_add(bytes);
+        }
+        for parent in parents.unwrap_or_default() {
+            self.sites
+                .entry((*parent, group))
+                .or_default()
+                .checked_add(bytes);
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Counter {
+    bytes: DownUpOrder<u64>,
+    last_cycle_bytes: DownUpOrder<u64>,
+    last_updated_cycle: u64,
+}
+
+impl Counter {
+    fn record(&mut self, bytes: DownUpOrder<u64>, cycle: u64) {
+        self.bytes.checked_add(bytes);
+        self.last_cycle_bytes = bytes;
+        self.last_updated_cycle = cycle;
+    }
+
+    fn to_bus(self, group: Option<TrafficGroupId>, cycle: u64) -> TrafficGroupCounter {
+        // A counter not updated this cycle carried no traffic.
+        let rate = if self.last_updated_cycle == cycle {
+            self.last_cycle_bytes
+        } else {
+            DownUpOrder::zeroed()
+        };
+        TrafficGroupCounter {
+            group: group_name(group),
+            bytes: self.bytes,
+            bits_per_second: DownUpOrder::new(
+                rate.down.saturating_mul(8),
+                rate.up.saturating_mul(8),
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrafficGroupStore {
+    cycle: u64,
+    totals: FxHashMap<Option<TrafficGroupId>, Counter>,
+    circuits: FxHashMap<(i64, Option<TrafficGroupId>), Counter>,
+    sites: FxHashMap<(usize, Option<TrafficGroupId>), Counter>,
+}
+
+impl TrafficGroupStore {
+    fn update(&mut self, aggregate: TrafficGroupAggregate, current_cycle: u64) {
+        self.cycle = current_cycle;
+        for (group, bytes) in aggregate.totals {
+            self.totals
+                .entry(group)
+                .or_default()
+                .record(bytes, current_cycle);
+        }
+        for (key, bytes) in aggregate.circuits {
+            self.circuits
+                .entry(key)
+                .or_default()
+                .record(bytes, current_cycle);
+        }
+        for (key, bytes) in aggregate.sites {
+            self.sites
+                .entry(key)
+                .or_default()
+                .record(bytes, current_cycle);
+        }
+        let stale = |counter: &Counter| {
+            current_cycle.saturating_sub(counter.last_updated_cycle) > EXPIRE_CYCLES
+        };
+        self.circuits.retain(|_, counter| !stale(counter));
+        self.sites.retain(|_, counter| !stale(counter));
+    }
+
+    fn clear(&mut self) {
+        self.totals.clear();
+        self.circuits.clear();
+        self.sites.clear();
+    }
+}
+
+static TRAFFIC_GROUPS: Lazy<Mutex<TrafficGroupStore>> =
+    Lazy::new(|| Mutex::new(TrafficGroupStore::default()));
+
+fn group_name(group: Option<TrafficGroupId>) -> String {
+    match group {
+        Some(group) => group.name(),
+        None => TRAFFIC_GROUP_OTHER.to_string(),
+    }
+}
+
+/// Merges one flow cycle of samples into the cumulative counters. Counters
+/// are discarded when no traffic groups are configured.
+pub fn update_traffic_groups(aggregate: TrafficGroupAggregate, current_cycle: u64, enable: bool) {
+    let mut store = TRAFFIC_GROUPS.lock();
+    if enable {
+        store.update(aggregate, current_cycle);
+    } else {
+        store.clear();
+    }
+}
+
+fn sorted_counters(mut counters: Vec<TrafficGroupCounter>) -> Vec<TrafficGroupCounter> {
+    counters.sort_by(|a, b| a.group.cmp(&b.group));
+    counters
+}
+
+fn totals(store: &TrafficGroupStore) -> Vec<TrafficGroupCounter> {
+    sorted_counters(
+        store
+            .totals
+            .iter()
+            .map(|(group, counter)| counter.to_bus(*group, store.cycle))
+            .collect(),
+    )
+}
+
+/// Network-wide per-group counters, sorted by group name.
+pub fn traffic_group_totals() -> Vec<TrafficGroupCounter> {
+    totals(&TRAFFIC_GROUPS.lock())
+}
+
+/// Builds the bus report, resolving circuit and site names.
+pub fn traffic_group_report() -> TrafficGroupReport {
+    let store = TRAFFIC_GROUPS.lock();
+    let cycle = store.cycle;
+    let totals = totals(&store);
+
+    let mut by_site: FxHashMap<usize, Vec<TrafficGroupCounter>> = FxHashMap::default();
+    for ((site, group), counter) in &store.sites {
+        by_site
+            .entry(*site)
+            .or_default()
+            .push(counter.to_bus(*group, cycle));
+    }
+    let mut sites: Vec<TrafficGroupSite> =
+        lqos_network_devices::with_network_json_read(|net_json| {
+            by_site
+                .into_iter()
+                .filter_map(|(index, groups)| {
+                    net_json.nodes.get(index).map(|node| TrafficGroupSite {
+                        site: node.name.clone(),
+                        groups: sorted_counters(groups),
+                    })
+                })
+                .collect()
+        });
+    sites.sort_by(|a, b| a.site.cmp(&b.site));
+
+    let mut by_circuit: FxHashMap<i64, Vec<TrafficGroupCounter>> = FxHashMap::default();
+    for ((circuit_hash, group), counter) in &store.circuits {
+        by_circuit
+            .entry(*circuit_hash)
+            .or_default()
+            .push(counter.to_bus(*group, cycle));
+    }
+    drop(store);
+    let catalog = lqos_network_devices::shaped_devices_catalog();
+    let mut circuits: Vec<TrafficGroupCircuit> = by_circuit
+        .into_iter()
+        .filter_map(|(circuit_hash, groups)| {
+            catalog
+                .device_by_hashes(None, Some(circuit_hash))
+                .map(|device| TrafficGroupCircuit {
+                    circuit_id: device.circuit_id.clone(),
+                    circuit_name: device.circuit_name.clone(),
+                    groups: sorted_counters(groups),
+                })
+        })
+        .collect();
+    circuits.sort_by(|a, b| a.circuit_id.cmp(&b.circuit_id));
+
+    TrafficGroupReport {
+        totals,
+        sites,
+        circuits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_roll_up_to_circuits_and_parents() {
+        let mut aggregate = TrafficGroupAggregate::default();
+        aggregate.add(None, Some(7), Some(&[0, 3]), DownUpOrder::new(100, 10));
+        aggregate.add(None, Some(7), Some(&[0, 3]), DownUpOrder::new(50, 5));
+        aggregate.add(None, None, None, DownUpOrder::new(1, 1));
+        aggregate.add(None, Some(8), None, DownUpOrder::zeroed());
+
+        let mut store = TrafficGroupStore::default();
+        store.update(aggregate, 1);
+        assert_eq!(store.totals[&None].bytes, DownUpOrder::new(151, 16));
+        assert_eq!(store.circuits[&(7, None)].bytes, DownUpOrder::new(150, 15));
+        assert_eq!(store.sites[&(3, None)].bytes, DownUpOrder::new(150, 15));
+        assert!(!store.circuits.contains_key(&(8, None)));
+
+        store.update(TrafficGroupAggregate::default(), 2);
+        let counter = store.circuits[&(7, None)].to_bus(None, store.cycle);
+        assert_eq!(counter.group, TRAFFIC_GROUP_OTHER);
+        assert_eq!(counter.bytes, DownUpOrder::new(150, 15));
+        assert_eq!(counter.bits_per_second, DownUpOrder::zeroed());
+    }
+}
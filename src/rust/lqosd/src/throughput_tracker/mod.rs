@@ -308,6 +308,7 @@ fn throughput_task(
 ) {
     // Load RTT exclusion overrides once on startup. UI/API calls will refresh this on update.
     crate::rtt_exclusions::refresh_from_disk();
+    crate::qoo_assignment::start_qoo_assignment();
 
     // Obtain the flow timeout from the config, default to 30 seconds
     let timeout_seconds = if let Ok(config) = lqos_config::load_config() {
@@ -406,7 +407,10 @@ fn throughput_task(
                 let enable_site_heatmaps = lqos_config::load_config()
                     .map(|config| config.enable_site_heatmaps)
                     .unwrap_or(true);
-                net_json_calc.record_site_heatmaps(enable_site_heatmaps);
+                let qoo = crate::qoo_assignment::assignments();
+                net_json_calc.record_site_heatmaps_with_profiles(enable_site_heatmaps, |name| {
+                    qoo.site_profile(name)
+                });
 
                 timer_metrics.apply_flow_data = timer_metrics.start.elapsed().as_secs_f64();
                 if bakery_reload_in_progress {
@@ -1235,6 +1239,7 @@ fn flow_summary_from_snapshot(flow: &ActiveFlowSnapshot) -> lqos_bus::FlowbeeSum
         remote_asn_name: flow.display.remote_asn_name.clone(),
         remote_asn_country: flow.display.remote_asn_country.clone(),
         analysis: flow.display.analysis.clone(),
+        remote_hostname: flow.display.remote_hostname.clone(),
         last_seen: flow.last_seen,
         start_time: flow.start_time,
         rtt_nanos: flow.rtt_nanos,
@@ -1298,9 +1303,10 @@ pub fn flows_by_ip(ip: &str) -> BusResponse {
     if let Ok(ip) = ip.parse::<IpAddr>() {
         let ip = XdpIpAddress::from_ip(ip);
         let snapshot = active_flow_snapshot();
+        let cgnat = crate::cgnat::table();
         let matching_flows: Vec<_> = snapshot
             .iter()
-            .filter(|flow| flow.key.local_ip == ip)
+            .filter(|flow| cgnat.flow_host(&flow.key) == ip)
             .map(flow_summary_from_snapshot)
             .collect();
 
@@ -1,15 +1,19 @@
 use super::{
     RETIRE_AFTER_SECONDS,
     flow_data::{
-        AsnAggregate, FlowAnalysis, FlowbeeLocalData, RttBuffer, RttData,
-        get_flowbee_event_count_and_reset, mutate_all_flows, update_asn_heatmaps,
+        AsnAggregate, FlowAnalysis, FlowbeeLocalData, RttBuffer, RttData, TrafficGroupAggregate,
+        get_flowbee_event_count_and_reset, mutate_all_flows, traffic_groups_enabled,
+        update_asn_heatmaps, update_traffic_groups,
     },
     throughput_entry::ThroughputEntry,
 };
 use crate::throughput_tracker::{CIRCUIT_RTT_BUFFERS, resolve_flow_device};
 use crate::{
     stats::HIGH_WATERMARK,
-    throughput_tracker::flow_data::{FlowbeeEffectiveDirection, expire_rtt_flows, flowbee_rtt_map},
+    throughput_tracker::flow_data::{
+        FlowbeeEffectiveDirection, dns_application, expire_rtt_flows, flow_hostname,
+        flowbee_rtt_map, take_flow_hostnames,
+    },
 };
 use fxhash::{FxHashMap, FxHashSet};
 use lqos_bakery::BakeryCommands;
@@ -146,6 +150,7 @@ fn shrink_flow_table_if_sparse(
 fn accumulate_device_rtt_samples(
     rtt_circuit_tracker: &mut FxHashMap<XdpIpAddress, RttBuffer>,
     raw_entry: Option<&ThroughputEntry>,
+    host: &XdpIpAddress,
     key: &FlowbeeKey,
     flow: &FlowbeeLocalData,
     rtt_buffer: &RttBuffer,
@@ -160,7 +165,7 @@ fn accumulate_device_rtt_samples(
         return;
     }
 
-    let device_rtt = rtt_circuit_tracker.entry(key.local_ip).or_default();
+    let device_rtt = rtt_circuit_tracker.entry(*host).or_default();
     if include_download {
         device_rtt.accumulate_direction(rtt_buffer, FlowbeeEffectiveDirection::Download);
     }
@@ -224,10 +229,12 @@ fn flow_deltas(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn apply_flow_rtt_and_qoo(
     flow: &mut FlowbeeLocalData,
     raw_entry: Option<&ThroughputEntry>,
     rtt_circuit_tracker: &mut FxHashMap<XdpIpAddress, RttBuffer>,
+    host: &XdpIpAddress,
     key: &FlowbeeKey,
     rtt_buffer: Option<RttBuffer>,
     rtt_expire: u64,
@@ -241,7 +248,14 @@ fn apply_flow_rtt_and_qoo(
         );
     }
     let retired_stale_rtt = if let Some(rtt_buffer) = rtt_buffer {
-        accumulate_device_rtt_samples(rtt_circuit_tracker, raw_entry, key, flow, &rtt_buffer);
+        accumulate_device_rtt_samples(
+            rtt_circuit_tracker,
+            raw_entry,
+            host,
+            key,
+            flow,
+            &rtt_buffer,
+        );
         flow.set_rtt_buffer(rtt_buffer);
         false
     } else {
@@ -363,7 +377,7 @@ impl ThroughputTracker {
         let Ok(config) = lqos_config::load_config() else {
             return;
         };
-        let qoo_profile = lqos_config::active_qoo_profile().ok();
+        let qoo = crate::qoo_assignment::assignments();
         let global_down_mbps = config.queues.downlink_bandwidth_mbps as f32;
         let global_up_mbps = config.queues.uplink_bandwidth_mbps as f32;
 
@@ -521,8 +535,8 @@ impl ThroughputTracker {
             );
             let loss_upload =
                 tcp_retransmit_loss_proxy(aggregate.tcp_retransmits.up, aggregate.tcp_packets.up);
-            let scores = if let Some(profile) = qoo_profile.as_ref() {
-                compute_qoq_scores(profile.as_ref(), rtt, loss_download, loss_upload)
+            let scores = if let Some(profile) = qoo.circuit_profile(Some(circuit_hash)) {
+                compute_qoq_scores(profile, rtt, loss_download, loss_upload)
             } else {
                 QoqScores::default()
             };
@@ -568,13 +582,8 @@ impl ThroughputTracker {
             tcp_retransmit_loss_proxy(total_retransmits.down, total_tcp_packets.down);
         let loss_upload = tcp_retransmit_loss_proxy(total_retransmits.up, total_tcp_packets.up);
 
-        let scores = if let Some(profile) = qoo_profile.as_ref() {
-            compute_qoq_scores(
-                profile.as_ref(),
-                &global_rtt_buffer,
-                loss_download,
-                loss_upload,
-            )
+        let scores = if let Some(profile) = qoo.default_profile() {
+            compute_qoq_scores(profile, &global_rtt_buffer, loss_download, loss_upload)
         } else {
             QoqScores::default()
         };
@@ -986,7 +995,10 @@ impl ThroughputTracker {
         let enable_asn_heatmaps = lqos_config::load_config()
             .map(|config| config.enable_asn_heatmaps)
             .unwrap_or(true);
-        let qoo_profile = lqos_config::active_qoo_profile().ok();
+        let enable_traffic_groups = traffic_groups_enabled();
+        let mut traffic_groups = TrafficGroupAggregate::default();
+        let qoo = crate::qoo_assignment::assignments();
+        let mut traffic_mix = crate::qoo_assignment::TrafficMixSample::default();
         let mut asn_aggregates: FxHashMap<u32, AsnAggregate> = FxHashMap::default();
         let mut add_asn_sample = |asn: u32,
                                   bytes: DownUpOrder<u64>,
@@ -1007,6 +1019,7 @@ impl ThroughputTracker {
 
         if let Ok(now) = time_since_boot() {
             let mut rtt_samples = flowbee_rtt_map();
+            let mut flow_hostnames = take_flow_hostnames();
             get_flowbee_event_count_and_reset();
             let since_boot = Duration::from(now);
             let expire = since_boot
@@ -1020,11 +1033,16 @@ impl ThroughputTracker {
 
             mutate_all_flows(|flow_data| {
                 let mut raw_data = self.raw_data.lock();
+                let cgnat = crate::cgnat::table();
 
                 // Track through all the flows
                 iterate_flows(&mut |key, data| {
                     let mut rtt_buffer = rtt_samples.remove(key);
-                    let raw_entry = raw_data.get(&key.local_ip);
+                    let hostname = flow_hostnames.remove(key);
+                    // Behind a CGNAT the local address is shared; the port
+                    // block says which subscriber the flow belongs to.
+                    let host = cgnat.flow_host(key);
+                    let raw_entry = raw_data.get(&host);
                     if data.end_status == 3 {
                         // The flow has been handled already and should be ignored.
                         // DO NOT process it again.
@@ -1058,6 +1076,10 @@ impl ThroughputTracker {
                             this_flow.0.set_end_status(data.end_status);
                             this_flow.0.set_tos(data.tos);
                             this_flow.0.set_flags(data.flags);
+                            if let Some(hostname) = hostname {
+                                this_flow.1.apply_hostname(&hostname);
+                                this_flow.0.remote_hostname = flow_hostname(key, Some(hostname));
+                            }
 
                             record_rtt_flow_contribution(
                                 net_json_calc,
@@ -1070,10 +1092,18 @@ impl ThroughputTracker {
                                 &mut this_flow.0,
                                 raw_entry,
                                 rtt_circuit_tracker,
+                                &host,
                                 key,
                                 rtt_buffer.take(),
                                 rtt_expire,
-                                qoo_profile.as_deref(),
+                                qoo.circuit_profile(raw_entry.and_then(|entry| entry.circuit_hash)),
+                            );
+                            traffic_mix.add_flow(
+                                &qoo,
+                                raw_entry.and_then(|entry| entry.circuit_hash),
+                                &this_flow.1,
+                                key.ip_protocol,
+                                delta_bytes,
                             );
                             if enable_asn_heatmaps {
                                 add_asn_sample(
@@ -1084,14 +1114,23 @@ impl ThroughputTracker {
                                     flow_asn_rtt_ms(raw_entry, &this_flow.0),
                                 );
                             }
+                            if enable_traffic_groups {
+                                traffic_groups.add(
+                                    this_flow.1.traffic_group,
+                                    raw_entry.and_then(|entry| entry.circuit_hash),
+                                    raw_entry
+                                        .and_then(|entry| entry.network_json_parents.as_deref()),
+                                    delta_bytes,
+                                );
+                            }
                             if key.ip_protocol == 6 && data.end_status == 0 && raw_entry.is_some()
                             {
                                 tcp_retries
-                                    .entry(key.local_ip)
+                                    .entry(host)
                                     .or_insert_with(DownUpOrder::zeroed)
                                     .checked_add(delta_retrans);
                                 tcp_retry_packets
-                                    .entry(key.local_ip)
+                                    .entry(host)
                                     .or_insert_with(DownUpOrder::zeroed)
                                     .checked_add(delta_packets);
                             }
@@ -1112,8 +1151,18 @@ impl ThroughputTracker {
                                 }
                             } else {
                                 // Insert it into the map
-                                let flow_analysis = FlowAnalysis::new(key);
+                                let remote_hostname = flow_hostname(key, hostname.clone());
+                                let mut flow_analysis = FlowAnalysis::new(key);
+                                // The handshake name, or failing that the DNS answer,
+                                // classifies the flow even when naming is disabled for
+                                // this subscriber.
+                                if let Some(name) = hostname.or(remote_hostname.clone()) {
+                                    flow_analysis.apply_hostname(&name);
+                                } else if let Some(application) = dns_application(key) {
+                                    flow_analysis.application = Some(application);
+                                }
                                 let mut flow_summary = FlowbeeLocalData::from_flow(data, key);
+                                flow_summary.remote_hostname = remote_hostname;
                                 record_rtt_flow_contribution(
                                     net_json_calc,
                                     raw_entry,
@@ -1125,10 +1174,20 @@ impl ThroughputTracker {
                                     &mut flow_summary,
                                     raw_entry,
                                     rtt_circuit_tracker,
+                                    &host,
                                     key,
                                     rtt_buffer.take(),
                                     rtt_expire,
-                                    qoo_profile.as_deref(),
+                                    qoo.circuit_profile(
+                                        raw_entry.and_then(|entry| entry.circuit_hash),
+                                    ),
+                                );
+                                traffic_mix.add_flow(
+                                    &qoo,
+                                    raw_entry.and_then(|entry| entry.circuit_hash),
+                                    &flow_analysis,
+                                    key.ip_protocol,
+                                    data.bytes_sent,
                                 );
                                 if enable_asn_heatmaps {
                                     let delta_retrans = DownUpOrder::new(
@@ -1143,6 +1202,16 @@ impl ThroughputTracker {
                                         flow_asn_rtt_ms(raw_entry, &flow_summary),
                                     );
                                 }
+                                if enable_traffic_groups {
+                                    traffic_groups.add(
+                                        flow_analysis.traffic_group,
+                                        raw_entry.and_then(|entry| entry.circuit_hash),
+                                        raw_entry.and_then(|entry| {
+                                            entry.network_json_parents.as_deref()
+                                        }),
+                                        data.bytes_sent,
+                                    );
+                                }
                                 flow_data.insert(*key, (flow_summary, flow_analysis));
                             }
                         }
@@ -1226,36 +1295,35 @@ impl ThroughputTracker {
                 // meaningful even when the current RTT window has few samples. We only update scores
                 // when prerequisites are available; otherwise we keep the last known values so the UI
                 // doesn't flap to unknown ("-") on idle seconds.
-                if let Some(profile) = qoo_profile.as_ref() {
-                    for tracker in raw_data.values_mut() {
-                        if tracker
-                            .circuit_hash
-                            .is_some_and(crate::rtt_exclusions::is_excluded_hash)
-                        {
-                            tracker.qoq = QoqScores::default();
-                            continue;
-                        }
-                        let tcp_packets_delta = tracker.tcp_retransmit_packets;
-                        let loss_download = tcp_retransmit_loss_proxy(
-                            tracker.tcp_retransmits.down,
-                            tcp_packets_delta.down,
-                        );
-                        let loss_upload = tcp_retransmit_loss_proxy(
-                            tracker.tcp_retransmits.up,
-                            tcp_packets_delta.up,
-                        );
-                        let scores = compute_qoq_scores(
-                            profile.as_ref(),
-                            &tracker.rtt_buffer,
-                            loss_download,
-                            loss_upload,
-                        );
-                        if scores.download_total != QOQ_UNKNOWN {
-                            tracker.qoq.download_total = scores.download_total;
-                        }
-                        if scores.upload_total != QOQ_UNKNOWN {
-                            tracker.qoq.upload_total = scores.upload_total;
-                        }
+                for tracker in raw_data.values_mut() {
+                    if tracker
+                        .circuit_hash
+                        .is_some_and(crate::rtt_exclusions::is_excluded_hash)
+                    {
+                        tracker.qoq = QoqScores::default();
+                        continue;
+                    }
+                    let Some(profile) = qoo.circuit_profile(tracker.circuit_hash) else {
+                        continue;
+                    };
+                    let tcp_packets_delta = tracker.tcp_retransmit_packets;
+                    let loss_download = tcp_retransmit_loss_proxy(
+                        tracker.tcp_retransmits.down,
+                        tcp_packets_delta.down,
+                    );
+                    let loss_upload =
+                        tcp_retransmit_loss_proxy(tracker.tcp_retransmits.up, tcp_packets_delta.up);
+                    let scores = compute_qoq_scores(
+                        profile,
+                        &tracker.rtt_buffer,
+                        loss_download,
+                        loss_upload,
+                    );
+                    if scores.download_total != QOQ_UNKNOWN {
+                        tracker.qoq.download_total = scores.download_total;
+                    }
+                    if scores.upload_total != QOQ_UNKNOWN {
+                        tracker.qoq.upload_total = scores.upload_total;
                     }
                 }
 
@@ -1278,6 +1346,8 @@ impl ThroughputTracker {
         if enable_asn_heatmaps || !asn_aggregates.is_empty() {
             update_asn_heatmaps(asn_aggregates, self_cycle, enable_asn_heatmaps);
         }
+        update_traffic_groups(traffic_groups, self_cycle, enable_traffic_groups);
+        crate::qoo_assignment::record_traffic_mix(traffic_mix);
     }
 
     pub(crate) fn update_totals(&self) {
@@ -1944,6 +2014,7 @@ mod tests {
             &mut flow,
             Some(&entry),
             &mut rtt_circuit_tracker,
+            &key.local_ip,
             &key,
             Some(rtt),
             0,
@@ -2046,6 +2117,7 @@ mod tests {
             &mut flow,
             None,
             &mut rtt_circuit_tracker,
+            &key.local_ip,
             &key,
             None,
             raw.last_seen + 1,
@@ -2094,6 +2166,7 @@ mod tests {
             &mut flow,
             Some(&entry),
             &mut rtt_circuit_tracker,
+            &key.local_ip,
             &key,
             None,
             0,
@@ -2111,6 +2184,7 @@ mod tests {
             &mut flow,
             Some(&entry),
             &mut rtt_circuit_tracker,
+            &key.local_ip,
             &key,
             None,
             0,
@@ -0,0 +1,559 @@
+//! Time-limited traffic mirror sessions.
+//!
+//! An operator starts a session for a circuit, a list of addresses and
+//! prefixes, or both. While it runs, TC egress clones matching packets to
+//! the `[traffic_mirror]` interface. Sessions end when stopped or when they
+//! expire; a circuit session follows changes to the circuit's addresses.
+//! Every start, stop and expiry is appended to an audit trail naming who
+//! requested it.
+
+use fxhash::FxHashMap;
+use lqos_bus::{BusResponse, TrafficMirrorSession};
+use lqos_config::{
+    Config, MAX_MIRROR_PREFIXES, MAX_MIRROR_SESSIONS, ShapedDevice, TrafficMirrorConfig,
+    parse_mirror_prefix,
+};
+use lqos_utils::unix_time::unix_now;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const TICK: Duration = Duration::from_secs(1);
+const AUDIT_FILE: &str = "traffic_mirror_audit.jsonl";
+
+#[derive(Clone, Debug, PartialEq)]
+struct Session {
+    session_id: u64,
+    /// Index into the dataplane's per-session counters.
+    slot: u32,
+    circuit_id: Option<String>,
+    circuit_name: String,
+    /// Addresses of the circuit's devices, refreshed as they change.
+    circuit_prefixes: Vec<(IpAddr, u8)>,
+    /// Addresses and prefixes given in the request.
+    extra_prefixes: Vec<(IpAddr, u8)>,
+    requested_by: String,
+    reason: String,
+    started_unix: u64,
+    expires_unix: u64,
+}
+
+impl Session {
+    fn prefixes(&self) -> impl Iterator<Item = &(IpAddr, u8)> {
+        self.circuit_prefixes
+            .iter()
+            .chain(self.extra_prefixes.iter())
+    }
+}
+
+/// A circuit to mirror and its current addresses.
+struct MirroredCircuit {
+    circuit_id: String,
+    circuit_name: String,
+    prefixes: Vec<(IpAddr, u8)>,
+}
+
+/// A resolved request to start mirroring.
+struct StartRequest {
+    circuit: Option<MirroredCircuit>,
+    prefixes: Vec<(IpAddr, u8)>,
+    minutes: Option<u32>,
+    requested_by: String,
+    reason: String,
+}
+
+#[derive(Default)]
+struct MirrorState {
+    config: TrafficMirrorConfig,
+    audit_path: Option<PathBuf>,
+    next_session_id: u64,
+    sessions: Vec<Session>,
+    shaped_devices: Option<Arc<lqos_config::ConfigShapedDevices>>,
+}
+
+static STATE: Lazy<Mutex<MirrorState>> = Lazy::new(|| Mutex::new(MirrorState::default()));
+
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    time_unix: u64,
+    event: &'a str,
+    session_id: u64,
+    by: &'a str,
+    circuit_id: Option<&'a str>,
+    prefixes: Vec<String>,
+    reason: &'a str,
+    expires_unix: u64,
+}
+
+fn format_prefix(&(ip, prefix): &(IpAddr, u8)) -> String {
+    format!("{ip}/{prefix}")
+}
+
+fn device_prefixes(device: &ShapedDevice) -> impl Iterator<Item = (IpAddr, u8)> + '_ {
+    device
+        .ipv4
+        .iter()
+        .map(|(ip, prefix)| (IpAddr::V4(*ip), *prefix as u8))
+        .chain(
+            device
+                .ipv6
+                .iter()
+                .map(|(ip, prefix)| (IpAddr::V6(*ip), *prefix as u8)),
+        )
+}
+
+/// The name and addresses of a circuit, if it exists.
+fn circuit_addresses(
+    shaped: &lqos_config::ConfigShapedDevices,
+    circuit_id: &str,
+) -> Option<(String, Vec<(IpAddr, u8)>)> {
+    let mut devices = shaped
+        .devices
+        .iter()
+        .filter(|device| device.circuit_id == circuit_id)
+        .peekable();
+    let name = devices.peek()?.circuit_name.clone();
+    Some((name, devices.flat_map(device_prefixes).collect()))
+}
+
+impl MirrorState {
+    fn start(&mut self, request: StartRequest, now: u64) -> Result<&Session, String> {
+        if !self.config.enabled {
+            return Err("Traffic mirroring is disabled in the configuration".to_string());
+        }
+        if request.requested_by.trim().is_empty() {
+            return Err("A mirror session must name who requested it".to_string());
+        }
+        if request.circuit.is_none() && request.prefixes.is_empty() {
+            return Err("Give a circuit, addresses or prefixes to mirror".to_string());
+        }
+        let slot = (0..MAX_MIRROR_SESSIONS as u32)
+            .find(|slot| self.sessions.iter().all(|session| session.slot != *slot))
+            .ok_or_else(|| {
+                format!("At most {MAX_MIRROR_SESSIONS} mirror sessions can run at once")
+            })?;
+        let (circuit_id, circuit_name, circuit_prefixes) = match request.circuit {
+            Some(circuit) => (
+                Some(circuit.circuit_id),
+                circuit.circuit_name,
+                circuit.prefixes,
+            ),
+            None => (None, String::new(), Vec::new()),
+        };
+        let total: usize = self
+            .sessions
+            .iter()
+            .map(|session| session.prefixes().count())
+            .sum::<usize>()
+            + circuit_prefixes.len()
+            + request.prefixes.len();
+        if total > MAX_MIRROR_PREFIXES {
+            return Err(format!(
+                "Mirror sessions may cover at most {MAX_MIRROR_PREFIXES} addresses and prefixes"
+            ));
+        }
+
+        self.next_session_id += 1;
+        let minutes = self.config.session_minutes(request.minutes);
+        self.sessions.push(Session {
+            session_id: self.next_session_id,
+            slot,
+            circuit_id,
+            circuit_name,
+            circuit_prefixes,
+            extra_prefixes: request.prefixes,
+            requested_by: request.requested_by,
+            reason: request.reason,
+            started_unix: now,
+            expires_unix: now + u64::from(minutes) * 60,
+        });
+        self.sessions
+            .last()
+            .ok_or_else(|| "Session was not stored".to_string())
+    }
+
+    fn stop(&mut self, session_id: u64) -> Option<Session> {
+        let index = self
+            .sessions
+            .iter()
+            .position(|session| session.session_id == session_id)?;
+        Some(self.sessions.remove(index))
+    }
+
+    fn expire(&mut self, now: u64) -> Vec<Session> {
+        let (expired, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.sessions)
+            .into_iter()
+            .partition(|session| session.expires_unix <= now);
+        self.sessions = running;
+        expired
+    }
+
+    /// Refreshes circuit sessions from a new shaped devices snapshot.
+    /// Returns true if any mirrored address changed.
+    fn refresh_circuits(&mut self, shaped: &lqos_config::ConfigShapedDevices) -> bool {
+        let mut changed = false;
+        for session in self.sessions.iter_mut() {
+            let Some(circuit_id) = &session.circuit_id else {
+                continue;
+            };
+            let prefixes = circuit_addresses(shaped, circuit_id)
+                .map(|(_, prefixes)| prefixes)
+                .unwrap_or_default();
+            if prefixes != session.circuit_prefixes {
+                session.circuit_prefixes = prefixes;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Mirrored prefixes and their session slots. When sessions overlap,
+    /// the oldest session counts the packets.
+    fn dataplane_prefixes(&self) -> Vec<(IpAddr, u8, u32)> {
+        let mut prefixes: FxHashMap<(IpAddr, u8), u32> = FxHashMap::default();
+        for session in self.sessions.iter() {
+            for prefix in session.prefixes() {
+                prefixes.entry(*prefix).or_insert(session.slot);
+            }
+        }
+        prefixes
+            .into_iter()
+            .map(|((ip, prefix), slot)| (ip, prefix, slot))
+            .collect()
+    }
+
+    fn audit(&self, event: &str, session: &Session, by: &str, now: u64) {
+        let record = AuditRecord {
+            time_unix: now,
+            event,
+            session_id: session.session_id,
+            by,
+            circuit_id: session.circuit_id.as_deref(),
+            prefixes: session.prefixes().map(format_prefix).collect(),
+            reason: &session.reason,
+            expires_unix: session.expires_unix,
+        };
+        info!(
+            "Traffic mirror session {} {event} by {by}",
+            session.session_id
+        );
+        let Some(path) = &self.audit_path else {
+            return;
+        };
+        let line = match serde_json::to_string(&record) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!("Unable to encode traffic mirror audit record: {err:?}");
+                return;
+            }
+        };
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{line}"));
+        if let Err(err) = written {
+            warn!("Unable to write traffic mirror audit trail {path:?}: {err:?}");
+        }
+    }
+}
+
+fn program_dataplane(state: &MirrorState) {
+    if let Err(err) = lqos_sys::set_traffic_mirror(&state.config, &state.dataplane_prefixes()) {
+        warn!("Unable to load traffic mirror prefixes: {err:?}");
+    }
+}
+
+/// Clears mirroring left by an earlier run, applies the config and starts
+/// expiring sessions.
+pub(crate) fn start_traffic_mirror(config: &Config) {
+    reload(config);
+    program_dataplane(&STATE.lock());
+    if let Err(err) = std::thread::Builder::new()
+        .name("Traffic Mirror".to_string())
+        .spawn(traffic_mirror_loop)
+    {
+        warn!("Failed to start traffic mirroring: {err:?}");
+    }
+}
+
+/// Applies a changed `[traffic_mirror]` section. Disabling mirroring ends
+/// every session.
+pub(crate) fn reload(config: &Config) {
+    let now = unix_now().unwrap_or(0);
+    let mut state = STATE.lock();
+    let changed = state.config != config.traffic_mirror;
+    state.config = config.traffic_mirror.clone();
+    state.audit_path = Some(config.resolved_state_directory().join(AUDIT_FILE));
+    if !state.config.enabled {
+        for session in std::mem::take(&mut state.sessions) {
+            state.audit("stopped", &session, "configuration", now);
+        }
+    }
+    if changed {
+        program_dataplane(&state);
+    }
+}
+
+fn traffic_mirror_loop() {
+    loop {
+        std::thread::sleep(TICK);
+        let now = unix_now().unwrap_or(0);
+        let mut state = STATE.lock();
+        if state.sessions.is_empty() {
+            continue;
+        }
+        let expired = state.expire(now);
+        for session in expired.iter() {
+            state.audit("expired", session, "lqosd", now);
+        }
+        let mut changed = !expired.is_empty();
+
+        let shaped = lqos_network_devices::shaped_devices_snapshot();
+        if state
+            .shaped_devices
+            .as_ref()
+            .is_none_or(|previous| !Arc::ptr_eq(previous, &shaped))
+        {
+            changed |= state.refresh_circuits(&shaped);
+            state.shaped_devices = Some(shaped);
+        }
+        if changed {
+            program_dataplane(&state);
+        }
+    }
+}
+
+fn session_entry(session: &Session, counters: &[lqos_sys::MirrorCounters]) -> TrafficMirrorSession {
+    let counter = counters
+        .get(session.slot as usize)
+        .copied()
+        .unwrap_or_default();
+    TrafficMirrorSession {
+        session_id: session.session_id,
+        circuit_id: session.circuit_id.clone(),
+        circuit_name: session.circuit_name.clone(),
+        prefixes: session.prefixes().map(format_prefix).collect(),
+        requested_by: session.requested_by.clone(),
+        reason: session.reason.clone(),
+        started_unix: session.started_unix,
+        expires_unix: session.expires_unix,
+        packets: counter.packets,
+        bytes: counter.bytes,
+        errors: counter.errors,
+    }
+}
+
+/// The running mirror sessions with their counters.
+pub(crate) fn sessions() -> Vec<TrafficMirrorSession> {
+    let counters = lqos_sys::mirror_counters().unwrap_or_default();
+    let state = STATE.lock();
+    state
+        .sessions
+        .iter()
+        .map(|session| session_entry(session, &counters))
+        .collect()
+}
+
+/// Starts a mirror session for a circuit and/or a list of addresses and
+/// prefixes.
+pub(crate) fn start(
+    circuit_id: Option<&str>,
+    prefixes: &[String],
+    minutes: Option<u32>,
+    requested_by: &str,
+    reason: &str,
+) -> Result<TrafficMirrorSession, String> {
+    let circuit = match circuit_id.map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) => {
+            let shaped = lqos_network_devices::shaped_devices_snapshot();
+            let (circuit_name, prefixes) = circuit_addresses(&shaped, id)
+                .ok_or_else(|| format!("Circuit {id} was not found"))?;
+            Some(MirroredCircuit {
+                circuit_id: id.to_string(),
+                circuit_name,
+                prefixes,
+            })
+        }
+        None => None,
+    };
+    let prefixes = prefixes
+        .iter()
+        .filter(|prefix| !prefix.trim().is_empty())
+        .map(|prefix| parse_mirror_prefix(prefix))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let now = unix_now().unwrap_or(0);
+    let mut state = STATE.lock();
+    let request = StartRequest {
+        circuit,
+        prefixes,
+        minutes,
+        requested_by: requested_by.trim().to_string(),
+        reason: reason.trim().to_string(),
+    };
+    let session = state.start(request, now)?.clone();
+    if let Err(err) = lqos_sys::reset_mirror_counters(session.slot) {
+        warn!("Unable to reset traffic mirror counters: {err:?}");
+    }
+    if let Err(err) = lqos_sys::set_traffic_mirror(&state.config, &state.dataplane_prefixes()) {
+        state.stop(session.session_id);
+        program_dataplane(&state);
+        return Err(format!("Unable to start mirroring: {err}"));
+    }
+    state.audit("started", &session, &session.requested_by, now);
+    Ok(session_entry(&session, &[]))
+}
+
+/// Stops a mirror session before it expires.
+pub(crate) fn stop(session_id: u64, requested_by: &str) -> Result<(), String> {
+    let now = unix_now().unwrap_or(0);
+    let mut state = STATE.lock();
+    let session = state
+        .stop(session_id)
+        .ok_or_else(|| format!("Mirror session {session_id} is not running"))?;
+    program_dataplane(&state);
+    let by = requested_by.trim();
+    state.audit(
+        "stopped",
+        &session,
+        if by.is_empty() { "unknown" } else { by },
+        now,
+    );
+    Ok(())
+}
+
+/// Bus wrapper for [`sessions`].
+pub(crate) fn list_sessions() -> BusResponse {
+    BusResponse::TrafficMirrorSessions(sessions())
+}
+
+/// Bus wrapper for [`start`], answering with the new session.
+pub(crate) fn start_session(
+    circuit_id: Option<&str>,
+    prefixes: &[String],
+    minutes: Option<u32>,
+    requested_by: &str,
+    reason: &str,
+) -> BusResponse {
+    match start(circuit_id, prefixes, minutes, requested_by, reason) {
+        Ok(session) => BusResponse::TrafficMirrorSessions(vec![session]),
+        Err(err) => BusResponse::Fail(err),
+    }
+}
+
+/// Bus wrapper for [`stop`].
+pub(crate) fn stop_session(session_id: u64, requested_by: &str) -> BusResponse {
+    match stop(session_id, requested_by) {
+        Ok(()) => BusResponse::Ack,
+        Err(err) => BusResponse::Fail(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn state() -> MirrorState {
+        MirrorState {
+            config: TrafficMirrorConfig {
+                enabled: true,
+                interface: "eth9".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn address(last: u8) -> (IpAddr, u8) {
+        (IpAddr::V4(Ipv4Addr::new(100, 64, 0, last)), 32)
+    }
+
+    fn request(prefixes: Vec<(IpAddr, u8)>) -> StartRequest {
+        StartRequest {
+            circuit: None,
+            prefixes,
+            minutes: None,
+            requested_by: "alice".to_string(),
+            reason: "ticket 42".to_string(),
+        }
+    }
+
+    #[test]
+    fn sessions_take_free_slots_and_expire() {
+        let mut state = state();
+        let first = state
+            .start(request(vec![address(1)]), 1_000)
+            .expect("starts")
+            .clone();
+        assert_eq!((first.session_id, first.slot), (1, 0));
+        assert_eq!(first.expires_unix, 1_000 + 30 * 60);
+
+        let mut second = request(vec![address(2)]);
+        second.minutes = Some(5);
+        let second = state.start(second, 1_000).expect("starts").clone();
+        assert_eq!((second.session_id, second.slot), (2, 1));
+
+        assert!(state.stop(first.session_id).is_some());
+        let third = state
+            .start(request(vec![address(3)]), 1_000)
+            .expect("starts");
+        assert_eq!((third.session_id, third.slot), (3, 0));
+
+        let expired = state.expire(1_000 + 5 * 60);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].session_id, 2);
+        assert_eq!(state.sessions.len(), 1);
+    }
+
+    #[test]
+    fn rejects_requests_it_cannot_honour() {
+        let mut state = state();
+        assert!(state.start(request(Vec::new()), 0).is_err());
+
+        let mut anonymous = request(vec![address(1)]);
+        anonymous.requested_by = " ".to_string();
+        assert!(state.start(anonymous, 0).is_err());
+
+        for last in 0..MAX_MIRROR_SESSIONS as u8 {
+            assert!(state.start(request(vec![address(last)]), 0).is_ok());
+        }
+        assert!(state.start(request(vec![address(200)]), 0).is_err());
+
+        let mut disabled = MirrorState::default();
+        assert!(disabled.start(request(vec![address(1)]), 0).is_err());
+    }
+
+    #[test]
+    fn overlapping_prefixes_count_towards_the_oldest_session() {
+        let mut state = state();
+        let mut circuit = request(Vec::new());
+        circuit.circuit = Some(MirroredCircuit {
+            circuit_id: "c-1".to_string(),
+            circuit_name: "Circuit One".to_string(),
+            prefixes: vec![address(1), address(2)],
+        });
+        state.start(circuit, 0).expect("starts");
+        state
+            .start(request(vec![address(2), address(3)]), 0)
+            .expect("starts");
+
+        let mut prefixes = state.dataplane_prefixes();
+        prefixes.sort();
+        assert_eq!(
+            prefixes,
+            vec![
+                (address(1).0, 32, 0),
+                (address(2).0, 32, 0),
+                (address(3).0, 32, 1),
+            ]
+        );
+    }
+}
@@ -171,6 +171,16 @@ pub(crate) fn is_runtime_virtualized_node(node_name: &str) -> bool {
         .is_some_and(|cache| cache.read().contains(node_name))
 }
 
+/// Returns the nodes TreeGuard currently has virtualized at runtime, sorted by name.
+pub(crate) fn runtime_virtualized_nodes() -> Vec<String> {
+    let mut nodes: Vec<String> = TREEGUARD_RUNTIME_VIRTUALIZED_NODES
+        .get()
+        .map(|cache| cache.read().iter().cloned().collect())
+        .unwrap_or_default();
+    nodes.sort();
+    nodes
+}
+
 /// Requests a status snapshot from the TreeGuard actor.
 ///
 /// This function is not pure: it sends a message to the TreeGuard actor thread.
@@ -893,6 +903,14 @@ fn run_tick(
     }
     runtime_state.last_dry_run = Some(tg.dry_run);
 
+    // A standby mirrors the active peer's virtualization decisions, so that
+    // TreeGuard owns them if this node is promoted.
+    if crate::ha_sync::is_standby() {
+        runtime_state.runtime_virtualized_nodes =
+            crate::ha_sync::replicated_virtualized_nodes().into_iter().collect();
+        runtime_state.pending_link_operations.clear();
+    }
+
     if pause_for_bakery_reload(status, tick_seconds, runtime_state, tg.enabled, tg.dry_run) {
         return;
     }
@@ -1771,7 +1789,10 @@ fn run_tick(
                         && let Some(token) =
                             find_circuit_override_token_in_overrides(&entry.devices, overrides)
                     {
-                        let parsed = decisions::parse_directional_sqm_override(&token);
+                        let parsed = decisions::parse_directional_sqm_override(
+                            &token,
+                            &config.treeguard.circuits,
+                        );
                         if let Some(down) = parsed.down {
                             state.down.desired = down;
                         }
@@ -1925,7 +1946,11 @@ fn pause_for_bakery_reload(
         enabled,
         dry_run,
         lqos_bakery::full_reload_in_progress(),
-        lqos_bakery::bakery_reload_required_reason(),
+        if crate::ha_sync::is_standby() {
+            Some("this node is an HA standby".to_string())
+        } else {
+            lqos_bakery::bakery_reload_required_reason()
+        },
     )
 }
 
@@ -2603,6 +2628,7 @@ fn overrides_device_sqm(overrides: &OverrideFile, device_id: &str) -> Option<Str
         if let lqos_overrides::CircuitAdjustment::DeviceAdjustSqm {
             device_id: current,
             sqm_override,
+            ..
         } = adj
         {
             if current != device_id {
@@ -2639,6 +2665,7 @@ fn overrides_sqm_device_ids(overrides: &OverrideFile) -> FxHashSet<String> {
         if let lqos_overrides::CircuitAdjustment::DeviceAdjustSqm {
             device_id,
             sqm_override,
+            ..
         } = adj
             && sqm_override
                 .as_deref()
@@ -2735,7 +2762,7 @@ fn base_circuit_sqm_state(
     let mut up = default_sqm_state_for_rate(cap_up, config);
 
     if let Some(token) = infer_circuit_sqm_override_token(devices, operator_overrides) {
-        let parsed = decisions::parse_directional_sqm_override(&token);
+        let parsed = decisions::parse_directional_sqm_override(&token, &config.treeguard.circuits);
         if let Some(v) = parsed.down {
             down = v;
         }
@@ -2774,6 +2801,7 @@ struct CircuitSqmApplyContext<'a> {
     now_unix: u64,
     dry_run: bool,
     persist_sqm_overrides: bool,
+    circuits_cfg: &'a lqos_config::TreeguardCircuitsConfig,
     circuit_id: &'a str,
     circuit_entity_id: &'a str,
     circuit_label: &'a str,
@@ -2890,6 +2918,7 @@ fn apply_circuit_sqm_change<P, C, L>(
         now_unix,
         dry_run,
         persist_sqm_overrides,
+        circuits_cfg,
         circuit_id,
         circuit_entity_id,
         circuit_label,
@@ -2904,7 +2933,8 @@ fn apply_circuit_sqm_change<P, C, L>(
         changed_up,
     } = transition;
 
-    let token = decisions::format_directional_sqm_override(proposed_down, proposed_up);
+    let token =
+        decisions::format_directional_sqm_override(proposed_down, proposed_up, circuits_cfg);
     let returning_to_base = proposed_down == base_sqm.down && proposed_up == base_sqm.up;
     let live_token = if returning_to_base {
         "/"
@@ -3234,6 +3264,7 @@ where
                     now_unix,
                     dry_run,
                     persist_sqm_overrides: circuits_cfg.persist_sqm_overrides,
+                    circuits_cfg,
                     circuit_id,
                     circuit_entity_id,
                     circuit_label,
@@ -3925,6 +3956,7 @@ mod tests {
                 now_unix: 1_000,
                 dry_run: false,
                 persist_sqm_overrides: false,
+                circuits_cfg: &lqos_config::TreeguardCircuitsConfig::default(),
                 circuit_id: "circuit-1",
                 circuit_entity_id: "Circuit One (circuit-1)",
                 circuit_label: "Circuit One",
@@ -4011,6 +4043,7 @@ mod tests {
                 now_unix: 1_000,
                 dry_run: false,
                 persist_sqm_overrides: true,
+                circuits_cfg: &lqos_config::TreeguardCircuitsConfig::default(),
                 circuit_id: "circuit-1",
                 circuit_entity_id: "Circuit One (circuit-1)",
                 circuit_label: "Circuit One",
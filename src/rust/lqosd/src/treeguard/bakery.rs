@@ -133,6 +133,9 @@ fn send_live_sqm_override(
             up_qdisc_handle: None,
             ip_addresses,
             sqm_override: Some(sqm_override.to_string()),
+            priority_classes: priority_class_list(devices),
+            down_lane_qdisc_handles: Vec::new(),
+            up_lane_qdisc_handles: Vec::new(),
         })
         .map_err(|e| TreeguardError::BakerySend {
             details: e.to_string(),
@@ -240,6 +243,14 @@ fn ip_list(devices: &[ShapedDevice]) -> String {
     ips.join(",")
 }
 
+fn priority_class_list(devices: &[ShapedDevice]) -> Vec<String> {
+    devices
+        .iter()
+        .find(|dev| !dev.priority_classes.is_empty())
+        .map(|dev| dev.priority_classes.clone())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::apply_circuit_sqm_override_live_with_sender_and_snapshot;
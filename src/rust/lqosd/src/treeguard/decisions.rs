@@ -383,41 +383,60 @@ pub fn decide_circuit_sqm(input: CircuitSqmInput<'_>) -> CircuitSqmDecision {
     decision
 }
 
+/// Returns the override token TreeGuard writes for an SQM state: the configured
+/// SQM profile for that state, or the built-in qdisc name.
+///
+/// This function is pure: it has no side effects.
+fn sqm_state_token(state: CircuitSqmState, circuits_cfg: &TreeguardCircuitsConfig) -> &str {
+    match state {
+        CircuitSqmState::Cake => circuits_cfg.cake_profile.as_deref().unwrap_or("cake"),
+        CircuitSqmState::FqCodel => circuits_cfg
+            .fq_codel_profile
+            .as_deref()
+            .unwrap_or("fq_codel"),
+    }
+}
+
 /// Formats an SQM override token from per-direction desired states.
 ///
 /// This function is pure: it has no side effects.
-pub fn format_directional_sqm_override(down: CircuitSqmState, up: CircuitSqmState) -> String {
-    let down_s = match down {
-        CircuitSqmState::Cake => "cake",
-        CircuitSqmState::FqCodel => "fq_codel",
-    };
-    let up_s = match up {
-        CircuitSqmState::Cake => "cake",
-        CircuitSqmState::FqCodel => "fq_codel",
-    };
-    format!("{down_s}/{up_s}")
+pub fn format_directional_sqm_override(
+    down: CircuitSqmState,
+    up: CircuitSqmState,
+    circuits_cfg: &TreeguardCircuitsConfig,
+) -> String {
+    format!(
+        "{}/{}",
+        sqm_state_token(down, circuits_cfg),
+        sqm_state_token(up, circuits_cfg)
+    )
 }
 
 /// Parses an SQM override token into per-direction SQM states.
 ///
 /// The token may be a single value (applies to both directions) or a `down/up` token.
-/// Empty and `"none"` tokens map to `None` for that direction.
+/// Empty and `"none"` tokens map to `None` for that direction. The SQM profiles
+/// configured for TreeGuard's states map back to those states.
 ///
 /// This function is pure: it has no side effects.
-pub fn parse_directional_sqm_override(token: &str) -> DownUpOrder<Option<CircuitSqmState>> {
-    fn parse_one(t: &str) -> Option<CircuitSqmState> {
+pub fn parse_directional_sqm_override(
+    token: &str,
+    circuits_cfg: &TreeguardCircuitsConfig,
+) -> DownUpOrder<Option<CircuitSqmState>> {
+    let parse_one = |t: &str| -> Option<CircuitSqmState> {
         let t = t.trim();
         if t.is_empty() || t.eq_ignore_ascii_case("none") {
             return None;
         }
-        if t.eq_ignore_ascii_case("cake") {
+        if t.eq_ignore_ascii_case("cake") || circuits_cfg.cake_profile.as_deref() == Some(t) {
             return Some(CircuitSqmState::Cake);
         }
-        if t.eq_ignore_ascii_case("fq_codel") {
+        if t.eq_ignore_ascii_case("fq_codel") || circuits_cfg.fq_codel_profile.as_deref() == Some(t)
+        {
             return Some(CircuitSqmState::FqCodel);
         }
         None
-    }
+    };
 
     let token = token.trim();
     if token.is_empty() {
@@ -1115,25 +1134,48 @@ mod tests {
 
     #[test]
     fn directional_token_format_and_parse() {
+        let cfg = TreeguardCircuitsConfig::default();
         assert_eq!(
-            format_directional_sqm_override(CircuitSqmState::Cake, CircuitSqmState::FqCodel),
+            format_directional_sqm_override(CircuitSqmState::Cake, CircuitSqmState::FqCodel, &cfg),
             "cake/fq_codel"
         );
 
-        let parsed = parse_directional_sqm_override("cake/fq_codel");
+        let parsed = parse_directional_sqm_override("cake/fq_codel", &cfg);
         assert_eq!(parsed.down, Some(CircuitSqmState::Cake));
         assert_eq!(parsed.up, Some(CircuitSqmState::FqCodel));
 
-        let parsed = parse_directional_sqm_override("fq_codel");
+        let parsed = parse_directional_sqm_override("fq_codel", &cfg);
         assert_eq!(parsed.down, Some(CircuitSqmState::FqCodel));
         assert_eq!(parsed.up, Some(CircuitSqmState::FqCodel));
 
-        let parsed = parse_directional_sqm_override("none");
+        let parsed = parse_directional_sqm_override("none", &cfg);
         assert_eq!(parsed.down, None);
         assert_eq!(parsed.up, None);
 
-        let parsed = parse_directional_sqm_override("/fq_codel");
+        let parsed = parse_directional_sqm_override("/fq_codel", &cfg);
         assert_eq!(parsed.down, None);
         assert_eq!(parsed.up, Some(CircuitSqmState::FqCodel));
     }
+
+    #[test]
+    fn directional_token_uses_configured_profiles() {
+        let cfg = TreeguardCircuitsConfig {
+            cake_profile: Some("docsis".to_string()),
+            fq_codel_profile: Some("light".to_string()),
+            ..TreeguardCircuitsConfig::default()
+        };
+        assert_eq!(
+            format_directional_sqm_override(CircuitSqmState::FqCodel, CircuitSqmState::Cake, &cfg),
+            "light/docsis"
+        );
+
+        let parsed = parse_directional_sqm_override("light/docsis", &cfg);
+        assert_eq!(parsed.down, Some(CircuitSqmState::FqCodel));
+        assert_eq!(parsed.up, Some(CircuitSqmState::Cake));
+
+        // Built-in names keep their meaning, other profiles are not TreeGuard states.
+        let parsed = parse_directional_sqm_override("cake/voip", &cfg);
+        assert_eq!(parsed.down, Some(CircuitSqmState::Cake));
+        assert_eq!(parsed.up, None);
+    }
 }
@@ -69,7 +69,11 @@ impl TopWidget for TopFlows {
             t.add_row([
                 flow_circuit_label(flow),
                 flow.local_ip.to_string(),
-                flow.remote_ip.to_string(),
+                if flow.remote_hostname.is_empty() {
+                    flow.remote_ip.to_string()
+                } else {
+                    flow.remote_hostname.to_string()
+                },
                 flow.analysis.to_string(),
                 scale_bits(flow.bytes_sent.down),
                 scale_bits(flow.bytes_sent.up),
@@ -16,7 +16,7 @@ pub use blackboard::*;
 use lqos_config::Config;
 use lqos_utils::rustls::ensure_rustls_crypto_provider;
 use tokio::time::Instant;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// Start the tracing/logging system
 fn init_tracing() {
@@ -37,6 +37,14 @@ fn check_enabled_status(config: &Config) -> Result<(), UispIntegrationError> {
     }
 }
 
+/// Marks the next input revision as ours. Marked again after the run because
+/// long runs can outlive the mark.
+fn mark_history_source(config: &Config) {
+    if let Err(e) = lqos_config::mark_input_change_source(config, "uisp integration") {
+        warn!("Unable to mark the input history source: {e}");
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), UispIntegrationError> {
     let now = Instant::now();
@@ -62,8 +70,12 @@ async fn main() -> Result<(), UispIntegrationError> {
     // Build our allowed/excluded IP ranges
     let ip_ranges = IpRanges::new(&config)?;
 
+    // Attribute the files we are about to write in the input revision history
+    mark_history_source(&config);
+
     // Select a strategy and go from there
-    strategies::build_with_strategy(config, ip_ranges).await?;
+    strategies::build_with_strategy(config.clone(), ip_ranges).await?;
+    mark_history_source(&config);
 
     // Print timings
     let elapsed = now.elapsed();
@@ -226,6 +226,7 @@ fn build_ap_only_shaping_outputs(
                     download_max: ethernet_decision.download_max,
                     upload_max: ethernet_decision.upload_max,
                     comment: "".to_string(),
+                    access_policy: site.access_policy(config),
                 };
                 shaped_devices.push(sd);
             }
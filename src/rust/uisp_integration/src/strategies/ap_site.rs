@@ -386,6 +386,7 @@ impl Layer {
                                 download_max: ethernet_decision.download_max,
                                 upload_max: ethernet_decision.upload_max,
                                 comment: "".to_string(),
+                                access_policy: site.access_policy(config),
                             };
                             shaped_devices.push(sd);
                         }
@@ -4,7 +4,7 @@ use crate::ethernet_advisory::{apply_ethernet_rate_cap, write_ethernet_advisorie
 use crate::ip_ranges::IpRanges;
 use crate::strategies::common::{dedup_raw_sites_by_id, dedup_site_names};
 use crate::strategies::full::shaped_devices_writer::{ShapedDevice, write_circuit_anchors};
-use crate::uisp_types::UispDevice;
+use crate::uisp_types::{UispDevice, suspended_access_policy};
 use lqos_config::{
     CircuitEthernetMetadata, Config, EthernetPortLimitPolicy, RequestedCircuitRates,
 };
@@ -78,12 +78,13 @@ pub async fn build_flat_network(
             && site_type == "endpoint"
         {
             // Prefer UISP QoS + burst for client sites
-            let suspended_slow = site
+            let suspended = site
                 .identification
                 .as_ref()
                 .map(|id| id.suspended)
-                .unwrap_or(false)
-                && config.uisp_integration.suspended_strategy == "slow";
+                .unwrap_or(false);
+            let suspended_slow = suspended && config.uisp_integration.suspended_strategy == "slow";
+            let access_policy = suspended_access_policy(&config, suspended);
             let requested = if suspended_slow {
                 (0.1, 0.1, 0.1, 0.1)
             } else if let Some(qos) = &site.qos {
@@ -204,6 +205,7 @@ pub async fn build_flat_network(
                         upload_min: ethernet_decision.upload_min,
                         upload_max: ethernet_decision.upload_max,
                         comment: "".to_string(),
+                        access_policy: access_policy.clone(),
                     };
                     shaped_devices.push(sd);
                 }
@@ -95,6 +95,7 @@ struct LegacyShapedDevice {
     download_max: f32,
     upload_max: f32,
     comment: String,
+    access_policy: String,
 }
 
 #[cfg(test)]
@@ -142,6 +143,7 @@ fn config_shaped_device(legacy: LegacyShapedDevice) -> ConfigShapedDevice {
         "download_max",
         "upload_max",
         "comment",
+        "access_policy",
     ]);
     let record = csv::StringRecord::from(vec![
         legacy.circuit_id,
@@ -159,6 +161,7 @@ fn config_shaped_device(legacy: LegacyShapedDevice) -> ConfigShapedDevice {
         legacy.download_max.to_string(),
         legacy.upload_max.to_string(),
         legacy.comment,
+        legacy.access_policy,
     ]);
     ConfigShapedDevice::from_csv(&record, Some(&headers))
         .expect("UISP shaped-device rows must remain valid during compile conversion")
@@ -945,6 +948,7 @@ async fn build_imported_full2_bundle_from_data(
                     download_max: ethernet_decision.download_max,
                     upload_max: ethernet_decision.upload_max,
                     comment: "".to_string(),
+                    access_policy: site.access_policy(&config),
                 };
                 debug!(
                     "Created shaped device for '{}' in site '{}' with parent '{}'",
@@ -104,18 +104,27 @@ fn legacy_bandwidth_csv_path(config: &Config) -> PathBuf {
     Path::new(&config.lqos_directory).join(LEGACY_UISP_BANDWIDTH_FILE)
 }
 
+/// Active `AdjustSiteSpeed` entries, with timed entries first so they win the
+/// first-match lookup in `find_bandwidth_override` over permanent ones.
 fn materialize_operator_site_bandwidth_overrides(
     overrides: &OverrideFile,
 ) -> Vec<BandwidthOverride> {
-    overrides
+    let now = lqos_utils::unix_time::unix_now().unwrap_or(0);
+    let mut active = overrides
         .network_adjustments()
         .iter()
+        .filter(|adjustment| adjustment.schedule().is_active_at(now))
+        .collect::<Vec<_>>();
+    active.sort_by_key(|adjustment| !adjustment.schedule().is_timed());
+    active
+        .into_iter()
         .filter_map(|adjustment| match adjustment {
             NetworkAdjustment::AdjustSiteSpeed {
                 node_id,
                 site_name,
                 download_bandwidth_mbps,
                 upload_bandwidth_mbps,
+                ..
             } => Some(BandwidthOverride {
                 node_id: node_id.clone(),
                 site_name: site_name.clone(),
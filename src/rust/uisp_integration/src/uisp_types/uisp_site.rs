@@ -1,10 +1,21 @@
 use crate::uisp_types::DetectedAccessPoint;
 use crate::uisp_types::uisp_site_type::UispSiteType;
-use lqos_config::Config;
+use lqos_config::{CircuitAccessPolicy, Config};
 use std::collections::HashSet;
 use tracing::warn;
 use uisp::{DataLink, Device, Site};
 
+/// `ShapedDevices.csv` access policy for a client site. Suspended sites are
+/// put behind the walled garden portal under the `walled_garden` strategy;
+/// everything else is left open (an empty column).
+pub fn suspended_access_policy(config: &Config, suspended: bool) -> String {
+    if suspended && config.uisp_integration.suspended_strategy == "walled_garden" {
+        CircuitAccessPolicy::Portal.as_str().to_string()
+    } else {
+        String::new()
+    }
+}
+
 /// Shortened/flattened version of the UISP Site type.
 #[derive(Debug)]
 pub struct UispSite {
@@ -122,6 +133,10 @@ impl UispSite {
                     burst_down_mbps = 0.0;
                     burst_up_mbps = 0.0;
                 }
+                "walled_garden" => warn!(
+                    "{} is suspended. Using walled garden strategy.",
+                    value.name_or_blank()
+                ),
                 _ => warn!(
                     "{} is suspended. No strategy is set, leaving at full speed.",
                     value.name_or_blank()
@@ -148,6 +163,11 @@ impl UispSite {
         }
     }
 
+    /// `ShapedDevices.csv` access policy for this site's client rows.
+    pub fn access_policy(&self, config: &Config) -> String {
+        suspended_access_policy(config, self.suspended)
+    }
+
     /// Compute burst-aware min/max in Mbps using UISP qos + config multipliers.
     /// Returns None if no qos base rates are present.
     pub fn burst_rates(&self, config: &Config) -> Option<(f32, f32, f32, f32)> {
@@ -0,0 +1,86 @@
+import asyncio
+import sys
+import types
+import unittest
+
+
+def install_liblqos_stub():
+    lqlib = types.ModuleType("liblqos_python")
+    lqlib.live_throughput = lambda: {
+        "bits_per_second_down": 8_000,
+        "bits_per_second_up": 2_000,
+        "shaped_bits_per_second_down": 6_000,
+        "shaped_bits_per_second_up": 1_000,
+    }
+    lqlib.live_top_circuits = lambda n, direction: [
+        {
+            "circuit_id": "c1",
+            "circuit_name": "Subscriber 1",
+            "bytes_per_second_down": 1_000,
+            "bytes_per_second_up": 100,
+            "rtt_current_p50_nanos_down": 12_500_000,
+            "rtt_current_p50_nanos_up": None,
+            "qoo_down": 91.5,
+            "tcp_retransmit_sample_down_retransmits": 5,
+            "tcp_retransmit_sample_down_packets": 100,
+            "dscp_remarked_per_second_down": 0,
+        }
+    ][:n]
+    lqlib.live_circuit = lambda circuit_id: None
+    lqlib.live_bakery_status = lambda: {
+        "active_circuits": 12,
+        "reload_in_progress": False,
+        "circuit_count": 14,
+    }
+    sys.modules["liblqos_python"] = lqlib
+
+
+install_liblqos_stub()
+
+import lqos_live  # noqa: E402
+
+
+class TestLqosLive(unittest.TestCase):
+    def test_throughput_is_typed_and_timestamped(self):
+        sample = lqos_live.throughput()
+        self.assertEqual(sample.bits_per_second_down, 8_000)
+        self.assertEqual(sample.shaped_bits_per_second_up, 1_000)
+        self.assertGreater(sample.timestamp, 0)
+
+    def test_circuit_rows_become_dataclasses(self):
+        circuits = lqos_live.top_circuits(5)
+        self.assertEqual(len(circuits), 1)
+        circuit = circuits[0]
+        self.assertEqual(circuit.circuit_id, "c1")
+        self.assertEqual(circuit.rtt_ms_down, 12.5)
+        self.assertIsNone(circuit.rtt_ms_up)
+        self.assertEqual(circuit.retransmit_fraction_down, 0.05)
+        self.assertEqual(circuit.retransmit_fraction_up, 0.0)
+        # Fields the dataclass doesn't name are kept, not dropped.
+        self.assertEqual(circuit.extra, {"dscp_remarked_per_second_down": 0})
+
+    def test_missing_circuit_is_none(self):
+        self.assertIsNone(lqos_live.circuit("missing"))
+
+    def test_throughput_ticks_stop_after_count(self):
+        ticks = list(lqos_live.throughput_ticks(interval=0, count=3, rows=True))
+        self.assertEqual(len(ticks), 3)
+        self.assertIn("timestamp", ticks[0])
+
+    def test_async_helpers(self):
+        async def run():
+            status = await lqos_live.abakery_status()
+            ticks = [
+                tick
+                async for tick in lqos_live.athroughput_ticks(interval=0, count=2)
+            ]
+            return status, ticks
+
+        status, ticks = asyncio.run(run())
+        self.assertEqual(status.active_circuits, 12)
+        self.assertEqual(len(ticks), 2)
+        self.assertEqual(ticks[1].bits_per_second_up, 2_000)
+
+
+if __name__ == "__main__":
+    unittest.main()
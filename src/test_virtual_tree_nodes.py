@@ -2,6 +2,7 @@ import unittest
 
 from virtual_tree_nodes import (
     build_logical_to_physical_node_map,
+    build_node_sqm_map,
     build_physical_network,
     collect_physical_parent_node_aliases,
     collect_physical_parent_node_ids,
@@ -116,5 +117,30 @@ class TestVirtualTreeNodes(unittest.TestCase):
         )
 
 
+    def test_node_sqm_map_inherits_through_virtual_nodes(self):
+        logical = {
+            "Region": {
+                "sqm": "Rural",
+                "children": {
+                    "Town": {
+                        "virtual": True,
+                        "children": {
+                            "AP1": {},
+                            "AP2": {"sqm": "cake/fq_codel"},
+                        },
+                    },
+                },
+            },
+            "Other": {},
+        }
+
+        mapping = build_node_sqm_map(logical)
+
+        self.assertEqual(mapping["Region"], "rural")
+        self.assertEqual(mapping["Town"], "rural")
+        self.assertEqual(mapping["AP1"], "rural")
+        self.assertEqual(mapping["AP2"], "cake/fq_codel")
+        self.assertNotIn("Other", mapping)
+
 if __name__ == "__main__":
     unittest.main()
@@ -50,6 +50,36 @@ def build_logical_to_physical_node_map(logical_network):
     return mapping, virtual_nodes
 
 
+def build_node_sqm_map(logical_network):
+    """
+    Returns a dict of logical_node_name -> inherited SQM token (profile name or
+    "down/up" token) for every node that has one.
+
+    A node's {"sqm": "..."} applies to the node and everything beneath it until a
+    descendant sets its own value. Virtual nodes participate like any other node.
+    """
+    mapping = {}
+
+    def recurse(level, inherited):
+        if not isinstance(level, dict):
+            return
+        for name, node in level.items():
+            if not isinstance(name, str) or not isinstance(node, dict):
+                continue
+            own = node.get("sqm", None)
+            effective = inherited
+            if isinstance(own, str) and own.strip() != "":
+                effective = own.strip().lower()
+            if effective is not None:
+                mapping[name] = effective
+            children = node.get("children", None)
+            if isinstance(children, dict):
+                recurse(children, effective)
+
+    recurse(logical_network, None)
+    return mapping
+
+
 def build_physical_network(logical_network):
     """
     Builds a physical HTB topology by removing virtual nodes and promoting their children